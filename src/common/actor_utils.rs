@@ -1,5 +1,28 @@
+use std::sync::Mutex;
+
 use actix::prelude::*;
 
+lazy_static::lazy_static! {
+    /// 独立线程中运行的actor System,关闭进程时统一停止
+    static ref ACTOR_SYSTEMS: Mutex<Vec<System>> = Mutex::new(vec![]);
+}
+
+fn register_system() {
+    if let Ok(mut systems) = ACTOR_SYSTEMS.lock() {
+        systems.push(System::current());
+    }
+}
+
+///
+/// 停止所有通过create_actor_at_thread*创建的actor线程
+pub fn stop_actor_threads() {
+    if let Ok(mut systems) = ACTOR_SYSTEMS.lock() {
+        for system in systems.drain(..) {
+            system.stop();
+        }
+    }
+}
+
 pub fn create_actor_at_thread<T>(actor: T) -> Addr<T>
 where
     T: Actor<Context = Context<T>> + Send,
@@ -7,6 +30,7 @@ where
     let (tx, rx) = std::sync::mpsc::sync_channel(1);
     std::thread::spawn(move || {
         let rt = System::new();
+        rt.block_on(async { register_system() });
         let addrs = rt.block_on(async { actor.start() });
         tx.send(addrs).unwrap();
         rt.run().unwrap();
@@ -22,6 +46,7 @@ where
     let (tx, rx) = std::sync::mpsc::sync_channel(1);
     std::thread::spawn(move || {
         let rt = System::new();
+        rt.block_on(async { register_system() });
         let addrs = rt.block_on(async { (a.start(), b.start()) });
         tx.send(addrs).unwrap();
         rt.run().unwrap();
@@ -38,6 +63,7 @@ where
     let (tx, rx) = std::sync::mpsc::sync_channel(1);
     std::thread::spawn(move || {
         let rt = System::new();
+        rt.block_on(async { register_system() });
         let addrs = rt.block_on(async { (a.start(), b.start(), c.start()) });
         tx.send(addrs).unwrap();
         rt.run().unwrap();
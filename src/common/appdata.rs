@@ -1,3 +1,12 @@
+use crate::common::cluster_settings::ClusterSettingsManager;
+use crate::common::concurrency_limit::ConcurrencyLimiter;
+use crate::common::dead_letter::DeadLetterManager;
+use crate::common::namespace_quota::NamespaceQuotaManager;
+use crate::common::public_status::PublicStatusManager;
+use crate::common::resource_event::ResourceEventStore;
+use crate::common::sdk_usage::SdkUsageManage;
+use crate::common::shutdown::ShutdownManager;
+use crate::common::support_bundle::SlowRequestLog;
 use crate::common::AppSysConfig;
 use crate::config::core::ConfigActor;
 use crate::grpc::bistream_manage::BiStreamManage;
@@ -5,12 +14,14 @@ use crate::metrics::core::MetricsManager;
 use crate::naming::cluster::node_manage::{InnerNodeManage, NodeManage};
 use crate::naming::cluster::route::NamingRoute;
 use crate::naming::core::NamingActor;
+use crate::naming::instance_history::InstanceHistoryActor;
 use crate::raft::cache::route::CacheRoute;
 use crate::raft::cache::CacheManager;
 use crate::raft::cluster::route::ConfigRoute;
 use crate::raft::db::route::TableRoute;
 use crate::raft::db::table::TableManager;
 use crate::raft::filestore::core::FileStore;
+use crate::raft::freshness::DataFreshness;
 use crate::raft::network::factory::RaftClusterRequestSender;
 use crate::raft::NacosRaft;
 use crate::user::UserManager;
@@ -39,4 +50,15 @@ pub struct AppShareData {
     pub cache_manager: Addr<CacheManager>,
     pub timezone_offset: Arc<FixedOffset>,
     pub metrics_manager: Addr<MetricsManager>,
+    pub sdk_usage_manage: Addr<SdkUsageManage>,
+    pub data_freshness: Arc<DataFreshness>,
+    pub cluster_settings: Arc<ClusterSettingsManager>,
+    pub namespace_quota_manager: Addr<NamespaceQuotaManager>,
+    pub dead_letter_manager: Addr<DeadLetterManager>,
+    pub instance_history: Addr<InstanceHistoryActor>,
+    pub resource_event_store: Arc<ResourceEventStore>,
+    pub slow_request_log: Arc<SlowRequestLog>,
+    pub public_status: Arc<PublicStatusManager>,
+    pub shutdown_manager: Arc<ShutdownManager>,
+    pub concurrency_limiter: Arc<ConcurrencyLimiter>,
 }
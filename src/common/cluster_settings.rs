@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use super::namespace_quota::NamespaceQuota;
+use super::AppSysConfig;
+
+/// 集群共享设置在系统配置中的key
+pub const SYSCONFIG_CLUSTER_SETTINGS_KEY: &str = "cluster_settings";
+
+///
+/// 集群共享的服务端设置,保存在系统配置中通过raft同步到所有节点;
+/// 未设置的项使用节点自身的环境变量配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSettings {
+    pub config_max_content: Option<usize>,
+    pub stale_read_max_millis: Option<u64>,
+    pub config_protect_cipher_delete: Option<bool>,
+    /// webhook名称->地址
+    #[serde(default)]
+    pub webhooks: HashMap<String, String>,
+    /// 命名空间id->配额
+    #[serde(default)]
+    pub namespace_quotas: HashMap<String, NamespaceQuota>,
+    /// 用量达到配额的百分比时发出告警,默认80
+    pub quota_warn_percent: Option<u32>,
+}
+
+impl ClusterSettings {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        if value.is_empty() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(value)?)
+    }
+
+    pub fn check(&self) -> anyhow::Result<()> {
+        if let Some(0) = self.config_max_content {
+            return Err(anyhow::anyhow!("configMaxContent must be greater than 0"));
+        }
+        if let Some(percent) = self.quota_warn_percent {
+            if percent == 0 || percent > 100 {
+                return Err(anyhow::anyhow!("quotaWarnPercent must be in 1..=100"));
+            }
+        }
+        for (name, url) in &self.webhooks {
+            if name.is_empty() {
+                return Err(anyhow::anyhow!("webhook name is empty"));
+            }
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(anyhow::anyhow!("webhook {} url is invalid: {}", name, url));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ClusterSettingsManager {
+    sys_config: Arc<AppSysConfig>,
+    settings: RwLock<Arc<ClusterSettings>>,
+}
+
+impl ClusterSettingsManager {
+    pub fn new(sys_config: Arc<AppSysConfig>) -> Self {
+        Self {
+            sys_config,
+            settings: Default::default(),
+        }
+    }
+
+    pub fn update_from_str(&self, value: &str) {
+        match ClusterSettings::parse(value) {
+            Ok(settings) => self.update(settings),
+            Err(err) => log::warn!("parse cluster settings error,{}", err),
+        }
+    }
+
+    pub fn update(&self, settings: ClusterSettings) {
+        if let Ok(mut v) = self.settings.write() {
+            *v = Arc::new(settings);
+        }
+    }
+
+    pub fn get_settings(&self) -> Arc<ClusterSettings> {
+        self.settings.read().map(|v| v.clone()).unwrap_or_default()
+    }
+
+    pub fn config_max_content(&self) -> usize {
+        self.get_settings()
+            .config_max_content
+            .unwrap_or(self.sys_config.config_max_content)
+    }
+
+    pub fn stale_read_max_millis(&self) -> u64 {
+        self.get_settings()
+            .stale_read_max_millis
+            .unwrap_or(self.sys_config.stale_read_max_millis)
+    }
+
+    pub fn config_protect_cipher_delete(&self) -> bool {
+        self.get_settings()
+            .config_protect_cipher_delete
+            .unwrap_or(self.sys_config.config_protect_cipher_delete)
+    }
+
+    pub fn get_webhook(&self, name: &str) -> Option<String> {
+        self.get_settings().webhooks.get(name).cloned()
+    }
+
+    pub fn quota_warn_percent(&self) -> u32 {
+        self.get_settings().quota_warn_percent.unwrap_or(80)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_settings() {
+        assert!(ClusterSettings::parse("").unwrap().webhooks.is_empty());
+        let settings = ClusterSettings::parse(
+            r#"{"configMaxContent":1024,"webhooks":{"ops":"http://127.0.0.1/hook"}}"#,
+        )
+        .unwrap();
+        assert!(settings.check().is_ok());
+        let invalid = ClusterSettings::parse(r#"{"webhooks":{"ops":"ftp://a"}}"#).unwrap();
+        assert!(invalid.check().is_err());
+        let invalid = ClusterSettings::parse(r#"{"quotaWarnPercent":120}"#).unwrap();
+        assert!(invalid.check().is_err());
+
+        let sys_config = Arc::new(AppSysConfig::default());
+        let manager = ClusterSettingsManager::new(sys_config.clone());
+        assert_eq!(manager.config_max_content(), sys_config.config_max_content);
+        manager.update(settings);
+        assert_eq!(manager.config_max_content(), 1024);
+        assert_eq!(
+            manager.get_webhook("ops").as_deref(),
+            Some("http://127.0.0.1/hook")
+        );
+        //解析失败时保留原设置
+        manager.update_from_str("{");
+        assert_eq!(manager.config_max_content(), 1024);
+    }
+}
@@ -0,0 +1,213 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::AppSysConfig;
+use crate::grpc::handler::{
+    BATCH_INSTANCE_REQUEST, CONFIG_PUBLISH_REQUEST, CONFIG_REMOVE_REQUEST, INSTANCE_REQUEST,
+};
+
+///
+/// 按接口类别隔离的并发限制,一类接口过载时不影响其它接口
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointClass {
+    ConfigPublish,
+    InstanceRegister,
+    ConsoleQuery,
+}
+
+impl EndpointClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EndpointClass::ConfigPublish => "config_publish",
+            EndpointClass::InstanceRegister => "instance_register",
+            EndpointClass::ConsoleQuery => "console_query",
+        }
+    }
+
+    ///
+    /// 按请求方法与路径归类,不在限制范围内的返回None
+    pub fn classify(method: &str, path: &str) -> Option<Self> {
+        let is_get = method.eq_ignore_ascii_case("GET");
+        if path.starts_with("/rnacos/api/console/") || path.starts_with("/nacos/v1/console/") {
+            if is_get {
+                return Some(EndpointClass::ConsoleQuery);
+            }
+            if path.contains("/config") {
+                return Some(EndpointClass::ConfigPublish);
+            }
+            return None;
+        }
+        if is_get {
+            return None;
+        }
+        match path {
+            "/nacos/v1/cs/configs" | "/nacos/v2/cs/config" => Some(EndpointClass::ConfigPublish),
+            "/nacos/v1/ns/instance" | "/nacos/v2/ns/instance" => {
+                Some(EndpointClass::InstanceRegister)
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// 按gRPC请求类型归类,与http接口共用同一个限制
+    pub fn classify_grpc(type_url: &str) -> Option<Self> {
+        match type_url {
+            CONFIG_PUBLISH_REQUEST | CONFIG_REMOVE_REQUEST => Some(EndpointClass::ConfigPublish),
+            INSTANCE_REQUEST | BATCH_INSTANCE_REQUEST => Some(EndpointClass::InstanceRegister),
+            _ => None,
+        }
+    }
+}
+
+struct EndpointLimiter {
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+    queue_size: usize,
+}
+
+impl EndpointLimiter {
+    fn new(max_concurrency: usize, queue_size: usize) -> Option<Self> {
+        if max_concurrency == 0 {
+            return None;
+        }
+        Some(Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrency)),
+            queued: AtomicUsize::new(0),
+            queue_size,
+        })
+    }
+}
+
+///
+/// 每类接口最多max_concurrency个请求同时处理,超出的进入有界队列等待;
+/// 队列已满或等待超时的请求直接失败,避免排队拉长所有请求的耗时
+pub struct ConcurrencyLimiter {
+    config_publish: Option<EndpointLimiter>,
+    instance_register: Option<EndpointLimiter>,
+    console_query: Option<EndpointLimiter>,
+    queue_timeout: Duration,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(sys_config: &AppSysConfig) -> Self {
+        let queue_size = sys_config.concurrency_queue_size;
+        Self {
+            config_publish: EndpointLimiter::new(
+                sys_config.concurrency_limit_config_publish,
+                queue_size,
+            ),
+            instance_register: EndpointLimiter::new(
+                sys_config.concurrency_limit_instance_register,
+                queue_size,
+            ),
+            console_query: EndpointLimiter::new(
+                sys_config.concurrency_limit_console_query,
+                queue_size,
+            ),
+            queue_timeout: Duration::from_millis(sys_config.concurrency_queue_timeout_millis),
+        }
+    }
+
+    fn get_limiter(&self, class: EndpointClass) -> Option<&EndpointLimiter> {
+        match class {
+            EndpointClass::ConfigPublish => self.config_publish.as_ref(),
+            EndpointClass::InstanceRegister => self.instance_register.as_ref(),
+            EndpointClass::ConsoleQuery => self.console_query.as_ref(),
+        }
+    }
+
+    ///
+    /// 获取处理许可,许可在请求处理完成后释放;该类接口未限制时返回Ok(None)
+    pub async fn acquire(
+        &self,
+        class: EndpointClass,
+    ) -> anyhow::Result<Option<OwnedSemaphorePermit>> {
+        let limiter = match self.get_limiter(class) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        if let Ok(permit) = limiter.semaphore.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+        if limiter.queued.fetch_add(1, Ordering::SeqCst) >= limiter.queue_size {
+            limiter.queued.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow::anyhow!("{} queue is full", class.name()));
+        }
+        let result = tokio::time::timeout(
+            self.queue_timeout,
+            limiter.semaphore.clone().acquire_owned(),
+        )
+        .await;
+        limiter.queued.fetch_sub(1, Ordering::SeqCst);
+        match result {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            Ok(Err(err)) => Err(anyhow::anyhow!(
+                "{} limiter is closed,{}",
+                class.name(),
+                err
+            )),
+            Err(_) => Err(anyhow::anyhow!("{} queue wait timeout", class.name())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            EndpointClass::classify("POST", "/nacos/v1/cs/configs"),
+            Some(EndpointClass::ConfigPublish)
+        );
+        assert_eq!(EndpointClass::classify("GET", "/nacos/v1/cs/configs"), None);
+        assert_eq!(
+            EndpointClass::classify("POST", "/nacos/v1/cs/configs/listener"),
+            None
+        );
+        assert_eq!(
+            EndpointClass::classify("DELETE", "/nacos/v2/ns/instance"),
+            Some(EndpointClass::InstanceRegister)
+        );
+        assert_eq!(
+            EndpointClass::classify("GET", "/rnacos/api/console/v2/config/list"),
+            Some(EndpointClass::ConsoleQuery)
+        );
+        assert_eq!(
+            EndpointClass::classify("POST", "/rnacos/api/console/v2/config/update"),
+            Some(EndpointClass::ConfigPublish)
+        );
+        assert_eq!(
+            EndpointClass::classify_grpc("BatchInstanceRequest"),
+            Some(EndpointClass::InstanceRegister)
+        );
+        assert_eq!(EndpointClass::classify_grpc("ConfigQueryRequest"), None);
+    }
+
+    #[tokio::test]
+    async fn test_acquire() {
+        let limiter = ConcurrencyLimiter {
+            config_publish: EndpointLimiter::new(1, 1),
+            instance_register: None,
+            console_query: None,
+            queue_timeout: Duration::from_millis(50),
+        };
+        let class = EndpointClass::ConfigPublish;
+        let permit = limiter.acquire(class).await.unwrap();
+        assert!(permit.is_some());
+        assert!(limiter
+            .acquire(EndpointClass::InstanceRegister)
+            .await
+            .unwrap()
+            .is_none());
+        //队列中的请求等待超时
+        assert!(limiter.acquire(class).await.is_err());
+        drop(permit);
+        assert!(limiter.acquire(class).await.unwrap().is_some());
+    }
+}
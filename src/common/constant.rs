@@ -14,11 +14,19 @@ pub const SEQ_KEY_CONFIG: &str = "SEQ_CONFIG";
 
 pub const AUTHORIZATION_HEADER: &str = "Authorization";
 pub const ACCESS_TOKEN_HEADER: &str = "accessToken";
+/// unix domain socket连接没有对端ip,对端总是本机
+pub const LOCAL_CLIENT_IP: &str = "127.0.0.1";
+/// 路由到金丝雀节点的测试流量标记
+pub const CANARY_HEADER: &str = "X-Rnacos-Canary";
 
 lazy_static::lazy_static! {
     pub static ref CONFIG_TREE_NAME: Arc<String> =  Arc::new("T_CONFIG".to_string());
     pub static ref SEQUENCE_TREE_NAME: Arc<String> =  Arc::new("T_SEQUENCE".to_string());
     pub static ref USER_TREE_NAME: Arc<String> =  Arc::new("T_USER".to_string());
     pub static ref CACHE_TREE_NAME: Arc<String> =  Arc::new("T_CACHE".to_string());
+    pub static ref USER_PREFERENCE_TREE_NAME: Arc<String> =  Arc::new("T_USER_PREFERENCE".to_string());
+    pub static ref DEAD_LETTER_TREE_NAME: Arc<String> =  Arc::new("T_DEAD_LETTER".to_string());
+    pub static ref NAMING_PERSISTENT_INSTANCE_TREE_NAME: Arc<String> =  Arc::new("T_NAMING_PERSISTENT_INSTANCE".to_string());
+    pub static ref NAMING_SERVICE_TREE_NAME: Arc<String> =  Arc::new("T_NAMING_SERVICE".to_string());
     pub static ref EMPTY_ARC_STRING: Arc<String> = Arc::new("".to_string());
 }
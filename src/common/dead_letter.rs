@@ -0,0 +1,376 @@
+use std::sync::Arc;
+
+use actix::prelude::*;
+use bean_factory::{bean, Inject};
+use serde::{Deserialize, Serialize};
+
+use super::appdata::AppShareData;
+use super::constant::DEAD_LETTER_TREE_NAME;
+use super::webhook::WebhookUtils;
+use super::AppSysConfig;
+use crate::naming::listener::{InnerNamingListener, NamingListenerCmd};
+use crate::naming::model::ServiceKey;
+use crate::now_millis;
+use crate::raft::db::route::TableRoute;
+use crate::raft::db::table::{
+    TableManager, TableManagerQueryReq, TableManagerReq, TableManagerResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeadLetterType {
+    Webhook,
+    UdpPush,
+}
+
+///
+/// 重试耗尽后仍未投递成功的通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter {
+    #[serde(default)]
+    pub id: String,
+    pub letter_type: DeadLetterType,
+    /// webhook名称或服务名称
+    pub name: String,
+    /// webhook地址或udp客户端地址
+    pub target: String,
+    /// webhook请求内容;udp推送为服务key,重新投递时推送服务的当前实例
+    pub payload: String,
+    pub error: String,
+    pub create_time: u64,
+    #[serde(default)]
+    pub requeue_count: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PushServiceKey {
+    namespace_id: String,
+    group_name: String,
+    service_name: String,
+}
+
+impl DeadLetter {
+    pub fn new_webhook(name: &str, url: &str, body: &[u8], error: String) -> Self {
+        Self {
+            id: String::new(),
+            letter_type: DeadLetterType::Webhook,
+            name: name.to_owned(),
+            target: url.to_owned(),
+            payload: String::from_utf8_lossy(body).into_owned(),
+            error,
+            create_time: now_millis(),
+            requeue_count: 0,
+        }
+    }
+
+    pub fn new_udp_push(key: &ServiceKey, addr: &str, error: String) -> Self {
+        let service_key = PushServiceKey {
+            namespace_id: key.namespace_id.as_ref().to_owned(),
+            group_name: key.group_name.as_ref().to_owned(),
+            service_name: key.service_name.as_ref().to_owned(),
+        };
+        Self {
+            id: String::new(),
+            letter_type: DeadLetterType::UdpPush,
+            name: format!("{}@@{}", &key.group_name, &key.service_name),
+            target: addr.to_owned(),
+            payload: serde_json::to_string(&service_key).unwrap_or_default(),
+            error,
+            create_time: now_millis(),
+            requeue_count: 0,
+        }
+    }
+
+    ///
+    /// 按写入时间排序;带上节点id避免多个节点同时写入时冲突
+    fn build_id(time: u64, node_id: u64, seq: u64) -> String {
+        format!("{:013}_{}_{}", time, node_id, seq)
+    }
+
+    fn get_service_key(&self) -> anyhow::Result<ServiceKey> {
+        let key: PushServiceKey = serde_json::from_str(&self.payload)?;
+        Ok(ServiceKey::new(
+            &key.namespace_id,
+            &key.group_name,
+            &key.service_name,
+        ))
+    }
+}
+
+///
+/// 死信存储,保存在raft表中;超过最大数量时淘汰最早的记录
+#[bean(inject)]
+#[derive(Default)]
+pub struct DeadLetterManager {
+    raft_table_route: Option<Arc<TableRoute>>,
+    table_manager: Option<Addr<TableManager>>,
+    node_id: u64,
+    max_size: usize,
+    seq: u64,
+}
+
+impl DeadLetterManager {
+    pub fn new(sys_config: &AppSysConfig) -> Self {
+        Self {
+            node_id: sys_config.raft_node_id,
+            max_size: sys_config.dead_letter_max_size,
+            ..Default::default()
+        }
+    }
+
+    async fn query_list(
+        table_manager: &Addr<TableManager>,
+        limit: Option<i64>,
+    ) -> anyhow::Result<(usize, Vec<DeadLetter>)> {
+        let req = TableManagerQueryReq::QueryPageList {
+            table_name: DEAD_LETTER_TREE_NAME.clone(),
+            like_key: None,
+            offset: None,
+            limit,
+            is_rev: false,
+        };
+        let mut list = vec![];
+        match table_manager.send(req).await?? {
+            TableManagerResult::PageListResult(total, values) => {
+                for (_, v) in values {
+                    list.push(serde_json::from_slice(&v)?);
+                }
+                Ok((total, list))
+            }
+            _ => Ok((0, list)),
+        }
+    }
+
+    async fn add(
+        raft_table_route: &TableRoute,
+        table_manager: &Addr<TableManager>,
+        letter: DeadLetter,
+        max_size: usize,
+    ) -> anyhow::Result<()> {
+        let req = TableManagerReq::Set {
+            table_name: DEAD_LETTER_TREE_NAME.clone(),
+            key: letter.id.as_bytes().to_vec(),
+            value: serde_json::to_vec(&letter)?,
+            last_seq_id: None,
+        };
+        raft_table_route.request(req).await?;
+        let (total, _) = Self::query_list(table_manager, Some(0)).await?;
+        if total > max_size {
+            let (_, list) =
+                Self::query_list(table_manager, Some((total - max_size) as i64)).await?;
+            let ids = list.into_iter().map(|e| e.id).collect();
+            Self::remove(raft_table_route, ids).await?;
+        }
+        Ok(())
+    }
+
+    async fn remove(raft_table_route: &TableRoute, ids: Vec<String>) -> anyhow::Result<()> {
+        for id in ids {
+            let req = TableManagerReq::Remove {
+                table_name: DEAD_LETTER_TREE_NAME.clone(),
+                key: id.into_bytes(),
+            };
+            raft_table_route.request(req).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Actor for DeadLetterManager {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("DeadLetterManager started");
+    }
+}
+
+impl Inject for DeadLetterManager {
+    type Context = Context<Self>;
+
+    fn inject(
+        &mut self,
+        factory_data: bean_factory::FactoryData,
+        _factory: bean_factory::BeanFactory,
+        _ctx: &mut Self::Context,
+    ) {
+        self.raft_table_route = factory_data.get_bean();
+        self.table_manager = factory_data.get_actor();
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "anyhow::Result<DeadLetterResult>")]
+pub enum DeadLetterCmd {
+    Add(DeadLetter),
+    /// 更新重新投递失败的记录
+    Update(DeadLetter),
+    QueryList,
+    /// 为空时清空全部
+    Remove(Vec<String>),
+}
+
+pub enum DeadLetterResult {
+    None,
+    List(Vec<DeadLetter>),
+}
+
+impl Handler<DeadLetterCmd> for DeadLetterManager {
+    type Result = ResponseActFuture<Self, anyhow::Result<DeadLetterResult>>;
+
+    fn handle(&mut self, msg: DeadLetterCmd, _ctx: &mut Self::Context) -> Self::Result {
+        let raft_table_route = self.raft_table_route.clone();
+        let table_manager = self.table_manager.clone();
+        let max_size = self.max_size;
+        let msg = match msg {
+            DeadLetterCmd::Add(mut letter) => {
+                if max_size == 0 {
+                    return Box::pin(async { Ok(DeadLetterResult::None) }.into_actor(self));
+                }
+                self.seq += 1;
+                letter.id = DeadLetter::build_id(letter.create_time, self.node_id, self.seq);
+                DeadLetterCmd::Add(letter)
+            }
+            _ => msg,
+        };
+        let fut = async move {
+            let (raft_table_route, table_manager) = match (raft_table_route, table_manager) {
+                (Some(a), Some(b)) => (a, b),
+                _ => return Err(anyhow::anyhow!("dead letter store is not ready")),
+            };
+            match msg {
+                DeadLetterCmd::Add(letter) => {
+                    Self::add(&raft_table_route, &table_manager, letter, max_size).await?;
+                    Ok(DeadLetterResult::None)
+                }
+                DeadLetterCmd::Update(letter) => {
+                    let req = TableManagerReq::Set {
+                        table_name: DEAD_LETTER_TREE_NAME.clone(),
+                        key: letter.id.as_bytes().to_vec(),
+                        value: serde_json::to_vec(&letter)?,
+                        last_seq_id: None,
+                    };
+                    raft_table_route.request(req).await?;
+                    Ok(DeadLetterResult::None)
+                }
+                DeadLetterCmd::QueryList => {
+                    let (_, list) = Self::query_list(&table_manager, None).await?;
+                    Ok(DeadLetterResult::List(list))
+                }
+                DeadLetterCmd::Remove(mut ids) => {
+                    if ids.is_empty() {
+                        let (_, list) = Self::query_list(&table_manager, None).await?;
+                        ids = list.into_iter().map(|e| e.id).collect();
+                    }
+                    Self::remove(&raft_table_route, ids).await?;
+                    Ok(DeadLetterResult::None)
+                }
+            }
+        }
+        .into_actor(self)
+        .map(|res, _act, _ctx| {
+            if let Err(err) = &res {
+                log::warn!("dead letter request error,{}", err);
+            }
+            res
+        });
+        Box::pin(fut)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterRequeueResult {
+    pub success_count: usize,
+    pub fail_count: usize,
+}
+
+pub struct DeadLetterUtils;
+
+impl DeadLetterUtils {
+    pub async fn query_list(app: &AppShareData) -> anyhow::Result<Vec<DeadLetter>> {
+        match app
+            .dead_letter_manager
+            .send(DeadLetterCmd::QueryList)
+            .await??
+        {
+            DeadLetterResult::List(list) => Ok(list),
+            DeadLetterResult::None => Ok(vec![]),
+        }
+    }
+
+    ///
+    /// 重新投递指定的死信,ids为空时重新投递全部;投递成功后删除,失败的保留
+    pub async fn requeue(
+        app: &AppShareData,
+        ids: Vec<String>,
+    ) -> anyhow::Result<DeadLetterRequeueResult> {
+        let mut list = Self::query_list(app).await?;
+        if !ids.is_empty() {
+            list.retain(|e| ids.contains(&e.id));
+        }
+        let mut result = DeadLetterRequeueResult::default();
+        let mut success_ids = vec![];
+        for mut letter in list {
+            match Self::deliver(app, &letter).await {
+                Ok(_) => {
+                    result.success_count += 1;
+                    success_ids.push(letter.id);
+                }
+                Err(err) => {
+                    result.fail_count += 1;
+                    letter.error = err.to_string();
+                    letter.requeue_count += 1;
+                    app.dead_letter_manager
+                        .send(DeadLetterCmd::Update(letter))
+                        .await??;
+                }
+            }
+        }
+        if !success_ids.is_empty() {
+            app.dead_letter_manager
+                .send(DeadLetterCmd::Remove(success_ids))
+                .await??;
+        }
+        Ok(result)
+    }
+
+    async fn deliver(app: &AppShareData, letter: &DeadLetter) -> anyhow::Result<()> {
+        match letter.letter_type {
+            DeadLetterType::Webhook => {
+                //优先使用当前配置的webhook地址
+                let url = app
+                    .cluster_settings
+                    .get_webhook(&letter.name)
+                    .unwrap_or_else(|| letter.target.clone());
+                WebhookUtils::post(&url, letter.payload.as_bytes().to_vec()).await
+            }
+            DeadLetterType::UdpPush => {
+                let key = letter.get_service_key()?;
+                let listener: Addr<InnerNamingListener> = app
+                    .factory_data
+                    .get_actor()
+                    .ok_or_else(|| anyhow::anyhow!("naming listener is not found"))?;
+                listener.send(NamingListenerCmd::Changed(key)).await??;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_letter() {
+        let a = DeadLetter::build_id(999, 2, 10);
+        let b = DeadLetter::build_id(1000, 1, 1);
+        assert!(a < b);
+        let key = ServiceKey::new("dev", "DEFAULT_GROUP", "foo");
+        let letter = DeadLetter::new_udp_push(&key, "127.0.0.1:5000", "ack timeout".to_owned());
+        assert_eq!(letter.name, "DEFAULT_GROUP@@foo");
+        assert_eq!(letter.get_service_key().unwrap(), key);
+    }
+}
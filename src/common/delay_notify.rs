@@ -19,6 +19,8 @@ where
 {
     pub(crate) timeout_set: TimeoutSet<K>,
     pub(crate) notify_map: HashMap<K, T>,
+    /// 防抖窗口: key -> (首次事件时间, 当前触发时间)
+    pub(crate) debounce_map: HashMap<K, (u64, u64)>,
 }
 
 impl<K, T> DelayNotify<K, T>
@@ -30,6 +32,7 @@ where
         DelayNotify {
             timeout_set: Default::default(),
             notify_map: Default::default(),
+            debounce_map: Default::default(),
         }
     }
 
@@ -44,7 +47,37 @@ where
         Ok(())
     }
 
+    ///
+    /// 防抖方式添加事件：窗口内的新事件会合并并把触发时间顺延delay,
+    /// 但从首次事件起最多延迟max_delay,避免持续变更时一直不推送。
+    pub fn add_debounce_event(
+        &mut self,
+        delay: u64,
+        max_delay: u64,
+        key: K,
+        event: T,
+    ) -> anyhow::Result<()> {
+        let now = now_millis();
+        if let Some(v) = self.notify_map.get_mut(&key) {
+            v.merge(event)?;
+            if let Some((first_time, deadline)) = self.debounce_map.get_mut(&key) {
+                let next_deadline = std::cmp::min(now + delay, *first_time + max_delay);
+                if next_deadline > *deadline {
+                    *deadline = next_deadline;
+                    self.timeout_set.add(next_deadline, key);
+                }
+            }
+        } else {
+            let time_out = now + delay;
+            self.timeout_set.add(time_out, key.to_owned());
+            self.debounce_map.insert(key.to_owned(), (now, time_out));
+            self.notify_map.insert(key, event);
+        }
+        Ok(())
+    }
+
     pub fn notify(&mut self, key: &K) -> anyhow::Result<()> {
+        self.debounce_map.remove(key);
         if let Some(v) = self.notify_map.remove(key) {
             v.on_event()?;
         }
@@ -57,15 +90,23 @@ where
     }
 
     pub fn notify_timeout(&mut self) -> anyhow::Result<()> {
-        for key in self.timeout_set.timeout(now_millis()) {
-            self.notify(&key)?;
+        for v in self.timeout()? {
+            v.on_event()?;
         }
         Ok(())
     }
 
     pub fn timeout(&mut self) -> anyhow::Result<Vec<T>> {
+        let now = now_millis();
         let mut l = vec![];
-        for key in self.timeout_set.timeout(now_millis()) {
+        for key in self.timeout_set.timeout(now) {
+            if let Some((_, deadline)) = self.debounce_map.get(&key) {
+                if *deadline > now {
+                    //触发时间已被顺延
+                    continue;
+                }
+                self.debounce_map.remove(&key);
+            }
             if let Some(v) = self.notify_map.remove(&key) {
                 l.push(v);
             }
@@ -73,3 +114,45 @@ where
         Ok(l)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct CountEvent(usize);
+
+    impl NotifyEvent for CountEvent {
+        fn on_event(self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn merge(&mut self, other: Self) -> anyhow::Result<()> {
+            self.0 += other.0;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_debounce_event_merge() {
+        let mut delay_notify: DelayNotify<u64, CountEvent> = DelayNotify::new();
+        for _ in 0..3 {
+            delay_notify
+                .add_debounce_event(0, 0, 1, CountEvent(1))
+                .unwrap();
+        }
+        delay_notify
+            .add_debounce_event(0, 0, 2, CountEvent(1))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let mut list: Vec<usize> = delay_notify
+            .timeout()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.0)
+            .collect();
+        list.sort();
+        assert_eq!(list, vec![1, 3]);
+        assert!(delay_notify.debounce_map.is_empty());
+    }
+}
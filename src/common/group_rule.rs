@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::naming::{DEFAULT_GROUP, DEFAULT_NAMESPACE};
+
+/// 分组规则在系统配置中的key
+pub const SYSCONFIG_GROUP_RULE_KEY: &str = "namespace_group_rules";
+
+///
+/// 命名空间分组规则
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceGroupRule {
+    pub namespace_id: String,
+    /// 客户端使用DEFAULT_GROUP且该分组不存在数据时,使用此分组
+    pub default_group: Option<String>,
+    /// 分组别名,旧分组名->新分组名
+    #[serde(default)]
+    pub group_alias: HashMap<String, String>,
+}
+
+///
+/// 命名空间默认分组与分组别名管理;
+/// 配置与服务查询时,原分组下没有数据才按规则解析到目标分组
+#[derive(Debug, Default)]
+pub struct GroupRuleManager {
+    rules: RwLock<HashMap<String, HashMap<String, Arc<String>>>>,
+}
+
+impl GroupRuleManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn namespace_key(namespace_id: &str) -> &str {
+        if namespace_id.is_empty() {
+            DEFAULT_NAMESPACE
+        } else {
+            namespace_id
+        }
+    }
+
+    pub fn parse_rules(value: &str) -> anyhow::Result<Vec<NamespaceGroupRule>> {
+        if value.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(serde_json::from_str(value)?)
+    }
+
+    pub fn update_rules(&self, rules: Vec<NamespaceGroupRule>) {
+        let mut map = HashMap::new();
+        for rule in rules {
+            let mut alias_map: HashMap<String, Arc<String>> = HashMap::new();
+            for (from, to) in rule.group_alias {
+                if !from.is_empty() && !to.is_empty() && from != to {
+                    alias_map.insert(from, Arc::new(to));
+                }
+            }
+            if let Some(default_group) = rule.default_group {
+                if !default_group.is_empty() && default_group != DEFAULT_GROUP {
+                    alias_map
+                        .entry(DEFAULT_GROUP.to_owned())
+                        .or_insert(Arc::new(default_group));
+                }
+            }
+            if !alias_map.is_empty() {
+                map.insert(
+                    Self::namespace_key(&rule.namespace_id).to_owned(),
+                    alias_map,
+                );
+            }
+        }
+        if let Ok(mut rules) = self.rules.write() {
+            *rules = map;
+        }
+    }
+
+    pub fn update_from_str(&self, value: &str) {
+        match Self::parse_rules(value) {
+            Ok(rules) => self.update_rules(rules),
+            Err(err) => log::warn!("parse namespace group rules error,{}", err),
+        }
+    }
+
+    ///
+    /// 按规则解析分组,没有匹配规则时返回None
+    pub fn resolve_group(&self, namespace_id: &str, group: &str) -> Option<Arc<String>> {
+        if let Ok(rules) = self.rules.read() {
+            if let Some(alias_map) = rules.get(Self::namespace_key(namespace_id)) {
+                return alias_map.get(group).cloned();
+            }
+        }
+        None
+    }
+
+    ///
+    /// 查询会被解析到目标分组的旧分组列表,用于变更时通知使用旧分组的客户端
+    pub fn alias_groups(&self, namespace_id: &str, group: &str) -> Vec<Arc<String>> {
+        let mut list = vec![];
+        if let Ok(rules) = self.rules.read() {
+            if let Some(alias_map) = rules.get(Self::namespace_key(namespace_id)) {
+                for (from, to) in alias_map {
+                    if to.as_str() == group {
+                        list.push(Arc::new(from.to_owned()));
+                    }
+                }
+            }
+        }
+        list
+    }
+}
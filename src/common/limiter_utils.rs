@@ -66,3 +66,33 @@ impl From<RateLimiter> for LimiterData {
         Self::new(a, b, c)
     }
 }
+
+///
+/// 按秒计数的简单限流器,用于单节点内的请求tps控制
+pub struct SecondLimiter {
+    limit: u32,
+    state: std::sync::Mutex<(u64, u32)>,
+}
+
+impl SecondLimiter {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            state: std::sync::Mutex::new((0, 0)),
+        }
+    }
+
+    pub fn acquire(&self) -> bool {
+        let now_second = crate::now_millis() / 1000;
+        if let Ok(mut state) = self.state.lock() {
+            if state.0 != now_second {
+                *state = (now_second, 0);
+            }
+            if state.1 >= self.limit {
+                return false;
+            }
+            state.1 += 1;
+        }
+        true
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::naming::model::{Instance, ServiceKey};
+use crate::naming::DEFAULT_NAMESPACE;
+
+/// 服务实例元数据约束在系统配置中的key
+pub const SYSCONFIG_METADATA_SCHEMA_KEY: &str = "naming_metadata_schemas";
+
+/// 标记模式下,不符合约束的实例会在元数据中写入该key,值为不符合的原因
+pub const METADATA_SCHEMA_VIOLATION_KEY: &str = "rnacos.schema.violation";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataValueType {
+    #[default]
+    String,
+    Int,
+    Number,
+    Bool,
+    Semver,
+}
+
+impl MetadataValueType {
+    fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Int => value.parse::<i64>().is_ok(),
+            Self::Number => value.parse::<f64>().map(|v| v.is_finite()).unwrap_or(false),
+            Self::Bool => value == "true" || value == "false",
+            Self::Semver => is_semver(value),
+        }
+    }
+}
+
+///
+/// MAJOR.MINOR.PATCH,可带 -预发布版本 与 +构建信息
+fn is_semver(value: &str) -> bool {
+    let value = value.split_once('+').map(|(v, _)| v).unwrap_or(value);
+    let (version, pre) = match value.split_once('-') {
+        Some((v, pre)) => (v, Some(pre)),
+        None => (value, None),
+    };
+    let parts: Vec<&str> = version.split('.').collect();
+    let is_number = |v: &&str| {
+        !v.is_empty()
+            && v.chars().all(|c| c.is_ascii_digit())
+            && (v.len() == 1 || !v.starts_with('0'))
+    };
+    if parts.len() != 3 || !parts.iter().all(is_number) {
+        return false;
+    }
+    match pre {
+        Some(pre) => pre
+            .split('.')
+            .all(|v| !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')),
+        None => true,
+    }
+}
+
+///
+/// 不符合约束时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataSchemaMode {
+    /// 拒绝注册
+    #[default]
+    Reject,
+    /// 允许注册,在实例元数据中标记不符合的原因
+    Flag,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataFieldRule {
+    pub key: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub value_type: MetadataValueType,
+    /// 值需要完整匹配的正则表达式
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// 可选值列表,为空时不限制
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+}
+
+///
+/// 服务实例的元数据约束,注册实例时校验
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceMetadataSchema {
+    pub namespace_id: String,
+    pub group_name: String,
+    pub service_name: String,
+    #[serde(default)]
+    pub mode: MetadataSchemaMode,
+    #[serde(default)]
+    pub fields: Vec<MetadataFieldRule>,
+}
+
+impl ServiceMetadataSchema {
+    pub fn check_valid(&self) -> anyhow::Result<()> {
+        if self.group_name.is_empty() || self.service_name.is_empty() {
+            return Err(anyhow::anyhow!("groupName and serviceName can't be empty"));
+        }
+        for field in &self.fields {
+            if field.key.is_empty() {
+                return Err(anyhow::anyhow!("metadata key can't be empty"));
+            }
+            if let Some(pattern) = &field.pattern {
+                Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("invalid pattern of {},{}", &field.key, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_service_key(&self) -> ServiceKey {
+        ServiceKey::new(
+            MetadataSchemaManager::namespace_key(&self.namespace_id),
+            &self.group_name,
+            &self.service_name,
+        )
+    }
+}
+
+struct CompiledFieldRule {
+    rule: MetadataFieldRule,
+    pattern: Option<Regex>,
+}
+
+struct CompiledSchema {
+    mode: MetadataSchemaMode,
+    fields: Vec<CompiledFieldRule>,
+}
+
+impl CompiledSchema {
+    fn new(schema: ServiceMetadataSchema) -> Self {
+        let fields = schema
+            .fields
+            .into_iter()
+            .map(|rule| CompiledFieldRule {
+                pattern: rule
+                    .pattern
+                    .as_ref()
+                    .and_then(|v| Regex::new(&format!("^(?:{})$", v)).ok()),
+                rule,
+            })
+            .collect();
+        Self {
+            mode: schema.mode,
+            fields,
+        }
+    }
+
+    fn validate(&self, metadata: &HashMap<String, String>) -> Result<(), String> {
+        for field in &self.fields {
+            let key = &field.rule.key;
+            let value = match metadata.get(key) {
+                Some(v) => v,
+                None if field.rule.required => return Err(format!("{} is required", key)),
+                None => continue,
+            };
+            if !field.rule.value_type.is_valid(value) {
+                return Err(format!(
+                    "{}={} is not {:?}",
+                    key, value, field.rule.value_type
+                ));
+            }
+            if let Some(pattern) = &field.pattern {
+                if !pattern.is_match(value) {
+                    return Err(format!("{}={} does not match the pattern", key, value));
+                }
+            }
+            if !field.rule.allowed_values.is_empty() && !field.rule.allowed_values.contains(value) {
+                return Err(format!("{}={} is not an allowed value", key, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// 服务实例元数据约束管理
+#[derive(Default)]
+pub struct MetadataSchemaManager {
+    schemas: RwLock<HashMap<ServiceKey, Arc<CompiledSchema>>>,
+}
+
+impl MetadataSchemaManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn namespace_key(namespace_id: &str) -> &str {
+        if namespace_id.is_empty() {
+            DEFAULT_NAMESPACE
+        } else {
+            namespace_id
+        }
+    }
+
+    pub fn parse_schemas(value: &str) -> anyhow::Result<Vec<ServiceMetadataSchema>> {
+        if value.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(serde_json::from_str(value)?)
+    }
+
+    pub fn update_schemas(&self, schemas: Vec<ServiceMetadataSchema>) {
+        let mut map = HashMap::new();
+        for schema in schemas {
+            if let Err(err) = schema.check_valid() {
+                log::warn!("ignore invalid metadata schema,{}", err);
+                continue;
+            }
+            map.insert(
+                schema.get_service_key(),
+                Arc::new(CompiledSchema::new(schema)),
+            );
+        }
+        if let Ok(mut v) = self.schemas.write() {
+            *v = map;
+        }
+    }
+
+    pub fn update_from_str(&self, value: &str) {
+        match Self::parse_schemas(value) {
+            Ok(schemas) => self.update_schemas(schemas),
+            Err(err) => log::warn!("parse metadata schemas error,{}", err),
+        }
+    }
+
+    ///
+    /// 按服务的元数据约束校验实例;拒绝模式返回错误,标记模式在元数据中写入不符合的原因
+    pub fn check_instance(&self, instance: &mut Instance) -> anyhow::Result<()> {
+        let schema = match self
+            .schemas
+            .read()
+            .ok()
+            .and_then(|v| v.get(&instance.get_service_key()).cloned())
+        {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let result = schema.validate(&instance.metadata);
+        match (result, schema.mode) {
+            (Ok(_), _) => {
+                if instance
+                    .metadata
+                    .contains_key(METADATA_SCHEMA_VIOLATION_KEY)
+                {
+                    Arc::make_mut(&mut instance.metadata).remove(METADATA_SCHEMA_VIOLATION_KEY);
+                }
+                Ok(())
+            }
+            (Err(message), MetadataSchemaMode::Reject) => Err(anyhow::anyhow!(
+                "instance metadata does not match the schema of service {}@@{}, {}",
+                &instance.group_name,
+                &instance.service_name,
+                message
+            )),
+            (Err(message), MetadataSchemaMode::Flag) => {
+                Arc::make_mut(&mut instance.metadata)
+                    .insert(METADATA_SCHEMA_VIOLATION_KEY.to_owned(), message);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_schema() {
+        assert!(is_semver("1.2.3"));
+        assert!(is_semver("1.0.0-rc.1+build.5"));
+        assert!(!is_semver("1.2"));
+        assert!(!is_semver("01.2.3"));
+
+        let manager = MetadataSchemaManager::new();
+        let fields = vec![
+            MetadataFieldRule {
+                key: "version".to_owned(),
+                required: true,
+                value_type: MetadataValueType::Semver,
+                ..Default::default()
+            },
+            MetadataFieldRule {
+                key: "zone".to_owned(),
+                allowed_values: vec!["a".to_owned(), "b".to_owned()],
+                ..Default::default()
+            },
+        ];
+        manager.update_schemas(vec![
+            ServiceMetadataSchema {
+                namespace_id: "".to_owned(),
+                group_name: "DEFAULT_GROUP".to_owned(),
+                service_name: "api".to_owned(),
+                fields: fields.clone(),
+                ..Default::default()
+            },
+            ServiceMetadataSchema {
+                namespace_id: "".to_owned(),
+                group_name: "DEFAULT_GROUP".to_owned(),
+                service_name: "web".to_owned(),
+                mode: MetadataSchemaMode::Flag,
+                fields,
+            },
+        ]);
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.service_name = Arc::new("api".to_owned());
+        assert!(manager.check_instance(&mut instance).is_err());
+        let mut metadata = HashMap::new();
+        metadata.insert("version".to_owned(), "1.2.0".to_owned());
+        metadata.insert("zone".to_owned(), "a".to_owned());
+        instance.metadata = Arc::new(metadata);
+        assert!(manager.check_instance(&mut instance).is_ok());
+
+        instance.service_name = Arc::new("web".to_owned());
+        Arc::make_mut(&mut instance.metadata).insert("zone".to_owned(), "c".to_owned());
+        assert!(manager.check_instance(&mut instance).is_ok());
+        assert!(instance
+            .metadata
+            .contains_key(METADATA_SCHEMA_VIOLATION_KEY));
+    }
+}
@@ -1,26 +1,49 @@
 use crate::common::string_utils::StringUtils;
+use crate::console::middle::trusted_header::{
+    TrustedHeaderAuth, DEFAULT_TRUSTED_GROUPS_HEADER, DEFAULT_TRUSTED_USER_HEADER,
+};
+use crate::user::password::{PasswordHashAlgorithm, PasswordPolicy};
+use chrono::{FixedOffset, Local, Offset};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 pub mod actor_utils;
 pub mod appdata;
 pub mod byte_utils;
+pub mod cluster_settings;
+pub mod concurrency_limit;
 pub mod constant;
 pub mod crypto_utils;
 pub mod cycle_queue;
 pub mod datetime_utils;
+pub mod dead_letter;
 pub mod delay_notify;
+pub mod group_rule;
 pub mod hash_utils;
 pub mod limiter_utils;
 pub mod macros;
+pub mod metadata_schema;
 pub mod model;
+pub mod namespace_quota;
 pub mod option_utils;
 pub mod protobuf_utils;
+pub mod public_status;
+pub mod resource_event;
 pub mod rusqlite_utils;
+pub mod schema_version;
+pub mod sdk_usage;
 pub mod sequence_utils;
+pub mod service_link;
+pub mod shutdown;
 pub mod sled_utils;
 pub mod string_utils;
+pub mod support_bundle;
+pub mod trace_utils;
+#[cfg(unix)]
+pub mod unix_socket;
 pub mod web_utils;
+pub mod webhook;
 /*
 use lazy_static::lazy_static;
 lazy_static! {
@@ -59,9 +82,25 @@ pub struct AppSysConfig {
     pub grpc_port: u16,
     pub raft_node_id: u64,
     pub raft_node_addr: String,
+    pub http_bind_host: String,
+    pub grpc_bind_host: String,
+    pub console_bind_host: String,
+    /// 集群内部通信(raft、节点同步)独立的grpc监听地址ip:port
+    pub cluster_grpc_addr: Option<String>,
+    /// open api额外监听的unix domain socket路径,供本机sidecar访问
+    pub http_unix_socket: Option<String>,
+    /// grpc额外监听的unix domain socket路径
+    pub grpc_unix_socket: Option<String>,
+    /// unix domain socket文件权限
+    pub unix_socket_mode: u32,
     pub raft_auto_init: bool,
     pub raft_join_addr: String,
     pub raft_snapshot_log_size: u64,
+    pub raft_compress_threshold: usize,
+    pub raft_diff_snapshot_enable: bool,
+    pub raft_max_payload_entries: u64,
+    pub session_store: String,
+    pub session_redis_url: String,
     pub console_login_timeout: i32,
     pub console_login_one_hour_limit: u32,
     pub gmt_fixed_offset_hours: Option<i32>,
@@ -75,7 +114,47 @@ pub struct AppSysConfig {
     pub metrics_collect_interval_second: u64,
     pub metrics_log_interval_second: u64,
     pub metrics_log_enable: bool,
+    /// 推送监控指标的方式: statsd、otlp,为空时不推送
+    pub metrics_exporter: String,
+    pub metrics_exporter_addr: String,
+    pub metrics_exporter_interval_second: u64,
     pub console_captcha_enable: bool,
+    pub naming_push_debounce_millis: u64,
+    pub naming_push_max_delay_millis: u64,
+    pub naming_push_ack_timeout_second: u64,
+    pub naming_repush_batch_size: usize,
+    pub naming_flapping_window_second: u64,
+    pub naming_flapping_threshold: usize,
+    pub naming_flapping_damping: bool,
+    pub naming_change_history_size: usize,
+    pub naming_quarantine_ttl_second: u64,
+    pub naming_client_beat_interval_millis: i64,
+    pub naming_light_beat_enabled: bool,
+    pub dead_letter_max_size: usize,
+    pub config_subscribe_replay_ttl_second: u64,
+    pub system_namespaces: Vec<String>,
+    pub grpc_request_tps_limit: HashMap<String, u32>,
+    pub console_static_dir: Option<String>,
+    pub stale_read_max_millis: u64,
+    pub naming_srv_weight_strategy: String,
+    pub naming_srv_priority_strategy: String,
+    pub naming_srv_ttl_second: u32,
+    pub config_protect_cipher_delete: bool,
+    pub config_encryption_keys: String,
+    pub public_status_tps: u32,
+    pub shutdown_api_local_only: bool,
+    pub concurrency_limit_config_publish: usize,
+    pub concurrency_limit_instance_register: usize,
+    pub concurrency_limit_console_query: usize,
+    pub concurrency_queue_size: usize,
+    pub concurrency_queue_timeout_millis: u64,
+    pub node_labels: HashMap<String, String>,
+    pub canary_node: bool,
+    pub canary_token: String,
+    pub naming_zone_aware: bool,
+    pub naming_weighted_shuffle: bool,
+    pub user_password_policy: PasswordPolicy,
+    pub console_trusted_header_auth: Arc<TrustedHeaderAuth>,
 }
 
 impl AppSysConfig {
@@ -108,8 +187,31 @@ impl AppSysConfig {
             .unwrap_or("1".to_owned())
             .parse()
             .unwrap_or(1);
+        let bind_host = std::env::var("RNACOS_BIND_HOST").unwrap_or("0.0.0.0".to_owned());
+        let http_bind_host = std::env::var("RNACOS_HTTP_BIND_HOST").unwrap_or(bind_host.clone());
+        let grpc_bind_host = std::env::var("RNACOS_GRPC_BIND_HOST").unwrap_or(bind_host.clone());
+        let console_bind_host = std::env::var("RNACOS_CONSOLE_BIND_HOST").unwrap_or(bind_host);
+        let cluster_grpc_addr =
+            StringUtils::map_not_empty(std::env::var("RNACOS_CLUSTER_GRPC_ADDR").ok());
+        let default_raft_node_addr = match cluster_grpc_addr
+            .as_ref()
+            .and_then(|v| v.parse::<std::net::SocketAddr>().ok())
+        {
+            Some(addr) if !addr.ip().is_unspecified() => addr.to_string(),
+            Some(addr) => format!("127.0.0.1:{}", addr.port()),
+            None => format!("127.0.0.1:{}", &grpc_port),
+        };
         let raft_node_addr =
-            std::env::var("RNACOS_RAFT_NODE_ADDR").unwrap_or(format!("127.0.0.1:{}", &grpc_port));
+            std::env::var("RNACOS_RAFT_NODE_ADDR").unwrap_or(default_raft_node_addr);
+        let http_unix_socket =
+            StringUtils::map_not_empty(std::env::var("RNACOS_HTTP_UNIX_SOCKET").ok());
+        let grpc_unix_socket =
+            StringUtils::map_not_empty(std::env::var("RNACOS_GRPC_UNIX_SOCKET").ok());
+        let unix_socket_mode = u32::from_str_radix(
+            &std::env::var("RNACOS_UNIX_SOCKET_MODE").unwrap_or("660".to_owned()),
+            8,
+        )
+        .unwrap_or(0o660);
         let raft_auto_init = std::env::var("RNACOS_RAFT_AUTO_INIT")
             .unwrap_or("".to_owned())
             .parse()
@@ -135,6 +237,21 @@ impl AppSysConfig {
             .unwrap_or("10000".to_owned())
             .parse()
             .unwrap_or(10000);
+        let raft_compress_threshold = std::env::var("RNACOS_RAFT_COMPRESS_THRESHOLD")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let raft_diff_snapshot_enable = std::env::var("RNACOS_RAFT_DIFF_SNAPSHOT_ENABLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let raft_max_payload_entries = std::env::var("RNACOS_RAFT_MAX_PAYLOAD_ENTRIES")
+            .unwrap_or("300".to_owned())
+            .parse()
+            .unwrap_or(300);
+        let session_store = std::env::var("RNACOS_SESSION_STORE").unwrap_or("raft".to_owned());
+        let session_redis_url = std::env::var("RNACOS_SESSION_REDIS_URL")
+            .unwrap_or("redis://127.0.0.1:6379".to_owned());
         let enable_no_auth_console = std::env::var("RNACOS_ENABLE_NO_AUTH_CONSOLE")
             .unwrap_or("false".to_owned())
             .parse()
@@ -169,6 +286,164 @@ impl AppSysConfig {
             .unwrap_or("true".to_owned())
             .parse()
             .unwrap_or(true);
+        let naming_push_debounce_millis = std::env::var("RNACOS_NAMING_PUSH_DEBOUNCE_MILLIS")
+            .unwrap_or("500".to_owned())
+            .parse()
+            .unwrap_or(500);
+        let mut naming_push_max_delay_millis = std::env::var("RNACOS_NAMING_PUSH_MAX_DELAY_MILLIS")
+            .unwrap_or("3000".to_owned())
+            .parse()
+            .unwrap_or(3000);
+        if naming_push_max_delay_millis < naming_push_debounce_millis {
+            naming_push_max_delay_millis = naming_push_debounce_millis;
+        }
+        let naming_push_ack_timeout_second = std::env::var("RNACOS_NAMING_PUSH_ACK_TIMEOUT_SECOND")
+            .unwrap_or("10".to_owned())
+            .parse()
+            .unwrap_or(10);
+        let naming_repush_batch_size = std::env::var("RNACOS_NAMING_REPUSH_BATCH_SIZE")
+            .unwrap_or("200".to_owned())
+            .parse()
+            .unwrap_or(200);
+        let naming_flapping_window_second = std::env::var("RNACOS_NAMING_FLAPPING_WINDOW_SECOND")
+            .unwrap_or("60".to_owned())
+            .parse()
+            .unwrap_or(60);
+        let naming_flapping_threshold = std::env::var("RNACOS_NAMING_FLAPPING_THRESHOLD")
+            .unwrap_or("4".to_owned())
+            .parse()
+            .unwrap_or(4);
+        let naming_flapping_damping = std::env::var("RNACOS_NAMING_FLAPPING_DAMPING")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let naming_change_history_size = std::env::var("RNACOS_NAMING_CHANGE_HISTORY_SIZE")
+            .unwrap_or("100".to_owned())
+            .parse()
+            .unwrap_or(100);
+        let naming_quarantine_ttl_second = std::env::var("RNACOS_NAMING_QUARANTINE_TTL_SECOND")
+            .unwrap_or("600".to_owned())
+            .parse()
+            .unwrap_or(600);
+        let naming_client_beat_interval_millis =
+            std::env::var("RNACOS_NAMING_CLIENT_BEAT_INTERVAL_MILLIS")
+                .unwrap_or("5000".to_owned())
+                .parse()
+                .unwrap_or(5000);
+        let naming_light_beat_enabled = std::env::var("RNACOS_NAMING_LIGHT_BEAT_ENABLED")
+            .unwrap_or("true".to_owned())
+            .parse()
+            .unwrap_or(true);
+        let dead_letter_max_size = std::env::var("RNACOS_DEAD_LETTER_MAX_SIZE")
+            .unwrap_or("1000".to_owned())
+            .parse()
+            .unwrap_or(1000);
+        let config_subscribe_replay_ttl_second =
+            std::env::var("RNACOS_CONFIG_SUBSCRIBE_REPLAY_TTL_SECOND")
+                .unwrap_or("60".to_owned())
+                .parse()
+                .unwrap_or(60);
+        let system_namespaces: Vec<String> = std::env::var("RNACOS_SYSTEM_NAMESPACES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_owned())
+            .collect();
+        let grpc_request_tps_limit = Self::parse_tps_limit(
+            &std::env::var("RNACOS_GRPC_REQUEST_TPS_LIMIT").unwrap_or_default(),
+        );
+        let console_static_dir =
+            StringUtils::map_not_empty(std::env::var("RNACOS_CONSOLE_STATIC_DIR").ok());
+        let stale_read_max_millis = std::env::var("RNACOS_STALE_READ_MAX_MILLIS")
+            .unwrap_or("0".to_owned())
+            .parse()
+            .unwrap_or(0);
+        let naming_srv_weight_strategy =
+            std::env::var("RNACOS_NAMING_SRV_WEIGHT_STRATEGY").unwrap_or("scale".to_owned());
+        let naming_srv_priority_strategy =
+            std::env::var("RNACOS_NAMING_SRV_PRIORITY_STRATEGY").unwrap_or("cluster".to_owned());
+        let naming_srv_ttl_second = std::env::var("RNACOS_NAMING_SRV_TTL_SECOND")
+            .unwrap_or("5".to_owned())
+            .parse()
+            .unwrap_or(5);
+        let config_protect_cipher_delete = std::env::var("RNACOS_CONFIG_PROTECT_CIPHER_DELETE")
+            .unwrap_or("true".to_owned())
+            .parse()
+            .unwrap_or(true);
+        let config_encryption_keys =
+            std::env::var("RNACOS_CONFIG_ENCRYPTION_KEYS").unwrap_or_default();
+        let public_status_tps = std::env::var("RNACOS_PUBLIC_STATUS_TPS")
+            .unwrap_or("10".to_owned())
+            .parse()
+            .unwrap_or(10);
+        let shutdown_api_local_only = std::env::var("RNACOS_SHUTDOWN_API_LOCAL_ONLY")
+            .unwrap_or("true".to_owned())
+            .parse()
+            .unwrap_or(true);
+        let concurrency_limit_config_publish =
+            std::env::var("RNACOS_CONCURRENCY_LIMIT_CONFIG_PUBLISH")
+                .unwrap_or("64".to_owned())
+                .parse()
+                .unwrap_or(64);
+        let concurrency_limit_instance_register =
+            std::env::var("RNACOS_CONCURRENCY_LIMIT_INSTANCE_REGISTER")
+                .unwrap_or("256".to_owned())
+                .parse()
+                .unwrap_or(256);
+        let concurrency_limit_console_query =
+            std::env::var("RNACOS_CONCURRENCY_LIMIT_CONSOLE_QUERY")
+                .unwrap_or("32".to_owned())
+                .parse()
+                .unwrap_or(32);
+        let concurrency_queue_size = std::env::var("RNACOS_CONCURRENCY_QUEUE_SIZE")
+            .unwrap_or("512".to_owned())
+            .parse()
+            .unwrap_or(512);
+        let concurrency_queue_timeout_millis =
+            std::env::var("RNACOS_CONCURRENCY_QUEUE_TIMEOUT_MILLIS")
+                .unwrap_or("3000".to_owned())
+                .parse()
+                .unwrap_or(3000);
+        let mut node_labels = crate::naming::zone::ZoneUtils::parse_labels(
+            &std::env::var("RNACOS_NODE_LABELS").unwrap_or_default(),
+        );
+        let canary_node = std::env::var("RNACOS_CANARY_NODE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let canary_token = std::env::var("RNACOS_CANARY_TOKEN").unwrap_or_default();
+        if canary_node {
+            //在集群节点列表中标识金丝雀节点
+            node_labels.insert("canary".to_owned(), "true".to_owned());
+        }
+        let naming_zone_aware = std::env::var("RNACOS_NAMING_ZONE_AWARE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let naming_weighted_shuffle = std::env::var("RNACOS_NAMING_WEIGHTED_SHUFFLE")
+            .unwrap_or("false".to_owned())
+            .parse()
+            .unwrap_or(false);
+        let user_password_policy = PasswordPolicy {
+            algorithm: PasswordHashAlgorithm::from_name(
+                &std::env::var("RNACOS_USER_PASSWORD_HASH").unwrap_or("bcrypt".to_owned()),
+            ),
+            bcrypt_cost: std::env::var("RNACOS_USER_PASSWORD_BCRYPT_COST")
+                .unwrap_or("10".to_owned())
+                .parse()
+                .unwrap_or(10)
+                .clamp(4, 31),
+        };
+        let console_trusted_header_auth = Arc::new(TrustedHeaderAuth::new(
+            &std::env::var("RNACOS_CONSOLE_TRUSTED_PROXY_CIDRS").unwrap_or_default(),
+            std::env::var("RNACOS_CONSOLE_TRUSTED_USER_HEADER")
+                .unwrap_or(DEFAULT_TRUSTED_USER_HEADER.to_owned()),
+            std::env::var("RNACOS_CONSOLE_TRUSTED_GROUPS_HEADER")
+                .unwrap_or(DEFAULT_TRUSTED_GROUPS_HEADER.to_owned()),
+            &std::env::var("RNACOS_CONSOLE_TRUSTED_GROUP_ROLES").unwrap_or_default(),
+            &std::env::var("RNACOS_CONSOLE_TRUSTED_DEFAULT_ROLE").unwrap_or_default(),
+        ));
         if metrics_collect_interval_second < 1 {
             metrics_collect_interval_second = 1;
         }
@@ -186,6 +461,17 @@ impl AppSysConfig {
         if metrics_log_interval_second < metrics_collect_interval_second {
             metrics_collect_interval_second = metrics_log_interval_second;
         }
+        let metrics_exporter = std::env::var("RNACOS_METRICS_EXPORTER").unwrap_or_default();
+        let metrics_exporter_addr =
+            std::env::var("RNACOS_METRICS_EXPORTER_ADDR").unwrap_or_default();
+        let mut metrics_exporter_interval_second =
+            std::env::var("RNACOS_METRICS_EXPORTER_INTERVAL_SECOND")
+                .unwrap_or("60".to_owned())
+                .parse()
+                .unwrap_or(60);
+        if metrics_exporter_interval_second < metrics_collect_interval_second {
+            metrics_exporter_interval_second = metrics_collect_interval_second;
+        }
         Self {
             config_db_dir,
             config_db_file,
@@ -197,9 +483,21 @@ impl AppSysConfig {
             http_workers,
             raft_node_id,
             raft_node_addr,
+            http_bind_host,
+            grpc_bind_host,
+            console_bind_host,
+            cluster_grpc_addr,
+            http_unix_socket,
+            grpc_unix_socket,
+            unix_socket_mode,
             raft_auto_init,
             raft_join_addr,
             raft_snapshot_log_size,
+            raft_compress_threshold,
+            raft_diff_snapshot_enable,
+            raft_max_payload_entries,
+            session_store,
+            session_redis_url,
             console_login_timeout,
             console_login_one_hour_limit,
             openapi_login_timeout,
@@ -213,20 +511,96 @@ impl AppSysConfig {
             metrics_log_enable,
             metrics_collect_interval_second,
             metrics_log_interval_second,
+            metrics_exporter,
+            metrics_exporter_addr,
+            metrics_exporter_interval_second,
             console_captcha_enable,
+            naming_push_debounce_millis,
+            naming_push_max_delay_millis,
+            naming_push_ack_timeout_second,
+            naming_repush_batch_size,
+            naming_flapping_window_second,
+            naming_flapping_threshold,
+            naming_flapping_damping,
+            naming_change_history_size,
+            naming_quarantine_ttl_second,
+            naming_client_beat_interval_millis,
+            naming_light_beat_enabled,
+            dead_letter_max_size,
+            config_subscribe_replay_ttl_second,
+            system_namespaces,
+            grpc_request_tps_limit,
+            console_static_dir,
+            stale_read_max_millis,
+            naming_srv_weight_strategy,
+            naming_srv_priority_strategy,
+            naming_srv_ttl_second,
+            config_protect_cipher_delete,
+            config_encryption_keys,
+            public_status_tps,
+            shutdown_api_local_only,
+            concurrency_limit_config_publish,
+            concurrency_limit_instance_register,
+            concurrency_limit_console_query,
+            concurrency_queue_size,
+            concurrency_queue_timeout_millis,
+            node_labels,
+            canary_node,
+            canary_token,
+            naming_zone_aware,
+            naming_weighted_shuffle,
+            user_password_policy,
+            console_trusted_header_auth,
+        }
+    }
+
+    ///
+    /// 解析请求类型限流配置,格式: ConfigPublishRequest:100,InstanceRequest:1000
+    fn parse_tps_limit(value: &str) -> HashMap<String, u32> {
+        let mut map = HashMap::new();
+        for item in value.split(',') {
+            if let Some((key, limit)) = item.split_once(':') {
+                let key = key.trim();
+                if let Ok(limit) = limit.trim().parse::<u32>() {
+                    if !key.is_empty() {
+                        map.insert(key.to_owned(), limit);
+                    }
+                }
+            }
         }
+        map
     }
 
     pub fn get_grpc_addr(&self) -> String {
-        format!("0.0.0.0:{}", &self.grpc_port)
+        format!("{}:{}", &self.grpc_bind_host, &self.grpc_port)
     }
 
     pub fn get_http_addr(&self) -> String {
-        format!("0.0.0.0:{}", &self.http_port)
+        format!("{}:{}", &self.http_bind_host, &self.http_port)
     }
 
     pub fn get_http_console_addr(&self) -> String {
-        format!("0.0.0.0:{}", &self.http_console_port)
+        format!("{}:{}", &self.console_bind_host, &self.http_console_port)
+    }
+
+    pub fn get_timezone_offset(&self) -> FixedOffset {
+        if let Some(offset_value) = self.gmt_fixed_offset_hours.map(|e| e * 3600) {
+            FixedOffset::east_opt(offset_value).unwrap_or(Local::now().offset().fix())
+        } else {
+            Local::now().offset().fix()
+        }
+    }
+
+    ///
+    /// 金丝雀节点只处理携带路由标记的客户端请求;未设置token时标记值不为空即可
+    pub fn pass_canary(&self, header_value: Option<&str>) -> bool {
+        if !self.canary_node {
+            return true;
+        }
+        match header_value {
+            Some(v) if !v.is_empty() => self.canary_token.is_empty() || v == self.canary_token,
+            _ => false,
+        }
     }
 }
 
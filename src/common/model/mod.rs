@@ -2,6 +2,8 @@ use std::{collections::HashMap, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 
+use crate::common::trace_utils::TraceUtils;
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ApiResultOld<T>
 where
@@ -51,6 +53,9 @@ where
     pub success: bool,
     pub code: Option<String>,
     pub message: Option<String>,
+    /// 出错时返回请求链路id,便于排查
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<Arc<String>>,
 }
 
 impl<T> crate::common::model::ApiResult<T>
@@ -63,6 +68,7 @@ where
             success: true,
             code: None,
             message: None,
+            request_id: None,
         }
     }
 
@@ -72,6 +78,7 @@ where
             success: false,
             code: Some(code),
             message,
+            request_id: TraceUtils::current_request_id(),
         }
     }
 }
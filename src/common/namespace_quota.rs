@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::prelude::*;
+use bean_factory::{bean, Inject};
+use serde::{Deserialize, Serialize};
+
+use super::cluster_settings::ClusterSettingsManager;
+use super::dead_letter::DeadLetterManager;
+use super::webhook::WebhookUtils;
+use super::AppSysConfig;
+use crate::config::core::{ConfigActor, ConfigCmd, ConfigResult};
+use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
+use crate::naming::DEFAULT_NAMESPACE;
+use crate::now_millis;
+use crate::raft::NacosRaft;
+
+/// 命名空间用量告警推送的webhook名称
+pub const NAMESPACE_QUOTA_WEBHOOK: &str = "namespace_quota";
+
+const QUOTA_CHECK_INTERVAL_MILLIS: u64 = 30000;
+
+/// 注册实例超出命名空间配额时返回的错误码
+pub const QUOTA_EXCEEDED_ERROR_CODE: u16 = 403;
+
+///
+/// 命名空间配额,未设置的项不限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceQuota {
+    pub max_config_count: Option<u64>,
+    pub max_config_bytes: Option<u64>,
+    pub max_instance_count: Option<u64>,
+    /// 命名空间下的最大服务数
+    pub max_service_count: Option<u64>,
+    /// 单个服务下的最大实例数
+    pub max_instance_per_service: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceUsage {
+    pub config_count: u64,
+    pub config_bytes: u64,
+    pub instance_count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceQuotaWarning {
+    /// configCount,configBytes,instanceCount
+    pub item: String,
+    pub used: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceQuotaInfo {
+    pub namespace_id: Arc<String>,
+    pub quota: NamespaceQuota,
+    pub usage: NamespaceUsage,
+    pub warnings: Vec<NamespaceQuotaWarning>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NamespaceQuotaEvent<'a> {
+    event: &'a str,
+    namespace_id: &'a str,
+    warning: &'a NamespaceQuotaWarning,
+    warn_percent: u32,
+    time: u64,
+}
+
+///
+/// 注册超出配额,由NamingActor返回,接口层据此返回QUOTA_EXCEEDED_ERROR_CODE
+#[derive(Debug, Clone)]
+pub struct QuotaExceededError {
+    pub namespace_id: Arc<String>,
+    /// serviceCount,instancePerService
+    pub item: String,
+    pub limit: u64,
+}
+
+impl QuotaExceededError {
+    pub fn new(namespace_id: Arc<String>, item: &str, limit: u64) -> Self {
+        Self {
+            namespace_id,
+            item: item.to_owned(),
+            limit,
+        }
+    }
+
+    pub fn error_code(err: &anyhow::Error) -> u16 {
+        if err.downcast_ref::<Self>().is_some() {
+            QUOTA_EXCEEDED_ERROR_CODE
+        } else {
+            500u16
+        }
+    }
+}
+
+impl std::fmt::Display for QuotaExceededError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "namespace {} {} exceeds quota limit {}",
+            self.namespace_id, self.item, self.limit
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceededError {}
+
+impl NamespaceQuota {
+    ///
+    /// 用量达到配额的warn_percent百分比时返回告警项
+    pub fn check(&self, usage: &NamespaceUsage, warn_percent: u32) -> Vec<NamespaceQuotaWarning> {
+        let items = [
+            ("configCount", usage.config_count, self.max_config_count),
+            ("configBytes", usage.config_bytes, self.max_config_bytes),
+            (
+                "instanceCount",
+                usage.instance_count,
+                self.max_instance_count,
+            ),
+        ];
+        let mut list = vec![];
+        for (item, used, limit) in items {
+            if let Some(limit) = limit {
+                if used * 100 >= limit * warn_percent as u64 {
+                    list.push(NamespaceQuotaWarning {
+                        item: item.to_owned(),
+                        used,
+                        limit,
+                    });
+                }
+            }
+        }
+        list
+    }
+}
+
+///
+/// 定时统计命名空间用量,用量接近配额时推送webhook告警
+/// 告警只由raft主节点推送,避免重复通知
+#[bean(inject)]
+#[derive(Default)]
+pub struct NamespaceQuotaManager {
+    config_addr: Option<Addr<ConfigActor>>,
+    naming_addr: Option<Addr<NamingActor>>,
+    cluster_settings: Option<Arc<ClusterSettingsManager>>,
+    raft: Option<Arc<NacosRaft>>,
+    sys_config: Option<Arc<AppSysConfig>>,
+    dead_letter_manager: Option<Addr<DeadLetterManager>>,
+    usage_map: HashMap<Arc<String>, NamespaceUsage>,
+    warning_map: HashMap<Arc<String>, Vec<NamespaceQuotaWarning>>,
+}
+
+impl NamespaceQuotaManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn is_leader(&self) -> bool {
+        match (&self.raft, &self.sys_config) {
+            (Some(raft), Some(sys_config)) => {
+                raft.metrics().borrow().current_leader == Some(sys_config.raft_node_id)
+            }
+            _ => false,
+        }
+    }
+
+    async fn query_usage(
+        config_addr: Option<Addr<ConfigActor>>,
+        naming_addr: Option<Addr<NamingActor>>,
+    ) -> anyhow::Result<HashMap<Arc<String>, NamespaceUsage>> {
+        let mut usage_map: HashMap<Arc<String>, NamespaceUsage> = HashMap::new();
+        if let Some(config_addr) = config_addr {
+            if let ConfigResult::NamespaceUsage(map) =
+                config_addr.send(ConfigCmd::QueryNamespaceUsage).await??
+            {
+                for (namespace_id, (count, bytes)) in map {
+                    let usage = usage_map.entry(namespace_id).or_default();
+                    usage.config_count = count;
+                    usage.config_bytes = bytes;
+                }
+            }
+        }
+        if let Some(naming_addr) = naming_addr {
+            if let NamingResult::NamespaceInstanceCount(map) = naming_addr
+                .send(NamingCmd::QueryNamespaceInstanceCount)
+                .await??
+            {
+                for (namespace_id, count) in map {
+                    //配置中心默认命名空间id为空串
+                    let namespace_id = if namespace_id.as_str() == DEFAULT_NAMESPACE {
+                        Arc::new(String::new())
+                    } else {
+                        namespace_id
+                    };
+                    usage_map.entry(namespace_id).or_default().instance_count += count;
+                }
+            }
+        }
+        Ok(usage_map)
+    }
+
+    fn update_usage(&mut self, usage_map: HashMap<Arc<String>, NamespaceUsage>) {
+        let cluster_settings = match &self.cluster_settings {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        let settings = cluster_settings.get_settings();
+        let warn_percent = cluster_settings.quota_warn_percent();
+        let is_leader = self.is_leader();
+        let mut warning_map = HashMap::new();
+        for (namespace_id, quota) in &settings.namespace_quotas {
+            let namespace_id = Arc::new(namespace_id.to_owned());
+            let usage = usage_map.get(&namespace_id).cloned().unwrap_or_default();
+            let warnings = quota.check(&usage, warn_percent);
+            if warnings.is_empty() {
+                continue;
+            }
+            let old_items: Vec<&str> = self
+                .warning_map
+                .get(&namespace_id)
+                .map(|v| v.iter().map(|e| e.item.as_str()).collect())
+                .unwrap_or_default();
+            for warning in &warnings {
+                if old_items.contains(&warning.item.as_str()) {
+                    continue;
+                }
+                log::warn!(
+                    "namespace {} {} usage {} reach {}% of quota {}",
+                    &namespace_id,
+                    &warning.item,
+                    warning.used,
+                    warn_percent,
+                    warning.limit
+                );
+                if is_leader {
+                    let event = NamespaceQuotaEvent {
+                        event: "namespace_quota_warning",
+                        namespace_id: &namespace_id,
+                        warning,
+                        warn_percent,
+                        time: now_millis(),
+                    };
+                    WebhookUtils::notify(
+                        &cluster_settings,
+                        self.dead_letter_manager.as_ref(),
+                        NAMESPACE_QUOTA_WEBHOOK,
+                        &event,
+                    );
+                }
+            }
+            warning_map.insert(namespace_id, warnings);
+        }
+        self.warning_map = warning_map;
+        self.usage_map = usage_map;
+    }
+
+    fn check_usage(&mut self, ctx: &mut Context<Self>) {
+        let config_addr = self.config_addr.clone();
+        let naming_addr = self.naming_addr.clone();
+        async move { Self::query_usage(config_addr, naming_addr).await }
+            .into_actor(self)
+            .map(|res, act, _| match res {
+                Ok(usage_map) => act.update_usage(usage_map),
+                Err(err) => log::warn!("query namespace usage error,{}", err),
+            })
+            .wait(ctx);
+    }
+
+    fn build_info_list(&self) -> Vec<NamespaceQuotaInfo> {
+        let settings = self
+            .cluster_settings
+            .as_ref()
+            .map(|v| v.get_settings())
+            .unwrap_or_default();
+        let mut list: Vec<NamespaceQuotaInfo> = self
+            .usage_map
+            .iter()
+            .map(|(namespace_id, usage)| NamespaceQuotaInfo {
+                namespace_id: namespace_id.clone(),
+                quota: settings
+                    .namespace_quotas
+                    .get(namespace_id.as_str())
+                    .cloned()
+                    .unwrap_or_default(),
+                usage: usage.clone(),
+                warnings: self
+                    .warning_map
+                    .get(namespace_id)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.namespace_id.cmp(&b.namespace_id));
+        list
+    }
+}
+
+impl Actor for NamespaceQuotaManager {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("NamespaceQuotaManager started");
+    }
+}
+
+impl Inject for NamespaceQuotaManager {
+    type Context = Context<Self>;
+
+    fn inject(
+        &mut self,
+        factory_data: bean_factory::FactoryData,
+        _factory: bean_factory::BeanFactory,
+        ctx: &mut Self::Context,
+    ) {
+        self.config_addr = factory_data.get_actor();
+        self.naming_addr = factory_data.get_actor();
+        self.cluster_settings = factory_data.get_bean();
+        self.raft = factory_data.get_bean();
+        self.sys_config = factory_data.get_bean();
+        self.dead_letter_manager = factory_data.get_actor();
+        ctx.run_interval(
+            Duration::from_millis(QUOTA_CHECK_INTERVAL_MILLIS),
+            |act, ctx| {
+                act.check_usage(ctx);
+            },
+        );
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "anyhow::Result<NamespaceQuotaResult>")]
+pub enum NamespaceQuotaCmd {
+    QueryInfoList,
+}
+
+pub enum NamespaceQuotaResult {
+    InfoList(Vec<NamespaceQuotaInfo>),
+}
+
+impl Handler<NamespaceQuotaCmd> for NamespaceQuotaManager {
+    type Result = anyhow::Result<NamespaceQuotaResult>;
+
+    fn handle(&mut self, msg: NamespaceQuotaCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        match msg {
+            NamespaceQuotaCmd::QueryInfoList => {
+                Ok(NamespaceQuotaResult::InfoList(self.build_info_list()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_check() {
+        let quota = NamespaceQuota {
+            max_config_count: Some(100),
+            max_config_bytes: None,
+            max_instance_count: Some(10),
+            max_service_count: None,
+            max_instance_per_service: None,
+        };
+        let usage = NamespaceUsage {
+            config_count: 80,
+            config_bytes: 1 << 30,
+            instance_count: 7,
+        };
+        let warnings = quota.check(&usage, 80);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].item, "configCount");
+        assert_eq!(quota.check(&usage, 70).len(), 2);
+    }
+}
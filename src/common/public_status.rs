@@ -0,0 +1,141 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::common::limiter_utils::SecondLimiter;
+use crate::naming::cluster::node_manage::{NodeManageRequest, NodeManageResponse, NodeStatus};
+use crate::now_millis;
+
+pub const STATUS_OK: &str = "ok";
+pub const STATUS_DEGRADED: &str = "degraded";
+
+/// 状态结果缓存时间,避免高频访问时反复查询集群信息
+const STATUS_CACHE_MILLIS: u64 = 1000;
+
+///
+/// 免鉴权状态接口返回的粗粒度健康信息,不包含节点地址等内部信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicStatus {
+    /// ok 或 degraded
+    pub status: String,
+    pub node_count: usize,
+    pub healthy_node_count: usize,
+    pub uptime_seconds: u64,
+    pub version: String,
+}
+
+impl PublicStatus {
+    ///
+    /// raft没有leader或存在不健康节点时为degraded
+    pub fn new(
+        has_leader: bool,
+        node_count: usize,
+        healthy_node_count: usize,
+        uptime_seconds: u64,
+    ) -> Self {
+        let status = if has_leader && healthy_node_count >= node_count {
+            STATUS_OK
+        } else {
+            STATUS_DEGRADED
+        };
+        Self {
+            status: status.to_owned(),
+            node_count,
+            healthy_node_count,
+            uptime_seconds,
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == STATUS_OK
+    }
+}
+
+///
+/// 免鉴权状态接口的限流与结果缓存,供负载均衡与状态页使用
+pub struct PublicStatusManager {
+    start_time: u64,
+    limiter: Option<SecondLimiter>,
+    cache: Mutex<Option<(u64, PublicStatus)>>,
+}
+
+impl PublicStatusManager {
+    pub fn new(tps: u32) -> Self {
+        Self {
+            start_time: now_millis(),
+            limiter: if tps > 0 {
+                Some(SecondLimiter::new(tps))
+            } else {
+                None
+            },
+            cache: Mutex::new(None),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limiter.is_some()
+    }
+
+    pub fn acquire(&self) -> bool {
+        self.limiter.as_ref().map(|v| v.acquire()).unwrap_or(false)
+    }
+
+    pub async fn get_status(&self, app: &AppShareData) -> PublicStatus {
+        let now = now_millis();
+        if let Ok(cache) = self.cache.lock() {
+            if let Some((time, status)) = cache.as_ref() {
+                if now < time + STATUS_CACHE_MILLIS {
+                    return status.clone();
+                }
+            }
+        }
+        let status = self.build_status(app, now).await;
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some((now, status.clone()));
+        }
+        status
+    }
+
+    async fn build_status(&self, app: &AppShareData, now: u64) -> PublicStatus {
+        let has_leader = app.raft.metrics().borrow().current_leader.is_some();
+        let (node_count, healthy_node_count) = match app
+            .naming_inner_node_manage
+            .send(NodeManageRequest::GetAllNodes)
+            .await
+        {
+            Ok(Ok(NodeManageResponse::AllNodes(nodes))) => (
+                nodes.len(),
+                nodes
+                    .iter()
+                    .filter(|e| e.is_local || e.status == NodeStatus::Valid)
+                    .count(),
+            ),
+            _ => (0, 0),
+        };
+        PublicStatus::new(
+            has_leader,
+            node_count,
+            healthy_node_count,
+            now.saturating_sub(self.start_time) / 1000,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_status() {
+        assert!(PublicStatus::new(true, 3, 3, 10).is_ok());
+        assert!(!PublicStatus::new(true, 3, 2, 10).is_ok());
+        assert!(!PublicStatus::new(false, 1, 1, 10).is_ok());
+        let manager = PublicStatusManager::new(2);
+        assert!(manager.acquire());
+        assert!(manager.acquire());
+        assert!(!PublicStatusManager::new(0).is_enabled());
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::core::ConfigKey;
+use crate::naming::model::ServiceKey;
+use crate::now_millis_i64;
+
+/// 每个资源最多保留的事件数
+const MAX_EVENTS_PER_RESOURCE: usize = 100;
+/// 最多记录的资源数,超过后淘汰最早记录的资源
+const MAX_RESOURCE_SIZE: usize = 10000;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResourceType {
+    Config,
+    Service,
+}
+
+///
+/// 配置: (tenant,group,dataId);服务: (namespace,group,serviceName)
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceKey {
+    pub resource_type: ResourceType,
+    pub namespace_id: Arc<String>,
+    pub group: Arc<String>,
+    pub name: Arc<String>,
+}
+
+impl ResourceKey {
+    pub fn config(key: &ConfigKey) -> Self {
+        Self {
+            resource_type: ResourceType::Config,
+            namespace_id: key.tenant.clone(),
+            group: key.group.clone(),
+            name: key.data_id.clone(),
+        }
+    }
+
+    pub fn service(key: &ServiceKey) -> Self {
+        Self {
+            resource_type: ResourceType::Service,
+            namespace_id: key.namespace_id.clone(),
+            group: key.group_name.clone(),
+            name: key.service_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResourceEventType {
+    /// 变更历史
+    History,
+    /// 控制台操作记录
+    Audit,
+    /// 变更推送
+    Push,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceEvent {
+    pub time: i64,
+    pub event_type: ResourceEventType,
+    pub action: String,
+    pub operator: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Default)]
+struct ResourceEventData {
+    events: HashMap<ResourceKey, VecDeque<ResourceEvent>>,
+    /// 按首次记录顺序排列的资源,满了之后从队首淘汰
+    keys: VecDeque<ResourceKey>,
+}
+
+///
+/// 按资源记录当前节点的操作与推送事件,只保存在内存中
+#[derive(Default)]
+pub struct ResourceEventStore {
+    data: Mutex<ResourceEventData>,
+}
+
+impl ResourceEventStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, key: ResourceKey, event: ResourceEvent) {
+        let mut data = match self.data.lock() {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if !data.events.contains_key(&key) {
+            if data.keys.len() >= MAX_RESOURCE_SIZE {
+                if let Some(oldest) = data.keys.pop_front() {
+                    data.events.remove(&oldest);
+                }
+            }
+            data.keys.push_back(key.clone());
+        }
+        let list = data.events.entry(key).or_default();
+        if list.len() >= MAX_EVENTS_PER_RESOURCE {
+            list.pop_front();
+        }
+        list.push_back(event);
+    }
+
+    pub fn record_audit(
+        &self,
+        key: ResourceKey,
+        action: &str,
+        operator: Option<String>,
+        detail: Option<String>,
+    ) {
+        self.record(
+            key,
+            ResourceEvent {
+                time: now_millis_i64(),
+                event_type: ResourceEventType::Audit,
+                action: action.to_owned(),
+                operator,
+                detail,
+            },
+        );
+    }
+
+    pub fn record_push(&self, key: ResourceKey, client_count: usize) {
+        self.record(
+            key,
+            ResourceEvent {
+                time: now_millis_i64(),
+                event_type: ResourceEventType::Push,
+                action: "push".to_owned(),
+                operator: None,
+                detail: Some(format!("clients:{}", client_count)),
+            },
+        );
+    }
+
+    pub fn query(&self, key: &ResourceKey) -> Vec<ResourceEvent> {
+        self.data
+            .lock()
+            .ok()
+            .and_then(|data| data.events.get(key).map(|v| v.iter().cloned().collect()))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_event() {
+        let store = ResourceEventStore::new();
+        let key = ResourceKey::service(&ServiceKey::new("public", "DEFAULT_GROUP", "foo"));
+        for _ in 0..MAX_EVENTS_PER_RESOURCE + 5 {
+            store.record_push(key.clone(), 1);
+        }
+        store.record_audit(key.clone(), "update", Some("admin".to_owned()), None);
+        let list = store.query(&key);
+        assert_eq!(list.len(), MAX_EVENTS_PER_RESOURCE);
+        assert_eq!(list.last().unwrap().event_type, ResourceEventType::Audit);
+    }
+
+    #[test]
+    fn test_evict_resource() {
+        let store = ResourceEventStore::new();
+        let build_key = |i: usize| {
+            ResourceKey::service(&ServiceKey::new("public", "DEFAULT_GROUP", &i.to_string()))
+        };
+        for i in 0..MAX_RESOURCE_SIZE + 1 {
+            store.record_push(build_key(i), 1);
+        }
+        assert!(store.query(&build_key(0)).is_empty());
+        assert_eq!(store.query(&build_key(1)).len(), 1);
+        assert_eq!(store.query(&build_key(MAX_RESOURCE_SIZE)).len(), 1);
+        assert_eq!(store.data.lock().unwrap().keys.len(), MAX_RESOURCE_SIZE);
+    }
+}
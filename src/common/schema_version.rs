@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 当前版本能识别的持久化记录最高版本
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+lazy_static::lazy_static! {
+    /// 写入记录使用的版本;滚动升级期间设置为旧节点支持的版本,所有节点升级后再提升
+    static ref WRITE_SCHEMA_VERSION: u32 = SchemaVersionUtils::parse_write_version(
+        &std::env::var("RNACOS_PERSIST_SCHEMA_VERSION").unwrap_or_default()
+    );
+}
+
+static NEWER_VERSION_WARNED: AtomicBool = AtomicBool::new(false);
+
+///
+/// 带版本号的持久化记录;版本号为0的记录是引入版本号之前写入的数据
+pub trait VersionedRecord {
+    fn schema_version(&self) -> u32;
+
+    fn set_schema_version(&mut self, version: u32);
+
+    ///
+    /// 从指定版本升级到下一个版本,新增字段的默认值在这里补齐
+    fn upgrade_from(&mut self, _version: u32) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct SchemaVersionUtils;
+
+impl SchemaVersionUtils {
+    pub fn write_version() -> u32 {
+        *WRITE_SCHEMA_VERSION
+    }
+
+    fn parse_write_version(value: &str) -> u32 {
+        value
+            .trim()
+            .parse()
+            .unwrap_or(CURRENT_SCHEMA_VERSION)
+            .clamp(1, CURRENT_SCHEMA_VERSION)
+    }
+
+    ///
+    /// 读取记录后逐级升级到写入版本;
+    /// 高于当前版本的记录由已升级的节点写入,保留其版本号,已知字段照常读取
+    pub fn upgrade<T: VersionedRecord>(record: &mut T) -> anyhow::Result<()> {
+        Self::upgrade_to(record, Self::write_version())
+    }
+
+    fn upgrade_to<T: VersionedRecord>(record: &mut T, target: u32) -> anyhow::Result<()> {
+        let mut version = record.schema_version();
+        if version > CURRENT_SCHEMA_VERSION {
+            if !NEWER_VERSION_WARNED.swap(true, Ordering::Relaxed) {
+                log::warn!(
+                    "read persisted record with newer schema version:{},current version:{}",
+                    version,
+                    CURRENT_SCHEMA_VERSION
+                );
+            }
+            return Ok(());
+        }
+        if version >= target {
+            return Ok(());
+        }
+        while version < target {
+            record.upgrade_from(version)?;
+            version += 1;
+        }
+        record.set_schema_version(version);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::model::ConfigValueDO;
+    use crate::raft::cache::model::CacheItemDo;
+    use prost::Message;
+
+    /// 引入版本号之前的记录格式
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct LegacyCacheItemDo {
+        #[prost(uint32, tag = "1")]
+        pub cache_type: u32,
+        #[prost(bytes = "vec", tag = "2")]
+        pub data: Vec<u8>,
+        #[prost(int32, tag = "3")]
+        pub timeout: i32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct LegacyConfigValueDO {
+        #[prost(string, optional, tag = "1")]
+        pub content: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub config_type: Option<String>,
+        #[prost(string, optional, tag = "4")]
+        pub desc: Option<String>,
+    }
+
+    fn encode<T: Message>(value: &T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        value.encode(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_parse_write_version() {
+        assert_eq!(SchemaVersionUtils::parse_write_version(""), 1);
+        assert_eq!(SchemaVersionUtils::parse_write_version("0"), 1);
+        assert_eq!(
+            SchemaVersionUtils::parse_write_version("99"),
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_cache_item_compatibility() {
+        let legacy = LegacyCacheItemDo {
+            cache_type: 1,
+            data: b"abc".to_vec(),
+            timeout: 10,
+        };
+        //新版本读取旧数据,升级到当前版本
+        let item = CacheItemDo::from_bytes(&encode(&legacy)).unwrap();
+        assert_eq!(item.data, b"abc".to_vec());
+        assert_eq!(item.timeout, 10);
+        assert_eq!(item.schema_version, CURRENT_SCHEMA_VERSION);
+        //旧版本读取新数据,忽略版本号字段
+        let old = LegacyCacheItemDo::decode(item.to_bytes().as_slice()).unwrap();
+        assert_eq!(old, legacy);
+        //更新版本写入的数据保留版本号
+        let mut newer = item;
+        newer.schema_version = CURRENT_SCHEMA_VERSION + 1;
+        let item = CacheItemDo::from_bytes(&newer.to_bytes()).unwrap();
+        assert_eq!(item.schema_version, CURRENT_SCHEMA_VERSION + 1);
+    }
+
+    #[test]
+    fn test_config_value_compatibility() {
+        let legacy = LegacyConfigValueDO {
+            content: Some("a=1".to_owned()),
+            config_type: Some("properties".to_owned()),
+            desc: None,
+        };
+        let value = ConfigValueDO::from_bytes(&encode(&legacy)).unwrap();
+        assert_eq!(value.content.as_deref(), Some("a=1"));
+        assert_eq!(value.schema_version, Some(CURRENT_SCHEMA_VERSION));
+        let old = LegacyConfigValueDO::decode(value.to_bytes().unwrap().as_slice()).unwrap();
+        assert_eq!(old, legacy);
+    }
+}
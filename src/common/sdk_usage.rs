@@ -0,0 +1,191 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::now_millis;
+
+/// http心跳上报的客户端超过该时长未上报则移除
+const HTTP_CLIENT_TIMEOUT_MILLIS: u64 = 60000;
+
+/// 统计维度:(命名空间, sdk名称, sdk版本)
+type SdkUsageKey = (Arc<String>, Arc<String>, Arc<String>);
+
+#[derive(Debug, Clone)]
+pub struct ClientSdkInfo {
+    pub namespace_id: Arc<String>,
+    pub sdk_name: Arc<String>,
+    pub sdk_version: Arc<String>,
+    pub from_grpc: bool,
+    pub last_active_time: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SdkUsageItem {
+    pub namespace_id: Arc<String>,
+    pub sdk_name: Arc<String>,
+    pub sdk_version: Arc<String>,
+    pub grpc_client_count: u64,
+    pub http_client_count: u64,
+    pub last_active_time: u64,
+}
+
+///
+/// 统计客户端上报的sdk语言/版本信息
+/// 数据只统计当前节点的连接与心跳
+#[derive(Default)]
+pub struct SdkUsageManage {
+    clients: HashMap<Arc<String>, ClientSdkInfo>,
+}
+
+impl SdkUsageManage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// 解析客户端版本,如: Nacos-Java-Client:v2.1.0 , nacos-sdk-rust/0.3.3
+    pub fn parse_client_version(client_version: &str) -> (String, String) {
+        let client_version = client_version.trim();
+        if client_version.is_empty() {
+            return ("unknown".to_owned(), "unknown".to_owned());
+        }
+        if let Some((name, version)) = client_version.rsplit_once([':', '/']) {
+            if !name.is_empty() && !version.is_empty() {
+                return (name.to_owned(), version.to_owned());
+            }
+        }
+        ("unknown".to_owned(), client_version.to_owned())
+    }
+
+    fn report(
+        &mut self,
+        client_id: Arc<String>,
+        namespace_id: Arc<String>,
+        client_version: &str,
+        from_grpc: bool,
+    ) {
+        let now = now_millis();
+        let (sdk_name, sdk_version) = Self::parse_client_version(client_version);
+        self.clients.insert(
+            client_id,
+            ClientSdkInfo {
+                namespace_id,
+                sdk_name: Arc::new(sdk_name),
+                sdk_version: Arc::new(sdk_version),
+                from_grpc,
+                last_active_time: now,
+            },
+        );
+    }
+
+    fn clear_timeout_client(&mut self) {
+        let timeout = now_millis().saturating_sub(HTTP_CLIENT_TIMEOUT_MILLIS);
+        self.clients
+            .retain(|_, v| v.from_grpc || v.last_active_time >= timeout);
+    }
+
+    fn build_report(&self, namespace_id: Option<Arc<String>>) -> Vec<SdkUsageItem> {
+        let mut map: HashMap<SdkUsageKey, SdkUsageItem> = HashMap::new();
+        for info in self.clients.values() {
+            if let Some(namespace_id) = &namespace_id {
+                if namespace_id != &info.namespace_id {
+                    continue;
+                }
+            }
+            let key = (
+                info.namespace_id.clone(),
+                info.sdk_name.clone(),
+                info.sdk_version.clone(),
+            );
+            let item = map.entry(key).or_insert_with(|| SdkUsageItem {
+                namespace_id: info.namespace_id.clone(),
+                sdk_name: info.sdk_name.clone(),
+                sdk_version: info.sdk_version.clone(),
+                ..Default::default()
+            });
+            if info.from_grpc {
+                item.grpc_client_count += 1;
+            } else {
+                item.http_client_count += 1;
+            }
+            if info.last_active_time > item.last_active_time {
+                item.last_active_time = info.last_active_time;
+            }
+        }
+        let mut list: Vec<SdkUsageItem> = map.into_values().collect();
+        list.sort_by(|a, b| {
+            a.namespace_id
+                .cmp(&b.namespace_id)
+                .then(a.sdk_name.cmp(&b.sdk_name))
+                .then(a.sdk_version.cmp(&b.sdk_version))
+        });
+        list
+    }
+}
+
+impl Actor for SdkUsageManage {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        log::info!("SdkUsageManage started");
+        ctx.run_interval(Duration::from_millis(10000), |act, _| {
+            act.clear_timeout_client();
+        });
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "anyhow::Result<SdkUsageResult>")]
+pub enum SdkUsageCmd {
+    /// client_id,namespace_id,client_version,from_grpc
+    Report(Arc<String>, Arc<String>, String, bool),
+    RemoveClient(Arc<String>),
+    QueryReport(Option<Arc<String>>),
+}
+
+pub enum SdkUsageResult {
+    Report(Vec<SdkUsageItem>),
+    None,
+}
+
+impl Handler<SdkUsageCmd> for SdkUsageManage {
+    type Result = anyhow::Result<SdkUsageResult>;
+
+    fn handle(&mut self, msg: SdkUsageCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        match msg {
+            SdkUsageCmd::Report(client_id, namespace_id, client_version, from_grpc) => {
+                self.report(client_id, namespace_id, &client_version, from_grpc);
+            }
+            SdkUsageCmd::RemoveClient(client_id) => {
+                self.clients.remove(&client_id);
+            }
+            SdkUsageCmd::QueryReport(namespace_id) => {
+                return Ok(SdkUsageResult::Report(self.build_report(namespace_id)));
+            }
+        }
+        Ok(SdkUsageResult::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SdkUsageManage;
+
+    #[test]
+    fn parse_client_version() {
+        assert_eq!(
+            SdkUsageManage::parse_client_version("Nacos-Java-Client:v2.1.0"),
+            ("Nacos-Java-Client".to_owned(), "v2.1.0".to_owned())
+        );
+        assert_eq!(
+            SdkUsageManage::parse_client_version("nacos-sdk-rust/0.3.3"),
+            ("nacos-sdk-rust".to_owned(), "0.3.3".to_owned())
+        );
+        assert_eq!(
+            SdkUsageManage::parse_client_version(""),
+            ("unknown".to_owned(), "unknown".to_owned())
+        );
+    }
+}
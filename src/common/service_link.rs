@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::naming::model::ServiceKey;
+use crate::naming::DEFAULT_NAMESPACE;
+
+/// 跨命名空间服务链接在系统配置中的key
+pub const SYSCONFIG_SERVICE_LINK_KEY: &str = "namespace_service_links";
+
+///
+/// 把一个命名空间的服务以只读链接的方式暴露到其它命名空间,分组与服务名不变
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceLink {
+    /// 服务实际注册的命名空间
+    pub source_namespace_id: String,
+    pub group_name: String,
+    pub service_name: String,
+    /// 可以查询到该服务的命名空间
+    #[serde(default)]
+    pub target_namespace_ids: Vec<String>,
+}
+
+impl ServiceLink {
+    pub fn check_valid(&self) -> anyhow::Result<()> {
+        if self.group_name.is_empty() || self.service_name.is_empty() {
+            return Err(anyhow::anyhow!("groupName and serviceName can't be empty"));
+        }
+        let source = ServiceLinkManager::namespace_key(&self.source_namespace_id);
+        if self
+            .target_namespace_ids
+            .iter()
+            .any(|e| ServiceLinkManager::namespace_key(e) == source)
+        {
+            return Err(anyhow::anyhow!(
+                "the target namespace is the same as the source namespace {}",
+                source
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct ServiceLinkIndex {
+    /// 链接的服务 -> 源服务
+    links: HashMap<ServiceKey, ServiceKey>,
+    /// 源服务 -> 链接的服务
+    linked_keys: HashMap<ServiceKey, Vec<ServiceKey>>,
+}
+
+///
+/// 跨命名空间服务链接管理;
+/// 查询服务时,本命名空间下不存在该服务才按链接解析到源服务
+#[derive(Debug, Default)]
+pub struct ServiceLinkManager {
+    index: RwLock<ServiceLinkIndex>,
+}
+
+impl ServiceLinkManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn namespace_key(namespace_id: &str) -> &str {
+        if namespace_id.is_empty() {
+            DEFAULT_NAMESPACE
+        } else {
+            namespace_id
+        }
+    }
+
+    pub fn parse_links(value: &str) -> anyhow::Result<Vec<ServiceLink>> {
+        if value.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(serde_json::from_str(value)?)
+    }
+
+    pub fn update_links(&self, links: Vec<ServiceLink>) {
+        let mut index = ServiceLinkIndex::default();
+        for link in links {
+            if link.check_valid().is_err() {
+                continue;
+            }
+            let source = ServiceKey::new(
+                Self::namespace_key(&link.source_namespace_id),
+                &link.group_name,
+                &link.service_name,
+            );
+            for namespace_id in &link.target_namespace_ids {
+                let target = ServiceKey::new(
+                    Self::namespace_key(namespace_id),
+                    &link.group_name,
+                    &link.service_name,
+                );
+                index
+                    .linked_keys
+                    .entry(source.clone())
+                    .or_default()
+                    .push(target.clone());
+                index.links.insert(target, source.clone());
+            }
+        }
+        if let Ok(mut v) = self.index.write() {
+            *v = index;
+        }
+    }
+
+    pub fn update_from_str(&self, value: &str) {
+        match Self::parse_links(value) {
+            Ok(links) => self.update_links(links),
+            Err(err) => log::warn!("parse namespace service links error,{}", err),
+        }
+    }
+
+    ///
+    /// 解析链接指向的源服务,不是链接时返回None
+    pub fn resolve(&self, key: &ServiceKey) -> Option<ServiceKey> {
+        self.index.read().ok()?.links.get(key).cloned()
+    }
+
+    ///
+    /// 查询链接到源服务的服务列表,用于源服务变更时通知链接方的订阅者
+    pub fn linked_keys(&self, key: &ServiceKey) -> Vec<ServiceKey> {
+        self.index
+            .read()
+            .ok()
+            .and_then(|v| v.linked_keys.get(key).cloned())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_link() {
+        let manager = ServiceLinkManager::new();
+        manager.update_links(vec![ServiceLink {
+            source_namespace_id: "".to_owned(),
+            group_name: "infra".to_owned(),
+            service_name: "redis".to_owned(),
+            target_namespace_ids: vec!["tenant_a".to_owned(), "tenant_b".to_owned()],
+        }]);
+        let source = ServiceKey::new("public", "infra", "redis");
+        let target = ServiceKey::new("tenant_a", "infra", "redis");
+        assert_eq!(manager.resolve(&target), Some(source.clone()));
+        assert!(manager.resolve(&source).is_none());
+        assert_eq!(manager.linked_keys(&source).len(), 2);
+        let link = ServiceLink {
+            source_namespace_id: "public".to_owned(),
+            group_name: "g".to_owned(),
+            service_name: "s".to_owned(),
+            target_namespace_ids: vec!["".to_owned()],
+        };
+        assert!(link.check_valid().is_err());
+    }
+}
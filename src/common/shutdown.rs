@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use super::appdata::AppShareData;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownState {
+    #[default]
+    Running,
+    /// 已停止对外监听,正在释放资源
+    Stopping,
+    Stopped,
+}
+
+///
+/// 进程关闭控制;请求关闭后各服务停止监听,再由启动方调用cleanup刷盘并释放资源
+pub struct ShutdownManager {
+    sender: watch::Sender<ShutdownState>,
+    cleanup_started: AtomicBool,
+}
+
+impl Default for ShutdownManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownManager {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(ShutdownState::Running);
+        Self {
+            sender,
+            cleanup_started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn get_state(&self) -> ShutdownState {
+        *self.sender.borrow()
+    }
+
+    ///
+    /// 请求关闭;已经在关闭中时返回false
+    pub fn request_shutdown(&self) -> bool {
+        self.sender.send_if_modified(|state| {
+            if *state == ShutdownState::Running {
+                *state = ShutdownState::Stopping;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    ///
+    /// 抢占资源释放;只有第一个调用方返回true
+    fn start_cleanup(&self) -> bool {
+        self.cleanup_started
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    fn set_stopped(&self) {
+        self.sender.send_replace(ShutdownState::Stopped);
+    }
+
+    ///
+    /// 等待进入指定状态,可作为服务监听的关闭信号
+    pub async fn wait_for(&self, state: ShutdownState) {
+        let mut receiver = self.sender.subscribe();
+        while *receiver.borrow_and_update() < state {
+            if receiver.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+pub struct ShutdownUtils;
+
+impl ShutdownUtils {
+    ///
+    /// 服务监听停止后调用:停止raft,把raft日志写入磁盘,停止actor线程,移除unix socket文件
+    pub async fn cleanup(app: &AppShareData) -> anyhow::Result<()> {
+        let manager = &app.shutdown_manager;
+        manager.request_shutdown();
+        //关闭可能由接口或信号先行请求,资源释放只由第一个调用方执行
+        if !manager.start_cleanup() {
+            return Ok(());
+        }
+        log::info!("rnacos stopping");
+        if let Err(err) = app.raft.shutdown().await {
+            log::warn!("raft shutdown error,{}", err);
+        }
+        app.raft_store.flush().await?;
+        //raft日志已写入磁盘后再停止actor线程,避免丢失未处理的写入
+        super::actor_utils::stop_actor_threads();
+        #[cfg(unix)]
+        for path in app
+            .sys_config
+            .http_unix_socket
+            .iter()
+            .chain(app.sys_config.grpc_unix_socket.iter())
+        {
+            super::unix_socket::UnixSocketUtils::remove_stale(path).ok();
+        }
+        manager.set_stopped();
+        log::info!("rnacos stopped");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_state() {
+        let manager = std::sync::Arc::new(ShutdownManager::new());
+        let waiter = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.wait_for(ShutdownState::Stopping).await })
+        };
+        assert!(manager.request_shutdown());
+        assert!(!manager.request_shutdown());
+        waiter.await.unwrap();
+        manager.set_stopped();
+        manager.wait_for(ShutdownState::Stopping).await;
+        assert_eq!(manager.get_state(), ShutdownState::Stopped);
+    }
+
+    #[test]
+    fn test_start_cleanup_once() {
+        let manager = ShutdownManager::new();
+        assert!(manager.request_shutdown());
+        assert!(manager.start_cleanup());
+        assert!(!manager.start_cleanup());
+    }
+}
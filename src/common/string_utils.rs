@@ -13,6 +13,40 @@ impl StringUtils {
         a.rfind(b)
     }
 
+    ///
+    /// 模糊匹配:pattern包含通配符`*`、`?`时按通配符匹配整个字符串,否则按子串匹配
+    pub fn like_pattern(a: &str, pattern: &str) -> bool {
+        if pattern.contains(['*', '?']) {
+            Self::wildcard_match(a, pattern)
+        } else {
+            a.contains(pattern)
+        }
+    }
+
+    pub fn wildcard_match(a: &str, pattern: &str) -> bool {
+        let a: Vec<char> = a.chars().collect();
+        let p: Vec<char> = pattern.chars().collect();
+        let (mut i, mut j) = (0, 0);
+        //最近一个`*`的位置及其匹配到的a的位置
+        let mut star: Option<(usize, usize)> = None;
+        while i < a.len() {
+            if j < p.len() && (p[j] == '?' || p[j] == a[i]) {
+                i += 1;
+                j += 1;
+            } else if j < p.len() && p[j] == '*' {
+                star = Some((j, i));
+                j += 1;
+            } else if let Some((star_j, star_i)) = star {
+                j = star_j + 1;
+                i = star_i + 1;
+                star = Some((star_j, i));
+            } else {
+                return false;
+            }
+        }
+        p[j..].iter().all(|c| *c == '*')
+    }
+
     pub fn is_option_empty(v: &Option<String>) -> bool {
         if let Some(v) = &v {
             v.is_empty()
@@ -31,4 +65,14 @@ impl StringUtils {
         }
         v
     }
+
+    ///
+    /// csv字段转义,包含分隔符、引号或换行时加引号
+    pub fn csv_field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_owned()
+        }
+    }
 }
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::common::appdata::AppShareData;
+use crate::common::cluster_settings::ClusterSettings;
+use crate::common::constant::APP_VERSION;
+use crate::console::model::cluster_model::ClusterNodeInfo;
+use crate::grpc::bistream_manage::{BiStreamManageCmd, BiStreamManageResult};
+use crate::metrics::model::{MetricsRequest, MetricsResponse};
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::now_millis_i64;
+
+/// 最多保留的慢请求记录数
+const MAX_SLOW_REQUEST_SIZE: usize = 200;
+const MASK_VALUE: &str = "******";
+/// 环境变量名包含这些词时值按敏感信息处理
+const SECRET_KEY_WORDS: [&str; 6] = ["PASSWORD", "TOKEN", "SECRET", "KEY", "URL", "CIPHER"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlowRequestItem {
+    pub time: i64,
+    pub request: String,
+    /// 耗时,单位秒
+    pub duration: f64,
+}
+
+///
+/// 当前节点最近的慢请求,只保存在内存中
+#[derive(Default)]
+pub struct SlowRequestLog {
+    list: Mutex<VecDeque<SlowRequestItem>>,
+}
+
+impl SlowRequestLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, request: String, duration: f64) {
+        if let Ok(mut list) = self.list.lock() {
+            if list.len() >= MAX_SLOW_REQUEST_SIZE {
+                list.pop_front();
+            }
+            list.push_back(SlowRequestItem {
+                time: now_millis_i64(),
+                request,
+                duration,
+            });
+        }
+    }
+
+    pub fn list(&self) -> Vec<SlowRequestItem> {
+        self.list
+            .lock()
+            .map(|v| v.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionInfo {
+    version: String,
+    node_id: u64,
+    time: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigInfo {
+    env: BTreeMap<String, String>,
+    cluster_settings: ClusterSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClusterStateInfo {
+    nodes: Vec<ClusterNodeInfo>,
+    raft_metrics: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActorStatsInfo {
+    grpc_conn_count: usize,
+    namespace_instance_count: HashMap<Arc<String>, u64>,
+}
+
+///
+/// 收集当前节点的诊断信息(版本、配置、集群状态、慢请求、指标、actor状态),打包成zip用于问题反馈;敏感信息会被脱敏
+pub struct SupportBundleUtils;
+
+impl SupportBundleUtils {
+    pub fn mask_env(key: &str, value: &str) -> String {
+        let upper_key = key.to_uppercase();
+        if !value.is_empty() && SECRET_KEY_WORDS.iter().any(|w| upper_key.contains(w)) {
+            MASK_VALUE.to_owned()
+        } else {
+            value.to_owned()
+        }
+    }
+
+    pub fn collect_env() -> BTreeMap<String, String> {
+        std::env::vars()
+            .filter(|(key, _)| key.starts_with("RNACOS_") || key == "RUST_LOG")
+            .map(|(key, value)| {
+                let value = Self::mask_env(&key, &value);
+                (key, value)
+            })
+            .collect()
+    }
+
+    ///
+    /// webhook地址中可能带有访问凭证,统一脱敏
+    pub fn mask_settings(settings: &ClusterSettings) -> ClusterSettings {
+        let mut settings = settings.clone();
+        for value in settings.webhooks.values_mut() {
+            *value = MASK_VALUE.to_owned();
+        }
+        settings
+    }
+
+    pub async fn build(app: &AppShareData) -> anyhow::Result<Vec<u8>> {
+        let version = VersionInfo {
+            version: APP_VERSION.to_owned(),
+            node_id: app.sys_config.raft_node_id,
+            time: now_millis_i64(),
+        };
+        let config = ConfigInfo {
+            env: Self::collect_env(),
+            cluster_settings: Self::mask_settings(&app.cluster_settings.get_settings()),
+        };
+        let cluster = Self::collect_cluster_state(app).await;
+        let slow_requests = app.slow_request_log.list();
+        let metrics = match app.metrics_manager.send(MetricsRequest::Export).await {
+            Ok(Ok(MetricsResponse::ExportInfo(v))) => v,
+            Ok(Err(e)) => format!("export metrics error: {}", e),
+            Ok(_) => "export metrics result error".to_owned(),
+            Err(e) => format!("export metrics error: {}", e),
+        };
+        let actor_stats = Self::collect_actor_stats(app).await;
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        Self::write_file(
+            &mut zip,
+            "version.json",
+            &serde_json::to_vec_pretty(&version)?,
+        )?;
+        Self::write_file(
+            &mut zip,
+            "config.json",
+            &serde_json::to_vec_pretty(&config)?,
+        )?;
+        Self::write_file(
+            &mut zip,
+            "cluster.json",
+            &serde_json::to_vec_pretty(&cluster)?,
+        )?;
+        Self::write_file(
+            &mut zip,
+            "slow_requests.json",
+            &serde_json::to_vec_pretty(&slow_requests)?,
+        )?;
+        Self::write_file(&mut zip, "metrics.txt", metrics.as_bytes())?;
+        Self::write_file(
+            &mut zip,
+            "actor_stats.json",
+            &serde_json::to_vec_pretty(&actor_stats)?,
+        )?;
+        Ok(zip.finish()?.into_inner())
+    }
+
+    async fn collect_cluster_state(app: &AppShareData) -> ClusterStateInfo {
+        let leader_node = app.raft.current_leader().await;
+        let nodes = app
+            .naming_node_manage
+            .get_all_valid_nodes()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|node| {
+                let mut node_info: ClusterNodeInfo = node.into();
+                node_info.raft_leader = leader_node == Some(node_info.node_id);
+                node_info.current_node = app.sys_config.raft_node_id == node_info.node_id;
+                node_info
+            })
+            .collect();
+        let raft_metrics = format!("{:?}", app.raft.metrics().borrow());
+        ClusterStateInfo {
+            nodes,
+            raft_metrics,
+        }
+    }
+
+    async fn collect_actor_stats(app: &AppShareData) -> ActorStatsInfo {
+        let mut stats = ActorStatsInfo::default();
+        if let Ok(Ok(BiStreamManageResult::ConnList(list))) = app
+            .bi_stream_manage
+            .send(BiStreamManageCmd::QueryConnList)
+            .await
+        {
+            stats.grpc_conn_count = list.len();
+        }
+        if let Ok(Ok(NamingResult::NamespaceInstanceCount(map))) = app
+            .naming_addr
+            .send(NamingCmd::QueryNamespaceInstanceCount)
+            .await
+        {
+            stats.namespace_instance_count = map;
+        }
+        stats
+    }
+
+    fn write_file(
+        zip: &mut ZipWriter<Cursor<Vec<u8>>>,
+        name: &str,
+        content: &[u8],
+    ) -> anyhow::Result<()> {
+        let options = FileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(0o644);
+        zip.start_file(name, options)?;
+        zip.write_all(content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(
+            SupportBundleUtils::mask_env("RNACOS_CLUSTER_TOKEN", "abc"),
+            MASK_VALUE
+        );
+        assert_eq!(
+            SupportBundleUtils::mask_env("RNACOS_SESSION_REDIS_URL", "redis://:pwd@127.0.0.1"),
+            MASK_VALUE
+        );
+        assert_eq!(
+            SupportBundleUtils::mask_env("RNACOS_HTTP_PORT", "8848"),
+            "8848"
+        );
+        let mut settings = ClusterSettings::default();
+        settings
+            .webhooks
+            .insert("ops".to_owned(), "http://x/hook?token=1".to_owned());
+        let masked = SupportBundleUtils::mask_settings(&settings);
+        assert_eq!(masked.webhooks.get("ops").unwrap(), MASK_VALUE);
+    }
+}
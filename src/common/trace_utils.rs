@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::sync::Arc;
+
+/// 请求链路id的http header;grpc请求使用payload metadata中的同名header
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: Arc<String>;
+}
+
+///
+/// 请求链路id,在处理请求的异步任务内传递,转发到集群其它节点时随请求带上
+pub struct TraceUtils;
+
+impl TraceUtils {
+    pub fn new_request_id() -> Arc<String> {
+        Arc::new(uuid::Uuid::new_v4().to_string().replace('-', ""))
+    }
+
+    ///
+    /// 优先使用客户端传入的id,为空或不合法时重新生成
+    pub fn get_or_new_request_id(value: Option<&str>) -> Arc<String> {
+        match value {
+            Some(v)
+                if !v.is_empty()
+                    && v.len() <= REQUEST_ID_MAX_LEN
+                    && v.bytes().all(|c| c.is_ascii_graphic()) =>
+            {
+                Arc::new(v.to_owned())
+            }
+            _ => Self::new_request_id(),
+        }
+    }
+
+    pub fn current_request_id() -> Option<Arc<String>> {
+        REQUEST_ID.try_with(|v| v.clone()).ok()
+    }
+
+    pub async fn scope<F: Future>(request_id: Arc<String>, f: F) -> F::Output {
+        REQUEST_ID.scope(request_id, f).await
+    }
+}
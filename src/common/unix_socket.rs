@@ -0,0 +1,154 @@
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UnixListener;
+use tonic::transport::server::Connected;
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+pub struct UnixSocketUtils;
+
+impl UnixSocketUtils {
+    ///
+    /// 移除上次进程退出时残留的socket文件;路径被普通文件占用时报错
+    pub fn remove_stale(path: &str) -> anyhow::Result<()> {
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_socket() => Ok(std::fs::remove_file(path)?),
+            Ok(_) => Err(anyhow::anyhow!("unix socket path {} is not a socket", path)),
+            Err(_) => Ok(()),
+        }
+    }
+
+    pub fn set_mode(path: &str, mode: u32) -> anyhow::Result<()> {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        Ok(())
+    }
+
+    pub fn bind(path: &str, mode: u32) -> anyhow::Result<UnixListener> {
+        Self::remove_stale(path)?;
+        let listener = UnixListener::bind(path)?;
+        Self::set_mode(path, mode)?;
+        Ok(listener)
+    }
+
+    ///
+    /// grpc服务使用的连接流
+    pub fn incoming(
+        listener: UnixListener,
+    ) -> impl Stream<Item = std::io::Result<UnixConnection>> + Send + 'static {
+        Box::pin(futures_util::stream::unfold(
+            listener,
+            |listener| async move {
+                let res = listener
+                    .accept()
+                    .await
+                    .map(|(stream, _)| UnixConnection::new(stream));
+                Some((res, listener))
+            },
+        ))
+    }
+}
+
+/// 连接标识使用的地址段(RFC 6666丢弃前缀),不会与真实的对端地址冲突
+const CONNECTION_ADDR_PREFIX: [u16; 4] = [0x100, 0, 0, 0];
+
+///
+/// unix domain socket连接没有对端ip地址;
+/// grpc只能通过对端地址区分连接,这里把连接序号编码在100::/64地址段中,只作为连接id使用,
+/// 对应的客户端ip按本机处理
+pub struct UnixConnection {
+    stream: tokio::net::UnixStream,
+    remote_addr: SocketAddr,
+}
+
+impl UnixConnection {
+    pub fn new(stream: tokio::net::UnixStream) -> Self {
+        Self {
+            stream,
+            remote_addr: Self::next_remote_addr(),
+        }
+    }
+
+    fn next_remote_addr() -> SocketAddr {
+        let id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+        let [a, b, c, d] = CONNECTION_ADDR_PREFIX;
+        let ip = Ipv6Addr::new(
+            a,
+            b,
+            c,
+            d,
+            (id >> 48) as u16,
+            (id >> 32) as u16,
+            (id >> 16) as u16,
+            id as u16,
+        );
+        SocketAddr::new(IpAddr::V6(ip), 0)
+    }
+
+    ///
+    /// 是否为unix domain socket连接的标识地址
+    pub fn is_connection_addr(addr: &SocketAddr) -> bool {
+        match addr.ip() {
+            IpAddr::V6(ip) => ip.segments()[..4] == CONNECTION_ADDR_PREFIX,
+            IpAddr::V4(_) => false,
+        }
+    }
+}
+
+impl Connected for UnixConnection {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+}
+
+impl AsyncRead for UnixConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_addr_unique() {
+        let a = UnixConnection::next_remote_addr();
+        let b = UnixConnection::next_remote_addr();
+        assert_ne!(a, b);
+        assert!(UnixConnection::is_connection_addr(&a));
+        assert!(!UnixConnection::is_connection_addr(
+            &"127.0.0.1:9848".parse().unwrap()
+        ));
+        assert!(!UnixConnection::is_connection_addr(
+            &"[::1]:9848".parse().unwrap()
+        ));
+    }
+}
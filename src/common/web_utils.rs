@@ -2,6 +2,7 @@ use actix_web::web;
 use tokio_stream::StreamExt;
 
 const MAX_SIZE: usize = 10485760;
+const BEARER_TOKEN_PREFIX: &str = "Bearer ";
 
 pub async fn get_req_body(mut payload: web::Payload) -> anyhow::Result<Vec<u8>> {
     let mut body = web::BytesMut::new();
@@ -15,3 +16,12 @@ pub async fn get_req_body(mut payload: web::Payload) -> anyhow::Result<Vec<u8>>
     }
     Ok(body.to_vec())
 }
+
+///
+/// 兼容nacos的Authorization头,去掉"Bearer "前缀
+pub fn get_bearer_token(value: &str) -> &str {
+    value
+        .strip_prefix(BEARER_TOKEN_PREFIX)
+        .unwrap_or(value)
+        .trim()
+}
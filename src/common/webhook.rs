@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use actix::Addr;
+use serde::Serialize;
+
+use super::cluster_settings::ClusterSettingsManager;
+use super::dead_letter::{DeadLetter, DeadLetterCmd, DeadLetterManager};
+
+const WEBHOOK_TIMEOUT_MILLIS: u64 = 3000;
+/// 推送失败后的最大重试次数,重试间隔逐次递增
+const WEBHOOK_MAX_RETRY: u64 = 3;
+const WEBHOOK_RETRY_INTERVAL_MILLIS: u64 = 1000;
+
+///
+/// 向集群设置中配置的webhook推送事件,未配置对应名称的webhook时忽略;
+/// 重试耗尽后写入死信列表
+pub struct WebhookUtils;
+
+impl WebhookUtils {
+    pub fn notify<T>(
+        cluster_settings: &ClusterSettingsManager,
+        dead_letter_manager: Option<&Addr<DeadLetterManager>>,
+        name: &str,
+        event: &T,
+    ) where
+        T: Serialize,
+    {
+        let url = match cluster_settings.get_webhook(name) {
+            Some(url) => url,
+            None => return,
+        };
+        let body = match serde_json::to_vec(event) {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("webhook {} serialize event error,{}", name, err);
+                return;
+            }
+        };
+        let name = name.to_owned();
+        let dead_letter_manager = dead_letter_manager.cloned();
+        tokio::spawn(async move {
+            let mut retry = 0;
+            loop {
+                match Self::post(&url, body.clone()).await {
+                    Ok(_) => return,
+                    Err(err) if retry < WEBHOOK_MAX_RETRY => {
+                        log::warn!("webhook {} post to {} error,{}", name, url, err);
+                        retry += 1;
+                        tokio::time::sleep(Duration::from_millis(
+                            WEBHOOK_RETRY_INTERVAL_MILLIS * retry,
+                        ))
+                        .await;
+                    }
+                    Err(err) => {
+                        log::warn!("webhook {} post to {} error,{}", name, url, err);
+                        if let Some(dead_letter_manager) = dead_letter_manager {
+                            let letter =
+                                DeadLetter::new_webhook(&name, &url, &body, err.to_string());
+                            dead_letter_manager.do_send(DeadLetterCmd::Add(letter));
+                        }
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    pub(crate) async fn post(url: &str, body: Vec<u8>) -> anyhow::Result<()> {
+        let resp = reqwest::Client::new()
+            .post(url)
+            .timeout(Duration::from_millis(WEBHOOK_TIMEOUT_MILLIS))
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!("response status {}", resp.status()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::console::SYSCONFIG_NAMESPACE;
+use crate::utils::get_md5;
+
+use super::core::ConfigKey;
+
+/// beta配置在系统配置中的分组
+pub const SYSCONFIG_BETA_GROUP: &str = "config_beta";
+/// 客户端版本在匹配标签中的key
+pub const CLIENT_VERSION_LABEL: &str = "clientVersion";
+
+///
+/// beta配置内容及客户端匹配规则,以系统配置的形式保存;
+/// 客户端ip在beta_ips中,或连接标签全部匹配时使用beta配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBetaDto {
+    pub content: Arc<String>,
+    /// 客户端连接标签需要全部匹配;如: AppName=demo,clientVersion=Nacos-Java-Client:v2.1.0
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// 客户端ip列表,对http与grpc客户端都生效
+    #[serde(default)]
+    pub beta_ips: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigBetaValue {
+    pub content: Arc<String>,
+    pub md5: Arc<String>,
+    pub labels: HashMap<String, String>,
+    pub beta_ips: Vec<String>,
+}
+
+impl ConfigBetaValue {
+    pub fn new(dto: ConfigBetaDto) -> Self {
+        let md5 = Arc::new(get_md5(&dto.content));
+        Self {
+            content: dto.content,
+            md5,
+            labels: dto.labels,
+            beta_ips: dto.beta_ips,
+        }
+    }
+
+    pub fn is_match(
+        &self,
+        client_labels: Option<&HashMap<String, String>>,
+        client_ip: Option<&str>,
+    ) -> bool {
+        if let Some(ip) = client_ip {
+            if self.beta_ips.iter().any(|e| e == ip) {
+                return true;
+            }
+        }
+        match client_labels {
+            Some(client_labels) => {
+                !self.labels.is_empty()
+                    && self
+                        .labels
+                        .iter()
+                        .all(|(k, v)| client_labels.get(k).map(|e| e == v).unwrap_or(false))
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct ConfigBetaUtils;
+
+impl ConfigBetaUtils {
+    ///
+    /// 配置对应的beta系统配置key
+    pub fn build_beta_key(key: &ConfigKey) -> ConfigKey {
+        let data_id = format!("{}@@{}@@{}", &key.tenant, &key.group, &key.data_id);
+        ConfigKey::new(&data_id, SYSCONFIG_BETA_GROUP, SYSCONFIG_NAMESPACE)
+    }
+
+    ///
+    /// 解析beta ip列表,与nacos的betaIps请求头一致使用逗号分隔
+    pub fn parse_beta_ips(value: &str) -> Vec<String> {
+        value
+            .split(',')
+            .map(|e| e.trim())
+            .filter(|e| !e.is_empty())
+            .map(|e| e.to_owned())
+            .collect()
+    }
+
+    ///
+    /// 从grpc连接id(ip:port)或http客户端ip中取出ip
+    pub fn get_client_ip(client_id: &str) -> Option<String> {
+        if let Ok(addr) = client_id.parse::<SocketAddr>() {
+            return Some(addr.ip().to_string());
+        }
+        client_id.parse::<IpAddr>().ok().map(|e| e.to_string())
+    }
+
+    pub fn is_beta_key(key: &ConfigKey) -> bool {
+        key.group.as_str() == SYSCONFIG_BETA_GROUP && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    ///
+    /// 从beta系统配置key解析出对应的配置key
+    pub fn parse_config_key(beta_key: &ConfigKey) -> Option<ConfigKey> {
+        let mut iter = beta_key.data_id.splitn(3, "@@");
+        let tenant = iter.next()?;
+        let group = iter.next()?;
+        let data_id = iter.next()?;
+        Some(ConfigKey::new(data_id, group, tenant))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beta_match() {
+        let beta = ConfigBetaValue::new(ConfigBetaDto {
+            content: Arc::new("a=1".to_owned()),
+            labels: HashMap::new(),
+            beta_ips: ConfigBetaUtils::parse_beta_ips("10.0.0.1, 10.0.0.2"),
+        });
+        let ip = ConfigBetaUtils::get_client_ip("10.0.0.2:52310");
+        assert!(beta.is_match(None, ip.as_deref()));
+        let ip = ConfigBetaUtils::get_client_ip("10.0.0.3");
+        assert!(!beta.is_match(Some(&HashMap::new()), ip.as_deref()));
+        assert!(ConfigBetaUtils::get_client_ip("abc").is_none());
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::common::appdata::AppShareData;
+use crate::console::SYSCONFIG_NAMESPACE;
+use crate::raft::cluster::model::SetConfigReq;
+
+use super::config_index::ConfigQueryParam;
+use super::core::{ConfigCmd, ConfigInfoDto, ConfigKey, ConfigResult};
+use super::nacos_zip::{ConfigImportItemKey, ConfigImportPolicy, ConfigImportResult};
+
+///
+/// 命名空间之间复制配置,全部写入在同一条raft日志中提交
+pub struct ConfigCloneUtils;
+
+impl ConfigCloneUtils {
+    fn item_key(item: &ConfigInfoDto) -> ConfigImportItemKey {
+        ConfigImportItemKey {
+            group: item.group.clone(),
+            data_id: item.data_id.clone(),
+        }
+    }
+
+    ///
+    /// 按冲突策略筛选需要写入的配置;Abort策略存在冲突时不写入任何配置
+    pub fn plan(
+        source: Vec<ConfigInfoDto>,
+        target_keys: &HashSet<(Arc<String>, Arc<String>)>,
+        policy: ConfigImportPolicy,
+    ) -> (Vec<ConfigInfoDto>, ConfigImportResult) {
+        let mut result = ConfigImportResult::default();
+        if policy == ConfigImportPolicy::Overwrite {
+            return (source, result);
+        }
+        let (conflicts, list): (Vec<_>, Vec<_>) = source
+            .into_iter()
+            .partition(|e| target_keys.contains(&(e.group.clone(), e.data_id.clone())));
+        let conflict_keys = conflicts.iter().map(Self::item_key).collect();
+        if policy == ConfigImportPolicy::Abort {
+            if !conflicts.is_empty() {
+                result.fail_data = conflict_keys;
+                return (vec![], result);
+            }
+        } else {
+            result.skip_count = conflicts.len();
+            result.skip_data = conflict_keys;
+        }
+        (list, result)
+    }
+
+    async fn query_configs(
+        app: &AppShareData,
+        tenant: Arc<String>,
+        query_context: bool,
+    ) -> anyhow::Result<Vec<ConfigInfoDto>> {
+        let param = ConfigQueryParam {
+            tenant: Some(tenant),
+            query_context,
+            limit: 0xffff_ffff,
+            ..Default::default()
+        };
+        match app
+            .config_addr
+            .send(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await??
+        {
+            ConfigResult::ConfigInfoPage(_, list) => Ok(list),
+            _ => Ok(vec![]),
+        }
+    }
+
+    ///
+    /// selected_keys格式为group/dataId,为None时复制源命名空间的全部配置;
+    /// 选中的配置在源命名空间不存在时不写入任何配置
+    pub async fn clone_configs(
+        app: &AppShareData,
+        source_tenant: Arc<String>,
+        target_tenant: Arc<String>,
+        selected_keys: Option<HashSet<String>>,
+        policy: ConfigImportPolicy,
+        op_user: Option<Arc<String>>,
+    ) -> anyhow::Result<ConfigImportResult> {
+        if source_tenant == target_tenant {
+            return Err(anyhow::anyhow!(
+                "the target namespace is the same as the source namespace"
+            ));
+        }
+        if source_tenant.as_str() == SYSCONFIG_NAMESPACE
+            || target_tenant.as_str() == SYSCONFIG_NAMESPACE
+        {
+            return Err(anyhow::anyhow!("can't clone the system configs"));
+        }
+        let mut source = Self::query_configs(app, source_tenant, true).await?;
+        //configKeys为空列表时与不指定一致,复制全部配置
+        if let Some(keys) = selected_keys.filter(|e| !e.is_empty()) {
+            source.retain(|e| keys.contains(&format!("{}/{}", &e.group, &e.data_id)));
+            let found: HashSet<String> = source
+                .iter()
+                .map(|e| format!("{}/{}", &e.group, &e.data_id))
+                .collect();
+            let missing: Vec<ConfigImportItemKey> = keys
+                .iter()
+                .filter(|e| !found.contains(*e))
+                .filter_map(|e| e.split_once('/'))
+                .map(|(group, data_id)| ConfigImportItemKey {
+                    group: Arc::new(group.to_owned()),
+                    data_id: Arc::new(data_id.to_owned()),
+                })
+                .collect();
+            if !missing.is_empty() {
+                return Ok(ConfigImportResult {
+                    fail_data: missing,
+                    ..Default::default()
+                });
+            }
+        }
+        let target_keys: HashSet<_> = Self::query_configs(app, target_tenant.clone(), false)
+            .await?
+            .into_iter()
+            .map(|e| (e.group, e.data_id))
+            .collect();
+        let (list, mut result) = Self::plan(source, &target_keys, policy);
+        if list.is_empty() {
+            return Ok(result);
+        }
+        let count = list.len();
+        let reqs = list
+            .into_iter()
+            .map(|item| {
+                let key = ConfigKey::new_by_arc(item.data_id, item.group, target_tenant.clone());
+                let mut req = SetConfigReq::new(key, item.content.unwrap_or_default());
+                req.config_type = item.config_type;
+                req.desc = item.desc;
+                req.op_user = op_user.clone();
+                if policy != ConfigImportPolicy::Overwrite {
+                    //在写入批次中检查目标配置不存在,避免与筛选之后的并发写入冲突
+                    req.expected_md5 = Some(Arc::new(String::new()));
+                }
+                req
+            })
+            .collect();
+        app.config_route.batch_set_config(reqs).await?;
+        result.succ_count = count;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_item(group: &str, data_id: &str) -> ConfigInfoDto {
+        ConfigInfoDto {
+            group: Arc::new(group.to_owned()),
+            data_id: Arc::new(data_id.to_owned()),
+            content: Some(Arc::new("a=1".to_owned())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clone_plan() {
+        let source = || vec![build_item("G", "a"), build_item("G", "b")];
+        let mut target_keys = HashSet::new();
+        target_keys.insert((Arc::new("G".to_owned()), Arc::new("b".to_owned())));
+
+        let (list, result) =
+            ConfigCloneUtils::plan(source(), &target_keys, ConfigImportPolicy::Abort);
+        assert!(list.is_empty());
+        assert_eq!(result.fail_data[0].data_id.as_str(), "b");
+
+        let (list, result) =
+            ConfigCloneUtils::plan(source(), &target_keys, ConfigImportPolicy::Skip);
+        assert_eq!(list.len(), 1);
+        assert_eq!(result.skip_count, 1);
+
+        let (list, _) =
+            ConfigCloneUtils::plan(source(), &target_keys, ConfigImportPolicy::Overwrite);
+        assert_eq!(list.len(), 2);
+    }
+}
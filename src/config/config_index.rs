@@ -13,6 +13,8 @@ pub struct ConfigQueryParam {
     pub data_id: Option<Arc<String>>,
     pub like_group: Option<String>,
     pub like_data_id: Option<String>,
+    /// dataId通配符,支持`*`、`?`
+    pub data_id_pattern: Option<String>,
     pub query_context: bool,
     pub offset: usize,
     pub limit: usize,
@@ -29,6 +31,11 @@ impl ConfigQueryParam {
         }
     }
     pub fn match_data_id(&self, s: &Arc<String>) -> bool {
+        if let Some(pattern) = &self.data_id_pattern {
+            if !StringUtils::wildcard_match(s, pattern) {
+                return false;
+            }
+        }
         if let Some(data_id) = &self.data_id {
             data_id.is_empty() || StringUtils::eq(s, data_id)
         } else if let Some(like_data_id) = &self.like_data_id {
@@ -265,6 +272,12 @@ fn query_service() {
     assert!(size == 5);
     assert!(list.len() == 2);
 
+    param.data_id_pattern = Some("2*".to_owned());
+    let (size, list) = index.query_config_page(&param);
+    assert!(size == 1);
+    assert!(list[0] == key5);
+    param.data_id_pattern = None;
+
     index.remove_config(&key1);
     index.remove_config(&key2);
     index.remove_config(&key3);
@@ -10,7 +10,8 @@ use actix::prelude::*;
 #[derive(Default)]
 pub struct Subscriber {
     listener: HashMap<ConfigKey, HashSet<Arc<String>>>,
-    client_keys: HashMap<Arc<String>, HashSet<ConfigKey>>,
+    /// client_id -> 订阅的配置及客户端持有的md5
+    client_keys: HashMap<Arc<String>, HashMap<ConfigKey, Arc<String>>>,
     conn_manage: Option<Addr<BiStreamManage>>,
 }
 
@@ -40,19 +41,9 @@ impl Subscriber {
                 }
             };
         }
-        match self.client_keys.get_mut(&client_id) {
-            Some(set) => {
-                for item in items {
-                    set.insert(item.key);
-                }
-            }
-            None => {
-                let mut set = HashSet::new();
-                for item in items {
-                    set.insert(item.key);
-                }
-                self.client_keys.insert(client_id, set);
-            }
+        let map = self.client_keys.entry(client_id).or_default();
+        for item in items {
+            map.insert(item.key, item.md5);
         }
     }
 
@@ -87,7 +78,7 @@ impl Subscriber {
     pub fn remove_client_subscribe(&mut self, client_id: Arc<String>) {
         if let Some(set) = self.client_keys.remove(&client_id) {
             let mut remove_keys = vec![];
-            for key in set {
+            for key in set.into_keys() {
                 if let Some(set) = self.listener.get_mut(&key) {
                     set.remove(&client_id);
                     if set.is_empty() {
@@ -138,10 +129,18 @@ impl Subscriber {
         sum
     }
 
+    pub fn get_key_client_size(&self, key: &ConfigKey) -> usize {
+        self.listener.get(key).map(|e| e.len()).unwrap_or_default()
+    }
+
     pub fn get_client_size(&self) -> usize {
         self.client_keys.len()
     }
 
+    pub fn get_client_keys(&self) -> &HashMap<Arc<String>, HashMap<ConfigKey, Arc<String>>> {
+        &self.client_keys
+    }
+
     pub fn get_client_value_size(&self) -> usize {
         let mut sum = 0;
         for item in self.client_keys.values() {
@@ -1,9 +1,10 @@
 use async_raft_ext::raft::ClientWriteRequest;
 use bean_factory::bean;
 use bean_factory::Inject;
-use chrono::Local;
+use chrono::{DateTime, FixedOffset, Local, Offset, Utc};
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::hash::Hash;
 use std::sync::Arc;
 use std::sync::Weak;
@@ -16,24 +17,56 @@ use crate::utils::get_md5;
 use serde::{Deserialize, Serialize};
 
 use crate::common::byte_utils::id_to_bin;
+use crate::common::cluster_settings::{ClusterSettingsManager, SYSCONFIG_CLUSTER_SETTINGS_KEY};
 use crate::common::constant::{CONFIG_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG};
+use crate::common::group_rule::{GroupRuleManager, SYSCONFIG_GROUP_RULE_KEY};
+use crate::common::metadata_schema::{MetadataSchemaManager, SYSCONFIG_METADATA_SCHEMA_KEY};
 use crate::common::sequence_utils::SimpleSequence;
+use crate::common::service_link::{ServiceLinkManager, SYSCONFIG_SERVICE_LINK_KEY};
+use crate::common::AppSysConfig;
+use crate::config::beta::{ConfigBetaDto, ConfigBetaUtils, ConfigBetaValue};
+use crate::config::freeze::{ConfigFreezeRule, ConfigFreezeUtils};
+use crate::config::include::{ConfigIncludeUtils, MAX_INCLUDE_DEPTH};
+use crate::config::schedule::{
+    ConfigScheduleUtils, ScheduledConfigChange, SYSCONFIG_CONFIG_SCHEDULE_KEY,
+};
+use crate::config::tag::ConfigTagUtils;
+use crate::config::time_travel::{ConfigAsOfItem, ConfigTimeTravelUtils};
+use crate::config::utils::param_utils::is_cipher_data_id;
+use crate::config::validation::ConfigValidationUtils;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
 use actix::prelude::*;
 
 use super::config_subscribe::Subscriber;
 use super::dal::ConfigHistoryParam;
 use super::dal::ConfigListenerDo;
 use super::dal::QueryListeners;
+use super::subscribe_replay::{ConfigReplayItem, SubscribeReplayMemory, CLIENT_INSTANCE_ID_LABEL};
 use crate::config::config_index::{ConfigQueryParam, TenantIndex};
 use crate::config::config_type::ConfigType;
+use crate::config::dry_run::{ConfigDiffUtils, ConfigNotifyTarget, ConfigPublishImpact};
+use crate::config::encryption::ConfigEncryptionManager;
 use crate::config::model::{
-    ConfigRaftCmd, ConfigRaftResult, ConfigValueDO, HistoryItem, SetConfigParam,
+    ConfigRaftCmd, ConfigRaftResult, ConfigValueDO, HistoryItem, ListenedConfigDto,
+    ListenerConnectionDto, SetConfigParam,
 };
 use crate::config::utils::param_utils;
+use crate::grpc::get_connection_ip;
+use crate::now_millis;
 use crate::now_millis_i64;
+use crate::raft::cluster::model::SetConfigReq;
 use crate::raft::filestore::model::SnapshotRecordDto;
 use crate::raft::filestore::raftsnapshot::{SnapshotWriterActor, SnapshotWriterRequest};
 
+/// 内存中每个配置最多保留的历史版本数
+pub(crate) const CONFIG_HISTORY_MAX_SIZE: usize = 100;
+/// 比较md5写入时配置已被其它请求修改
+pub const CONFIG_MD5_CONFLICT_MSG: &str = "config is modified concurrently";
+
+pub fn is_md5_conflict(err: &anyhow::Error) -> bool {
+    err.to_string().contains(CONFIG_MD5_CONFLICT_MSG)
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub struct ConfigKey {
     pub(crate) data_id: Arc<String>,
@@ -70,18 +103,8 @@ impl ConfigKey {
     /// 暂时只用于接口层面判断,以支持对部分场景不校验
     ///
     pub fn is_valid(&self) -> anyhow::Result<()> {
-        if !param_utils::is_valid(self.data_id.as_str()) {
-            return Err(anyhow::anyhow!(
-                "the config data_id is invalid : {}",
-                self.data_id.as_str()
-            ));
-        }
-        if !param_utils::is_valid(self.group.as_str()) {
-            return Err(anyhow::anyhow!(
-                "the config group is invalid : {}",
-                self.group.as_str()
-            ));
-        }
+        param_utils::check_data_id(self.data_id.as_str())?;
+        param_utils::check_group(self.group.as_str())?;
         Ok(())
     }
 }
@@ -181,7 +204,7 @@ impl ConfigValue {
             modified_time: op_time,
             op_user,
         };
-        if self.histories.len() >= 100 {
+        if self.histories.len() >= CONFIG_HISTORY_MAX_SIZE {
             self.histories.remove(0);
         }
         self.last_modified = op_time;
@@ -197,6 +220,25 @@ pub struct ConfigInfoDto {
     pub data_id: Arc<String>,
     pub content: Option<Arc<String>>,
     pub md5: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_type: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<Arc<String>>,
+}
+
+///
+/// 按dataId通配符批量查询的配置,内容与客户端按key查询一致
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAggregateItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub md5: Arc<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_type: Option<Arc<String>>,
+    pub last_modified: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -208,6 +250,7 @@ pub struct ConfigHistoryInfoDto {
     pub data_id: Option<String>,
     pub content: Option<String>,
     pub modified_time: Option<i64>, //给历史记录使用
+    pub op_user: Option<String>,
 }
 
 #[derive(Debug)]
@@ -424,6 +467,17 @@ impl ConfigListener {
     pub(crate) fn get_listener_key_size(&self) -> usize {
         self.listener.len()
     }
+
+    pub(crate) fn get_key_listener_size(&self, key: &ConfigKey) -> usize {
+        self.listener
+            .get(key)
+            .map(|list| {
+                list.iter()
+                    .filter(|v| self.channels.contains_key(v))
+                    .count()
+            })
+            .unwrap_or_default()
+    }
 }
 
 #[bean(inject)]
@@ -434,6 +488,29 @@ pub struct ConfigActor {
     pub(crate) tenant_index: TenantIndex,
     raft: Option<Weak<NacosRaft>>,
     sequence: SimpleSequence,
+    group_rule: Arc<GroupRuleManager>,
+    cluster_settings: Option<Arc<ClusterSettingsManager>>,
+    service_link: Option<Arc<ServiceLinkManager>>,
+    metadata_schema: Option<Arc<MetadataSchemaManager>>,
+    /// cipher-配置写入raft前加密,加载时解密
+    encryption: Arc<ConfigEncryptionManager>,
+    beta_map: HashMap<ConfigKey, ConfigBetaValue>,
+    client_labels: HashMap<Arc<String>, HashMap<String, String>>,
+    /// 片段 -> 引用该片段的配置
+    include_index: HashMap<ConfigKey, HashSet<ConfigKey>>,
+    /// 配置 -> 引用的片段
+    include_refs: HashMap<ConfigKey, Vec<ConfigKey>>,
+    /// 引用片段渲染后的内容与md5
+    rendered_map: HashMap<ConfigKey, (Arc<String>, Arc<String>)>,
+    /// 断开连接的客户端订阅记录,用于重连后恢复订阅
+    subscribe_replay: SubscribeReplayMemory,
+    sys_config: Option<Arc<AppSysConfig>>,
+    /// 待生效的定时发布
+    schedules: Vec<ScheduledConfigChange>,
+    /// 已提交raft还未应用的定时发布id
+    schedule_applying: HashSet<String>,
+    /// 已提交raft还未完成的写入,比较md5写入时这些配置视为冲突
+    writing_keys: HashMap<ConfigKey, usize>,
 }
 
 impl Inject for ConfigActor {
@@ -447,6 +524,27 @@ impl Inject for ConfigActor {
     ) {
         let raft: Option<Arc<NacosRaft>> = factory_data.get_bean();
         self.raft = raft.map(|e| Arc::downgrade(&e));
+        let group_rule: Option<Arc<GroupRuleManager>> = factory_data.get_bean();
+        if let Some(group_rule) = group_rule {
+            self.group_rule = group_rule;
+            self.refresh_group_rule();
+        }
+        self.cluster_settings = factory_data.get_bean();
+        self.refresh_cluster_settings();
+        self.service_link = factory_data.get_bean();
+        self.refresh_service_link();
+        self.metadata_schema = factory_data.get_bean();
+        self.refresh_metadata_schema();
+        if let Some(encryption) = factory_data.get_bean() {
+            self.encryption = encryption;
+        }
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = &sys_config {
+            self.subscribe_replay.ttl_millis = sys_config
+                .config_subscribe_replay_ttl_second
+                .saturating_mul(1000);
+        }
+        self.sys_config = sys_config;
         if let Some(conn_manage) = factory_data.get_actor() {
             self.subscriber.set_conn_manage(conn_manage);
         }
@@ -469,7 +567,696 @@ impl ConfigActor {
             tenant_index: TenantIndex::new(),
             raft: None,
             sequence: SimpleSequence::new(0, 100),
+            group_rule: Default::default(),
+            cluster_settings: None,
+            service_link: None,
+            metadata_schema: None,
+            encryption: Default::default(),
+            beta_map: Default::default(),
+            client_labels: Default::default(),
+            include_index: Default::default(),
+            include_refs: Default::default(),
+            rendered_map: Default::default(),
+            subscribe_replay: SubscribeReplayMemory::new(60_000),
+            sys_config: None,
+            schedules: vec![],
+            schedule_applying: Default::default(),
+            writing_keys: Default::default(),
+        }
+    }
+
+    fn is_group_rule_key(key: &ConfigKey) -> bool {
+        key.data_id.as_str() == SYSCONFIG_GROUP_RULE_KEY
+            && key.group.as_str() == SYSCONFIG_GROUP
+            && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    fn refresh_group_rule(&self) {
+        let key = ConfigKey::new(
+            SYSCONFIG_GROUP_RULE_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        );
+        if let Some(v) = self.cache.get(&key) {
+            self.group_rule.update_from_str(&v.content);
+        } else {
+            self.group_rule.update_rules(vec![]);
+        }
+    }
+
+    fn is_cluster_settings_key(key: &ConfigKey) -> bool {
+        key.data_id.as_str() == SYSCONFIG_CLUSTER_SETTINGS_KEY
+            && key.group.as_str() == SYSCONFIG_GROUP
+            && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    fn is_service_link_key(key: &ConfigKey) -> bool {
+        key.data_id.as_str() == SYSCONFIG_SERVICE_LINK_KEY
+            && key.group.as_str() == SYSCONFIG_GROUP
+            && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    fn refresh_service_link(&self) {
+        if let Some(service_link) = &self.service_link {
+            let key = ConfigKey::new(
+                SYSCONFIG_SERVICE_LINK_KEY,
+                SYSCONFIG_GROUP,
+                SYSCONFIG_NAMESPACE,
+            );
+            if let Some(v) = self.cache.get(&key) {
+                service_link.update_from_str(&v.content);
+            } else {
+                service_link.update_links(vec![]);
+            }
+        }
+    }
+
+    fn is_metadata_schema_key(key: &ConfigKey) -> bool {
+        key.data_id.as_str() == SYSCONFIG_METADATA_SCHEMA_KEY
+            && key.group.as_str() == SYSCONFIG_GROUP
+            && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    fn refresh_metadata_schema(&self) {
+        if let Some(metadata_schema) = &self.metadata_schema {
+            let key = ConfigKey::new(
+                SYSCONFIG_METADATA_SCHEMA_KEY,
+                SYSCONFIG_GROUP,
+                SYSCONFIG_NAMESPACE,
+            );
+            if let Some(v) = self.cache.get(&key) {
+                metadata_schema.update_from_str(&v.content);
+            } else {
+                metadata_schema.update_schemas(vec![]);
+            }
+        }
+    }
+
+    fn is_schedule_key(key: &ConfigKey) -> bool {
+        key.data_id.as_str() == SYSCONFIG_CONFIG_SCHEDULE_KEY
+            && key.group.as_str() == SYSCONFIG_GROUP
+            && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    fn refresh_schedules(&mut self) {
+        self.schedules = self
+            .cache
+            .get(&ConfigScheduleUtils::build_key())
+            .map(|v| ConfigScheduleUtils::parse(&v.content))
+            .unwrap_or_default();
+        let ids: HashSet<&String> = self.schedules.iter().map(|e| &e.id).collect();
+        self.schedule_applying.retain(|id| ids.contains(id));
+    }
+
+    fn add_writing_keys(&mut self, keys: &[ConfigKey]) {
+        for key in keys {
+            *self.writing_keys.entry(key.clone()).or_insert(0) += 1;
+        }
+    }
+
+    fn remove_writing_keys(&mut self, keys: &[ConfigKey]) {
+        for key in keys {
+            if let Some(count) = self.writing_keys.get_mut(key) {
+                *count -= 1;
+                if *count == 0 {
+                    self.writing_keys.remove(key);
+                }
+            }
+        }
+    }
+
+    fn get_timezone_offset(&self) -> FixedOffset {
+        self.sys_config
+            .as_ref()
+            .map(|e| e.get_timezone_offset())
+            .unwrap_or(Local::now().offset().fix())
+    }
+
+    fn get_freeze_rules(&self) -> Vec<ConfigFreezeRule> {
+        self.cache
+            .get(&ConfigFreezeUtils::build_key())
+            .map(|v| ConfigFreezeUtils::parse_rules(&v.content))
+            .unwrap_or_default()
+    }
+
+    ///
+    /// 配置写入前的统一检查,所有发布入口都在leader上经过这里
+    fn check_write(&self, msg: &ConfigAsyncCmd) -> anyhow::Result<()> {
+        let items: Vec<&SetConfigReq> = match msg {
+            ConfigAsyncCmd::Add(req) => vec![req],
+            ConfigAsyncCmd::BatchAdd(items) => items.iter().collect(),
+            ConfigAsyncCmd::Delete(_) => return Ok(()),
+        };
+        let freeze_rules = self.get_freeze_rules();
+        let now = Utc::now().with_timezone(&self.get_timezone_offset());
+        for req in items {
+            self.check_expected_md5(req)?;
+            self.check_write_policy(req, &freeze_rules, &now)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// 比较md5写入:配置有未完成的写入或当前md5与期望值不一致时返回冲突
+    fn check_expected_md5(&self, req: &SetConfigReq) -> anyhow::Result<()> {
+        let md5 = match &req.expected_md5 {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let key = &req.config_key;
+        let current_md5 = self
+            .get_config_value(key)
+            .map(|v| v.md5.as_str())
+            .unwrap_or_default();
+        if self.writing_keys.contains_key(key) || current_md5 != md5.as_str() {
+            return Err(anyhow::anyhow!(
+                "{}: {}#{}#{}",
+                CONFIG_MD5_CONFLICT_MSG,
+                &key.tenant,
+                &key.group,
+                &key.data_id
+            ));
+        }
+        Ok(())
+    }
+
+    ///
+    /// 检查变更冻结期与内容格式;tag与beta配置保存在系统命名空间,按对应的配置检查
+    fn check_write_policy(
+        &self,
+        req: &SetConfigReq,
+        freeze_rules: &[ConfigFreezeRule],
+        now: &DateTime<FixedOffset>,
+    ) -> anyhow::Result<()> {
+        let (key, content, config_type) = if ConfigTagUtils::is_tag_key(&req.config_key) {
+            match ConfigTagUtils::parse_tag_key(&req.config_key) {
+                Some((key, _)) => (key, req.value.clone(), req.config_type.clone()),
+                None => return Ok(()),
+            }
+        } else if ConfigBetaUtils::is_beta_key(&req.config_key) {
+            match ConfigBetaUtils::parse_config_key(&req.config_key) {
+                Some(key) => {
+                    let dto: ConfigBetaDto = serde_json::from_str(&req.value)?;
+                    let config_type = self
+                        .get_config_value(&key)
+                        .and_then(|v| v.config_type.clone());
+                    (key, dto.content, config_type)
+                }
+                None => return Ok(()),
+            }
+        } else {
+            (
+                req.config_key.clone(),
+                req.value.clone(),
+                req.config_type.clone(),
+            )
+        };
+        if key.tenant.as_str() == SYSCONFIG_NAMESPACE {
+            return Ok(());
+        }
+        if let (Some(config_type), false) = (&config_type, req.skip_validation) {
+            ConfigValidationUtils::validate(config_type, &content)?;
+        }
+        let operator = req
+            .op_user
+            .as_deref()
+            .map(|v| v.as_str())
+            .unwrap_or_default();
+        ConfigFreezeUtils::check_rules(freeze_rules, &key, operator, req.freeze_override, now)
+    }
+
+    fn is_leader(&self) -> bool {
+        match (
+            self.raft.as_ref().and_then(|e| e.upgrade()),
+            &self.sys_config,
+        ) {
+            (Some(raft), Some(sys_config)) => {
+                raft.metrics().borrow().current_leader == Some(sys_config.raft_node_id)
+            }
+            _ => false,
+        }
+    }
+
+    ///
+    /// leader节点把到达生效时间的定时发布与剩余列表在同一条raft日志中写入,
+    /// 各节点应用日志时更新配置并通知监听者
+    fn check_schedules(&mut self, ctx: &mut Context<Self>) {
+        let schedule_key = ConfigScheduleUtils::build_key();
+        if self.schedules.is_empty()
+            || !self.is_leader()
+            || self.writing_keys.contains_key(&schedule_key)
+        {
+            return;
+        }
+        let list: Vec<ScheduledConfigChange> = self
+            .schedules
+            .iter()
+            .filter(|e| !self.schedule_applying.contains(&e.id))
+            .cloned()
+            .collect();
+        let (mut due, _) = ConfigScheduleUtils::split_due(list, now_millis_i64());
+        if !due.is_empty() {
+            //处于变更冻结期的定时发布延后到冻结期结束后生效
+            let rules = self.get_freeze_rules();
+            if !rules.is_empty() {
+                let now = Utc::now().with_timezone(&self.get_timezone_offset());
+                due.retain(|e| ConfigFreezeUtils::match_rule(&rules, &e.tenant, &now).is_none());
+            }
+        }
+        if due.is_empty() {
+            return;
+        }
+        let due_ids: HashSet<String> = due.iter().map(|e| e.id.clone()).collect();
+        let remain: Vec<&ScheduledConfigChange> = self
+            .schedules
+            .iter()
+            .filter(|e| !due_ids.contains(&e.id))
+            .collect();
+        let remain_value = match serde_json::to_string(&remain) {
+            Ok(v) => Arc::new(v),
+            Err(err) => {
+                log::error!("serialize config schedules error,{}", err);
+                return;
+            }
+        };
+        let op_time = now_millis_i64();
+        let mut reqs = Vec::with_capacity(due.len() + 1);
+        let mut writing_keys = Vec::with_capacity(due.len() + 1);
+        for item in due {
+            let key = item.get_config_key();
+            writing_keys.push(key.clone());
+            let (history_id, history_table_id) = match self.sequence.next_state() {
+                Ok(v) => v,
+                Err(err) => {
+                    log::error!("build config history id error,{}", err);
+                    return;
+                }
+            };
+            let value = match self.encryption.encrypt(&key, item.content) {
+                Ok(v) => v,
+                Err(err) => {
+                    log::error!("encrypt scheduled config error,{}", err);
+                    return;
+                }
+            };
+            reqs.push(ClientRequest::ConfigSet {
+                key: key.build_key(),
+                value,
+                config_type: item.config_type,
+                desc: item.desc,
+                history_id,
+                history_table_id,
+                op_time,
+                op_user: item.op_user,
+            });
+        }
+        let (history_id, history_table_id) = match self.sequence.next_state() {
+            Ok(v) => v,
+            Err(err) => {
+                log::error!("build config history id error,{}", err);
+                return;
+            }
+        };
+        reqs.push(ClientRequest::ConfigSet {
+            key: schedule_key.build_key(),
+            value: remain_value,
+            config_type: None,
+            desc: None,
+            history_id,
+            history_table_id,
+            op_time,
+            op_user: None,
+        });
+        writing_keys.push(schedule_key);
+        self.schedule_applying.extend(due_ids.iter().cloned());
+        self.add_writing_keys(&writing_keys);
+        let raft = self.raft.clone();
+        async move { Self::send_raft_request(&raft, ClientRequest::ConfigBatch(reqs)).await }
+            .into_actor(self)
+            .map(move |r, act, _ctx| {
+                act.remove_writing_keys(&writing_keys);
+                if let Err(err) = r {
+                    log::error!("apply scheduled configs error,{}", err);
+                    for id in &due_ids {
+                        act.schedule_applying.remove(id);
+                    }
+                }
+            })
+            .spawn(ctx);
+    }
+
+    ///
+    /// 集群共享设置变更后刷新到本节点
+    fn refresh_cluster_settings(&self) {
+        if let Some(cluster_settings) = &self.cluster_settings {
+            let key = ConfigKey::new(
+                SYSCONFIG_CLUSTER_SETTINGS_KEY,
+                SYSCONFIG_GROUP,
+                SYSCONFIG_NAMESPACE,
+            );
+            if let Some(v) = self.cache.get(&key) {
+                cluster_settings.update_from_str(&v.content);
+            } else {
+                cluster_settings.update(Default::default());
+            }
+        }
+    }
+
+    ///
+    /// 获取配置,原分组不存在时按命名空间分组规则解析
+    fn get_config_entry(&self, key: &ConfigKey) -> Option<(&ConfigKey, &ConfigValue)> {
+        if let Some(v) = self.cache.get_key_value(key) {
+            return Some(v);
+        }
+        let group = self.group_rule.resolve_group(&key.tenant, &key.group)?;
+        self.cache.get_key_value(&ConfigKey::new_by_arc(
+            key.data_id.clone(),
+            group,
+            key.tenant.clone(),
+        ))
+    }
+
+    fn get_config_value(&self, key: &ConfigKey) -> Option<&ConfigValue> {
+        self.get_config_entry(key).map(|(_, v)| v)
+    }
+
+    ///
+    /// 获取面向客户端的配置内容与md5,包含引用片段时返回渲染后的内容
+    fn get_render_value(
+        &self,
+        key: &ConfigKey,
+    ) -> Option<(Arc<String>, Arc<String>, &ConfigValue)> {
+        let (key, v) = self.get_config_entry(key)?;
+        if let Some((content, md5)) = self.rendered_map.get(key) {
+            Some((content.clone(), md5.clone(), v))
+        } else {
+            Some((v.content.clone(), v.md5.clone(), v))
+        }
+    }
+
+    fn build_config_data(&self, key: &ConfigKey) -> Option<ConfigResult> {
+        let (content, md5, v) = self.get_render_value(key)?;
+        Some(ConfigResult::Data {
+            value: content,
+            md5,
+            config_type: v.config_type.clone(),
+            desc: v.desc.clone(),
+            last_modified: v.last_modified,
+        })
+    }
+
+    ///
+    /// 更新配置引用的片段索引,并重新渲染
+    fn update_include(&mut self, key: &ConfigKey) {
+        if let Some(old_refs) = self.include_refs.remove(key) {
+            for item in old_refs {
+                if let Some(set) = self.include_index.get_mut(&item) {
+                    set.remove(key);
+                    if set.is_empty() {
+                        self.include_index.remove(&item);
+                    }
+                }
+            }
+        }
+        let includes = self
+            .cache
+            .get(key)
+            .map(|v| ConfigIncludeUtils::parse_includes(key, &v.content))
+            .unwrap_or_default();
+        if !includes.is_empty() {
+            for item in &includes {
+                self.include_index
+                    .entry(item.clone())
+                    .or_default()
+                    .insert(key.clone());
+            }
+            self.include_refs.insert(key.clone(), includes);
+        }
+        self.render_include(key);
+    }
+
+    fn render_include(&mut self, key: &ConfigKey) {
+        let rendered = if self.include_refs.contains_key(key) {
+            self.cache.get(key).map(|v| {
+                ConfigIncludeUtils::render(key, &v.content, &|k: &ConfigKey| {
+                    //加密配置在缓存中为明文,不能作为片段被引用
+                    if is_cipher_data_id(&k.data_id) {
+                        return None;
+                    }
+                    self.cache.get(k).map(|e| e.content.clone())
+                })
+            })
+        } else {
+            None
+        };
+        if let Some(content) = rendered {
+            let md5 = get_md5(&content);
+            self.rendered_map
+                .insert(key.clone(), (Arc::new(content), Arc::new(md5)));
+        } else {
+            self.rendered_map.remove(key);
+        }
+    }
+
+    ///
+    /// 片段变更后重新渲染引用它的配置,返回受影响的配置列表
+    fn refresh_include_referrers(&mut self, key: &ConfigKey) -> Vec<ConfigKey> {
+        let mut changes: Vec<ConfigKey> = vec![];
+        let mut current = vec![key.clone()];
+        for _ in 0..MAX_INCLUDE_DEPTH {
+            let mut next = vec![];
+            for item in &current {
+                if let Some(set) = self.include_index.get(item) {
+                    for referrer in set {
+                        if !changes.contains(referrer) && referrer != key {
+                            next.push(referrer.clone());
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            for item in &next {
+                self.render_include(item);
+            }
+            changes.extend(next.iter().cloned());
+            current = next;
+        }
+        changes
+    }
+
+    ///
+    /// 预演配置发布,计算内容差异与变更时会通知的订阅者,不修改数据
+    fn get_publish_impact(&self, key: &ConfigKey, content: &str) -> ConfigPublishImpact {
+        let mut impact = ConfigPublishImpact {
+            valid: true,
+            new_md5: Arc::new(get_md5(content)),
+            ..Default::default()
+        };
+        let old_content = if let Some(v) = self.cache.get(key) {
+            impact.exists = true;
+            impact.old_md5 = Some(v.md5.clone());
+            v.content.clone()
+        } else {
+            Arc::new(String::new())
+        };
+        impact.changed = impact.old_md5.as_ref() != Some(&impact.new_md5);
+        if impact.changed {
+            match ConfigDiffUtils::line_diff(&old_content, content) {
+                Some(diff) => impact.diff = diff,
+                None => impact.diff_truncated = true,
+            }
+        }
+        let mut targets = vec![ConfigNotifyTarget::new(key, "self")];
+        let mut visited = HashSet::new();
+        visited.insert(key.clone());
+        let mut current = vec![key.clone()];
+        for _ in 0..MAX_INCLUDE_DEPTH {
+            let mut next = vec![];
+            for item in &current {
+                if let Some(set) = self.include_index.get(item) {
+                    for referrer in set {
+                        if visited.insert(referrer.clone()) {
+                            next.push(referrer.clone());
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            for item in &next {
+                targets.push(ConfigNotifyTarget::new(item, "include"));
+            }
+            current = next;
         }
+        for group in self.group_rule.alias_groups(&key.tenant, &key.group) {
+            let alias_key = ConfigKey::new_by_arc(key.data_id.clone(), group, key.tenant.clone());
+            if !self.cache.contains_key(&alias_key) {
+                targets.push(ConfigNotifyTarget::new(&alias_key, "alias"));
+            }
+        }
+        for target in targets.iter_mut() {
+            let target_key = ConfigKey::new_by_arc(
+                target.data_id.clone(),
+                target.group.clone(),
+                target.tenant.clone(),
+            );
+            target.subscriber_count = self.subscriber.get_key_client_size(&target_key);
+            target.listener_count = self.listener.get_key_listener_size(&target_key);
+            impact.subscriber_count += target.subscriber_count;
+            impact.listener_count += target.listener_count;
+        }
+        impact.targets = targets;
+        impact
+    }
+
+    ///
+    /// 挂在配置上的beta与tag系统配置,按所属配置过滤
+    fn get_attached_keys<F>(&self, filter: F) -> Vec<ConfigKey>
+    where
+        F: Fn(&ConfigKey) -> bool,
+    {
+        self.cache
+            .keys()
+            .filter(|key| {
+                let base_key = if ConfigTagUtils::is_tag_key(key) {
+                    ConfigTagUtils::parse_tag_key(key).map(|(v, _)| v)
+                } else if ConfigBetaUtils::is_beta_key(key) {
+                    ConfigBetaUtils::parse_config_key(key)
+                } else {
+                    None
+                };
+                base_key.is_some_and(|v| filter(&v))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn refresh_beta(&mut self, beta_key: &ConfigKey) -> Option<ConfigKey> {
+        let key = ConfigBetaUtils::parse_config_key(beta_key)?;
+        let dto = self
+            .cache
+            .get(beta_key)
+            .and_then(|v| serde_json::from_str::<ConfigBetaDto>(&v.content).ok());
+        if let Some(dto) = dto {
+            self.beta_map.insert(key.clone(), ConfigBetaValue::new(dto));
+        } else {
+            self.beta_map.remove(&key);
+        }
+        Some(key)
+    }
+
+    ///
+    /// 获取客户端匹配的beta配置;client_id为grpc连接id(ip:port)或http客户端ip
+    fn get_client_beta_value(
+        &self,
+        key: &ConfigKey,
+        client_id: &Arc<String>,
+    ) -> Option<&ConfigBetaValue> {
+        let beta = self.beta_map.get(key)?;
+        let labels = self.client_labels.get(client_id);
+        let client_ip = ConfigBetaUtils::get_client_ip(client_id);
+        if beta.is_match(labels, client_ip.as_deref()) {
+            Some(beta)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// 客户端查询的配置,匹配beta规则时返回beta配置
+    fn get_client_config(&self, key: &ConfigKey, client_id: &Arc<String>) -> Option<ConfigResult> {
+        if let Some(beta) = self.get_client_beta_value(key, client_id) {
+            let (config_type, last_modified) = self
+                .cache
+                .get(key)
+                .map(|v| (v.config_type.clone(), v.last_modified))
+                .unwrap_or_default();
+            return Some(ConfigResult::BetaData {
+                value: beta.content.clone(),
+                md5: beta.md5.clone(),
+                config_type,
+                last_modified,
+            });
+        }
+        self.build_config_data(key)
+    }
+
+    fn query_aggregate_configs(
+        &self,
+        param: &ConfigQueryParam,
+        client_id: &Arc<String>,
+    ) -> Vec<ConfigAggregateItem> {
+        let (_, keys) = self.tenant_index.query_config_page(param);
+        let mut list = Vec::with_capacity(keys.len());
+        for key in keys {
+            let (content, md5, config_type, last_modified) =
+                match self.get_client_config(&key, client_id) {
+                    Some(ConfigResult::Data {
+                        value,
+                        md5,
+                        config_type,
+                        last_modified,
+                        ..
+                    })
+                    | Some(ConfigResult::BetaData {
+                        value,
+                        md5,
+                        config_type,
+                        last_modified,
+                    }) => (value, md5, config_type, last_modified),
+                    _ => continue,
+                };
+            list.push(ConfigAggregateItem {
+                group: key.group,
+                data_id: key.data_id,
+                md5,
+                content: if param.query_context {
+                    Some(content)
+                } else {
+                    None
+                },
+                config_type,
+                last_modified,
+            });
+        }
+        list
+    }
+
+    fn notify_change(&mut self, key: ConfigKey) {
+        self.update_include(&key);
+        for referrer in self.refresh_include_referrers(&key) {
+            self.listener.notify(referrer.clone());
+            self.subscriber.notify(referrer);
+        }
+        if ConfigBetaUtils::is_beta_key(&key) {
+            if let Some(config_key) = self.refresh_beta(&key) {
+                self.listener.notify(config_key.clone());
+                self.subscriber.notify(config_key);
+            }
+        }
+        for group in self.group_rule.alias_groups(&key.tenant, &key.group) {
+            let alias_key = ConfigKey::new_by_arc(key.data_id.clone(), group, key.tenant.clone());
+            if !self.cache.contains_key(&alias_key) {
+                self.listener.notify(alias_key.clone());
+                self.subscriber.notify(alias_key);
+            }
+        }
+        if Self::is_group_rule_key(&key) {
+            self.refresh_group_rule();
+        } else if Self::is_cluster_settings_key(&key) {
+            self.refresh_cluster_settings();
+        } else if Self::is_service_link_key(&key) {
+            self.refresh_service_link();
+        } else if Self::is_metadata_schema_key(&key) {
+            self.refresh_metadata_schema();
+        } else if Self::is_schedule_key(&key) {
+            self.refresh_schedules();
+        }
+        self.listener.notify(key.clone());
+        self.subscriber.notify(key);
     }
 
     fn set_tmp_config(&mut self, key: ConfigKey, val: Arc<String>) {
@@ -486,7 +1273,24 @@ impl ConfigActor {
 
     fn inner_set_config(&mut self, key: ConfigKey, value: ConfigValue) {
         self.tenant_index.insert_config(key.clone());
-        self.cache.insert(key, value);
+        let is_group_rule = Self::is_group_rule_key(&key);
+        let is_beta = ConfigBetaUtils::is_beta_key(&key);
+        self.cache.insert(key.clone(), value);
+        if is_group_rule {
+            self.refresh_group_rule();
+        } else if Self::is_cluster_settings_key(&key) {
+            self.refresh_cluster_settings();
+        } else if Self::is_service_link_key(&key) {
+            self.refresh_service_link();
+        } else if Self::is_metadata_schema_key(&key) {
+            self.refresh_metadata_schema();
+        } else if Self::is_schedule_key(&key) {
+            self.refresh_schedules();
+        } else if is_beta {
+            self.refresh_beta(&key);
+        }
+        self.update_include(&key);
+        self.refresh_include_referrers(&key);
     }
 
     fn set_config(&mut self, param: SetConfigParam) -> anyhow::Result<ConfigResult> {
@@ -527,8 +1331,7 @@ impl ConfigActor {
             self.cache.insert(param.key.clone(), v);
             self.tenant_index.insert_config(param.key.clone());
         }
-        self.listener.notify(param.key.clone());
-        self.subscriber.notify(param.key);
+        self.notify_change(param.key);
         Ok(ConfigResult::NULL)
     }
 
@@ -536,8 +1339,7 @@ impl ConfigActor {
         self.cache.remove(&key);
         //self.config_db.del_config(&key).ok();
         self.tenant_index.remove_config(&key);
-        self.listener.notify(key.clone());
-        self.subscriber.notify(key.clone());
+        self.notify_change(key.clone());
         self.subscriber.remove_config_key(key);
         Ok(())
     }
@@ -571,6 +1373,117 @@ impl ConfigActor {
         Ok(())
     }
 
+    ///
+    /// 客户端应持有的配置md5,匹配beta规则时为beta配置的md5
+    fn get_client_expected_md5(
+        &self,
+        key: &ConfigKey,
+        client_id: Option<&Arc<String>>,
+    ) -> Option<Arc<String>> {
+        if let Some(beta) = client_id.and_then(|id| self.get_client_beta_value(key, id)) {
+            return Some(beta.md5.clone());
+        }
+        self.get_render_value(key).map(|(_, md5, _)| md5)
+    }
+
+    fn build_listened_config(
+        &self,
+        key: &ConfigKey,
+        md5: Arc<String>,
+        client_id: Option<&Arc<String>>,
+    ) -> ListenedConfigDto {
+        let stale = match self.get_client_expected_md5(key, client_id) {
+            Some(v) => v != md5,
+            None => !md5.is_empty(),
+        };
+        ListenedConfigDto {
+            data_id: key.data_id.clone(),
+            group: key.group.clone(),
+            md5,
+            stale,
+        }
+    }
+
+    ///
+    /// 本节点上监听命名空间配置的客户端连接,包含grpc订阅与正在等待的http长轮询
+    pub fn get_listener_connection_map(&self, tenant: &Arc<String>) -> Vec<ListenerConnectionDto> {
+        self.get_listener_connections(|key| &key.tenant == tenant)
+    }
+
+    ///
+    /// 本节点上监听单个配置的客户端连接及其持有的md5,用于排查客户端收不到配置变更
+    pub fn get_config_listener_connections(
+        &self,
+        config_key: &ConfigKey,
+    ) -> Vec<ListenerConnectionDto> {
+        self.get_listener_connections(|key| key == config_key)
+    }
+
+    fn get_listener_connections<F>(&self, filter: F) -> Vec<ListenerConnectionDto>
+    where
+        F: Fn(&ConfigKey) -> bool,
+    {
+        let mut list = vec![];
+        for (client_id, keys) in self.subscriber.get_client_keys() {
+            let mut configs: Vec<ListenedConfigDto> = keys
+                .iter()
+                .filter(|(key, _)| filter(key))
+                .map(|(key, md5)| self.build_listened_config(key, md5.clone(), Some(client_id)))
+                .collect();
+            if configs.is_empty() {
+                continue;
+            }
+            configs.sort_by(|a, b| (&a.group, &a.data_id).cmp(&(&b.group, &b.data_id)));
+            let ip = match client_id.rsplit_once(':') {
+                Some((ip, _)) => ip.trim_matches(|c| c == '[' || c == ']').to_owned(),
+                None => client_id.as_ref().to_owned(),
+            };
+            let app_name = self
+                .client_labels
+                .get(client_id)
+                .and_then(|labels| labels.get("AppName"))
+                .cloned()
+                .unwrap_or_default();
+            list.push(ListenerConnectionDto {
+                connection: client_id.as_ref().to_owned(),
+                ip,
+                listen_type: "grpc".to_owned(),
+                app_name,
+                configs,
+            });
+        }
+        //http长轮询只在md5一致时等待,等待中的监听不会过期
+        let mut http_listeners: BTreeMap<u64, Vec<ListenedConfigDto>> = BTreeMap::new();
+        for (key, ids) in &self.listener.listener {
+            if !filter(key) {
+                continue;
+            }
+            let md5 = self.get_client_expected_md5(key, None).unwrap_or_default();
+            for id in ids {
+                if self.listener.channels.contains_key(id) {
+                    http_listeners
+                        .entry(*id)
+                        .or_default()
+                        .push(self.build_listened_config(key, md5.clone(), None));
+                }
+            }
+        }
+        for (id, mut configs) in http_listeners {
+            if let Some(channel) = self.listener.channels.get(&id) {
+                configs.sort_by(|a, b| (&a.group, &a.data_id).cmp(&(&b.group, &b.data_id)));
+                list.push(ListenerConnectionDto {
+                    connection: id.to_string(),
+                    ip: channel.info.ip.clone(),
+                    listen_type: "http".to_owned(),
+                    app_name: channel.info.name.as_str().to_owned(),
+                    configs,
+                });
+            }
+        }
+        list.sort_by(|a, b| (&a.ip, &a.connection).cmp(&(&b.ip, &b.connection)));
+        list
+    }
+
     pub fn get_config_listeners(
         &self,
         config_key: &ConfigKey,
@@ -617,6 +1530,8 @@ impl ConfigActor {
                 if param.query_context {
                     info.content = Some(value.content.clone());
                     info.md5 = Some(value.md5.clone());
+                    info.config_type = value.config_type.clone();
+                    info.desc = value.desc.clone();
                 }
                 info_list.push(info);
             }
@@ -680,7 +1595,8 @@ impl ConfigActor {
     ///
     fn build_snapshot(&self, writer: Addr<SnapshotWriterActor>) -> anyhow::Result<()> {
         for (key, value) in &self.cache {
-            let value_db: ConfigValueDO = value.clone().into();
+            let mut value_db: ConfigValueDO = value.clone().into();
+            self.encryption.encrypt_value_do(key, &mut value_db)?;
             let record = SnapshotRecordDto {
                 tree: CONFIG_TREE_NAME.clone(),
                 key: key.build_key().as_bytes().to_vec(),
@@ -699,9 +1615,65 @@ impl ConfigActor {
         Ok(())
     }
 
+    ///
+    /// 按断开前的订阅记录为新连接恢复订阅,返回订阅配置的当前md5;
+    /// 客户端稳定标识只从本连接的标签中读取,本节点没有对应记录时返回NotFound
+    fn replay_subscribe(
+        &mut self,
+        client_id: Arc<String>,
+        remote_keys: Option<Vec<ConfigKey>>,
+        with_content: bool,
+    ) -> ConfigResult {
+        let keys = match remote_keys {
+            Some(keys) => keys,
+            None => {
+                let instance_id = match self
+                    .client_labels
+                    .get(&client_id)
+                    .and_then(|labels| labels.get(CLIENT_INSTANCE_ID_LABEL))
+                {
+                    Some(v) => Arc::new(v.to_owned()),
+                    None => return ConfigResult::ReplaySubscribe(vec![]),
+                };
+                let owner_ip = get_connection_ip(&client_id).unwrap_or_default();
+                match self
+                    .subscribe_replay
+                    .take(&instance_id, &owner_ip, now_millis())
+                {
+                    Some(keys) => keys,
+                    None => return ConfigResult::ReplayNotFound(instance_id, owner_ip),
+                }
+            }
+        };
+        let mut list = Vec::with_capacity(keys.len());
+        let mut items = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = if let Some(beta) = self.get_client_beta_value(&key, &client_id) {
+                Some((beta.content.clone(), beta.md5.clone()))
+            } else {
+                self.get_render_value(&key)
+                    .map(|(content, md5, _)| (content, md5))
+            };
+            let md5 = value.as_ref().map(|(_, md5)| md5.clone());
+            items.push(ListenerItem::new(
+                key.clone(),
+                md5.clone().unwrap_or_default(),
+            ));
+            list.push(ConfigReplayItem {
+                key,
+                md5,
+                content: value.filter(|_| with_content).map(|(content, _)| content),
+            });
+        }
+        self.subscriber.add_subscribe(client_id, items);
+        ConfigResult::ReplaySubscribe(list)
+    }
+
     pub fn hb(&self, ctx: &mut actix::Context<Self>) {
         ctx.run_later(Duration::from_millis(500), |act, ctx| {
             act.listener.timeout();
+            act.subscribe_replay.clear_timeout(now_millis());
+            act.check_schedules(ctx);
             act.hb(ctx);
         });
     }
@@ -714,10 +1686,23 @@ pub enum ConfigCmd {
     //DELETE(ConfigKey),
     SetTmpValue(ConfigKey, Arc<String>),
     InnerSet(ConfigKey, ConfigValue),
+    /// 写入配置并通知监听者,用于运行中应用差异镜像
+    InnerSetAndNotify(ConfigKey, ConfigValue),
     InnerSetLastId(u64),
     GET(ConfigKey),
+    /// 查询原始配置内容,不渲染引用片段
+    GetRaw(ConfigKey),
+    /// 按客户端(grpc连接id或http客户端ip)查询配置,匹配beta规则时返回beta配置
+    GetByClient(ConfigKey, Arc<String>),
+    SetClientLabels(Arc<String>, HashMap<String, String>),
     QueryPageInfo(Box<ConfigQueryParam>),
+    /// 按客户端批量查询匹配的配置,query_context为false时不返回内容
+    QueryAggregate(Box<ConfigQueryParam>, Arc<String>),
     QueryHistoryPageInfo(Box<ConfigHistoryParam>),
+    /// 按历史记录id查询配置的一条历史记录
+    GetHistory(ConfigKey, u64),
+    /// 按历史记录还原指定时间点(毫秒)生效的配置
+    QueryAsOf(Box<ConfigQueryParam>, i64),
     Listener(
         Vec<ListenerItem>,
         ListenerSenderType,
@@ -725,23 +1710,51 @@ pub enum ConfigCmd {
         i64,
     ),
     QueryListeners(QueryListeners),
+    /// 查询命名空间下客户端连接与监听配置的对应关系
+    QueryListenerConnectionMap(Arc<String>),
+    /// 查询监听单个配置的客户端连接
+    QueryConfigListenerConnections(ConfigKey),
+    /// 预演配置发布
+    QueryPublishImpact(ConfigKey, Arc<String>),
+    /// 查询命名空间下配置挂载的beta与tag系统配置
+    QueryAttachedKeys(Arc<String>),
     Subscribe(Vec<ListenerItem>, Arc<String>),
     RemoveSubscribe(Vec<ListenerItem>, Arc<String>),
     RemoveSubscribeClient(Arc<String>),
+    /// 客户端重连后恢复订阅
+    ReplaySubscribe {
+        client_id: Arc<String>,
+        /// 从其它节点取回的订阅记录,为空时使用本节点的记录
+        remote_keys: Option<Vec<ConfigKey>>,
+        with_content: bool,
+    },
+    /// 其它节点上重连的客户端取回本节点保存的订阅记录
+    TakeSubscribeReplay {
+        instance_id: Arc<String>,
+        owner_ip: String,
+    },
     BuildSnapshot(Addr<SnapshotWriterActor>),
+    /// 查询各命名空间的配置数量与内容字节数
+    QueryNamespaceUsage,
 }
 
 #[derive(Message)]
 #[rtype(result = "anyhow::Result<ConfigResult>")]
 pub enum ConfigAsyncCmd {
-    Add {
-        key: ConfigKey,
-        value: Arc<String>,
-        op_user: Option<Arc<String>>,
-        config_type: Option<Arc<String>>,
-        desc: Option<Arc<String>>,
-    },
+    Add(SetConfigReq),
     Delete(ConfigKey),
+    /// 多个配置在同一条raft日志中写入
+    BatchAdd(Vec<SetConfigReq>),
+}
+
+impl ConfigAsyncCmd {
+    fn get_keys(&self) -> Vec<ConfigKey> {
+        match self {
+            ConfigAsyncCmd::Add(req) => vec![req.config_key.clone()],
+            ConfigAsyncCmd::Delete(key) => vec![key.clone()],
+            ConfigAsyncCmd::BatchAdd(items) => items.iter().map(|e| e.config_key.clone()).collect(),
+        }
+    }
 }
 
 pub enum ConfigResult {
@@ -752,11 +1765,28 @@ pub enum ConfigResult {
         desc: Option<Arc<String>>,
         last_modified: i64,
     },
+    BetaData {
+        value: Arc<String>,
+        md5: Arc<String>,
+        config_type: Option<Arc<String>>,
+        last_modified: i64,
+    },
     NULL,
     ChangeKey(Vec<ConfigKey>),
+    AttachedKeys(Vec<ConfigKey>),
     ConfigInfoPage(usize, Vec<ConfigInfoDto>),
+    AggregateConfigs(Vec<ConfigAggregateItem>),
+    ConfigAsOfList(Vec<ConfigAsOfItem>),
     ConfigHistoryInfoPage(usize, Vec<ConfigHistoryInfoDto>),
     ConfigListenerInfoPage(usize, Vec<ConfigListenerDo>),
+    PublishImpact(Box<ConfigPublishImpact>),
+    ListenerConnectionMap(Vec<ListenerConnectionDto>),
+    /// namespace_id -> (配置数量,内容字节数)
+    NamespaceUsage(HashMap<Arc<String>, (u64, u64)>),
+    ReplaySubscribe(Vec<ConfigReplayItem>),
+    /// 本节点没有订阅记录,(客户端稳定标识,客户端ip)
+    ReplayNotFound(Arc<String>, String),
+    SubscribeReplayKeys(Option<Vec<ConfigKey>>),
 }
 
 impl Actor for ConfigActor {
@@ -782,14 +1812,29 @@ impl Handler<ConfigCmd> for ConfigActor {
             ConfigCmd::SetTmpValue(key, value) => {
                 self.set_tmp_config(key, value);
             }
-            ConfigCmd::InnerSet(key, value) => {
-                self.inner_set_config(key, value);
+            ConfigCmd::InnerSet(key, value) => match self.encryption.decrypt_value(&key, value) {
+                Ok(value) => self.inner_set_config(key, value),
+                Err(err) => log::error!("skip loading config,{}", err),
+            },
+            ConfigCmd::InnerSetAndNotify(key, value) => {
+                match self.encryption.decrypt_value(&key, value) {
+                    Ok(value) => {
+                        self.inner_set_config(key.clone(), value);
+                        self.notify_change(key);
+                    }
+                    Err(err) => log::error!("skip loading config,{}", err),
+                }
             }
             ConfigCmd::InnerSetLastId(last_id) => {
                 self.sequence.set_last_id(last_id);
             }
             ConfigCmd::GET(key) => {
-                if let Some(v) = self.cache.get(&key) {
+                if let Some(data) = self.build_config_data(&key) {
+                    return Ok(data);
+                }
+            }
+            ConfigCmd::GetRaw(key) => {
+                if let Some(v) = self.get_config_value(&key) {
                     return Ok(ConfigResult::Data {
                         value: v.content.clone(),
                         md5: v.md5.clone(),
@@ -799,6 +1844,39 @@ impl Handler<ConfigCmd> for ConfigActor {
                     });
                 }
             }
+            ConfigCmd::GetByClient(key, client_id) => {
+                if let Some(data) = self.get_client_config(&key, &client_id) {
+                    return Ok(data);
+                }
+            }
+            ConfigCmd::QueryAggregate(param, client_id) => {
+                return Ok(ConfigResult::AggregateConfigs(
+                    self.query_aggregate_configs(&param, &client_id),
+                ));
+            }
+            ConfigCmd::SetClientLabels(client_id, labels) => {
+                self.client_labels.insert(client_id, labels);
+            }
+            ConfigCmd::QueryPublishImpact(key, content) => {
+                return Ok(ConfigResult::PublishImpact(Box::new(
+                    self.get_publish_impact(&key, &content),
+                )));
+            }
+            ConfigCmd::QueryAttachedKeys(tenant) => {
+                return Ok(ConfigResult::AttachedKeys(
+                    self.get_attached_keys(|v| v.tenant == tenant),
+                ));
+            }
+            ConfigCmd::QueryListenerConnectionMap(tenant) => {
+                return Ok(ConfigResult::ListenerConnectionMap(
+                    self.get_listener_connection_map(&tenant),
+                ));
+            }
+            ConfigCmd::QueryConfigListenerConnections(key) => {
+                return Ok(ConfigResult::ListenerConnectionMap(
+                    self.get_config_listener_connections(&key),
+                ));
+            }
             ConfigCmd::QueryListeners(cmd) => {
                 let (total, subscribers) =
                     self.get_config_listeners(&cmd.config_key, &cmd.paginate);
@@ -807,9 +1885,11 @@ impl Handler<ConfigCmd> for ConfigActor {
             }
             ConfigCmd::Listener(items, sender, subscribe_info, time) => {
                 let mut changes = vec![];
+                //http客户端按ip匹配beta配置
+                let client_ip = Arc::new(subscribe_info.ip.clone());
                 for item in &items {
-                    if let Some(v) = self.cache.get(&item.key) {
-                        if v.md5 != item.md5 {
+                    if let Some(md5) = self.get_client_expected_md5(&item.key, Some(&client_ip)) {
+                        if md5 != item.md5 {
                             changes.push(item.key.clone());
                         }
                     } else if !item.md5.is_empty() {
@@ -834,8 +1914,12 @@ impl Handler<ConfigCmd> for ConfigActor {
             ConfigCmd::Subscribe(items, client_id) => {
                 let mut changes = vec![];
                 for item in &items {
-                    if let Some(v) = self.cache.get(&item.key) {
-                        if v.md5 != item.md5 {
+                    if let Some(beta) = self.get_client_beta_value(&item.key, &client_id) {
+                        if beta.md5 != item.md5 {
+                            changes.push(item.key.clone());
+                        }
+                    } else if let Some((_, md5, _)) = self.get_render_value(&item.key) {
+                        if md5 != item.md5 {
                             changes.push(item.key.clone());
                         }
                     } else if !item.md5.is_empty() {
@@ -851,8 +1935,38 @@ impl Handler<ConfigCmd> for ConfigActor {
                 self.subscriber.remove_subscribe(client_id, items);
             }
             ConfigCmd::RemoveSubscribeClient(client_id) => {
+                if let Some(labels) = self.client_labels.remove(&client_id) {
+                    if let (Some(instance_id), Some(keys), Some(owner_ip)) = (
+                        labels.get(CLIENT_INSTANCE_ID_LABEL),
+                        self.subscriber.get_client_keys().get(&client_id),
+                        get_connection_ip(&client_id),
+                    ) {
+                        self.subscribe_replay.save(
+                            Arc::new(instance_id.to_owned()),
+                            owner_ip,
+                            keys.keys().cloned().collect(),
+                            now_millis(),
+                        );
+                    }
+                }
                 self.subscriber.remove_client_subscribe(client_id);
             }
+            ConfigCmd::ReplaySubscribe {
+                client_id,
+                remote_keys,
+                with_content,
+            } => {
+                return Ok(self.replay_subscribe(client_id, remote_keys, with_content));
+            }
+            ConfigCmd::TakeSubscribeReplay {
+                instance_id,
+                owner_ip,
+            } => {
+                return Ok(ConfigResult::SubscribeReplayKeys(
+                    self.subscribe_replay
+                        .take(&instance_id, &owner_ip, now_millis()),
+                ));
+            }
             ConfigCmd::QueryPageInfo(config_query_param) => {
                 let (size, list) = self.get_config_info_page(config_query_param.as_ref());
                 return Ok(ConfigResult::ConfigInfoPage(size, list));
@@ -861,9 +1975,45 @@ impl Handler<ConfigCmd> for ConfigActor {
                 let (size, list) = self.get_history_info_page(query_param.as_ref());
                 return Ok(ConfigResult::ConfigHistoryInfoPage(size, list));
             }
+            ConfigCmd::GetHistory(key, id) => {
+                if let Some(item) = self
+                    .cache
+                    .get(&key)
+                    .and_then(|v| v.histories.iter().find(|e| e.id == id))
+                {
+                    return Ok(ConfigResult::ConfigHistoryInfoPage(
+                        1,
+                        vec![item.to_dto(&key)],
+                    ));
+                }
+            }
+            ConfigCmd::QueryAsOf(param, time) => {
+                let (_, keys) = self.tenant_index.query_config_page(&param);
+                let list = keys
+                    .iter()
+                    .filter_map(|key| {
+                        self.cache.get(key).map(|value| {
+                            ConfigTimeTravelUtils::resolve(key, value, time, param.query_context)
+                        })
+                    })
+                    .collect();
+                return Ok(ConfigResult::ConfigAsOfList(list));
+            }
             ConfigCmd::BuildSnapshot(writer) => {
                 self.build_snapshot(writer).ok();
             }
+            ConfigCmd::QueryNamespaceUsage => {
+                let mut usage: HashMap<Arc<String>, (u64, u64)> = HashMap::new();
+                for (key, value) in &self.cache {
+                    if value.tmp {
+                        continue;
+                    }
+                    let item = usage.entry(key.tenant.clone()).or_default();
+                    item.0 += 1;
+                    item.1 += value.content.len() as u64;
+                }
+                return Ok(ConfigResult::NamespaceUsage(usage));
+            }
         }
         Ok(ConfigResult::NULL)
     }
@@ -873,8 +2023,14 @@ impl Handler<ConfigAsyncCmd> for ConfigActor {
     type Result = ResponseActFuture<Self, anyhow::Result<ConfigResult>>;
 
     fn handle(&mut self, msg: ConfigAsyncCmd, _ctx: &mut Context<Self>) -> Self::Result {
+        if let Err(err) = self.check_write(&msg) {
+            return Box::pin(actix::fut::ready(Err(err)));
+        }
+        let writing_keys = msg.get_keys();
+        self.add_writing_keys(&writing_keys);
         let raft = self.raft.clone();
-        let history_info = if let ConfigAsyncCmd::Add { .. } = &msg {
+        let encryption = self.encryption.clone();
+        let history_info = if let ConfigAsyncCmd::Add(_) = &msg {
             match self.sequence.next_state() {
                 Ok(v) => Some(v),
                 Err(_) => None,
@@ -882,40 +2038,86 @@ impl Handler<ConfigAsyncCmd> for ConfigActor {
         } else {
             None
         };
+        let batch_history_info: Vec<(u64, Option<u64>)> =
+            if let ConfigAsyncCmd::BatchAdd(items) = &msg {
+                items
+                    .iter()
+                    .filter_map(|_| self.sequence.next_state().ok())
+                    .collect()
+            } else {
+                vec![]
+            };
+        //删除配置时同一条日志删除挂在它上面的beta与tag配置
+        let attached_keys = if let ConfigAsyncCmd::Delete(key) = &msg {
+            self.get_attached_keys(|v| v == key)
+        } else {
+            vec![]
+        };
         let fut = async move {
             match msg {
-                ConfigAsyncCmd::Add {
-                    key,
-                    value,
-                    op_user,
-                    config_type,
-                    desc,
-                } => {
-                    if let Some((history_id, history_table_id)) = history_info {
-                        let req = ClientRequest::ConfigSet {
-                            key: key.build_key(),
-                            value,
-                            config_type,
-                            desc,
-                            history_id,
-                            history_table_id,
-                            op_time: now_millis_i64(),
-                            op_user,
-                        };
-                        Self::send_raft_request(&raft, req).await.ok();
-                    }
+                ConfigAsyncCmd::Add(req) => {
+                    let (history_id, history_table_id) = history_info
+                        .ok_or_else(|| anyhow::anyhow!("build config history id error"))?;
+                    let value = encryption.encrypt(&req.config_key, req.value)?;
+                    let req = ClientRequest::ConfigSet {
+                        key: req.config_key.build_key(),
+                        value,
+                        config_type: req.config_type,
+                        desc: req.desc,
+                        history_id,
+                        history_table_id,
+                        op_time: now_millis_i64(),
+                        op_user: req.op_user,
+                    };
+                    Self::send_raft_request(&raft, req).await?;
                 }
                 ConfigAsyncCmd::Delete(key) => {
                     let req = ClientRequest::ConfigRemove {
                         key: key.build_key(),
                     };
+                    let req = if attached_keys.is_empty() {
+                        req
+                    } else {
+                        let mut list = vec![req];
+                        list.extend(
+                            attached_keys
+                                .into_iter()
+                                .map(|v| ClientRequest::ConfigRemove { key: v.build_key() }),
+                        );
+                        ClientRequest::ConfigBatch(list)
+                    };
                     Self::send_raft_request(&raft, req).await.ok();
                 }
+                ConfigAsyncCmd::BatchAdd(items) => {
+                    if batch_history_info.len() != items.len() {
+                        return Err(anyhow::anyhow!("build config history id error"));
+                    }
+                    let op_time = now_millis_i64();
+                    let mut list = Vec::with_capacity(items.len());
+                    for (item, (history_id, history_table_id)) in
+                        items.into_iter().zip(batch_history_info)
+                    {
+                        list.push(ClientRequest::ConfigSet {
+                            key: item.config_key.build_key(),
+                            value: encryption.encrypt(&item.config_key, item.value)?,
+                            config_type: item.config_type,
+                            desc: item.desc,
+                            history_id,
+                            history_table_id,
+                            op_time,
+                            op_user: item.op_user,
+                        });
+                    }
+                    Self::send_raft_request(&raft, ClientRequest::ConfigBatch(list)).await?;
+                }
             }
             Ok(ConfigResult::NULL)
         }
         .into_actor(self)
-        .map(|r, _act, _ctx| r);
+        .map(move |r, act, _ctx| {
+            act.remove_writing_keys(&writing_keys);
+            r
+        });
         Box::pin(fut)
     }
 }
@@ -924,6 +2126,12 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
     type Result = anyhow::Result<ConfigRaftResult>;
 
     fn handle(&mut self, msg: ConfigRaftCmd, _ctx: &mut Self::Context) -> Self::Result {
+        self.apply_raft_cmd(msg)
+    }
+}
+
+impl ConfigActor {
+    fn apply_raft_cmd(&mut self, msg: ConfigRaftCmd) -> anyhow::Result<ConfigRaftResult> {
         match msg {
             ConfigRaftCmd::ConfigAdd {
                 key,
@@ -936,6 +2144,14 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
                 op_user,
             } => {
                 let key: ConfigKey = (&key as &str).into();
+                let value = match self.encryption.decrypt(&key, value) {
+                    Ok(v) => v,
+                    Err(err) => {
+                        //本节点缺少密钥时跳过该配置,不影响同一批次的其它配置
+                        log::error!("skip applying config,{}", err);
+                        return Ok(ConfigRaftResult::None);
+                    }
+                };
                 let param = SetConfigParam {
                     key,
                     value,
@@ -953,6 +2169,11 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
                 let config_key: ConfigKey = (&key as &str).into();
                 self.del_config(config_key).ok();
             }
+            ConfigRaftCmd::ConfigBatch(list) => {
+                for cmd in list {
+                    self.apply_raft_cmd(cmd)?;
+                }
+            }
             ConfigRaftCmd::ApplySnaphot => {
                 //self.load_config();
             }
@@ -960,3 +2181,173 @@ impl Handler<ConfigRaftCmd> for ConfigActor {
         Ok(ConfigRaftResult::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_listener_items() {
+        //dataId%02group%02md5%01 与 dataId%02group%02md5%02tenant%01
+        let probe = "a\x02DEFAULT_GROUP\x02md5a\x01b\x02DEFAULT_GROUP\x02\x02dev\x01c\x02g\x02md5c\x02public\x01";
+        let list = ListenerItem::decode_listener_items(probe);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].key.build_key(), "a\x02DEFAULT_GROUP");
+        assert_eq!(list[0].md5.as_str(), "md5a");
+        assert_eq!(list[1].key.tenant.as_str(), "dev");
+        assert!(list[1].md5.is_empty());
+        assert!(list[2].key.tenant.is_empty());
+    }
+
+    #[test]
+    fn test_check_expected_md5() {
+        let mut actor = ConfigActor::new();
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let build_cmd = |md5: &str| {
+            let mut req = SetConfigReq::new(key.clone(), Arc::new("v2".to_owned()));
+            req.expected_md5 = Some(Arc::new(md5.to_owned()));
+            ConfigAsyncCmd::BatchAdd(vec![req])
+        };
+        //空md5表示配置不存在
+        assert!(actor.check_write(&build_cmd("")).is_ok());
+        let value = ConfigValue::new(Arc::new("v1".to_owned()));
+        let md5 = value.md5.as_ref().to_owned();
+        actor.cache.insert(key.clone(), value);
+        assert!(actor.check_write(&build_cmd("")).is_err());
+        assert!(actor.check_write(&build_cmd(&md5)).is_ok());
+        actor.add_writing_keys(std::slice::from_ref(&key));
+        let err = actor.check_write(&build_cmd(&md5)).unwrap_err();
+        assert!(is_md5_conflict(&err));
+        actor.remove_writing_keys(std::slice::from_ref(&key));
+        assert!(actor.writing_keys.is_empty());
+        assert!(actor.check_write(&build_cmd(&md5)).is_ok());
+    }
+
+    #[test]
+    fn test_check_write_freeze() {
+        let mut actor = ConfigActor::new();
+        let rules = vec![ConfigFreezeRule {
+            namespace_id: "dev".to_owned(),
+            cron: "* * * * *".to_owned(),
+            description: None,
+        }];
+        actor.cache.insert(
+            ConfigFreezeUtils::build_key(),
+            ConfigValue::new(Arc::new(serde_json::to_string(&rules).unwrap())),
+        );
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        let mut req = SetConfigReq::new(key.clone(), Arc::new("v".to_owned()));
+        assert!(actor
+            .check_write(&ConfigAsyncCmd::Add(req.clone()))
+            .is_err());
+        //tag配置按对应的配置检查
+        let tag_req = SetConfigReq::new(
+            ConfigTagUtils::build_tag_key(&key, "v2"),
+            Arc::new("v".to_owned()),
+        );
+        assert!(actor.check_write(&ConfigAsyncCmd::Add(tag_req)).is_err());
+        req.freeze_override = true;
+        assert!(actor.check_write(&ConfigAsyncCmd::Add(req)).is_ok());
+        let other = SetConfigReq::new(
+            ConfigKey::new("a", "DEFAULT_GROUP", "prod"),
+            Arc::new("v".to_owned()),
+        );
+        assert!(actor.check_write(&ConfigAsyncCmd::Add(other)).is_ok());
+    }
+
+    #[test]
+    fn test_check_write_validation() {
+        let mut actor = ConfigActor::new();
+        let key = ConfigKey::new("a.json", "DEFAULT_GROUP", "");
+        let mut req = SetConfigReq::new(key.clone(), Arc::new("{a:".to_owned()));
+        req.config_type = Some(Arc::new("json".to_owned()));
+        assert!(actor
+            .check_write(&ConfigAsyncCmd::Add(req.clone()))
+            .is_err());
+        req.skip_validation = true;
+        assert!(actor.check_write(&ConfigAsyncCmd::Add(req)).is_ok());
+        //beta配置按正式配置的类型校验
+        let mut value = ConfigValue::new(Arc::new("{}".to_owned()));
+        value.config_type = Some(Arc::new("json".to_owned()));
+        actor.cache.insert(key.clone(), value);
+        let dto = ConfigBetaDto {
+            content: Arc::new("{a:".to_owned()),
+            ..Default::default()
+        };
+        let beta_req = SetConfigReq::new(
+            ConfigBetaUtils::build_beta_key(&key),
+            Arc::new(serde_json::to_string(&dto).unwrap()),
+        );
+        assert!(actor
+            .check_write(&ConfigAsyncCmd::BatchAdd(vec![beta_req]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_attached_keys() {
+        let mut actor = ConfigActor::new();
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        let other = ConfigKey::new("b", "DEFAULT_GROUP", "dev");
+        for attached in [
+            ConfigTagUtils::build_tag_key(&key, "v1"),
+            ConfigTagUtils::build_tag_key(&key, "v2"),
+            ConfigTagUtils::build_tag_key(&other, "v1"),
+            ConfigBetaUtils::build_beta_key(&key),
+        ] {
+            actor
+                .cache
+                .insert(attached, ConfigValue::new(Arc::new("v".to_owned())));
+        }
+        assert_eq!(actor.get_attached_keys(|v| v == &key).len(), 3);
+        assert_eq!(
+            actor
+                .get_attached_keys(|v| v.tenant.as_str() == "dev")
+                .len(),
+            4
+        );
+        assert!(actor
+            .get_attached_keys(|v| v.tenant.as_str() == "prod")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_render_include() {
+        let mut actor = ConfigActor::new();
+        let part = ConfigKey::new("part", "DEFAULT_GROUP", "dev");
+        let cipher = ConfigKey::new("cipher-aes-db", "DEFAULT_GROUP", "dev");
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        for (k, content) in [
+            (&part, "x=1"),
+            (&cipher, "password=123"),
+            (&key, "${include:part}\n${include:cipher-aes-db}"),
+        ] {
+            actor
+                .cache
+                .insert(k.clone(), ConfigValue::new(Arc::new(content.to_owned())));
+        }
+        actor.update_include(&key);
+        let (content, _, _) = actor.get_render_value(&key).unwrap();
+        assert_eq!(content.as_str(), "x=1\n${include:cipher-aes-db}");
+    }
+
+    #[test]
+    fn test_get_publish_impact_targets() {
+        let mut actor = ConfigActor::new();
+        let part = ConfigKey::new("part", "DEFAULT_GROUP", "dev");
+        let a = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        let b = ConfigKey::new("b", "DEFAULT_GROUP", "dev");
+        for (k, content) in [
+            (&part, "x=1"),
+            (&a, "${include:part}"),
+            (&b, "${include:part}\n${include:a}"),
+        ] {
+            actor
+                .cache
+                .insert(k.clone(), ConfigValue::new(Arc::new(content.to_owned())));
+            actor.update_include(k);
+        }
+        //b同时直接与间接引用part,只通知一次
+        let impact = actor.get_publish_impact(&part, "x=2");
+        assert_eq!(impact.targets.len(), 3);
+    }
+}
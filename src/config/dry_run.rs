@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+use super::freeze::ConfigFreezeUtils;
+use super::utils::param_utils;
+use super::validation::ConfigValidationUtils;
+
+/// 参与比较的最大行数,超过时只返回前后内容是否变化
+const DIFF_MAX_LINES: usize = 1000;
+
+///
+/// 配置变更时会收到通知的配置及其订阅数量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigNotifyTarget {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub tenant: Arc<String>,
+    /// self:配置本身;include:引用该配置的配置;alias:通过分组别名访问的配置
+    pub reason: String,
+    /// grpc订阅的客户端数量
+    pub subscriber_count: usize,
+    /// http长轮询监听数量
+    pub listener_count: usize,
+}
+
+impl ConfigNotifyTarget {
+    pub fn new(key: &ConfigKey, reason: &str) -> Self {
+        Self {
+            data_id: key.data_id.clone(),
+            group: key.group.clone(),
+            tenant: key.tenant.clone(),
+            reason: reason.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// 配置发布预演结果,不会实际写入
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigPublishImpact {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub exists: bool,
+    pub changed: bool,
+    pub old_md5: Option<Arc<String>>,
+    pub new_md5: Arc<String>,
+    pub diff: Vec<String>,
+    pub diff_truncated: bool,
+    pub targets: Vec<ConfigNotifyTarget>,
+    pub subscriber_count: usize,
+    pub listener_count: usize,
+}
+
+impl ConfigPublishImpact {
+    pub fn add_error(&mut self, err: String) {
+        self.valid = false;
+        self.errors.push(err);
+    }
+}
+
+pub struct ConfigDiffUtils;
+
+impl ConfigDiffUtils {
+    ///
+    /// 按行比较配置内容,返回以"+ ","- ","  "开头的行;内容过大时返回None
+    pub fn line_diff(old: &str, new: &str) -> Option<Vec<String>> {
+        let a: Vec<&str> = old.lines().collect();
+        let b: Vec<&str> = new.lines().collect();
+        if a.len() > DIFF_MAX_LINES || b.len() > DIFF_MAX_LINES {
+            return None;
+        }
+        let (n, m) = (a.len(), b.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let mut result = Vec::with_capacity(n.max(m));
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if a[i] == b[j] {
+                result.push(format!("  {}", a[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                result.push(format!("- {}", a[i]));
+                i += 1;
+            } else {
+                result.push(format!("+ {}", b[j]));
+                j += 1;
+            }
+        }
+        for line in &a[i..] {
+            result.push(format!("- {}", line));
+        }
+        for line in &b[j..] {
+            result.push(format!("+ {}", line));
+        }
+        Some(result)
+    }
+}
+
+pub struct ConfigDryRunUtils;
+
+impl ConfigDryRunUtils {
+    ///
+    /// 执行发布前的校验并查询变更影响
+    pub async fn publish_dry_run(
+        app: &AppShareData,
+        key: ConfigKey,
+        content: Arc<String>,
+        config_type: Option<&str>,
+    ) -> anyhow::Result<ConfigPublishImpact> {
+        let mut errors = vec![];
+        if let Err(err) = key
+            .is_valid()
+            .and_then(|_| param_utils::check_reserved(&key, false, false))
+        {
+            errors.push(err.to_string());
+        }
+        if content.is_empty() {
+            errors.push("content is blank".to_owned());
+        } else if let Err(err) =
+            param_utils::check_content(&content, app.cluster_settings.config_max_content())
+        {
+            errors.push(err.to_string());
+        }
+        if let Some(config_type) = config_type {
+            if let Err(err) = ConfigValidationUtils::validate(config_type, &content) {
+                errors.push(err.to_string());
+            }
+        }
+        let freeze_rules = ConfigFreezeUtils::get_rules(&app.config_addr).await?;
+        let now = Utc::now().with_timezone(app.timezone_offset.as_ref());
+        if let Some(rule) = ConfigFreezeUtils::match_rule(&freeze_rules, &key.tenant, &now) {
+            errors.push(format!("config change is frozen now, rule: {}", &rule.cron));
+        }
+        let mut impact = match app
+            .config_addr
+            .send(ConfigCmd::QueryPublishImpact(key, content))
+            .await??
+        {
+            ConfigResult::PublishImpact(v) => *v,
+            _ => return Err(anyhow::anyhow!("query publish impact error")),
+        };
+        for err in errors {
+            impact.add_error(err);
+        }
+        Ok(impact)
+    }
+}
@@ -0,0 +1,324 @@
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+use crate::common::appdata::AppShareData;
+use crate::common::crypto_utils::{decode_base64, encode_base64};
+use crate::common::AppSysConfig;
+use crate::raft::cluster::model::SetConfigReq;
+use crate::utils::get_md5;
+
+use super::config_index::ConfigQueryParam;
+use super::core::{ConfigCmd, ConfigKey, ConfigResult, ConfigValue};
+use super::model::ConfigValueDO;
+use super::utils::param_utils::{is_cipher_data_id, CIPHER_PREFIX};
+
+/// 加密后内容的前缀,完整格式为 {前缀}{密钥id}:{base64(nonce+密文)}
+const ENCRYPTED_CONTENT_PREFIX: &str = "{rnacos-cipher}";
+const AES_GCM_NONCE_SIZE: usize = 12;
+const AES_256_KEY_SIZE: usize = 32;
+
+///
+/// 配置内容加密服务,对应nacos加密插件;
+/// dataId以 cipher-{algorithm_name}- 开头的配置使用对应算法;
+/// aad为配置key,密文只能在原配置下解密,避免密文被替换到其它配置
+pub trait EncryptionService: Send + Sync {
+    fn algorithm_name(&self) -> &str;
+
+    fn encrypt(&self, content: &str, aad: &[u8]) -> anyhow::Result<String>;
+
+    fn decrypt(&self, content: &str, aad: &[u8]) -> anyhow::Result<String>;
+}
+
+///
+/// 使用服务端管理的密钥进行AES-256-GCM加密;
+/// 可配置多个密钥,第一个用于加密,全部用于解密,以支持密钥轮换
+pub struct AesGcmEncryptionService {
+    keys: Vec<(String, Key<Aes256Gcm>)>,
+}
+
+impl AesGcmEncryptionService {
+    pub const ALGORITHM_NAME: &'static str = "aes";
+
+    ///
+    /// keys格式: 密钥id:base64编码的32字节密钥,多个以逗号分隔
+    pub fn new(keys: &str) -> anyhow::Result<Self> {
+        let mut list = vec![];
+        for item in keys.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            let (key_id, value) = item
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("invalid encryption key format: {}", item))?;
+            if key_id.is_empty() {
+                return Err(anyhow::anyhow!("encryption key id is empty"));
+            }
+            let bytes = decode_base64(value)?;
+            if bytes.len() != AES_256_KEY_SIZE {
+                return Err(anyhow::anyhow!(
+                    "encryption key {} must be {} bytes",
+                    key_id,
+                    AES_256_KEY_SIZE
+                ));
+            }
+            list.push((key_id.to_owned(), *Key::<Aes256Gcm>::from_slice(&bytes)));
+        }
+        if list.is_empty() {
+            return Err(anyhow::anyhow!("encryption keys is empty"));
+        }
+        Ok(Self { keys: list })
+    }
+
+    fn parse_content(content: &str) -> Option<(&str, &str)> {
+        content
+            .strip_prefix(ENCRYPTED_CONTENT_PREFIX)
+            .and_then(|v| v.split_once(':'))
+    }
+}
+
+impl EncryptionService for AesGcmEncryptionService {
+    fn algorithm_name(&self) -> &str {
+        Self::ALGORITHM_NAME
+    }
+
+    fn encrypt(&self, content: &str, aad: &[u8]) -> anyhow::Result<String> {
+        let (key_id, key) = &self.keys[0];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: content.as_bytes(),
+            aad,
+        };
+        let cipher_text = Aes256Gcm::new(key)
+            .encrypt(&nonce, payload)
+            .map_err(|e| anyhow::anyhow!("encrypt config error,{}", e))?;
+        let mut buf = nonce.to_vec();
+        buf.extend_from_slice(&cipher_text);
+        Ok(format!(
+            "{}{}:{}",
+            ENCRYPTED_CONTENT_PREFIX,
+            key_id,
+            encode_base64(&buf)
+        ))
+    }
+
+    fn decrypt(&self, content: &str, aad: &[u8]) -> anyhow::Result<String> {
+        let (key_id, value) = Self::parse_content(content)
+            .ok_or_else(|| anyhow::anyhow!("invalid encrypted content"))?;
+        let (_, key) = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .ok_or_else(|| anyhow::anyhow!("encryption key {} is not found", key_id))?;
+        let buf = decode_base64(value)?;
+        if buf.len() < AES_GCM_NONCE_SIZE {
+            return Err(anyhow::anyhow!("invalid encrypted content"));
+        }
+        let (nonce, cipher_text) = buf.split_at(AES_GCM_NONCE_SIZE);
+        let payload = Payload {
+            msg: cipher_text,
+            aad,
+        };
+        let plain = Aes256Gcm::new(key)
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|e| anyhow::anyhow!("decrypt config error,{}", e))?;
+        Ok(String::from_utf8(plain)?)
+    }
+}
+
+///
+/// 加密配置管理;配置内容在写入raft日志与镜像前加密,加载到内存时解密,
+/// 内存中保存明文,读取与md5比较对客户端透明
+#[derive(Default)]
+pub struct ConfigEncryptionManager {
+    /// 第一个为默认算法
+    services: Vec<Arc<dyn EncryptionService>>,
+}
+
+impl ConfigEncryptionManager {
+    ///
+    /// 密钥配置无效时返回错误,阻止服务以未加密状态启动
+    pub fn new(sys_config: &AppSysConfig) -> anyhow::Result<Self> {
+        let mut manager = Self::default();
+        if !sys_config.config_encryption_keys.is_empty() {
+            let service = AesGcmEncryptionService::new(&sys_config.config_encryption_keys)
+                .map_err(|e| anyhow::anyhow!("init config encryption keys error,{}", e))?;
+            manager.register(Arc::new(service));
+        }
+        Ok(manager)
+    }
+
+    pub fn register(&mut self, service: Arc<dyn EncryptionService>) {
+        self.services.push(service);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.services.is_empty()
+    }
+
+    pub fn is_encrypted(content: &str) -> bool {
+        content.starts_with(ENCRYPTED_CONTENT_PREFIX)
+    }
+
+    ///
+    /// 按dataId中的算法名选择加密服务,未匹配时使用默认算法
+    fn get_service(&self, key: &ConfigKey) -> Option<&Arc<dyn EncryptionService>> {
+        if !is_cipher_data_id(&key.data_id) {
+            return None;
+        }
+        let algorithm = key.data_id[CIPHER_PREFIX.len()..]
+            .split_once('-')
+            .map(|(v, _)| v)
+            .unwrap_or_default();
+        self.services
+            .iter()
+            .find(|v| v.algorithm_name() == algorithm)
+            .or_else(|| self.services.first())
+    }
+
+    ///
+    /// 写入前加密;不是加密配置、未配置密钥或内容已加密时原样返回
+    pub fn encrypt(&self, key: &ConfigKey, content: Arc<String>) -> anyhow::Result<Arc<String>> {
+        match self.get_service(key) {
+            Some(service) if !Self::is_encrypted(&content) => Ok(Arc::new(
+                service.encrypt(&content, key.build_key().as_bytes())?,
+            )),
+            _ => Ok(content),
+        }
+    }
+
+    ///
+    /// 加载持久化内容后解密;未配置密钥或解密失败时返回错误,不能把密文当作配置内容
+    pub fn decrypt(&self, key: &ConfigKey, content: Arc<String>) -> anyhow::Result<Arc<String>> {
+        if !Self::is_encrypted(&content) {
+            return Ok(content);
+        }
+        let service = self.get_service(key).ok_or_else(|| {
+            anyhow::anyhow!("decrypt config {} error,no encryption key", key.build_key())
+        })?;
+        let plain = service
+            .decrypt(&content, key.build_key().as_bytes())
+            .map_err(|e| anyhow::anyhow!("decrypt config {} error,{}", key.build_key(), e))?;
+        Ok(Arc::new(plain))
+    }
+
+    pub fn decrypt_value(
+        &self,
+        key: &ConfigKey,
+        mut value: ConfigValue,
+    ) -> anyhow::Result<ConfigValue> {
+        if !is_cipher_data_id(&key.data_id) {
+            return Ok(value);
+        }
+        value.content = self.decrypt(key, value.content)?;
+        value.md5 = Arc::new(get_md5(&value.content));
+        for item in value.histories.iter_mut() {
+            item.content = self.decrypt(key, item.content.clone())?;
+        }
+        Ok(value)
+    }
+
+    ///
+    /// 写入raft镜像前加密配置内容与历史记录
+    pub fn encrypt_value_do(
+        &self,
+        key: &ConfigKey,
+        value: &mut ConfigValueDO,
+    ) -> anyhow::Result<()> {
+        let service = match self.get_service(key) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let contents = std::iter::once(&mut value.content)
+            .chain(value.histories.iter_mut().map(|e| &mut e.content));
+        for content in contents.flatten() {
+            if !Self::is_encrypted(content) {
+                *content = service.encrypt(content, key.build_key().as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// 密钥轮换:把全部加密配置按当前密钥重新写入raft日志;
+    /// 旧密钥需保留到下一次raft镜像压缩日志后再移除
+    pub async fn reencrypt_all(
+        app: &AppShareData,
+        op_user: Option<Arc<String>>,
+    ) -> anyhow::Result<usize> {
+        let param = ConfigQueryParam {
+            like_data_id: Some(CIPHER_PREFIX.to_owned()),
+            query_context: true,
+            limit: 0xffff_ffff,
+            ..Default::default()
+        };
+        let list = match app
+            .config_addr
+            .send(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await??
+        {
+            ConfigResult::ConfigInfoPage(_, list) => list,
+            _ => vec![],
+        };
+        let mut count = 0;
+        for item in list {
+            if !is_cipher_data_id(&item.data_id) {
+                continue;
+            }
+            let key = ConfigKey::new_by_arc(item.data_id, item.group, item.tenant);
+            let mut req = SetConfigReq::new(key, item.content.unwrap_or_default());
+            req.op_user = op_user.clone();
+            app.config_route.set_config(req).await?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_encryption() {
+        let old_key = encode_base64(&[1u8; 32]);
+        let new_key = encode_base64(&[2u8; 32]);
+        let old_service = AesGcmEncryptionService::new(&format!("k1:{}", &old_key)).unwrap();
+        let aad = b"cipher-aes-db.properties";
+        let encrypted = old_service.encrypt("password=123", aad).unwrap();
+        assert!(ConfigEncryptionManager::is_encrypted(&encrypted));
+        assert_eq!(
+            old_service.decrypt(&encrypted, aad).unwrap(),
+            "password=123"
+        );
+        //密文与配置key绑定
+        assert!(old_service.decrypt(&encrypted, b"other").is_err());
+
+        //轮换后新密钥加密,旧密钥仍可解密
+        let service =
+            AesGcmEncryptionService::new(&format!("k2:{},k1:{}", &new_key, &old_key)).unwrap();
+        assert_eq!(service.decrypt(&encrypted, aad).unwrap(), "password=123");
+        assert!(service.encrypt("a", aad).unwrap().contains("}k2:"));
+
+        let mut manager = ConfigEncryptionManager::default();
+        manager.register(Arc::new(service));
+        let key = ConfigKey::new("cipher-aes-db.properties", "DEFAULT_GROUP", "");
+        let content = manager.encrypt(&key, Arc::new("a=1".to_owned())).unwrap();
+        assert_ne!(content.as_str(), "a=1");
+        assert_eq!(
+            manager.decrypt(&key, content.clone()).unwrap().as_str(),
+            "a=1"
+        );
+        let other_key = ConfigKey::new("cipher-aes-other.properties", "DEFAULT_GROUP", "");
+        assert!(manager.decrypt(&other_key, content).is_err());
+        let plain_key = ConfigKey::new("db.properties", "DEFAULT_GROUP", "");
+        let content = manager
+            .encrypt(&plain_key, Arc::new("a=1".to_owned()))
+            .unwrap();
+        assert_eq!(content.as_str(), "a=1");
+        assert!(AesGcmEncryptionService::new("k1:abc").is_err());
+        let sys_config = AppSysConfig {
+            config_encryption_keys: "k1:abc".to_owned(),
+            ..Default::default()
+        };
+        assert!(ConfigEncryptionManager::new(&sys_config).is_err());
+    }
+}
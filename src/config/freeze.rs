@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use actix::Addr;
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::raft::cluster::model::SetConfigReq;
+
+use super::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult};
+
+/// 配置变更冻结规则在系统配置中的key
+pub const SYSCONFIG_CONFIG_FREEZE_KEY: &str = "config_freeze_rules";
+
+///
+/// 配置变更冻结规则;当前时间匹配cron表达式时,命名空间下的配置禁止发布与删除
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFreezeRule {
+    /// 为空时对所有命名空间生效
+    #[serde(default)]
+    pub namespace_id: String,
+    /// 分 时 日 月 周,支持 * , - /;周的取值为0-6,0表示周日
+    pub cron: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CronField {
+    values: Vec<bool>,
+    min: u32,
+    /// 以*开头的字段不限制取值
+    wildcard: bool,
+}
+
+impl CronField {
+    fn parse(value: &str, min: u32, max: u32) -> anyhow::Result<Self> {
+        let mut values = vec![false; (max - min + 1) as usize];
+        for item in value.split(',') {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>()?),
+                None => (item, 1),
+            };
+            if step == 0 {
+                return Err(anyhow::anyhow!("cron step is 0: {}", item));
+            }
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((start, end)) = range.split_once('-') {
+                (start.parse::<u32>()?, end.parse::<u32>()?)
+            } else {
+                let v = range.parse::<u32>()?;
+                (v, v)
+            };
+            if start < min || end > max || start > end {
+                return Err(anyhow::anyhow!("cron value out of range: {}", item));
+            }
+            for v in (start..=end).step_by(step as usize) {
+                values[(v - min) as usize] = true;
+            }
+        }
+        Ok(Self {
+            values,
+            min,
+            wildcard: value.starts_with('*'),
+        })
+    }
+
+    fn is_match(&self, v: u32) -> bool {
+        v >= self.min
+            && self
+                .values
+                .get((v - self.min) as usize)
+                .cloned()
+                .unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minute: CronField,
+    hour: CronField,
+    day: CronField,
+    month: CronField,
+    weekday: CronField,
+}
+
+impl CronExpr {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(anyhow::anyhow!(
+                "cron must have 5 fields(minute hour day month weekday): {}",
+                value
+            ));
+        }
+        Ok(Self {
+            minute: CronField::parse(fields[0], 0, 59)?,
+            hour: CronField::parse(fields[1], 0, 23)?,
+            day: CronField::parse(fields[2], 1, 31)?,
+            month: CronField::parse(fields[3], 1, 12)?,
+            weekday: CronField::parse(fields[4], 0, 6)?,
+        })
+    }
+
+    ///
+    /// 与标准cron一致,日与周都有限制时满足其中一个即可
+    pub fn is_match(&self, time: &DateTime<FixedOffset>) -> bool {
+        let day_match = self.day.is_match(time.day());
+        let weekday_match = self.weekday.is_match(time.weekday().num_days_from_sunday());
+        let day_match = if !self.day.wildcard && !self.weekday.wildcard {
+            day_match || weekday_match
+        } else {
+            day_match && weekday_match
+        };
+        self.minute.is_match(time.minute())
+            && self.hour.is_match(time.hour())
+            && self.month.is_match(time.month())
+            && day_match
+    }
+}
+
+pub struct ConfigFreezeUtils;
+
+impl ConfigFreezeUtils {
+    pub(crate) fn build_key() -> ConfigKey {
+        ConfigKey::new(
+            SYSCONFIG_CONFIG_FREEZE_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        )
+    }
+
+    pub async fn get_rules(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<ConfigFreezeRule>> {
+        match config_addr
+            .send(ConfigCmd::GET(Self::build_key()))
+            .await??
+        {
+            ConfigResult::Data { value, .. } if !value.is_empty() => {
+                Ok(serde_json::from_str(&value)?)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    pub(crate) fn parse_rules(value: &str) -> Vec<ConfigFreezeRule> {
+        if value.is_empty() {
+            return vec![];
+        }
+        match serde_json::from_str(value) {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("parse config freeze rules error,{}", err);
+                vec![]
+            }
+        }
+    }
+
+    pub async fn set_rules(app: &AppShareData, rules: Vec<ConfigFreezeRule>) -> anyhow::Result<()> {
+        for rule in &rules {
+            CronExpr::parse(&rule.cron)?;
+        }
+        let req = SetConfigReq::new(Self::build_key(), Arc::new(serde_json::to_string(&rules)?));
+        app.config_route.set_config(req).await?;
+        Ok(())
+    }
+
+    ///
+    /// 返回当前生效的冻结规则
+    pub fn match_rule<'a>(
+        rules: &'a [ConfigFreezeRule],
+        tenant: &str,
+        time: &DateTime<FixedOffset>,
+    ) -> Option<&'a ConfigFreezeRule> {
+        rules.iter().find(|rule| {
+            (rule.namespace_id.is_empty() || rule.namespace_id == tenant)
+                && CronExpr::parse(&rule.cron)
+                    .map(|e| e.is_match(time))
+                    .unwrap_or(false)
+        })
+    }
+
+    ///
+    /// 检查配置是否处于变更冻结期;允许强制变更时只记录日志
+    pub async fn check(
+        app: &AppShareData,
+        key: &ConfigKey,
+        operator: &str,
+        allow_override: bool,
+    ) -> anyhow::Result<()> {
+        if key.tenant.as_str() == SYSCONFIG_NAMESPACE {
+            return Ok(());
+        }
+        let rules = Self::get_rules(&app.config_addr).await?;
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now().with_timezone(app.timezone_offset.as_ref());
+        Self::check_rules(&rules, key, operator, allow_override, &now)
+    }
+
+    ///
+    /// 按冻结规则检查配置在指定时间能否变更
+    pub fn check_rules(
+        rules: &[ConfigFreezeRule],
+        key: &ConfigKey,
+        operator: &str,
+        allow_override: bool,
+        now: &DateTime<FixedOffset>,
+    ) -> anyhow::Result<()> {
+        if key.tenant.as_str() == SYSCONFIG_NAMESPACE {
+            return Ok(());
+        }
+        if let Some(rule) = Self::match_rule(rules, &key.tenant, now) {
+            if allow_override {
+                log::warn!(
+                    "config change in freeze window is overridden,operator:{},key:{}#{}#{},rule:{}",
+                    operator,
+                    &key.tenant,
+                    &key.group,
+                    &key.data_id,
+                    &rule.cron
+                );
+                return Ok(());
+            }
+            log::warn!(
+                "config change is rejected in freeze window,operator:{},key:{}#{}#{},rule:{}",
+                operator,
+                &key.tenant,
+                &key.group,
+                &key.data_id,
+                &rule.cron
+            );
+            return Err(anyhow::anyhow!(
+                "config change is frozen now, rule: {} {}",
+                &rule.cron,
+                rule.description.as_deref().unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_cron_expr() {
+        let offset = FixedOffset::east_opt(8 * 3600).unwrap();
+        // 2024-01-06 是周六
+        let saturday = offset.with_ymd_and_hms(2024, 1, 6, 10, 30, 0).unwrap();
+        let monday = offset.with_ymd_and_hms(2024, 1, 8, 10, 30, 0).unwrap();
+        let weekend = CronExpr::parse("* * * * 0,6").unwrap();
+        assert!(weekend.is_match(&saturday));
+        assert!(!weekend.is_match(&monday));
+        let work_hours = CronExpr::parse("*/15 9-18 * 1-6 1-5").unwrap();
+        assert!(work_hours.is_match(&monday));
+        assert!(!work_hours.is_match(&saturday));
+        //日与周都有限制时满足其中一个即可
+        let first_or_monday = CronExpr::parse("* * 1 * 1").unwrap();
+        assert!(first_or_monday.is_match(&monday));
+        assert!(!first_or_monday.is_match(&saturday));
+        let first_day = offset.with_ymd_and_hms(2024, 2, 1, 10, 30, 0).unwrap();
+        assert!(first_or_monday.is_match(&first_day));
+        assert!(CronExpr::parse("* 24 * * *").is_err());
+        assert!(CronExpr::parse("* * *").is_err());
+    }
+
+    #[test]
+    fn test_freeze_window_boundary() {
+        let offset = FixedOffset::east_opt(8 * 3600).unwrap();
+        let rules = vec![ConfigFreezeRule {
+            namespace_id: "prod".to_owned(),
+            // 周五 22:00 - 23:59 冻结
+            cron: "* 22-23 * * 5".to_owned(),
+            description: None,
+        }];
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "prod");
+        let check = |time: DateTime<FixedOffset>| {
+            ConfigFreezeUtils::check_rules(&rules, &key, "admin", false, &time).is_ok()
+        };
+        // 2024-01-05 是周五
+        assert!(check(
+            offset.with_ymd_and_hms(2024, 1, 5, 21, 59, 59).unwrap()
+        ));
+        assert!(!check(
+            offset.with_ymd_and_hms(2024, 1, 5, 22, 0, 0).unwrap()
+        ));
+        assert!(!check(
+            offset.with_ymd_and_hms(2024, 1, 5, 23, 59, 59).unwrap()
+        ));
+        assert!(check(offset.with_ymd_and_hms(2024, 1, 6, 0, 0, 0).unwrap()));
+        //按配置的时区计算,同一时刻在UTC下还未进入冻结期
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let time = offset.with_ymd_and_hms(2024, 1, 5, 22, 0, 0).unwrap();
+        assert!(check(time.with_timezone(&utc)));
+        //只对规则的命名空间生效,允许强制变更时放行
+        let time = offset.with_ymd_and_hms(2024, 1, 5, 22, 0, 0).unwrap();
+        let other_key = ConfigKey::new("a", "DEFAULT_GROUP", "dev");
+        assert!(ConfigFreezeUtils::check_rules(&rules, &other_key, "admin", false, &time).is_ok());
+        assert!(ConfigFreezeUtils::check_rules(&rules, &key, "admin", true, &time).is_ok());
+    }
+
+    #[test]
+    fn test_freeze_window_step_boundary() {
+        let offset = FixedOffset::east_opt(8 * 3600).unwrap();
+        // 每小时的 0-4 分与 30-34 分
+        let expr = CronExpr::parse("0-4,30-34 * * * *").unwrap();
+        let at = |minute: u32| offset.with_ymd_and_hms(2024, 1, 8, 10, minute, 0).unwrap();
+        assert!(expr.is_match(&at(0)));
+        assert!(expr.is_match(&at(4)));
+        assert!(!expr.is_match(&at(5)));
+        assert!(!expr.is_match(&at(29)));
+        assert!(expr.is_match(&at(30)));
+        assert!(!expr.is_match(&at(35)));
+        let expr = CronExpr::parse("10-50/20 * * * *").unwrap();
+        assert!(!expr.is_match(&at(9)));
+        assert!(expr.is_match(&at(10)));
+        assert!(expr.is_match(&at(30)));
+        assert!(expr.is_match(&at(50)));
+        assert!(!expr.is_match(&at(51)));
+    }
+}
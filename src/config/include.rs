@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use regex::Regex;
+
+use super::core::ConfigKey;
+
+/// 引用片段的最大嵌套层级
+pub const MAX_INCLUDE_DEPTH: usize = 3;
+
+lazy_static::lazy_static! {
+    /// 引用指令: ${include:dataId} 或 ${include:group/dataId},只能引用同一命名空间的配置
+    static ref INCLUDE_REGEX: Regex = Regex::new(r"\$\{include:([^}/]+/)?([^}]+)\}").unwrap();
+}
+
+pub struct ConfigIncludeUtils;
+
+impl ConfigIncludeUtils {
+    fn build_key(key: &ConfigKey, group: Option<&str>, data_id: &str) -> ConfigKey {
+        let group = group
+            .map(|e| Arc::new(e.trim_end_matches('/').to_owned()))
+            .unwrap_or(key.group.clone());
+        ConfigKey::new_by_arc(
+            Arc::new(data_id.trim().to_owned()),
+            group,
+            key.tenant.clone(),
+        )
+    }
+
+    ///
+    /// 解析配置内容引用的片段
+    pub fn parse_includes(key: &ConfigKey, content: &str) -> Vec<ConfigKey> {
+        if !content.contains("${include:") {
+            return vec![];
+        }
+        let mut list = vec![];
+        for cap in INCLUDE_REGEX.captures_iter(content) {
+            let group = cap.get(1).map(|e| e.as_str());
+            let data_id = cap.get(2).map(|e| e.as_str()).unwrap_or_default();
+            let include_key = Self::build_key(key, group, data_id);
+            if &include_key != key && !list.contains(&include_key) {
+                list.push(include_key);
+            }
+        }
+        list
+    }
+
+    ///
+    /// 渲染引用片段;片段不存在或超过嵌套层级时保留原指令
+    pub fn render<F>(key: &ConfigKey, content: &str, get_content: &F) -> String
+    where
+        F: Fn(&ConfigKey) -> Option<Arc<String>>,
+    {
+        Self::render_with_depth(key, content, get_content, 0)
+    }
+
+    fn render_with_depth<F>(key: &ConfigKey, content: &str, get_content: &F, depth: usize) -> String
+    where
+        F: Fn(&ConfigKey) -> Option<Arc<String>>,
+    {
+        if depth >= MAX_INCLUDE_DEPTH || !content.contains("${include:") {
+            return content.to_owned();
+        }
+        INCLUDE_REGEX
+            .replace_all(content, |cap: &regex::Captures| {
+                let group = cap.get(1).map(|e| e.as_str());
+                let data_id = cap.get(2).map(|e| e.as_str()).unwrap_or_default();
+                let include_key = Self::build_key(key, group, data_id);
+                if &include_key == key {
+                    return cap[0].to_owned();
+                }
+                match get_content(&include_key) {
+                    Some(v) => Self::render_with_depth(&include_key, &v, get_content, depth + 1),
+                    None => cap[0].to_owned(),
+                }
+            })
+            .into_owned()
+    }
+}
@@ -1,3 +1,5 @@
+pub mod beta;
+pub mod config_clone;
 pub mod config_db;
 pub mod config_index;
 pub mod config_sled;
@@ -5,9 +7,21 @@ pub mod config_subscribe;
 pub mod config_type;
 pub mod core;
 pub mod dal;
+pub mod dry_run;
+pub mod encryption;
+pub mod freeze;
+pub mod include;
 pub mod metrics;
 pub mod model;
+pub mod nacos_zip;
+pub mod rollback;
+pub mod schedule;
+pub mod subscribe_replay;
+pub mod tag;
+pub mod template;
+pub mod time_travel;
 pub mod utils;
+pub mod validation;
 
 pub struct ConfigUtils;
 
@@ -1,5 +1,7 @@
+use crate::common::schema_version::{SchemaVersionUtils, VersionedRecord};
 use crate::config::config_type::ConfigType;
 use crate::config::core::{ConfigHistoryInfoDto, ConfigKey, ConfigValue};
+use crate::raft::store::ClientRequest;
 use crate::utils::get_md5;
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -21,9 +23,44 @@ pub enum ConfigRaftCmd {
     ConfigRemove {
         key: String,
     },
+    /// 同一条raft日志中的多个配置变更,按顺序应用
+    ConfigBatch(Vec<ConfigRaftCmd>),
     ApplySnaphot,
 }
 
+impl ConfigRaftCmd {
+    pub fn new_batch(items: Vec<ClientRequest>) -> Self {
+        let mut list = Vec::with_capacity(items.len());
+        for item in items {
+            match item {
+                ClientRequest::ConfigSet {
+                    key,
+                    value,
+                    config_type,
+                    desc,
+                    history_id,
+                    history_table_id,
+                    op_time,
+                    op_user,
+                } => list.push(Self::ConfigAdd {
+                    key,
+                    value,
+                    config_type,
+                    desc,
+                    history_id,
+                    history_table_id,
+                    op_time,
+                    op_user,
+                }),
+                ClientRequest::ConfigRemove { key } => list.push(Self::ConfigRemove { key }),
+                ClientRequest::ConfigBatch(items) => list.push(Self::new_batch(items)),
+                _ => log::warn!("ignore the non config request in config batch"),
+            }
+        }
+        Self::ConfigBatch(list)
+    }
+}
+
 #[derive(Debug)]
 pub struct SetConfigParam {
     pub key: ConfigKey,
@@ -61,6 +98,7 @@ impl HistoryItem {
             data_id: Some(key.data_id.to_string()),
             content: Some(self.content.to_string()),
             modified_time: Some(self.modified_time),
+            op_user: self.op_user.as_ref().map(|v| v.as_ref().to_owned()),
         }
     }
 }
@@ -108,6 +146,9 @@ pub struct ConfigValueDO {
     pub config_type: Option<String>,
     #[prost(string, optional, tag = "4")]
     pub desc: Option<String>,
+    #[prost(uint32, optional, tag = "5")]
+    #[serde(default)]
+    pub schema_version: Option<u32>,
 }
 
 impl ConfigValueDO {
@@ -120,11 +161,22 @@ impl ConfigValueDO {
 
     pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
         use prost::Message;
-        let s = Self::decode(data)?;
+        let mut s = Self::decode(data)?;
+        SchemaVersionUtils::upgrade(&mut s)?;
         Ok(s)
     }
 }
 
+impl VersionedRecord for ConfigValueDO {
+    fn schema_version(&self) -> u32 {
+        self.schema_version.unwrap_or_default()
+    }
+
+    fn set_schema_version(&mut self, version: u32) {
+        self.schema_version = Some(version);
+    }
+}
+
 impl From<ConfigValue> for ConfigValueDO {
     fn from(value: ConfigValue) -> Self {
         Self {
@@ -132,6 +184,7 @@ impl From<ConfigValue> for ConfigValueDO {
             histories: value.histories.into_iter().map(|e| e.into()).collect(),
             config_type: value.config_type.map(|e| e.as_ref().to_owned()),
             desc: value.desc.map(|e| e.as_ref().to_owned()),
+            schema_version: Some(SchemaVersionUtils::write_version()),
         }
     }
 }
@@ -159,3 +212,28 @@ impl From<ConfigValueDO> for ConfigValue {
         }
     }
 }
+
+///
+/// 客户端连接监听的配置,用于导出连接与配置的对应关系
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerConnectionDto {
+    /// grpc为连接地址,http长轮询为监听id
+    pub connection: String,
+    pub ip: String,
+    /// grpc,http
+    pub listen_type: String,
+    pub app_name: String,
+    pub configs: Vec<ListenedConfigDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenedConfigDto {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    /// 客户端持有的md5
+    pub md5: Arc<String>,
+    /// 客户端持有的内容与服务端当前内容不一致
+    pub stale: bool,
+}
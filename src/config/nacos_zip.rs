@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, Write};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::common::appdata::AppShareData;
+use crate::raft::cluster::model::SetConfigReq;
+use crate::raft::cluster::route::{ConfigRoute, CONFIG_CAS_RETRY_TIMES};
+
+use super::core::{
+    is_md5_conflict, ConfigCmd, ConfigInfoDto, ConfigKey, ConfigResult, CONFIG_MD5_CONFLICT_MSG,
+};
+use super::validation::ConfigValidationUtils;
+
+/// nacos 2.x导出包中的元数据文件
+pub const NACOS_METADATA_FILE: &str = ".metadata.yml";
+/// nacos 1.x导出包中的元数据文件,只包含appName,导入时忽略
+const NACOS_OLD_METADATA_FILE: &str = ".meta.yml";
+
+///
+/// 导入时配置已存在的处理策略,与nacos的SameConfigPolicy一致;控制台与openapi默认都为Abort
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigImportPolicy {
+    /// 存在冲突时整体不导入
+    #[default]
+    Abort,
+    Skip,
+    Overwrite,
+}
+
+impl ConfigImportPolicy {
+    pub fn new_by_value(value: &str) -> Option<Self> {
+        match value.to_uppercase().as_str() {
+            "ABORT" => Some(Self::Abort),
+            "SKIP" => Some(Self::Skip),
+            "OVERWRITE" => Some(Self::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NacosConfigMetadataItem {
+    pub group: String,
+    pub data_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub config_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NacosConfigMetadata {
+    #[serde(default)]
+    pub metadata: Vec<NacosConfigMetadataItem>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportConfigItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub content: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportItemKey {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+}
+
+///
+/// 导入结果,字段与nacos一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportResult {
+    pub succ_count: usize,
+    pub skip_count: usize,
+    pub fail_data: Vec<ConfigImportItemKey>,
+    pub skip_data: Vec<ConfigImportItemKey>,
+}
+
+///
+/// nacos导出格式的配置包: {group}/{dataId} 文件加 .metadata.yml 元数据
+pub struct NacosZipUtils;
+
+impl NacosZipUtils {
+    pub fn build_zip(list: &[ConfigInfoDto]) -> anyhow::Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .unix_permissions(0o755);
+        let mut metadata = NacosConfigMetadata::default();
+        for item in list {
+            zip.add_directory(item.group.as_str(), Default::default())
+                .ok();
+            zip.start_file(
+                format!("{}/{}", item.group.as_str(), item.data_id.as_str()),
+                options,
+            )?;
+            if let Some(content) = &item.content {
+                zip.write_all(content.as_bytes())?;
+            }
+            metadata.metadata.push(NacosConfigMetadataItem {
+                group: item.group.as_ref().to_owned(),
+                data_id: item.data_id.as_ref().to_owned(),
+                desc: item.desc.as_ref().map(|v| v.as_ref().to_owned()),
+                config_type: item.config_type.as_ref().map(|v| v.as_ref().to_owned()),
+                app_name: None,
+            });
+        }
+        zip.start_file(NACOS_METADATA_FILE, options)?;
+        zip.write_all(serde_yaml::to_string(&metadata)?.as_bytes())?;
+        Ok(zip.finish()?.into_inner())
+    }
+
+    ///
+    /// 解析配置包,返回配置列表与内容不是UTF-8的配置
+    pub fn parse_zip<R: Read + Seek>(
+        reader: R,
+    ) -> anyhow::Result<(Vec<ImportConfigItem>, Vec<ConfigImportItemKey>)> {
+        let mut archive = ZipArchive::new(reader)?;
+        let mut metadata = HashMap::new();
+        let mut list = vec![];
+        let mut invalid_list = vec![];
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let name = file.name().to_owned();
+            let mut buf = vec![];
+            file.read_to_end(&mut buf)?;
+            let content = match String::from_utf8(buf) {
+                Ok(v) => v,
+                Err(_) => {
+                    if let Some((group, data_id)) = name.split_once('/') {
+                        invalid_list.push(ConfigImportItemKey {
+                            group: Arc::new(group.to_owned()),
+                            data_id: Arc::new(data_id.to_owned()),
+                        });
+                    } else {
+                        log::warn!("ignore config import file {}", &name);
+                    }
+                    continue;
+                }
+            };
+            if name == NACOS_METADATA_FILE {
+                let value: NacosConfigMetadata = serde_yaml::from_str(&content)?;
+                for item in value.metadata {
+                    metadata.insert((item.group.clone(), item.data_id.clone()), item);
+                }
+                continue;
+            }
+            let (group, data_id) = match name.split_once('/') {
+                Some((group, data_id))
+                    if !group.is_empty() && !data_id.is_empty() && !data_id.contains('/') =>
+                {
+                    (group.to_owned(), data_id.to_owned())
+                }
+                //根目录下的 .meta.yml、.ignore 等文件
+                _ => {
+                    if name != NACOS_OLD_METADATA_FILE {
+                        log::warn!("ignore config import file {}", &name);
+                    }
+                    continue;
+                }
+            };
+            list.push(ImportConfigItem {
+                group: Arc::new(group),
+                data_id: Arc::new(data_id),
+                content: Arc::new(content),
+                config_type: None,
+                desc: None,
+            });
+        }
+        for item in list.iter_mut() {
+            let key = (
+                item.group.as_ref().to_owned(),
+                item.data_id.as_ref().to_owned(),
+            );
+            match metadata.remove(&key) {
+                Some(meta) => {
+                    item.config_type = meta.config_type.map(Arc::new);
+                    item.desc = meta.desc.map(Arc::new);
+                }
+                None => {
+                    item.config_type = SetConfigReq::detect_config_type(item.data_id.clone());
+                }
+            }
+        }
+        Ok((list, invalid_list))
+    }
+
+    async fn exists(app: &AppShareData, key: &ConfigKey) -> anyhow::Result<bool> {
+        let res = app.config_addr.send(ConfigCmd::GetRaw(key.clone())).await?;
+        Ok(matches!(res, Ok(ConfigResult::Data { .. })))
+    }
+
+    fn item_key(item: &ImportConfigItem) -> ConfigImportItemKey {
+        ConfigImportItemKey {
+            group: item.group.clone(),
+            data_id: item.data_id.clone(),
+        }
+    }
+
+    ///
+    /// 按冲突策略与配置是否已存在筛选需要写入的配置;Abort策略存在冲突时不写入任何配置
+    fn plan(
+        items: &[ImportConfigItem],
+        exists: &[bool],
+        policy: ConfigImportPolicy,
+    ) -> (Vec<usize>, ConfigImportResult) {
+        let mut result = ConfigImportResult::default();
+        let mut write_list = vec![];
+        for (i, item) in items.iter().enumerate() {
+            if !exists[i] {
+                write_list.push(i);
+            } else if policy == ConfigImportPolicy::Abort {
+                result.fail_data.push(Self::item_key(item));
+            } else {
+                result.skip_count += 1;
+                result.skip_data.push(Self::item_key(item));
+            }
+        }
+        if !result.fail_data.is_empty() {
+            write_list.clear();
+        }
+        (write_list, result)
+    }
+
+    ///
+    /// 按冲突策略导入配置,全部写入在同一条raft日志中提交;
+    /// 非Overwrite策略在写入批次中检查配置不存在,与并发写入冲突时重新筛选;
+    /// invalid_list为无法解析的配置,与格式校验失败的配置一起记录到fail_data,Abort策略下存在时不写入任何配置
+    pub async fn import(
+        app: &AppShareData,
+        tenant: Arc<String>,
+        items: Vec<ImportConfigItem>,
+        invalid_list: Vec<ConfigImportItemKey>,
+        policy: ConfigImportPolicy,
+        op_user: Option<Arc<String>>,
+    ) -> anyhow::Result<ConfigImportResult> {
+        let mut invalid_list = invalid_list;
+        //格式校验失败的配置单独记录,不影响同一批次的其它配置
+        let (items, invalid_items): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| {
+            item.config_type.as_ref().is_none_or(|config_type| {
+                ConfigValidationUtils::validate(config_type, &item.content).is_ok()
+            })
+        });
+        invalid_list.extend(invalid_items.iter().map(Self::item_key));
+        if policy == ConfigImportPolicy::Abort && !invalid_list.is_empty() {
+            return Ok(ConfigImportResult {
+                fail_data: invalid_list,
+                ..Default::default()
+            });
+        }
+        let keys: Vec<ConfigKey> = items
+            .iter()
+            .map(|item| {
+                ConfigKey::new_by_arc(item.data_id.clone(), item.group.clone(), tenant.clone())
+            })
+            .collect();
+        for i in 0..CONFIG_CAS_RETRY_TIMES {
+            let mut exists = Vec::with_capacity(keys.len());
+            for key in &keys {
+                exists
+                    .push(policy != ConfigImportPolicy::Overwrite && Self::exists(app, key).await?);
+            }
+            let (write_list, mut result) = Self::plan(&items, &exists, policy);
+            let reqs: Vec<SetConfigReq> = write_list
+                .iter()
+                .map(|&i| {
+                    let item = &items[i];
+                    let mut req = SetConfigReq::new(keys[i].clone(), item.content.clone());
+                    req.config_type = item.config_type.clone();
+                    req.desc = item.desc.clone();
+                    req.op_user = op_user.clone();
+                    if policy != ConfigImportPolicy::Overwrite {
+                        req.expected_md5 = Some(Arc::new(String::new()));
+                    }
+                    req
+                })
+                .collect();
+            match app.config_route.batch_set_config(reqs).await {
+                Ok(_) => {
+                    result.succ_count = write_list.len();
+                    result.fail_data.extend(invalid_list);
+                    return Ok(result);
+                }
+                Err(err) if is_md5_conflict(&err) => ConfigRoute::conflict_backoff(i).await,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "{}, retry times exceeded",
+            CONFIG_MD5_CONFLICT_MSG
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nacos_zip() {
+        let list = vec![ConfigInfoDto {
+            tenant: Arc::new("".to_owned()),
+            group: Arc::new("DEFAULT_GROUP".to_owned()),
+            data_id: Arc::new("app.yaml".to_owned()),
+            content: Some(Arc::new("a: 1".to_owned())),
+            config_type: Some(Arc::new("yaml".to_owned())),
+            desc: Some(Arc::new("app config".to_owned())),
+            ..Default::default()
+        }];
+        let buf = NacosZipUtils::build_zip(&list).unwrap();
+        let (items, invalid_list) = NacosZipUtils::parse_zip(Cursor::new(buf)).unwrap();
+        assert!(invalid_list.is_empty());
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].data_id.as_str(), "app.yaml");
+        assert_eq!(items[0].content.as_str(), "a: 1");
+        assert_eq!(items[0].desc.as_ref().unwrap().as_str(), "app config");
+        let metadata: NacosConfigMetadata = serde_yaml::from_str(
+            "metadata:\n- dataId: a.json\n  group: G\n  type: json\n  appName: ''\n",
+        )
+        .unwrap();
+        assert_eq!(metadata.metadata[0].config_type.as_deref(), Some("json"));
+        assert_eq!(
+            ConfigImportPolicy::new_by_value("skip"),
+            Some(ConfigImportPolicy::Skip)
+        );
+    }
+
+    #[test]
+    fn test_parse_zip_invalid_utf8() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("G/a.txt", options).unwrap();
+        zip.write_all(b"a=1").unwrap();
+        zip.start_file("G/b.bin", options).unwrap();
+        zip.write_all(&[0xff, 0xfe, 0x00]).unwrap();
+        let buf = zip.finish().unwrap().into_inner();
+        let (items, invalid_list) = NacosZipUtils::parse_zip(Cursor::new(buf)).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(invalid_list.len(), 1);
+        assert_eq!(invalid_list[0].data_id.as_str(), "b.bin");
+    }
+
+    #[test]
+    fn test_import_plan() {
+        let build_item = |data_id: &str| ImportConfigItem {
+            group: Arc::new("G".to_owned()),
+            data_id: Arc::new(data_id.to_owned()),
+            content: Arc::new("a=1".to_owned()),
+            config_type: None,
+            desc: None,
+        };
+        let items = vec![build_item("a"), build_item("b")];
+        let exists = [false, true];
+        let (write_list, result) = NacosZipUtils::plan(&items, &exists, ConfigImportPolicy::Abort);
+        assert!(write_list.is_empty());
+        assert_eq!(result.fail_data[0].data_id.as_str(), "b");
+        let (write_list, result) = NacosZipUtils::plan(&items, &exists, ConfigImportPolicy::Skip);
+        assert_eq!(write_list, vec![0]);
+        assert_eq!(result.skip_count, 1);
+        assert_eq!(ConfigImportPolicy::default(), ConfigImportPolicy::Abort);
+    }
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::config::core::ConfigHistoryInfoDto;
+use crate::raft::cluster::model::SetConfigReq;
+use crate::utils::get_md5;
+
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+use super::dry_run::ConfigDiffUtils;
+
+///
+/// 历史版本与当前内容的差异,diff为回滚后的变化(当前内容 -> 历史内容)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistoryDiff {
+    pub id: u64,
+    pub modified_time: Option<i64>,
+    pub op_user: Option<String>,
+    /// 当前配置是否存在
+    pub exists: bool,
+    pub changed: bool,
+    pub current_md5: Option<Arc<String>>,
+    pub history_md5: Arc<String>,
+    pub diff: Vec<String>,
+    pub diff_truncated: bool,
+}
+
+struct CurrentConfig {
+    content: Arc<String>,
+    md5: Arc<String>,
+    config_type: Option<Arc<String>>,
+    desc: Option<Arc<String>>,
+}
+
+pub struct ConfigRollbackUtils;
+
+impl ConfigRollbackUtils {
+    async fn get_history(
+        app: &AppShareData,
+        key: &ConfigKey,
+        id: u64,
+    ) -> anyhow::Result<ConfigHistoryInfoDto> {
+        let cmd = ConfigCmd::GetHistory(key.clone(), id);
+        if let ConfigResult::ConfigHistoryInfoPage(_, mut list) =
+            app.config_addr.send(cmd).await??
+        {
+            if let Some(v) = list.pop() {
+                return Ok(v);
+            }
+        }
+        Err(anyhow::anyhow!("config history {} is not found", id))
+    }
+
+    async fn get_current(
+        app: &AppShareData,
+        key: &ConfigKey,
+    ) -> anyhow::Result<Option<CurrentConfig>> {
+        match app
+            .config_addr
+            .send(ConfigCmd::GetRaw(key.clone()))
+            .await??
+        {
+            ConfigResult::Data {
+                value,
+                md5,
+                config_type,
+                desc,
+                ..
+            } => Ok(Some(CurrentConfig {
+                content: value,
+                md5,
+                config_type,
+                desc,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn diff(
+        app: &AppShareData,
+        key: &ConfigKey,
+        id: u64,
+    ) -> anyhow::Result<ConfigHistoryDiff> {
+        let history = Self::get_history(app, key, id).await?;
+        let history_content = history.content.unwrap_or_default();
+        let current = Self::get_current(app, key).await?;
+        let current_content = current
+            .as_ref()
+            .map(|v| v.content.as_str())
+            .unwrap_or_default();
+        let history_md5 = Arc::new(get_md5(&history_content));
+        let mut result = ConfigHistoryDiff {
+            id,
+            modified_time: history.modified_time,
+            op_user: history.op_user,
+            exists: current.is_some(),
+            current_md5: current.as_ref().map(|v| v.md5.clone()),
+            ..Default::default()
+        };
+        result.changed = result.current_md5.as_ref() != Some(&history_md5);
+        result.history_md5 = history_md5;
+        match ConfigDiffUtils::line_diff(current_content, &history_content) {
+            Some(diff) => result.diff = diff,
+            None => result.diff_truncated = true,
+        }
+        Ok(result)
+    }
+
+    ///
+    /// 构建回滚写入请求;按读取到的当前md5做compare-and-set,
+    /// 指定expected_md5时当前配置必须仍是该版本
+    fn build_rollback_req(
+        key: &ConfigKey,
+        content: Arc<String>,
+        current: Option<CurrentConfig>,
+        expected_md5: Option<Arc<String>>,
+    ) -> anyhow::Result<SetConfigReq> {
+        let current_md5 = current.as_ref().map(|v| v.md5.clone()).unwrap_or_default();
+        if let Some(expected_md5) = expected_md5 {
+            if expected_md5 != current_md5 {
+                return Err(anyhow::anyhow!(
+                    "config is changed after diff, current md5: {}",
+                    current_md5
+                ));
+            }
+        }
+        let mut req = SetConfigReq::new(key.clone(), content);
+        if let Some(current) = current {
+            req.config_type = current.config_type;
+            req.desc = current.desc;
+        }
+        req.expected_md5 = Some(current_md5);
+        Ok(req)
+    }
+
+    ///
+    /// 把配置内容回滚到历史版本,通过raft写入;保留当前的配置类型与描述
+    pub async fn rollback(
+        app: &AppShareData,
+        key: &ConfigKey,
+        id: u64,
+        expected_md5: Option<Arc<String>>,
+        op_user: Option<Arc<String>>,
+        freeze_override: bool,
+    ) -> anyhow::Result<()> {
+        let history = Self::get_history(app, key, id).await?;
+        let content = Arc::new(history.content.unwrap_or_default());
+        let current = Self::get_current(app, key).await?;
+        let mut req = Self::build_rollback_req(key, content, current, expected_md5)?;
+        req.op_user = op_user;
+        req.freeze_override = freeze_override;
+        app.config_route.set_config(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_current(content: &str) -> CurrentConfig {
+        CurrentConfig {
+            content: Arc::new(content.to_owned()),
+            md5: Arc::new(get_md5(content)),
+            config_type: Some(Arc::new("yaml".to_owned())),
+            desc: None,
+        }
+    }
+
+    #[test]
+    fn test_build_rollback_req() {
+        let key = ConfigKey::new("a", "DEFAULT_GROUP", "");
+        let content = Arc::new("a: 1".to_owned());
+        let req =
+            ConfigRollbackUtils::build_rollback_req(&key, content.clone(), None, None).unwrap();
+        assert_eq!(req.expected_md5.unwrap().as_str(), "");
+
+        let req = ConfigRollbackUtils::build_rollback_req(
+            &key,
+            content.clone(),
+            Some(build_current("a: 2")),
+            Some(Arc::new(get_md5("a: 2"))),
+        )
+        .unwrap();
+        assert_eq!(req.expected_md5.unwrap().as_str(), get_md5("a: 2"));
+        assert_eq!(req.config_type.unwrap().as_str(), "yaml");
+
+        //diff之后配置已被修改
+        assert!(ConfigRollbackUtils::build_rollback_req(
+            &key,
+            content,
+            Some(build_current("a: 3")),
+            Some(Arc::new(get_md5("a: 2"))),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use actix::Addr;
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::now_millis_i64;
+
+use super::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult};
+use super::utils::param_utils;
+use super::validation::ConfigValidationUtils;
+
+/// 定时发布的配置变更在系统配置中的key
+pub const SYSCONFIG_CONFIG_SCHEDULE_KEY: &str = "config_publish_schedules";
+
+///
+/// 待生效的配置变更;到达生效时间后由leader节点通过raft写入配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledConfigChange {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub tenant: String,
+    pub group: String,
+    pub data_id: String,
+    pub content: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+    /// 生效时间,毫秒
+    pub effective_time: i64,
+    pub op_user: Option<Arc<String>>,
+    #[serde(default)]
+    pub create_time: i64,
+}
+
+impl ScheduledConfigChange {
+    pub fn get_config_key(&self) -> ConfigKey {
+        ConfigKey::new(&self.data_id, &self.group, &self.tenant)
+    }
+}
+
+pub struct ConfigScheduleUtils;
+
+impl ConfigScheduleUtils {
+    pub(crate) fn build_key() -> ConfigKey {
+        ConfigKey::new(
+            SYSCONFIG_CONFIG_SCHEDULE_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        )
+    }
+
+    pub fn parse(value: &str) -> Vec<ScheduledConfigChange> {
+        if value.is_empty() {
+            return vec![];
+        }
+        match serde_json::from_str(value) {
+            Ok(v) => v,
+            Err(err) => {
+                log::warn!("parse config schedules error,{}", err);
+                vec![]
+            }
+        }
+    }
+
+    ///
+    /// 拆分出到达生效时间的变更,按生效时间排序
+    pub fn split_due(
+        list: Vec<ScheduledConfigChange>,
+        now: i64,
+    ) -> (Vec<ScheduledConfigChange>, Vec<ScheduledConfigChange>) {
+        let (mut due, remain): (Vec<_>, Vec<_>) =
+            list.into_iter().partition(|e| e.effective_time <= now);
+        due.sort_by_key(|e| e.effective_time);
+        (due, remain)
+    }
+
+    pub async fn query(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<ScheduledConfigChange>> {
+        match config_addr
+            .send(ConfigCmd::GetRaw(Self::build_key()))
+            .await??
+        {
+            ConfigResult::Data { value, .. } => {
+                let mut list = Self::parse(&value);
+                list.sort_by_key(|e| e.effective_time);
+                Ok(list)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    ///
+    /// 新增定时发布;加密配置的明文不能存放在系统配置中,不支持定时发布
+    pub async fn add(
+        app: &AppShareData,
+        mut item: ScheduledConfigChange,
+    ) -> anyhow::Result<ScheduledConfigChange> {
+        param_utils::check_tenant(&Some(item.tenant.clone()))?;
+        let key = item.get_config_key();
+        key.is_valid()?;
+        param_utils::check_reserved(&key, false, false)?;
+        param_utils::check_content(&item.content, app.cluster_settings.config_max_content())?;
+        if param_utils::is_cipher_data_id(&item.data_id) {
+            return Err(anyhow::anyhow!("can't schedule the cipher configs"));
+        }
+        if let Some(config_type) = &item.config_type {
+            ConfigValidationUtils::validate(config_type, &item.content)?;
+        }
+        let now = now_millis_i64();
+        if item.effective_time <= now {
+            return Err(anyhow::anyhow!("effective time must be later than now"));
+        }
+        item.id = uuid::Uuid::new_v4().to_string().replace('-', "");
+        item.create_time = now;
+        app.config_route
+            .update_config(&Self::build_key(), |value| {
+                let mut list = Self::parse(value);
+                list.push(item.clone());
+                Ok(Some(serde_json::to_string(&list)?))
+            })
+            .await?;
+        Ok(item)
+    }
+
+    ///
+    /// 取消尚未生效的定时发布,返回被取消的变更;不存在或已生效时返回None
+    pub async fn cancel(
+        app: &AppShareData,
+        id: &str,
+    ) -> anyhow::Result<Option<ScheduledConfigChange>> {
+        let mut canceled = None;
+        app.config_route
+            .update_config(&Self::build_key(), |value| {
+                let mut list = Self::parse(value);
+                canceled = list.iter().position(|e| e.id == id).map(|i| list.remove(i));
+                if canceled.is_none() {
+                    return Ok(None);
+                }
+                Ok(Some(serde_json::to_string(&list)?))
+            })
+            .await?;
+        Ok(canceled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_item(id: &str, effective_time: i64) -> ScheduledConfigChange {
+        ScheduledConfigChange {
+            id: id.to_owned(),
+            group: "DEFAULT_GROUP".to_owned(),
+            data_id: "app.yaml".to_owned(),
+            content: Arc::new("a=1".to_owned()),
+            effective_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_due() {
+        let list = vec![
+            build_item("a", 3000),
+            build_item("b", 1000),
+            build_item("c", 2000),
+        ];
+        let (due, remain) = ConfigScheduleUtils::split_due(list, 2000);
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].id, "b");
+        assert_eq!(remain[0].id, "a");
+
+        let value = serde_json::to_string(&due).unwrap();
+        assert_eq!(ConfigScheduleUtils::parse(&value).len(), 2);
+        assert!(ConfigScheduleUtils::parse("").is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::common::appdata::AppShareData;
+use crate::grpc::handler::RAFT_ROUTE_REQUEST;
+use crate::grpc::PayloadUtils;
+use crate::raft::cluster::model::{RouterRequest, RouterResponse};
+
+use super::core::ConfigKey;
+
+/// 客户端在ConnectionSetupRequest标签中上报的稳定标识,重连后按该标识恢复配置订阅
+pub const CLIENT_INSTANCE_ID_LABEL: &str = "rnacos.clientId";
+
+#[derive(Debug, Clone)]
+pub struct ConfigReplayItem {
+    pub key: ConfigKey,
+    /// 配置已删除时为空
+    pub md5: Option<Arc<String>>,
+    pub content: Option<Arc<String>>,
+}
+
+#[derive(Debug)]
+struct ReplayRecord {
+    /// 断开连接的客户端ip,只有同一ip的连接可以取回
+    owner_ip: String,
+    keys: Vec<ConfigKey>,
+    expire_time: u64,
+}
+
+///
+/// 断开连接的客户端的订阅记录,保留一段时间;
+/// 客户端重连后一次性取回订阅配置的当前md5,避免逐个重新监听
+#[derive(Debug, Default)]
+pub struct SubscribeReplayMemory {
+    pub ttl_millis: u64,
+    /// 客户端稳定标识 -> 订阅记录
+    records: HashMap<Arc<String>, ReplayRecord>,
+}
+
+impl SubscribeReplayMemory {
+    pub fn new(ttl_millis: u64) -> Self {
+        Self {
+            ttl_millis,
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn save(
+        &mut self,
+        client_id: Arc<String>,
+        owner_ip: String,
+        keys: Vec<ConfigKey>,
+        now: u64,
+    ) {
+        if self.ttl_millis == 0 || keys.is_empty() {
+            return;
+        }
+        let record = ReplayRecord {
+            owner_ip,
+            keys,
+            expire_time: now.saturating_add(self.ttl_millis),
+        };
+        self.records.insert(client_id, record);
+    }
+
+    ///
+    /// 取回订阅记录;客户端ip与断开的连接不一致时不返回,记录继续保留
+    pub fn take(
+        &mut self,
+        client_id: &Arc<String>,
+        owner_ip: &str,
+        now: u64,
+    ) -> Option<Vec<ConfigKey>> {
+        match self.records.get(client_id) {
+            Some(record) if record.owner_ip == owner_ip => {}
+            _ => return None,
+        }
+        self.records
+            .remove(client_id)
+            .filter(|record| record.expire_time > now)
+            .map(|record| record.keys)
+    }
+
+    pub fn clear_timeout(&mut self, now: u64) {
+        if self.records.is_empty() {
+            return;
+        }
+        self.records.retain(|_, record| record.expire_time > now);
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+pub struct SubscribeReplayUtils;
+
+impl SubscribeReplayUtils {
+    ///
+    /// 订阅记录保存在客户端断开时所连接的节点上,本节点没有记录时到其它节点取回
+    pub async fn take_from_other_nodes(
+        app: &AppShareData,
+        instance_id: Arc<String>,
+        owner_ip: String,
+    ) -> Option<Vec<ConfigKey>> {
+        let request = serde_json::to_string(&RouterRequest::TakeSubscribeReplay {
+            instance_id,
+            owner_ip,
+        })
+        .ok()?;
+        for node in app.naming_node_manage.get_other_valid_nodes().await.ok()? {
+            let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request.clone());
+            let resp_payload = match app
+                .cluster_sender
+                .send_request(node.addr.clone(), payload)
+                .await
+            {
+                Ok(v) => v,
+                Err(err) => {
+                    log::warn!("take subscribe replay from node {} error,{}", node.id, err);
+                    continue;
+                }
+            };
+            let body_vec = resp_payload.body.unwrap_or_default().value;
+            if let Ok(RouterResponse::SubscribeReplayKeys { keys: Some(keys) }) =
+                serde_json::from_slice(&body_vec)
+            {
+                return Some(keys.iter().map(|v| ConfigKey::from(v.as_str())).collect());
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_replay_memory() {
+        let mut memory = SubscribeReplayMemory::new(100);
+        let client_id = Arc::new("app-01".to_owned());
+        let ip = "10.0.0.1";
+        let keys = vec![ConfigKey::new("a", "DEFAULT_GROUP", "")];
+        memory.save(client_id.clone(), ip.to_owned(), keys.clone(), 10);
+        memory.save(Arc::new("app-02".to_owned()), ip.to_owned(), keys, 20);
+        //其它ip的连接不能取回
+        assert!(memory.take(&client_id, "10.0.0.2", 50).is_none());
+        assert_eq!(memory.take(&client_id, ip, 50).unwrap().len(), 1);
+        //取回后不再保留
+        assert!(memory.take(&client_id, ip, 50).is_none());
+        memory.clear_timeout(120);
+        assert!(memory.is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::config::config_index::ConfigQueryParam;
+use crate::console::SYSCONFIG_NAMESPACE;
+use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
+
+use super::core::{ConfigCmd, ConfigKey, ConfigResult};
+
+/// tag配置在系统配置中的分组
+pub const SYSCONFIG_TAG_GROUP: &str = "config_tag";
+const TAG_MAX_LENGTH: usize = 128;
+
+///
+/// 同一个配置下按tag区分的配置内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTagDto {
+    pub tag: String,
+    pub content: Option<Arc<String>>,
+    pub md5: Option<Arc<String>>,
+}
+
+pub struct ConfigTagUtils;
+
+impl ConfigTagUtils {
+    ///
+    /// 配置指定tag对应的系统配置key
+    pub fn build_tag_key(key: &ConfigKey, tag: &str) -> ConfigKey {
+        let data_id = format!("{}{}", Self::build_tag_prefix(key), tag);
+        ConfigKey::new(&data_id, SYSCONFIG_TAG_GROUP, SYSCONFIG_NAMESPACE)
+    }
+
+    fn build_tag_prefix(key: &ConfigKey) -> String {
+        format!("{}@@{}@@{}@@", &key.tenant, &key.group, &key.data_id)
+    }
+
+    pub fn check_tag(tag: &str) -> anyhow::Result<()> {
+        if tag.is_empty() || tag.len() > TAG_MAX_LENGTH || tag.contains("@@") {
+            return Err(anyhow::anyhow!("tag is invalid: {}", tag));
+        }
+        Ok(())
+    }
+
+    pub fn is_tag_key(key: &ConfigKey) -> bool {
+        key.group.as_str() == SYSCONFIG_TAG_GROUP && key.tenant.as_str() == SYSCONFIG_NAMESPACE
+    }
+
+    ///
+    /// 从tag系统配置key解析出对应的配置key与tag
+    pub fn parse_tag_key(tag_key: &ConfigKey) -> Option<(ConfigKey, String)> {
+        let (prefix, tag) = tag_key.data_id.rsplit_once("@@")?;
+        let mut iter = prefix.splitn(3, "@@");
+        let tenant = iter.next()?;
+        let group = iter.next()?;
+        let data_id = iter.next()?;
+        Some((ConfigKey::new(data_id, group, tenant), tag.to_owned()))
+    }
+
+    ///
+    /// 查询配置下的全部tag
+    pub async fn query_tags(
+        app: &AppShareData,
+        key: &ConfigKey,
+        query_context: bool,
+    ) -> anyhow::Result<Vec<ConfigTagDto>> {
+        let param = ConfigQueryParam {
+            tenant: Some(Arc::new(SYSCONFIG_NAMESPACE.to_owned())),
+            group: Some(Arc::new(SYSCONFIG_TAG_GROUP.to_owned())),
+            like_data_id: Some(Self::build_tag_prefix(key)),
+            query_context,
+            limit: 0xffff_ffff,
+            ..Default::default()
+        };
+        let mut tags = vec![];
+        if let ConfigResult::ConfigInfoPage(_, list) = app
+            .config_addr
+            .send(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await??
+        {
+            for item in list {
+                let tag_key = ConfigKey::new_by_arc(item.data_id, item.group, item.tenant);
+                if let Some((config_key, tag)) = Self::parse_tag_key(&tag_key) {
+                    if &config_key == key {
+                        tags.push(ConfigTagDto {
+                            tag,
+                            content: item.content,
+                            md5: item.md5,
+                        });
+                    }
+                }
+            }
+        }
+        tags.sort_by(|a, b| a.tag.cmp(&b.tag));
+        Ok(tags)
+    }
+
+    pub async fn publish(
+        app: &AppShareData,
+        key: &ConfigKey,
+        tag: &str,
+        content: Arc<String>,
+        config_type: Option<Arc<String>>,
+        skip_validation: bool,
+    ) -> anyhow::Result<()> {
+        Self::check_tag(tag)?;
+        let mut req = SetConfigReq::new(Self::build_tag_key(key, tag), content);
+        req.config_type = config_type;
+        req.skip_validation = skip_validation;
+        app.config_route.set_config(req).await?;
+        Ok(())
+    }
+
+    pub async fn remove(app: &AppShareData, key: &ConfigKey, tag: &str) -> anyhow::Result<()> {
+        Self::check_tag(tag)?;
+        let req = DelConfigReq::new(Self::build_tag_key(key, tag));
+        app.config_route.del_config(req).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_key() {
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "dev");
+        let tag_key = ConfigTagUtils::build_tag_key(&key, "v2");
+        assert!(ConfigTagUtils::is_tag_key(&tag_key));
+        let (config_key, tag) = ConfigTagUtils::parse_tag_key(&tag_key).unwrap();
+        assert_eq!(config_key, key);
+        assert_eq!(tag, "v2");
+        assert!(ConfigTagUtils::check_tag("a@@b").is_err());
+        assert!(ConfigTagUtils::check_tag("").is_err());
+    }
+}
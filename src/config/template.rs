@@ -0,0 +1,429 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use actix::Addr;
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::console::{NamespaceUtils, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::raft::cluster::model::SetConfigReq;
+use crate::raft::cluster::route::{ConfigRoute, CONFIG_CAS_RETRY_TIMES};
+
+use super::core::{
+    is_md5_conflict, ConfigActor, ConfigCmd, ConfigKey, ConfigResult, CONFIG_MD5_CONFLICT_MSG,
+};
+use super::utils::param_utils;
+use super::ConfigUtils;
+
+/// 配置模板在系统配置中的key
+pub const SYSCONFIG_CONFIG_TEMPLATE_KEY: &str = "config_templates";
+/// 各命名空间的模板变量在系统配置中的key
+pub const SYSCONFIG_CONFIG_TEMPLATE_VARIABLES_KEY: &str = "config_template_variables";
+const TEMPLATE_NAME_MAX_LENGTH: usize = 128;
+
+///
+/// 由模板渲染生成的配置;variables优先于所在命名空间的变量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTemplateTarget {
+    #[serde(default)]
+    pub tenant: String,
+    pub group: String,
+    pub data_id: String,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+impl ConfigTemplateTarget {
+    pub fn get_config_key(&self) -> ConfigKey {
+        ConfigKey::new(&self.data_id, &self.group, &self.tenant)
+    }
+}
+
+///
+/// 配置模板,内容中的 `${var}` 在发布时替换为变量值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTemplate {
+    pub name: String,
+    pub content: Arc<String>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+    #[serde(default)]
+    pub targets: Vec<ConfigTemplateTarget>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTemplateVariableSet {
+    #[serde(default)]
+    pub namespace_id: String,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+pub struct ConfigTemplateUtils;
+
+impl ConfigTemplateUtils {
+    pub(crate) fn build_key(data_id: &str) -> ConfigKey {
+        ConfigKey::new(data_id, SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE)
+    }
+
+    ///
+    /// 替换内容中的 `${var}`,未定义的变量返回错误
+    pub fn render(
+        content: &str,
+        variables: &HashMap<String, String>,
+        target_variables: &HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let mut result = String::with_capacity(content.len());
+        let mut missing = vec![];
+        let mut rest = content;
+        while let Some(start) = rest.find("${") {
+            let len = match rest[start + 2..].find('}') {
+                Some(v) => v,
+                None => break,
+            };
+            let name = &rest[start + 2..start + 2 + len];
+            result.push_str(&rest[..start]);
+            match target_variables.get(name).or_else(|| variables.get(name)) {
+                Some(v) => result.push_str(v),
+                None => {
+                    if !missing.contains(&name) {
+                        missing.push(name);
+                    }
+                }
+            }
+            rest = &rest[start + 3 + len..];
+        }
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!(
+                "undefined template variables: {}",
+                missing.join(",")
+            ));
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    fn parse_list<T: serde::de::DeserializeOwned>(value: &str) -> anyhow::Result<Vec<T>> {
+        if value.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(serde_json::from_str(value)?)
+    }
+
+    async fn query_list<T: serde::de::DeserializeOwned>(
+        config_addr: &Addr<ConfigActor>,
+        data_id: &str,
+    ) -> anyhow::Result<Vec<T>> {
+        match config_addr
+            .send(ConfigCmd::GetRaw(Self::build_key(data_id)))
+            .await??
+        {
+            ConfigResult::Data { value, .. } => Self::parse_list(&value),
+            _ => Ok(vec![]),
+        }
+    }
+
+    pub async fn query_templates(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<ConfigTemplate>> {
+        Self::query_list(config_addr, SYSCONFIG_CONFIG_TEMPLATE_KEY).await
+    }
+
+    pub async fn query_variable_sets(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<ConfigTemplateVariableSet>> {
+        Self::query_list(config_addr, SYSCONFIG_CONFIG_TEMPLATE_VARIABLES_KEY).await
+    }
+
+    fn check_template(template: &ConfigTemplate) -> anyhow::Result<()> {
+        if template.name.is_empty() || template.name.len() > TEMPLATE_NAME_MAX_LENGTH {
+            return Err(anyhow::anyhow!(
+                "template name is invalid: {}",
+                &template.name
+            ));
+        }
+        for target in &template.targets {
+            param_utils::check_tenant(&Some(target.tenant.clone()))?;
+            let key = target.get_config_key();
+            key.is_valid()?;
+            param_utils::check_reserved(&key, false, false)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// 一个配置只能由一个模板生成,同一模板内的目标也不能重复
+    fn check_duplicate_target(
+        templates: &[ConfigTemplate],
+        template: &ConfigTemplate,
+    ) -> anyhow::Result<()> {
+        let mut keys = HashSet::new();
+        for target in &template.targets {
+            let key = target.get_config_key();
+            if !keys.insert(key.clone()) {
+                return Err(anyhow::anyhow!(
+                    "template target is duplicated: {}#{}#{}",
+                    &key.tenant,
+                    &key.group,
+                    &key.data_id
+                ));
+            }
+        }
+        for other in templates.iter().filter(|e| e.name != template.name) {
+            if let Some(target) = other
+                .targets
+                .iter()
+                .find(|e| keys.contains(&e.get_config_key()))
+            {
+                return Err(anyhow::anyhow!(
+                    "config {}#{}#{} is already generated by template {}",
+                    &target.tenant,
+                    &target.group,
+                    &target.data_id,
+                    &other.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// 渲染模板的生成配置;filter用于只渲染受变量变更影响的命名空间
+    fn build_reqs(
+        app: &AppShareData,
+        template: &ConfigTemplate,
+        variable_sets: &[ConfigTemplateVariableSet],
+        filter_namespace: Option<&str>,
+        op_user: &Option<Arc<String>>,
+    ) -> anyhow::Result<Vec<SetConfigReq>> {
+        let empty = HashMap::new();
+        let mut reqs = vec![];
+        for target in &template.targets {
+            if let Some(namespace_id) = filter_namespace {
+                if target.tenant != namespace_id {
+                    continue;
+                }
+            }
+            let variables = variable_sets
+                .iter()
+                .find(|e| e.namespace_id == target.tenant)
+                .map(|e| &e.variables)
+                .unwrap_or(&empty);
+            let key = target.get_config_key();
+            let content =
+                Self::render(&template.content, variables, &target.variables).map_err(|e| {
+                    anyhow::anyhow!(
+                        "render {}#{}#{} error,{}",
+                        &key.tenant,
+                        &key.group,
+                        &key.data_id,
+                        e
+                    )
+                })?;
+            //变更冻结期与内容格式在写入时统一检查
+            param_utils::check_content(&content, app.cluster_settings.config_max_content())?;
+            let mut req = SetConfigReq::new(key, Arc::new(content));
+            req.config_type = template.config_type.clone();
+            req.desc = template.desc.clone();
+            req.op_user = op_user.clone();
+            reqs.push(req);
+        }
+        Ok(reqs)
+    }
+
+    ///
+    /// 模板列表或变量与渲染后的配置在同一条raft日志中写入,写入时比较列表的md5;
+    /// 期间列表被其它请求修改时返回None,由调用方重新读取后重试
+    async fn batch_set_with_md5(
+        app: &AppShareData,
+        mut reqs: Vec<SetConfigReq>,
+        data_id: &str,
+        value: String,
+        expected_md5: Arc<String>,
+    ) -> anyhow::Result<Option<Vec<ConfigKey>>> {
+        let keys: Vec<ConfigKey> = reqs.iter().map(|e| e.config_key.clone()).collect();
+        let mut sys_req = SetConfigReq::new(Self::build_key(data_id), Arc::new(value));
+        sys_req.expected_md5 = Some(expected_md5);
+        reqs.push(sys_req);
+        match app.config_route.batch_set_config(reqs).await {
+            Ok(_) => Ok(Some(keys)),
+            Err(err) if is_md5_conflict(&err) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    ///
+    /// 新增或更新模板,模板与渲染后的配置在同一条raft日志中写入;返回发布的配置
+    pub async fn set_template(
+        app: &AppShareData,
+        mut template: ConfigTemplate,
+        op_user: Option<Arc<String>>,
+        is_manager: bool,
+    ) -> anyhow::Result<Vec<ConfigKey>> {
+        for target in template.targets.iter_mut() {
+            target.tenant = ConfigUtils::default_tenant(std::mem::take(&mut target.tenant));
+            NamespaceUtils::check_config_write(&app.sys_config, &target.tenant, is_manager)?;
+        }
+        Self::check_template(&template)?;
+        let template_key = Self::build_key(SYSCONFIG_CONFIG_TEMPLATE_KEY);
+        for i in 0..CONFIG_CAS_RETRY_TIMES {
+            let (value, md5) = app.config_route.get_raw_config(&template_key).await?;
+            let mut templates: Vec<ConfigTemplate> = Self::parse_list(&value)?;
+            Self::check_duplicate_target(&templates, &template)?;
+            templates.retain(|e| e.name != template.name);
+            templates.push(template.clone());
+            templates.sort_by(|a, b| a.name.cmp(&b.name));
+            let variable_sets = Self::query_variable_sets(&app.config_addr).await?;
+            let reqs = Self::build_reqs(app, &template, &variable_sets, None, &op_user)?;
+            if let Some(keys) = Self::batch_set_with_md5(
+                app,
+                reqs,
+                SYSCONFIG_CONFIG_TEMPLATE_KEY,
+                serde_json::to_string(&templates)?,
+                md5,
+            )
+            .await?
+            {
+                return Ok(keys);
+            }
+            ConfigRoute::conflict_backoff(i).await;
+        }
+        Err(anyhow::anyhow!(
+            "{}, retry times exceeded",
+            CONFIG_MD5_CONFLICT_MSG
+        ))
+    }
+
+    ///
+    /// 删除模板,已生成的配置保留
+    pub async fn remove_template(app: &AppShareData, name: &str) -> anyhow::Result<bool> {
+        app.config_route
+            .update_config(&Self::build_key(SYSCONFIG_CONFIG_TEMPLATE_KEY), |value| {
+                let mut templates: Vec<ConfigTemplate> = Self::parse_list(value)?;
+                let len = templates.len();
+                templates.retain(|e| e.name != name);
+                if templates.len() == len {
+                    return Ok(None);
+                }
+                Ok(Some(serde_json::to_string(&templates)?))
+            })
+            .await
+    }
+
+    ///
+    /// 更新命名空间的变量,并重新发布该命名空间下由模板生成的配置;返回发布的配置
+    pub async fn set_variables(
+        app: &AppShareData,
+        mut variable_set: ConfigTemplateVariableSet,
+        op_user: Option<Arc<String>>,
+        is_manager: bool,
+    ) -> anyhow::Result<Vec<ConfigKey>> {
+        variable_set.namespace_id = ConfigUtils::default_tenant(variable_set.namespace_id);
+        param_utils::check_tenant(&Some(variable_set.namespace_id.clone()))?;
+        NamespaceUtils::check_config_write(
+            &app.sys_config,
+            &variable_set.namespace_id,
+            is_manager,
+        )?;
+        let namespace_id = variable_set.namespace_id.clone();
+        let variables_key = Self::build_key(SYSCONFIG_CONFIG_TEMPLATE_VARIABLES_KEY);
+        for i in 0..CONFIG_CAS_RETRY_TIMES {
+            let (value, md5) = app.config_route.get_raw_config(&variables_key).await?;
+            let mut variable_sets: Vec<ConfigTemplateVariableSet> = Self::parse_list(&value)?;
+            variable_sets.retain(|e| e.namespace_id != namespace_id);
+            if !variable_set.variables.is_empty() {
+                variable_sets.push(variable_set.clone());
+            }
+            variable_sets.sort_by(|a, b| a.namespace_id.cmp(&b.namespace_id));
+            let mut reqs = vec![];
+            for template in Self::query_templates(&app.config_addr).await? {
+                reqs.extend(Self::build_reqs(
+                    app,
+                    &template,
+                    &variable_sets,
+                    Some(&namespace_id),
+                    &op_user,
+                )?);
+            }
+            if let Some(keys) = Self::batch_set_with_md5(
+                app,
+                reqs,
+                SYSCONFIG_CONFIG_TEMPLATE_VARIABLES_KEY,
+                serde_json::to_string(&variable_sets)?,
+                md5,
+            )
+            .await?
+            {
+                return Ok(keys);
+            }
+            ConfigRoute::conflict_backoff(i).await;
+        }
+        Err(anyhow::anyhow!(
+            "{}, retry times exceeded",
+            CONFIG_MD5_CONFLICT_MSG
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut variables = HashMap::new();
+        variables.insert("env".to_owned(), "prod".to_owned());
+        variables.insert("port".to_owned(), "80".to_owned());
+        let mut target_variables = HashMap::new();
+        target_variables.insert("port".to_owned(), "8080".to_owned());
+        let content = "env=${env}\nport=${port}\nkeep=${unclosed";
+        assert_eq!(
+            ConfigTemplateUtils::render(content, &variables, &target_variables).unwrap(),
+            "env=prod\nport=8080\nkeep=${unclosed"
+        );
+        let err = ConfigTemplateUtils::render("${a}${b}${a}", &variables, &target_variables)
+            .unwrap_err()
+            .to_string();
+        assert!(err.ends_with("a,b"));
+    }
+
+    #[test]
+    fn test_check_duplicate_target() {
+        let build_template = |name: &str, data_ids: &[&str]| ConfigTemplate {
+            name: name.to_owned(),
+            targets: data_ids
+                .iter()
+                .map(|data_id| ConfigTemplateTarget {
+                    group: "DEFAULT_GROUP".to_owned(),
+                    data_id: data_id.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let templates = vec![build_template("a", &["a.yaml", "b.yaml"])];
+        assert!(ConfigTemplateUtils::check_duplicate_target(
+            &templates,
+            &build_template("a", &["a.yaml"])
+        )
+        .is_ok());
+        assert!(ConfigTemplateUtils::check_duplicate_target(
+            &templates,
+            &build_template("b", &["c.yaml"])
+        )
+        .is_ok());
+        assert!(ConfigTemplateUtils::check_duplicate_target(
+            &templates,
+            &build_template("b", &["b.yaml"])
+        )
+        .is_err());
+        assert!(ConfigTemplateUtils::check_duplicate_target(
+            &[],
+            &build_template("b", &["c.yaml", "c.yaml"])
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use chrono::{FixedOffset, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::get_md5;
+
+use super::core::{ConfigKey, ConfigValue, CONFIG_HISTORY_MAX_SIZE};
+
+const DATETIME_FMT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigAsOfStatus {
+    /// 该时间点生效的版本
+    Live,
+    /// 该时间点配置还未创建
+    NotCreated,
+    /// 该时间点之前的历史已被淘汰,无法确定
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAsOfItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    pub status: ConfigAsOfStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified_time: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub op_user: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Arc<String>>,
+}
+
+impl ConfigAsOfItem {
+    fn new(key: &ConfigKey, status: ConfigAsOfStatus) -> Self {
+        Self {
+            group: key.group.clone(),
+            data_id: key.data_id.clone(),
+            status,
+            history_id: None,
+            modified_time: None,
+            op_user: None,
+            md5: None,
+            content: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAsOfReport {
+    pub tenant: String,
+    pub time: i64,
+    pub live_count: usize,
+    pub unknown_count: usize,
+    pub list: Vec<ConfigAsOfItem>,
+}
+
+impl ConfigAsOfReport {
+    pub fn new(tenant: String, time: i64, list: Vec<ConfigAsOfItem>) -> Self {
+        let count = |status| list.iter().filter(|e| e.status == status).count();
+        Self {
+            tenant,
+            time,
+            live_count: count(ConfigAsOfStatus::Live),
+            unknown_count: count(ConfigAsOfStatus::Unknown),
+            list,
+        }
+    }
+}
+
+///
+/// 按配置的历史记录还原某个时间点生效的配置;
+/// 已删除配置的历史不保留,不在结果中
+pub struct ConfigTimeTravelUtils;
+
+impl ConfigTimeTravelUtils {
+    ///
+    /// 支持毫秒时间戳,或按服务时区解析的 `yyyy-MM-dd HH:mm:ss`
+    pub fn parse_time(value: &str, offset: &FixedOffset) -> anyhow::Result<i64> {
+        let value = value.trim();
+        if let Ok(v) = value.parse::<i64>() {
+            return Ok(v);
+        }
+        let datetime = NaiveDateTime::parse_from_str(value, DATETIME_FMT)
+            .map_err(|_| anyhow::anyhow!("invalid time {}", value))?;
+        offset
+            .from_local_datetime(&datetime)
+            .single()
+            .map(|v| v.timestamp_millis())
+            .ok_or_else(|| anyhow::anyhow!("invalid time {}", value))
+    }
+
+    pub(crate) fn resolve(
+        key: &ConfigKey,
+        value: &ConfigValue,
+        time: i64,
+        with_content: bool,
+    ) -> ConfigAsOfItem {
+        if let Some(history) = value
+            .histories
+            .iter()
+            .rev()
+            .find(|e| e.modified_time <= time)
+        {
+            let mut item = ConfigAsOfItem::new(key, ConfigAsOfStatus::Live);
+            item.history_id = Some(history.id);
+            item.modified_time = Some(history.modified_time);
+            item.op_user = history.op_user.clone();
+            item.md5 = Some(Arc::new(get_md5(&history.content)));
+            if with_content {
+                item.content = Some(history.content.clone());
+            }
+            return item;
+        }
+        if value.histories.is_empty() && value.last_modified <= time {
+            //没有历史记录时以当前内容为准
+            let mut item = ConfigAsOfItem::new(key, ConfigAsOfStatus::Live);
+            item.modified_time = Some(value.last_modified);
+            item.md5 = Some(value.md5.clone());
+            if with_content {
+                item.content = Some(value.content.clone());
+            }
+            return item;
+        }
+        if value.histories.is_empty() || value.histories.len() >= CONFIG_HISTORY_MAX_SIZE {
+            ConfigAsOfItem::new(key, ConfigAsOfStatus::Unknown)
+        } else {
+            ConfigAsOfItem::new(key, ConfigAsOfStatus::NotCreated)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_as_of() {
+        let key = ConfigKey::new("app.yaml", "DEFAULT_GROUP", "");
+        let mut value = ConfigValue::init(Arc::new("v1".to_owned()), 1, 1000, None, None);
+        value.update_value(Arc::new("v2".to_owned()), 2, 2000, None, None);
+
+        let item = ConfigTimeTravelUtils::resolve(&key, &value, 1500, true);
+        assert_eq!(item.status, ConfigAsOfStatus::Live);
+        assert_eq!(item.history_id, Some(1));
+        assert_eq!(item.content.unwrap().as_str(), "v1");
+        let item = ConfigTimeTravelUtils::resolve(&key, &value, 2000, false);
+        assert_eq!(item.history_id, Some(2));
+        assert!(item.content.is_none());
+        let item = ConfigTimeTravelUtils::resolve(&key, &value, 500, false);
+        assert_eq!(item.status, ConfigAsOfStatus::NotCreated);
+
+        let offset = FixedOffset::east_opt(8 * 3600).unwrap();
+        assert_eq!(
+            ConfigTimeTravelUtils::parse_time("1970-01-01 08:00:01", &offset).unwrap(),
+            1000
+        );
+        assert_eq!(
+            ConfigTimeTravelUtils::parse_time("1000", &offset).unwrap(),
+            1000
+        );
+        assert!(ConfigTimeTravelUtils::parse_time("14:32", &offset).is_err());
+    }
+}
@@ -1,10 +1,68 @@
 pub mod param_utils {
     use anyhow::Ok;
 
-    use super::property_util;
+    use crate::config::core::ConfigKey;
+    use crate::console::SYSCONFIG_NAMESPACE;
 
     const VALID_CHARS: [char; 4] = ['_', '-', '.', ':'];
     const TENANT_MAX_LEN: usize = 128;
+    const DATA_ID_MAX_LEN: usize = 256;
+    const GROUP_MAX_LEN: usize = 128;
+    /// 以此前缀开头的dataId按加密配置处理,与nacos加密插件约定一致
+    pub const CIPHER_PREFIX: &str = "cipher-";
+
+    pub fn check_data_id(data_id: &str) -> anyhow::Result<()> {
+        if !is_valid(data_id) {
+            return Err(anyhow::anyhow!("invalid dataId : {}", data_id));
+        }
+        if data_id.len() > DATA_ID_MAX_LEN {
+            return Err(anyhow::anyhow!("Too long dataId, over {}", DATA_ID_MAX_LEN));
+        }
+        if data_id == CIPHER_PREFIX {
+            return Err(anyhow::anyhow!(
+                "invalid dataId : {}, the cipher dataId is empty",
+                data_id
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn check_group(group: &str) -> anyhow::Result<()> {
+        if !is_valid(group) {
+            return Err(anyhow::anyhow!("invalid group : {}", group));
+        }
+        if group.len() > GROUP_MAX_LEN {
+            return Err(anyhow::anyhow!("Too long group, over {}", GROUP_MAX_LEN));
+        }
+        Ok(())
+    }
+
+    pub fn is_cipher_data_id(data_id: &str) -> bool {
+        data_id.starts_with(CIPHER_PREFIX)
+    }
+
+    ///
+    /// 客户端接口写入或删除配置前检查保留前缀:
+    /// 系统命名空间只能通过控制台专用接口维护;开启保护时加密配置不允许通过客户端接口删除
+    pub fn check_reserved(
+        key: &ConfigKey,
+        is_delete: bool,
+        protect_cipher: bool,
+    ) -> anyhow::Result<()> {
+        if key.tenant.as_str() == SYSCONFIG_NAMESPACE {
+            return Err(anyhow::anyhow!(
+                "the namespace {} is reserved",
+                SYSCONFIG_NAMESPACE
+            ));
+        }
+        if is_delete && protect_cipher && is_cipher_data_id(&key.data_id) {
+            return Err(anyhow::anyhow!(
+                "the cipher config {} is protected, remove it from console",
+                &key.data_id
+            ));
+        }
+        Ok(())
+    }
 
     pub fn check_tenant(tenant: &Option<String>) -> anyhow::Result<()> {
         if let Some(t) = tenant {
@@ -19,26 +77,28 @@ pub mod param_utils {
         }
         Ok(())
     }
+    pub fn check_content(content: &str, max_content: usize) -> anyhow::Result<()> {
+        if content.is_empty() {
+            return Err(anyhow::anyhow!("content is blank : {}", content));
+        } else if content.len() > max_content {
+            return Err(anyhow::anyhow!("invalid content, over {}", max_content));
+        }
+        Ok(())
+    }
+
     pub fn check_param(
         data_id: &Option<String>,
         group: &Option<String>,
         datum_id: &Option<String>,
         content: &Option<String>,
+        max_content: usize,
     ) -> anyhow::Result<()> {
         match data_id {
-            Some(data_id) => {
-                if data_id.is_empty() || !is_valid(data_id.trim()) {
-                    return Err(anyhow::anyhow!("invalid dataId : {}", data_id));
-                }
-            }
+            Some(data_id) => check_data_id(data_id.trim())?,
             None => return Err(anyhow::anyhow!("invalid dataId : ")),
         }
         match group {
-            Some(group) => {
-                if group.is_empty() || !is_valid(group) {
-                    return Err(anyhow::anyhow!("invalid group : {}", group));
-                }
-            }
+            Some(group) => check_group(group)?,
             None => return Err(anyhow::anyhow!("invalid group : ")),
         }
         match datum_id {
@@ -50,16 +110,7 @@ pub mod param_utils {
             None => return Err(anyhow::anyhow!("invalid datumId : ")),
         }
         match content {
-            Some(content) => {
-                if content.is_empty() {
-                    return Err(anyhow::anyhow!("content is blank : {}", content));
-                } else if content.len() > property_util::get_max_content() {
-                    return Err(anyhow::anyhow!(
-                        "invalid content, over {}",
-                        property_util::get_max_content()
-                    ));
-                }
-            }
+            Some(content) => check_content(content, max_content)?,
             None => return Err(anyhow::anyhow!("content is blank : ")),
         }
 
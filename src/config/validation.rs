@@ -0,0 +1,218 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::config_type::ConfigType;
+
+///
+/// 配置内容格式错误,行列号从1开始
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Error)]
+#[serde(rename_all = "camelCase")]
+#[error("invalid {config_type} content at line {line}, column {column}: {message}")]
+pub struct ConfigFormatError {
+    pub config_type: &'static str,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ConfigFormatError {
+    fn new(config_type: &'static str, line: usize, column: usize, message: String) -> Self {
+        Self {
+            config_type,
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn new_by_offset(
+        config_type: &'static str,
+        content: &str,
+        offset: usize,
+        message: String,
+    ) -> Self {
+        let (line, column) = ConfigValidationUtils::get_line_column(content, offset);
+        Self::new(config_type, line, column, message)
+    }
+}
+
+pub struct ConfigValidationUtils;
+
+impl ConfigValidationUtils {
+    ///
+    /// 按配置类型校验内容格式;text、html与空内容不校验
+    pub fn validate(config_type: &str, content: &str) -> Result<(), ConfigFormatError> {
+        if content.is_empty() {
+            return Ok(());
+        }
+        match ConfigType::new_by_value(config_type) {
+            ConfigType::Json => Self::validate_json(content),
+            ConfigType::Yaml => Self::validate_yaml(content),
+            ConfigType::Xml => Self::validate_xml(content),
+            ConfigType::Properties => Self::validate_properties(content),
+            ConfigType::Toml => Self::validate_toml(content),
+            ConfigType::Text | ConfigType::Html => Ok(()),
+        }
+    }
+
+    ///
+    /// 字节偏移转换为行列号
+    fn get_line_column(content: &str, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(content.len());
+        while !content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &content[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// serde_json与serde_yaml的错误信息自带位置,只保留描述部分
+    fn strip_location(message: String) -> String {
+        match message.find(" at line ") {
+            Some(i) => message[..i].to_owned(),
+            None => message,
+        }
+    }
+
+    fn validate_json(content: &str) -> Result<(), ConfigFormatError> {
+        serde_json::from_str::<serde::de::IgnoredAny>(content)
+            .map(|_| ())
+            .map_err(|e| {
+                ConfigFormatError::new(
+                    "json",
+                    e.line(),
+                    e.column(),
+                    Self::strip_location(e.to_string()),
+                )
+            })
+    }
+
+    fn validate_yaml(content: &str) -> Result<(), ConfigFormatError> {
+        //支持以---分隔的多文档
+        for document in serde_yaml::Deserializer::from_str(content) {
+            if let Err(e) = serde_yaml::Value::deserialize(document) {
+                let (line, column) = e
+                    .location()
+                    .map(|v| (v.line(), v.column()))
+                    .unwrap_or((1, 1));
+                return Err(ConfigFormatError::new(
+                    "yaml",
+                    line,
+                    column,
+                    Self::strip_location(e.to_string()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_toml(content: &str) -> Result<(), ConfigFormatError> {
+        toml::from_str::<toml::Table>(content)
+            .map(|_| ())
+            .map_err(|e| {
+                let offset = e.span().map(|v| v.start).unwrap_or(0);
+                ConfigFormatError::new_by_offset("toml", content, offset, e.message().to_owned())
+            })
+    }
+
+    fn validate_xml(content: &str) -> Result<(), ConfigFormatError> {
+        let mut reader = Reader::from_str(content);
+        let mut depth = 0usize;
+        let mut has_root = false;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(_)) => {
+                    depth += 1;
+                    has_root = true;
+                }
+                Ok(Event::Empty(_)) => has_root = true,
+                Ok(Event::End(_)) => depth = depth.saturating_sub(1),
+                Ok(Event::Eof) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(ConfigFormatError::new_by_offset(
+                        "xml",
+                        content,
+                        reader.buffer_position(),
+                        e.to_string(),
+                    ));
+                }
+            }
+        }
+        let message = if depth > 0 {
+            "unclosed element"
+        } else if !has_root {
+            "no root element"
+        } else {
+            return Ok(());
+        };
+        Err(ConfigFormatError::new_by_offset(
+            "xml",
+            content,
+            content.len(),
+            message.to_owned(),
+        ))
+    }
+
+    ///
+    /// properties格式很宽松,只校验\uxxxx转义是否完整
+    fn validate_properties(content: &str) -> Result<(), ConfigFormatError> {
+        for (i, line) in content.lines().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            let first = chars.iter().position(|c| !c.is_whitespace());
+            if matches!(first.map(|v| chars[v]), None | Some('#') | Some('!')) {
+                continue;
+            }
+            let mut j = 0;
+            while j < chars.len() {
+                if chars[j] != '\\' {
+                    j += 1;
+                    continue;
+                }
+                if chars.get(j + 1) == Some(&'u') {
+                    let valid = chars.len() >= j + 6
+                        && chars[j + 2..j + 6].iter().all(|c| c.is_ascii_hexdigit());
+                    if !valid {
+                        return Err(ConfigFormatError::new(
+                            "properties",
+                            i + 1,
+                            j + 1,
+                            "malformed \\uxxxx encoding".to_owned(),
+                        ));
+                    }
+                }
+                j += 2;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_config_format() {
+        assert!(ConfigValidationUtils::validate("json", "{\"a\":1}").is_ok());
+        let err = ConfigValidationUtils::validate("json", "{\n\"a\":1,\n}").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(ConfigValidationUtils::validate("yaml", "a: 1\n---\nb: [1, 2]").is_ok());
+        let err = ConfigValidationUtils::validate("yaml", "a: 1\nb: [1, 2").unwrap_err();
+        assert_eq!(err.config_type, "yaml");
+        assert!(ConfigValidationUtils::validate("toml", "[a]\nb = 1").is_ok());
+        let err = ConfigValidationUtils::validate("toml", "a = 1\nb = ").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(ConfigValidationUtils::validate("xml", "<a><b/></a>").is_ok());
+        assert!(ConfigValidationUtils::validate("xml", "<a><b></a>").is_err());
+        assert!(ConfigValidationUtils::validate("xml", "<a>").is_err());
+        assert!(ConfigValidationUtils::validate("properties", "a=\\u4e2d").is_ok());
+        let err = ConfigValidationUtils::validate("properties", "a=1\nb=\\u4e").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert!(ConfigValidationUtils::validate("text", "{").is_ok());
+    }
+}
@@ -4,7 +4,7 @@ use crate::common::appdata::AppShareData;
 use crate::common::string_utils::StringUtils;
 use crate::config::core::ConfigActor;
 use actix::prelude::*;
-use actix_web::{http::header, web, HttpResponse, Responder};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
 use uuid::Uuid;
 
 use crate::naming::ops::ops_api::query_opt_service_list;
@@ -22,6 +22,7 @@ use super::{
     NamespaceUtils,
 };
 use super::{login_api, user_api};
+use crate::openapi::auth::is_manager_request;
 
 use super::v2;
 
@@ -62,10 +63,12 @@ pub async fn add_namespace(
 }
 
 pub async fn update_namespace(
+    req: HttpRequest,
     param: web::Form<NamespaceInfo>,
     app_data: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
-    match NamespaceUtils::update_namespace(&app_data, param.0).await {
+    let is_manager = is_manager_request(&req, &app_data.sys_config);
+    match NamespaceUtils::update_namespace(&app_data, param.0, is_manager).await {
         Ok(_) => {
             let result = ConsoleResult::success(true);
             let v = serde_json::to_string(&result).unwrap();
@@ -138,7 +141,10 @@ pub fn console_api_config_v1(config: &mut web::ServiceConfig) {
                     .route(web::delete().to(remove_namespace)),
             )
             .service(web::resource("/configs").route(web::get().to(query_config_list)))
-            .service(web::resource("/configs/:id/listeners").route(web::get().to(query_config_listener_list)))
+            .service(
+                web::resource("/configs/:id/listeners")
+                    .route(web::get().to(query_config_listener_list)),
+            )
             .service(web::resource("/config/import").route(web::post().to(import_config)))
             .service(web::resource("/config/download").route(web::get().to(download_config)))
             .service(
@@ -188,6 +194,10 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
             .service(web::resource("/user/add").route(web::post().to(v2::user_api::add_user)))
             .service(web::resource("/user/update").route(web::post().to(v2::user_api::update_user)))
             .service(web::resource("/user/remove").route(web::post().to(v2::user_api::remove_user)))
+            .service(
+                web::resource("/user/access_report")
+                    .route(web::get().to(v2::user_api::get_access_report)),
+            )
             .service(
                 web::resource("/user/web_resources")
                     .route(web::get().to(v2::user_api::get_user_web_resources)),
@@ -196,6 +206,27 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/user/reset_password")
                     .route(web::post().to(v2::user_api::reset_password)),
             )
+            .service(
+                web::resource("/user/preferences")
+                    .route(web::get().to(v2::user_api::get_user_preferences)),
+            )
+            .service(
+                web::resource("/user/preferences/set")
+                    .route(web::post().to(v2::user_api::set_user_preference)),
+            )
+            .service(
+                web::resource("/user/favorites")
+                    .route(web::get().to(v2::user_api::get_user_favorites)),
+            )
+            .service(
+                web::resource("/user/favorites/add")
+                    .route(web::post().to(v2::user_api::add_user_favorite)),
+            )
+            .service(
+                web::resource("/user/favorites/remove")
+                    .route(web::post().to(v2::user_api::remove_user_favorite)),
+            )
+            .service(web::resource("/search").route(web::get().to(v2::search_api::global_search)))
             .service(
                 web::resource("/namespaces/list")
                     .route(web::get().to(v2::namespace_api::query_namespace_list)),
@@ -212,10 +243,66 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/namespaces/remove")
                     .route(web::post().to(v2::namespace_api::remove_namespace)),
             )
+            .service(
+                web::resource("/namespaces/quota_usage")
+                    .route(web::get().to(v2::namespace_api::query_quota_usage)),
+            )
+            .service(
+                web::resource("/namespaces/compare")
+                    .route(web::get().to(v2::namespace_api::compare_namespaces)),
+            )
+            .service(
+                web::resource("/namespaces/group_rules")
+                    .route(web::get().to(v2::namespace_api::query_group_rules)),
+            )
+            .service(
+                web::resource("/namespaces/group_rules/set")
+                    .route(web::post().to(v2::namespace_api::set_group_rule)),
+            )
+            .service(
+                web::resource("/namespaces/service_links")
+                    .route(web::get().to(v2::namespace_api::query_service_links)),
+            )
+            .service(
+                web::resource("/namespaces/service_links/set")
+                    .route(web::post().to(v2::namespace_api::set_service_link)),
+            )
             .service(
                 web::resource("/cluster/cluster_node_list")
                     .route(web::get().to(v2::cluster_api::query_cluster_info)),
             )
+            .service(
+                web::resource("/cluster/settings")
+                    .route(web::get().to(v2::cluster_api::query_cluster_settings)),
+            )
+            .service(
+                web::resource("/cluster/settings/set")
+                    .route(web::post().to(v2::cluster_api::set_cluster_settings)),
+            )
+            .service(
+                web::resource("/cluster/rebalance_by_zone")
+                    .route(web::post().to(v2::cluster_api::rebalance_by_zone)),
+            )
+            .service(
+                web::resource("/cluster/shutdown")
+                    .route(web::post().to(v2::cluster_api::shutdown_node)),
+            )
+            .service(
+                web::resource("/cluster/support_bundle")
+                    .route(web::get().to(v2::cluster_api::download_support_bundle)),
+            )
+            .service(
+                web::resource("/cluster/dead_letters")
+                    .route(web::get().to(v2::cluster_api::query_dead_letters)),
+            )
+            .service(
+                web::resource("/cluster/dead_letters/requeue")
+                    .route(web::post().to(v2::cluster_api::requeue_dead_letters)),
+            )
+            .service(
+                web::resource("/cluster/dead_letters/purge")
+                    .route(web::post().to(v2::cluster_api::purge_dead_letters)),
+            )
             .service(
                 web::resource("/config/import")
                     .route(web::post().to(v2::config_api::import_config)),
@@ -224,6 +311,9 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/config/download")
                     .route(web::get().to(v2::config_api::download_config)),
             )
+            .service(
+                web::resource("/config/clone").route(web::post().to(v2::config_api::clone_configs)),
+            )
             .service(
                 web::resource("/config/list")
                     .route(web::get().to(v2::config_api::query_config_list)),
@@ -241,20 +331,164 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/config/history")
                     .route(web::get().to(v2::config_api::query_history_config_page)),
             )
+            .service(
+                web::resource("/config/history/diff")
+                    .route(web::get().to(v2::config_api::query_history_diff)),
+            )
+            .service(
+                web::resource("/config/history/rollback")
+                    .route(web::post().to(v2::config_api::rollback_config_history)),
+            )
+            .service(
+                web::resource("/config/as_of")
+                    .route(web::get().to(v2::config_api::query_config_as_of)),
+            )
+            .service(
+                web::resource("/config/template/list")
+                    .route(web::get().to(v2::config_api::query_config_templates)),
+            )
+            .service(
+                web::resource("/config/template/set")
+                    .route(web::post().to(v2::config_api::set_config_template)),
+            )
+            .service(
+                web::resource("/config/template/remove")
+                    .route(web::post().to(v2::config_api::remove_config_template)),
+            )
+            .service(
+                web::resource("/config/template/variables")
+                    .route(web::get().to(v2::config_api::query_config_template_variables)),
+            )
+            .service(
+                web::resource("/config/template/variables/set")
+                    .route(web::post().to(v2::config_api::set_config_template_variables)),
+            )
+            .service(
+                web::resource("/config/schedule/list")
+                    .route(web::get().to(v2::config_api::query_config_schedules)),
+            )
+            .service(
+                web::resource("/config/schedule/add")
+                    .route(web::post().to(v2::config_api::add_config_schedule)),
+            )
+            .service(
+                web::resource("/config/schedule/cancel")
+                    .route(web::post().to(v2::config_api::cancel_config_schedule)),
+            )
+            .service(
+                web::resource("/config/publish/dry_run")
+                    .route(web::post().to(v2::config_api::publish_config_dry_run)),
+            )
+            .service(
+                web::resource("/config/listeners")
+                    .route(web::get().to(v2::config_api::query_config_listeners)),
+            )
+            .service(
+                web::resource("/config/listener_connections")
+                    .route(web::get().to(v2::config_api::query_listener_connection_map)),
+            )
+            .service(
+                web::resource("/config/beta/info")
+                    .route(web::get().to(v2::config_api::get_config_beta)),
+            )
+            .service(
+                web::resource("/config/beta/publish")
+                    .route(web::post().to(v2::config_api::publish_config_beta)),
+            )
+            .service(
+                web::resource("/config/beta/remove")
+                    .route(web::post().to(v2::config_api::remove_config_beta)),
+            )
+            .service(
+                web::resource("/config/tag/list")
+                    .route(web::get().to(v2::config_api::query_config_tags)),
+            )
+            .service(
+                web::resource("/config/tag/publish")
+                    .route(web::post().to(v2::config_api::publish_config_tag)),
+            )
+            .service(
+                web::resource("/config/tag/remove")
+                    .route(web::post().to(v2::config_api::remove_config_tag)),
+            )
+            .service(
+                web::resource("/config/freeze_rules")
+                    .route(web::get().to(v2::config_api::get_freeze_rules)),
+            )
+            .service(
+                web::resource("/config/freeze_rules/set")
+                    .route(web::post().to(v2::config_api::set_freeze_rules)),
+            )
+            .service(
+                web::resource("/config/encryption/rotate")
+                    .route(web::post().to(v2::config_api::rotate_config_encryption)),
+            )
             .service(
                 web::resource("/service/list")
                     .route(web::get().to(v2::naming_api::query_service_list)),
             )
+            .service(
+                web::resource("/config/timeline")
+                    .route(web::get().to(v2::timeline_api::query_config_timeline)),
+            )
+            .service(
+                web::resource("/service/timeline")
+                    .route(web::get().to(v2::timeline_api::query_service_timeline)),
+            )
+            .service(
+                web::resource("/service/instance_history")
+                    .route(web::get().to(v2::naming_api::query_instance_count_history)),
+            )
+            .service(
+                web::resource("/service/metadata_schemas")
+                    .route(web::get().to(v2::naming_api::query_metadata_schemas)),
+            )
+            .service(
+                web::resource("/service/metadata_schemas/set")
+                    .route(web::post().to(v2::naming_api::set_metadata_schema)),
+            )
+            .service(
+                web::resource("/service/stats")
+                    .route(web::get().to(v2::naming_api::query_namespace_stats)),
+            )
+            .service(
+                web::resource("/service/subscribers")
+                    .route(web::get().to(v2::naming_api::query_service_subscribers)),
+            )
             .service(
                 web::resource("/service/add").route(web::post().to(v2::naming_api::add_service)),
             )
             .service(
-                web::resource("/service/update").route(web::post().to(v2::naming_api::add_service)),
+                web::resource("/service/update")
+                    .route(web::post().to(v2::naming_api::update_service)),
+            )
+            .service(
+                web::resource("/service/info")
+                    .route(web::get().to(v2::naming_api::query_service_detail)),
             )
             .service(
                 web::resource("/service/remove")
                     .route(web::post().to(v2::naming_api::remove_service)),
             )
+            .service(
+                web::resource("/service/move").route(web::post().to(v2::naming_api::move_service)),
+            )
+            .service(
+                web::resource("/service/backup/export")
+                    .route(web::get().to(v2::naming_api::export_naming_backup)),
+            )
+            .service(
+                web::resource("/service/backup/import")
+                    .route(web::post().to(v2::naming_api::import_naming_backup)),
+            )
+            .service(
+                web::resource("/service/export")
+                    .route(web::get().to(v2::naming_api::export_services)),
+            )
+            .service(
+                web::resource("/service/import")
+                    .route(web::post().to(v2::naming_api::import_services)),
+            )
             .service(
                 web::resource("/instance/list")
                     .route(web::get().to(v2::naming_api::query_instances_list)),
@@ -273,10 +507,38 @@ pub fn console_api_config_v2(config: &mut web::ServiceConfig) {
                 web::resource("/instance/remove")
                     .route(web::post().to(v2::naming_api::remove_instance)),
             )
+            .service(
+                web::resource("/instance/drain")
+                    .route(web::post().to(v2::naming_api::drain_instance)),
+            )
+            .service(
+                web::resource("/service/change_history")
+                    .route(web::get().to(v2::naming_api::query_service_change_history)),
+            )
+            .service(
+                web::resource("/service/quarantine_instances")
+                    .route(web::get().to(v2::naming_api::query_quarantine_instances)),
+            )
+            .service(
+                web::resource("/service/pattern_subscribers")
+                    .route(web::get().to(v2::naming_api::query_pattern_subscribers)),
+            )
+            .service(
+                web::resource("/instance/flapping_list")
+                    .route(web::get().to(v2::naming_api::query_flapping_instances)),
+            )
+            .service(
+                web::resource("/instance/query_by_ip")
+                    .route(web::get().to(v2::naming_api::query_instances_by_ip)),
+            )
             .service(
                 web::resource("/metrics/timeline")
                     .route(web::get().to(v2::metrics_api::query_metrics_timeline))
                     .route(web::post().to(v2::metrics_api::query_metrics_timeline_json)),
+            )
+            .service(
+                web::resource("/client/sdk_usage")
+                    .route(web::get().to(v2::client_api::query_sdk_usage)),
             ),
     );
 }
@@ -8,24 +8,28 @@ use actix_multipart::form::tempfile::TempFile;
 use actix_multipart::form::text::Text;
 use actix_multipart::form::MultipartForm;
 use actix_multipart::Multipart;
-use actix_web::{error, http::header, web, Error, HttpRequest, HttpResponse, Responder, Result};
-use zip::write::FileOptions;
+use actix_web::{
+    error, http::header, web, Error, HttpMessage, HttpRequest, HttpResponse, Responder, Result,
+};
 
 use crate::common::appdata::AppShareData;
+use crate::common::model::{ApiResult, UserSession};
 use crate::config::core::{
     ConfigActor, ConfigAsyncCmd, ConfigCmd, ConfigInfoDto, ConfigKey, ConfigResult,
 };
 use crate::config::dal::QueryListeners;
+use crate::config::nacos_zip::{ConfigImportPolicy, NacosZipUtils};
 use crate::config::ConfigUtils;
 use crate::console::model::config_model::{
     OpsConfigOptQueryListResponse, OpsConfigQueryListRequest,
 };
+use crate::console::NamespaceUtils;
 use crate::now_millis;
+use crate::openapi::auth::is_manager_request;
 use crate::raft::cluster::model::SetConfigReq;
 use actix::prelude::Addr;
 use tokio_stream::StreamExt;
 use uuid::Uuid;
-use zip::{ZipArchive, ZipWriter};
 
 use super::model::config_model::OpsConfigImportInfo;
 use super::model::paginate::{PaginateQuery, PaginateResponse};
@@ -93,10 +97,15 @@ pub async fn query_history_config_page(
 pub struct UploadForm {
     #[multipart(rename = "tenant")]
     pub tenant: Option<Text<String>>,
+    /// 配置已存在时的处理策略: abort、skip、overwrite(默认)
+    #[multipart(rename = "policy")]
+    pub policy: Option<Text<String>>,
     #[multipart(rename = "file")]
     pub files: Vec<TempFile>,
 }
 
+///
+/// 导入nacos导出格式(group/dataId 与 .metadata.yml)的配置包
 pub async fn import_config(
     req: HttpRequest,
     MultipartForm(form): MultipartForm<UploadForm>,
@@ -108,72 +117,32 @@ pub async fn import_config(
             None => "".to_owned(),
         },
     ));
-    //let tenant = Arc::new(ConfigUtils::default_tenant(config_info.0.tenant.unwrap_or_default()));
+    NamespaceUtils::check_config_write(
+        &app.sys_config,
+        &tenant,
+        is_manager_request(&req, &app.sys_config),
+    )
+    .map_err(error::ErrorForbidden)?;
+    let policy = match form.policy.as_ref().map(|v| v.as_str()) {
+        Some(v) => ConfigImportPolicy::new_by_value(v)
+            .ok_or_else(|| error::ErrorBadRequest(format!("unknown import policy {}", v)))?,
+        None => ConfigImportPolicy::default(),
+    };
+    let op_user = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|session| session.username.clone());
+    let mut items = vec![];
+    let mut invalid_list = vec![];
     for f in form.files {
-        match zip::ZipArchive::new(f.file) {
-            Ok(mut archive) => {
-                for i in 0..archive.len() {
-                    let mut file = archive.by_index(i).unwrap();
-                    /*
-                    let filepath = match file.enclosed_name() {
-                        Some(path) => path,
-                        None => continue,
-                    };
-                    */
-                    let filename = file.name();
-                    if !(*filename).ends_with('/') {
-                        let parts = filename.split('/').collect::<Vec<_>>();
-                        if parts.len() != 2 {
-                            continue;
-                        }
-                        assert!(parts.len() == 2);
-                        let config_key = ConfigKey::new_by_arc(
-                            Arc::new(parts[1].to_owned()),
-                            Arc::new(parts[0].to_owned()),
-                            tenant.clone(),
-                        );
-                        let value = match io::read_to_string(&mut file) {
-                            Ok(v) => v,
-                            Err(_) => continue,
-                        };
-                        //println!("update load, {:?}:{}",&config_key,&value);
-                        //config_addr.do_send(ConfigAsyncCmd::Add(config_key, Arc::new(value)));
-                        let mut req = SetConfigReq::new(config_key.clone(), Arc::new(value));
-                        let data_id_clone = config_key.data_id.clone();
-                        req.config_type = SetConfigReq::detect_config_type(data_id_clone);
-
-                        app.config_route.set_config(req).await.ok();
-                    }
-                }
-            }
-            Err(_) => todo!(),
-        }
+        let (list, invalid) = NacosZipUtils::parse_zip(f.file).map_err(error::ErrorBadRequest)?;
+        items.extend(list);
+        invalid_list.extend(invalid);
     }
-    Ok(HttpResponse::Ok())
-}
-
-fn zip_file(mut zip: ZipWriter<&mut File>, list: Vec<ConfigInfoDto>) -> anyhow::Result<()> {
-    if list.is_empty() {
-        let options = FileOptions::default()
-            .compression_method(zip::CompressionMethod::Stored)
-            .unix_permissions(0o755);
-        zip.start_file(".ignore", options)?;
-        zip.write_all("empty config".as_bytes())?;
-    }
-    for item in &list {
-        zip.add_directory(item.group.as_str(), Default::default())
-            .ok();
-        let options = FileOptions::default()
-            .compression_method(zip::CompressionMethod::Stored)
-            .unix_permissions(0o755);
-        zip.start_file(
-            format!("{}/{}", item.group.as_str(), item.data_id.as_str()),
-            options,
-        )?;
-        zip.write_all(item.content.as_ref().unwrap().as_bytes())?;
-    }
-    zip.finish()?;
-    Ok(())
+    let result = NacosZipUtils::import(&app, tenant, items, invalid_list, policy, op_user)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(ApiResult::success(Some(result))))
 }
 
 ///
@@ -182,6 +151,7 @@ pub async fn download_config(
     request: web::Query<OpsConfigQueryListRequest>,
     config_addr: web::Data<Addr<ConfigActor>>,
 ) -> impl Responder {
+    let selected_keys = request.0.get_selected_keys();
     let mut param = request.0.to_param().unwrap();
     param.limit = 0xffff_ffff;
     param.query_context = true;
@@ -190,18 +160,16 @@ pub async fn download_config(
         Ok(res) => {
             let r: ConfigResult = res.unwrap();
             match r {
-                ConfigResult::ConfigInfoPage(_, list) => {
-                    let mut tmpfile: File = tempfile::tempfile().unwrap();
-                    {
-                        let write = std::io::Write::by_ref(&mut tmpfile);
-                        let zip = ZipWriter::new(write);
-                        zip_file(zip, list).ok();
+                ConfigResult::ConfigInfoPage(_, mut list) => {
+                    if let Some(keys) = selected_keys {
+                        list.retain(|e| keys.contains(&format!("{}/{}", &e.group, &e.data_id)));
                     }
-                    // Seek to start
-                    tmpfile.seek(SeekFrom::Start(0)).unwrap();
-                    let mut buf = vec![];
-                    tmpfile.read_to_end(&mut buf).unwrap();
-
+                    let buf = match NacosZipUtils::build_zip(&list) {
+                        Ok(v) => v,
+                        Err(err) => {
+                            return HttpResponse::InternalServerError().body(err.to_string())
+                        }
+                    };
                     let filename = format!("rnacos_config_export_{}.zip", now_millis());
                     HttpResponse::Ok()
                         .insert_header(header::ContentType::octet_stream())
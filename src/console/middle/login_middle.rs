@@ -20,7 +20,7 @@ use crate::user::permission::UserRole;
 
 lazy_static::lazy_static! {
     pub static ref IGNORE_CHECK_LOGIN: Vec<&'static str> = vec![
-        "/rnacos/p/login", "/rnacos/404",
+        "/rnacos/p/login", "/rnacos/404", "/rnacos/status",
         "/rnacos/api/console/login/login", "/rnacos/api/console/login/captcha",
         "/rnacos/api/console/v2/login/login", "/rnacos/api/console/v2/login/captcha",
     ];
@@ -90,6 +90,14 @@ where
         };
         let token = Arc::new(token);
         let cache_manager = self.app_share_data.cache_manager.clone();
+        let trusted_session = if is_check_path {
+            self.app_share_data
+                .sys_config
+                .console_trusted_header_auth
+                .build_session(request.peer_addr().map(|v| v.ip()), request.headers())
+        } else {
+            None
+        };
         //request.parts()
         //let (http_request, _pl) = request.parts();
         //let http_request = http_request.to_owned();
@@ -102,7 +110,13 @@ where
             let path = request.path();
             let method = request.method().as_str();
             if is_check_path {
-                is_login = if token.is_empty() {
+                is_login = if let Some(session) = trusted_session {
+                    //可信代理转发的身份头,不需要走密码登录
+                    user_has_permission =
+                        UserRole::match_url_by_roles(&session.roles, path, method);
+                    request.extensions_mut().insert(session);
+                    true
+                } else if token.is_empty() {
                     false
                 } else if let Ok(Some(session)) = get_user_session(
                     &cache_manager,
@@ -1 +1,2 @@
 pub mod login_middle;
+pub mod trusted_header;
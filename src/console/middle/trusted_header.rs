@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use actix_web::http::header::HeaderMap;
+
+use crate::common::model::UserSession;
+use crate::user::permission::{UserRoleHelper, USER_ROLE_VISITOR};
+
+pub const DEFAULT_TRUSTED_USER_HEADER: &str = "X-Auth-Request-User";
+pub const DEFAULT_TRUSTED_GROUPS_HEADER: &str = "X-Auth-Request-Groups";
+
+///
+/// ip网段,如 10.0.0.0/8、::1/128;不带前缀长度时只匹配单个ip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpCidr {
+    pub addr: IpAddr,
+    pub prefix: u8,
+}
+
+impl IpCidr {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr: IpAddr = addr.parse().ok()?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(v) => v.parse::<u8>().ok()?,
+            None => max_prefix,
+        };
+        if prefix > max_prefix {
+            return None;
+        }
+        Some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v) => v.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(*ip),
+            _ => *ip,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => Self::match_prefix(
+                u32::from(net) as u128,
+                u32::from(ip) as u128,
+                32,
+                self.prefix,
+            ),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                Self::match_prefix(u128::from(net), u128::from(ip), 128, self.prefix)
+            }
+            _ => false,
+        }
+    }
+
+    fn match_prefix(net: u128, ip: u128, bits: u8, prefix: u8) -> bool {
+        if prefix == 0 {
+            return true;
+        }
+        let shift = bits - prefix;
+        (net >> shift) == (ip >> shift)
+    }
+}
+
+///
+/// 反向代理身份头登录:请求来自可信代理网段且带有用户头时,直接按头信息构建会话
+#[derive(Debug, Clone, Default)]
+pub struct TrustedHeaderAuth {
+    pub proxy_cidrs: Vec<IpCidr>,
+    pub user_header: String,
+    pub groups_header: String,
+    /// 代理组名 -> 角色
+    pub group_roles: HashMap<String, Arc<String>>,
+    /// 没有匹配到组时使用的角色
+    pub default_role: Arc<String>,
+}
+
+impl TrustedHeaderAuth {
+    pub fn new(
+        proxy_cidrs: &str,
+        user_header: String,
+        groups_header: String,
+        group_roles: &str,
+        default_role: &str,
+    ) -> Self {
+        let proxy_cidrs = proxy_cidrs
+            .split(',')
+            .filter(|v| !v.trim().is_empty())
+            .filter_map(|v| {
+                let cidr = IpCidr::parse(v);
+                if cidr.is_none() {
+                    log::warn!("ignore invalid trusted proxy cidr: {}", v);
+                }
+                cidr
+            })
+            .collect();
+        let group_roles = group_roles
+            .split(',')
+            .filter_map(|v| v.split_once('='))
+            .map(|(group, role)| {
+                (
+                    group.trim().to_owned(),
+                    UserRoleHelper::get_role(role.trim()),
+                )
+            })
+            .collect();
+        let default_role = if default_role.is_empty() {
+            USER_ROLE_VISITOR.clone()
+        } else {
+            UserRoleHelper::get_role(default_role)
+        };
+        Self {
+            proxy_cidrs,
+            user_header,
+            groups_header,
+            group_roles,
+            default_role,
+        }
+    }
+
+    pub fn is_enable(&self) -> bool {
+        !self.proxy_cidrs.is_empty()
+    }
+
+    pub fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.proxy_cidrs.iter().any(|v| v.contains(ip))
+    }
+
+    ///
+    /// 按组映射角色,组之间用逗号分隔
+    pub fn build_roles(&self, groups: &str) -> Vec<Arc<String>> {
+        let mut roles: Vec<Arc<String>> = vec![];
+        for group in groups.split(',') {
+            if let Some(role) = self.group_roles.get(group.trim()) {
+                if !roles.contains(role) {
+                    roles.push(role.clone());
+                }
+            }
+        }
+        if roles.is_empty() {
+            roles.push(self.default_role.clone());
+        }
+        roles
+    }
+
+    pub fn build_session(
+        &self,
+        peer_ip: Option<IpAddr>,
+        headers: &HeaderMap,
+    ) -> Option<Arc<UserSession>> {
+        if !self.is_enable() || !self.is_trusted(&peer_ip?) {
+            return None;
+        }
+        let username = headers
+            .get(self.user_header.as_str())?
+            .to_str()
+            .ok()?
+            .trim();
+        if username.is_empty() {
+            return None;
+        }
+        let groups = headers
+            .get(self.groups_header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let mut extend_infos = HashMap::new();
+        extend_infos.insert("login_from".to_owned(), "trusted_header".to_owned());
+        Some(Arc::new(UserSession {
+            username: Arc::new(username.to_owned()),
+            nickname: Some(username.to_owned()),
+            roles: self.build_roles(groups),
+            extend_infos,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::permission::USER_ROLE_MANAGER;
+
+    #[test]
+    fn test_trusted_header_auth() {
+        let auth = TrustedHeaderAuth::new(
+            "10.0.0.0/8, 127.0.0.1,bad",
+            DEFAULT_TRUSTED_USER_HEADER.to_owned(),
+            DEFAULT_TRUSTED_GROUPS_HEADER.to_owned(),
+            "admins=0",
+            "",
+        );
+        assert_eq!(auth.proxy_cidrs.len(), 2);
+        assert!(auth.is_trusted(&"10.1.2.3".parse().unwrap()));
+        assert!(auth.is_trusted(&"::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!auth.is_trusted(&"11.0.0.1".parse().unwrap()));
+        assert_eq!(
+            auth.build_roles("dev,admins"),
+            vec![USER_ROLE_MANAGER.clone()]
+        );
+        assert_eq!(auth.build_roles("dev"), vec![USER_ROLE_VISITOR.clone()]);
+    }
+}
@@ -4,6 +4,7 @@ pub mod config_api;
 pub mod connection_api;
 pub mod login_api;
 pub mod model;
+pub mod namespace_compare;
 pub mod naming_api;
 pub mod user_api;
 
@@ -14,8 +15,14 @@ use std::sync::Arc;
 
 use crate::{
     common::appdata::AppShareData,
+    common::group_rule::{GroupRuleManager, NamespaceGroupRule, SYSCONFIG_GROUP_RULE_KEY},
+    common::metadata_schema::{
+        MetadataSchemaManager, ServiceMetadataSchema, SYSCONFIG_METADATA_SCHEMA_KEY,
+    },
+    common::service_link::{ServiceLink, ServiceLinkManager, SYSCONFIG_SERVICE_LINK_KEY},
+    common::AppSysConfig,
     config::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult},
-    raft::cluster::model::SetConfigReq,
+    raft::cluster::model::{DelConfigReq, SetConfigReq},
 };
 use actix::prelude::*;
 
@@ -37,6 +44,39 @@ lazy_static::lazy_static! {
 }
 
 impl NamespaceUtils {
+    ///
+    /// 系统命名空间不能删除,只有管理员可以修改
+    pub fn is_system_namespace(sys_config: &AppSysConfig, namespace_id: &str) -> bool {
+        namespace_id.is_empty()
+            || namespace_id == DEFAULT_NAMESPACE
+            || namespace_id == SYSCONFIG_NAMESPACE
+            || sys_config
+                .system_namespaces
+                .iter()
+                .any(|v| v == namespace_id)
+    }
+
+    ///
+    /// RNACOS_SYSTEM_NAMESPACES中的命名空间只有管理员可以写入配置;public命名空间不限制
+    pub fn check_config_write(
+        sys_config: &AppSysConfig,
+        namespace_id: &str,
+        is_manager: bool,
+    ) -> anyhow::Result<()> {
+        if !is_manager
+            && sys_config
+                .system_namespaces
+                .iter()
+                .any(|v| v == namespace_id)
+        {
+            return Err(anyhow::anyhow!(
+                "configs in system namespace {} can only be changed by manager",
+                namespace_id
+            ));
+        }
+        Ok(())
+    }
+
     pub async fn get_namespaces(config_addr: &Addr<ConfigActor>) -> Vec<Arc<NamespaceInfo>> {
         let cmd = ConfigCmd::GET(ConfigKey::new(
             SYSCONFIG_NAMESPACE_KEY,
@@ -121,6 +161,11 @@ impl NamespaceUtils {
             if namespace_id.is_empty() || namespace_id.eq(DEFAULT_NAMESPACE) {
                 return Err(anyhow::anyhow!("namespace is exist"));
             }
+            let namespace_type = if Self::is_system_namespace(&app_data.sys_config, &namespace_id) {
+                "1"
+            } else {
+                "2"
+            };
             let mut infos = Self::load_namespace_from_config(&app_data.config_addr).await;
             for item in &infos {
                 if namespace_id.eq(item.namespace_id.as_ref().unwrap() as &str) {
@@ -130,7 +175,7 @@ impl NamespaceUtils {
             let new_info = NamespaceInfo {
                 namespace_id: Some(namespace_id),
                 namespace_name: Some(namespace_name),
-                r#type: Some("2".to_owned()),
+                r#type: Some(namespace_type.to_owned()),
             };
             infos.push(new_info);
             Self::save_namespace(app_data, &infos).await
@@ -142,12 +187,18 @@ impl NamespaceUtils {
     pub async fn update_namespace(
         app_data: &Arc<AppShareData>,
         info: NamespaceInfo,
+        is_manager: bool,
     ) -> anyhow::Result<()> {
         if let (Some(namespace_id), Some(namespace_name)) = (info.namespace_id, info.namespace_name)
         {
             if namespace_id.is_empty() || namespace_id.eq(DEFAULT_NAMESPACE) {
                 return Err(anyhow::anyhow!("namespace can't update"));
             }
+            if !is_manager && Self::is_system_namespace(&app_data.sys_config, &namespace_id) {
+                return Err(anyhow::anyhow!(
+                    "system namespace can only be updated by manager"
+                ));
+            }
             let infos = Self::load_namespace_from_config(&app_data.config_addr).await;
             let mut new_infos = Vec::with_capacity(infos.len());
             let mut update_mark = false;
@@ -172,8 +223,8 @@ impl NamespaceUtils {
         namespace_id: Option<String>,
     ) -> anyhow::Result<()> {
         if let Some(namespace_id) = namespace_id {
-            if namespace_id.is_empty() || namespace_id.eq(DEFAULT_NAMESPACE) {
-                return Err(anyhow::anyhow!("namespace can't delete"));
+            if Self::is_system_namespace(&app_data.sys_config, &namespace_id) {
+                return Err(anyhow::anyhow!("system namespace can't delete"));
             }
             let infos = Self::load_namespace_from_config(&app_data.config_addr).await;
             let infos_len = infos.len();
@@ -187,9 +238,182 @@ impl NamespaceUtils {
             if new_infos.len() == infos_len {
                 return Err(anyhow::anyhow!("namespace is not exist"));
             }
-            Self::save_namespace(app_data, &new_infos).await
+            Self::save_namespace(app_data, &new_infos).await?;
+            Self::remove_attached_configs(app_data, namespace_id).await
         } else {
             Err(anyhow::anyhow!("params is empty"))
         }
     }
+
+    ///
+    /// 删除命名空间后清理其中配置挂载的beta与tag系统配置
+    async fn remove_attached_configs(
+        app_data: &Arc<AppShareData>,
+        namespace_id: String,
+    ) -> anyhow::Result<()> {
+        let cmd = ConfigCmd::QueryAttachedKeys(Arc::new(namespace_id));
+        if let ConfigResult::AttachedKeys(keys) = app_data.config_addr.send(cmd).await?? {
+            for key in keys {
+                app_data
+                    .config_route
+                    .del_config(DelConfigReq::new(key))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get_group_rules(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<NamespaceGroupRule>> {
+        let cmd = ConfigCmd::GET(ConfigKey::new(
+            SYSCONFIG_GROUP_RULE_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        ));
+        match config_addr.send(cmd).await?? {
+            ConfigResult::Data { value, .. } => GroupRuleManager::parse_rules(&value),
+            _ => Ok(vec![]),
+        }
+    }
+
+    ///
+    /// 设置命名空间的默认分组与分组别名,默认分组与别名都为空时移除该命名空间的规则
+    pub async fn set_group_rule(
+        app_data: &Arc<AppShareData>,
+        rule: NamespaceGroupRule,
+    ) -> anyhow::Result<()> {
+        let mut rules = Self::get_group_rules(&app_data.config_addr).await?;
+        let namespace_id = if rule.namespace_id.eq(DEFAULT_NAMESPACE) {
+            "".to_owned()
+        } else {
+            rule.namespace_id.clone()
+        };
+        rules.retain(|e| e.namespace_id != namespace_id);
+        let is_empty = rule
+            .default_group
+            .as_ref()
+            .map(|e| e.is_empty())
+            .unwrap_or(true)
+            && rule.group_alias.is_empty();
+        if !is_empty {
+            rules.push(NamespaceGroupRule {
+                namespace_id,
+                ..rule
+            });
+        }
+        let req = SetConfigReq::new(
+            ConfigKey::new(
+                SYSCONFIG_GROUP_RULE_KEY,
+                SYSCONFIG_GROUP,
+                SYSCONFIG_NAMESPACE,
+            ),
+            Arc::new(serde_json::to_string(&rules)?),
+        );
+        match app_data.config_route.set_config(req).await {
+            Ok(_) => Ok(()),
+            Err(err) => Err(anyhow::anyhow!(err)),
+        }
+    }
+
+    pub async fn get_service_links(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<ServiceLink>> {
+        let cmd = ConfigCmd::GET(ConfigKey::new(
+            SYSCONFIG_SERVICE_LINK_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        ));
+        match config_addr.send(cmd).await?? {
+            ConfigResult::Data { value, .. } => ServiceLinkManager::parse_links(&value),
+            _ => Ok(vec![]),
+        }
+    }
+
+    ///
+    /// 设置跨命名空间的服务链接,按源服务覆盖;链接的命名空间为空时移除该服务的链接
+    pub async fn set_service_link(
+        app_data: &Arc<AppShareData>,
+        link: ServiceLink,
+    ) -> anyhow::Result<()> {
+        link.check_valid()?;
+        let mut links = Self::get_service_links(&app_data.config_addr).await?;
+        let source_namespace_id = if link.source_namespace_id.eq(DEFAULT_NAMESPACE) {
+            "".to_owned()
+        } else {
+            link.source_namespace_id.clone()
+        };
+        links.retain(|e| {
+            !(e.source_namespace_id == source_namespace_id
+                && e.group_name == link.group_name
+                && e.service_name == link.service_name)
+        });
+        if !link.target_namespace_ids.is_empty() {
+            links.push(ServiceLink {
+                source_namespace_id,
+                ..link
+            });
+        }
+        let req = SetConfigReq::new(
+            ConfigKey::new(
+                SYSCONFIG_SERVICE_LINK_KEY,
+                SYSCONFIG_GROUP,
+                SYSCONFIG_NAMESPACE,
+            ),
+            Arc::new(serde_json::to_string(&links)?),
+        );
+        app_data.config_route.set_config(req).await
+    }
+
+    pub async fn get_metadata_schemas(
+        config_addr: &Addr<ConfigActor>,
+    ) -> anyhow::Result<Vec<ServiceMetadataSchema>> {
+        let cmd = ConfigCmd::GET(ConfigKey::new(
+            SYSCONFIG_METADATA_SCHEMA_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        ));
+        match config_addr.send(cmd).await?? {
+            ConfigResult::Data { value, .. } => MetadataSchemaManager::parse_schemas(&value),
+            _ => Ok(vec![]),
+        }
+    }
+
+    ///
+    /// 设置服务实例的元数据约束,按服务覆盖;约束字段为空时移除该服务的约束
+    pub async fn set_metadata_schema(
+        app_data: &Arc<AppShareData>,
+        schema: ServiceMetadataSchema,
+    ) -> anyhow::Result<()> {
+        schema.check_valid()?;
+        let namespace_id = if schema.namespace_id.eq(DEFAULT_NAMESPACE) {
+            "".to_owned()
+        } else {
+            schema.namespace_id.clone()
+        };
+        let key = ConfigKey::new(
+            SYSCONFIG_METADATA_SCHEMA_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        );
+        app_data
+            .config_route
+            .update_config(&key, |value| {
+                let mut schemas = MetadataSchemaManager::parse_schemas(value)?;
+                schemas.retain(|e| {
+                    !(e.namespace_id == namespace_id
+                        && e.group_name == schema.group_name
+                        && e.service_name == schema.service_name)
+                });
+                if !schema.fields.is_empty() {
+                    schemas.push(ServiceMetadataSchema {
+                        namespace_id: namespace_id.clone(),
+                        ..schema.clone()
+                    });
+                }
+                Ok(Some(serde_json::to_string(&schemas)?))
+            })
+            .await?;
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::naming::cluster::node_manage::{ClusterNode, NodeStatus};
@@ -11,6 +12,7 @@ pub struct ClusterNodeInfo {
     pub current_node: bool,
     pub raft_leader: bool,
     pub distro_valid: bool,
+    pub labels: Arc<HashMap<String, String>>,
 }
 
 impl From<ClusterNode> for ClusterNodeInfo {
@@ -21,6 +23,15 @@ impl From<ClusterNode> for ClusterNodeInfo {
             raft_leader: false,
             current_node: false,
             distro_valid: value.is_local || value.status == NodeStatus::Valid,
+            labels: value.labels,
         }
     }
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterParam {
+    /// 为空时处理全部死信
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
@@ -3,6 +3,7 @@ use crate::config::core::{ConfigInfoDto, ConfigKey};
 use crate::config::dal::ConfigHistoryParam;
 use crate::config::ConfigUtils;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -11,6 +12,19 @@ pub struct OpsConfigImportInfo {
     pub tenant: Option<String>,
 }
 
+///
+/// 命名空间之间复制配置
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigCloneParams {
+    pub source_tenant: Option<String>,
+    pub target_tenant: Option<String>,
+    /// 复制选中的配置,格式为group/dataId;为空时复制源命名空间的全部配置
+    pub config_keys: Option<Vec<String>>,
+    /// 配置已存在时的处理策略: ABORT、SKIP、OVERWRITE,默认ABORT
+    pub policy: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct OpsConfigQueryListRequest {
@@ -21,9 +35,20 @@ pub struct OpsConfigQueryListRequest {
     pub data_param: Option<String>,
     pub group: Option<String>,
     pub data_id: Option<String>,
+    /// 导出选中的配置,格式为group/dataId,多个以逗号分隔
+    pub config_keys: Option<String>,
 }
 
 impl OpsConfigQueryListRequest {
+    pub fn get_selected_keys(&self) -> Option<HashSet<String>> {
+        self.config_keys.as_ref().map(|v| {
+            v.split(',')
+                .map(|e| e.trim().to_owned())
+                .filter(|e| !e.is_empty())
+                .collect()
+        })
+    }
+
     pub fn to_param(self) -> anyhow::Result<ConfigQueryParam> {
         let limit = self.page_size.unwrap_or(0xffff_ffff);
         let offset = (self.page_no.unwrap_or(1) - 1) * limit;
@@ -86,6 +111,10 @@ pub struct ConfigParams {
     pub content: Option<Arc<String>>,
     pub config_type: Option<Arc<String>>,
     pub desc: Option<Arc<String>>,
+    /// 在变更冻结期内强制变更,仅管理员可用
+    pub freeze_override: Option<bool>,
+    /// 为true时跳过配置内容格式校验
+    pub force: Option<bool>,
 }
 
 impl ConfigParams {
@@ -99,6 +128,125 @@ impl ConfigParams {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerConnectionQueryParam {
+    pub tenant: Option<String>,
+    /// json(默认),csv
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistoryRollbackParams {
+    pub data_id: Arc<String>,
+    pub group: Option<Arc<String>>,
+    pub tenant: Option<String>,
+    /// 历史记录id
+    pub id: u64,
+    pub freeze_override: Option<bool>,
+    /// 预览差异时的当前配置md5,回滚时当前配置已变更则拒绝
+    pub current_md5: Option<Arc<String>>,
+}
+
+///
+/// 查询指定时间点生效的配置
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAsOfParams {
+    pub tenant: Option<String>,
+    pub group: Option<String>,
+    /// 为空时查询命名空间下的全部配置
+    pub data_id: Option<String>,
+    /// 毫秒时间戳,或按服务时区的 yyyy-MM-dd HH:mm:ss
+    pub time: Option<String>,
+    pub with_content: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigScheduleParams {
+    pub data_id: String,
+    pub group: Option<String>,
+    pub tenant: Option<String>,
+    pub content: Option<Arc<String>>,
+    pub config_type: Option<Arc<String>>,
+    pub desc: Option<Arc<String>>,
+    /// 生效时间,毫秒时间戳或按服务时区的 yyyy-MM-dd HH:mm:ss
+    pub effective_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTemplateRemoveParams {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigScheduleQueryParams {
+    /// 为空时查询全部命名空间
+    pub tenant: Option<String>,
+    pub id: Option<String>,
+}
+
+impl ConfigHistoryRollbackParams {
+    pub fn to_key(&self) -> ConfigKey {
+        let group = self
+            .group
+            .clone()
+            .unwrap_or(Arc::new("DEFAULT_GROUP".to_owned()));
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        ConfigKey::new_by_arc(self.data_id.clone(), group, Arc::new(tenant))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTagParams {
+    pub data_id: Arc<String>,
+    pub group: Option<Arc<String>>,
+    pub tenant: Option<String>,
+    pub tag: String,
+    pub content: Option<Arc<String>>,
+    pub config_type: Option<Arc<String>>,
+    /// 为true时跳过配置内容格式校验
+    pub force: Option<bool>,
+}
+
+impl ConfigTagParams {
+    pub fn to_key(&self) -> ConfigKey {
+        let group = self
+            .group
+            .clone()
+            .unwrap_or(Arc::new("DEFAULT_GROUP".to_owned()));
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        ConfigKey::new_by_arc(self.data_id.clone(), group, Arc::new(tenant))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigBetaParams {
+    pub data_id: Arc<String>,
+    pub group: Option<Arc<String>>,
+    pub tenant: Option<String>,
+    pub content: Option<Arc<String>>,
+    pub labels: Option<HashMap<String, String>>,
+    pub beta_ips: Option<Vec<String>>,
+}
+
+impl ConfigBetaParams {
+    pub fn to_key(&self) -> ConfigKey {
+        let group = self
+            .group
+            .clone()
+            .unwrap_or(Arc::new("DEFAULT_GROUP".to_owned()));
+        let tenant = ConfigUtils::default_tenant(self.tenant.clone().unwrap_or_default());
+        ConfigKey::new_by_arc(self.data_id.clone(), group, Arc::new(tenant))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigInfo {
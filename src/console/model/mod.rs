@@ -1,3 +1,4 @@
+pub mod client_model;
 pub mod cluster_model;
 pub mod config_model;
 pub mod login_model;
@@ -5,9 +6,11 @@ pub mod metrics_model;
 pub mod naming_model;
 pub mod paginate;
 pub mod raft_model;
+pub mod search_model;
 pub mod user_model;
 
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +20,27 @@ pub struct NamespaceInfo {
     pub r#type: Option<String>,
 }
 
+///
+/// 命名空间列表项,quotaWarning表示用量已接近配额
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceListItem {
+    #[serde(flatten)]
+    pub info: Arc<NamespaceInfo>,
+    pub quota_warning: bool,
+}
+
+///
+/// 资源事件时间线查询参数;resourceType为config时name是dataId,为service时name是服务名
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTimelineParam {
+    pub namespace_id: Option<String>,
+    pub group: Option<String>,
+    pub name: Option<String>,
+    pub limit: Option<usize>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ConsoleResult<T>
@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+use crate::naming::selector::ServiceSelector;
 use crate::naming::service::ServiceInfoDto;
 use crate::naming::service_index::ServiceQueryParam;
 use crate::naming::{
@@ -68,6 +69,7 @@ pub struct ServiceDto {
     pub trigger_flag: Option<bool>,
     pub metadata: Option<String>,
     pub protect_threshold: Option<f32>,
+    pub selector: Option<ServiceSelector>,
 }
 
 impl From<ServiceInfoDto> for ServiceDto {
@@ -85,6 +87,7 @@ impl From<ServiceInfoDto> for ServiceDto {
             trigger_flag: Some(value.trigger_flag),
             metadata,
             protect_threshold: value.protect_threshold,
+            selector: value.selector,
         }
     }
 }
@@ -135,6 +138,7 @@ pub struct ServiceParam {
     pub group_name: Option<String>,
     pub metadata: Option<Arc<HashMap<String, String>>>,
     pub protect_threshold: Option<f32>,
+    pub selector: Option<ServiceSelector>,
 }
 
 impl ServiceParam {
@@ -149,6 +153,43 @@ impl ServiceParam {
     }
 }
 
+///
+/// 服务改名或移动分组,目标服务名或分组为空时沿用原值
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceMoveParam {
+    pub service_name: Arc<String>,
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub target_service_name: Option<Arc<String>>,
+    pub target_group_name: Option<String>,
+}
+
+impl ServiceMoveParam {
+    pub fn to_key(&self) -> ServiceKey {
+        let group_name = Arc::new(NamingUtils::default_group(
+            self.group_name.clone().unwrap_or_default(),
+        ));
+        let namespace_id = Arc::new(NamingUtils::default_namespace(
+            self.namespace_id.clone().unwrap_or_default(),
+        ));
+        ServiceKey::new_by_arc(namespace_id, group_name, self.service_name.clone())
+    }
+
+    pub fn to_target_key(&self) -> ServiceKey {
+        let key = self.to_key();
+        let group_name = match &self.target_group_name {
+            Some(v) if !v.is_empty() => Arc::new(v.to_owned()),
+            _ => key.group_name,
+        };
+        let service_name = match &self.target_service_name {
+            Some(v) if !v.is_empty() => v.clone(),
+            _ => key.service_name,
+        };
+        ServiceKey::new_by_arc(key.namespace_id, group_name, service_name)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ServiceInfo {
@@ -214,3 +255,83 @@ impl InstanceParams {
         Ok(instance)
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceQueryParam {
+    pub namespace_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingBackupParam {
+    pub namespace_id: Option<String>,
+    /// json(默认)或zip
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceHistoryQueryParam {
+    pub service_name: Arc<String>,
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    /// 默认为24小时前
+    pub start_time: Option<i64>,
+    /// 默认为当前时间
+    pub end_time: Option<i64>,
+}
+
+impl ServiceHistoryQueryParam {
+    pub fn to_key(&self) -> ServiceKey {
+        let group_name = Arc::new(NamingUtils::default_group(
+            self.group_name.clone().unwrap_or_default(),
+        ));
+        let namespace_id = Arc::new(NamingUtils::default_namespace(
+            self.namespace_id.clone().unwrap_or_default(),
+        ));
+        ServiceKey::new_by_arc(namespace_id, group_name, self.service_name.clone())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceIpQueryParam {
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub namespace_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IpInstanceDto {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub ip: Arc<String>,
+    pub port: u32,
+    pub cluster_name: String,
+    pub weight: f32,
+    pub healthy: bool,
+    pub enabled: bool,
+    pub ephemeral: bool,
+    pub last_modified_millis: i64,
+}
+
+impl From<&Instance> for IpInstanceDto {
+    fn from(v: &Instance) -> Self {
+        Self {
+            namespace_id: v.namespace_id.clone(),
+            group_name: v.group_name.clone(),
+            service_name: v.service_name.clone(),
+            ip: v.ip.clone(),
+            port: v.port,
+            cluster_name: v.cluster_name.clone(),
+            weight: v.weight,
+            healthy: v.healthy,
+            enabled: v.enabled,
+            ephemeral: v.ephemeral,
+            last_modified_millis: v.last_modified_millis,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+/// 每类资源默认返回的最大数量
+pub const SEARCH_DEFAULT_LIMIT: usize = 10;
+pub const SEARCH_MAX_LIMIT: usize = 100;
+
+pub const SEARCH_TYPE_NAMESPACE: &str = "namespace";
+pub const SEARCH_TYPE_SERVICE: &str = "service";
+pub const SEARCH_TYPE_CONFIG: &str = "config";
+pub const SEARCH_TYPE_INSTANCE: &str = "instance";
+pub const SEARCH_TYPE_USER: &str = "user";
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchParam {
+    pub keyword: Option<String>,
+    /// 每类资源返回的最大数量
+    pub limit: Option<usize>,
+}
+
+impl GlobalSearchParam {
+    pub fn get_limit(&self) -> usize {
+        self.limit
+            .unwrap_or(SEARCH_DEFAULT_LIMIT)
+            .clamp(1, SEARCH_MAX_LIMIT)
+    }
+}
+
+///
+/// 全局搜索结果项;name为命名空间id、服务名、dataId、实例ip:port或用户名
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalSearchItem {
+    pub r#type: String,
+    pub namespace_id: Option<Arc<String>>,
+    pub group: Option<Arc<String>>,
+    /// 实例所属服务
+    pub service_name: Option<Arc<String>>,
+    pub name: Arc<String>,
+    /// 命名空间名称、用户昵称等补充说明
+    pub desc: Option<String>,
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-use crate::user::{model::UserDto, permission::UserRoleHelper};
+use crate::user::{favorite::FavoriteType, model::UserDto, permission::UserRoleHelper};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -73,3 +73,25 @@ impl UserPageParams {
         (limit, offset)
     }
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPreferenceParam {
+    pub key: Option<String>,
+    /// 为空时删除该偏好
+    pub value: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FavoriteQueryParam {
+    #[serde(rename = "type")]
+    pub favorite_type: Option<FavoriteType>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessReportParam {
+    /// json(默认),csv
+    pub format: Option<String>,
+}
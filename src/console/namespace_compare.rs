@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::core::{ConfigCmd, ConfigResult};
+use crate::config::ConfigUtils;
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::naming::NamingUtils;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceCompareParam {
+    pub source_namespace_id: Option<String>,
+    pub target_namespace_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigCompareItem {
+    pub group: Arc<String>,
+    pub data_id: Arc<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_md5: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_md5: Option<Arc<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigCompareReport {
+    pub only_in_source: Vec<ConfigCompareItem>,
+    pub only_in_target: Vec<ConfigCompareItem>,
+    /// 两边都存在但md5不同
+    pub different: Vec<ConfigCompareItem>,
+    pub same_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceCompareItem {
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub instance_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceCompareReport {
+    pub only_in_source: Vec<ServiceCompareItem>,
+    pub only_in_target: Vec<ServiceCompareItem>,
+    pub same_count: usize,
+}
+
+///
+/// 两个命名空间的差异报告,用于切换前核对环境是否一致
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceCompareReport {
+    pub source_namespace_id: String,
+    pub target_namespace_id: String,
+    /// 配置与服务都没有差异
+    pub consistent: bool,
+    pub configs: ConfigCompareReport,
+    pub services: ServiceCompareReport,
+}
+
+type ConfigMd5Map = HashMap<(Arc<String>, Arc<String>), Arc<String>>;
+type ServiceCountMap = HashMap<(Arc<String>, Arc<String>), u64>;
+
+pub struct NamespaceCompareUtils;
+
+impl NamespaceCompareUtils {
+    pub fn compare_configs(source: &ConfigMd5Map, target: &ConfigMd5Map) -> ConfigCompareReport {
+        let mut report = ConfigCompareReport::default();
+        for ((group, data_id), md5) in source {
+            match target.get(&(group.clone(), data_id.clone())) {
+                Some(target_md5) if target_md5 == md5 => report.same_count += 1,
+                Some(target_md5) => report.different.push(ConfigCompareItem {
+                    group: group.clone(),
+                    data_id: data_id.clone(),
+                    source_md5: Some(md5.clone()),
+                    target_md5: Some(target_md5.clone()),
+                }),
+                None => report.only_in_source.push(ConfigCompareItem {
+                    group: group.clone(),
+                    data_id: data_id.clone(),
+                    source_md5: Some(md5.clone()),
+                    target_md5: None,
+                }),
+            }
+        }
+        for ((group, data_id), md5) in target {
+            if !source.contains_key(&(group.clone(), data_id.clone())) {
+                report.only_in_target.push(ConfigCompareItem {
+                    group: group.clone(),
+                    data_id: data_id.clone(),
+                    source_md5: None,
+                    target_md5: Some(md5.clone()),
+                });
+            }
+        }
+        let sort_key = |e: &ConfigCompareItem| (e.group.clone(), e.data_id.clone());
+        report.only_in_source.sort_by_key(sort_key);
+        report.only_in_target.sort_by_key(sort_key);
+        report.different.sort_by_key(sort_key);
+        report
+    }
+
+    pub fn compare_services(
+        source: &ServiceCountMap,
+        target: &ServiceCountMap,
+    ) -> ServiceCompareReport {
+        let mut report = ServiceCompareReport::default();
+        let diff = |a: &ServiceCountMap, b: &ServiceCountMap| {
+            let mut list: Vec<ServiceCompareItem> = a
+                .iter()
+                .filter(|(key, _)| !b.contains_key(*key))
+                .map(|((group_name, service_name), count)| ServiceCompareItem {
+                    group_name: group_name.clone(),
+                    service_name: service_name.clone(),
+                    instance_count: *count,
+                })
+                .collect();
+            list.sort_by_key(|e| (e.group_name.clone(), e.service_name.clone()));
+            list
+        };
+        report.only_in_source = diff(source, target);
+        report.only_in_target = diff(target, source);
+        report.same_count = source.len() - report.only_in_source.len();
+        report
+    }
+
+    async fn query_config_md5s(
+        app: &AppShareData,
+        namespace_id: &str,
+    ) -> anyhow::Result<ConfigMd5Map> {
+        let param = ConfigQueryParam {
+            tenant: Some(Arc::new(ConfigUtils::default_tenant(
+                namespace_id.to_owned(),
+            ))),
+            query_context: true,
+            limit: 0xffff_ffff,
+            ..Default::default()
+        };
+        let mut map = HashMap::new();
+        if let ConfigResult::ConfigInfoPage(_, list) = app
+            .config_addr
+            .send(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await??
+        {
+            for item in list {
+                map.insert((item.group, item.data_id), item.md5.unwrap_or_default());
+            }
+        }
+        Ok(map)
+    }
+
+    async fn query_service_counts(
+        app: &AppShareData,
+        namespace_id: &str,
+    ) -> anyhow::Result<ServiceCountMap> {
+        let namespace_id = NamingUtils::default_namespace(namespace_id.to_owned());
+        let mut map = HashMap::new();
+        if let NamingResult::ServiceInstanceCounts(counts) = app
+            .naming_addr
+            .send(NamingCmd::QueryServiceInstanceCounts)
+            .await??
+        {
+            for (key, instance_count, _) in counts {
+                if key.namespace_id.as_str() == namespace_id {
+                    map.insert((key.group_name, key.service_name), instance_count);
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    pub async fn compare(
+        app: &AppShareData,
+        source_namespace_id: String,
+        target_namespace_id: String,
+    ) -> anyhow::Result<NamespaceCompareReport> {
+        let configs = Self::compare_configs(
+            &Self::query_config_md5s(app, &source_namespace_id).await?,
+            &Self::query_config_md5s(app, &target_namespace_id).await?,
+        );
+        let services = Self::compare_services(
+            &Self::query_service_counts(app, &source_namespace_id).await?,
+            &Self::query_service_counts(app, &target_namespace_id).await?,
+        );
+        let consistent = configs.only_in_source.is_empty()
+            && configs.only_in_target.is_empty()
+            && configs.different.is_empty()
+            && services.only_in_source.is_empty()
+            && services.only_in_target.is_empty();
+        Ok(NamespaceCompareReport {
+            source_namespace_id,
+            target_namespace_id,
+            consistent,
+            configs,
+            services,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(group: &str, name: &str) -> (Arc<String>, Arc<String>) {
+        (Arc::new(group.to_owned()), Arc::new(name.to_owned()))
+    }
+
+    #[test]
+    fn test_namespace_compare() {
+        let md5 = |v: &str| Arc::new(v.to_owned());
+        let source: ConfigMd5Map = vec![
+            (key("G", "a"), md5("1")),
+            (key("G", "b"), md5("2")),
+            (key("G", "c"), md5("3")),
+        ]
+        .into_iter()
+        .collect();
+        let target: ConfigMd5Map = vec![
+            (key("G", "a"), md5("1")),
+            (key("G", "b"), md5("x")),
+            (key("G", "d"), md5("4")),
+        ]
+        .into_iter()
+        .collect();
+        let report = NamespaceCompareUtils::compare_configs(&source, &target);
+        assert_eq!(report.same_count, 1);
+        assert_eq!(report.different[0].data_id.as_str(), "b");
+        assert_eq!(report.only_in_source[0].data_id.as_str(), "c");
+        assert_eq!(report.only_in_target[0].data_id.as_str(), "d");
+
+        let source: ServiceCountMap = vec![(key("G", "api"), 2), (key("G", "web"), 1)]
+            .into_iter()
+            .collect();
+        let target: ServiceCountMap = vec![(key("G", "api"), 3)].into_iter().collect();
+        let report = NamespaceCompareUtils::compare_services(&source, &target);
+        assert_eq!(report.same_count, 1);
+        assert_eq!(report.only_in_source[0].service_name.as_str(), "web");
+        assert!(report.only_in_target.is_empty());
+    }
+}
@@ -13,7 +13,10 @@ use crate::{
         constant::{APP_VERSION, EMPTY_STR},
         model::{ApiResult, PageResultOld, UserSession},
     },
-    user::{model::UserDto, permission::UserRole, UserManagerReq, UserManagerResult},
+    user::{
+        model::UserDto, permission::UserRole, preference::UserPreferenceUtils, UserManagerReq,
+        UserManagerResult,
+    },
 };
 
 use super::model::user_model::{UpdateUserInfoParam, UserInfo, UserPageParams, UserPermissions};
@@ -180,9 +183,13 @@ pub async fn remove_user(
     app: Data<Arc<AppShareData>>,
     web::Form(user): web::Form<UpdateUserInfoParam>,
 ) -> actix_web::Result<impl Responder> {
+    let username = user.username;
     let msg = UserManagerReq::Remove {
-        username: user.username,
+        username: username.clone(),
     };
     app.user_manager.send(msg).await.ok();
+    UserPreferenceUtils::remove_user_preferences(&app, &username)
+        .await
+        .ok();
     Ok(HttpResponse::Ok().json(ApiResult::success(Some(true))))
 }
@@ -0,0 +1,36 @@
+use crate::common::appdata::AppShareData;
+use crate::common::model::ApiResult;
+use crate::common::sdk_usage::{SdkUsageCmd, SdkUsageResult};
+use crate::common::string_utils::StringUtils;
+use crate::console::model::client_model::SdkUsageQueryRequest;
+use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+
+pub async fn query_sdk_usage(
+    app: web::Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<SdkUsageQueryRequest>,
+) -> impl Responder {
+    let namespace_id = StringUtils::map_not_empty(param.namespace_id).map(Arc::new);
+    match app
+        .sdk_usage_manage
+        .send(SdkUsageCmd::QueryReport(namespace_id))
+        .await
+    {
+        Ok(Ok(SdkUsageResult::Report(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(list)))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
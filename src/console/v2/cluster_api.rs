@@ -1,7 +1,18 @@
 use crate::common::appdata::AppShareData;
+use crate::common::cluster_settings::{ClusterSettings, SYSCONFIG_CLUSTER_SETTINGS_KEY};
+use crate::common::dead_letter::{DeadLetterCmd, DeadLetterUtils};
 use crate::common::model::ApiResult;
-use crate::console::model::cluster_model::ClusterNodeInfo;
-use actix_web::{web, HttpResponse, Responder};
+use crate::common::support_bundle::SupportBundleUtils;
+use crate::config::core::ConfigKey;
+use crate::console::model::cluster_model::{ClusterNodeInfo, DeadLetterParam};
+use crate::console::{SYSCONFIG_GROUP, SYSCONFIG_NAMESPACE};
+use crate::grpc::bistream_manage::{BiStreamManageCmd, BiStreamManageResult};
+use crate::naming::zone::ZoneUtils;
+use crate::now_millis_i64;
+use crate::raft::cluster::model::SetConfigReq;
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub async fn query_cluster_info(app: web::Data<Arc<AppShareData>>) -> impl Responder {
@@ -22,3 +33,191 @@ pub async fn query_cluster_info(app: web::Data<Arc<AppShareData>>) -> impl Respo
     }
     HttpResponse::Ok().json(ApiResult::success(Some(list)))
 }
+
+pub async fn query_cluster_settings(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let settings = app.cluster_settings.get_settings();
+    HttpResponse::Ok().json(ApiResult::success(Some(settings.as_ref().clone())))
+}
+
+///
+/// 设置集群共享的服务端设置,通过raft同步到所有节点
+pub async fn set_cluster_settings(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(settings): web::Json<ClusterSettings>,
+) -> impl Responder {
+    if let Err(e) = settings.check() {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let value = match serde_json::to_string(&settings) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                "SYSTEM_ERROR".to_string(),
+                Some(e.to_string()),
+            ))
+        }
+    };
+    let req = SetConfigReq::new(
+        ConfigKey::new(
+            SYSCONFIG_CLUSTER_SETTINGS_KEY,
+            SYSCONFIG_GROUP,
+            SYSCONFIG_NAMESPACE,
+        ),
+        Arc::new(value),
+    );
+    match app.config_route.set_config(req).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 关闭本节点:停止对外监听后停止raft并把日志写入磁盘,完成后进程退出;
+/// 本机反向代理转发的请求带有转发头,不视为本机请求
+pub async fn shutdown_node(req: HttpRequest, app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let headers = req.headers();
+    let is_forwarded = headers.contains_key("X-Forwarded-For")
+        || headers.contains_key("X-Real-IP")
+        || headers.contains_key("Forwarded");
+    let is_local = !is_forwarded
+        && req
+            .peer_addr()
+            .map(|v| v.ip().is_loopback())
+            .unwrap_or(false);
+    if app.sys_config.shutdown_api_local_only && !is_local {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some("shutdown is only allowed from the local host".to_owned()),
+        ));
+    }
+    if app.shutdown_manager.request_shutdown() {
+        log::warn!(
+            "shutdown requested by console api, peer:{:?}",
+            req.peer_addr()
+        );
+    }
+    HttpResponse::Ok().json(ApiResult::success(Some(app.shutdown_manager.get_state())))
+}
+
+///
+/// 按可用区重新分配本节点的grpc连接:客户端所在可用区存在其它节点时,通知客户端重连到该可用区的节点
+pub async fn rebalance_by_zone(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let local_zone = match ZoneUtils::get_zone(&app.sys_config.node_labels) {
+        Some(v) => Arc::new(v.to_owned()),
+        None => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                "SYSTEM_ERROR".to_string(),
+                Some("the node zone label is not set".to_owned()),
+            ))
+        }
+    };
+    let nodes = match app.naming_node_manage.get_other_valid_nodes().await {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                "SYSTEM_ERROR".to_string(),
+                Some(e.to_string()),
+            ))
+        }
+    };
+    let mut zone_addrs = HashMap::new();
+    for node in nodes {
+        if let (Some(zone), Some(addr)) = (
+            ZoneUtils::get_zone(&node.labels),
+            ZoneUtils::to_client_server_addr(&node.addr),
+        ) {
+            zone_addrs.entry(zone.to_owned()).or_insert(addr);
+        }
+    }
+    match app
+        .bi_stream_manage
+        .send(BiStreamManageCmd::RebalanceByZone(local_zone, zone_addrs))
+        .await
+    {
+        Ok(Ok(BiStreamManageResult::ResetCount(count))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(count)))
+        }
+        Ok(Err(e)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+        Ok(_) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some("rebalance result error".to_owned()),
+        )),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 下载当前节点的诊断信息包,用于问题反馈
+pub async fn download_support_bundle(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    match SupportBundleUtils::build(&app).await {
+        Ok(data) => {
+            let filename = format!("rnacos_support_bundle_{}.zip", now_millis_i64());
+            HttpResponse::Ok()
+                .insert_header(header::ContentType::octet_stream())
+                .insert_header(header::ContentDisposition::attachment(filename))
+                .body(data)
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 查询重试耗尽后未投递成功的webhook与udp推送
+pub async fn query_dead_letters(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    match DeadLetterUtils::query_list(&app).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn requeue_dead_letters(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<DeadLetterParam>,
+) -> impl Responder {
+    match DeadLetterUtils::requeue(&app, param.ids).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResult::success(Some(result))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn purge_dead_letters(
+    app: web::Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<DeadLetterParam>,
+) -> impl Responder {
+    match app
+        .dead_letter_manager
+        .send(DeadLetterCmd::Remove(param.ids))
+        .await
+    {
+        Ok(Ok(_)) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Ok(Err(e)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
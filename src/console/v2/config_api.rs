@@ -1,14 +1,44 @@
 use crate::common::appdata::AppShareData;
+use crate::common::model::UserSession;
 use crate::common::model::{ApiResult, PageResult};
-use crate::config::core::{ConfigActor, ConfigCmd, ConfigResult};
-use crate::console::model::config_model::{ConfigInfo, ConfigParams, OpsConfigQueryListRequest};
+use crate::common::string_utils::StringUtils;
+use crate::config::beta::{ConfigBetaDto, ConfigBetaUtils};
+use crate::config::config_clone::ConfigCloneUtils;
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::core::{ConfigActor, ConfigCmd, ConfigKey, ConfigResult};
+use crate::config::dry_run::ConfigDryRunUtils;
+use crate::config::encryption::ConfigEncryptionManager;
+use crate::config::freeze::{ConfigFreezeRule, ConfigFreezeUtils};
+use crate::config::model::ListenerConnectionDto;
+use crate::config::nacos_zip::ConfigImportPolicy;
+use crate::config::rollback::ConfigRollbackUtils;
+use crate::config::schedule::{ConfigScheduleUtils, ScheduledConfigChange};
+use crate::config::tag::ConfigTagUtils;
+use crate::config::template::{
+    ConfigTemplate, ConfigTemplateUtils, ConfigTemplateVariableSet, SYSCONFIG_CONFIG_TEMPLATE_KEY,
+    SYSCONFIG_CONFIG_TEMPLATE_VARIABLES_KEY,
+};
+use crate::config::time_travel::{ConfigAsOfReport, ConfigTimeTravelUtils};
+use crate::config::utils::param_utils;
+use crate::config::ConfigUtils;
+use crate::console::model::config_model::{
+    ConfigAsOfParams, ConfigBetaParams, ConfigCloneParams, ConfigHistoryRollbackParams, ConfigInfo,
+    ConfigParams, ConfigScheduleParams, ConfigScheduleQueryParams, ConfigTagParams,
+    ConfigTemplateRemoveParams, ListenerConnectionQueryParam, OpsConfigQueryListRequest,
+};
+use crate::now_millis;
 use actix::Addr;
+use actix_http::HttpMessage;
+use actix_web::http::header;
 use actix_web::web::Data;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use std::sync::Arc;
 
+use crate::common::resource_event::ResourceKey;
 pub use crate::console::config_api::{download_config, import_config};
-use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::console::v2::{record_audit, ERROR_CODE_SYSTEM_ERROR};
+use crate::console::NamespaceUtils;
+use crate::openapi::auth::is_manager_request;
 use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
 
 pub async fn query_config_list(
@@ -73,7 +103,7 @@ pub(crate) async fn get_config(
     appdata: Data<Arc<AppShareData>>,
 ) -> impl Responder {
     let config_key = param.to_key();
-    let cmd = ConfigCmd::GET(config_key);
+    let cmd = ConfigCmd::GetRaw(config_key);
     if let Ok(Ok(ConfigResult::Data {
         value: v,
         md5,
@@ -97,21 +127,434 @@ pub(crate) async fn get_config(
 }
 
 pub async fn add_config(
+    req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
     web::Json(param): web::Json<ConfigParams>,
 ) -> impl Responder {
     let content = param.content.clone().unwrap_or_default();
     let config_key = param.to_key();
-    if let Err(e) = config_key.is_valid() {
+    if let Err(e) = config_key
+        .is_valid()
+        .and_then(|_| param_utils::check_reserved(&config_key, false, false))
+        .and_then(|_| check_namespace_write(&req, &appdata, &config_key.tenant))
+    {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let mut set_req = SetConfigReq::new(config_key.clone(), content);
+    set_req.config_type = param.config_type;
+    set_req.desc = param.desc;
+    set_req.op_user = get_op_user(&req);
+    set_req.freeze_override = is_freeze_override(&req, &appdata, param.freeze_override);
+    set_req.skip_validation = param.force.unwrap_or(false);
+    match appdata.config_route.set_config(set_req).await {
+        Ok(_) => {
+            record_audit(
+                &req,
+                &appdata,
+                ResourceKey::config(&config_key),
+                "publish",
+                None,
+            );
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 预览回滚到历史版本时的内容差异
+pub async fn query_history_diff(
+    web::Query(param): web::Query<ConfigHistoryRollbackParams>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match ConfigRollbackUtils::diff(&appdata, &param.to_key(), param.id).await {
+        Ok(diff) => HttpResponse::Ok().json(ApiResult::success(Some(diff))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 按历史记录还原命名空间(或单个配置)在指定时间点生效的配置
+pub async fn query_config_as_of(
+    web::Query(param): web::Query<ConfigAsOfParams>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let time = match ConfigTimeTravelUtils::parse_time(
+        param.time.as_deref().unwrap_or_default(),
+        &appdata.timezone_offset,
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(e.to_string()),
+            ))
+        }
+    };
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let query_param = ConfigQueryParam {
+        tenant: Some(Arc::new(tenant.clone())),
+        group: param.group.filter(|v| !v.is_empty()).map(Arc::new),
+        data_id: param.data_id.filter(|v| !v.is_empty()).map(Arc::new),
+        query_context: param.with_content.unwrap_or(false),
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryAsOf(Box::new(query_param), time);
+    match appdata.config_addr.send(cmd).await {
+        Ok(Ok(ConfigResult::ConfigAsOfList(list))) => HttpResponse::Ok().json(ApiResult::success(
+            Some(ConfigAsOfReport::new(tenant, time, list)),
+        )),
+        Ok(Err(e)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 查询待生效的定时发布
+pub async fn query_config_schedules(
+    web::Query(param): web::Query<ConfigScheduleQueryParams>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match ConfigScheduleUtils::query(&appdata.config_addr).await {
+        Ok(mut list) => {
+            if let Some(tenant) = param.tenant {
+                let tenant = ConfigUtils::default_tenant(tenant);
+                list.retain(|e| e.tenant == tenant);
+            }
+            HttpResponse::Ok().json(ApiResult::success(Some(list)))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 新增定时发布,到达生效时间后写入配置并通知监听者
+pub async fn add_config_schedule(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigScheduleParams>,
+) -> impl Responder {
+    let effective_time = match ConfigTimeTravelUtils::parse_time(
+        param.effective_time.as_deref().unwrap_or_default(),
+        &appdata.timezone_offset,
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(e.to_string()),
+            ))
+        }
+    };
+    let op_user = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone());
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    if let Err(e) = check_namespace_write(&req, &appdata, &tenant) {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let item = ScheduledConfigChange {
+        tenant,
+        group: param
+            .group
+            .filter(|v| !v.is_empty())
+            .unwrap_or("DEFAULT_GROUP".to_owned()),
+        data_id: param.data_id,
+        content: param.content.unwrap_or_default(),
+        config_type: param.config_type,
+        desc: param.desc,
+        effective_time,
+        op_user,
+        ..Default::default()
+    };
+    match ConfigScheduleUtils::add(&appdata, item).await {
+        Ok(item) => {
+            record_audit(
+                &req,
+                &appdata,
+                ResourceKey::config(&item.get_config_key()),
+                "schedule",
+                Some(format!("effective time: {}", item.effective_time)),
+            );
+            HttpResponse::Ok().json(ApiResult::success(Some(item)))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 取消尚未生效的定时发布
+pub async fn cancel_config_schedule(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigScheduleQueryParams>,
+) -> impl Responder {
+    match ConfigScheduleUtils::cancel(&appdata, param.id.as_deref().unwrap_or_default()).await {
+        Ok(Some(item)) => {
+            record_audit(
+                &req,
+                &appdata,
+                ResourceKey::config(&item.get_config_key()),
+                "cancel_schedule",
+                Some(format!("schedule id: {}", &item.id)),
+            );
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
+        }
+        Ok(None) => HttpResponse::Ok().json(ApiResult::success(Some(false))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 查询配置模板
+pub async fn query_config_templates(appdata: Data<Arc<AppShareData>>) -> impl Responder {
+    match ConfigTemplateUtils::query_templates(&appdata.config_addr).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 新增或更新配置模板,并发布渲染后的配置;返回发布的配置数量
+pub async fn set_config_template(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(template): web::Json<ConfigTemplate>,
+) -> impl Responder {
+    let op_user = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone());
+    let name = template.name.clone();
+    let is_manager = is_manager_request(&req, &appdata.sys_config);
+    match ConfigTemplateUtils::set_template(&appdata, template, op_user, is_manager).await {
+        Ok(keys) => {
+            for key in &keys {
+                record_audit(
+                    &req,
+                    &appdata,
+                    ResourceKey::config(key),
+                    "template",
+                    Some(format!("template: {}", &name)),
+                );
+            }
+            HttpResponse::Ok().json(ApiResult::success(Some(keys.len())))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn remove_config_template(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigTemplateRemoveParams>,
+) -> impl Responder {
+    match ConfigTemplateUtils::remove_template(&appdata, &param.name).await {
+        Ok(v) => {
+            if v {
+                record_audit(
+                    &req,
+                    &appdata,
+                    ResourceKey::config(&ConfigTemplateUtils::build_key(
+                        SYSCONFIG_CONFIG_TEMPLATE_KEY,
+                    )),
+                    "remove_template",
+                    Some(format!("template: {}", &param.name)),
+                );
+            }
+            HttpResponse::Ok().json(ApiResult::success(Some(v)))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 查询各命名空间的模板变量
+pub async fn query_config_template_variables(appdata: Data<Arc<AppShareData>>) -> impl Responder {
+    match ConfigTemplateUtils::query_variable_sets(&appdata.config_addr).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 设置命名空间的模板变量,并重新发布该命名空间下由模板生成的配置
+pub async fn set_config_template_variables(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(variable_set): web::Json<ConfigTemplateVariableSet>,
+) -> impl Responder {
+    let op_user = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone());
+    let namespace_id = variable_set.namespace_id.clone();
+    let is_manager = is_manager_request(&req, &appdata.sys_config);
+    match ConfigTemplateUtils::set_variables(&appdata, variable_set, op_user, is_manager).await {
+        Ok(keys) => {
+            record_audit(
+                &req,
+                &appdata,
+                ResourceKey::config(&ConfigTemplateUtils::build_key(
+                    SYSCONFIG_CONFIG_TEMPLATE_VARIABLES_KEY,
+                )),
+                "template_variables",
+                Some(format!("namespace: {}", &namespace_id)),
+            );
+            for key in &keys {
+                record_audit(
+                    &req,
+                    &appdata,
+                    ResourceKey::config(key),
+                    "template",
+                    Some(format!(
+                        "template variables of namespace: {}",
+                        &namespace_id
+                    )),
+                );
+            }
+            HttpResponse::Ok().json(ApiResult::success(Some(keys.len())))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 回滚配置到历史版本,记录回滚操作人
+pub async fn rollback_config_history(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigHistoryRollbackParams>,
+) -> impl Responder {
+    let config_key = param.to_key();
+    if let Err(e) = check_namespace_write(&req, &appdata, &config_key.tenant) {
         return HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
             Some(e.to_string()),
         ));
     }
-    let mut req = SetConfigReq::new(config_key, content);
-    req.config_type = param.config_type;
-    req.desc = param.desc;
-    if appdata.config_route.set_config(req).await.is_ok() {
+    let freeze_override = is_freeze_override(&req, &appdata, param.freeze_override);
+    match ConfigRollbackUtils::rollback(
+        &appdata,
+        &config_key,
+        param.id,
+        param.current_md5,
+        get_op_user(&req),
+        freeze_override,
+    )
+    .await
+    {
+        Ok(_) => {
+            record_audit(
+                &req,
+                &appdata,
+                ResourceKey::config(&config_key),
+                "rollback",
+                Some(format!("history id: {}", param.id)),
+            );
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
+        }
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 预演发布配置,返回校验结果、内容差异与会被通知的订阅数量,不实际写入
+pub async fn publish_config_dry_run(
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigParams>,
+) -> impl Responder {
+    let content = param.content.clone().unwrap_or_default();
+    let config_type = param.config_type.as_ref().map(|v| v.as_str());
+    match ConfigDryRunUtils::publish_dry_run(&appdata, param.to_key(), content, config_type).await {
+        Ok(impact) => HttpResponse::Ok().json(ApiResult::success(Some(impact))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn remove_config(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigParams>,
+) -> impl Responder {
+    let config_key = param.to_key();
+    if let Err(e) = param_utils::check_reserved(&config_key, true, false)
+        .and_then(|_| check_namespace_write(&req, &appdata, &config_key.tenant))
+    {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    if let Err(e) = check_freeze(&req, &appdata, &config_key, &param).await {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let del_req = DelConfigReq::new(config_key.clone());
+    if appdata.config_route.del_config(del_req).await.is_ok() {
+        record_audit(
+            &req,
+            &appdata,
+            ResourceKey::config(&config_key),
+            "remove",
+            None,
+        );
         HttpResponse::Ok().json(ApiResult::success(Some(true)))
     } else {
         HttpResponse::Ok().json(ApiResult::<()>::error(
@@ -121,13 +564,246 @@ pub async fn add_config(
     }
 }
 
-pub async fn remove_config(
+fn get_op_user(req: &HttpRequest) -> Option<Arc<String>> {
+    req.extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone())
+}
+
+///
+/// 管理员可以通过freezeOverride在变更冻结期内强制变更
+fn is_freeze_override(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    freeze_override: Option<bool>,
+) -> bool {
+    freeze_override.unwrap_or(false) && is_manager_request(req, &appdata.sys_config)
+}
+
+///
+/// 系统命名空间中的配置只有管理员可以修改
+fn check_namespace_write(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    namespace_id: &str,
+) -> anyhow::Result<()> {
+    NamespaceUtils::check_config_write(
+        &appdata.sys_config,
+        namespace_id,
+        is_manager_request(req, &appdata.sys_config),
+    )
+}
+
+///
+/// 检查删除配置时的变更冻结期;发布配置的检查在写入时统一处理
+async fn check_freeze(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    config_key: &ConfigKey,
+    param: &ConfigParams,
+) -> anyhow::Result<()> {
+    let operator = get_op_user(req)
+        .map(|v| v.as_str().to_owned())
+        .unwrap_or_default();
+    let allow_override = is_freeze_override(req, appdata, param.freeze_override);
+    ConfigFreezeUtils::check(appdata, config_key, &operator, allow_override).await
+}
+
+///
+/// 查询配置变更冻结规则
+pub async fn get_freeze_rules(appdata: Data<Arc<AppShareData>>) -> impl Responder {
+    match ConfigFreezeUtils::get_rules(&appdata.config_addr).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResult::success(Some(rules))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 设置配置变更冻结规则,通过raft同步到集群
+pub async fn set_freeze_rules(
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(rules): web::Json<Vec<ConfigFreezeRule>>,
+) -> impl Responder {
+    match ConfigFreezeUtils::set_rules(&appdata, rules).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 加密密钥轮换后,按当前密钥重新加密全部cipher-配置,返回处理的配置数量
+pub async fn rotate_config_encryption(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    if appdata.sys_config.config_encryption_keys.is_empty() {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some("config encryption is not enabled".to_owned()),
+        ));
+    }
+    let op_user = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone());
+    match ConfigEncryptionManager::reencrypt_all(&appdata, op_user).await {
+        Ok(count) => HttpResponse::Ok().json(ApiResult::success(Some(count))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 把配置从源命名空间复制到目标命名空间,全部配置在同一条raft日志中写入
+pub async fn clone_configs(
+    req: HttpRequest,
+    web::Json(param): web::Json<ConfigCloneParams>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let policy = match param.policy.as_ref() {
+        Some(v) => match ConfigImportPolicy::new_by_value(v) {
+            Some(policy) => policy,
+            None => {
+                return HttpResponse::Ok().json(ApiResult::<()>::error(
+                    ERROR_CODE_SYSTEM_ERROR.to_string(),
+                    Some(format!("unknown clone policy {}", v)),
+                ))
+            }
+        },
+        None => ConfigImportPolicy::Abort,
+    };
+    let source_tenant = Arc::new(ConfigUtils::default_tenant(
+        param.source_tenant.unwrap_or_default(),
+    ));
+    let target_tenant = Arc::new(ConfigUtils::default_tenant(
+        param.target_tenant.unwrap_or_default(),
+    ));
+    if let Err(e) = check_namespace_write(&req, &appdata, &target_tenant) {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let selected_keys = param
+        .config_keys
+        .map(|keys| keys.into_iter().map(|e| e.trim().to_owned()).collect());
+    let op_user = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.clone());
+    match ConfigCloneUtils::clone_configs(
+        &appdata,
+        source_tenant,
+        target_tenant,
+        selected_keys,
+        policy,
+        op_user,
+    )
+    .await
+    {
+        Ok(result) => HttpResponse::Ok().json(ApiResult::success(Some(result))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 查询配置的beta内容与客户端标签规则
+pub(crate) async fn get_config_beta(
+    web::Query(param): web::Query<ConfigParams>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let beta_key = ConfigBetaUtils::build_beta_key(&param.to_key());
+    let beta = match appdata.config_addr.send(ConfigCmd::GET(beta_key)).await {
+        Ok(Ok(ConfigResult::Data { value, .. })) => {
+            serde_json::from_str::<ConfigBetaDto>(&value).ok()
+        }
+        _ => None,
+    };
+    HttpResponse::Ok().json(ApiResult::success(beta))
+}
+
+///
+/// 发布beta配置,只对ip在beta_ips中或连接标签全部匹配的客户端生效
+pub async fn publish_config_beta(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigBetaParams>,
+) -> impl Responder {
+    let config_key = param.to_key();
+    if let Err(e) = config_key
+        .is_valid()
+        .and_then(|_| check_namespace_write(&req, &appdata, &config_key.tenant))
+    {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let labels = param.labels.unwrap_or_default();
+    let beta_ips: Vec<String> = param
+        .beta_ips
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|e| !e.trim().is_empty())
+        .collect();
+    if labels.is_empty() && beta_ips.is_empty() {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some("beta labels and betaIps are empty".to_owned()),
+        ));
+    }
+    let dto = ConfigBetaDto {
+        content: param.content.unwrap_or_default(),
+        labels,
+        beta_ips,
+    };
+    let value = match serde_json::to_string(&dto) {
+        Ok(v) => v,
+        Err(e) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(e.to_string()),
+            ));
+        }
+    };
+    let set_req = SetConfigReq::new(
+        ConfigBetaUtils::build_beta_key(&config_key),
+        Arc::new(value),
+    );
+    match appdata.config_route.set_config(set_req).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn remove_config_beta(
+    req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
     web::Json(param): web::Json<ConfigParams>,
 ) -> impl Responder {
     let config_key = param.to_key();
-    let req = DelConfigReq::new(config_key);
-    if appdata.config_route.del_config(req).await.is_ok() {
+    if let Err(e) = check_namespace_write(&req, &appdata, &config_key.tenant) {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let del_req = DelConfigReq::new(ConfigBetaUtils::build_beta_key(&config_key));
+    if appdata.config_route.del_config(del_req).await.is_ok() {
         HttpResponse::Ok().json(ApiResult::success(Some(true)))
     } else {
         HttpResponse::Ok().json(ApiResult::<()>::error(
@@ -136,3 +812,163 @@ pub async fn remove_config(
         ))
     }
 }
+
+///
+/// 查询配置下的全部tag及内容
+pub(crate) async fn query_config_tags(
+    web::Query(param): web::Query<ConfigParams>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match ConfigTagUtils::query_tags(&appdata, &param.to_key(), true).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn publish_config_tag(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigTagParams>,
+) -> impl Responder {
+    let config_key = param.to_key();
+    if let Err(e) = config_key
+        .is_valid()
+        .and_then(|_| check_namespace_write(&req, &appdata, &config_key.tenant))
+    {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    let content = param.content.unwrap_or_default();
+    match ConfigTagUtils::publish(
+        &appdata,
+        &config_key,
+        &param.tag,
+        content,
+        param.config_type,
+        param.force.unwrap_or(false),
+    )
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn remove_config_tag(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ConfigTagParams>,
+) -> impl Responder {
+    let config_key = param.to_key();
+    if let Err(e) = check_namespace_write(&req, &appdata, &config_key.tenant) {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        ));
+    }
+    match ConfigTagUtils::remove(&appdata, &config_key, &param.tag).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+fn listener_connection_to_csv(list: &[ListenerConnectionDto]) -> String {
+    let mut csv = String::from("ip,connection,listenType,appName,group,dataId,md5,stale\n");
+    for item in list {
+        for config in &item.configs {
+            let row = [
+                StringUtils::csv_field(&item.ip),
+                StringUtils::csv_field(&item.connection),
+                StringUtils::csv_field(&item.listen_type),
+                StringUtils::csv_field(&item.app_name),
+                StringUtils::csv_field(&config.group),
+                StringUtils::csv_field(&config.data_id),
+                StringUtils::csv_field(&config.md5),
+                config.stale.to_string(),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+///
+/// 查询本节点上监听指定配置的客户端连接(ip、应用名、连接类型)及其最近收到的md5
+pub async fn query_config_listeners(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ConfigParams>,
+) -> impl Responder {
+    match appdata
+        .config_addr
+        .send(ConfigCmd::QueryConfigListenerConnections(param.to_key()))
+        .await
+    {
+        Ok(Ok(ConfigResult::ListenerConnectionMap(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(list)))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 导出本节点上客户端连接与监听配置的对应关系,format=csv时导出csv文件
+pub async fn query_listener_connection_map(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ListenerConnectionQueryParam>,
+) -> impl Responder {
+    let tenant = Arc::new(ConfigUtils::default_tenant(
+        param.tenant.unwrap_or_default(),
+    ));
+    match appdata
+        .config_addr
+        .send(ConfigCmd::QueryListenerConnectionMap(tenant))
+        .await
+    {
+        Ok(Ok(ConfigResult::ListenerConnectionMap(list))) => {
+            if param.format.as_deref() == Some("csv") {
+                let filename = format!("rnacos_config_listeners_{}.csv", now_millis());
+                HttpResponse::Ok()
+                    .insert_header(header::ContentType(mime::TEXT_CSV_UTF_8))
+                    .insert_header(header::ContentDisposition::attachment(filename))
+                    .body(listener_connection_to_csv(&list))
+            } else {
+                HttpResponse::Ok().json(ApiResult::success(Some(list)))
+            }
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
@@ -1,16 +1,41 @@
-use crate::common::model::ApiResult;
-use actix_web::HttpResponse;
+use crate::common::appdata::AppShareData;
+use crate::common::model::{ApiResult, UserSession};
+use crate::common::resource_event::ResourceKey;
+use actix_http::HttpMessage;
+use actix_web::{HttpRequest, HttpResponse};
+use std::sync::Arc;
 
+pub mod client_api;
 pub mod cluster_api;
 pub mod config_api;
 pub mod login_api;
 pub mod metrics_api;
 pub mod namespace_api;
 pub mod naming_api;
+pub mod search_api;
+pub mod timeline_api;
 pub mod user_api;
 
 pub const ERROR_CODE_SYSTEM_ERROR: &str = "SYSTEM_ERROR";
 
+///
+/// 记录控制台用户对资源的操作,用于资源事件时间线
+pub(crate) fn record_audit(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    key: ResourceKey,
+    action: &str,
+    detail: Option<String>,
+) {
+    let operator = req
+        .extensions()
+        .get::<Arc<UserSession>>()
+        .map(|v| v.username.as_ref().to_owned());
+    appdata
+        .resource_event_store
+        .record_audit(key, action, operator, detail);
+}
+
 pub enum ApiResponse<T>
 where
     T: Sized + Default + serde::Serialize,
@@ -1,17 +1,65 @@
 use crate::common::appdata::AppShareData;
+use crate::common::group_rule::NamespaceGroupRule;
 use crate::common::model::ApiResult;
+use crate::common::namespace_quota::{NamespaceQuotaCmd, NamespaceQuotaInfo, NamespaceQuotaResult};
+use crate::common::service_link::ServiceLink;
 use crate::common::string_utils::StringUtils;
 use crate::config::core::ConfigActor;
-use crate::console::model::NamespaceInfo;
+use crate::console::model::{NamespaceInfo, NamespaceListItem};
+use crate::console::namespace_compare::{NamespaceCompareParam, NamespaceCompareUtils};
 use crate::console::NamespaceUtils;
+use crate::openapi::auth::is_manager_request;
 use actix::Addr;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
-pub async fn query_namespace_list(config_addr: web::Data<Addr<ConfigActor>>) -> impl Responder {
+pub async fn query_namespace_list(
+    config_addr: web::Data<Addr<ConfigActor>>,
+    app_data: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
     let namespaces = NamespaceUtils::get_namespaces(&config_addr).await;
-    HttpResponse::Ok().json(ApiResult::success(Some(namespaces)))
+    let warning_set: HashSet<Arc<String>> = query_quota_info_list(&app_data)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|e| !e.warnings.is_empty())
+        .map(|e| e.namespace_id)
+        .collect();
+    let list: Vec<NamespaceListItem> = namespaces
+        .into_iter()
+        .map(|info| {
+            let namespace_id = info.namespace_id.clone().unwrap_or_default();
+            NamespaceListItem {
+                quota_warning: warning_set.contains(&namespace_id),
+                info,
+            }
+        })
+        .collect();
+    HttpResponse::Ok().json(ApiResult::success(Some(list)))
+}
+
+async fn query_quota_info_list(
+    app_data: &Arc<AppShareData>,
+) -> anyhow::Result<Vec<NamespaceQuotaInfo>> {
+    match app_data
+        .namespace_quota_manager
+        .send(NamespaceQuotaCmd::QueryInfoList)
+        .await??
+    {
+        NamespaceQuotaResult::InfoList(list) => Ok(list),
+    }
+}
+
+pub async fn query_quota_usage(app_data: web::Data<Arc<AppShareData>>) -> impl Responder {
+    match query_quota_info_list(&app_data).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
 }
 
 pub async fn add_namespace(
@@ -32,10 +80,12 @@ pub async fn add_namespace(
 }
 
 pub async fn update_namespace(
+    req: HttpRequest,
     param: web::Json<NamespaceInfo>,
     app_data: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
-    match NamespaceUtils::update_namespace(&app_data, param.0).await {
+    let is_manager = is_manager_request(&req, &app_data.sys_config);
+    match NamespaceUtils::update_namespace(&app_data, param.0, is_manager).await {
         Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
         Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
             "SYSTEM_ERROR".to_string(),
@@ -56,3 +106,67 @@ pub async fn remove_namespace(
         )),
     }
 }
+
+pub async fn query_group_rules(config_addr: web::Data<Addr<ConfigActor>>) -> impl Responder {
+    match NamespaceUtils::get_group_rules(&config_addr).await {
+        Ok(rules) => HttpResponse::Ok().json(ApiResult::success(Some(rules))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn set_group_rule(
+    param: web::Json<NamespaceGroupRule>,
+    app_data: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match NamespaceUtils::set_group_rule(&app_data, param.0).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 比较两个命名空间的配置与服务差异
+pub async fn compare_namespaces(
+    web::Query(param): web::Query<NamespaceCompareParam>,
+    app_data: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let source_namespace_id = param.source_namespace_id.unwrap_or_default();
+    let target_namespace_id = param.target_namespace_id.unwrap_or_default();
+    match NamespaceCompareUtils::compare(&app_data, source_namespace_id, target_namespace_id).await
+    {
+        Ok(report) => HttpResponse::Ok().json(ApiResult::success(Some(report))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn query_service_links(config_addr: web::Data<Addr<ConfigActor>>) -> impl Responder {
+    match NamespaceUtils::get_service_links(&config_addr).await {
+        Ok(links) => HttpResponse::Ok().json(ApiResult::success(Some(links))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn set_service_link(
+    param: web::Json<ServiceLink>,
+    app_data: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match NamespaceUtils::set_service_link(&app_data, param.0).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
@@ -1,15 +1,32 @@
 use crate::common::appdata::AppShareData;
+use crate::common::metadata_schema::ServiceMetadataSchema;
 use crate::common::model::{ApiResult, PageResult};
+use crate::common::resource_event::ResourceKey;
+use crate::common::string_utils::StringUtils;
+use crate::common::web_utils::get_req_body;
 use crate::console::model::naming_model::{
-    InstanceParams, ServiceDto, ServiceParam, ServiceQueryListRequest,
+    InstanceIpQueryParam, InstanceParams, IpInstanceDto, NamespaceQueryParam, NamingBackupParam,
+    ServiceDto, ServiceHistoryQueryParam, ServiceMoveParam, ServiceParam, ServiceQueryListRequest,
 };
-use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::console::v2::{record_audit, ERROR_CODE_SYSTEM_ERROR};
+use crate::console::NamespaceUtils;
 use crate::naming::api_model::InstanceVO;
+use crate::naming::backup::{NamingBackup, NamingBackupImportResult};
+use crate::naming::change_history::ServiceChangeHistory;
+use crate::naming::cluster::model::{NamingRouteRequest, NamingRouterResponse};
+use crate::naming::cluster::query_other_nodes;
 use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
-use crate::naming::model::{InstanceUpdateTag, ServiceDetailDto};
+use crate::naming::instance_history::{InstanceHistoryCmd, InstanceHistoryResult};
+use crate::naming::model::{InstanceUpdateTag, ServiceDetailDto, ServiceKey};
+use crate::naming::persistent::{PersistentInstanceUtils, PersistentServiceUtils};
+use crate::naming::service_export::NacosServiceExport;
+use crate::naming::NamingUtils;
+use crate::openapi::naming::model::InstanceDrainWebParams;
+use crate::{now_millis, now_millis_i64};
 use actix::Addr;
+use actix_web::http::header;
 use actix_web::web::Data;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use std::sync::Arc;
 
 pub async fn query_service_list(
@@ -45,60 +62,183 @@ pub async fn query_service_list(
 }
 
 pub async fn add_service(
+    req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
     web::Json(param): web::Json<ServiceParam>,
 ) -> impl Responder {
+    save_service(&req, &appdata, param, "addService").await
+}
+
+///
+/// 修改已存在服务的metadata、保护阈值、选择器,通过raft同步到所有节点
+pub async fn update_service(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ServiceParam>,
+) -> impl Responder {
+    match query_service_detail_by_key(&appdata, param.to_key()).await {
+        Ok(Some(_)) => save_service(&req, &appdata, param, "updateService").await,
+        Ok(None) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some("service not found".to_owned()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+async fn save_service(
+    req: &HttpRequest,
+    appdata: &Arc<AppShareData>,
+    param: ServiceParam,
+    action: &str,
+) -> HttpResponse {
     let service_key = param.to_key();
+    let resource_key = ResourceKey::service(&service_key);
+    if let Some(Err(err)) = param.selector.as_ref().map(|v| v.build_label_selector()) {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        ));
+    }
     let service_info = ServiceDetailDto {
         namespace_id: service_key.namespace_id,
         service_name: service_key.service_name,
         group_name: service_key.group_name,
         metadata: param.metadata,
         protect_threshold: param.protect_threshold,
+        selector: param.selector,
     };
-    if let Ok(res) = appdata
+    match PersistentServiceUtils::update_service(appdata, service_info).await {
+        Ok(_) => {
+            record_audit(req, appdata, resource_key, action, None);
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
+        }
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+async fn query_service_detail_by_key(
+    appdata: &Arc<AppShareData>,
+    service_key: ServiceKey,
+) -> anyhow::Result<Option<ServiceDetailDto>> {
+    match appdata
         .naming_addr
-        .send(NamingCmd::UpdateService(service_info))
-        .await
+        .send(NamingCmd::QueryServiceDetail(service_key))
+        .await??
     {
-        match res {
-            Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
-            Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
-                ERROR_CODE_SYSTEM_ERROR.to_string(),
-                Some(err.to_string()),
-            )),
-        }
-    } else {
-        HttpResponse::Ok().json(ApiResult::<()>::error(
+        NamingResult::ServiceDetail(v) => Ok(v),
+        _ => Err(anyhow::anyhow!("query service detail result error")),
+    }
+}
+
+pub async fn query_service_detail(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ServiceParam>,
+) -> impl Responder {
+    match query_service_detail_by_key(&appdata, param.to_key()).await {
+        Ok(v) => HttpResponse::Ok().json(ApiResult::success(v)),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
-            None,
-        ))
+            Some(err.to_string()),
+        )),
     }
 }
 
 pub async fn remove_service(
+    req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
     web::Json(param): web::Json<ServiceParam>,
 ) -> impl Responder {
     let service_key = param.to_key();
-    if let Ok(res) = appdata
+    let resource_key = ResourceKey::service(&service_key);
+    let res = match appdata
         .naming_addr
-        .send(NamingCmd::RemoveService(service_key))
+        .send(NamingCmd::RemoveService(service_key.clone()))
         .await
     {
-        match res {
-            Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
-            Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
-                ERROR_CODE_SYSTEM_ERROR.to_string(),
-                Some(err.to_string()),
-            )),
+        Ok(Ok(_)) => PersistentServiceUtils::remove_service(&appdata, &service_key).await,
+        Ok(Err(err)) => Err(err),
+        Err(err) => Err(err.into()),
+    };
+    match res {
+        Ok(_) => {
+            record_audit(&req, &appdata, resource_key, "removeService", None);
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
         }
-    } else {
-        HttpResponse::Ok().json(ApiResult::<()>::error(
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
             ERROR_CODE_SYSTEM_ERROR.to_string(),
-            None,
-        ))
+            Some(err.to_string()),
+        )),
+    }
+}
+
+///
+/// 服务改名或移动到其它分组;实例与订阅方迁移到新服务,旧服务的订阅方收到一次空实例推送
+pub async fn move_service(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<ServiceMoveParam>,
+) -> impl Responder {
+    let old_key = param.to_key();
+    let new_key = param.to_target_key();
+    match do_move_service(&appdata, old_key.clone(), new_key.clone()).await {
+        Ok(_) => {
+            let detail = format!("{}@@{}", &new_key.group_name, &new_key.service_name);
+            record_audit(
+                &req,
+                &appdata,
+                ResourceKey::service(&old_key),
+                "moveService",
+                Some(detail),
+            );
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
+        }
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+async fn do_move_service(
+    appdata: &Arc<AppShareData>,
+    old_key: ServiceKey,
+    new_key: ServiceKey,
+) -> anyhow::Result<()> {
+    let (service_info, instances) = match appdata
+        .naming_addr
+        .send(NamingCmd::MoveService(old_key.clone(), new_key.clone()))
+        .await??
+    {
+        NamingResult::ServiceMoved(service_info, instances) => (service_info, instances),
+        _ => return Err(anyhow::anyhow!("move service result error")),
+    };
+    //持久化的服务定义与实例通过raft迁移,临时实例通过distro路由到负责的节点;
+    //先写入新服务再删除旧服务,中途失败时不丢失实例
+    if let Some(service_info) = &service_info {
+        PersistentServiceUtils::update_service(appdata, service_info.clone()).await?;
+    }
+    for old_instance in instances {
+        let mut instance = old_instance.clone();
+        instance.set_service_key(&new_key);
+        if instance.ephemeral {
+            appdata.naming_route.update_instance(instance, None).await?;
+            appdata.naming_route.delete_instance(old_instance).await?;
+        } else {
+            PersistentInstanceUtils::update_instance(appdata, instance, None).await?;
+            PersistentInstanceUtils::delete_instance(appdata, &old_instance).await?;
+        }
     }
+    if service_info.is_some() {
+        PersistentServiceUtils::remove_service(appdata, &old_key).await?;
+    }
+    Ok(())
 }
 
 pub async fn query_instances_list(
@@ -146,7 +286,7 @@ pub async fn get_instance(
                 let result: NamingResult = res.unwrap();
                 match result {
                     NamingResult::Instance(v) => {
-                        let vo = InstanceVO::from_instance(&v);
+                        let vo = InstanceVO::from_instance(&v).with_client_info(&v);
                         HttpResponse::Ok().json(ApiResult::success(Some(vo)))
                     }
                     _ => HttpResponse::Ok().json(ApiResult::<()>::error(
@@ -167,6 +307,7 @@ pub async fn get_instance(
     }
 }
 pub async fn add_instance(
+    req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
     web::Json(param): web::Json<InstanceParams>,
 ) -> impl Responder {
@@ -188,6 +329,7 @@ pub async fn add_instance(
             None => false,
         },
         from_update: true,
+        metadata_patch: None,
     };
     match param.to_instance() {
         Ok(instance) => {
@@ -197,12 +339,30 @@ pub async fn add_instance(
                     Some("instance check is invalid".to_string()),
                 ))
             } else {
-                match appdata
-                    .naming_route
-                    .update_instance(instance, Some(update_tag))
-                    .await
-                {
-                    Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+                let resource_key = ResourceKey::service(&instance.get_service_key());
+                let detail = Some(format!("{}:{}", &instance.ip, instance.port));
+                let res = match PersistentInstanceUtils::is_persistent(&appdata, &instance).await {
+                    Ok(true) => {
+                        PersistentInstanceUtils::update_instance(
+                            &appdata,
+                            instance,
+                            Some(update_tag),
+                        )
+                        .await
+                    }
+                    Ok(false) => {
+                        appdata
+                            .naming_route
+                            .update_instance(instance, Some(update_tag))
+                            .await
+                    }
+                    Err(err) => Err(err),
+                };
+                match res {
+                    Ok(_) => {
+                        record_audit(&req, &appdata, resource_key, "updateInstance", detail);
+                        HttpResponse::Ok().json(ApiResult::success(Some(true)))
+                    }
                     Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
                         ERROR_CODE_SYSTEM_ERROR.to_string(),
                         Some(err.to_string()),
@@ -218,6 +378,7 @@ pub async fn add_instance(
 }
 
 pub async fn remove_instance(
+    req: HttpRequest,
     appdata: Data<Arc<AppShareData>>,
     web::Json(param): web::Json<InstanceParams>,
 ) -> impl Responder {
@@ -229,8 +390,18 @@ pub async fn remove_instance(
                     Some("instance check is invalid".to_string()),
                 ))
             } else {
-                match appdata.naming_route.delete_instance(instance).await {
-                    Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+                let resource_key = ResourceKey::service(&instance.get_service_key());
+                let detail = Some(format!("{}:{}", &instance.ip, instance.port));
+                let res = match PersistentInstanceUtils::is_persistent(&appdata, &instance).await {
+                    Ok(true) => PersistentInstanceUtils::delete_instance(&appdata, &instance).await,
+                    Ok(false) => appdata.naming_route.delete_instance(instance).await,
+                    Err(err) => Err(err),
+                };
+                match res {
+                    Ok(_) => {
+                        record_audit(&req, &appdata, resource_key, "removeInstance", detail);
+                        HttpResponse::Ok().json(ApiResult::success(Some(true)))
+                    }
                     Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
                         ERROR_CODE_SYSTEM_ERROR.to_string(),
                         Some(err.to_string()),
@@ -244,3 +415,541 @@ pub async fn remove_instance(
         )),
     }
 }
+
+///
+/// 实例摘流,宽限期后自动删除
+pub async fn drain_instance(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<InstanceDrainWebParams>,
+) -> impl Responder {
+    let instance = match param.to_instance() {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err),
+            ))
+        }
+    };
+    let resource_key = ResourceKey::service(&instance.get_service_key());
+    let grace_millis = param.grace_millis();
+    let detail = Some(format!(
+        "{}:{},graceMillis:{}",
+        &instance.ip, instance.port, grace_millis
+    ));
+    match appdata
+        .naming_route
+        .drain_instance(instance, grace_millis)
+        .await
+    {
+        Ok(_) => {
+            record_audit(&req, &appdata, resource_key, "drainInstance", detail);
+            HttpResponse::Ok().json(ApiResult::success(Some(true)))
+        }
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+pub async fn query_service_subscribers(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ServiceParam>,
+) -> impl Responder {
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QuerySubscriberList(param.to_key()))
+        .await
+    {
+        Ok(Ok(NamingResult::SubscriberList(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+pub async fn query_namespace_stats(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamespaceQueryParam>,
+) -> impl Responder {
+    let namespace_id = StringUtils::map_not_empty(param.namespace_id).map(Arc::new);
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryNamespaceStats(namespace_id))
+        .await
+    {
+        Ok(Ok(NamingResult::NamespaceStats(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(list)))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+pub async fn query_instance_count_history(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ServiceHistoryQueryParam>,
+) -> impl Responder {
+    let end_time = param.end_time.unwrap_or_else(now_millis_i64);
+    let start_time = param.start_time.unwrap_or(end_time - 24 * 3600 * 1000);
+    match appdata
+        .instance_history
+        .send(InstanceHistoryCmd::Query(
+            param.to_key(),
+            start_time,
+            end_time,
+        ))
+        .await
+    {
+        Ok(Ok(InstanceHistoryResult::Points(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(list)))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+pub async fn query_metadata_schemas(appdata: Data<Arc<AppShareData>>) -> impl Responder {
+    match NamespaceUtils::get_metadata_schemas(&appdata.config_addr).await {
+        Ok(schemas) => HttpResponse::Ok().json(ApiResult::success(Some(schemas))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+pub async fn set_metadata_schema(
+    param: web::Json<ServiceMetadataSchema>,
+    appdata: Data<Arc<AppShareData>>,
+) -> impl Responder {
+    match NamespaceUtils::set_metadata_schema(&appdata, param.0).await {
+        Ok(_) => HttpResponse::Ok().json(ApiResult::success(Some(true))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 查询服务最近的实例变更事件(注册、注销、过期、健康状态变化)
+pub async fn query_service_change_history(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ServiceHistoryQueryParam>,
+) -> impl Responder {
+    let key = param.to_key();
+    let end_time = param.end_time.map(|v| v.max(0) as u64).unwrap_or(u64::MAX);
+    let start_time = param.start_time.map(|v| v.max(0) as u64).unwrap_or(0);
+    let req = NamingRouteRequest::query_service_change_history(&key, start_time, end_time);
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryServiceChangeHistory(
+            key, start_time, end_time,
+        ))
+        .await
+    {
+        Ok(Ok(NamingResult::ServiceChangeHistory(local_list))) => {
+            //实例变更事件只记录在实例归属的节点上,需要合并其它节点的记录
+            let mut lists = vec![local_list];
+            for resp in query_other_nodes(&appdata, &req).await.unwrap_or_default() {
+                if let NamingRouterResponse::ServiceChangeHistory(list) = resp {
+                    lists.push(list);
+                }
+            }
+            let list = ServiceChangeHistory::merge(lists);
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 查询服务最近心跳超时被移除的实例
+pub async fn query_quarantine_instances(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ServiceParam>,
+) -> impl Responder {
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryQuarantineInstances(param.to_key()))
+        .await
+    {
+        Ok(Ok(NamingResult::QuarantineInstances(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 查询按服务名模式(前缀或正则)订阅的客户端
+pub async fn query_pattern_subscribers(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamespaceQueryParam>,
+) -> impl Responder {
+    let namespace_id = StringUtils::map_not_empty(param.namespace_id).map(Arc::new);
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryPatternSubscribers(namespace_id))
+        .await
+    {
+        Ok(Ok(NamingResult::PatternSubscribers(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+pub async fn query_flapping_instances(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamespaceQueryParam>,
+) -> impl Responder {
+    let namespace_id = StringUtils::map_not_empty(param.namespace_id).map(Arc::new);
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryFlappingInstances(namespace_id))
+        .await
+    {
+        Ok(Ok(NamingResult::FlappingInstances(list))) => {
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 按ip(及端口)反查所有命名空间下注册的实例
+pub async fn query_instances_by_ip(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<InstanceIpQueryParam>,
+) -> impl Responder {
+    let ip = if let Some(ip) = StringUtils::map_not_empty(param.ip) {
+        Arc::new(ip)
+    } else {
+        return HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some("ip is empty".to_owned()),
+        ));
+    };
+    let namespace_id = StringUtils::map_not_empty(param.namespace_id).map(Arc::new);
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryInstancesByIp(ip, param.port, namespace_id))
+        .await
+    {
+        Ok(Ok(NamingResult::InstanceList(list))) => {
+            let list: Vec<IpInstanceDto> = list.iter().map(|e| e.as_ref().into()).collect();
+            HttpResponse::Ok().json(ApiResult::success(Some(PageResult {
+                total_count: list.len(),
+                list,
+            })))
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 按nacos控制台的服务详情格式导出命名空间下的服务定义,不包含实例
+pub async fn export_services(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamespaceQueryParam>,
+) -> impl Responder {
+    let namespace_id = Arc::new(NamingUtils::default_namespace(
+        param.namespace_id.unwrap_or_default(),
+    ));
+    match appdata
+        .naming_addr
+        .send(NamingCmd::QueryServiceExport(namespace_id))
+        .await
+    {
+        Ok(Ok(NamingResult::ServiceExport(list))) => {
+            let filename = format!("rnacos_service_export_{}.json", now_millis());
+            HttpResponse::Ok()
+                .insert_header(header::ContentDisposition::attachment(filename))
+                .json(list)
+        }
+        Ok(Err(err)) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+        _ => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            None,
+        )),
+    }
+}
+
+///
+/// 导入服务定义,已存在的服务会覆盖元数据与保护阈值;返回导入的服务数量
+pub async fn import_services(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamespaceQueryParam>,
+    web::Json(list): web::Json<Vec<NacosServiceExport>>,
+) -> impl Responder {
+    let namespace_id = Arc::new(NamingUtils::default_namespace(
+        param.namespace_id.unwrap_or_default(),
+    ));
+    let mut service_list = Vec::with_capacity(list.len());
+    for item in &list {
+        match item.to_service_detail(namespace_id.clone()) {
+            Ok(v) => service_list.push(v),
+            Err(err) => {
+                return HttpResponse::Ok().json(ApiResult::<()>::error(
+                    ERROR_CODE_SYSTEM_ERROR.to_string(),
+                    Some(err.to_string()),
+                ))
+            }
+        }
+    }
+    let count = service_list.len();
+    for service_info in service_list {
+        match appdata
+            .naming_addr
+            .send(NamingCmd::UpdateService(service_info))
+            .await
+        {
+            Ok(Ok(_)) => {}
+            Ok(Err(err)) => {
+                return HttpResponse::Ok().json(ApiResult::<()>::error(
+                    ERROR_CODE_SYSTEM_ERROR.to_string(),
+                    Some(err.to_string()),
+                ))
+            }
+            Err(err) => {
+                return HttpResponse::Ok().json(ApiResult::<()>::error(
+                    ERROR_CODE_SYSTEM_ERROR.to_string(),
+                    Some(err.to_string()),
+                ))
+            }
+        }
+    }
+    HttpResponse::Ok().json(ApiResult::success(Some(count)))
+}
+
+///
+/// 导出命名空间下的服务定义与持久化实例,可在其它r-nacos实例导入
+pub async fn export_naming_backup(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamingBackupParam>,
+) -> impl Responder {
+    let namespace_id = Arc::new(NamingUtils::default_namespace(
+        param.namespace_id.unwrap_or_default(),
+    ));
+    let backup = match appdata
+        .naming_addr
+        .send(NamingCmd::QueryNamingBackup(namespace_id))
+        .await
+    {
+        Ok(Ok(NamingResult::NamingBackup(v))) => v,
+        Ok(Err(err)) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ))
+        }
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ))
+        }
+        _ => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                None,
+            ))
+        }
+    };
+    if param.format.as_deref() == Some("zip") {
+        match backup.to_zip() {
+            Ok(buf) => {
+                let filename = format!("rnacos_naming_backup_{}.zip", now_millis());
+                HttpResponse::Ok()
+                    .insert_header(header::ContentType(mime::APPLICATION_OCTET_STREAM))
+                    .insert_header(header::ContentDisposition::attachment(filename))
+                    .body(buf)
+            }
+            Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            )),
+        }
+    } else {
+        let filename = format!("rnacos_naming_backup_{}.json", now_millis());
+        HttpResponse::Ok()
+            .insert_header(header::ContentDisposition::attachment(filename))
+            .json(backup)
+    }
+}
+
+///
+/// 导入导出的json或zip文件;服务定义与持久化实例按控制台注册的方式写入raft,同步到所有节点。
+/// 指定命名空间时导入到该命名空间,否则导入到导出时的命名空间
+pub async fn import_naming_backup(
+    req: HttpRequest,
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<NamespaceQueryParam>,
+    payload: web::Payload,
+) -> impl Responder {
+    let body = match get_req_body(payload).await {
+        Ok(v) => v,
+        Err(err) => {
+            return HttpResponse::Ok().json(ApiResult::<()>::error(
+                ERROR_CODE_SYSTEM_ERROR.to_string(),
+                Some(err.to_string()),
+            ))
+        }
+    };
+    match do_import_naming_backup(&req, &appdata, param.namespace_id, &body).await {
+        Ok(result) => HttpResponse::Ok().json(ApiResult::success(Some(result))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+async fn do_import_naming_backup(
+    req: &HttpRequest,
+    appdata: &Arc<AppShareData>,
+    namespace_id: Option<String>,
+    body: &[u8],
+) -> anyhow::Result<NamingBackupImportResult> {
+    let mut backup = NamingBackup::from_bytes(body)?;
+    if let Some(namespace_id) = namespace_id.filter(|v| !v.is_empty()) {
+        backup.set_namespace(Arc::new(NamingUtils::default_namespace(namespace_id)));
+    }
+    for instance in &backup.instances {
+        if instance.ip.is_empty() || instance.port == 0 || instance.service_name.is_empty() {
+            return Err(anyhow::anyhow!(
+                "invalid instance,{}:{}",
+                &instance.ip,
+                instance.port
+            ));
+        }
+    }
+    let result = NamingBackupImportResult {
+        service_count: backup.services.len(),
+        instance_count: backup.instances.len(),
+    };
+    for service_info in backup.services {
+        if let Some(Err(err)) = service_info
+            .selector
+            .as_ref()
+            .map(|v| v.build_label_selector())
+        {
+            return Err(err);
+        }
+        let resource_key = ResourceKey::service(&service_info.to_service_key());
+        PersistentServiceUtils::update_service(appdata, service_info).await?;
+        record_audit(req, appdata, resource_key, "importService", None);
+    }
+    for instance in backup.instances {
+        PersistentInstanceUtils::update_instance(appdata, instance, None).await?;
+    }
+    Ok(result)
+}
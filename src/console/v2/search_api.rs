@@ -0,0 +1,221 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+
+use crate::common::appdata::AppShareData;
+use crate::common::model::{ApiResult, UserSession};
+use crate::config::config_index::ConfigQueryParam;
+use crate::config::core::{ConfigCmd, ConfigResult};
+use crate::console::model::search_model::{
+    GlobalSearchItem, GlobalSearchParam, SEARCH_TYPE_CONFIG, SEARCH_TYPE_INSTANCE,
+    SEARCH_TYPE_NAMESPACE, SEARCH_TYPE_SERVICE, SEARCH_TYPE_USER,
+};
+use crate::console::model::NamespaceInfo;
+use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::console::NamespaceUtils;
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::user::permission::{
+    UserRole, PERMISSION_CONFIG_READ, PERMISSION_NAMING_READ, PERMISSION_USER_WRITE,
+};
+use crate::user::{UserManagerReq, UserManagerResult};
+
+///
+/// 按关键字搜索命名空间、服务、配置、实例ip与用户,只返回当前用户有权限查看的资源类型
+pub async fn global_search(
+    req: HttpRequest,
+    web::Query(param): web::Query<GlobalSearchParam>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let keyword = param.keyword.clone().unwrap_or_default().trim().to_owned();
+    if keyword.is_empty() {
+        return HttpResponse::Ok().json(ApiResult::success(Some(Vec::<GlobalSearchItem>::new())));
+    }
+    let permissions = get_permissions(&req);
+    match search(&appdata, &keyword, param.get_limit(), &permissions).await {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(e) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(e.to_string()),
+        )),
+    }
+}
+
+///
+/// 未开启控制台登录时没有会话,按管理员权限处理
+fn get_permissions(req: &HttpRequest) -> Vec<&'static str> {
+    match req.extensions().get::<Arc<UserSession>>() {
+        Some(session) => session
+            .roles
+            .iter()
+            .flat_map(|role| UserRole::new(role).get_permissions())
+            .collect(),
+        None => UserRole::Manager.get_permissions(),
+    }
+}
+
+async fn search(
+    appdata: &AppShareData,
+    keyword: &str,
+    limit: usize,
+    permissions: &[&'static str],
+) -> anyhow::Result<Vec<GlobalSearchItem>> {
+    let namespaces = NamespaceUtils::get_namespaces(&appdata.config_addr).await;
+    let mut list = search_namespaces(&namespaces, keyword, limit);
+    if permissions.contains(&PERMISSION_NAMING_READ) {
+        list.extend(search_services(appdata, keyword, limit).await?);
+        list.extend(search_instances(appdata, keyword, limit).await?);
+    }
+    if permissions.contains(&PERMISSION_CONFIG_READ) {
+        list.extend(search_configs(appdata, &namespaces, keyword, limit).await?);
+    }
+    if permissions.contains(&PERMISSION_USER_WRITE) {
+        list.extend(search_users(appdata, keyword, limit).await?);
+    }
+    Ok(list)
+}
+
+fn search_namespaces(
+    namespaces: &[Arc<NamespaceInfo>],
+    keyword: &str,
+    limit: usize,
+) -> Vec<GlobalSearchItem> {
+    namespaces
+        .iter()
+        .filter_map(|info| {
+            let namespace_id = info.namespace_id.clone().unwrap_or_default();
+            let namespace_name = info.namespace_name.clone().unwrap_or_default();
+            if namespace_id.contains(keyword) || namespace_name.contains(keyword) {
+                Some(GlobalSearchItem {
+                    r#type: SEARCH_TYPE_NAMESPACE.to_owned(),
+                    namespace_id: Some(Arc::new(namespace_id.clone())),
+                    name: Arc::new(namespace_id),
+                    desc: Some(namespace_name),
+                    ..Default::default()
+                })
+            } else {
+                None
+            }
+        })
+        .take(limit)
+        .collect()
+}
+
+async fn search_services(
+    appdata: &AppShareData,
+    keyword: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<GlobalSearchItem>> {
+    let mut list = vec![];
+    if let NamingResult::ServiceInstanceCounts(counts) = appdata
+        .naming_addr
+        .send(NamingCmd::QueryServiceInstanceCounts)
+        .await??
+    {
+        let mut keys: Vec<_> = counts
+            .into_iter()
+            .filter(|(key, _, _)| key.service_name.contains(keyword))
+            .collect();
+        keys.sort_by(|a, b| {
+            a.0.get_join_service_name()
+                .cmp(&b.0.get_join_service_name())
+        });
+        for (key, instance_count, healthy_count) in keys.into_iter().take(limit) {
+            list.push(GlobalSearchItem {
+                r#type: SEARCH_TYPE_SERVICE.to_owned(),
+                namespace_id: Some(key.namespace_id),
+                group: Some(key.group_name),
+                name: key.service_name,
+                desc: Some(format!("instances: {}/{}", healthy_count, instance_count)),
+                ..Default::default()
+            });
+        }
+    }
+    Ok(list)
+}
+
+async fn search_instances(
+    appdata: &AppShareData,
+    keyword: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<GlobalSearchItem>> {
+    let cmd = NamingCmd::SearchInstancesByIp(keyword.to_owned(), limit);
+    let mut list = vec![];
+    if let NamingResult::InstanceList(instances) = appdata.naming_addr.send(cmd).await?? {
+        for instance in instances {
+            list.push(GlobalSearchItem {
+                r#type: SEARCH_TYPE_INSTANCE.to_owned(),
+                namespace_id: Some(instance.namespace_id.clone()),
+                group: Some(instance.group_name.clone()),
+                service_name: Some(instance.service_name.clone()),
+                name: Arc::new(format!("{}:{}", &instance.ip, instance.port)),
+                desc: Some(instance.cluster_name.clone()),
+            });
+        }
+    }
+    Ok(list)
+}
+
+///
+/// 按命名空间列表逐个查询,不返回系统配置
+async fn search_configs(
+    appdata: &AppShareData,
+    namespaces: &[Arc<NamespaceInfo>],
+    keyword: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<GlobalSearchItem>> {
+    let mut list = vec![];
+    for info in namespaces {
+        if list.len() >= limit {
+            break;
+        }
+        //默认命名空间的id为空,与配置的tenant一致
+        let namespace_id = Arc::new(info.namespace_id.clone().unwrap_or_default());
+        let param = ConfigQueryParam {
+            tenant: Some(namespace_id.clone()),
+            like_data_id: Some(keyword.to_owned()),
+            limit: limit - list.len(),
+            ..Default::default()
+        };
+        if let ConfigResult::ConfigInfoPage(_, configs) = appdata
+            .config_addr
+            .send(ConfigCmd::QueryPageInfo(Box::new(param)))
+            .await??
+        {
+            for item in configs {
+                list.push(GlobalSearchItem {
+                    r#type: SEARCH_TYPE_CONFIG.to_owned(),
+                    namespace_id: Some(namespace_id.clone()),
+                    group: Some(item.group),
+                    name: item.data_id,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    Ok(list)
+}
+
+async fn search_users(
+    appdata: &AppShareData,
+    keyword: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<GlobalSearchItem>> {
+    let msg = UserManagerReq::QueryPageList {
+        like_username: Some(keyword.to_owned()),
+        offset: Some(0),
+        limit: Some(limit as i64),
+        is_rev: false,
+    };
+    let mut list = vec![];
+    if let UserManagerResult::UserPageResult(_, users) = appdata.user_manager.send(msg).await?? {
+        for user in users {
+            list.push(GlobalSearchItem {
+                r#type: SEARCH_TYPE_USER.to_owned(),
+                name: user.username,
+                desc: user.nickname,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(list)
+}
@@ -0,0 +1,140 @@
+use crate::common::appdata::AppShareData;
+use crate::common::model::ApiResult;
+use crate::common::resource_event::{ResourceEvent, ResourceEventType, ResourceKey};
+use crate::config::core::{ConfigCmd, ConfigKey, ConfigResult};
+use crate::config::dal::ConfigHistoryParam;
+use crate::config::ConfigUtils;
+use crate::console::model::ResourceTimelineParam;
+use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::naming::cluster::model::{NamingRouteRequest, NamingRouterResponse};
+use crate::naming::cluster::query_other_nodes;
+use crate::naming::model::ServiceKey;
+use crate::naming::NamingUtils;
+use actix_web::web::Data;
+use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+
+const DEFAULT_TIMELINE_LIMIT: usize = 100;
+
+///
+/// 合并配置的变更历史、操作记录与推送事件,按时间倒序返回
+pub async fn query_config_timeline(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ResourceTimelineParam>,
+) -> impl Responder {
+    build_response(do_query_config_timeline(&appdata, param).await)
+}
+
+///
+/// 合并服务的操作记录与推送事件,按时间倒序返回
+pub async fn query_service_timeline(
+    appdata: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ResourceTimelineParam>,
+) -> impl Responder {
+    build_response(do_query_service_timeline(&appdata, param).await)
+}
+
+fn build_response(result: anyhow::Result<Vec<ResourceEvent>>) -> HttpResponse {
+    match result {
+        Ok(list) => HttpResponse::Ok().json(ApiResult::success(Some(list))),
+        Err(err) => HttpResponse::Ok().json(ApiResult::<()>::error(
+            ERROR_CODE_SYSTEM_ERROR.to_string(),
+            Some(err.to_string()),
+        )),
+    }
+}
+
+fn get_name_and_limit(param: &ResourceTimelineParam) -> anyhow::Result<(String, usize)> {
+    let name = param.name.clone().unwrap_or_default();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("name can't be empty"));
+    }
+    Ok((name, param.limit.unwrap_or(DEFAULT_TIMELINE_LIMIT).max(1)))
+}
+
+async fn do_query_config_timeline(
+    appdata: &AppShareData,
+    param: ResourceTimelineParam,
+) -> anyhow::Result<Vec<ResourceEvent>> {
+    let (name, limit) = get_name_and_limit(&param)?;
+    let group = param
+        .group
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "DEFAULT_GROUP".to_owned());
+    let config_key = ConfigKey::new(
+        &name,
+        &group,
+        &ConfigUtils::default_tenant(param.namespace_id.unwrap_or_default()),
+    );
+    let mut list = query_config_history(appdata, &config_key, limit).await?;
+    list.extend(query_events(appdata, ResourceKey::config(&config_key)).await);
+    Ok(sort_and_truncate(list, limit))
+}
+
+async fn do_query_service_timeline(
+    appdata: &AppShareData,
+    param: ResourceTimelineParam,
+) -> anyhow::Result<Vec<ResourceEvent>> {
+    let (name, limit) = get_name_and_limit(&param)?;
+    let service_key = ServiceKey::new(
+        &NamingUtils::default_namespace(param.namespace_id.unwrap_or_default()),
+        &NamingUtils::default_group(param.group.unwrap_or_default()),
+        &name,
+    );
+    let list = query_events(appdata, ResourceKey::service(&service_key)).await;
+    Ok(sort_and_truncate(list, limit))
+}
+
+///
+/// 操作记录与推送事件只记录在处理请求的节点上,需要合并其它节点的记录
+async fn query_events(appdata: &AppShareData, key: ResourceKey) -> Vec<ResourceEvent> {
+    let mut list = appdata.resource_event_store.query(&key);
+    let req = NamingRouteRequest::QueryResourceEvents(key);
+    for resp in query_other_nodes(appdata, &req).await.unwrap_or_default() {
+        if let NamingRouterResponse::ResourceEvents(events) = resp {
+            list.extend(events);
+        }
+    }
+    list
+}
+
+fn sort_and_truncate(mut list: Vec<ResourceEvent>, limit: usize) -> Vec<ResourceEvent> {
+    list.sort_by_key(|e| std::cmp::Reverse(e.time));
+    list.truncate(limit);
+    list
+}
+
+async fn query_config_history(
+    appdata: &AppShareData,
+    key: &ConfigKey,
+    limit: usize,
+) -> anyhow::Result<Vec<ResourceEvent>> {
+    let param = ConfigHistoryParam {
+        data_id: Some(key.data_id.as_ref().to_owned()),
+        group: Some(key.group.as_ref().to_owned()),
+        tenant: Some(key.tenant.as_ref().to_owned()),
+        order_by: Some("last_time".to_owned()),
+        order_by_desc: Some(true),
+        limit: Some(limit as i64),
+        offset: Some(0),
+        ..Default::default()
+    };
+    let list = match appdata
+        .config_addr
+        .send(ConfigCmd::QueryHistoryPageInfo(Box::new(param)))
+        .await??
+    {
+        ConfigResult::ConfigHistoryInfoPage(_, list) => list
+            .into_iter()
+            .map(|item| ResourceEvent {
+                time: item.modified_time.unwrap_or_default(),
+                event_type: ResourceEventType::History,
+                action: "publish".to_owned(),
+                operator: item.op_user,
+                detail: item.id.map(|id| format!("historyId:{}", id)),
+            })
+            .collect(),
+        _ => vec![],
+    };
+    Ok(list)
+}
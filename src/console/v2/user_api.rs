@@ -1,15 +1,23 @@
 use crate::common::appdata::AppShareData;
 use crate::common::model::{ApiResult, PageResult, UserSession};
-use crate::console::model::user_model::{UpdateUserInfoParam, UserPageParams};
+use crate::console::model::user_model::{
+    AccessReportParam, FavoriteQueryParam, UpdateUserInfoParam, UserPageParams, UserPreferenceParam,
+};
 use crate::user::{UserManagerReq, UserManagerResult};
 use actix_http::HttpMessage;
+use actix_web::http::header;
 use actix_web::web::Data;
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use std::sync::Arc;
 
 use crate::console::user_api::ResetPasswordParam;
 pub use crate::console::user_api::{get_user_info, get_user_web_resources};
+use crate::console::{NamespaceUtils, DEFAULT_NAMESPACE};
+use crate::now_millis;
+use crate::user::access_report::AccessReportUtils;
+use crate::user::favorite::{FavoriteItem, UserFavoriteUtils};
 use crate::user::model::UserDto;
+use crate::user::preference::UserPreferenceUtils;
 
 pub async fn reset_password(
     req: HttpRequest,
@@ -124,9 +132,199 @@ pub async fn remove_user(
     app: Data<Arc<AppShareData>>,
     web::Json(user): web::Json<UpdateUserInfoParam>,
 ) -> actix_web::Result<impl Responder> {
+    let username = user.username;
     let msg = UserManagerReq::Remove {
-        username: user.username,
+        username: username.clone(),
     };
     app.user_manager.send(msg).await.ok();
+    UserPreferenceUtils::remove_user_preferences(&app, &username)
+        .await
+        .ok();
     Ok(HttpResponse::Ok().json(ApiResult::success(Some(true))))
 }
+
+///
+/// 全部用户在各命名空间下的权限矩阵,用于定期权限审查;format=csv时导出csv文件
+pub async fn get_access_report(
+    app: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<AccessReportParam>,
+) -> actix_web::Result<impl Responder> {
+    let msg = UserManagerReq::QueryPageList {
+        like_username: None,
+        offset: None,
+        limit: None,
+        is_rev: false,
+    };
+    let users = match app.user_manager.send(msg).await {
+        Ok(Ok(UserManagerResult::UserPageResult(_, list))) => list,
+        _ => {
+            return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+                "SYSTEM_ERROR".to_owned(),
+                Some("query user list error".to_owned()),
+            )))
+        }
+    };
+    let namespaces: Vec<Arc<String>> = NamespaceUtils::get_namespaces(&app.config_addr)
+        .await
+        .iter()
+        .map(|e| match e.namespace_id.as_deref() {
+            None | Some("") => Arc::new(DEFAULT_NAMESPACE.to_owned()),
+            Some(v) => Arc::new(v.to_owned()),
+        })
+        .collect();
+    let list = AccessReportUtils::build(users, &namespaces);
+    if param.format.as_deref() == Some("csv") {
+        let filename = format!("rnacos_access_report_{}.csv", now_millis());
+        Ok(HttpResponse::Ok()
+            .insert_header(header::ContentType(mime::TEXT_CSV_UTF_8))
+            .insert_header(header::ContentDisposition::attachment(filename))
+            .body(AccessReportUtils::to_csv(&list)))
+    } else {
+        Ok(HttpResponse::Ok().json(ApiResult::success(Some(list))))
+    }
+}
+
+fn get_session_username(req: &HttpRequest) -> Option<Arc<String>> {
+    req.extensions()
+        .get::<Arc<UserSession>>()
+        .map(|session| session.username.clone())
+}
+
+///
+/// 查询当前用户的偏好设置;指定key时只返回对应的值
+pub async fn get_user_preferences(
+    req: HttpRequest,
+    app: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<UserPreferenceParam>,
+) -> actix_web::Result<impl Responder> {
+    let username = match get_session_username(&req) {
+        Some(v) => v,
+        None => {
+            return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+                "NOT_FOUND_USER_SESSION".to_owned(),
+                None,
+            )))
+        }
+    };
+    let result = match param.key.as_ref().filter(|e| !e.is_empty()) {
+        Some(key) => UserPreferenceUtils::get_preference(&app, &username, key)
+            .await
+            .map(|v| serde_json::json!(v)),
+        None => UserPreferenceUtils::get_preferences(&app, &username)
+            .await
+            .map(|v| serde_json::json!(v)),
+    };
+    match result {
+        Ok(v) => Ok(HttpResponse::Ok().json(ApiResult::success(Some(v)))),
+        Err(err) => Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_owned(),
+            Some(err.to_string()),
+        ))),
+    }
+}
+
+///
+/// 设置当前用户的偏好,value为空时删除
+pub async fn set_user_preference(
+    req: HttpRequest,
+    app: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<UserPreferenceParam>,
+) -> actix_web::Result<impl Responder> {
+    let username = match get_session_username(&req) {
+        Some(v) => v,
+        None => {
+            return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+                "NOT_FOUND_USER_SESSION".to_owned(),
+                None,
+            )))
+        }
+    };
+    let key = param.key.unwrap_or_default();
+    match UserPreferenceUtils::set_preference(&app, &username, &key, param.value).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResult::success(Some(true)))),
+        Err(err) => Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_owned(),
+            Some(err.to_string()),
+        ))),
+    }
+}
+
+///
+/// 查询当前用户收藏的配置与服务及其当前状态
+pub async fn get_user_favorites(
+    req: HttpRequest,
+    app: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<FavoriteQueryParam>,
+) -> actix_web::Result<impl Responder> {
+    let username = match get_session_username(&req) {
+        Some(v) => v,
+        None => {
+            return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+                "NOT_FOUND_USER_SESSION".to_owned(),
+                None,
+            )))
+        }
+    };
+    let result = match UserFavoriteUtils::get_favorites(&app, &username).await {
+        Ok(mut list) => {
+            if let Some(favorite_type) = param.favorite_type {
+                list.retain(|e| e.favorite_type == favorite_type);
+            }
+            UserFavoriteUtils::query_favorite_status(&app, list).await
+        }
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(list) => Ok(HttpResponse::Ok().json(ApiResult::success(Some(list)))),
+        Err(err) => Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_owned(),
+            Some(err.to_string()),
+        ))),
+    }
+}
+
+pub async fn add_user_favorite(
+    req: HttpRequest,
+    app: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<FavoriteItem>,
+) -> actix_web::Result<impl Responder> {
+    let username = match get_session_username(&req) {
+        Some(v) => v,
+        None => {
+            return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+                "NOT_FOUND_USER_SESSION".to_owned(),
+                None,
+            )))
+        }
+    };
+    match UserFavoriteUtils::add_favorite(&app, &username, param).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResult::success(Some(true)))),
+        Err(err) => Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_owned(),
+            Some(err.to_string()),
+        ))),
+    }
+}
+
+pub async fn remove_user_favorite(
+    req: HttpRequest,
+    app: Data<Arc<AppShareData>>,
+    web::Json(param): web::Json<FavoriteItem>,
+) -> actix_web::Result<impl Responder> {
+    let username = match get_session_username(&req) {
+        Some(v) => v,
+        None => {
+            return Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+                "NOT_FOUND_USER_SESSION".to_owned(),
+                None,
+            )))
+        }
+    };
+    match UserFavoriteUtils::remove_favorite(&app, &username, param).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResult::success(Some(true)))),
+        Err(err) => Ok(HttpResponse::Ok().json(ApiResult::<()>::error(
+            "SYSTEM_ERROR".to_owned(),
+            Some(err.to_string()),
+        ))),
+    }
+}
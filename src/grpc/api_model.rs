@@ -74,6 +74,18 @@ pub struct ConnectResetRequest {
     pub server_port: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSetupRequest {
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+
+    pub client_version: Option<String>,
+    pub tenant: Option<String>,
+    pub labels: Option<HashMap<String, String>>,
+}
+
 // --- config ---
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -182,6 +194,40 @@ pub struct ConfigChangeBatchListenResponse {
     pub changed_configs: Vec<ConfigContext>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSubscribeReplayRequest {
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+
+    /// 客户端稳定标识取自本连接标签中的rnacos.clientId,不能由请求指定
+    #[serde(default)]
+    pub with_content: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReplayContext {
+    pub data_id: Arc<String>,
+    pub group: Arc<String>,
+    pub tenant: Arc<String>,
+    /// 配置已删除时为空
+    pub md5: Option<Arc<String>>,
+    pub content: Option<Arc<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSubscribeReplayResponse {
+    pub result_code: u16,
+    pub error_code: u16,
+    pub message: Option<String>,
+    pub request_id: Option<String>,
+
+    pub configs: Vec<ConfigReplayContext>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigChangeNotifyRequest {
@@ -305,6 +351,36 @@ pub struct BatchInstanceRequest {
     pub instances: Option<Vec<Instance>>,
 }
 
+///
+/// 局部更新实例metadata;metadata为新增或覆盖的key,removeKeys为待删除的key
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataPatchRequest {
+    pub module: Option<String>,
+    pub request_id: Option<String>,
+    pub headers: Option<HashMap<String, String>>,
+
+    pub namespace: Option<String>,
+    pub service_name: Option<String>,
+    pub group_name: Option<String>,
+
+    pub ip: Option<String>,
+    pub port: u32,
+    pub metadata: Option<HashMap<String, String>>,
+    pub remove_keys: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataPatchResponse {
+    pub result_code: u16,
+    pub error_code: u16,
+    pub message: Option<String>,
+    pub request_id: Option<String>,
+
+    pub metadata: Option<Arc<HashMap<String, String>>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchInstanceResponse {
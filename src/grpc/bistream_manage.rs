@@ -5,16 +5,26 @@ use std::{
 };
 
 use crate::{
+    common::resource_event::{ResourceEventStore, ResourceKey},
+    common::sdk_usage::{SdkUsageCmd, SdkUsageManage},
+    common::AppSysConfig,
+    config::beta::CLIENT_VERSION_LABEL,
     config::core::{ConfigActor, ConfigCmd, ConfigKey},
+    naming::push_tracker::{NamingPushTracker, PushTrackerConfig},
+    naming::zone::ZoneUtils,
+    naming::NamingUtils,
     naming::{
-        core::{NamingActor, NamingCmd},
+        core::{NamingActor, NamingCmd, NamingResult},
         model::{ServiceInfo, ServiceKey},
     },
     now_millis,
 };
 
 use super::{
-    api_model::{ConfigChangeNotifyRequest, NotifySubscriberRequest, CONFIG_MODEL, NAMING_MODEL},
+    api_model::{
+        BaseResponse, ConfigChangeNotifyRequest, ConnectionSetupRequest, NotifySubscriberRequest,
+        CONFIG_MODEL, NAMING_MODEL,
+    },
     bistream_conn::{BiStreamConn, BiStreamSenderCmd},
     handler::converter::ModelConverter,
     nacos_proto::Payload,
@@ -27,6 +37,7 @@ use inner_mem_cache::TimeoutSet;
 pub(crate) struct ConnCacheItem {
     last_active_time: u64,
     conn: Addr<BiStreamConn>,
+    zone: Option<Arc<String>>,
 }
 
 impl ConnCacheItem {
@@ -34,6 +45,7 @@ impl ConnCacheItem {
         Self {
             last_active_time,
             conn,
+            zone: None,
         }
     }
 }
@@ -49,6 +61,9 @@ pub struct BiStreamManage {
     request_id: u64,
     config_addr: Option<Addr<ConfigActor>>,
     naming_addr: Option<Addr<NamingActor>>,
+    sdk_usage_addr: Option<Addr<SdkUsageManage>>,
+    resource_event_store: Option<Arc<ResourceEventStore>>,
+    push_tracker: NamingPushTracker,
 }
 
 impl BiStreamManage {
@@ -84,6 +99,125 @@ impl BiStreamManage {
         }
     }
 
+    ///
+    /// 客户端所在可用区与本节点不同且存在该可用区的节点时,通知客户端重连到该节点
+    fn rebalance_by_zone(
+        &mut self,
+        local_zone: &str,
+        zone_addrs: &HashMap<String, (String, String)>,
+    ) -> usize {
+        let mut reset_list = vec![];
+        for item in self.conn_cache.values() {
+            if let Some(zone) = &item.zone {
+                if zone.as_str() == local_zone {
+                    continue;
+                }
+                if let Some((ip, port)) = zone_addrs.get(zone.as_str()) {
+                    reset_list.push((item.conn.clone(), ip.to_owned(), port.to_owned()));
+                }
+            }
+        }
+        let count = reset_list.len();
+        for (conn, ip, port) in reset_list {
+            conn.do_send(BiStreamSenderCmd::Reset(
+                self.next_request_id(),
+                Some(ip),
+                Some(port),
+            ));
+        }
+        count
+    }
+
+    fn handle_connection_setup(&mut self, client_id: Arc<String>, payload: &Payload) {
+        let body_vec = payload.body.as_ref().map(|e| e.value.as_slice());
+        let request = if let Ok(request) =
+            serde_json::from_slice::<ConnectionSetupRequest>(body_vec.unwrap_or_default())
+        {
+            request
+        } else {
+            return;
+        };
+        let client_version = request.client_version.unwrap_or_default();
+        if let Some(zone) = request.labels.as_ref().and_then(ZoneUtils::get_zone) {
+            if let Some(item) = self.conn_cache.get_mut(&client_id) {
+                item.zone = Some(Arc::new(zone.to_owned()));
+            }
+        }
+        let mut labels = request.labels.unwrap_or_default();
+        if !client_version.is_empty() {
+            labels.insert(CLIENT_VERSION_LABEL.to_owned(), client_version.clone());
+        }
+        if let Some(naming_addr) = &self.naming_addr {
+            naming_addr.do_send(NamingCmd::SetClientLabels(
+                client_id.clone(),
+                labels.clone(),
+            ));
+        }
+        if let Some(config_addr) = &self.config_addr {
+            config_addr.do_send(ConfigCmd::SetClientLabels(client_id.clone(), labels));
+        }
+        if let Some(sdk_usage_addr) = &self.sdk_usage_addr {
+            let namespace_id = Arc::new(NamingUtils::default_namespace(
+                request.tenant.unwrap_or_default(),
+            ));
+            sdk_usage_addr.do_send(SdkUsageCmd::Report(
+                client_id,
+                namespace_id,
+                client_version,
+                true,
+            ));
+        }
+    }
+
+    fn handle_notify_subscriber_response(&mut self, client_id: &Arc<String>, payload: &Payload) {
+        let body_vec = payload.body.as_ref().map(|e| e.value.as_slice());
+        if let Ok(response) = serde_json::from_slice::<BaseResponse>(body_vec.unwrap_or_default()) {
+            if let Some(request_id) = &response.request_id {
+                self.push_tracker.ack(client_id, request_id);
+            }
+        }
+    }
+
+    ///
+    /// 超时未确认服务变更推送的订阅者可能持有旧版本,查询当前服务信息后重推
+    fn check_push_ack(&mut self, now: u64, ctx: &mut actix::Context<Self>) {
+        if !self.push_tracker.is_enabled() {
+            return;
+        }
+        let timeout_pushes = self.push_tracker.timeout_pushes(now);
+        if timeout_pushes.is_empty() {
+            return;
+        }
+        let naming_addr = if let Some(naming_addr) = &self.naming_addr {
+            naming_addr.clone()
+        } else {
+            return;
+        };
+        log::info!(
+            "repush stale service info, service size:{}",
+            timeout_pushes.len()
+        );
+        let self_addr = ctx.address();
+        async move {
+            for (key, client_id_set) in timeout_pushes {
+                //按各订阅者的集群过滤条件重推
+                let cmd = NamingCmd::QueryRepushServiceInfo(key.clone(), client_id_set);
+                if let Ok(Ok(NamingResult::RepushServiceInfo(list))) = naming_addr.send(cmd).await {
+                    for (client_id_set, service_info) in list {
+                        self_addr.do_send(BiStreamManageCmd::NotifyNaming(
+                            key.clone(),
+                            client_id_set,
+                            service_info,
+                        ));
+                    }
+                }
+            }
+        }
+        .into_actor(self)
+        .map(|_, _, _| {})
+        .spawn(ctx);
+    }
+
     fn next_request_id(&mut self) -> String {
         if self.request_id >= 0x7fff_ffff_ffff_ffff {
             self.request_id = 0;
@@ -135,6 +269,7 @@ impl BiStreamManage {
             log::info!("check timeout close client, size:{}", del_keys.len());
         }
         for key in &del_keys {
+            self.push_tracker.remove_client(key);
             if let Some(item) = self.conn_cache.remove(key) {
                 //item.conn.do_send(BiStreamSenderCmd::Reset(self.next_request_id(),None,None));
                 item.conn.do_send(BiStreamSenderCmd::Close);
@@ -150,6 +285,11 @@ impl BiStreamManage {
                 naming_addr.do_send(NamingCmd::RemoveClient(key.clone()));
             }
         }
+        if let Some(sdk_usage_addr) = &self.sdk_usage_addr {
+            for key in &del_keys {
+                sdk_usage_addr.do_send(SdkUsageCmd::RemoveClient(key.clone()));
+            }
+        }
     }
 
     pub fn time_out_heartbeat(&self, ctx: &mut actix::Context<Self>) {
@@ -157,6 +297,7 @@ impl BiStreamManage {
             let now = now_millis();
             act.check_active_time_set(now);
             act.check_response_time_set(now);
+            act.check_push_ack(now, ctx);
             act.time_out_heartbeat(ctx);
         });
     }
@@ -182,6 +323,17 @@ impl Inject for BiStreamManage {
     ) {
         self.config_addr = factory_data.get_actor();
         self.naming_addr = factory_data.get_actor();
+        self.sdk_usage_addr = factory_data.get_actor();
+        self.resource_event_store = factory_data.get_bean();
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = sys_config {
+            self.push_tracker.config = PushTrackerConfig {
+                ack_timeout_millis: sys_config
+                    .naming_push_ack_timeout_second
+                    .saturating_mul(1000),
+                repush_batch_size: sys_config.naming_repush_batch_size,
+            };
+        }
         log::info!("BiStreamManage inject complete");
     }
 }
@@ -202,10 +354,13 @@ pub enum BiStreamManageCmd {
     NotifyConfig(ConfigKey, HashSet<Arc<String>>),
     NotifyNaming(ServiceKey, HashSet<Arc<String>>, ServiceInfo),
     QueryConnList,
+    /// 本节点可用区,可用区->(节点ip,端口);把其它可用区的客户端重置到对应可用区的节点
+    RebalanceByZone(Arc<String>, HashMap<String, (String, String)>),
 }
 
 pub enum BiStreamManageResult {
     ConnList(Vec<Arc<String>>),
+    ResetCount(usize),
     None,
 }
 
@@ -216,7 +371,12 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
         match msg {
             BiStreamManageCmd::Response(client_id, payload) => {
                 //println!("BiStreamManageCmd payload:{},client_id:{}",PayloadUtils::get_payload_string(&payload),&client_id);
-                if let Some(_t) = PayloadUtils::get_payload_type(&payload) {
+                if let Some(t) = PayloadUtils::get_payload_type(&payload) {
+                    if t.as_str() == "ConnectionSetupRequest" {
+                        self.handle_connection_setup(client_id.clone(), &payload);
+                    } else if t.as_str() == "NotifySubscriberResponse" {
+                        self.handle_notify_subscriber_response(&client_id, &payload);
+                    }
                     self.active_client(client_id).ok();
                     //if "ClientDetectionResponse"== t {
                     //}
@@ -224,9 +384,13 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
             }
             BiStreamManageCmd::ConnClose(client_id) => {
                 self.conn_cache.remove(&client_id);
+                self.push_tracker.remove_client(&client_id);
                 if let Some(config_addr) = &self.config_addr {
                     config_addr.do_send(ConfigCmd::RemoveSubscribeClient(client_id.clone()))
                 }
+                if let Some(sdk_usage_addr) = &self.sdk_usage_addr {
+                    sdk_usage_addr.do_send(SdkUsageCmd::RemoveClient(client_id.clone()));
+                }
                 if let Some(naming_addr) = &self.naming_addr {
                     naming_addr.do_send(NamingCmd::RemoveClient(client_id));
                 }
@@ -240,6 +404,9 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                 self.active_client(client_id)?;
             }
             BiStreamManageCmd::NotifyConfig(config_key, client_id_set) => {
+                if let Some(store) = &self.resource_event_store {
+                    store.record_push(ResourceKey::config(&config_key), client_id_set.len());
+                }
                 let request = ConfigChangeNotifyRequest {
                     group: config_key.group,
                     data_id: config_key.data_id,
@@ -259,13 +426,26 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                 }
             }
             BiStreamManageCmd::NotifyNaming(service_key, client_id_set, service_info) => {
+                if let Some(store) = &self.resource_event_store {
+                    store.record_push(ResourceKey::service(&service_key), client_id_set.len());
+                }
                 let service_info = ModelConverter::to_api_service_info(service_info);
+                let request_id = Arc::new(self.next_request_id());
+                if self.push_tracker.is_enabled() {
+                    let now = now_millis();
+                    for item in &client_id_set {
+                        if self.conn_cache.contains_key(item) {
+                            self.push_tracker
+                                .record_push(item, &service_key, &request_id, now);
+                        }
+                    }
+                }
                 let request = NotifySubscriberRequest {
                     namespace: Some(service_key.namespace_id),
                     group_name: Some(service_key.group_name),
                     service_name: Some(service_key.service_name),
                     service_info: Some(service_info),
-                    request_id: Some(self.next_request_id()),
+                    request_id: Some(request_id.as_ref().to_owned()),
                     module: Some(NAMING_MODEL.to_string()),
                     ..Default::default()
                 };
@@ -286,6 +466,11 @@ impl Handler<BiStreamManageCmd> for BiStreamManage {
                 }
                 return Ok(BiStreamManageResult::ConnList(list));
             }
+            BiStreamManageCmd::RebalanceByZone(local_zone, zone_addrs) => {
+                return Ok(BiStreamManageResult::ResetCount(
+                    self.rebalance_by_zone(&local_zone, &zone_addrs),
+                ));
+            }
         }
         Ok(BiStreamManageResult::None)
     }
@@ -4,6 +4,8 @@ use std::sync::Arc;
 
 use crate::common::string_utils::StringUtils;
 use crate::config::config_type::ConfigType;
+use crate::config::utils::param_utils;
+use crate::console::NamespaceUtils;
 use crate::grpc::HandlerResult;
 use crate::{
     common::appdata::AppShareData,
@@ -33,7 +35,7 @@ impl PayloadHandler for ConfigPublishRequestHandler {
     async fn handle(
         &self,
         request_payload: crate::grpc::nacos_proto::Payload,
-        _request_meta: crate::grpc::RequestMeta,
+        request_meta: crate::grpc::RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
         let body_vec = request_payload.body.unwrap_or_default().value;
         let request: ConfigPublishRequest = serde_json::from_slice(&body_vec)?;
@@ -41,12 +43,39 @@ impl PayloadHandler for ConfigPublishRequestHandler {
             .map(|v| ConfigType::new_by_value(v.as_ref()).get_value());
         let desc =
             StringUtils::map_not_empty(request.get_addition_param("desc").cloned()).map(Arc::new);
-        let mut req = SetConfigReq::new(
-            ConfigKey::new(&request.data_id, &request.group, &request.tenant),
-            request.content,
-        );
+        let config_key = ConfigKey::new(&request.data_id, &request.group, &request.tenant);
+        if let Err(err) = config_key
+            .is_valid()
+            .and_then(|_| param_utils::check_reserved(&config_key, false, false))
+            .and_then(|_| {
+                NamespaceUtils::check_config_write(
+                    &self.app_data.sys_config,
+                    &config_key.tenant,
+                    request_meta.is_manager(&self.app_data.sys_config),
+                )
+            })
+            .and_then(|_| {
+                param_utils::check_content(
+                    &request.content,
+                    self.app_data.cluster_settings.config_max_content(),
+                )
+            })
+        {
+            let mut response = BaseResponse::build_error_response(400u16, err.to_string());
+            response.request_id = request.request_id;
+            return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                "ErrorResponse",
+                serde_json::to_string(&response)?,
+            )));
+        }
+        //变更冻结期与内容格式在写入时统一检查
+        let mut req = SetConfigReq::new(config_key, request.content);
         req.config_type = config_type;
         req.desc = desc;
+        req.op_user = request_meta
+            .token_session
+            .as_ref()
+            .map(|v| v.username.clone());
         match self.app_data.config_route.set_config(req).await {
             Ok(_res) => {
                 //let res:ConfigResult = res.unwrap();
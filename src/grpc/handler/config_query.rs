@@ -35,15 +35,14 @@ impl PayloadHandler for ConfigQueryRequestHandler {
     async fn handle(
         &self,
         request_payload: crate::grpc::nacos_proto::Payload,
-        _request_meta: crate::grpc::RequestMeta,
+        request_meta: crate::grpc::RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
         let body_vec = request_payload.body.unwrap_or_default().value;
         let request: ConfigQueryRequest = serde_json::from_slice(&body_vec)?;
-        let cmd = ConfigCmd::GET(ConfigKey::new(
-            &request.data_id,
-            &request.group,
-            &request.tenant,
-        ));
+        let cmd = ConfigCmd::GetByClient(
+            ConfigKey::new(&request.data_id, &request.group, &request.tenant),
+            request_meta.connection_id.clone(),
+        );
         let mut response = ConfigQueryResponse {
             request_id: request.request_id,
             ..Default::default()
@@ -76,6 +75,20 @@ impl PayloadHandler for ConfigQueryRequestHandler {
                         response.last_modified = last_modified;
                         response.md5 = Some(md5);
                     }
+                    ConfigResult::BetaData {
+                        value: content,
+                        md5,
+                        config_type,
+                        last_modified,
+                    } => {
+                        response.result_code = SUCCESS_CODE;
+                        response.content = content;
+                        response.content_type =
+                            Some(config_type.unwrap_or(ConfigType::Text.get_value()));
+                        response.last_modified = last_modified;
+                        response.md5 = Some(md5);
+                        response.beta = true;
+                    }
                     _ => {
                         response.result_code = ERROR_CODE;
                         response.error_code = NOT_FOUND;
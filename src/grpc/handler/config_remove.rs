@@ -2,6 +2,9 @@
 
 use std::sync::Arc;
 
+use crate::config::freeze::ConfigFreezeUtils;
+use crate::config::utils::param_utils;
+use crate::console::NamespaceUtils;
 use crate::grpc::HandlerResult;
 use crate::{
     common::appdata::AppShareData,
@@ -31,15 +34,48 @@ impl PayloadHandler for ConfigRemoveRequestHandler {
     async fn handle(
         &self,
         request_payload: crate::grpc::nacos_proto::Payload,
-        _request_meta: crate::grpc::RequestMeta,
+        request_meta: crate::grpc::RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
         let body_vec = request_payload.body.unwrap_or_default().value;
         let request: ConfigRemoveRequest = serde_json::from_slice(&body_vec)?;
-        let req = DelConfigReq::new(ConfigKey::new(
-            &request.data_id,
-            &request.group,
-            &request.tenant,
-        ));
+        let config_key = ConfigKey::new(&request.data_id, &request.group, &request.tenant);
+        if let Err(err) = param_utils::check_reserved(
+            &config_key,
+            true,
+            self.app_data
+                .cluster_settings
+                .config_protect_cipher_delete(),
+        )
+        .and_then(|_| {
+            NamespaceUtils::check_config_write(
+                &self.app_data.sys_config,
+                &config_key.tenant,
+                request_meta.is_manager(&self.app_data.sys_config),
+            )
+        }) {
+            let mut response = BaseResponse::build_error_response(400u16, err.to_string());
+            response.request_id = request.request_id;
+            return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                "ErrorResponse",
+                serde_json::to_string(&response)?,
+            )));
+        }
+        let operator = request_meta
+            .token_session
+            .as_ref()
+            .map(|v| v.username.as_str().to_owned())
+            .unwrap_or_else(|| request_meta.client_ip.clone());
+        if let Err(err) =
+            ConfigFreezeUtils::check(&self.app_data, &config_key, &operator, false).await
+        {
+            let mut response = BaseResponse::build_error_response(400u16, err.to_string());
+            response.request_id = request.request_id;
+            return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                "ErrorResponse",
+                serde_json::to_string(&response)?,
+            )));
+        }
+        let req = DelConfigReq::new(config_key);
         match self.app_data.config_route.del_config(req).await {
             Ok(_res) => {
                 let mut response = BaseResponse::build_success_response();
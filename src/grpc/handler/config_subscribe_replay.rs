@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use crate::grpc::HandlerResult;
+use crate::{
+    common::appdata::AppShareData,
+    config::core::{ConfigCmd, ConfigResult},
+    config::subscribe_replay::SubscribeReplayUtils,
+    grpc::{
+        api_model::{
+            ConfigReplayContext, ConfigSubscribeReplayRequest, ConfigSubscribeReplayResponse,
+            ERROR_CODE, SUCCESS_CODE,
+        },
+        PayloadHandler, PayloadUtils,
+    },
+};
+use async_trait::async_trait;
+
+///
+/// 客户端重连后恢复断开前的配置订阅,一次返回订阅配置的当前md5
+pub struct ConfigSubscribeReplayRequestHandler {
+    app_data: Arc<AppShareData>,
+}
+
+impl ConfigSubscribeReplayRequestHandler {
+    pub fn new(app_data: Arc<AppShareData>) -> Self {
+        Self { app_data }
+    }
+}
+
+#[async_trait]
+impl PayloadHandler for ConfigSubscribeReplayRequestHandler {
+    async fn handle(
+        &self,
+        request_payload: crate::grpc::nacos_proto::Payload,
+        request_meta: crate::grpc::RequestMeta,
+    ) -> anyhow::Result<HandlerResult> {
+        let body_vec = request_payload.body.unwrap_or_default().value;
+        let request: ConfigSubscribeReplayRequest = serde_json::from_slice(&body_vec)?;
+        let cmd = ConfigCmd::ReplaySubscribe {
+            client_id: request_meta.connection_id.clone(),
+            remote_keys: None,
+            with_content: request.with_content,
+        };
+        let mut response = ConfigSubscribeReplayResponse {
+            request_id: request.request_id,
+            ..Default::default()
+        };
+        let mut result = self.app_data.config_addr.send(cmd).await;
+        if let Ok(Ok(ConfigResult::ReplayNotFound(instance_id, owner_ip))) = result {
+            //客户端断开前连接的是其它节点
+            let remote_keys =
+                SubscribeReplayUtils::take_from_other_nodes(&self.app_data, instance_id, owner_ip)
+                    .await;
+            let cmd = ConfigCmd::ReplaySubscribe {
+                client_id: request_meta.connection_id,
+                remote_keys: Some(remote_keys.unwrap_or_default()),
+                with_content: request.with_content,
+            };
+            result = self.app_data.config_addr.send(cmd).await;
+        }
+        match result {
+            Ok(Ok(ConfigResult::ReplaySubscribe(list))) => {
+                response.result_code = SUCCESS_CODE;
+                response.configs = list
+                    .into_iter()
+                    .map(|item| ConfigReplayContext {
+                        data_id: item.key.data_id,
+                        group: item.key.group,
+                        tenant: item.key.tenant,
+                        md5: item.md5,
+                        content: item.content,
+                    })
+                    .collect();
+                Ok(HandlerResult::success(PayloadUtils::build_payload(
+                    "ConfigSubscribeReplayResponse",
+                    serde_json::to_string(&response)?,
+                )))
+            }
+            Ok(Ok(_)) => {
+                response.result_code = SUCCESS_CODE;
+                Ok(HandlerResult::success(PayloadUtils::build_payload(
+                    "ConfigSubscribeReplayResponse",
+                    serde_json::to_string(&response)?,
+                )))
+            }
+            Ok(Err(err)) => Self::error_result(response, err.to_string()),
+            Err(err) => Self::error_result(response, err.to_string()),
+        }
+    }
+}
+
+impl ConfigSubscribeReplayRequestHandler {
+    fn error_result(
+        mut response: ConfigSubscribeReplayResponse,
+        message: String,
+    ) -> anyhow::Result<HandlerResult> {
+        response.result_code = ERROR_CODE;
+        response.error_code = ERROR_CODE;
+        response.message = Some(message);
+        Ok(HandlerResult::success(PayloadUtils::build_payload(
+            "ErrorResponse",
+            serde_json::to_string(&response)?,
+        )))
+    }
+}
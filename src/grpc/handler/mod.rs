@@ -1,13 +1,20 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::common::appdata::AppShareData;
+use crate::common::concurrency_limit::EndpointClass;
+use crate::common::limiter_utils::SecondLimiter;
+use crate::metrics::metrics_key::MetricsKey;
+use crate::metrics::model::{MetricsItem, MetricsRecord, MetricsRequest};
 
 use self::{
     config_change_batch_listen::ConfigChangeBatchListenRequestHandler,
     config_publish::ConfigPublishRequestHandler, config_query::ConfigQueryRequestHandler,
-    config_remove::ConfigRemoveRequestHandler, naming_batch_instance::BatchInstanceRequestHandler,
-    naming_instance::InstanceRequestHandler, naming_route::NamingRouteRequestHandler,
-    naming_service_list::ServiceListRequestHandler,
+    config_remove::ConfigRemoveRequestHandler,
+    config_subscribe_replay::ConfigSubscribeReplayRequestHandler,
+    naming_batch_instance::BatchInstanceRequestHandler, naming_instance::InstanceRequestHandler,
+    naming_instance_metadata::InstanceMetadataPatchRequestHandler,
+    naming_route::NamingRouteRequestHandler, naming_service_list::ServiceListRequestHandler,
     naming_service_query::ServiceQueryRequestHandler,
     naming_subscribe_service::SubscribeServiceRequestHandler, raft_route::RaftRouteRequestHandler,
 };
@@ -26,10 +33,12 @@ pub mod config_change_batch_listen;
 pub mod config_publish;
 pub mod config_query;
 pub mod config_remove;
+pub mod config_subscribe_replay;
 
 pub mod converter;
 pub mod naming_batch_instance;
 pub mod naming_instance;
+pub mod naming_instance_metadata;
 pub mod naming_route;
 pub mod naming_service_list;
 pub mod naming_service_query;
@@ -53,18 +62,62 @@ pub(crate) const CONFIG_QUERY_REQUEST: &str = "ConfigQueryRequest";
 pub(crate) const CONFIG_PUBLISH_REQUEST: &str = "ConfigPublishRequest";
 pub(crate) const CONFIG_REMOVE_REQUEST: &str = "ConfigRemoveRequest";
 pub(crate) const CONFIG_BATCH_LISTEN_REQUEST: &str = "ConfigBatchListenRequest";
+pub(crate) const CONFIG_SUBSCRIBE_REPLAY_REQUEST: &str = "ConfigSubscribeReplayRequest";
 
 pub(crate) const INSTANCE_REQUEST: &str = "InstanceRequest";
 pub(crate) const BATCH_INSTANCE_REQUEST: &str = "BatchInstanceRequest";
+pub(crate) const INSTANCE_METADATA_PATCH_REQUEST: &str = "InstanceMetadataPatchRequest";
 pub(crate) const SUBSCRIBE_SERVICE_REQUEST: &str = "SubscribeServiceRequest";
 pub(crate) const SERVICE_QUERY_REQUEST: &str = "ServiceQueryRequest";
 pub(crate) const SERVICE_LIST_REQUEST: &str = "ServiceListRequest";
 
+///
+/// 请求处理器的控制配置
+#[derive(Debug, Clone, Default)]
+pub struct HandlerConfig {
+    /// 不需要鉴权
+    pub ignore_auth: bool,
+    /// 集群内部请求,需要校验集群token
+    pub cluster_request: bool,
+    /// 不校验连接是否已注册
+    pub ignore_active_err: bool,
+    /// 每秒最大请求数,0表示不限制
+    pub tps_limit: u32,
+    /// 请求计数的监控指标
+    pub metrics_key: Option<MetricsKey>,
+}
+
+impl HandlerConfig {
+    pub fn client(metrics_key: MetricsKey) -> Self {
+        Self {
+            metrics_key: Some(metrics_key),
+            ..Default::default()
+        }
+    }
+
+    pub fn cluster() -> Self {
+        Self {
+            ignore_auth: true,
+            cluster_request: true,
+            ignore_active_err: true,
+            metrics_key: Some(MetricsKey::GrpcClusterRequestTotalCount),
+            ..Default::default()
+        }
+    }
+}
+
+pub struct HandlerItem {
+    pub handler: Box<dyn PayloadHandler + Send + Sync + 'static>,
+    pub config: HandlerConfig,
+    limiter: Option<SecondLimiter>,
+}
+
 pub struct InvokerHandler {
     app: Arc<AppShareData>,
-    handlers: Vec<(String, Box<dyn PayloadHandler + Send + Sync + 'static>)>,
+    handlers: HashMap<String, HandlerItem>,
 }
 pub struct HealthCheckRequestHandler {}
+pub struct ServerCheckRequestHandler {}
 
 impl InvokerHandler {
     pub fn new(app: Arc<AppShareData>) -> Self {
@@ -72,7 +125,23 @@ impl InvokerHandler {
             handlers: Default::default(),
             app,
         };
-        this.add_handler(HEALTH_CHECK_REQUEST, Box::new(HealthCheckRequestHandler {}));
+        this.add_handler_with_config(
+            SERVER_CHECK_REQUEST,
+            Box::new(ServerCheckRequestHandler {}),
+            HandlerConfig {
+                ignore_auth: true,
+                ignore_active_err: true,
+                ..Default::default()
+            },
+        );
+        this.add_handler_with_config(
+            HEALTH_CHECK_REQUEST,
+            Box::new(HealthCheckRequestHandler {}),
+            HandlerConfig {
+                ignore_auth: true,
+                ..Default::default()
+            },
+        );
         this
     }
 
@@ -81,110 +150,153 @@ impl InvokerHandler {
         url: &str,
         handler: Box<dyn PayloadHandler + Send + Sync + 'static>,
     ) {
-        self.handlers.push((url.to_owned(), handler));
+        self.add_handler_with_config(url, handler, HandlerConfig::default());
     }
 
-    pub fn match_handler<'a>(
-        &'a self,
+    pub fn add_handler_with_config(
+        &mut self,
         url: &str,
-    ) -> Option<&'a (dyn PayloadHandler + Send + Sync + 'static)> {
-        for (t, h) in &self.handlers {
-            if t == url {
-                return Some(h.as_ref());
-            }
+        handler: Box<dyn PayloadHandler + Send + Sync + 'static>,
+        mut config: HandlerConfig,
+    ) {
+        if let Some(tps_limit) = self.app.sys_config.grpc_request_tps_limit.get(url) {
+            config.tps_limit = *tps_limit;
         }
-        None
+        let limiter = if config.tps_limit > 0 {
+            Some(SecondLimiter::new(config.tps_limit))
+        } else {
+            None
+        };
+        self.handlers.insert(
+            url.to_owned(),
+            HandlerItem {
+                handler,
+                config,
+                limiter,
+            },
+        );
+    }
+
+    pub fn match_handler<'a>(&'a self, url: &str) -> Option<&'a HandlerItem> {
+        self.handlers.get(url)
     }
 
     pub fn ignore_active_err(&self, t: &str) -> bool {
-        SERVER_CHECK_REQUEST.eq(t)
-            || RAFT_APPEND_REQUEST.eq(t)
-            || RAFT_SNAPSHOT_REQUEST.eq(t)
-            || RAFT_VOTE_REQUEST.eq(t)
-            || RAFT_ROUTE_REQUEST.eq(t)
-            || NAMING_ROUTE_REQUEST.eq(t)
+        self.handlers
+            .get(t)
+            .map(|e| e.config.ignore_active_err)
+            .unwrap_or(false)
     }
 
     pub fn ignore_auth(&self, t: &str) -> bool {
-        SERVER_CHECK_REQUEST.eq(t)
-            || HEALTH_CHECK_REQUEST.eq(t)
-            || RAFT_APPEND_REQUEST.eq(t)
-            || RAFT_SNAPSHOT_REQUEST.eq(t)
-            || RAFT_VOTE_REQUEST.eq(t)
-            || RAFT_ROUTE_REQUEST.eq(t)
-            || NAMING_ROUTE_REQUEST.eq(t)
+        self.handlers
+            .get(t)
+            .map(|e| e.config.ignore_auth)
+            .unwrap_or(false)
     }
 
     pub fn is_cluster_request(&self, t: &str) -> bool {
-        RAFT_APPEND_REQUEST.eq(t)
-            || RAFT_SNAPSHOT_REQUEST.eq(t)
-            || RAFT_VOTE_REQUEST.eq(t)
-            || RAFT_ROUTE_REQUEST.eq(t)
-            || NAMING_ROUTE_REQUEST.eq(t)
+        self.handlers
+            .get(t)
+            .map(|e| e.config.cluster_request)
+            .unwrap_or(false)
+    }
+
+    fn record_metrics(&self, metrics_key: MetricsKey) {
+        self.app
+            .metrics_manager
+            .do_send(MetricsRequest::Record(MetricsItem::new(
+                metrics_key,
+                MetricsRecord::CounterInc(1),
+            )));
     }
 
     pub fn add_raft_handler(&mut self, app_data: &Arc<AppShareData>) {
-        self.add_handler(
+        self.add_handler_with_config(
             RAFT_APPEND_REQUEST,
             Box::new(RaftAppendRequestHandler::new(app_data.clone())),
+            HandlerConfig::cluster(),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             RAFT_SNAPSHOT_REQUEST,
             Box::new(RaftSnapshotRequestHandler::new(app_data.clone())),
+            HandlerConfig::cluster(),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             RAFT_VOTE_REQUEST,
             Box::new(RaftVoteRequestHandler::new(app_data.clone())),
+            HandlerConfig::cluster(),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             RAFT_ROUTE_REQUEST,
             Box::new(RaftRouteRequestHandler::new(app_data.clone())),
+            HandlerConfig::cluster(),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             NAMING_ROUTE_REQUEST,
             Box::new(NamingRouteRequestHandler::new(app_data.clone())),
+            HandlerConfig::cluster(),
         );
     }
 
     pub fn add_config_handler(&mut self, app_data: &Arc<AppShareData>) {
-        self.add_handler(
+        self.add_handler_with_config(
             CONFIG_QUERY_REQUEST,
             Box::new(ConfigQueryRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcConfigRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             CONFIG_PUBLISH_REQUEST,
             Box::new(ConfigPublishRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcConfigRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             CONFIG_REMOVE_REQUEST,
             Box::new(ConfigRemoveRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcConfigRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             CONFIG_BATCH_LISTEN_REQUEST,
             Box::new(ConfigChangeBatchListenRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcConfigRequestTotalCount),
+        );
+        self.add_handler_with_config(
+            CONFIG_SUBSCRIBE_REPLAY_REQUEST,
+            Box::new(ConfigSubscribeReplayRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcConfigRequestTotalCount),
         );
     }
 
     pub fn add_naming_handler(&mut self, app_data: &Arc<AppShareData>) {
-        self.add_handler(
+        self.add_handler_with_config(
             INSTANCE_REQUEST,
             Box::new(InstanceRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcNamingRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             BATCH_INSTANCE_REQUEST,
             Box::new(BatchInstanceRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcNamingRequestTotalCount),
+        );
+        self.add_handler_with_config(
+            INSTANCE_METADATA_PATCH_REQUEST,
+            Box::new(InstanceMetadataPatchRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcNamingRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             SUBSCRIBE_SERVICE_REQUEST,
             Box::new(SubscribeServiceRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcNamingRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             SERVICE_QUERY_REQUEST,
             Box::new(ServiceQueryRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcNamingRequestTotalCount),
         );
-        self.add_handler(
+        self.add_handler_with_config(
             SERVICE_LIST_REQUEST,
             Box::new(ServiceListRequestHandler::new(app_data.clone())),
+            HandlerConfig::client(MetricsKey::GrpcNamingRequestTotalCount),
         );
     }
 }
@@ -197,25 +309,30 @@ impl PayloadHandler for InvokerHandler {
         request_meta: RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
         if let Some(url) = PayloadUtils::get_payload_type(&request_payload) {
-            if SERVER_CHECK_REQUEST.eq(url) {
-                let response = ServerCheckResponse {
-                    result_code: SUCCESS_CODE,
-                    connection_id: Some(request_meta.connection_id.as_ref().to_owned()),
-                    ..Default::default()
-                };
-                return Ok(HandlerResult::success(PayloadUtils::build_payload(
-                    "ServerCheckResponse",
-                    serde_json::to_string(&response)?,
-                )));
+            let item = if let Some(item) = self.match_handler(url) {
+                item
+            } else {
+                log::warn!("InvokerHandler not fund handler,type:{}", url);
+                return Ok(HandlerResult::error(
+                    302u16,
+                    format!("{} RequestHandler Not Found", url),
+                ));
+            };
+            if request_meta.canary_unrouted && !item.config.cluster_request {
+                //金丝雀节点只处理路由过来的测试流量,客户端检查失败后会切换到其它节点
+                return Ok(HandlerResult::error(
+                    503u16,
+                    "canary node only serves routed test traffic".to_string(),
+                ));
             }
             if self.app.sys_config.openapi_enable_auth
-                && !self.ignore_auth(url)
+                && !item.config.ignore_auth
                 && request_meta.token_session.is_none()
             {
                 //开启鉴权，但取不到用户会话信息
                 return Ok(HandlerResult::error(403u16, "unknown user!".to_string()));
             } else if !self.app.sys_config.cluster_token.is_empty()
-                && self.is_cluster_request(url)
+                && item.config.cluster_request
                 && !request_meta.cluster_token_is_valid
             {
                 //集群请求key校验不通过
@@ -224,20 +341,57 @@ impl PayloadHandler for InvokerHandler {
                     "request cluster token is invalid".to_string(),
                 ));
             }
-            //println!("InvokerHandler type:{}",url);
-            if let Some(handler) = self.match_handler(url) {
-                return handler.handle(request_payload, request_meta).await;
+            if let Some(limiter) = &item.limiter {
+                if !limiter.acquire() {
+                    self.record_metrics(MetricsKey::GrpcRequestLimitedCount);
+                    return Ok(HandlerResult::error(
+                        429u16,
+                        format!("{} over tps limit", url),
+                    ));
+                }
+            }
+            if let Some(metrics_key) = &item.config.metrics_key {
+                self.record_metrics(metrics_key.clone());
             }
-            log::warn!("InvokerHandler not fund handler,type:{}", url);
-            return Ok(HandlerResult::error(
-                302u16,
-                format!("{} RequestHandler Not Found", url),
-            ));
+            let _permit = match EndpointClass::classify_grpc(url) {
+                Some(class) => match self.app.concurrency_limiter.acquire(class).await {
+                    Ok(v) => v,
+                    Err(err) => {
+                        log::warn!("request is rejected,{},type:{}", err, url);
+                        return Ok(HandlerResult::error(
+                            503u16,
+                            "too many concurrent requests".to_string(),
+                        ));
+                    }
+                },
+                None => None,
+            };
+            //println!("InvokerHandler type:{}",url);
+            return item.handler.handle(request_payload, request_meta).await;
         }
         Ok(HandlerResult::error(302u16, "empty type url".to_owned()))
     }
 }
 
+#[async_trait]
+impl PayloadHandler for ServerCheckRequestHandler {
+    async fn handle(
+        &self,
+        _request_payload: Payload,
+        request_meta: RequestMeta,
+    ) -> anyhow::Result<HandlerResult> {
+        let response = ServerCheckResponse {
+            result_code: SUCCESS_CODE,
+            connection_id: Some(request_meta.connection_id.as_ref().to_owned()),
+            ..Default::default()
+        };
+        Ok(HandlerResult::success(PayloadUtils::build_payload(
+            "ServerCheckResponse",
+            serde_json::to_string(&response)?,
+        )))
+    }
+}
+
 #[async_trait]
 impl PayloadHandler for HealthCheckRequestHandler {
     async fn handle(
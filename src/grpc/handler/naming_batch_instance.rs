@@ -2,6 +2,7 @@
 
 use std::sync::Arc;
 
+use crate::common::namespace_quota::QuotaExceededError;
 use crate::grpc::HandlerResult;
 use crate::{
     common::appdata::AppShareData,
@@ -16,6 +17,7 @@ use crate::{
     naming::{
         core::{NamingActor, NamingCmd},
         model::{Instance, InstanceUpdateTag},
+        validate::NamingValidateUtils,
         NamingUtils,
     },
     now_millis_i64,
@@ -49,7 +51,7 @@ impl BatchInstanceRequestHandler {
         ));
         let service_name = Arc::new(request.service_name.unwrap_or_default());
 
-        let namesapce_id = Arc::new(NamingUtils::default_group(
+        let namesapce_id = Arc::new(NamingUtils::default_namespace(
             request.namespace.unwrap_or_default(),
         ));
         let input = request.instances;
@@ -90,6 +92,10 @@ impl BatchInstanceRequestHandler {
                     from_grpc: true,
                     from_cluster: 0,
                     client_id: client_id.clone(),
+                    client_labels: None,
+                    //grpc注册的版本由服务端生成,注销按连接id隔离
+                    revision: 0,
+                    register_time: 0,
                 };
                 instance.generate_key();
                 list.push(instance);
@@ -117,7 +123,13 @@ impl PayloadHandler for BatchInstanceRequestHandler {
                 is_de_register = true;
             }
         }
-        let instances = Self::convert_to_instances(request, request_meta.connection_id)?;
+        let mut instances = Self::convert_to_instances(request, request_meta.connection_id)?;
+        //注销不校验参数,避免校验规则收紧后无法注销已注册的实例
+        if !is_de_register {
+            for instance in instances.iter_mut() {
+                NamingValidateUtils::normalize_instance(instance)?;
+            }
+        }
         let mut response = InstanceResponse {
             request_id,
             ..Default::default()
@@ -132,10 +144,18 @@ impl PayloadHandler for BatchInstanceRequestHandler {
                     enabled: !instance.enabled,
                     ephemeral: false,
                     from_update: false,
+                    metadata_patch: None,
                 };
                 NamingCmd::Update(instance, Some(update_tag))
             };
-            match self.app_data.naming_addr.send(cmd).await {
+            match self
+                .app_data
+                .naming_addr
+                .send(cmd)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|res| res)
+            {
                 Ok(_res) => {
                     //let res:ConfigResult = res.unwrap();
                     response.result_code = SUCCESS_CODE;
@@ -147,7 +167,7 @@ impl PayloadHandler for BatchInstanceRequestHandler {
                 }
                 Err(err) => {
                     response.result_code = ERROR_CODE;
-                    response.error_code = 500u16;
+                    response.error_code = QuotaExceededError::error_code(&err);
                     response.message = Some(err.to_string());
                     return Ok(HandlerResult::success(PayloadUtils::build_payload(
                         "ErrorResponse",
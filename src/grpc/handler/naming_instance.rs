@@ -5,6 +5,7 @@ use std::sync::{
     Arc,
 };
 
+use crate::common::namespace_quota::QuotaExceededError;
 use crate::grpc::HandlerResult;
 use crate::{
     common::appdata::AppShareData,
@@ -18,6 +19,7 @@ use crate::{
     naming::{
         core::{NamingActor, NamingCmd},
         model::{Instance, InstanceUpdateTag},
+        validate::NamingValidateUtils,
         NamingUtils,
     },
     now_millis_i64,
@@ -83,6 +85,10 @@ impl InstanceRequestHandler {
                 from_grpc: true,
                 from_cluster: 0,
                 client_id,
+                client_labels: None,
+                //grpc注册的版本由服务端生成,注销按连接id隔离
+                revision: 0,
+                register_time: 0,
             };
             instance.generate_key();
             Ok(instance)
@@ -108,7 +114,11 @@ impl PayloadHandler for InstanceRequestHandler {
                 is_de_register = true;
             }
         }
-        let instance = Self::convert_to_instance(request, request_meta.connection_id)?;
+        let mut instance = Self::convert_to_instance(request, request_meta.connection_id)?;
+        //注销不校验参数,避免校验规则收紧后无法注销已注册的实例
+        if !is_de_register {
+            NamingValidateUtils::normalize_instance(&mut instance)?;
+        }
         let cmd = if is_de_register {
             NamingCmd::Delete(instance)
         } else {
@@ -118,6 +128,7 @@ impl PayloadHandler for InstanceRequestHandler {
                 enabled: !instance.enabled,
                 ephemeral: false,
                 from_update: false,
+                metadata_patch: None,
             };
             NamingCmd::Update(instance, Some(update_tag))
         };
@@ -125,7 +136,14 @@ impl PayloadHandler for InstanceRequestHandler {
             request_id,
             ..Default::default()
         };
-        match self.app_data.naming_addr.send(cmd).await {
+        match self
+            .app_data
+            .naming_addr
+            .send(cmd)
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|res| res)
+        {
             Ok(_res) => {
                 //let res:ConfigResult = res.unwrap();
                 response.result_code = SUCCESS_CODE;
@@ -137,7 +155,7 @@ impl PayloadHandler for InstanceRequestHandler {
             }
             Err(err) => {
                 response.result_code = ERROR_CODE;
-                response.error_code = 500u16;
+                response.error_code = QuotaExceededError::error_code(&err);
                 response.message = Some(err.to_string());
                 return Ok(HandlerResult::success(PayloadUtils::build_payload(
                     "ErrorResponse",
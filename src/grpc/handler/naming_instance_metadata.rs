@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::common::appdata::AppShareData;
+use crate::grpc::api_model::{
+    InstanceMetadataPatchRequest, InstanceMetadataPatchResponse, ERROR_CODE, SUCCESS_CODE,
+};
+use crate::grpc::{HandlerResult, PayloadHandler, PayloadUtils};
+use crate::naming::metadata_patch::InstanceMetadataPatch;
+use crate::naming::model::Instance;
+use crate::naming::NamingUtils;
+use async_trait::async_trait;
+
+pub struct InstanceMetadataPatchRequestHandler {
+    app_data: Arc<AppShareData>,
+}
+
+impl InstanceMetadataPatchRequestHandler {
+    pub fn new(app_data: Arc<AppShareData>) -> Self {
+        Self { app_data }
+    }
+
+    fn convert(
+        request: InstanceMetadataPatchRequest,
+    ) -> anyhow::Result<(Instance, InstanceMetadataPatch)> {
+        let ip = match request.ip {
+            Some(ip) if !ip.is_empty() => ip,
+            _ => return Err(anyhow::anyhow!("ip is empty")),
+        };
+        let service_name = match request.service_name {
+            Some(v) if !v.is_empty() => v,
+            _ => return Err(anyhow::anyhow!("serviceName is empty")),
+        };
+        let mut instance = Instance::new(ip, request.port);
+        instance.namespace_id = Arc::new(NamingUtils::default_namespace(
+            request.namespace.unwrap_or_default(),
+        ));
+        instance.group_name = Arc::new(NamingUtils::default_group(
+            request.group_name.unwrap_or_default(),
+        ));
+        instance.service_name = Arc::new(service_name);
+        instance.generate_key();
+        let patch = InstanceMetadataPatch {
+            upsert: request.metadata.unwrap_or_default(),
+            remove_keys: request.remove_keys.unwrap_or_default(),
+        };
+        Ok((instance, patch))
+    }
+}
+
+#[async_trait]
+impl PayloadHandler for InstanceMetadataPatchRequestHandler {
+    async fn handle(
+        &self,
+        request_payload: crate::grpc::nacos_proto::Payload,
+        _request_meta: crate::grpc::RequestMeta,
+    ) -> anyhow::Result<HandlerResult> {
+        let body_vec = request_payload.body.unwrap_or_default().value;
+        let request: InstanceMetadataPatchRequest = serde_json::from_slice(&body_vec)?;
+        let mut response = InstanceMetadataPatchResponse {
+            request_id: request.request_id.clone(),
+            ..Default::default()
+        };
+        let res = match Self::convert(request) {
+            Ok((instance, patch)) => patch.patch_instance(&self.app_data, instance).await,
+            Err(err) => Err(err),
+        };
+        match res {
+            Ok(metadata) => {
+                response.result_code = SUCCESS_CODE;
+                response.metadata = Some(metadata);
+            }
+            Err(err) => {
+                response.result_code = ERROR_CODE;
+                response.error_code = 500u16;
+                response.message = Some(err.to_string());
+                return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                    "ErrorResponse",
+                    serde_json::to_string(&response)?,
+                )));
+            }
+        }
+        Ok(HandlerResult::success(PayloadUtils::build_payload(
+            "InstanceMetadataPatchResponse",
+            serde_json::to_string(&response)?,
+        )))
+    }
+}
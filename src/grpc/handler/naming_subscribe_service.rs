@@ -16,7 +16,8 @@ use crate::{
     naming::{
         core::{NamingActor, NamingCmd, NamingResult},
         model::{Instance, ServiceInfo, ServiceKey},
-        naming_subscriber::NamingListenerItem,
+        naming_subscriber::{NamingListenerItem, SubscriberClient},
+        pattern_subscriber::{PatternSubscribeItem, ServiceNamePattern},
         NamingUtils,
     },
     now_millis_i64,
@@ -39,22 +40,61 @@ impl SubscribeServiceRequestHandler {
         ModelConverter::to_api_service_info(info)
     }
 
+    fn build_subscriber_client(request_meta: &crate::grpc::RequestMeta) -> Arc<SubscriberClient> {
+        Arc::new(SubscriberClient {
+            client_ip: request_meta.client_ip.clone(),
+            agent: request_meta.client_version.clone(),
+            app_name: request_meta
+                .labels
+                .get("AppName")
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+
     fn build_subscribe_cmd(
         &self,
         subscribe: bool,
         service_key: ServiceKey,
-        connection_id: Arc<String>,
+        request_meta: &crate::grpc::RequestMeta,
     ) -> NamingCmd {
+        let connection_id = request_meta.connection_id.clone();
         let item = NamingListenerItem {
             service_key,
             clusters: None,
         };
         if subscribe {
-            NamingCmd::Subscribe(vec![item], connection_id)
+            let client = Self::build_subscriber_client(request_meta);
+            NamingCmd::Subscribe(vec![item], connection_id, Some(client))
         } else {
             NamingCmd::RemoveSubscribe(vec![item], connection_id)
         }
     }
+
+    ///
+    /// 服务名为模式(前缀*或regex:正则)时按模式订阅,匹配的服务变更通过推送下发
+    fn build_subscribe_pattern_cmd(
+        &self,
+        subscribe: bool,
+        service_key: ServiceKey,
+        request_meta: &crate::grpc::RequestMeta,
+    ) -> anyhow::Result<NamingCmd> {
+        let connection_id = request_meta.connection_id.clone();
+        if subscribe {
+            let item = PatternSubscribeItem::new(&service_key)?;
+            let client = Self::build_subscriber_client(request_meta);
+            Ok(NamingCmd::SubscribePattern(
+                item,
+                connection_id,
+                Some(client),
+            ))
+        } else {
+            Ok(NamingCmd::RemoveSubscribePattern(
+                service_key,
+                connection_id,
+            ))
+        }
+    }
 }
 
 #[async_trait]
@@ -87,13 +127,36 @@ impl PayloadHandler for SubscribeServiceRequestHandler {
             &NamingUtils::default_group(request.group_name.unwrap_or_default()),
             &request.service_name.unwrap_or_default(),
         );
-        let subscribe_cmd = self.build_subscribe_cmd(
-            request.subscribe,
-            key.clone(),
-            request_meta.connection_id.clone(),
-        );
+        if ServiceNamePattern::is_pattern(&key.service_name) {
+            match self.build_subscribe_pattern_cmd(request.subscribe, key.clone(), &request_meta) {
+                Ok(cmd) => {
+                    self.app_data.naming_addr.do_send(cmd);
+                    let service_info = ServiceInfo {
+                        name: Some(key.service_name),
+                        group_name: Some(key.group_name),
+                        clusters: Some(cluster),
+                        hosts: Some(vec![]),
+                        last_ref_time: now_millis_i64(),
+                        ..Default::default()
+                    };
+                    response.service_info = Some(self.convert_to_service_info(service_info));
+                    response.result_code = SUCCESS_CODE;
+                }
+                Err(err) => {
+                    response.result_code = ERROR_CODE;
+                    response.error_code = 500u16;
+                    response.message = Some(err.to_string());
+                }
+            }
+            return Ok(HandlerResult::success(PayloadUtils::build_payload(
+                "SubscribeServiceResponse",
+                serde_json::to_string(&response)?,
+            )));
+        }
+        let subscribe_cmd = self.build_subscribe_cmd(request.subscribe, key.clone(), &request_meta);
         self.app_data.naming_addr.do_send(subscribe_cmd);
-        let cmd = NamingCmd::QueryServiceInfo(key, cluster, true);
+        let cmd =
+            NamingCmd::QueryServiceInfo(key, cluster, true, Some(request_meta.client_ip.clone()));
         match self.app_data.naming_addr.send(cmd).await {
             Ok(res) => {
                 let result: NamingResult = res.unwrap();
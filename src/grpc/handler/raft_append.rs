@@ -3,6 +3,8 @@ use std::sync::Arc;
 use crate::common::appdata::AppShareData;
 use crate::grpc::nacos_proto::Payload;
 use crate::grpc::{HandlerResult, PayloadHandler, PayloadUtils, RequestMeta};
+use crate::now_millis;
+use crate::raft::network::core::RaftPayloadUtils;
 use crate::raft::store::ClientRequest;
 use async_trait::async_trait;
 
@@ -23,10 +25,13 @@ impl PayloadHandler for RaftAppendRequestHandler {
         request_payload: Payload,
         _request_meta: RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
-        let body_vec = request_payload.body.unwrap_or_default().value;
+        let body_vec = RaftPayloadUtils::get_body(request_payload)?;
         let request: async_raft_ext::raft::AppendEntriesRequest<ClientRequest> =
             serde_json::from_slice(&body_vec)?;
         let res = self.app_data.raft.append_entries(request).await?;
+        if res.success {
+            self.app_data.data_freshness.update_sync_time(now_millis());
+        }
         let value = serde_json::to_string(&res)?;
         //log::info!("RaftAppendRequestHandler result:{}",&value);
         let payload = PayloadUtils::build_payload("RaftAppendResponse", value);
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::common::appdata::AppShareData;
 use crate::grpc::nacos_proto::Payload;
 use crate::grpc::{HandlerResult, PayloadHandler, PayloadUtils, RequestMeta};
+use crate::raft::network::core::RaftPayloadUtils;
 use async_trait::async_trait;
 
 pub struct RaftSnapshotRequestHandler {
@@ -22,7 +23,7 @@ impl PayloadHandler for RaftSnapshotRequestHandler {
         request_payload: Payload,
         _request_meta: RequestMeta,
     ) -> anyhow::Result<HandlerResult> {
-        let body_vec = request_payload.body.unwrap_or_default().value;
+        let body_vec = RaftPayloadUtils::get_body(request_payload)?;
         let request: async_raft_ext::raft::InstallSnapshotRequest =
             serde_json::from_slice(&body_vec)?;
         let res = self.app_data.raft.install_snapshot(request).await?;
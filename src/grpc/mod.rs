@@ -1,7 +1,9 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use self::api_model::BaseResponse;
 use crate::common::model::TokenSession;
+use crate::common::AppSysConfig;
+use crate::user::permission::UserRoleHelper;
 use async_trait::async_trait;
 
 pub mod api_model;
@@ -20,6 +22,38 @@ pub struct RequestMeta {
     pub labels: HashMap<String, String>,
     pub token_session: Option<Arc<TokenSession>>,
     pub cluster_token_is_valid: bool,
+    /// 金丝雀节点上没有携带路由标记的请求
+    pub canary_unrouted: bool,
+}
+
+///
+/// 连接对端的客户端ip;unix domain socket连接的对端总是本机
+pub fn get_client_ip(remote_addr: &SocketAddr) -> String {
+    #[cfg(unix)]
+    if crate::common::unix_socket::UnixConnection::is_connection_addr(remote_addr) {
+        return crate::common::constant::LOCAL_CLIENT_IP.to_owned();
+    }
+    remote_addr.ip().to_string()
+}
+
+///
+/// grpc连接id为对端地址,从中取出客户端ip
+pub fn get_connection_ip(connection_id: &str) -> Option<String> {
+    connection_id
+        .parse::<SocketAddr>()
+        .ok()
+        .map(|addr| get_client_ip(&addr))
+}
+
+impl RequestMeta {
+    ///
+    /// 请求方是否为管理员;未开启接口鉴权时视为管理员
+    pub fn is_manager(&self, sys_config: &AppSysConfig) -> bool {
+        match &self.token_session {
+            Some(session) => UserRoleHelper::is_manager(&session.roles),
+            None => !sys_config.openapi_enable_auth,
+        }
+    }
 }
 
 pub struct HandlerResult {
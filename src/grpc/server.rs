@@ -2,14 +2,18 @@ use std::sync::Arc;
 use std::time::SystemTime;
 
 use crate::common::appdata::AppShareData;
-use crate::common::constant::{ACCESS_TOKEN_HEADER, AUTHORIZATION_HEADER, EMPTY_ARC_STRING};
+use crate::common::constant::{
+    ACCESS_TOKEN_HEADER, AUTHORIZATION_HEADER, CANARY_HEADER, EMPTY_ARC_STRING,
+};
 use crate::common::model::TokenSession;
+use crate::common::trace_utils::{TraceUtils, REQUEST_ID_HEADER};
+use crate::common::web_utils::get_bearer_token;
 use actix::prelude::*;
 //use tokio_stream::StreamExt;
 
 use crate::grpc::bistream_manage::BiStreamManageResult;
 use crate::grpc::nacos_proto::{request_server, Payload};
-use crate::grpc::{PayloadHandler, PayloadUtils, RequestMeta};
+use crate::grpc::{get_client_ip, PayloadHandler, PayloadUtils, RequestMeta};
 use crate::metrics::metrics_key::MetricsKey;
 use crate::metrics::model::{MetricsItem, MetricsRecord, MetricsRequest};
 use crate::raft::cache::model::{CacheKey, CacheType, CacheValue};
@@ -38,13 +42,20 @@ impl RequestServerImpl {
             if let Some(v) = meta.headers.get(ACCESS_TOKEN_HEADER) {
                 Arc::new(v.to_owned())
             } else if let Some(v) = meta.headers.get(AUTHORIZATION_HEADER) {
-                Arc::new(v.to_owned())
+                Arc::new(get_bearer_token(v).to_owned())
             } else {
                 EMPTY_ARC_STRING.clone()
             }
         } else {
             EMPTY_ARC_STRING.clone()
         };
+        request_meta.canary_unrouted = !self.app.sys_config.pass_canary(
+            payload
+                .metadata
+                .as_ref()
+                .and_then(|e| e.headers.get(CANARY_HEADER))
+                .map(|v| v.as_str()),
+        );
         if self.app.sys_config.openapi_enable_auth && !token.is_empty() {
             if let Ok(Some(session)) = get_user_session(
                 &self.app.cache_manager,
@@ -90,19 +101,28 @@ impl request_server::Request for RequestServerImpl {
         request: tonic::Request<Payload>,
     ) -> Result<tonic::Response<Payload>, tonic::Status> {
         let start = SystemTime::now();
-        let remote_addr = request.remote_addr().unwrap();
+        let remote_addr = request
+            .remote_addr()
+            .ok_or_else(|| tonic::Status::internal("unknown remote address"))?;
         let payload = request.into_inner();
         let mut request_meta = RequestMeta {
-            client_ip: remote_addr.ip().to_string(),
+            client_ip: get_client_ip(&remote_addr),
             connection_id: Arc::new(remote_addr.to_string()),
             ..Default::default()
         };
         //debug
         //log::info!( "client request: {}", PayloadUtils::get_payload_string(&payload));
         let request_type = PayloadUtils::get_payload_type(&payload).unwrap();
+        let request_id = TraceUtils::get_or_new_request_id(
+            payload
+                .metadata
+                .as_ref()
+                .and_then(|e| e.headers.get(REQUEST_ID_HEADER))
+                .map(|e| e.as_str()),
+        );
         let request_log_info = format!(
-            "|grpc|client_request|{}|{}",
-            &request_meta.connection_id, &request_type
+            "|grpc|client_request|{}|{}|{}",
+            &request_meta.connection_id, &request_type, &request_id
         );
         let ignore_active_err = self.invoker.ignore_active_err(request_type);
         //self.bistream_manage_addr.do_send(BiStreamManageCmd::ActiveClinet(request_meta.connection_id.clone()));
@@ -156,7 +176,11 @@ impl request_server::Request for RequestServerImpl {
         self.fill_token_session(&payload, &mut request_meta)
             .await
             .ok();
-        let handle_result = self.invoker.handle(payload, request_meta).await;
+        let handle_result = TraceUtils::scope(
+            request_id.clone(),
+            self.invoker.handle(payload, request_meta),
+        )
+        .await;
         let duration = SystemTime::now()
             .duration_since(start)
             .unwrap_or_default()
@@ -180,9 +204,17 @@ impl request_server::Request for RequestServerImpl {
                 } else {
                     //slow request handle
                     log::warn!("{}|ok|{}", request_log_info, duration);
+                    self.app
+                        .slow_request_log
+                        .record(request_log_info.clone(), duration);
                     self.record_req_metrics(duration, true);
                 }
-                Ok(tonic::Response::new(res.payload))
+                let mut payload = res.payload;
+                if let Some(meta) = payload.metadata.as_mut() {
+                    meta.headers
+                        .insert(REQUEST_ID_HEADER.to_owned(), request_id.as_ref().to_owned());
+                }
+                Ok(tonic::Response::new(payload))
             }
             Err(e) => {
                 //Err(tonic::Status::aborted(e.to_string()))
@@ -219,7 +251,10 @@ impl BiRequestStream for BiRequestStreamServerImpl {
         &self,
         request: tonic::Request<tonic::Streaming<Payload>>,
     ) -> Result<tonic::Response<Self::requestBiStreamStream>, tonic::Status> {
-        let client_id = Arc::new(request.remote_addr().unwrap().to_string());
+        let remote_addr = request
+            .remote_addr()
+            .ok_or_else(|| tonic::Status::internal("unknown remote address"))?;
+        let client_id = Arc::new(remote_addr.to_string());
         let req = request.into_inner();
         let (tx, rx) = tokio::sync::mpsc::channel(10);
         let r_stream = tokio_stream::wrappers::ReceiverStream::new(rx);
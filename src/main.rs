@@ -24,22 +24,31 @@ use rnacos::{grpc::server::RequestServerImpl, naming::core::NamingActor, openapi
 use sled::Db;
 use std::collections::{BTreeMap, HashSet};
 use std::error::Error;
+use std::net::SocketAddr;
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 use tonic::transport::Server;
 
 use actix_web::{middleware, HttpServer};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use env_logger::TimestampPrecision;
 use env_logger_timezone_fmt::{TimeZoneFormat, TimeZoneFormatEnv};
 //use mimalloc::MiMalloc;
 use rnacos::common::appdata::AppShareData;
 use rnacos::common::constant::APP_VERSION;
+use rnacos::common::shutdown::{ShutdownState, ShutdownUtils};
+#[cfg(unix)]
+use rnacos::common::unix_socket::UnixSocketUtils;
 use rnacos::openapi::middle::auth_middle::ApiCheckAuth;
+use rnacos::openapi::middle::concurrency_middle::ConcurrencyLimit;
+use rnacos::openapi::middle::trace_middle::RequestTrace;
 use rnacos::raft::NacosRaft;
 use rnacos::web_config::{app_config, console_config};
 
+/// actix默认日志格式,追加请求链路id
+const HTTP_LOG_FORMAT: &str = r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#;
+
 //#[global_allocator]
 //static GLOBAL: MiMalloc = MiMalloc;
 
@@ -49,11 +58,33 @@ pub struct AppOpt {
     /// env file path
     #[arg(short, long, default_value = "")]
     pub env_file: String,
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum Commands {
+    /// download a diagnostics bundle from a running node
+    SupportBundle {
+        /// console address of the node
+        #[arg(short, long, default_value = "http://127.0.0.1:10848")]
+        url: String,
+        /// console login token
+        #[arg(short, long, default_value = "")]
+        token: String,
+        /// output file path
+        #[arg(short, long, default_value = "rnacos_support_bundle.zip")]
+        output: String,
+    },
 }
 
 #[actix_web::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    init_env();
+    let app_opt = AppOpt::parse();
+    init_env(&app_opt.env_file);
+    if let Some(Commands::SupportBundle { url, token, output }) = app_opt.command {
+        return download_support_bundle(&url, &token, &output).await;
+    }
     let rust_log = std::env::var("RUST_LOG").unwrap_or("info".to_owned());
     println!("version:{}, RUST_LOG:{}", APP_VERSION, &rust_log);
     std::env::set_var("RUST_LOG", &rust_log);
@@ -73,30 +104,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
     log::info!("http server addr:{}", &http_addr);
     log::info!("grpc server addr:{}", &grpc_addr);
 
-    let mut invoker = InvokerHandler::new(app_data.clone());
-    invoker.add_config_handler(&app_data);
-    invoker.add_naming_handler(&app_data);
-    invoker.add_raft_handler(&app_data);
-
-    let grpc_app_data = app_data.clone();
-
-    tokio::spawn(async move {
-        let addr = grpc_addr.parse().unwrap();
-        let request_server = RequestServerImpl::new(grpc_app_data.clone(), invoker);
-        let bi_request_stream_server =
-            BiRequestStreamServerImpl::new(grpc_app_data.bi_stream_manage.clone());
-        Server::builder()
-            .add_service(RequestServer::new(request_server))
-            .add_service(BiRequestStreamServer::new(bi_request_stream_server))
-            .serve(addr)
-            .await
-            .unwrap();
-    });
+    let grpc_socket_addr = parse_socket_addr(&grpc_addr)?;
+    let mut grpc_servers = vec![tokio::spawn(run_grpc_server(
+        app_data.clone(),
+        grpc_socket_addr,
+    ))];
+    if let Some(cluster_grpc_addr) = sys_config.cluster_grpc_addr.clone() {
+        //集群内部通信使用独立的监听地址,便于按网卡隔离;只注册集群内部请求
+        log::info!("cluster grpc server addr:{}", &cluster_grpc_addr);
+        let cluster_socket_addr = parse_socket_addr(&cluster_grpc_addr)?;
+        grpc_servers.push(tokio::spawn(run_cluster_grpc_server(
+            app_data.clone(),
+            cluster_socket_addr,
+        )));
+    }
+    #[cfg(unix)]
+    if let Some(path) = sys_config.grpc_unix_socket.clone() {
+        log::info!("grpc server unix socket:{}", &path);
+        grpc_servers.push(tokio::spawn(run_grpc_unix_server(app_data.clone(), path)));
+    }
 
+    let mut console_server = None;
     if sys_config.http_console_port > 0 {
         let app_console_data = app_data.clone();
 
-        std::thread::spawn(move || {
+        console_server = Some(std::thread::spawn(move || {
             actix_rt::System::with_tokio_rt(|| {
                 tokio::runtime::Builder::new_current_thread()
                     .enable_all()
@@ -104,9 +136,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     .unwrap()
             })
             .block_on(run_console_web(app_console_data));
-        });
+        }));
     }
 
+    let shutdown_app_data = app_data.clone();
     let mut server = HttpServer::new(move || {
         let app_data = app_data.clone();
         let config_addr = app_data.config_addr.clone();
@@ -119,21 +152,48 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .app_data(Data::new(config_addr))
             .app_data(Data::new(naming_addr))
             .app_data(Data::new(bistream_manage_http_addr))
+            .wrap(ConcurrencyLimit::new(source_app_data.clone()))
             .wrap(ApiCheckAuth::new(source_app_data))
-            .wrap(middleware::Logger::default())
+            .wrap(RequestTrace::new())
+            .wrap(middleware::Logger::new(HTTP_LOG_FORMAT))
             .configure(app_config(app_config_shard))
     });
     if let Some(num) = sys_config.http_workers {
         server = server.workers(num);
     }
+    server = server.bind(http_addr)?;
+    #[cfg(unix)]
+    if let Some(path) = sys_config.http_unix_socket.as_ref() {
+        log::info!("http server unix socket:{}", path);
+        UnixSocketUtils::remove_stale(path)?;
+        server = server.bind_uds(path)?;
+        UnixSocketUtils::set_mode(path, sys_config.unix_socket_mode)?;
+    }
     println!("rnacos started");
-    server.bind(http_addr)?.run().await?;
+    let server = server.run();
+    let server_handle = server.handle();
+    let shutdown_manager = shutdown_app_data.shutdown_manager.clone();
+    tokio::spawn(async move {
+        shutdown_manager.wait_for(ShutdownState::Stopping).await;
+        server_handle.stop(true).await;
+    });
+    server.await?;
+    //等待所有服务停止监听后再释放资源
+    for grpc_server in grpc_servers {
+        match grpc_server.await {
+            Ok(Err(err)) => log::error!("grpc server error,{}", err),
+            Err(err) => log::error!("grpc server join error,{}", err),
+            _ => {}
+        }
+    }
+    if let Some(console_server) = console_server {
+        tokio::task::spawn_blocking(move || console_server.join().ok()).await?;
+    }
+    ShutdownUtils::cleanup(&shutdown_app_data).await?;
     Ok(())
 }
 
-fn init_env() {
-    let app_opt = AppOpt::parse();
-    let env_path = app_opt.env_file;
+fn init_env(env_path: &str) {
     //let env_path = std::env::var("RNACOS_ENV_FILE").unwrap_or_default();
     if env_path.is_empty() {
         dotenv::dotenv().ok();
@@ -142,11 +202,115 @@ fn init_env() {
     }
 }
 
+async fn download_support_bundle(
+    url: &str,
+    token: &str,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    let url = format!(
+        "{}/rnacos/api/console/v2/cluster/support_bundle",
+        url.trim_end_matches('/')
+    );
+    let resp = reqwest::Client::new()
+        .get(&url)
+        .header("Token", token)
+        .send()
+        .await?;
+    let is_zip = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .map(|v| v.as_bytes() == b"application/octet-stream")
+        .unwrap_or(false);
+    let body = resp.bytes().await?;
+    if !is_zip {
+        return Err(format!(
+            "download support bundle failed: {}",
+            String::from_utf8_lossy(&body)
+        )
+        .into());
+    }
+    std::fs::write(output, &body)?;
+    println!("support bundle saved to {}", output);
+    Ok(())
+}
+
+fn parse_socket_addr(addr: &str) -> anyhow::Result<SocketAddr> {
+    addr.parse()
+        .map_err(|err| anyhow::anyhow!("invalid grpc address {},{}", addr, err))
+}
+
+fn new_grpc_services(
+    app_data: &Arc<AppShareData>,
+) -> (
+    RequestServer<RequestServerImpl>,
+    BiRequestStreamServer<BiRequestStreamServerImpl>,
+) {
+    let mut invoker = InvokerHandler::new(app_data.clone());
+    invoker.add_config_handler(app_data);
+    invoker.add_naming_handler(app_data);
+    invoker.add_raft_handler(app_data);
+    let request_server = RequestServerImpl::new(app_data.clone(), invoker);
+    let bi_request_stream_server =
+        BiRequestStreamServerImpl::new(app_data.bi_stream_manage.clone());
+    (
+        RequestServer::new(request_server),
+        BiRequestStreamServer::new(bi_request_stream_server),
+    )
+}
+
+async fn run_grpc_server(app_data: Arc<AppShareData>, addr: SocketAddr) -> anyhow::Result<()> {
+    let (request_server, bi_request_stream_server) = new_grpc_services(&app_data);
+    let shutdown_manager = app_data.shutdown_manager.clone();
+    Server::builder()
+        .add_service(request_server)
+        .add_service(bi_request_stream_server)
+        .serve_with_shutdown(addr, async move {
+            shutdown_manager.wait_for(ShutdownState::Stopping).await
+        })
+        .await?;
+    Ok(())
+}
+
+///
+/// 集群内部通信的grpc服务,只处理raft与路由请求,不接受客户端的配置与服务请求及双向流
+async fn run_cluster_grpc_server(
+    app_data: Arc<AppShareData>,
+    addr: SocketAddr,
+) -> anyhow::Result<()> {
+    let mut invoker = InvokerHandler::new(app_data.clone());
+    invoker.add_raft_handler(&app_data);
+    let request_server = RequestServerImpl::new(app_data.clone(), invoker);
+    let shutdown_manager = app_data.shutdown_manager.clone();
+    Server::builder()
+        .add_service(RequestServer::new(request_server))
+        .serve_with_shutdown(addr, async move {
+            shutdown_manager.wait_for(ShutdownState::Stopping).await
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn run_grpc_unix_server(app_data: Arc<AppShareData>, path: String) -> anyhow::Result<()> {
+    let listener = UnixSocketUtils::bind(&path, app_data.sys_config.unix_socket_mode)?;
+    let (request_server, bi_request_stream_server) = new_grpc_services(&app_data);
+    let shutdown_manager = app_data.shutdown_manager.clone();
+    Server::builder()
+        .add_service(request_server)
+        .add_service(bi_request_stream_server)
+        .serve_with_incoming_shutdown(UnixSocketUtils::incoming(listener), async move {
+            shutdown_manager.wait_for(ShutdownState::Stopping).await
+        })
+        .await?;
+    Ok(())
+}
+
 async fn run_console_web(source_app_data: Arc<AppShareData>) {
     let http_console_addr = source_app_data.sys_config.get_http_console_addr();
     log::info!("new console server http addr:{}", &http_console_addr);
     let app_data = Data::new(source_app_data.clone());
-    HttpServer::new(move || {
+    let shutdown_manager = source_app_data.shutdown_manager.clone();
+    let server = HttpServer::new(move || {
         let source_app_data = source_app_data.clone();
         let config_addr = app_data.config_addr.clone();
         let naming_addr = app_data.naming_addr.clone();
@@ -157,15 +321,21 @@ async fn run_console_web(source_app_data: Arc<AppShareData>) {
             .app_data(Data::new(config_addr))
             .app_data(Data::new(naming_addr))
             .app_data(Data::new(bistream_manage_http_addr))
+            .wrap(ConcurrencyLimit::new(source_app_data.clone()))
             .wrap(CheckLogin::new(source_app_data))
-            .wrap(middleware::Logger::default())
+            .wrap(RequestTrace::new())
+            .wrap(middleware::Logger::new(HTTP_LOG_FORMAT))
             .wrap(middleware::Compress::default())
             .configure(console_config)
     })
     .workers(2)
     .bind(http_console_addr)
     .unwrap()
-    .run()
-    .await
-    .ok();
+    .run();
+    let server_handle = server.handle();
+    actix_rt::spawn(async move {
+        shutdown_manager.wait_for(ShutdownState::Stopping).await;
+        server_handle.stop(true).await;
+    });
+    server.await.ok();
 }
@@ -2,6 +2,7 @@ use crate::common::AppSysConfig;
 use crate::config::core::ConfigActor;
 use crate::grpc::bistream_manage::BiStreamManage;
 use crate::metrics::counter::CounterManager;
+use crate::metrics::exporter::MetricsExporter;
 use crate::metrics::gauge::GaugeManager;
 use crate::metrics::histogram::HistogramManager;
 use crate::metrics::metrics_key::MetricsKey;
@@ -13,6 +14,7 @@ use crate::metrics::timeline::core::MetricsTimelineManager;
 use crate::metrics::timeline::model::{MetricsSnapshot, TimelineGroupType};
 use crate::naming::core::NamingActor;
 use crate::now_millis;
+use crate::raft::cache::CacheManager;
 use actix::prelude::*;
 use bean_factory::{bean, BeanFactory, FactoryData, Inject};
 use bytes::BytesMut;
@@ -31,7 +33,9 @@ pub struct MetricsManager {
     naming_actor: Option<Addr<NamingActor>>,
     config_actor: Option<Addr<ConfigActor>>,
     bi_stream_manage: Option<Addr<BiStreamManage>>,
+    cache_manager: Option<Addr<CacheManager>>,
     metrics_timeline_manager: MetricsTimelineManager,
+    exporter: Option<MetricsExporter>,
     system: System,
     current_process_id: u32,
     start_time_millis: u64,
@@ -59,7 +63,9 @@ impl MetricsManager {
             naming_actor: None,
             config_actor: None,
             bi_stream_manage: None,
+            cache_manager: None,
             metrics_timeline_manager: MetricsTimelineManager::new(),
+            exporter: MetricsExporter::new(&app_sys_config),
             system,
             current_process_id,
             start_time_millis,
@@ -122,6 +128,7 @@ impl MetricsManager {
         naming_actor: Option<Addr<NamingActor>>,
         config_actor: Option<Addr<ConfigActor>>,
         bi_stream_manage: Option<Addr<BiStreamManage>>,
+        cache_manager: Option<Addr<CacheManager>>,
     ) -> anyhow::Result<Vec<MetricsItem>> {
         let mut list = vec![];
         if let Some(naming_actor) = naming_actor {
@@ -136,6 +143,10 @@ impl MetricsManager {
             let mut t = bi_stream_manage.send(MetricsQuery).await??;
             list.append(&mut t);
         }
+        if let Some(cache_manager) = cache_manager {
+            let mut t = cache_manager.send(MetricsQuery).await??;
+            list.append(&mut t);
+        }
         Ok(list)
     }
 
@@ -230,21 +241,35 @@ impl MetricsManager {
         self.record_timeline_snapshot(now, TimelineGroupType::Least);
         self.record_timeline_snapshot(now, TimelineGroupType::Minute);
         self.record_timeline_snapshot(now, TimelineGroupType::Hour);
+        self.push_metrics(now);
+    }
+
+    fn push_metrics(&mut self, now_ms: u64) {
+        if self.exporter.is_none() {
+            return;
+        }
+        let snapshot = self.build_snapshot(now_ms);
+        if let Some(exporter) = self.exporter.as_mut() {
+            exporter.export(snapshot);
+        }
     }
 
     fn load_metrics(&mut self, ctx: &mut Context<Self>) {
         let naming_actor = self.naming_actor.clone();
         let config_actor = self.config_actor.clone();
         let bi_stream_manage = self.bi_stream_manage.clone();
-        async move { Self::do_peek_metrics(naming_actor, config_actor, bi_stream_manage).await }
-            .into_actor(self)
-            .map(|r, act, ctx| {
-                //Self::log_metrics(&r);
-                act.update_peek_metrics(r);
-                act.after_peek_metrics();
-                act.hb(ctx);
-            })
-            .spawn(ctx);
+        let cache_manager = self.cache_manager.clone();
+        async move {
+            Self::do_peek_metrics(naming_actor, config_actor, bi_stream_manage, cache_manager).await
+        }
+        .into_actor(self)
+        .map(|r, act, ctx| {
+            //Self::log_metrics(&r);
+            act.update_peek_metrics(r);
+            act.after_peek_metrics();
+            act.hb(ctx);
+        })
+        .spawn(ctx);
     }
 
     fn build_snapshot(&self, now_ms: u64) -> MetricsSnapshot {
@@ -307,6 +332,7 @@ impl Inject for MetricsManager {
         self.naming_actor = factory_data.get_actor();
         self.config_actor = factory_data.get_actor();
         self.bi_stream_manage = factory_data.get_actor();
+        self.cache_manager = factory_data.get_actor();
         self.metrics_timeline_manager
             .set_least_interval(self.app_sys_config.metrics_collect_interval_second);
         if self.app_sys_config.metrics_enable {
@@ -314,6 +340,13 @@ impl Inject for MetricsManager {
                 "metrics enable! log_interval: {}s",
                 self.app_sys_config.metrics_log_interval_second
             );
+            if let Some(exporter) = &self.exporter {
+                log::info!(
+                    "metrics exporter enable! type: {:?}, addr: {}",
+                    exporter.get_type(),
+                    exporter.get_addr()
+                );
+            }
             self.init(ctx);
         } else {
             log::info!("metrics disable!");
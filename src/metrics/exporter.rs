@@ -0,0 +1,295 @@
+use crate::common::AppSysConfig;
+use crate::metrics::model::HistogramValue;
+use crate::metrics::timeline::model::MetricsSnapshot;
+use crate::now_millis;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+/// statsd指标名前缀
+const STATSD_PREFIX: &str = "rnacos.";
+/// 单个udp包的最大字节数,避免超过常见网络的MTU
+const STATSD_MAX_PACKET_SIZE: usize = 1400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsExporterType {
+    Statsd,
+    Otlp,
+}
+
+impl MetricsExporterType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "statsd" => Some(Self::Statsd),
+            "otlp" => Some(Self::Otlp),
+            _ => None,
+        }
+    }
+
+    fn default_addr(&self) -> &'static str {
+        match self {
+            Self::Statsd => "127.0.0.1:8125",
+            Self::Otlp => "http://127.0.0.1:4318/v1/metrics",
+        }
+    }
+}
+
+///
+/// 按间隔把监控指标推送到statsd或otlp采集端,用于推模式的监控体系
+#[derive(Debug)]
+pub struct MetricsExporter {
+    exporter_type: MetricsExporterType,
+    addr: Arc<String>,
+    interval_millis: u64,
+    start_time_millis: u64,
+    last_export_time: u64,
+    /// statsd计数器按增量上报,记录上次上报的快照
+    last_snapshot: Option<MetricsSnapshot>,
+}
+
+impl MetricsExporter {
+    pub fn new(sys_config: &AppSysConfig) -> Option<Self> {
+        let exporter_type = MetricsExporterType::from_name(&sys_config.metrics_exporter)?;
+        let addr = if sys_config.metrics_exporter_addr.is_empty() {
+            exporter_type.default_addr().to_owned()
+        } else {
+            sys_config.metrics_exporter_addr.clone()
+        };
+        Some(Self {
+            exporter_type,
+            addr: Arc::new(addr),
+            interval_millis: sys_config.metrics_exporter_interval_second * 1000,
+            start_time_millis: now_millis(),
+            last_export_time: 0,
+            last_snapshot: None,
+        })
+    }
+
+    pub fn get_type(&self) -> MetricsExporterType {
+        self.exporter_type
+    }
+
+    pub fn get_addr(&self) -> &str {
+        &self.addr
+    }
+
+    ///
+    /// 到达推送间隔时,异步推送指标快照;推送失败只记录日志
+    pub fn export(&mut self, snapshot: MetricsSnapshot) {
+        //与采集间隔对齐,增加200ms容差
+        if snapshot.snapshot_time + 200 < self.last_export_time + self.interval_millis {
+            return;
+        }
+        self.last_export_time = snapshot.snapshot_time;
+        let addr = self.addr.clone();
+        match self.exporter_type {
+            MetricsExporterType::Statsd => {
+                let lines = Self::build_statsd_lines(&snapshot, self.last_snapshot.as_ref());
+                self.last_snapshot = Some(snapshot);
+                tokio::spawn(async move {
+                    if let Err(err) = Self::send_statsd(&addr, lines).await {
+                        log::warn!("export metrics to statsd {} error,{}", &addr, err);
+                    }
+                });
+            }
+            MetricsExporterType::Otlp => {
+                let body = Self::build_otlp_body(&snapshot, self.start_time_millis);
+                tokio::spawn(async move {
+                    if let Err(err) = Self::send_otlp(&addr, &body).await {
+                        log::warn!("export metrics to otlp {} error,{}", &addr, err);
+                    }
+                });
+            }
+        }
+    }
+
+    ///
+    /// 计数器与直方图按增量上报(c),仪表盘上报当前值(g)
+    pub fn build_statsd_lines(
+        snapshot: &MetricsSnapshot,
+        last_snapshot: Option<&MetricsSnapshot>,
+    ) -> Vec<String> {
+        let mut lines = vec![];
+        for (key, value) in &snapshot.gauge_data_map {
+            lines.push(format!("{}{}:{}|g", STATSD_PREFIX, key.get_key(), value.0));
+        }
+        let (counter_map, histogram_map) = match last_snapshot {
+            Some(last) => (
+                snapshot.diff_counter(&last.counter_data_map),
+                snapshot.diff_histogram(&last.histogram_data_map),
+            ),
+            None => (
+                snapshot.counter_data_map.clone(),
+                snapshot.histogram_data_map.clone(),
+            ),
+        };
+        for (key, value) in &counter_map {
+            lines.push(format!("{}{}:{}|c", STATSD_PREFIX, key.get_key(), value.0));
+        }
+        for (key, value) in &histogram_map {
+            let name = key.get_key();
+            lines.push(format!(
+                "{}{}.count:{}|c",
+                STATSD_PREFIX,
+                name,
+                value.count()
+            ));
+            lines.push(format!("{}{}.sum:{}|c", STATSD_PREFIX, name, value.sum()));
+        }
+        lines.sort();
+        lines
+    }
+
+    async fn send_statsd(addr: &str, lines: Vec<String>) -> anyhow::Result<()> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        let mut packet = String::new();
+        for line in lines {
+            if !packet.is_empty() && packet.len() + line.len() + 1 > STATSD_MAX_PACKET_SIZE {
+                socket.send_to(packet.as_bytes(), addr).await?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            socket.send_to(packet.as_bytes(), addr).await?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// 按otlp/http json格式构建请求内容,计数器与直方图使用累计值
+    pub fn build_otlp_body(snapshot: &MetricsSnapshot, start_time_millis: u64) -> Value {
+        let start_nanos = (start_time_millis * 1_000_000).to_string();
+        let time_nanos = (snapshot.snapshot_time * 1_000_000).to_string();
+        let mut metrics = vec![];
+        for (key, value) in &snapshot.gauge_data_map {
+            metrics.push(json!({
+                "name": key.get_key(),
+                "description": key.get_describe(),
+                "gauge": {
+                    "dataPoints": [{"timeUnixNano": time_nanos, "asDouble": value.0}]
+                }
+            }));
+        }
+        for (key, value) in &snapshot.counter_data_map {
+            metrics.push(json!({
+                "name": key.get_key(),
+                "description": key.get_describe(),
+                "sum": {
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                    "dataPoints": [{
+                        "startTimeUnixNano": start_nanos,
+                        "timeUnixNano": time_nanos,
+                        "asInt": value.0.to_string()
+                    }]
+                }
+            }));
+        }
+        for (key, value) in &snapshot.histogram_data_map {
+            let (bounds, bucket_counts) = Self::get_otlp_buckets(value);
+            metrics.push(json!({
+                "name": key.get_key(),
+                "description": key.get_describe(),
+                "histogram": {
+                    "aggregationTemporality": 2,
+                    "dataPoints": [{
+                        "startTimeUnixNano": start_nanos,
+                        "timeUnixNano": time_nanos,
+                        "count": value.count().to_string(),
+                        "sum": value.sum(),
+                        "explicitBounds": bounds,
+                        "bucketCounts": bucket_counts
+                    }]
+                }
+            }));
+        }
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{"key": "service.name", "value": {"stringValue": "rnacos"}}]
+                },
+                "scopeMetrics": [{
+                    "scope": {"name": "rnacos", "version": env!("CARGO_PKG_VERSION")},
+                    "metrics": metrics
+                }]
+            }]
+        })
+    }
+
+    ///
+    /// 直方图桶为累计值,otlp需要每个区间的数量,并包含最后一个(+Inf)区间
+    fn get_otlp_buckets(value: &HistogramValue) -> (Vec<f32>, Vec<String>) {
+        let mut bounds = vec![];
+        let mut bucket_counts = vec![];
+        let mut last_count = 0u64;
+        for (bound, count) in value.buckets() {
+            bounds.push(bound);
+            bucket_counts.push(count.saturating_sub(last_count).to_string());
+            last_count = count;
+        }
+        bucket_counts.push(value.count().saturating_sub(last_count).to_string());
+        (bounds, bucket_counts)
+    }
+
+    async fn send_otlp(url: &str, body: &Value) -> anyhow::Result<()> {
+        let resp = reqwest::Client::new().post(url).json(body).send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!("response status {}", resp.status()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::metrics_key::MetricsKey;
+    use crate::metrics::model::{CounterValue, GaugeValue};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_export_format() {
+        let mut histogram = HistogramValue::new(&[1f32, 5f32]).unwrap();
+        histogram.record(0.5);
+        histogram.record(3f32);
+        histogram.record(10f32);
+        let mut snapshot = MetricsSnapshot {
+            gauge_data_map: HashMap::new(),
+            counter_data_map: HashMap::new(),
+            histogram_data_map: HashMap::new(),
+            snapshot_time: 1000,
+        };
+        snapshot
+            .gauge_data_map
+            .insert(MetricsKey::AppCpuUsage, GaugeValue(1.5));
+        snapshot
+            .counter_data_map
+            .insert(MetricsKey::GrpcRequestTotalCount, CounterValue(10));
+        snapshot
+            .histogram_data_map
+            .insert(MetricsKey::GrpcRequestHandleRtHistogram, histogram);
+        let mut last = snapshot.clone();
+        last.counter_data_map
+            .insert(MetricsKey::GrpcRequestTotalCount, CounterValue(4));
+        let lines = MetricsExporter::build_statsd_lines(&snapshot, Some(&last));
+        assert!(lines.contains(&"rnacos.app_cpu_usage:1.5|g".to_owned()));
+        assert!(lines.contains(&"rnacos.grpc_request_total_count:6|c".to_owned()));
+
+        let body = MetricsExporter::build_otlp_body(&snapshot, 0);
+        let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        let histogram = metrics
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e.get("histogram").is_some())
+            .unwrap();
+        assert_eq!(
+            histogram["histogram"]["dataPoints"][0]["bucketCounts"],
+            json!(["1", "1", "1"])
+        );
+    }
+}
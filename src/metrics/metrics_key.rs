@@ -49,10 +49,16 @@ pub enum MetricsKey {
     GrpcConnSize,
     GrpcConnActiveTimeoutSetItemSize,
     GrpcConnResponseTimeoutSetItemSize,
+    //raft cache
+    RaftCacheExpiredPendingSize,
     //grpc request
     GrpcRequestHandleRtHistogram,
     GrpcRequestHandleRtSummary,
     GrpcRequestTotalCount,
+    GrpcConfigRequestTotalCount,
+    GrpcNamingRequestTotalCount,
+    GrpcClusterRequestTotalCount,
+    GrpcRequestLimitedCount,
     //http api request
     HttpRequestHandleRtHistogram,
     HttpRequestHandleRtSummary,
@@ -102,10 +108,16 @@ lazy_static! {
         MetricsKey::GrpcConnSize,
         MetricsKey::GrpcConnActiveTimeoutSetItemSize,
         MetricsKey::GrpcConnResponseTimeoutSetItemSize,
+        //raft cache
+        MetricsKey::RaftCacheExpiredPendingSize,
         //grpc request
         MetricsKey::GrpcRequestHandleRtHistogram,
         MetricsKey::GrpcRequestHandleRtSummary,
         MetricsKey::GrpcRequestTotalCount,
+        MetricsKey::GrpcConfigRequestTotalCount,
+        MetricsKey::GrpcNamingRequestTotalCount,
+        MetricsKey::GrpcClusterRequestTotalCount,
+        MetricsKey::GrpcRequestLimitedCount,
         //http request
         MetricsKey::HttpRequestHandleRtHistogram,
         MetricsKey::HttpRequestHandleRtSummary,
@@ -167,9 +179,14 @@ impl MetricsKey {
             MetricsKey::GrpcConnResponseTimeoutSetItemSize => {
                 "grpc_conn_response_timeout_set_item_size"
             }
+            MetricsKey::RaftCacheExpiredPendingSize => "raft_cache_expired_pending_size",
             MetricsKey::GrpcRequestHandleRtHistogram => "grpc_request_handle_rt_histogram",
             MetricsKey::GrpcRequestHandleRtSummary => "grpc_request_handle_rt_summary",
             MetricsKey::GrpcRequestTotalCount => "grpc_request_total_count",
+            MetricsKey::GrpcConfigRequestTotalCount => "grpc_config_request_total_count",
+            MetricsKey::GrpcNamingRequestTotalCount => "grpc_naming_request_total_count",
+            MetricsKey::GrpcClusterRequestTotalCount => "grpc_cluster_request_total_count",
+            MetricsKey::GrpcRequestLimitedCount => "grpc_request_limited_count",
             MetricsKey::HttpRequestHandleRtHistogram => "http_request_handle_rt_histogram",
             MetricsKey::HttpRequestHandleRtSummary => "http_request_handle_rt_summary",
             MetricsKey::HttpRequestTotalCount => "http_request_total_count",
@@ -241,11 +258,18 @@ impl MetricsKey {
             MetricsKey::GrpcConnResponseTimeoutSetItemSize => {
                 "Grpc conn response timeout set item size"
             }
+            MetricsKey::RaftCacheExpiredPendingSize => {
+                "Raft cache expired but not removed key size"
+            }
             MetricsKey::GrpcRequestHandleRtHistogram => {
                 "Grpc request handle rt histogram,unit is ms"
             }
             MetricsKey::GrpcRequestHandleRtSummary => "Grpc request handle rt summary, unit is ms",
             MetricsKey::GrpcRequestTotalCount => "Grpc request total count",
+            MetricsKey::GrpcConfigRequestTotalCount => "Grpc config request total count",
+            MetricsKey::GrpcNamingRequestTotalCount => "Grpc naming request total count",
+            MetricsKey::GrpcClusterRequestTotalCount => "Grpc cluster request total count",
+            MetricsKey::GrpcRequestLimitedCount => "Grpc request limited count",
             MetricsKey::HttpRequestHandleRtHistogram => {
                 "Http request handle rt histogram,unit is ms"
             }
@@ -1,5 +1,6 @@
 pub mod core;
 pub mod counter;
+pub mod exporter;
 pub mod gauge;
 pub mod histogram;
 pub mod metrics_key;
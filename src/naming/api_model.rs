@@ -1,6 +1,7 @@
 use crate::now_millis_i64;
 
 use super::model::{Instance, ServiceDetailDto, ServiceKey};
+use super::selector::ServiceSelector;
 use super::NamingUtils;
 use crate::common::option_utils::OptionUtils;
 use chrono::Local;
@@ -24,6 +25,8 @@ pub struct QueryListResult {
     pub reach_local_site_call_threshold: Option<bool>,
     pub dom: Option<Arc<String>>,
     pub metadata: Option<HashMap<String, String>>,
+    /// 健康实例比例低于保护阈值,返回了全部实例
+    pub reach_protection_threshold: Option<bool>,
 }
 
 impl QueryListResult {
@@ -31,6 +34,7 @@ impl QueryListResult {
         clusters: String,
         key: &ServiceKey,
         v: Vec<Arc<Instance>>,
+        reach_protection_threshold: bool,
     ) -> String {
         let now = now_millis_i64();
         let result = Self {
@@ -46,6 +50,7 @@ impl QueryListResult {
                 .map(|e| InstanceVO::from_instance(&e))
                 .collect::<Vec<_>>(),
             dom: Some(key.service_name.to_owned()),
+            reach_protection_threshold: Some(reach_protection_threshold),
             ..Default::default()
         };
         serde_json::to_string(&result).unwrap()
@@ -91,6 +96,14 @@ pub struct InstanceVO {
     pub enabled: Option<bool>,
     pub service_name: Option<Arc<String>>,
     pub ephemeral: Option<bool>,
+    #[serde(default)]
+    pub revision: u64,
+    /// 注册实例的grpc连接id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<Arc<String>>,
+    /// 注册实例的grpc连接标签
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_labels: Option<Arc<HashMap<String, String>>>,
 }
 
 impl InstanceVO {
@@ -109,8 +122,21 @@ impl InstanceVO {
             //service_name: Some(instance.service_name.clone()),
             service_name: Some(instance.group_service.clone()),
             ephemeral: Some(instance.ephemeral),
+            revision: instance.revision,
+            client_id: None,
+            client_labels: None,
         }
     }
+
+    ///
+    /// 附带注册来源的连接信息,只用于控制台
+    pub fn with_client_info(mut self, instance: &Instance) -> Self {
+        if !instance.client_id.is_empty() {
+            self.client_id = Some(instance.client_id.clone());
+        }
+        self.client_labels = instance.client_labels.clone();
+        self
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -159,6 +185,10 @@ impl ServiceInfoParam {
             } else {
                 None
             };
+            let selector = match self.selector.as_ref().filter(|v| !v.is_empty()) {
+                Some(v) => Some(ServiceSelector::parse(v)?),
+                None => None,
+            };
 
             Ok(ServiceDetailDto {
                 namespace_id: Arc::new(NamingUtils::default_namespace(
@@ -170,6 +200,7 @@ impl ServiceInfoParam {
                 )),
                 metadata,
                 protect_threshold: self.protect_threshold,
+                selector,
             })
         } else {
             Err(anyhow::anyhow!("service_name is empty"))
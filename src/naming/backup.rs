@@ -0,0 +1,123 @@
+use std::io::{Cursor, Read, Write};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::model::{Instance, ServiceDetailDto};
+
+pub const NAMING_BACKUP_VERSION: u32 = 1;
+/// zip格式备份中的数据文件名
+const NAMING_BACKUP_ENTRY_NAME: &str = "naming.json";
+
+///
+/// 命名空间下的服务定义与持久化实例,用于跨r-nacos实例迁移与备份;不包含临时实例
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingBackup {
+    pub version: u32,
+    pub namespace_id: Arc<String>,
+    pub export_time: i64,
+    #[serde(default)]
+    pub services: Vec<ServiceDetailDto>,
+    #[serde(default)]
+    pub instances: Vec<Instance>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingBackupImportResult {
+    pub service_count: usize,
+    pub instance_count: usize,
+}
+
+impl NamingBackup {
+    pub fn new(
+        namespace_id: Arc<String>,
+        export_time: i64,
+        services: Vec<ServiceDetailDto>,
+        instances: Vec<Instance>,
+    ) -> Self {
+        Self {
+            version: NAMING_BACKUP_VERSION,
+            namespace_id,
+            export_time,
+            services,
+            instances,
+        }
+    }
+
+    pub fn to_zip(&self) -> anyhow::Result<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file(NAMING_BACKUP_ENTRY_NAME, options)?;
+        zip.write_all(&serde_json::to_vec(self)?)?;
+        Ok(zip.finish()?.into_inner())
+    }
+
+    ///
+    /// 解析导出的json或zip文件
+    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+        let backup: Self = if data.starts_with(b"PK") {
+            let mut archive = ZipArchive::new(Cursor::new(data))?;
+            let mut file = archive.by_name(NAMING_BACKUP_ENTRY_NAME)?;
+            let mut buf = vec![];
+            file.read_to_end(&mut buf)?;
+            serde_json::from_slice(&buf)?
+        } else {
+            serde_json::from_slice(data)?
+        };
+        if backup.version > NAMING_BACKUP_VERSION {
+            return Err(anyhow::anyhow!(
+                "unsupported naming backup version:{}",
+                backup.version
+            ));
+        }
+        Ok(backup)
+    }
+
+    ///
+    /// 导入到指定命名空间
+    pub fn set_namespace(&mut self, namespace_id: Arc<String>) {
+        for service in self.services.iter_mut() {
+            service.namespace_id = namespace_id.clone();
+        }
+        for instance in self.instances.iter_mut() {
+            instance.namespace_id = namespace_id.clone();
+        }
+        self.namespace_id = namespace_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming_backup() {
+        let namespace_id = Arc::new("dev".to_owned());
+        let service = ServiceDetailDto {
+            namespace_id: namespace_id.clone(),
+            service_name: Arc::new("foo".to_owned()),
+            group_name: Arc::new("DEFAULT_GROUP".to_owned()),
+            metadata: None,
+            protect_threshold: Some(0.5),
+            selector: None,
+        };
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.namespace_id = namespace_id.clone();
+        instance.service_name = service.service_name.clone();
+        instance.group_name = service.group_name.clone();
+        instance.ephemeral = false;
+        let backup = NamingBackup::new(namespace_id, 1, vec![service], vec![instance]);
+        let mut data = NamingBackup::from_bytes(&backup.to_zip().unwrap()).unwrap();
+        assert_eq!(data.services.len(), 1);
+        assert_eq!(data.instances[0].port, 8080);
+        data.set_namespace(Arc::new("test".to_owned()));
+        let json = serde_json::to_vec(&data).unwrap();
+        let data = NamingBackup::from_bytes(&json).unwrap();
+        assert_eq!(data.instances[0].namespace_id.as_str(), "test");
+        assert_eq!(data.services[0].namespace_id.as_str(), "test");
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::ServiceKey;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InstanceChangeType {
+    /// 新注册
+    #[default]
+    Register,
+    /// 主动注销或连接断开
+    Deregister,
+    /// 心跳超时被移除
+    Expire,
+    /// 恢复为健康
+    Healthy,
+    /// 变为不健康
+    Unhealthy,
+}
+
+///
+/// 实例变更事件只记录在实例归属的节点上,查询时合并各节点的记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceChangeEvent {
+    pub time: u64,
+    pub ip: Arc<String>,
+    pub port: u32,
+    pub change_type: InstanceChangeType,
+    /// 注册实例的grpc连接id,http注册的实例为空
+    pub client_id: Arc<String>,
+}
+
+impl InstanceChangeEvent {
+    pub fn new(
+        time: u64,
+        ip: Arc<String>,
+        port: u32,
+        change_type: InstanceChangeType,
+        client_id: Arc<String>,
+    ) -> Self {
+        Self {
+            time,
+            ip,
+            port,
+            change_type,
+            client_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangeHistoryConfig {
+    /// 每个服务最多保留的变更事件数量,为0时不记录
+    pub capacity: usize,
+}
+
+impl Default for ChangeHistoryConfig {
+    fn default() -> Self {
+        Self { capacity: 100 }
+    }
+}
+
+///
+/// 按服务记录最近的实例变更事件,用于排查实例消失、健康状态变化的原因
+#[derive(Debug, Default)]
+pub struct ServiceChangeHistory {
+    pub config: ChangeHistoryConfig,
+    records: HashMap<ServiceKey, VecDeque<InstanceChangeEvent>>,
+}
+
+impl ServiceChangeHistory {
+    pub fn new(config: ChangeHistoryConfig) -> Self {
+        Self {
+            config,
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, key: &ServiceKey, event: InstanceChangeEvent) {
+        if self.config.capacity == 0 {
+            return;
+        }
+        let list = self.records.entry(key.clone()).or_default();
+        while list.len() >= self.config.capacity {
+            list.pop_front();
+        }
+        list.push_back(event);
+    }
+
+    ///
+    /// 服务被清理时一并移除其变更记录
+    pub fn remove_service(&mut self, key: &ServiceKey) {
+        self.records.remove(key);
+    }
+
+    ///
+    /// 按时间倒序查询时间范围内的变更事件
+    pub fn query(
+        &self,
+        key: &ServiceKey,
+        start_time: u64,
+        end_time: u64,
+    ) -> Vec<InstanceChangeEvent> {
+        match self.records.get(key) {
+            Some(list) => list
+                .iter()
+                .rev()
+                .filter(|e| e.time >= start_time && e.time <= end_time)
+                .cloned()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    ///
+    /// 合并多个节点的变更事件,按时间倒序
+    pub fn merge(lists: Vec<Vec<InstanceChangeEvent>>) -> Vec<InstanceChangeEvent> {
+        let mut list: Vec<InstanceChangeEvent> = lists.into_iter().flatten().collect();
+        list.sort_by_key(|e| std::cmp::Reverse(e.time));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_event(time: u64, change_type: InstanceChangeType) -> InstanceChangeEvent {
+        InstanceChangeEvent::new(
+            time,
+            Arc::new("127.0.0.1".to_owned()),
+            8080,
+            change_type,
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn test_change_history() {
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let mut history = ServiceChangeHistory::new(ChangeHistoryConfig { capacity: 3 });
+        history.record(&key, build_event(100, InstanceChangeType::Register));
+        history.record(&key, build_event(200, InstanceChangeType::Unhealthy));
+        history.record(&key, build_event(300, InstanceChangeType::Healthy));
+        history.record(&key, build_event(400, InstanceChangeType::Deregister));
+        let list = history.query(&key, 0, u64::MAX);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].change_type, InstanceChangeType::Deregister);
+        assert_eq!(history.query(&key, 150, 300).len(), 2);
+        let merged = ServiceChangeHistory::merge(vec![
+            history.query(&key, 0, 250),
+            vec![build_event(350, InstanceChangeType::Expire)],
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].change_type, InstanceChangeType::Expire);
+        history.remove_service(&key);
+        assert!(history.query(&key, 0, u64::MAX).is_empty());
+    }
+}
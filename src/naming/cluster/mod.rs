@@ -13,8 +13,12 @@ use self::{
     },
     node_manage::{NodeManageRequest, NodeManageResponse},
 };
+use crate::grpc::handler::NAMING_ROUTE_REQUEST;
+use crate::grpc::PayloadUtils;
 use crate::metrics::model::{MetricsRequest, MetricsResponse};
 use crate::naming::model::Instance;
+use crate::naming::model::ServiceKey;
+use crate::naming::zone::NODE_LABELS_EXTEND_KEY;
 use crate::{
     common::appdata::AppShareData,
     naming::core::{NamingCmd, NamingResult},
@@ -46,6 +50,15 @@ pub async fn handle_naming_route(
         NamingRouteRequest::Ping(cluster_id) => {
             //更新node_id节点活跃状态
             app.naming_node_manage.active_node(cluster_id);
+            let labels: HashMap<String, String> = extend_info
+                .get(NODE_LABELS_EXTEND_KEY)
+                .and_then(|v| serde_json::from_str(v).ok())
+                .unwrap_or_default();
+            app.naming_inner_node_manage
+                .do_send(NodeManageRequest::UpdateNodeLabels(
+                    cluster_id,
+                    Arc::new(labels),
+                ));
         }
         NamingRouteRequest::UpdateInstance { instance, tag } => {
             let cmd = NamingCmd::Update(instance, tag);
@@ -55,6 +68,29 @@ pub async fn handle_naming_route(
             let cmd = NamingCmd::Delete(instance);
             let _: NamingResult = app.naming_addr.send(cmd).await??;
         }
+        NamingRouteRequest::DrainInstance {
+            instance,
+            grace_millis,
+        } => {
+            app.naming_route
+                .drain_local_instance(instance, grace_millis)
+                .await?;
+        }
+        NamingRouteRequest::BatchInstance {
+            register,
+            instances,
+            tag,
+        } => {
+            let cmd = NamingCmd::BatchInstance(register, instances, tag.clone());
+            let res: NamingResult = app.naming_addr.send(cmd).await??;
+            if let NamingResult::RewriteToClusterList(list) = res {
+                for (_, instance) in list {
+                    app.naming_route
+                        .update_instance(instance, tag.clone())
+                        .await?;
+                }
+            }
+        }
         NamingRouteRequest::SyncUpdateService { service } => {
             let cluster_id = get_cluster_id(extend_info)?;
             app.naming_addr
@@ -153,10 +189,56 @@ pub async fn handle_naming_route(
                 return Ok(NamingRouterResponse::MetricsTimeLineResponse(resp));
             }
         }
+        NamingRouteRequest::QueryServiceChangeHistory {
+            namespace_id,
+            group_name,
+            service_name,
+            start_time,
+            end_time,
+        } => {
+            let key = ServiceKey::new_by_arc(namespace_id, group_name, service_name);
+            let cmd = NamingCmd::QueryServiceChangeHistory(key, start_time, end_time);
+            if let NamingResult::ServiceChangeHistory(list) = app.naming_addr.send(cmd).await?? {
+                return Ok(NamingRouterResponse::ServiceChangeHistory(list));
+            }
+        }
+        NamingRouteRequest::QueryResourceEvents(key) => {
+            return Ok(NamingRouterResponse::ResourceEvents(
+                app.resource_event_store.query(&key),
+            ));
+        }
     };
     Ok(NamingRouterResponse::None)
 }
 
+///
+/// 向其它有效节点发送查询请求,用于合并各节点本地维护的数据;单个节点失败时忽略该节点
+pub async fn query_other_nodes(
+    app: &AppShareData,
+    req: &NamingRouteRequest,
+) -> anyhow::Result<Vec<NamingRouterResponse>> {
+    let request = serde_json::to_string(req)?;
+    let mut list = vec![];
+    for node in app.naming_node_manage.get_other_valid_nodes().await? {
+        let payload = PayloadUtils::build_payload(NAMING_ROUTE_REQUEST, request.clone());
+        match app
+            .cluster_sender
+            .send_request(node.addr.clone(), payload)
+            .await
+        {
+            Ok(resp_payload) => {
+                let body_vec = resp_payload.body.unwrap_or_default().value;
+                match serde_json::from_slice(&body_vec) {
+                    Ok(resp) => list.push(resp),
+                    Err(err) => log::warn!("parse node {} response error,{}", node.id, err),
+                }
+            }
+            Err(err) => log::warn!("query node {} error,{}", node.id, err),
+        }
+    }
+    Ok(list)
+}
+
 fn reset_cluster_info(cluster_id: u64, instance: &mut Instance) {
     if instance.client_id.is_empty() && cluster_id > 0 {
         instance.client_id = Arc::new(format!("{}_G", &cluster_id));
@@ -1,5 +1,7 @@
+use crate::common::resource_event::{ResourceEvent, ResourceKey};
 use crate::metrics::timeline::model::{TimelineQueryParam, TimelineQueryResponse};
-use crate::naming::model::{Instance, InstanceUpdateTag, ServiceDetailDto};
+use crate::naming::change_history::InstanceChangeEvent;
+use crate::naming::model::{Instance, InstanceUpdateTag, ServiceDetailDto, ServiceKey};
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, sync::Arc};
@@ -25,6 +27,16 @@ pub enum NamingRouteRequest {
     RemoveInstance {
         instance: Instance,
     },
+    /// 实例摘流,instance只使用实例标识字段
+    DrainInstance {
+        instance: Instance,
+        grace_millis: u64,
+    },
+    BatchInstance {
+        register: bool,
+        instances: Vec<Instance>,
+        tag: Option<InstanceUpdateTag>,
+    },
     SyncUpdateInstance {
         instance: Instance,
     },
@@ -44,12 +56,36 @@ pub enum NamingRouteRequest {
     },
     Snapshot(Vec<u8>),
     MetricsTimelineQuery(TimelineQueryParam),
+    /// 查询本节点记录的服务实例变更事件
+    QueryServiceChangeHistory {
+        namespace_id: Arc<String>,
+        group_name: Arc<String>,
+        service_name: Arc<String>,
+        start_time: u64,
+        end_time: u64,
+    },
+    /// 查询本节点记录的资源操作与推送事件
+    QueryResourceEvents(ResourceKey),
+}
+
+impl NamingRouteRequest {
+    pub fn query_service_change_history(key: &ServiceKey, start_time: u64, end_time: u64) -> Self {
+        Self::QueryServiceChangeHistory {
+            namespace_id: key.namespace_id.clone(),
+            group_name: key.group_name.clone(),
+            service_name: key.service_name.clone(),
+            start_time,
+            end_time,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum NamingRouterResponse {
     None,
     MetricsTimeLineResponse(TimelineQueryResponse),
+    ServiceChangeHistory(Vec<InstanceChangeEvent>),
+    ResourceEvents(Vec<ResourceEvent>),
 }
 
 #[derive(Message, Debug, Clone)]
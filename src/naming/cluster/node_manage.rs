@@ -1,5 +1,5 @@
 use std::{
-    collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
     hash::{Hash, Hasher},
     sync::Arc,
     time::Duration,
@@ -41,6 +41,7 @@ pub struct ClusterNode {
     pub is_local: bool,
     pub addr: Arc<String>,
     pub status: NodeStatus,
+    pub labels: Arc<HashMap<String, String>>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -50,6 +51,7 @@ pub struct ClusterInnerNode {
     pub is_local: bool,
     pub addr: Arc<String>,
     pub status: NodeStatus,
+    pub labels: Arc<HashMap<String, String>>,
     pub last_active_time: u64,
     pub sync_sender: Option<Addr<ClusteSyncSender>>,
     pub client_set: HashSet<Arc<String>>,
@@ -69,6 +71,7 @@ impl From<ClusterInnerNode> for ClusterNode {
             is_local: value.is_local,
             addr: value.addr,
             status: value.status,
+            labels: value.labels,
         }
     }
 }
@@ -76,6 +79,7 @@ impl From<ClusterInnerNode> for ClusterNode {
 #[bean(inject)]
 pub struct InnerNodeManage {
     local_id: u64,
+    local_labels: Arc<HashMap<String, String>>,
     all_nodes: BTreeMap<u64, ClusterInnerNode>,
     cluster_sender: Option<Arc<RaftClusterRequestSender>>,
     naming_actor: Option<Addr<NamingActor>>,
@@ -85,9 +89,10 @@ pub struct InnerNodeManage {
 }
 
 impl InnerNodeManage {
-    pub fn new(local_id: u64) -> Self {
+    pub fn new(local_id: u64, local_labels: HashMap<String, String>) -> Self {
         Self {
             local_id,
+            local_labels: Arc::new(local_labels),
             cluster_sender: None,
             all_nodes: Default::default(),
             naming_actor: None,
@@ -126,6 +131,7 @@ impl InnerNodeManage {
                     Some(
                         ClusteSyncSender::new(
                             self.local_id,
+                            &self.local_labels,
                             key,
                             addr.clone(),
                             self.cluster_sender.clone(),
@@ -133,6 +139,11 @@ impl InnerNodeManage {
                         .start(),
                     )
                 };
+                let labels = if is_local {
+                    self.local_labels.clone()
+                } else {
+                    Default::default()
+                };
                 let node = ClusterInnerNode {
                     id: key,
                     index: 0,
@@ -140,6 +151,7 @@ impl InnerNodeManage {
                     addr,
                     sync_sender,
                     status: NodeStatus::Valid,
+                    labels,
                     last_active_time: now,
                     client_set: Default::default(),
                 };
@@ -184,6 +196,7 @@ impl InnerNodeManage {
             ClusterInnerNode {
                 id: self.local_id,
                 is_local: true,
+                labels: self.local_labels.clone(),
                 ..Default::default()
             }
         }
@@ -338,6 +351,19 @@ impl InnerNodeManage {
         }
     }
 
+    fn update_node_labels(&mut self, node_id: u64, labels: Arc<HashMap<String, String>>) {
+        if let Some(node) = self.all_nodes.get_mut(&node_id) {
+            if !node.is_local && node.labels != labels {
+                log::info!(
+                    "update node labels,node_id:{},labels:{:?}",
+                    node_id,
+                    &labels
+                );
+                node.labels = labels;
+            }
+        }
+    }
+
     fn remove_client_id(&mut self, client_id: Arc<String>) {
         for node in self.all_nodes.values_mut() {
             node.client_set.remove(&client_id);
@@ -393,6 +419,7 @@ pub enum NodeManageRequest {
     GetAllNodes,
     GetNode(u64),
     ActiveNode(u64),
+    UpdateNodeLabels(u64, Arc<HashMap<String, String>>),
     SendToOtherNodes(NamingRouteRequest),
     AddClientId(u64, Arc<String>),
     AddClientIds(u64, HashSet<Arc<String>>),
@@ -437,6 +464,10 @@ impl Handler<NodeManageRequest> for InnerNodeManage {
                 self.active_node(node_id);
                 Ok(NodeManageResponse::None)
             }
+            NodeManageRequest::UpdateNodeLabels(node_id, labels) => {
+                self.update_node_labels(node_id, labels);
+                Ok(NodeManageResponse::None)
+            }
             NodeManageRequest::AddClientId(node_id, client_id) => {
                 self.active_node(node_id);
                 self.node_add_client(node_id, client_id);
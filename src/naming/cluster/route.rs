@@ -61,11 +61,44 @@ impl NamingRoute {
         Ok(())
     }
 
+    ///
+    /// 批量心跳;本节点负责的实例合并为一次NamingActor请求,其它节点负责的实例逐个路由
+    pub async fn beat_instances(
+        &self,
+        instances: Vec<Instance>,
+        tag: InstanceUpdateTag,
+    ) -> anyhow::Result<()> {
+        let mut local_list = Vec::with_capacity(instances.len());
+        for instance in instances {
+            let key = instance.get_service_key();
+            match self.node_manage.route_addr(&key).await {
+                NamingRouteAddr::Local(_) => local_list.push(instance),
+                NamingRouteAddr::Remote(cluster_id, addr) => {
+                    self.do_route_instance(cluster_id, addr, instance, Some(tag.clone()), true)
+                        .await?;
+                }
+            }
+        }
+        if local_list.is_empty() {
+            return Ok(());
+        }
+        let cmd = NamingCmd::BeatBatch(local_list, tag.clone());
+        let res: NamingResult = self.naming_addr.send(cmd).await??;
+        if let NamingResult::RewriteToClusterList(list) = res {
+            for (node_id, instance) in list {
+                let addr = self.node_manage.get_node_addr(node_id).await?;
+                self.do_route_instance(node_id, addr, instance, Some(tag.clone()), true)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     async fn do_route_instance(
         &self,
         cluster_id: u64,
         addr: Arc<String>,
-        mut instance: Instance,
+        instance: Instance,
         tag: Option<InstanceUpdateTag>,
         is_update: bool,
     ) -> anyhow::Result<()> {
@@ -79,12 +112,31 @@ impl NamingRoute {
                 instance: instance.clone(),
             }
         };
-        let request = serde_json::to_string(&req).unwrap_or_default();
+        self.send_route_request(addr, &req).await?;
+        self.sync_routed_instance(cluster_id, instance, tag, is_update);
+        Ok(())
+    }
+
+    async fn send_route_request(
+        &self,
+        addr: Arc<String>,
+        req: &NamingRouteRequest,
+    ) -> anyhow::Result<()> {
+        let request = serde_json::to_string(req).unwrap_or_default();
         let payload = PayloadUtils::build_payload(NAMING_ROUTE_REQUEST, request);
         let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
         let body_vec = resp_payload.body.unwrap_or_default().value;
         let _: NamingRouterResponse = serde_json::from_slice(&body_vec)?;
+        Ok(())
+    }
 
+    fn sync_routed_instance(
+        &self,
+        cluster_id: u64,
+        mut instance: Instance,
+        tag: Option<InstanceUpdateTag>,
+        is_update: bool,
+    ) {
         //路由在其它节点后，立即同步本节点
         if is_update {
             if instance.client_id.is_empty() && cluster_id > 0 {
@@ -104,7 +156,90 @@ impl NamingRoute {
             let cmd = NamingCmd::Delete(instance);
             self.naming_addr.do_send(cmd);
         }
+    }
+
+    ///
+    /// 批量注册或注销同一服务的实例,由负责该服务的节点一次处理
+    pub async fn batch_instances(
+        &self,
+        register: bool,
+        instances: Vec<Instance>,
+        tag: Option<InstanceUpdateTag>,
+    ) -> anyhow::Result<()> {
+        let key = match instances.first() {
+            Some(instance) => instance.get_service_key(),
+            None => return Ok(()),
+        };
+        if instances.iter().any(|e| e.get_service_key() != key) {
+            return Err(anyhow::anyhow!(
+                "batch instances must belong to one service"
+            ));
+        }
+        match self.node_manage.route_addr(&key).await {
+            NamingRouteAddr::Local(_) => {
+                let cmd = NamingCmd::BatchInstance(register, instances, tag.clone());
+                let res: NamingResult = self.naming_addr.send(cmd).await??;
+                if let NamingResult::RewriteToClusterList(list) = res {
+                    for (node_id, instance) in list {
+                        let addr = self.node_manage.get_node_addr(node_id).await?;
+                        self.do_route_instance(node_id, addr, instance, tag.clone(), true)
+                            .await?;
+                    }
+                }
+            }
+            NamingRouteAddr::Remote(cluster_id, addr) => {
+                let req = NamingRouteRequest::BatchInstance {
+                    register,
+                    instances: instances.clone(),
+                    tag: tag.clone(),
+                };
+                self.send_route_request(addr, &req).await?;
+                for instance in instances {
+                    self.sync_routed_instance(cluster_id, instance, tag.clone(), register);
+                }
+            }
+        };
+        Ok(())
+    }
 
+    ///
+    /// 实例摘流;http实例由负责服务的节点处理,grpc实例再转发到客户端连接的节点
+    pub async fn drain_instance(
+        &self,
+        instance: Instance,
+        grace_millis: u64,
+    ) -> anyhow::Result<()> {
+        let key = instance.get_service_key();
+        match self.node_manage.route_addr(&key).await {
+            NamingRouteAddr::Local(_) => {
+                self.drain_local_instance(instance, grace_millis).await?;
+            }
+            NamingRouteAddr::Remote(_, addr) => {
+                let req = NamingRouteRequest::DrainInstance {
+                    instance,
+                    grace_millis,
+                };
+                self.send_route_request(addr, &req).await?;
+            }
+        };
+        Ok(())
+    }
+
+    pub(crate) async fn drain_local_instance(
+        &self,
+        instance: Instance,
+        grace_millis: u64,
+    ) -> anyhow::Result<()> {
+        let cmd = NamingCmd::DrainInstance(instance.get_instance_key(), grace_millis);
+        let res: NamingResult = self.naming_addr.send(cmd).await??;
+        if let NamingResult::DrainToCluster(node_id) = res {
+            let addr = self.node_manage.get_node_addr(node_id).await?;
+            let req = NamingRouteRequest::DrainInstance {
+                instance,
+                grace_millis,
+            };
+            self.send_route_request(addr, &req).await?;
+        }
         Ok(())
     }
 
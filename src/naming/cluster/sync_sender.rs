@@ -3,6 +3,7 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 use actix::prelude::*;
 
 use crate::grpc::handler::NAMING_ROUTE_REQUEST;
+use crate::naming::zone::NODE_LABELS_EXTEND_KEY;
 use crate::{grpc::PayloadUtils, raft::network::factory::RaftClusterRequestSender};
 
 use super::model::{
@@ -21,12 +22,19 @@ pub struct ClusteSyncSender {
 impl ClusteSyncSender {
     pub fn new(
         local_id: u64,
+        local_labels: &HashMap<String, String>,
         target_id: u64,
         target_addr: Arc<String>,
         cluster_sender: Option<Arc<RaftClusterRequestSender>>,
     ) -> Self {
         let mut send_extend_infos = HashMap::default();
         send_extend_infos.insert("cluster_id".to_owned(), local_id.to_string());
+        if !local_labels.is_empty() {
+            send_extend_infos.insert(
+                NODE_LABELS_EXTEND_KEY.to_owned(),
+                serde_json::to_string(local_labels).unwrap_or_default(),
+            );
+        }
         Self {
             //local_id,
             target_id,
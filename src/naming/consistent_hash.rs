@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use super::model::Instance;
+
+/// 每个实例在哈希环上的虚拟节点数
+const VIRTUAL_NODE_COUNT: u32 = 160;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+///
+/// fnv-1a,各节点及版本间结果一致,不使用DefaultHasher
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+        self
+    }
+
+    /// 打散低位,使虚拟节点在环上分布均匀
+    fn finish(&self) -> u64 {
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+pub struct ConsistentHashUtils;
+
+impl ConsistentHashUtils {
+    fn hash_key(key: &str) -> u64 {
+        StableHasher::new().write(key.as_bytes()).finish()
+    }
+
+    fn hash_node(instance: &Instance, index: u32) -> u64 {
+        StableHasher::new()
+            .write(instance.ip.as_bytes())
+            .write(&instance.port.to_be_bytes())
+            .write(&index.to_be_bytes())
+            .finish()
+    }
+
+    ///
+    /// 按一致性哈希从健康且启用的实例中选择一个;
+    /// 同一实例集合下相同hash_key总是返回相同实例,实例增减只影响环上相邻的部分key
+    pub fn select(list: &[Arc<Instance>], hash_key: &str) -> Option<Arc<Instance>> {
+        let key_hash = Self::hash_key(hash_key);
+        let mut selected: Option<(u64, &Arc<Instance>)> = None;
+        for instance in list
+            .iter()
+            .filter(|e| e.healthy && e.enabled && e.weight > 0f32)
+        {
+            for i in 0..VIRTUAL_NODE_COUNT {
+                //顺时针方向离key最近的虚拟节点
+                let distance = Self::hash_node(instance, i).wrapping_sub(key_hash);
+                if selected.map(|(d, _)| distance < d).unwrap_or(true) {
+                    selected = Some((distance, instance));
+                }
+            }
+        }
+        selected.map(|(_, e)| e.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_list(size: u32) -> Vec<Arc<Instance>> {
+        (0..size)
+            .map(|i| Arc::new(Instance::new("127.0.0.1".to_owned(), 8000 + i)))
+            .collect()
+    }
+
+    #[test]
+    fn test_consistent_hash_select() {
+        let list = build_list(5);
+        let mut reversed = list.clone();
+        reversed.reverse();
+        let mut moved = 0;
+        for i in 0..100 {
+            let key = format!("user_{}", i);
+            let a = ConsistentHashUtils::select(&list, &key).unwrap();
+            let b = ConsistentHashUtils::select(&reversed, &key).unwrap();
+            assert_eq!(a.port, b.port);
+            //移除一个实例,只有原来落在该实例上的key会变化
+            let c = ConsistentHashUtils::select(&list[..4], &key).unwrap();
+            if a.port != c.port {
+                assert_eq!(a.port, 8004);
+                moved += 1;
+            }
+        }
+        assert!(moved < 100);
+        assert!(ConsistentHashUtils::select(&[], "user_1").is_none());
+    }
+}
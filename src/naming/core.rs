@@ -7,6 +7,10 @@
 )]
 
 use super::api_model::QueryListResult;
+use super::backup::NamingBackup;
+use super::change_history::{
+    ChangeHistoryConfig, InstanceChangeEvent, InstanceChangeType, ServiceChangeHistory,
+};
 use super::cluster::instance_delay_notify::{
     ClusterInstanceDelayNotifyActor, InstanceDelayNotifyRequest,
 };
@@ -14,12 +18,16 @@ use super::cluster::model::{
     NamingRouteRequest, ProcessRange, SnapshotForReceive, SnapshotForSend,
 };
 use super::cluster::node_manage::{InnerNodeManage, NodeManageRequest};
+use super::drain::InstanceDraining;
 use super::filter::InstanceFilterUtils;
+use super::flapping::{FlappingConfig, FlappingDetector, FlappingInstanceInfo};
+use super::health_check::{HealthCheckTarget, HealthChecker};
 use super::listener::{InnerNamingListener, ListenerItem, NamingListenerCmd};
 use super::model::Instance;
 use super::model::InstanceKey;
 use super::model::InstanceShortKey;
 use super::model::InstanceUpdateTag;
+use super::model::NamespaceNamingStats;
 use super::model::ServiceDetailDto;
 use super::model::ServiceInfo;
 use super::model::ServiceKey;
@@ -27,16 +35,27 @@ use super::model::UpdateInstanceType;
 use super::naming_delay_nofity::DelayNotifyActor;
 use super::naming_delay_nofity::DelayNotifyCmd;
 use super::naming_subscriber::NamingListenerItem;
-use super::naming_subscriber::Subscriber;
+use super::naming_subscriber::{Subscriber, SubscriberClient, SubscriberInfo};
+use super::pattern_subscriber::{PatternSubscribeInfo, PatternSubscribeItem, PatternSubscriber};
+use super::quarantine::{InstanceQuarantine, QuarantineConfig, QuarantineInstance};
 use super::service::Service;
 use super::service::ServiceInfoDto;
 use super::service::ServiceMetadata;
+use super::service_export::NacosServiceExport;
 use super::service_index::NamespaceIndex;
 use super::service_index::ServiceQueryParam;
+use super::warmup::WarmupUtils;
 use super::NamingUtils;
+use super::DEFAULT_NAMESPACE;
+use crate::common::cluster_settings::ClusterSettingsManager;
 use crate::common::delay_notify;
+use crate::common::group_rule::GroupRuleManager;
 use crate::common::hash_utils::get_hash_value;
-use crate::common::NamingSysConfig;
+use crate::common::metadata_schema::MetadataSchemaManager;
+use crate::common::namespace_quota::QuotaExceededError;
+use crate::common::service_link::ServiceLinkManager;
+use crate::common::string_utils::StringUtils;
+use crate::common::{AppSysConfig, NamingSysConfig};
 use crate::grpc::bistream_manage::BiStreamManage;
 use crate::now_millis;
 use crate::now_millis_i64;
@@ -68,14 +87,27 @@ pub struct NamingActor {
     listener_addr: Option<Addr<InnerNamingListener>>,
     delay_notify_addr: Option<Addr<DelayNotifyActor>>,
     pub(crate) subscriber: Subscriber,
+    pub(crate) pattern_subscriber: PatternSubscriber,
     sys_config: NamingSysConfig,
     pub(crate) empty_service_set: TimeoutSet<ServiceKey>,
     pub(crate) instance_metadate_set: TimeoutSet<InstanceKey>,
     pub(crate) namespace_index: NamespaceIndex,
     pub(crate) client_instance_set: HashMap<Arc<String>, HashSet<InstanceKey>>,
+    /// grpc连接id->连接建立时客户端上报的标签
+    pub(crate) client_labels: HashMap<Arc<String>, Arc<HashMap<String, String>>>,
     cluster_node_manage: Option<Addr<InnerNodeManage>>,
     cluster_delay_notify: Option<Addr<ClusterInstanceDelayNotifyActor>>,
     current_range: Option<ProcessRange>,
+    pub(crate) flapping_detector: FlappingDetector,
+    pub(crate) change_history: ServiceChangeHistory,
+    pub(crate) quarantine: InstanceQuarantine,
+    pub(crate) draining: InstanceDraining,
+    group_rule: Arc<GroupRuleManager>,
+    service_link: Arc<ServiceLinkManager>,
+    metadata_schema: Arc<MetadataSchemaManager>,
+    cluster_settings: Option<Arc<ClusterSettingsManager>>,
+    /// 批量变更期间合并的订阅通知
+    batch_notify_keys: Option<HashSet<ServiceKey>>,
     //dal_addr: Addr<ServiceDalActor>,
 }
 
@@ -104,6 +136,33 @@ impl Inject for NamingActor {
         }
         self.cluster_node_manage = factory_data.get_actor();
         self.cluster_delay_notify = factory_data.get_actor();
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = sys_config {
+            self.flapping_detector.config = FlappingConfig {
+                window_millis: sys_config.naming_flapping_window_second * 1000,
+                threshold: sys_config.naming_flapping_threshold,
+                damping: sys_config.naming_flapping_damping,
+            };
+            self.change_history.config = ChangeHistoryConfig {
+                capacity: sys_config.naming_change_history_size,
+            };
+            self.quarantine.config = QuarantineConfig {
+                ttl_millis: sys_config.naming_quarantine_ttl_second * 1000,
+            };
+        }
+        let group_rule: Option<Arc<GroupRuleManager>> = factory_data.get_bean();
+        if let Some(group_rule) = group_rule {
+            self.group_rule = group_rule;
+        }
+        let service_link: Option<Arc<ServiceLinkManager>> = factory_data.get_bean();
+        if let Some(service_link) = service_link {
+            self.service_link = service_link;
+        }
+        let metadata_schema: Option<Arc<MetadataSchemaManager>> = factory_data.get_bean();
+        if let Some(metadata_schema) = metadata_schema {
+            self.metadata_schema = metadata_schema;
+        }
+        self.cluster_settings = factory_data.get_bean();
         log::info!("NamingActor inject complete");
     }
 }
@@ -123,15 +182,26 @@ impl NamingActor {
             last_id: 0u64,
             listener_addr: None,
             subscriber,
+            pattern_subscriber: Default::default(),
             delay_notify_addr: None,
             sys_config: NamingSysConfig::new(),
             empty_service_set: Default::default(),
             namespace_index: NamespaceIndex::new(),
             instance_metadate_set: Default::default(),
             client_instance_set: Default::default(),
+            client_labels: Default::default(),
             cluster_node_manage: None,
             cluster_delay_notify: None,
             current_range: None,
+            flapping_detector: FlappingDetector::default(),
+            change_history: ServiceChangeHistory::default(),
+            quarantine: InstanceQuarantine::default(),
+            draining: InstanceDraining::default(),
+            group_rule: Default::default(),
+            service_link: Default::default(),
+            metadata_schema: Default::default(),
+            cluster_settings: None,
+            batch_notify_keys: None,
             //dal_addr,
         }
     }
@@ -181,7 +251,86 @@ impl NamingActor {
                     now_millis() + self.sys_config.service_time_out_millis,
                     key.clone(),
                 );
+                self.add_pattern_subscribe(key);
+            }
+        }
+    }
+
+    ///
+    /// 新建的服务匹配模式订阅时,为订阅方增加该服务的订阅
+    fn add_pattern_subscribe(&mut self, key: &ServiceKey) {
+        if self.pattern_subscriber.is_empty() {
+            return;
+        }
+        for client_id in self.pattern_subscriber.match_clients(key) {
+            if self.subscriber.is_subscribed(key, &client_id) {
+                continue;
             }
+            let item = NamingListenerItem {
+                service_key: key.clone(),
+                clusters: None,
+            };
+            self.pattern_subscriber
+                .add_derived(client_id.clone(), key.clone());
+            self.subscriber.add_subscribe(client_id, vec![item]);
+        }
+    }
+
+    ///
+    /// 增加模式订阅;只为尚未订阅的服务增加订阅,并只通知该订阅方
+    fn subscribe_pattern(&mut self, item: PatternSubscribeItem, client_id: Arc<String>) {
+        let keys: Vec<ServiceKey> = self
+            .service_map
+            .keys()
+            .filter(|key| item.is_match(key) && !self.subscriber.is_subscribed(key, &client_id))
+            .cloned()
+            .collect();
+        self.pattern_subscriber.add(client_id.clone(), item);
+        let items = keys
+            .iter()
+            .map(|key| NamingListenerItem {
+                service_key: key.clone(),
+                clusters: None,
+            })
+            .collect();
+        self.subscriber.add_subscribe(client_id.clone(), items);
+        for key in keys {
+            self.pattern_subscriber
+                .add_derived(client_id.clone(), key.clone());
+            self.subscriber.notify_client(key, client_id.clone());
+        }
+    }
+
+    ///
+    /// 取消模式订阅,同时取消由该模式产生的服务订阅(显式订阅与仍被客户端其它模式匹配的除外)
+    fn remove_subscribe_pattern(&mut self, key: &ServiceKey, client_id: Arc<String>) {
+        let item = if let Some(item) = self.pattern_subscriber.remove(&client_id, key) {
+            item
+        } else {
+            return;
+        };
+        let keys: Vec<ServiceKey> = self
+            .service_map
+            .keys()
+            .filter(|key| {
+                item.is_match(key)
+                    && self.pattern_subscriber.is_derived(&client_id, key)
+                    && !self.pattern_subscriber.is_client_match(&client_id, key)
+            })
+            .cloned()
+            .collect();
+        let items: Vec<NamingListenerItem> = keys
+            .into_iter()
+            .map(|key| {
+                self.pattern_subscriber.remove_derived(&client_id, &key);
+                NamingListenerItem {
+                    service_key: key,
+                    clusters: None,
+                }
+            })
+            .collect();
+        if !items.is_empty() {
+            self.subscriber.remove_subscribe(client_id, items);
         }
     }
 
@@ -199,6 +348,9 @@ impl NamingActor {
                 if let Some(metadata) = service_info.metadata {
                     service.metadata = metadata;
                 }
+                if let Some(selector) = service_info.selector {
+                    service.set_selector(selector);
+                }
             }
             None => {
                 let mut service = Service::default();
@@ -217,6 +369,9 @@ impl NamingActor {
                 if let Some(metadata) = service_info.metadata {
                     service.metadata = metadata;
                 }
+                if let Some(selector) = service_info.selector {
+                    service.set_selector(selector);
+                }
                 service.recalculate_checksum();
                 self.namespace_index.insert_service(key.clone());
                 //self.dal_addr.do_send(ServiceDalMsg::AddService(service.get_service_do()));
@@ -225,6 +380,7 @@ impl NamingActor {
                     now_millis() + self.sys_config.service_time_out_millis,
                     key.clone(),
                 );
+                self.add_pattern_subscribe(&key);
             }
         }
     }
@@ -245,15 +401,233 @@ impl NamingActor {
         }
     }
 
+    ///
+    /// 服务改名或移动到其它分组:复制服务定义,迁移本节点的订阅方;
+    /// 旧服务保留订阅方,实例迁移后推送空实例列表,之后按空服务过期清理。
+    /// 返回需要通过raft迁移的持久化服务定义与需要迁移的实例,实例由调用方按类型路由写入
+    pub(crate) fn move_service(
+        &mut self,
+        old_key: &ServiceKey,
+        new_key: &ServiceKey,
+    ) -> anyhow::Result<(Option<ServiceDetailDto>, Vec<Instance>)> {
+        if old_key == new_key {
+            return Err(anyhow::anyhow!("the target service is same as the source"));
+        }
+        if self.service_map.contains_key(new_key) {
+            return Err(anyhow::anyhow!("the target service is exist"));
+        }
+        let (mut service_info, persistent, instances) = match self.service_map.get(old_key) {
+            Some(service) => (
+                service.get_service_detail(),
+                service.persistent,
+                service.instances.values().cloned().collect::<Vec<_>>(),
+            ),
+            None => return Err(anyhow::anyhow!("the service is not exist")),
+        };
+        service_info.namespace_id = new_key.namespace_id.clone();
+        service_info.group_name = new_key.group_name.clone();
+        service_info.service_name = new_key.service_name.clone();
+        self.update_service(service_info.clone());
+        for info in self.subscriber.get_subscribers(old_key) {
+            let clusters = if info.clusters.is_empty() {
+                None
+            } else {
+                Some(info.clusters.into_iter().collect())
+            };
+            let item = NamingListenerItem {
+                service_key: new_key.clone(),
+                clusters,
+            };
+            self.subscriber.add_subscribe(info.client_id, vec![item]);
+        }
+        let instances = instances.iter().map(|e| e.as_ref().clone()).collect();
+        Ok((persistent.then_some(service_info), instances))
+    }
+
+    fn flush_batch_notify(&mut self) {
+        if let Some(keys) = self.batch_notify_keys.take() {
+            for key in keys {
+                self.notify_service_key(key);
+            }
+        }
+    }
+
+    fn notify_subscriber(&mut self, key: ServiceKey) {
+        if let Some(keys) = self.batch_notify_keys.as_mut() {
+            keys.insert(key);
+        } else {
+            self.notify_service_key(key);
+        }
+    }
+
+    ///
+    /// 同时通知其它命名空间中链接到该服务的订阅方
+    fn notify_service_key(&mut self, key: ServiceKey) {
+        for link_key in self.service_link.linked_keys(&key) {
+            if !self.service_map.contains_key(&link_key) {
+                self.notify_udp_listener(&link_key);
+                self.subscriber.notify(link_key);
+            }
+        }
+        self.notify_udp_listener(&key);
+        self.subscriber.notify(key);
+    }
+
+    ///
+    /// 链接的服务是只读的,不能在链接所在的命名空间注册实例
+    fn check_service_link(&self, instances: &[Instance]) -> anyhow::Result<()> {
+        for instance in instances {
+            if instance.is_from_cluster() {
+                continue;
+            }
+            let key = instance.get_service_key();
+            if self.service_map.contains_key(&key) {
+                continue;
+            }
+            if let Some(source) = self.service_link.resolve(&key) {
+                return Err(anyhow::anyhow!(
+                    "the service {}@@{} is a read-only link of namespace {}",
+                    &key.group_name,
+                    &key.service_name,
+                    &source.namespace_id
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// 按服务声明的元数据约束校验注册的实例,集群同步的实例已在源节点校验
+    fn check_metadata_schema(&self, instances: &mut [Instance]) -> anyhow::Result<()> {
+        for instance in instances.iter_mut() {
+            if instance.is_from_cluster() {
+                continue;
+            }
+            self.metadata_schema.check_instance(instance)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// 通知1.x客户端的udp推送监听
+    fn notify_udp_listener(&self, key: &ServiceKey) {
+        if let Some(listener_addr) = self.listener_addr.as_ref() {
+            listener_addr.do_send(NamingListenerCmd::Changed(key.clone()));
+        }
+    }
+
+    ///
+    /// 在一次处理中批量注册或注销实例,每个服务只发出一次变更通知
+    ///
+    /// 检查新注册的临时实例是否超出命名空间的服务数与单服务实例数配额;
+    /// 已存在的实例更新、集群同步与持久化实例不检查
+    fn check_register_quota(&self, instances: &[Instance]) -> anyhow::Result<()> {
+        let settings = match &self.cluster_settings {
+            Some(cluster_settings) => cluster_settings.get_settings(),
+            None => return Ok(()),
+        };
+        if settings.namespace_quotas.is_empty() {
+            return Ok(());
+        }
+        //同一批次内新增的服务与实例
+        let mut new_services: HashMap<Arc<String>, HashSet<ServiceKey>> = HashMap::new();
+        let mut new_instances: HashMap<ServiceKey, HashSet<InstanceShortKey>> = HashMap::new();
+        for instance in instances {
+            if !instance.ephemeral || instance.is_from_cluster() {
+                continue;
+            }
+            //配置中心默认命名空间id为空串
+            let quota_key = if instance.namespace_id.as_str() == DEFAULT_NAMESPACE {
+                ""
+            } else {
+                instance.namespace_id.as_str()
+            };
+            let quota = match settings.namespace_quotas.get(quota_key) {
+                Some(v) => v,
+                None => continue,
+            };
+            let key = instance.get_service_key();
+            let short_key = instance.get_short_key();
+            let instance_count = match self.service_map.get(&key) {
+                Some(service) => {
+                    if service.instances.contains_key(&short_key) {
+                        continue;
+                    }
+                    service.instances.len()
+                }
+                None => {
+                    if let Some(limit) = quota.max_service_count {
+                        let services = new_services.entry(key.namespace_id.clone()).or_default();
+                        services.insert(key.clone());
+                        let service_count = self
+                            .namespace_index
+                            .namespace_group
+                            .get(&key.namespace_id)
+                            .map(|v| v.get_service_count())
+                            .unwrap_or_default();
+                        if (service_count + services.len()) as u64 > limit {
+                            return Err(QuotaExceededError::new(
+                                key.namespace_id,
+                                "serviceCount",
+                                limit,
+                            )
+                            .into());
+                        }
+                    }
+                    0
+                }
+            };
+            if let Some(limit) = quota.max_instance_per_service {
+                let pending = new_instances.entry(key.clone()).or_default();
+                pending.insert(short_key);
+                if (instance_count + pending.len()) as u64 > limit {
+                    return Err(QuotaExceededError::new(
+                        key.namespace_id,
+                        "instancePerService",
+                        limit,
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn batch_update_instances(
+        &mut self,
+        register: bool,
+        instances: Vec<Instance>,
+        tag: Option<InstanceUpdateTag>,
+    ) -> Vec<(u64, Instance)> {
+        let mut rewrite_list = vec![];
+        self.batch_notify_keys = Some(HashSet::new());
+        for instance in instances {
+            let key = instance.get_service_key();
+            if register {
+                if let UpdateInstanceType::UpdateOtherClusterMetaData(node_id, instance) =
+                    self.update_instance(&key, instance, tag.clone(), false)
+                {
+                    rewrite_list.push((node_id, instance));
+                }
+            } else {
+                self.remove_instance(&key, &instance.get_short_key(), Some(&instance.client_id));
+            }
+        }
+        self.flush_batch_notify();
+        rewrite_list
+    }
+
     fn do_notify(
         &mut self,
         tag: &UpdateInstanceType,
         key: ServiceKey,
         instance: Option<Arc<Instance>>,
     ) {
+        //持久化实例通过raft同步,不需要通知其它节点
+        let instance = instance.filter(|e| e.ephemeral);
         match tag {
             UpdateInstanceType::New => {
-                self.subscriber.notify(key);
+                self.notify_subscriber(key);
                 if let (Some(cluster_delay_notify), Some(instance)) =
                     (&self.cluster_delay_notify, instance)
                 {
@@ -262,7 +636,7 @@ impl NamingActor {
                 }
             }
             UpdateInstanceType::Remove => {
-                self.subscriber.notify(key);
+                self.notify_subscriber(key);
                 if let (Some(cluster_delay_notify), Some(instance)) =
                     (&self.cluster_delay_notify, instance)
                 {
@@ -271,7 +645,7 @@ impl NamingActor {
                 }
             }
             UpdateInstanceType::UpdateValue => {
-                self.subscriber.notify(key);
+                self.notify_subscriber(key);
                 if let (Some(cluster_delay_notify), Some(instance)) =
                     (&self.cluster_delay_notify, instance)
                 {
@@ -308,6 +682,18 @@ impl NamingActor {
         let now = now_millis();
         let tag = if let Some(old_instance) = &old_instance {
             real_client_id = Some(old_instance.client_id.clone());
+            if !old_instance.is_from_cluster() {
+                self.change_history.record(
+                    key,
+                    InstanceChangeEvent::new(
+                        now,
+                        old_instance.ip.clone(),
+                        old_instance.port,
+                        InstanceChangeType::Deregister,
+                        old_instance.client_id.clone(),
+                    ),
+                );
+            }
             let short_key = old_instance.get_short_key();
             if service.exist_priority_metadata(&short_key) {
                 let instance_key =
@@ -357,6 +743,9 @@ impl NamingActor {
             instance.from_cluster = 0;
             instance.client_id = EMPTY_ARC_STRING.clone();
         }
+        if instance.from_grpc && instance.client_labels.is_none() {
+            instance.client_labels = self.client_labels.get(&instance.client_id).cloned();
+        }
         //let cluster_name = instance.cluster_name.clone();
         let service = if let Some(service) = self.service_map.get_mut(key) {
             service
@@ -373,12 +762,60 @@ impl NamingActor {
             } else {
                 let mut set = HashSet::new();
                 set.insert(instance_key.clone());
-                self.client_instance_set.insert(client_id, set);
+                self.client_instance_set.insert(client_id.clone(), set);
             }
         }
         let instance_short_key = instance.get_short_key();
+        let now = now_millis();
+        let old_healthy = service.get_instance(&instance_short_key).map(|e| e.healthy);
+        if old_healthy == Some(false)
+            && instance.healthy
+            && self.flapping_detector.need_damping(&instance_key, now)
+        {
+            //抖动期间抑制实例恢复为健康
+            instance.healthy = false;
+        }
+        let new_healthy = instance.healthy;
+        //变更事件只在实例归属的节点记录,避免同步后各节点重复记录
+        let is_local_owned = !instance.is_from_cluster();
 
         let (tag, replace_old_client_id) = service.update_instance(instance, tag);
+        if old_healthy.is_some() && old_healthy != Some(new_healthy) {
+            self.flapping_detector
+                .record_transition(instance_key.clone(), new_healthy, now);
+            let change_type = if new_healthy {
+                InstanceChangeType::Healthy
+            } else {
+                InstanceChangeType::Unhealthy
+            };
+            if is_local_owned {
+                self.change_history.record(
+                    key,
+                    InstanceChangeEvent::new(
+                        now,
+                        instance_key.ip.clone(),
+                        instance_key.port,
+                        change_type,
+                        client_id.clone(),
+                    ),
+                );
+            }
+        } else if let UpdateInstanceType::New = &tag {
+            self.quarantine.remove(key, &instance_short_key);
+            self.draining.remove(&instance_key);
+            if is_local_owned {
+                self.change_history.record(
+                    key,
+                    InstanceChangeEvent::new(
+                        now,
+                        instance_key.ip.clone(),
+                        instance_key.port,
+                        InstanceChangeType::Register,
+                        client_id.clone(),
+                    ),
+                );
+            }
+        }
         if let UpdateInstanceType::UpdateOtherClusterMetaData(_, _) = &tag {
             return tag;
         }
@@ -395,6 +832,29 @@ impl NamingActor {
         tag
     }
 
+    ///
+    /// 注册或注销请求带的注册版本比当前实例旧时,说明实例已被新进程重新注册,忽略该请求
+    fn is_stale_write(&self, instance: &Instance) -> bool {
+        if instance.revision == 0 {
+            return false;
+        }
+        let current_revision = self
+            .get_instance(&instance.get_service_key(), &instance.get_short_key())
+            .map(|e| e.revision)
+            .unwrap_or_default();
+        if current_revision > instance.revision {
+            log::warn!(
+                "ignore stale instance write,{}:{},revision:{},current revision:{}",
+                &instance.ip,
+                instance.port,
+                instance.revision,
+                current_revision
+            );
+            return true;
+        }
+        false
+    }
+
     pub(crate) fn remove_client_instance(&mut self, client_id: &Arc<String>) {
         if let Some(keys) = self.client_instance_set.remove(client_id) {
             for instance_key in keys {
@@ -423,21 +883,61 @@ impl NamingActor {
         }
     }
 
+    ///
+    /// 查询服务,原分组不存在时按命名空间分组规则解析,再按跨命名空间的服务链接解析
+    fn get_service_with_group_rule(&self, key: &ServiceKey) -> Option<&Service> {
+        if let Some(service) = self.service_map.get(key) {
+            return Some(service);
+        }
+        if let Some(group_name) = self
+            .group_rule
+            .resolve_group(&key.namespace_id, &key.group_name)
+        {
+            let alias_key = ServiceKey::new_by_arc(
+                key.namespace_id.clone(),
+                group_name,
+                key.service_name.clone(),
+            );
+            if let Some(service) = self.service_map.get(&alias_key) {
+                return Some(service);
+            }
+        }
+        let source = self.service_link.resolve(key)?;
+        self.service_map.get(&source)
+    }
+
     pub fn get_instance_list(
         &self,
         key: &ServiceKey,
         cluster_str: &str,
         only_healthy: bool,
     ) -> Vec<Arc<Instance>> {
+        self.get_instance_list_with_protection(key, cluster_str, only_healthy, None)
+            .0
+    }
+
+    ///
+    /// 查询实例列表,并返回是否触发了保护阈值
+    pub fn get_instance_list_with_protection(
+        &self,
+        key: &ServiceKey,
+        cluster_str: &str,
+        only_healthy: bool,
+        consumer_ip: Option<&str>,
+    ) -> (Vec<Arc<Instance>>, bool) {
         let cluster_names = NamingUtils::split_filters(cluster_str);
-        if let Some(service) = self.service_map.get(key) {
-            return InstanceFilterUtils::default_instance_filter(
-                service.get_instance_list(cluster_names, false, true),
+        if let Some(service) = self.get_service_with_group_rule(key) {
+            return InstanceFilterUtils::instance_filter_with_protection(
+                self.select_instances(
+                    service,
+                    service.get_instance_list(cluster_names, false, true),
+                    consumer_ip,
+                ),
                 Some(service.get_metadata()),
                 only_healthy,
             );
         }
-        vec![]
+        (vec![], false)
     }
 
     pub fn get_instances_and_metadata(
@@ -445,17 +945,66 @@ impl NamingActor {
         key: &ServiceKey,
         cluster_str: &str,
         only_healthy: bool,
+        consumer_ip: Option<&str>,
     ) -> (Vec<Arc<Instance>>, Option<ServiceMetadata>) {
         let cluster_names = NamingUtils::split_filters(cluster_str);
-        if let Some(service) = self.service_map.get(key) {
+        if let Some(service) = self.get_service_with_group_rule(key) {
             return (
-                service.get_instance_list(cluster_names, only_healthy, true),
+                self.select_instances(
+                    service,
+                    service.get_instance_list(cluster_names, only_healthy, true),
+                    consumer_ip,
+                ),
                 Some(service.get_metadata()),
             );
         }
         (vec![], None)
     }
 
+    ///
+    /// 按服务选择器过滤实例;没有消费方ip时只按提供方标签过滤
+    fn select_instances(
+        &self,
+        service: &Service,
+        list: Vec<Arc<Instance>>,
+        consumer_ip: Option<&str>,
+    ) -> Vec<Arc<Instance>> {
+        let selector = match &service.label_selector {
+            Some(v) => v,
+            None => return list,
+        };
+        let consumer_labels = match consumer_ip {
+            Some(ip) if selector.need_consumer_labels() => {
+                self.get_consumer_labels(&service.namespace_id, ip)
+            }
+            _ => HashMap::new(),
+        };
+        selector.select(list, &consumer_labels)
+    }
+
+    ///
+    /// 消费方标签取同命名空间下该ip注册实例的metadata
+    fn get_consumer_labels(
+        &self,
+        namespace_id: &Arc<String>,
+        consumer_ip: &str,
+    ) -> HashMap<String, String> {
+        let mut labels = HashMap::new();
+        for service in self.service_map.values() {
+            if &service.namespace_id != namespace_id {
+                continue;
+            }
+            for instance in service.instances.values() {
+                if instance.ip.as_str() == consumer_ip {
+                    for (k, v) in instance.metadata.iter() {
+                        labels.entry(k.to_owned()).or_insert_with(|| v.to_owned());
+                    }
+                }
+            }
+        }
+        labels
+    }
+
     pub fn get_metadata(&self, key: &ServiceKey) -> Option<ServiceMetadata> {
         self.service_map.get(key).map(|e| e.get_metadata())
     }
@@ -484,8 +1033,10 @@ impl NamingActor {
         key: &ServiceKey,
         cluster_str: String,
         only_healthy: bool,
+        consumer_ip: Option<&str>,
     ) -> ServiceInfo {
-        let (hosts, metadata) = self.get_instances_and_metadata(key, &cluster_str, false);
+        let (hosts, metadata) =
+            self.get_instances_and_metadata(key, &cluster_str, false, consumer_ip);
         let service_info = ServiceInfo {
             name: Some(key.service_name.clone()),
             group_name: Some(key.group_name.clone()),
@@ -504,24 +1055,63 @@ impl NamingActor {
         key: &ServiceKey,
         cluster_str: String,
         only_healthy: bool,
+        consumer_ip: Option<&str>,
     ) -> String {
-        let list = self.get_instance_list(key, &cluster_str, only_healthy);
-        QueryListResult::get_instance_list_string(cluster_str, key, list)
+        let (list, reach_protection_threshold) =
+            self.get_instance_list_with_protection(key, &cluster_str, only_healthy, consumer_ip);
+        QueryListResult::get_instance_list_string(
+            cluster_str,
+            key,
+            list,
+            reach_protection_threshold,
+        )
     }
 
     pub fn time_check(&mut self) {
         let current_time = Local::now().timestamp_millis();
-        let healthy_time = current_time - 15000;
-        let offline_time = current_time - 30000;
         let mut size = 0;
         let now = now_millis();
         let mut change_list = vec![];
         for item in self.service_map.values_mut() {
             let service_key = item.get_service_key();
-            let (rlist, ulist) = item.time_check(healthy_time, offline_time);
+            let (removed_instances, ulist) = item.time_check(current_time);
+            let mut rlist = Vec::with_capacity(removed_instances.len());
+            for instance in removed_instances {
+                rlist.push(instance.get_short_key());
+                self.quarantine.add(&service_key, instance, now);
+            }
             size += rlist.len() + ulist.len();
+            for short_key in &ulist {
+                let instance_key = InstanceKey::new_by_service_key(
+                    &service_key,
+                    short_key.ip.clone(),
+                    short_key.port,
+                );
+                self.flapping_detector
+                    .record_transition(instance_key, false, now);
+                self.change_history.record(
+                    &service_key,
+                    InstanceChangeEvent::new(
+                        now,
+                        short_key.ip.clone(),
+                        short_key.port,
+                        InstanceChangeType::Unhealthy,
+                        EMPTY_ARC_STRING.clone(),
+                    ),
+                );
+            }
             if !rlist.is_empty() {
                 for short_key in &rlist {
+                    self.change_history.record(
+                        &service_key,
+                        InstanceChangeEvent::new(
+                            now,
+                            short_key.ip.clone(),
+                            short_key.port,
+                            InstanceChangeType::Expire,
+                            EMPTY_ARC_STRING.clone(),
+                        ),
+                    );
                     if item.exist_priority_metadata(short_key) {
                         let instance_key = InstanceKey::new_by_service_key(
                             &service_key,
@@ -551,6 +1141,98 @@ impl NamingActor {
         }
     }
 
+    ///
+    /// 设置了主动健康检查的服务及本节点负责的实例
+    fn get_health_check_targets(&self) -> Vec<HealthCheckTarget> {
+        self.service_map
+            .values()
+            .filter_map(|service| {
+                let checker = HealthChecker::from_metadata(&service.metadata)?;
+                let instances = service.get_health_check_instances();
+                if instances.is_empty() {
+                    return None;
+                }
+                Some(HealthCheckTarget {
+                    service_key: service.get_service_key(),
+                    checker,
+                    instances,
+                })
+            })
+            .collect()
+    }
+
+    ///
+    /// 按命名空间汇总服务与实例数量,namespace_id为空时统计所有命名空间
+    fn get_namespace_stats(&self, namespace_id: Option<&Arc<String>>) -> Vec<NamespaceNamingStats> {
+        let mut map: HashMap<Arc<String>, NamespaceNamingStats> = HashMap::new();
+        for service in self.service_map.values() {
+            if let Some(namespace_id) = namespace_id {
+                if &service.namespace_id != namespace_id {
+                    continue;
+                }
+            }
+            let stats = map
+                .entry(service.namespace_id.clone())
+                .or_insert_with(|| NamespaceNamingStats::new(service.namespace_id.clone()));
+            stats.service_count += 1;
+            if service.instances.is_empty() {
+                stats.empty_service_count += 1;
+            }
+            for instance in service.instances.values() {
+                stats.add_instance(instance);
+            }
+        }
+        let mut list: Vec<NamespaceNamingStats> = map.into_values().collect();
+        list.sort_by(|a, b| a.namespace_id.cmp(&b.namespace_id));
+        list
+    }
+
+    fn update_health_check_result(
+        &mut self,
+        service_key: ServiceKey,
+        list: Vec<(InstanceShortKey, bool)>,
+    ) {
+        let now = now_millis();
+        let mut update_list = vec![];
+        if let Some(service) = self.service_map.get_mut(&service_key) {
+            for (short_key, healthy) in list {
+                let changed = if healthy {
+                    service.update_instance_healthy_valid(&short_key)
+                } else {
+                    service.update_instance_healthy_invalid(&short_key)
+                };
+                if changed {
+                    let instance_key = InstanceKey::new_by_service_key(
+                        &service_key,
+                        short_key.ip.clone(),
+                        short_key.port,
+                    );
+                    self.flapping_detector
+                        .record_transition(instance_key, healthy, now);
+                    let change_type = if healthy {
+                        InstanceChangeType::Healthy
+                    } else {
+                        InstanceChangeType::Unhealthy
+                    };
+                    self.change_history.record(
+                        &service_key,
+                        InstanceChangeEvent::new(
+                            now,
+                            short_key.ip.clone(),
+                            short_key.port,
+                            change_type,
+                            EMPTY_ARC_STRING.clone(),
+                        ),
+                    );
+                    update_list.push(short_key);
+                }
+            }
+        }
+        if !update_list.is_empty() {
+            self.time_check_notify(service_key, vec![], update_list);
+        }
+    }
+
     fn time_check_notify(
         &mut self,
         key: ServiceKey,
@@ -626,6 +1308,7 @@ impl NamingActor {
             limit: page_size,
             namespace_id: Some(key.namespace_id.clone()),
             group: Some(key.group_name.clone()),
+            like_service: StringUtils::map_not_empty(Some(key.service_name.as_ref().to_owned())),
             ..Default::default()
         };
         let (size, list) = self.namespace_index.query_service_page(&param);
@@ -633,6 +1316,30 @@ impl NamingActor {
         (size, service_names)
     }
 
+    ///
+    /// 按ip(及端口)反查注册的实例
+    pub fn get_instances_by_ip(
+        &self,
+        ip: &str,
+        port: Option<u32>,
+        namespace_id: Option<&Arc<String>>,
+    ) -> Vec<Arc<Instance>> {
+        let mut list = vec![];
+        for (key, service) in &self.service_map {
+            if let Some(namespace_id) = namespace_id {
+                if namespace_id != &key.namespace_id {
+                    continue;
+                }
+            }
+            for instance in service.instances.values() {
+                if instance.ip.as_str() == ip && port.map(|p| p == instance.port).unwrap_or(true) {
+                    list.push(instance.clone());
+                }
+            }
+        }
+        list
+    }
+
     pub fn get_service_info_page(&self, param: ServiceQueryParam) -> (usize, Vec<ServiceInfoDto>) {
         let (size, list) = self.namespace_index.query_service_page(&param);
 
@@ -674,12 +1381,14 @@ impl NamingActor {
     fn clear_one_empty_service(&mut self, service_map_key: ServiceKey, now: u64) {
         if let Some(service) = self.service_map.get(&service_map_key) {
             if service.instance_size <= 0
+                && !service.persistent
                 && now - self.sys_config.service_time_out_millis >= service.last_empty_times
             {
                 //self.dal_addr.do_send(ServiceDalMsg::DeleteService(service.get_service_do().get_key_param().unwrap()));
                 self.namespace_index
                     .remove_service(&service.get_service_key());
                 self.service_map.remove(&service_map_key);
+                self.change_history.remove_service(&service_map_key);
                 log::info!("clear_empty_service:{:?}", &service_map_key);
             }
         }
@@ -701,10 +1410,56 @@ impl NamingActor {
         }
     }
 
+    ///
+    /// 实例摘流:设置为禁用并推送给订阅方,grace_millis后删除;
+    /// 实例由其它节点管理时返回该节点id,由调用方转发
+    pub(crate) fn drain_instance(
+        &mut self,
+        key: &InstanceKey,
+        grace_millis: u64,
+    ) -> anyhow::Result<Option<u64>> {
+        let service_key = key.get_service_key();
+        let old = self
+            .service_map
+            .get(&service_key)
+            .and_then(|service| service.get_instance(&key.get_short_key()))
+            .ok_or_else(|| anyhow::anyhow!("instance not found"))?;
+        if !old.ephemeral {
+            return Err(anyhow::anyhow!(
+                "persistent instance does not support draining"
+            ));
+        }
+        if old.is_from_cluster() {
+            return Ok(Some(old.from_cluster));
+        }
+        let mut instance = old.as_ref().clone();
+        instance.enabled = false;
+        let tag = InstanceUpdateTag {
+            weight: false,
+            metadata: false,
+            enabled: true,
+            ephemeral: false,
+            from_update: false,
+            metadata_patch: None,
+        };
+        self.update_instance(&service_key, instance, Some(tag), false);
+        self.draining.add(key.clone(), now_millis() + grace_millis);
+        Ok(None)
+    }
+
+    fn remove_drained_instances(&mut self) {
+        for key in self.draining.timeout(now_millis()) {
+            self.remove_instance(&key.get_service_key(), &key.get_short_key(), None);
+        }
+    }
+
     pub fn instance_time_out_heartbeat(&self, ctx: &mut actix::Context<Self>) {
         ctx.run_later(Duration::from_millis(2000), |act, ctx| {
             act.clear_empty_service();
             act.clear_timeout_instance_metadata();
+            act.flapping_detector.clear_timeout(now_millis());
+            act.quarantine.clear_timeout(now_millis());
+            act.remove_drained_instances();
             let addr = ctx.address();
             addr.do_send(NamingCmd::PeekListenerTimeout);
             act.instance_time_out_heartbeat(ctx);
@@ -833,13 +1588,19 @@ pub enum NamingCmd {
     Update(Instance, Option<InstanceUpdateTag>),
     UpdateFromSync(Instance, Option<InstanceUpdateTag>),
     UpdateBatch(Vec<Instance>),
+    /// 批量心跳,用于代理多个实例健康状态的节点agent
+    BeatBatch(Vec<Instance>, InstanceUpdateTag),
     Delete(Instance),
     DeleteBatch(Vec<Instance>),
     Query(Instance),
-    QueryList(ServiceKey, String, bool, Option<SocketAddr>),
+    /// 服务、集群、是否只返回健康实例、udp监听地址、消费方ip
+    QueryList(ServiceKey, String, bool, Option<SocketAddr>, Option<String>),
     QueryAllInstanceList(ServiceKey),
-    QueryListString(ServiceKey, String, bool, Option<SocketAddr>),
-    QueryServiceInfo(ServiceKey, String, bool),
+    QueryListString(ServiceKey, String, bool, Option<SocketAddr>, Option<String>),
+    QueryServiceInfo(ServiceKey, String, bool, Option<String>),
+    /// 按订阅者的集群过滤条件查询需要重推的服务信息
+    QueryRepushServiceInfo(ServiceKey, HashSet<Arc<String>>),
+    /// 服务名不为空时按服务名模糊匹配(支持通配符`*`、`?`)
     QueryServicePage(ServiceKey, usize, usize),
     //查询服务实际信息列表
     QueryServiceInfoPage(ServiceQueryParam),
@@ -847,17 +1608,69 @@ pub enum NamingCmd {
     UpdateService(ServiceDetailDto),
     UpdateServiceFromCluster(ServiceDetailDto),
     RemoveService(ServiceKey),
+    /// 服务改名或移动分组:原服务,目标服务
+    MoveService(ServiceKey, ServiceKey),
+    QueryServiceDetail(ServiceKey),
+    /// raft状态机中的服务定义变更
+    UpdatePersistentService(ServiceDetailDto),
+    RemovePersistentService(ServiceKey),
     PeekListenerTimeout,
     NotifyListener(ServiceKey, u64),
-    Subscribe(Vec<NamingListenerItem>, Arc<String>),
+    Subscribe(
+        Vec<NamingListenerItem>,
+        Arc<String>,
+        Option<Arc<SubscriberClient>>,
+    ),
     RemoveSubscribe(Vec<NamingListenerItem>, Arc<String>),
+    /// 按服务名模式订阅(前缀或正则),匹配当前及后续新建的服务
+    SubscribePattern(
+        PatternSubscribeItem,
+        Arc<String>,
+        Option<Arc<SubscriberClient>>,
+    ),
+    RemoveSubscribePattern(ServiceKey, Arc<String>),
+    QueryPatternSubscribers(Option<Arc<String>>),
     RemoveClient(Arc<String>),
+    /// 记录grpc连接的客户端标签,用于追溯实例的注册来源
+    SetClientLabels(Arc<String>, HashMap<String, String>),
     RemoveClientFromCluster(Arc<String>),
     QueryClientInstanceCount,
     QueryDalAddr,
     QuerySnapshot(Vec<ProcessRange>),
     ClusterRefreshProcessRange(ProcessRange),
     ReceiveSnapshot(SnapshotForReceive),
+    QueryFlappingInstances(Option<Arc<String>>),
+    /// 查询服务的实例变更事件:服务,开始时间,结束时间
+    QueryServiceChangeHistory(ServiceKey, u64, u64),
+    /// 查询服务最近心跳超时被移除的实例
+    QueryQuarantineInstances(ServiceKey),
+    /// 实例摘流,参数为宽限期毫秒数
+    DrainInstance(InstanceKey, u64),
+    /// raft状态机中的持久化实例变更
+    UpdatePersistent(Instance),
+    RemovePersistent(Instance),
+    /// 导出命名空间下的服务定义
+    QueryServiceExport(Arc<String>),
+    /// 导出命名空间下的服务定义与持久化实例
+    QueryNamingBackup(Arc<String>),
+    /// ip,port,namespace_id
+    QueryInstancesByIp(Arc<String>, Option<u32>, Option<Arc<String>>),
+    /// 按ip前缀搜索实例:ip前缀,最大数量
+    SearchInstancesByIp(String, usize),
+    /// 查询各命名空间的实例数量
+    QueryNamespaceInstanceCount,
+    /// 查询命名空间的服务与实例统计,为空时查询所有命名空间
+    QueryNamespaceStats(Option<Arc<String>>),
+    /// 查询各服务的实例数量与健康实例数量
+    QueryServiceInstanceCounts,
+    /// 批量注册(true)或注销(false)实例
+    BatchInstance(bool, Vec<Instance>, Option<InstanceUpdateTag>),
+    /// 查询服务的当前订阅方
+    QuerySubscriberList(ServiceKey),
+    /// 查询需要主动健康检查的实例
+    QueryHealthCheckTargets,
+    /// 主动健康检查结果:实例,是否健康
+    UpdateHealthCheckResult(ServiceKey, Vec<(InstanceShortKey, bool)>),
 }
 
 pub enum NamingResult {
@@ -870,7 +1683,25 @@ pub enum NamingResult {
     ServiceInfoPage((usize, Vec<ServiceInfoDto>)),
     ClientInstanceCount(Vec<(Arc<String>, usize)>),
     RewriteToCluster(u64, Instance),
+    RewriteToClusterList(Vec<(u64, Instance)>),
     Snapshot(SnapshotForSend),
+    FlappingInstances(Vec<FlappingInstanceInfo>),
+    ServiceChangeHistory(Vec<InstanceChangeEvent>),
+    RepushServiceInfo(Vec<(HashSet<Arc<String>>, ServiceInfo)>),
+    QuarantineInstances(Vec<QuarantineInstance>),
+    /// 实例由其它节点管理,需要转发到该节点摘流
+    DrainToCluster(u64),
+    ServiceExport(Vec<NacosServiceExport>),
+    NamingBackup(NamingBackup),
+    NamespaceInstanceCount(HashMap<Arc<String>, u64>),
+    NamespaceStats(Vec<NamespaceNamingStats>),
+    ServiceInstanceCounts(Vec<(ServiceKey, u64, u64)>),
+    SubscriberList(Vec<SubscriberInfo>),
+    PatternSubscribers(Vec<PatternSubscribeInfo>),
+    ServiceDetail(Option<ServiceDetailDto>),
+    /// 需要通过raft迁移的持久化服务定义与持久化实例
+    ServiceMoved(Option<ServiceDetailDto>, Vec<Instance>),
+    HealthCheckTargets(Vec<HealthCheckTarget>),
 }
 
 impl Supervised for NamingActor {
@@ -884,7 +1715,13 @@ impl Handler<NamingCmd> for NamingActor {
 
     fn handle(&mut self, msg: NamingCmd, ctx: &mut Context<Self>) -> Self::Result {
         match msg {
-            NamingCmd::Update(instance, tag) => {
+            NamingCmd::Update(mut instance, tag) => {
+                if self.is_stale_write(&instance) {
+                    return Ok(NamingResult::NULL);
+                }
+                self.check_service_link(std::slice::from_ref(&instance))?;
+                self.check_metadata_schema(std::slice::from_mut(&mut instance))?;
+                self.check_register_quota(std::slice::from_ref(&instance))?;
                 let tag = self.update_instance(&instance.get_service_key(), instance, tag, false);
                 if let UpdateInstanceType::UpdateOtherClusterMetaData(node_id, instance) = tag {
                     Ok(NamingResult::RewriteToCluster(node_id, instance))
@@ -906,7 +1743,25 @@ impl Handler<NamingCmd> for NamingActor {
                 }
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::BeatBatch(instances, tag) => {
+                let mut rewrite_list = vec![];
+                for instance in instances {
+                    let tag = self.update_instance(
+                        &instance.get_service_key(),
+                        instance,
+                        Some(tag.clone()),
+                        false,
+                    );
+                    if let UpdateInstanceType::UpdateOtherClusterMetaData(node_id, instance) = tag {
+                        rewrite_list.push((node_id, instance));
+                    }
+                }
+                Ok(NamingResult::RewriteToClusterList(rewrite_list))
+            }
             NamingCmd::Delete(instance) => {
+                if self.is_stale_write(&instance) {
+                    return Ok(NamingResult::NULL);
+                }
                 self.remove_instance(
                     &instance.get_service_key(),
                     &instance.get_short_key(),
@@ -916,6 +1771,9 @@ impl Handler<NamingCmd> for NamingActor {
             }
             NamingCmd::DeleteBatch(instances) => {
                 for instance in instances {
+                    if self.is_stale_write(&instance) {
+                        continue;
+                    }
                     self.remove_instance(
                         &instance.get_service_key(),
                         &instance.get_short_key(),
@@ -932,28 +1790,71 @@ impl Handler<NamingCmd> for NamingActor {
                 }
                 Ok(NamingResult::NULL)
             }
-            NamingCmd::QueryList(service_key, cluster_str, only_healthy, addr) => {
+            NamingCmd::QueryList(service_key, cluster_str, only_healthy, addr, consumer_ip) => {
                 let cluster_names = NamingUtils::split_filters(&cluster_str);
                 if let Some(addr) = addr {
                     self.update_listener(&service_key, &cluster_names, addr, only_healthy);
                 }
-                let list = self.get_instance_list(&service_key, &cluster_str, only_healthy);
+                let mut list = self
+                    .get_instance_list_with_protection(
+                        &service_key,
+                        &cluster_str,
+                        only_healthy,
+                        consumer_ip.as_deref(),
+                    )
+                    .0;
+                //服务端选择实例与dns记录按预热后的权重计算
+                if let Some(warmup) = self
+                    .get_service_with_group_rule(&service_key)
+                    .and_then(|e| WarmupUtils::get_warmup_millis(&e.metadata))
+                {
+                    list = WarmupUtils::apply(list, warmup, now_millis());
+                }
                 Ok(NamingResult::InstanceList(list))
             }
-            NamingCmd::QueryListString(service_key, cluster_str, only_healthy, addr) => {
+            NamingCmd::QueryListString(
+                service_key,
+                cluster_str,
+                only_healthy,
+                addr,
+                consumer_ip,
+            ) => {
                 //println!("QUERY_LIST_STRING addr: {:?}",&addr);
                 let cluster_names = NamingUtils::split_filters(&cluster_str);
                 if let Some(addr) = addr {
                     self.update_listener(&service_key, &cluster_names, addr, only_healthy);
                 }
-                let data = self.get_instance_list_string(&service_key, cluster_str, only_healthy);
+                let data = self.get_instance_list_string(
+                    &service_key,
+                    cluster_str,
+                    only_healthy,
+                    consumer_ip.as_deref(),
+                );
                 Ok(NamingResult::InstanceListString(data))
             }
-            NamingCmd::QueryServiceInfo(service_key, cluster_str, only_healthy) => {
+            NamingCmd::QueryServiceInfo(service_key, cluster_str, only_healthy, consumer_ip) => {
                 let cluster_names = NamingUtils::split_filters(&cluster_str);
-                let service_info = self.get_service_info(&service_key, cluster_str, only_healthy);
+                let service_info = self.get_service_info(
+                    &service_key,
+                    cluster_str,
+                    only_healthy,
+                    consumer_ip.as_deref(),
+                );
                 Ok(NamingResult::ServiceInfo(service_info))
             }
+            NamingCmd::QueryRepushServiceInfo(service_key, client_id_set) => {
+                let list = self
+                    .subscriber
+                    .group_by_clusters(&service_key, client_id_set)
+                    .into_iter()
+                    .map(|(cluster_str, client_id_set)| {
+                        let service_info =
+                            self.get_service_info(&service_key, cluster_str, true, None);
+                        (client_id_set, service_info)
+                    })
+                    .collect();
+                Ok(NamingResult::RepushServiceInfo(list))
+            }
             NamingCmd::QueryServicePage(service_key, page_size, page_index) => {
                 Ok(NamingResult::ServicePage(self.get_service_list(
                     page_size,
@@ -978,7 +1879,15 @@ impl Handler<NamingCmd> for NamingActor {
                 }
                 Ok(NamingResult::NULL)
             }
-            NamingCmd::Subscribe(items, client_id) => {
+            NamingCmd::Subscribe(items, client_id, client) => {
+                if let Some(client) = client {
+                    self.subscriber.set_client_info(client_id.clone(), client);
+                }
+                for item in &items {
+                    //显式订阅后不再随模式订阅取消
+                    self.pattern_subscriber
+                        .remove_derived(&client_id, &item.service_key);
+                }
                 self.subscriber.add_subscribe(client_id, items.clone());
                 //debug
                 for item in items {
@@ -990,13 +1899,36 @@ impl Handler<NamingCmd> for NamingActor {
                 self.subscriber.remove_subscribe(client_id, items);
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::SubscribePattern(item, client_id, client) => {
+                if let Some(client) = client {
+                    self.subscriber.set_client_info(client_id.clone(), client);
+                }
+                self.subscribe_pattern(item, client_id);
+                Ok(NamingResult::NULL)
+            }
+            NamingCmd::RemoveSubscribePattern(key, client_id) => {
+                self.remove_subscribe_pattern(&key, client_id);
+                Ok(NamingResult::NULL)
+            }
+            NamingCmd::QueryPatternSubscribers(namespace_id) => {
+                Ok(NamingResult::PatternSubscribers(
+                    self.pattern_subscriber.get_list(namespace_id.as_ref()),
+                ))
+            }
             NamingCmd::RemoveClient(client_id) => {
+                self.client_labels.remove(&client_id);
+                self.pattern_subscriber.remove_client(&client_id);
                 self.subscriber.remove_client_subscribe(client_id.clone());
                 self.remove_client_instance(&client_id);
                 self.notify_cluster_remove_client_id(client_id);
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::SetClientLabels(client_id, labels) => {
+                self.client_labels.insert(client_id, Arc::new(labels));
+                Ok(NamingResult::NULL)
+            }
             NamingCmd::RemoveClientFromCluster(client_id) => {
+                self.pattern_subscriber.remove_client(&client_id);
                 self.subscriber.remove_client_subscribe(client_id.clone());
                 self.remove_client_instance(&client_id);
                 Ok(NamingResult::NULL)
@@ -1026,6 +1958,38 @@ impl Handler<NamingCmd> for NamingActor {
                 self.remove_empty_service(service_key)?;
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::MoveService(old_key, new_key) => {
+                let (service_info, instances) = self.move_service(&old_key, &new_key)?;
+                Ok(NamingResult::ServiceMoved(service_info, instances))
+            }
+            NamingCmd::QueryHealthCheckTargets => Ok(NamingResult::HealthCheckTargets(
+                self.get_health_check_targets(),
+            )),
+            NamingCmd::UpdateHealthCheckResult(service_key, list) => {
+                self.update_health_check_result(service_key, list);
+                Ok(NamingResult::NULL)
+            }
+            NamingCmd::QueryServiceDetail(service_key) => Ok(NamingResult::ServiceDetail(
+                self.service_map
+                    .get(&service_key)
+                    .map(|service| service.get_service_detail()),
+            )),
+            NamingCmd::UpdatePersistentService(service_info) => {
+                let key = service_info.to_service_key();
+                self.update_service(service_info);
+                if let Some(service) = self.service_map.get_mut(&key) {
+                    service.persistent = true;
+                }
+                Ok(NamingResult::NULL)
+            }
+            NamingCmd::RemovePersistentService(service_key) => {
+                if let Some(service) = self.service_map.get_mut(&service_key) {
+                    service.persistent = false;
+                }
+                //还有实例时保留服务,等实例下线后按空服务过期清理
+                self.remove_empty_service(service_key).ok();
+                Ok(NamingResult::NULL)
+            }
             NamingCmd::QueryAllInstanceList(key) => {
                 if let Some(service) = self.service_map.get(&key) {
                     Ok(NamingResult::InstanceList(service.get_instance_list(
@@ -1059,6 +2023,129 @@ impl Handler<NamingCmd> for NamingActor {
                 }
                 Ok(NamingResult::NULL)
             }
+            NamingCmd::QueryInstancesByIp(ip, port, namespace_id) => {
+                Ok(NamingResult::InstanceList(self.get_instances_by_ip(
+                    &ip,
+                    port,
+                    namespace_id.as_ref(),
+                )))
+            }
+            NamingCmd::SearchInstancesByIp(ip_prefix, limit) => {
+                let list = self
+                    .service_map
+                    .values()
+                    .flat_map(|service| service.instances.values())
+                    .filter(|instance| instance.ip.starts_with(&ip_prefix))
+                    .take(limit)
+                    .cloned()
+                    .collect();
+                Ok(NamingResult::InstanceList(list))
+            }
+            NamingCmd::UpdatePersistent(instance) => {
+                self.update_instance(&instance.get_service_key(), instance, None, false);
+                Ok(NamingResult::NULL)
+            }
+            NamingCmd::RemovePersistent(instance) => {
+                self.remove_instance(&instance.get_service_key(), &instance.get_short_key(), None);
+                Ok(NamingResult::NULL)
+            }
+            NamingCmd::BatchInstance(register, mut instances, tag) => {
+                if register {
+                    self.check_service_link(&instances)?;
+                    self.check_metadata_schema(&mut instances)?;
+                    self.check_register_quota(&instances)?;
+                }
+                Ok(NamingResult::RewriteToClusterList(
+                    self.batch_update_instances(register, instances, tag),
+                ))
+            }
+            NamingCmd::QuerySubscriberList(key) => Ok(NamingResult::SubscriberList(
+                self.subscriber.get_subscribers(&key),
+            )),
+            NamingCmd::QueryNamespaceInstanceCount => {
+                let mut map: HashMap<Arc<String>, u64> = HashMap::new();
+                for service in self.service_map.values() {
+                    *map.entry(service.namespace_id.clone()).or_default() +=
+                        service.instances.len() as u64;
+                }
+                Ok(NamingResult::NamespaceInstanceCount(map))
+            }
+            NamingCmd::QueryNamespaceStats(namespace_id) => Ok(NamingResult::NamespaceStats(
+                self.get_namespace_stats(namespace_id.as_ref()),
+            )),
+            NamingCmd::QueryServiceInstanceCounts => {
+                let list = self
+                    .service_map
+                    .iter()
+                    .map(|(key, service)| {
+                        (
+                            key.clone(),
+                            service.instances.len() as u64,
+                            service.healthy_instance_size.max(0) as u64,
+                        )
+                    })
+                    .collect();
+                Ok(NamingResult::ServiceInstanceCounts(list))
+            }
+            NamingCmd::QueryServiceExport(namespace_id) => {
+                let mut list: Vec<NacosServiceExport> = self
+                    .service_map
+                    .values()
+                    .filter(|service| service.namespace_id == namespace_id)
+                    .map(|service| service.get_export_info())
+                    .collect();
+                list.sort_by(|a, b| {
+                    (&a.service.group_name, &a.service.name)
+                        .cmp(&(&b.service.group_name, &b.service.name))
+                });
+                Ok(NamingResult::ServiceExport(list))
+            }
+            NamingCmd::QueryNamingBackup(namespace_id) => {
+                let mut services = vec![];
+                let mut instances = vec![];
+                for service in self
+                    .service_map
+                    .values()
+                    .filter(|service| service.namespace_id == namespace_id)
+                {
+                    services.push(service.get_service_detail());
+                    instances.extend(
+                        service
+                            .instances
+                            .values()
+                            .filter(|e| !e.ephemeral)
+                            .map(|e| e.as_ref().clone()),
+                    );
+                }
+                services.sort_by(|a, b| {
+                    (&a.group_name, &a.service_name).cmp(&(&b.group_name, &b.service_name))
+                });
+                Ok(NamingResult::NamingBackup(NamingBackup::new(
+                    namespace_id,
+                    now_millis_i64(),
+                    services,
+                    instances,
+                )))
+            }
+            NamingCmd::QueryFlappingInstances(namespace_id) => {
+                let list = self
+                    .flapping_detector
+                    .get_flapping_list(namespace_id.as_ref(), now_millis());
+                Ok(NamingResult::FlappingInstances(list))
+            }
+            NamingCmd::QueryServiceChangeHistory(key, start_time, end_time) => {
+                let list = self.change_history.query(&key, start_time, end_time);
+                Ok(NamingResult::ServiceChangeHistory(list))
+            }
+            NamingCmd::DrainInstance(key, grace_millis) => {
+                match self.drain_instance(&key, grace_millis)? {
+                    Some(node_id) => Ok(NamingResult::DrainToCluster(node_id)),
+                    None => Ok(NamingResult::NULL),
+                }
+            }
+            NamingCmd::QueryQuarantineInstances(key) => Ok(NamingResult::QuarantineInstances(
+                self.quarantine.query(&key),
+            )),
         }
     }
 }
@@ -1076,7 +2163,7 @@ async fn query_healthy_instances() {
     instance.cluster_name = "DEFUALT".to_owned();
     instance.init();
     let key = instance.get_service_key();
-    naming.update_instance(&key, instance, None);
+    naming.update_instance(&key, instance, None, false);
     if let Some(service) = naming.service_map.get_mut(&key) {
         service.protect_threshold = 0.1;
     }
@@ -1123,6 +2210,7 @@ fn test_add_service() {
         group_name: service_key.group_name.clone(),
         metadata: Default::default(),
         protect_threshold: Some(0.5),
+        selector: None,
     };
     assert!(naming.namespace_index.service_size == 0);
     naming.update_service(service_info);
@@ -1150,6 +2238,7 @@ fn test_remove_has_instance_service() {
         group_name: service_key.group_name.clone(),
         metadata: Default::default(),
         protect_threshold: Some(0.5),
+        selector: None,
     };
     assert!(naming.namespace_index.service_size == 1);
     naming.update_service(service_info);
@@ -1162,3 +2251,300 @@ fn test_remove_has_instance_service() {
     assert!(naming.remove_empty_service(service_key.clone()).is_ok());
     assert!(naming.namespace_index.service_size == 0);
 }
+
+#[test]
+fn test_remove_client_instance() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    let client_id = Arc::new("conn_01".to_owned());
+    let mut service_key = None;
+    for port in [8080, 8081] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.from_grpc = true;
+        instance.client_id = client_id.clone();
+        let key = instance.get_service_key();
+        naming.update_instance(&key, instance, None, false);
+        service_key = Some(key);
+    }
+    let service_key = service_key.unwrap();
+    assert_eq!(naming.get_instance_list(&service_key, "", false).len(), 2);
+    //连接断开时立即注销该连接注册的实例,不等待心跳超时
+    naming.remove_client_instance(&client_id);
+    assert!(naming.get_instance_list(&service_key, "", false).is_empty());
+    assert!(!naming.client_instance_set.contains_key(&client_id));
+}
+
+#[test]
+fn test_move_service() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    let client_id = Arc::new("conn_01".to_owned());
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    let old_key = instance.get_service_key();
+    naming.update_instance(&old_key, instance, None, false);
+    let item = NamingListenerItem {
+        service_key: old_key.clone(),
+        clusters: None,
+    };
+    naming
+        .subscriber
+        .add_subscribe(client_id.clone(), vec![item]);
+    let new_key = ServiceKey::new("public", "gateway", "bar");
+    let (service_info, instances) = naming.move_service(&old_key, &new_key).unwrap();
+    assert!(service_info.is_none());
+    //实例由调用方先写入新服务再删除旧服务
+    assert_eq!(instances.len(), 1);
+    assert_eq!(naming.get_instance_list(&old_key, "", false).len(), 1);
+    let mut instance = instances[0].clone();
+    instance.set_service_key(&new_key);
+    naming.update_instance(&new_key, instance, None, false);
+    let list = naming.get_instance_list(&new_key, "", false);
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id.as_str(), "127.0.0.1#8080#DEFAULT#gateway@@bar");
+    //旧服务保留订阅方以推送空实例列表
+    assert_eq!(naming.subscriber.get_subscribers(&old_key).len(), 1);
+    assert_eq!(naming.subscriber.get_subscribers(&new_key).len(), 1);
+    assert!(naming.move_service(&old_key, &new_key).is_err());
+}
+
+#[test]
+fn test_protection_threshold() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    let mut key = None;
+    for (port, healthy) in [(8080, true), (8081, false)] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.cluster_name = "DEFAULT".to_owned();
+        instance.healthy = healthy;
+        instance.init();
+        let service_key = instance.get_service_key();
+        naming.update_instance(&service_key, instance, None, false);
+        key = Some(service_key);
+    }
+    let key = key.unwrap();
+    let (items, reach) = naming.get_instance_list_with_protection(&key, "", true, None);
+    assert!(!reach);
+    assert_eq!(items.len(), 1);
+    if let Some(service) = naming.service_map.get_mut(&key) {
+        service.protect_threshold = 0.6;
+    }
+    let (items, reach) = naming.get_instance_list_with_protection(&key, "", true, None);
+    assert!(reach);
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().all(|i| i.healthy));
+}
+
+#[test]
+fn test_instance_client_labels() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    let client_id = Arc::new("127.0.0.1:50001".to_owned());
+    let mut labels = HashMap::new();
+    labels.insert("AppName".to_owned(), "foo-app".to_owned());
+    naming
+        .client_labels
+        .insert(client_id.clone(), Arc::new(labels));
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    instance.from_grpc = true;
+    instance.client_id = client_id;
+    instance.init();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance.clone(), None, false);
+    let v = naming
+        .get_instance(&service_key, &instance.get_short_key())
+        .unwrap();
+    let labels = v.client_labels.as_ref().unwrap();
+    assert_eq!(labels.get("AppName").unwrap(), "foo-app");
+}
+
+#[test]
+fn test_stale_instance_write() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    instance.revision = 2;
+    instance.init();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance.clone(), None, false);
+    let mut old_deregister = instance.clone();
+    old_deregister.revision = 1;
+    assert!(naming.is_stale_write(&old_deregister));
+    assert!(!naming.is_stale_write(&instance));
+    old_deregister.revision = 0;
+    assert!(!naming.is_stale_write(&old_deregister));
+
+    //http重新注册(不带版本)时生成更大的版本
+    let mut reregister = instance.clone();
+    reregister.revision = 0;
+    naming.update_instance(&service_key, reregister, None, false);
+    let current = naming
+        .get_instance(&service_key, &instance.get_short_key())
+        .unwrap();
+    assert!(current.revision > 2);
+}
+
+#[test]
+fn test_namespace_stats() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    for (port, healthy, ephemeral) in [(8080, true, true), (8081, false, false)] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.healthy = healthy;
+        instance.ephemeral = ephemeral;
+        instance.init();
+        let service_key = instance.get_service_key();
+        naming.update_instance(&service_key, instance, None, false);
+    }
+    naming.update_service(ServiceDetailDto {
+        namespace_id: Arc::new("dev".to_owned()),
+        service_name: Arc::new("bar".to_owned()),
+        group_name: Arc::new("DEFAULT_GROUP".to_owned()),
+        metadata: Default::default(),
+        protect_threshold: None,
+        selector: None,
+    });
+    let list = naming.get_namespace_stats(None);
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].namespace_id.as_str(), "dev");
+    assert_eq!(list[0].empty_service_count, 1);
+    let public = Arc::new("public".to_owned());
+    let list = naming.get_namespace_stats(Some(&public));
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].instance_count, 2);
+    assert_eq!(list[0].healthy_instance_count, 1);
+    assert_eq!(list[0].persistent_instance_count, 1);
+}
+
+#[test]
+fn test_register_quota() {
+    use super::*;
+    use crate::common::cluster_settings::ClusterSettings;
+    use crate::common::namespace_quota::NamespaceQuota;
+    let mut naming = NamingActor::new();
+    let cluster_settings = ClusterSettingsManager::new(Arc::new(AppSysConfig::init_from_env()));
+    let mut settings = ClusterSettings::default();
+    settings.namespace_quotas.insert(
+        "dev".to_owned(),
+        NamespaceQuota {
+            max_service_count: Some(1),
+            max_instance_per_service: Some(2),
+            ..Default::default()
+        },
+    );
+    cluster_settings.update(settings);
+    naming.cluster_settings = Some(Arc::new(cluster_settings));
+    let new_instance = |service_name: &str, port: u32| {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), port);
+        instance.namespace_id = Arc::new("dev".to_owned());
+        instance.service_name = Arc::new(service_name.to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance
+    };
+    let list = vec![new_instance("foo", 8080), new_instance("foo", 8081)];
+    assert!(naming.check_register_quota(&list).is_ok());
+    for instance in list {
+        naming.update_instance(&instance.get_service_key(), instance, None, false);
+    }
+    //已存在实例的更新不受配额限制
+    assert!(naming
+        .check_register_quota(&[new_instance("foo", 8080)])
+        .is_ok());
+    let err = naming
+        .check_register_quota(&[new_instance("foo", 8082)])
+        .unwrap_err();
+    assert_eq!(
+        QuotaExceededError::error_code(&err),
+        crate::common::namespace_quota::QUOTA_EXCEEDED_ERROR_CODE
+    );
+    assert!(naming
+        .check_register_quota(&[new_instance("bar", 8080)])
+        .is_err());
+}
+
+#[test]
+fn test_subscribe_pattern() {
+    use super::*;
+    let mut naming = NamingActor::new();
+    for service_name in ["order-api", "order-job"] {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.service_name = Arc::new(service_name.to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.init();
+        naming.update_instance(&instance.get_service_key(), instance, None, false);
+    }
+    let client_id = Arc::new("c1".to_owned());
+    let explicit_key = ServiceKey::new("public", "DEFAULT_GROUP", "order-api");
+    let derived_key = ServiceKey::new("public", "DEFAULT_GROUP", "order-job");
+    naming.subscriber.add_subscribe(
+        client_id.clone(),
+        vec![NamingListenerItem {
+            service_key: explicit_key.clone(),
+            clusters: None,
+        }],
+    );
+    let pattern_key = ServiceKey::new("public", "DEFAULT_GROUP", "order-*");
+    naming.subscribe_pattern(
+        PatternSubscribeItem::new(&pattern_key).unwrap(),
+        client_id.clone(),
+    );
+    assert!(naming.subscriber.is_subscribed(&derived_key, &client_id));
+    assert!(!naming
+        .pattern_subscriber
+        .is_derived(&client_id, &explicit_key));
+    //取消模式订阅时保留显式订阅
+    naming.remove_subscribe_pattern(&pattern_key, client_id.clone());
+    assert!(naming.subscriber.is_subscribed(&explicit_key, &client_id));
+    assert!(!naming.subscriber.is_subscribed(&derived_key, &client_id));
+}
+
+#[test]
+fn test_patch_instance_metadata() {
+    use super::*;
+    use crate::naming::metadata_patch::InstanceMetadataPatch;
+    let mut naming = NamingActor::new();
+    let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+    instance.namespace_id = Arc::new("public".to_owned());
+    instance.service_name = Arc::new("foo".to_owned());
+    instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+    let mut metadata = HashMap::new();
+    metadata.insert("a".to_owned(), "1".to_owned());
+    instance.metadata = Arc::new(metadata);
+    instance.init();
+    let service_key = instance.get_service_key();
+    naming.update_instance(&service_key, instance.clone(), None, false);
+    //两个基于同一旧值的局部更新都保留
+    for key in ["b", "c"] {
+        let mut patch = InstanceMetadataPatch::default();
+        patch.upsert.insert(key.to_owned(), "2".to_owned());
+        naming.update_instance(
+            &service_key,
+            instance.clone(),
+            Some(patch.update_tag()),
+            false,
+        );
+    }
+    let v = naming
+        .get_instance(&service_key, &instance.get_short_key())
+        .unwrap();
+    assert_eq!(v.metadata.len(), 3);
+    assert_eq!(v.metadata.get("c").unwrap(), "2");
+}
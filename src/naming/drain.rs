@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use super::model::InstanceKey;
+
+/// 未指定时摘流实例保留的时长
+pub const DEFAULT_DRAIN_GRACE_MILLIS: u64 = 30000;
+
+///
+/// 摘流中的实例;实例先设置为禁用并推送给订阅方,宽限期结束后自动删除
+#[derive(Debug, Default)]
+pub struct InstanceDraining {
+    /// 实例 -> 删除时间
+    records: HashMap<InstanceKey, u64>,
+}
+
+impl InstanceDraining {
+    pub fn add(&mut self, key: InstanceKey, remove_time: u64) {
+        self.records.insert(key, remove_time);
+    }
+
+    ///
+    /// 实例注销后重新注册时取消摘流
+    pub fn remove(&mut self, key: &InstanceKey) {
+        self.records.remove(key);
+    }
+
+    pub fn get_remove_time(&self, key: &InstanceKey) -> Option<u64> {
+        self.records.get(key).cloned()
+    }
+
+    ///
+    /// 返回到期需要删除的实例
+    pub fn timeout(&mut self, now: u64) -> Vec<InstanceKey> {
+        if self.records.is_empty() {
+            return vec![];
+        }
+        let keys: Vec<InstanceKey> = self
+            .records
+            .iter()
+            .filter(|(_, remove_time)| **remove_time <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            self.records.remove(key);
+        }
+        keys
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::model::ServiceKey;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_instance_draining() {
+        let service_key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let a = InstanceKey::new_by_service_key(&service_key, Arc::new("127.0.0.1".to_owned()), 80);
+        let b = InstanceKey::new_by_service_key(&service_key, Arc::new("127.0.0.1".to_owned()), 81);
+        let mut draining = InstanceDraining::default();
+        draining.add(a.clone(), 100);
+        draining.add(b.clone(), 200);
+        assert!(draining.timeout(50).is_empty());
+        assert_eq!(draining.timeout(150), vec![a]);
+        draining.remove(&b);
+        assert!(draining.is_empty());
+    }
+}
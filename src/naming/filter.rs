@@ -11,82 +11,64 @@ impl InstanceFilterUtils {
     pub fn filter_healthy_instances(instances: Vec<Arc<Instance>>) -> Vec<Arc<Instance>> {
         instances.into_iter().filter(|i| i.healthy).collect()
     }
-    pub fn default_instance_filter(
+
+    ///
+    /// 健康实例比例不高于保护阈值时返回全部实例(不健康实例标记为健康),并返回是否触发保护
+    pub fn instance_filter_with_protection(
         all_instances: Vec<Arc<Instance>>,
         metadata: Option<ServiceMetadata>,
         filter_headlthy: bool,
-    ) -> Vec<Arc<Instance>> {
+    ) -> (Vec<Arc<Instance>>, bool) {
         if let Some(metadata) = metadata {
-            let original_total = all_instances.len();
-            // all_instances = from metadata select
-            let mut healthy_count = 0;
-            for item in &all_instances {
-                if item.healthy {
-                    healthy_count += 1;
-                }
-            }
-            let threshold = if metadata.protect_threshold <= 0f32 {
-                0f32
-            } else {
-                metadata.protect_threshold
-            };
-            if (healthy_count as f32) / original_total as f32 <= threshold {
-                let instances = all_instances
-                    .iter()
-                    .map(|i| {
-                        if !i.healthy {
-                            let mut raw = i.as_ref().clone();
-                            raw.healthy = true;
-                            Arc::new(raw)
-                        } else {
-                            i.clone()
-                        }
-                    })
-                    .collect();
-                return instances;
+            if Self::reach_protection_threshold(&all_instances, &metadata) {
+                return (Self::protect_instances(&all_instances), true);
             }
         };
         if filter_headlthy {
-            Self::filter_healthy_instances(all_instances)
+            (Self::filter_healthy_instances(all_instances), false)
         } else {
-            all_instances
+            (all_instances, false)
         }
     }
 
+    fn reach_protection_threshold(
+        all_instances: &[Arc<Instance>],
+        metadata: &ServiceMetadata,
+    ) -> bool {
+        let original_total = all_instances.len();
+        let healthy_count = all_instances.iter().filter(|i| i.healthy).count();
+        let threshold = if metadata.protect_threshold <= 0f32 {
+            0f32
+        } else {
+            metadata.protect_threshold
+        };
+        (healthy_count as f32) / original_total as f32 <= threshold
+    }
+
+    fn protect_instances(all_instances: &[Arc<Instance>]) -> Vec<Arc<Instance>> {
+        all_instances
+            .iter()
+            .map(|i| {
+                if !i.healthy {
+                    let mut raw = i.as_ref().clone();
+                    raw.healthy = true;
+                    Arc::new(raw)
+                } else {
+                    i.clone()
+                }
+            })
+            .collect()
+    }
+
     pub fn default_service_filter(
         mut service_info: ServiceInfo,
         metadata: Option<ServiceMetadata>,
         filter_headlthy: bool,
     ) -> ServiceInfo {
         if let (Some(all_instances), Some(metadata)) = (service_info.hosts.as_ref(), metadata) {
-            let original_total = all_instances.len();
-            // all_instances = from metadata select
-            let mut healthy_count = 0;
-            for item in all_instances {
-                if item.healthy {
-                    healthy_count += 1;
-                }
-            }
-            let threshold = if metadata.protect_threshold <= 0f32 {
-                0f32
-            } else {
-                metadata.protect_threshold
-            };
-            if (healthy_count as f32) / original_total as f32 <= threshold {
+            if Self::reach_protection_threshold(all_instances, &metadata) {
                 service_info.reach_protection_threshold = true;
-                let instances = all_instances
-                    .iter()
-                    .map(|i| {
-                        if !i.healthy {
-                            let mut raw = i.as_ref().clone();
-                            raw.healthy = true;
-                            Arc::new(raw)
-                        } else {
-                            i.clone()
-                        }
-                    })
-                    .collect();
-                service_info.hosts = Some(instances);
+                service_info.hosts = Some(Self::protect_instances(all_instances));
                 return service_info;
             }
         }
@@ -0,0 +1,214 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::InstanceKey;
+
+#[derive(Debug, Clone)]
+pub struct FlappingConfig {
+    /// 统计健康状态变更的滑动窗口时长
+    pub window_millis: u64,
+    /// 窗口内健康状态变更次数达到该值则标记为抖动
+    pub threshold: usize,
+    /// 抖动期间是否抑制实例恢复为健康状态(减少推送)
+    pub damping: bool,
+}
+
+impl Default for FlappingConfig {
+    fn default() -> Self {
+        Self {
+            window_millis: 60000,
+            threshold: 4,
+            damping: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FlappingRecord {
+    transitions: VecDeque<u64>,
+    healthy: bool,
+}
+
+impl FlappingRecord {
+    fn clear_timeout(&mut self, start_time: u64) {
+        while let Some(v) = self.transitions.front() {
+            if *v < start_time {
+                self.transitions.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlappingInstanceInfo {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_name: Arc<String>,
+    pub ip: Arc<String>,
+    pub port: u32,
+    pub healthy: bool,
+    pub transition_count: usize,
+    pub last_transition_time: u64,
+}
+
+///
+/// 实例健康状态抖动检测
+#[derive(Debug, Default)]
+pub struct FlappingDetector {
+    pub config: FlappingConfig,
+    records: HashMap<InstanceKey, FlappingRecord>,
+}
+
+impl FlappingDetector {
+    pub fn new(config: FlappingConfig) -> Self {
+        Self {
+            config,
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn record_transition(&mut self, key: InstanceKey, healthy: bool, now: u64) {
+        let start_time = now.saturating_sub(self.config.window_millis);
+        let record = self.records.entry(key).or_default();
+        record.clear_timeout(start_time);
+        record.transitions.push_back(now);
+        record.healthy = healthy;
+    }
+
+    pub fn is_flapping(&self, key: &InstanceKey, now: u64) -> bool {
+        if let Some(record) = self.records.get(key) {
+            let start_time = now.saturating_sub(self.config.window_millis);
+            let count = record
+                .transitions
+                .iter()
+                .filter(|v| **v >= start_time)
+                .count();
+            count >= self.config.threshold
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// 是否需要抑制实例从不健康恢复为健康
+    pub fn need_damping(&self, key: &InstanceKey, now: u64) -> bool {
+        self.config.damping && self.is_flapping(key, now)
+    }
+
+    pub fn clear_timeout(&mut self, now: u64) {
+        let start_time = now.saturating_sub(self.config.window_millis);
+        self.records.retain(|_, record| {
+            record.clear_timeout(start_time);
+            !record.transitions.is_empty()
+        });
+    }
+
+    pub fn get_flapping_list(
+        &self,
+        namespace_id: Option<&Arc<String>>,
+        now: u64,
+    ) -> Vec<FlappingInstanceInfo> {
+        let mut list = vec![];
+        for (key, record) in &self.records {
+            if let Some(namespace_id) = namespace_id {
+                if namespace_id != &key.namespace_id {
+                    continue;
+                }
+            }
+            if !self.is_flapping(key, now) {
+                continue;
+            }
+            list.push(FlappingInstanceInfo {
+                namespace_id: key.namespace_id.clone(),
+                group_name: key.group_name.clone(),
+                service_name: key.service_name.clone(),
+                ip: key.ip.clone(),
+                port: key.port,
+                healthy: record.healthy,
+                transition_count: record.transitions.len(),
+                last_transition_time: record.transitions.back().cloned().unwrap_or_default(),
+            });
+        }
+        list.sort_by_key(|e| std::cmp::Reverse(e.transition_count));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::model::ServiceKey;
+
+    fn build_key(namespace_id: &str, ip: &str) -> InstanceKey {
+        let service_key = ServiceKey::new(namespace_id, "DEFAULT_GROUP", "foo");
+        InstanceKey::new_by_service_key(&service_key, Arc::new(ip.to_owned()), 8080)
+    }
+
+    fn build_detector(damping: bool) -> FlappingDetector {
+        FlappingDetector::new(FlappingConfig {
+            window_millis: 1000,
+            threshold: 3,
+            damping,
+        })
+    }
+
+    #[test]
+    fn test_threshold_window() {
+        let mut detector = build_detector(false);
+        let key = build_key("public", "127.0.0.1");
+        detector.record_transition(key.clone(), false, 1000);
+        detector.record_transition(key.clone(), true, 1500);
+        assert!(!detector.is_flapping(&key, 1500));
+        detector.record_transition(key.clone(), false, 2000);
+        //窗口起点包含在内,超出窗口的变更不再计数
+        assert!(detector.is_flapping(&key, 2000));
+        assert!(!detector.is_flapping(&key, 2001));
+        //窗口外的旧变更在记录时被清理
+        detector.record_transition(key.clone(), true, 3200);
+        assert!(!detector.is_flapping(&key, 3200));
+        assert!(!detector.is_flapping(&build_key("public", "127.0.0.2"), 3200));
+    }
+
+    #[test]
+    fn test_damping() {
+        let key = build_key("public", "127.0.0.1");
+        let mut detector = build_detector(true);
+        let mut no_damping_detector = build_detector(false);
+        for (i, healthy) in [false, true, false].iter().enumerate() {
+            let now = 1000 + i as u64 * 100;
+            detector.record_transition(key.clone(), *healthy, now);
+            no_damping_detector.record_transition(key.clone(), *healthy, now);
+        }
+        assert!(detector.need_damping(&key, 1200));
+        assert!(no_damping_detector.is_flapping(&key, 1200));
+        assert!(!no_damping_detector.need_damping(&key, 1200));
+    }
+
+    #[test]
+    fn test_recovery() {
+        let mut detector = build_detector(true);
+        let key = build_key("public", "127.0.0.1");
+        let other_key = build_key("dev", "127.0.0.1");
+        for (i, healthy) in [false, true, false, true].iter().enumerate() {
+            detector.record_transition(key.clone(), *healthy, 1000 + i as u64 * 100);
+        }
+        detector.record_transition(other_key.clone(), false, 1300);
+        let list = detector.get_flapping_list(None, 1300);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].transition_count, 4);
+        assert!(list[0].healthy);
+        assert!(detector
+            .get_flapping_list(Some(&Arc::new("dev".to_owned())), 1300)
+            .is_empty());
+        //变更移出窗口后恢复,不再抑制,过期记录被清理
+        assert!(!detector.need_damping(&key, 2201));
+        assert!(detector.get_flapping_list(None, 2201).is_empty());
+        detector.clear_timeout(2301);
+        assert!(detector.records.is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix::prelude::*;
+use bean_factory::{bean, Inject};
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use super::core::{NamingActor, NamingCmd, NamingResult};
+use super::model::{InstanceShortKey, ServiceKey};
+use crate::common::cluster_settings::ClusterSettingsManager;
+
+/// 服务metadata中设置的主动健康检查方式: tcp、http、webhook;未设置时只依赖客户端心跳
+pub const HEALTH_CHECKER_TYPE_KEY: &str = "preserved.health.checker";
+/// http检查的请求路径,默认为 /
+pub const HEALTH_CHECKER_PATH_KEY: &str = "preserved.health.checker.path";
+/// http检查期望的响应码,默认为 200
+pub const HEALTH_CHECKER_EXPECTED_CODE_KEY: &str = "preserved.health.checker.expected.code";
+/// webhook检查使用的集群设置中的webhook名称,默认为 health_check
+pub const HEALTH_CHECKER_WEBHOOK_KEY: &str = "preserved.health.checker.webhook";
+const DEFAULT_HEALTH_CHECK_WEBHOOK: &str = "health_check";
+
+const HEALTH_CHECK_INTERVAL_MILLIS: u64 = 5000;
+const HEALTH_CHECK_TIMEOUT_MILLIS: u64 = 2000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthChecker {
+    Tcp,
+    Http {
+        path: String,
+        expected_code: u16,
+    },
+    /// 由运维提供的http接口探测,用于内置方式不支持的协议
+    Webhook {
+        name: String,
+    },
+}
+
+///
+/// webhook检查的请求内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookCheckRequest<'a> {
+    namespace_id: &'a str,
+    group_name: &'a str,
+    service_name: &'a str,
+    ip: &'a str,
+    port: u32,
+}
+
+///
+/// webhook检查的响应内容;响应体不是该格式时按响应码判断
+#[derive(Debug, Clone, Deserialize)]
+struct WebhookCheckResponse {
+    healthy: bool,
+}
+
+impl HealthChecker {
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        match metadata
+            .get(HEALTH_CHECKER_TYPE_KEY)?
+            .to_lowercase()
+            .as_str()
+        {
+            "tcp" => Some(Self::Tcp),
+            "http" => {
+                let path = metadata
+                    .get(HEALTH_CHECKER_PATH_KEY)
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .unwrap_or("/");
+                let path = if path.starts_with('/') {
+                    path.to_owned()
+                } else {
+                    format!("/{}", path)
+                };
+                let expected_code = metadata
+                    .get(HEALTH_CHECKER_EXPECTED_CODE_KEY)
+                    .and_then(|v| v.parse::<u16>().ok())
+                    .unwrap_or(200);
+                Some(Self::Http {
+                    path,
+                    expected_code,
+                })
+            }
+            "webhook" => {
+                let name = metadata
+                    .get(HEALTH_CHECKER_WEBHOOK_KEY)
+                    .map(|v| v.trim())
+                    .filter(|v| !v.is_empty())
+                    .unwrap_or(DEFAULT_HEALTH_CHECK_WEBHOOK);
+                Some(Self::Webhook {
+                    name: name.to_owned(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    async fn check(
+        &self,
+        client: &reqwest::Client,
+        webhook_url: Option<&str>,
+        service_key: &ServiceKey,
+        ip: &str,
+        port: u32,
+    ) -> bool {
+        let timeout = Duration::from_millis(HEALTH_CHECK_TIMEOUT_MILLIS);
+        match self {
+            Self::Tcp => matches!(
+                tokio::time::timeout(timeout, tokio::net::TcpStream::connect((ip, port as u16)))
+                    .await,
+                Ok(Ok(_))
+            ),
+            Self::Http {
+                path,
+                expected_code,
+            } => {
+                let url = format!("http://{}:{}{}", ip, port, path);
+                match client.get(url).timeout(timeout).send().await {
+                    Ok(resp) => resp.status().as_u16() == *expected_code,
+                    Err(_) => false,
+                }
+            }
+            Self::Webhook { .. } => {
+                let url = match webhook_url {
+                    Some(v) => v,
+                    None => return false,
+                };
+                let request = WebhookCheckRequest {
+                    namespace_id: &service_key.namespace_id,
+                    group_name: &service_key.group_name,
+                    service_name: &service_key.service_name,
+                    ip,
+                    port,
+                };
+                let resp = match client
+                    .post(url)
+                    .timeout(timeout)
+                    .json(&request)
+                    .send()
+                    .await
+                {
+                    Ok(v) => v,
+                    Err(_) => return false,
+                };
+                if !resp.status().is_success() {
+                    return false;
+                }
+                match resp.bytes().await {
+                    Ok(body) => serde_json::from_slice::<WebhookCheckResponse>(&body)
+                        .map(|v| v.healthy)
+                        .unwrap_or(true),
+                    Err(_) => false,
+                }
+            }
+        }
+    }
+}
+
+///
+/// 需要主动检查的服务实例;只检查本节点负责的实例,结果通过实例变更同步到其它节点
+#[derive(Debug, Clone)]
+pub struct HealthCheckTarget {
+    pub service_key: ServiceKey,
+    pub checker: HealthChecker,
+    pub instances: Vec<InstanceShortKey>,
+}
+
+///
+/// 服务端主动健康检查:按服务设置的tcp或http方式探测实例,成功视为一次心跳,失败时标记为不健康
+#[bean(inject)]
+#[derive(Default)]
+pub struct HealthCheckActor {
+    naming_addr: Option<Addr<NamingActor>>,
+    cluster_settings: Option<Arc<ClusterSettingsManager>>,
+    client: reqwest::Client,
+    checking: bool,
+}
+
+impl HealthCheckActor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn check_targets(
+        client: reqwest::Client,
+        cluster_settings: Option<Arc<ClusterSettingsManager>>,
+        targets: Vec<HealthCheckTarget>,
+    ) -> Vec<(ServiceKey, Vec<(InstanceShortKey, bool)>)> {
+        let mut results = Vec::with_capacity(targets.len());
+        for target in targets {
+            let webhook_url = if let HealthChecker::Webhook { name } = &target.checker {
+                //未配置webhook时不检查,避免误判实例不健康
+                match cluster_settings.as_ref().and_then(|v| v.get_webhook(name)) {
+                    Some(url) => Some(url),
+                    None => {
+                        log::warn!("health check webhook {} is not configured", name);
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+            let checks = target.instances.iter().map(|key| {
+                let checker = &target.checker;
+                let client = &client;
+                let webhook_url = webhook_url.as_deref();
+                let service_key = &target.service_key;
+                async move {
+                    checker
+                        .check(client, webhook_url, service_key, &key.ip, key.port)
+                        .await
+                }
+            });
+            let list = join_all(checks).await;
+            results.push((
+                target.service_key,
+                target.instances.into_iter().zip(list).collect(),
+            ));
+        }
+        results
+    }
+
+    fn do_check(&mut self, ctx: &mut Context<Self>) {
+        let naming_addr = match (&self.naming_addr, self.checking) {
+            (Some(v), false) => v.clone(),
+            _ => return,
+        };
+        self.checking = true;
+        let client = self.client.clone();
+        let cluster_settings = self.cluster_settings.clone();
+        async move {
+            if let NamingResult::HealthCheckTargets(targets) = naming_addr
+                .send(NamingCmd::QueryHealthCheckTargets)
+                .await??
+            {
+                for (service_key, list) in
+                    Self::check_targets(client, cluster_settings, targets).await
+                {
+                    naming_addr.do_send(NamingCmd::UpdateHealthCheckResult(service_key, list));
+                }
+            }
+            Ok::<(), anyhow::Error>(())
+        }
+        .into_actor(self)
+        .map(|res, act, _| {
+            act.checking = false;
+            if let Err(err) = res {
+                log::warn!("instance health check error,{}", err);
+            }
+        })
+        .spawn(ctx);
+    }
+}
+
+impl Actor for HealthCheckActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("HealthCheckActor started");
+    }
+}
+
+impl Inject for HealthCheckActor {
+    type Context = Context<Self>;
+
+    fn inject(
+        &mut self,
+        factory_data: bean_factory::FactoryData,
+        _factory: bean_factory::BeanFactory,
+        ctx: &mut Self::Context,
+    ) {
+        self.naming_addr = factory_data.get_actor();
+        self.cluster_settings = factory_data.get_bean();
+        ctx.run_interval(
+            Duration::from_millis(HEALTH_CHECK_INTERVAL_MILLIS),
+            |act, ctx| {
+                act.do_check(ctx);
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_checker_from_metadata() {
+        let mut metadata = HashMap::new();
+        assert_eq!(HealthChecker::from_metadata(&metadata), None);
+        metadata.insert(HEALTH_CHECKER_TYPE_KEY.to_owned(), "TCP".to_owned());
+        assert_eq!(
+            HealthChecker::from_metadata(&metadata),
+            Some(HealthChecker::Tcp)
+        );
+        metadata.insert(HEALTH_CHECKER_TYPE_KEY.to_owned(), "http".to_owned());
+        metadata.insert(HEALTH_CHECKER_PATH_KEY.to_owned(), "health".to_owned());
+        assert_eq!(
+            HealthChecker::from_metadata(&metadata),
+            Some(HealthChecker::Http {
+                path: "/health".to_owned(),
+                expected_code: 200
+            })
+        );
+        metadata.insert(HEALTH_CHECKER_TYPE_KEY.to_owned(), "webhook".to_owned());
+        assert_eq!(
+            HealthChecker::from_metadata(&metadata),
+            Some(HealthChecker::Webhook {
+                name: DEFAULT_HEALTH_CHECK_WEBHOOK.to_owned()
+            })
+        );
+    }
+}
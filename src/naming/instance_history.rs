@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use actix::prelude::*;
+use bean_factory::{bean, Inject};
+use serde::{Deserialize, Serialize};
+
+use crate::now_millis_i64;
+
+use super::core::{NamingActor, NamingCmd, NamingResult};
+use super::model::ServiceKey;
+
+/// 采集间隔
+const HISTORY_COLLECT_INTERVAL_MILLIS: u64 = 60_000;
+/// 保留最近24小时的记录
+const HISTORY_RETENTION_MILLIS: i64 = 24 * 3600 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceCountPoint {
+    pub time: i64,
+    pub instance_count: u64,
+    pub healthy_count: u64,
+    pub healthy_ratio: f32,
+}
+
+impl InstanceCountPoint {
+    pub fn new(time: i64, instance_count: u64, healthy_count: u64) -> Self {
+        let healthy_ratio = if instance_count == 0 {
+            0f32
+        } else {
+            healthy_count as f32 / instance_count as f32
+        };
+        Self {
+            time,
+            instance_count,
+            healthy_count,
+            healthy_ratio,
+        }
+    }
+
+    fn same_count(&self, other: &Self) -> bool {
+        self.instance_count == other.instance_count && self.healthy_count == other.healthy_count
+    }
+}
+
+///
+/// 服务实例数量的时间序列;数量不变时不新增记录,查询时按阶梯曲线展示
+#[derive(Debug, Default)]
+pub struct InstanceCountSeries {
+    points: VecDeque<InstanceCountPoint>,
+}
+
+impl InstanceCountSeries {
+    pub fn add(&mut self, point: InstanceCountPoint) {
+        if let Some(last) = self.points.back() {
+            if last.same_count(&point) {
+                return;
+            }
+        }
+        self.points.push_back(point);
+    }
+
+    ///
+    /// 清除过期记录,保留过期前的最后一条作为查询起点的值
+    pub fn clear_timeout(&mut self, timeout: i64) {
+        while self.points.len() > 1 && self.points[1].time <= timeout {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn is_timeout(&self, timeout: i64) -> bool {
+        match self.points.back() {
+            Some(v) => v.time <= timeout && v.instance_count == 0,
+            None => true,
+        }
+    }
+
+    ///
+    /// 查询时间范围内的记录,包含开始时间前的最后一条
+    pub fn query(&self, start_time: i64, end_time: i64) -> Vec<InstanceCountPoint> {
+        let mut list = vec![];
+        for point in &self.points {
+            if point.time > end_time {
+                break;
+            }
+            if point.time <= start_time {
+                list.clear();
+            }
+            list.push(point.clone());
+        }
+        list
+    }
+}
+
+///
+/// 定时记录各服务的实例数量与健康比例,用于控制台展示最近24小时的变化
+#[bean(inject)]
+#[derive(Default)]
+pub struct InstanceHistoryActor {
+    naming_addr: Option<Addr<NamingActor>>,
+    series_map: HashMap<ServiceKey, InstanceCountSeries>,
+}
+
+impl InstanceHistoryActor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, list: Vec<(ServiceKey, u64, u64)>) {
+        let now = now_millis_i64();
+        let mut keys = Vec::with_capacity(list.len());
+        for (key, instance_count, healthy_count) in list {
+            self.series_map
+                .entry(key.clone())
+                .or_default()
+                .add(InstanceCountPoint::new(now, instance_count, healthy_count));
+            keys.push(key);
+        }
+        //已删除的服务记录为0个实例
+        let keys: HashSet<ServiceKey> = keys.into_iter().collect();
+        let timeout = now - HISTORY_RETENTION_MILLIS;
+        self.series_map.retain(|key, series| {
+            if !keys.contains(key) {
+                series.add(InstanceCountPoint::new(now, 0, 0));
+            }
+            series.clear_timeout(timeout);
+            !series.is_timeout(timeout)
+        });
+    }
+
+    fn collect(&mut self, ctx: &mut Context<Self>) {
+        let naming_addr = match &self.naming_addr {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        async move {
+            if let NamingResult::ServiceInstanceCounts(list) = naming_addr
+                .send(NamingCmd::QueryServiceInstanceCounts)
+                .await??
+            {
+                Ok(list)
+            } else {
+                Err(anyhow::anyhow!(
+                    "QueryServiceInstanceCounts result is error"
+                ))
+            }
+        }
+        .into_actor(self)
+        .map(|res: anyhow::Result<_>, act, _| match res {
+            Ok(list) => act.update(list),
+            Err(err) => log::warn!("collect instance count history error,{}", err),
+        })
+        .wait(ctx);
+    }
+}
+
+impl Actor for InstanceHistoryActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _ctx: &mut Self::Context) {
+        log::info!("InstanceHistoryActor started");
+    }
+}
+
+impl Inject for InstanceHistoryActor {
+    type Context = Context<Self>;
+
+    fn inject(
+        &mut self,
+        factory_data: bean_factory::FactoryData,
+        _factory: bean_factory::BeanFactory,
+        ctx: &mut Self::Context,
+    ) {
+        self.naming_addr = factory_data.get_actor();
+        self.collect(ctx);
+        ctx.run_interval(
+            Duration::from_millis(HISTORY_COLLECT_INTERVAL_MILLIS),
+            |act, ctx| {
+                act.collect(ctx);
+            },
+        );
+    }
+}
+
+#[derive(Message, Debug)]
+#[rtype(result = "anyhow::Result<InstanceHistoryResult>")]
+pub enum InstanceHistoryCmd {
+    /// 服务,开始时间,结束时间
+    Query(ServiceKey, i64, i64),
+}
+
+pub enum InstanceHistoryResult {
+    Points(Vec<InstanceCountPoint>),
+}
+
+impl Handler<InstanceHistoryCmd> for InstanceHistoryActor {
+    type Result = anyhow::Result<InstanceHistoryResult>;
+
+    fn handle(&mut self, msg: InstanceHistoryCmd, _ctx: &mut Self::Context) -> Self::Result {
+        match msg {
+            InstanceHistoryCmd::Query(key, start_time, end_time) => {
+                let list = self
+                    .series_map
+                    .get(&key)
+                    .map(|series| series.query(start_time, end_time))
+                    .unwrap_or_default();
+                Ok(InstanceHistoryResult::Points(list))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_count_series() {
+        let mut series = InstanceCountSeries::default();
+        series.add(InstanceCountPoint::new(100, 2, 2));
+        series.add(InstanceCountPoint::new(200, 2, 2));
+        series.add(InstanceCountPoint::new(300, 3, 2));
+        series.add(InstanceCountPoint::new(400, 1, 1));
+        assert_eq!(series.points.len(), 3);
+        let list = series.query(250, 350);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].time, 100);
+        series.clear_timeout(350);
+        assert_eq!(series.points.len(), 2);
+        assert_eq!(series.points[0].time, 300);
+        assert!(!series.is_timeout(500));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::model::Instance;
+use super::NamingUtils;
+use crate::now_millis;
+
+/// 实例metadata中指定实例id生成方式: simple(默认)、snowflake、uuid
+pub const INSTANCE_ID_GENERATOR_KEY: &str = "preserved.instance.id.generator";
+
+/// 2021-01-01 00:00:00 UTC
+const SNOWFLAKE_EPOCH_MILLIS: u64 = 1609459200000;
+const SNOWFLAKE_WORKER_BITS: u64 = 10;
+const SNOWFLAKE_SEQUENCE_BITS: u64 = 12;
+const SNOWFLAKE_SEQUENCE_MASK: u64 = (1 << SNOWFLAKE_SEQUENCE_BITS) - 1;
+
+lazy_static::lazy_static! {
+    static ref SNOWFLAKE_WORKER: Mutex<SnowflakeWorker> = Mutex::new(SnowflakeWorker::new(
+        std::env::var("RNACOS_RAFT_NODE_ID")
+            .unwrap_or_default()
+            .parse()
+            .unwrap_or(1)
+    ));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstanceIdGenerator {
+    /// ip#port#cluster#group@@service
+    #[default]
+    Simple,
+    /// 雪花算法生成的数字id#cluster#group@@service
+    Snowflake,
+    Uuid,
+}
+
+impl InstanceIdGenerator {
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Self {
+        match metadata
+            .get(INSTANCE_ID_GENERATOR_KEY)
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            Some("snowflake") => Self::Snowflake,
+            Some("uuid") => Self::Uuid,
+            _ => Self::Simple,
+        }
+    }
+
+    ///
+    /// 生成的id是否与实例ip、端口无关;此类id在实例更新时需要沿用首次注册的值
+    pub fn is_random(&self) -> bool {
+        !matches!(self, Self::Simple)
+    }
+
+    pub fn generate(&self, instance: &Instance) -> String {
+        match self {
+            Self::Simple => Self::simple_id(instance),
+            Self::Snowflake => {
+                let id = SNOWFLAKE_WORKER
+                    .lock()
+                    .map(|mut worker| worker.next_id())
+                    .unwrap_or_default();
+                format!("{}#{}", id, Self::cluster_service_suffix(instance))
+            }
+            Self::Uuid => uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn simple_id(instance: &Instance) -> String {
+        format!(
+            "{}#{}#{}",
+            &instance.ip,
+            instance.port,
+            Self::cluster_service_suffix(instance)
+        )
+    }
+
+    fn cluster_service_suffix(instance: &Instance) -> String {
+        format!(
+            "{}#{}",
+            NamingUtils::default_cluster(instance.cluster_name.clone()),
+            NamingUtils::get_group_and_service_name(
+                &instance.service_name,
+                &NamingUtils::default_group(instance.group_name.as_ref().to_owned()),
+            )
+        )
+    }
+}
+
+struct SnowflakeWorker {
+    worker_id: u64,
+    last_timestamp: u64,
+    sequence: u64,
+}
+
+impl SnowflakeWorker {
+    fn new(worker_id: u64) -> Self {
+        Self {
+            worker_id: worker_id & ((1 << SNOWFLAKE_WORKER_BITS) - 1),
+            last_timestamp: 0,
+            sequence: 0,
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        //时钟回拨时沿用上次的时间,保证id递增
+        let mut timestamp = now_millis().max(self.last_timestamp);
+        if timestamp == self.last_timestamp {
+            self.sequence = (self.sequence + 1) & SNOWFLAKE_SEQUENCE_MASK;
+            if self.sequence == 0 {
+                timestamp += 1;
+            }
+        } else {
+            self.sequence = 0;
+        }
+        self.last_timestamp = timestamp;
+        ((timestamp - SNOWFLAKE_EPOCH_MILLIS) << (SNOWFLAKE_WORKER_BITS + SNOWFLAKE_SEQUENCE_BITS))
+            | (self.worker_id << SNOWFLAKE_SEQUENCE_BITS)
+            | self.sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_instance_id_generator() {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        let generator = InstanceIdGenerator::from_metadata(&instance.metadata);
+        assert_eq!(
+            generator.generate(&instance),
+            "127.0.0.1#8080#DEFAULT#DEFAULT_GROUP@@foo"
+        );
+        let mut metadata = HashMap::new();
+        metadata.insert(INSTANCE_ID_GENERATOR_KEY.to_owned(), "Snowflake".to_owned());
+        let generator = InstanceIdGenerator::from_metadata(&metadata);
+        assert_eq!(generator, InstanceIdGenerator::Snowflake);
+        let a = generator.generate(&instance);
+        let b = generator.generate(&instance);
+        assert_ne!(a, b);
+        assert!(a.ends_with("#DEFAULT#DEFAULT_GROUP@@foo"));
+        let mut worker = SnowflakeWorker::new(1);
+        let id = worker.next_id();
+        assert!(worker.next_id() > id);
+    }
+}
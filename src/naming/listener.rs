@@ -18,12 +18,30 @@ use tokio::net::UdpSocket;
 
 use actix::prelude::*;
 
+use crate::common::dead_letter::{DeadLetter, DeadLetterCmd, DeadLetterManager};
 use crate::{now_millis, TimeoutSet};
 
 use super::core::{NamingActor, NamingCmd};
 use super::model::{Instance, ServiceKey};
 use super::udp_actor::{UdpSenderCmd, UdpWorker};
 
+/// udp推送的心跳周期,超过两个周期没有查询或ack的监听会被清理
+pub const NAMING_UDP_PUSH_PERIOD: u64 = 10000;
+/// udp推送等待客户端ack的超时时间
+const PUSH_ACK_TIMEOUT_MILLIS: u64 = 10000;
+/// udp推送没有收到ack时的最大重试次数
+const PUSH_MAX_RETRY: u32 = 1;
+
+/// 一次推送的结果:(过期移除的监听地址, 已发送的(监听地址, 推送内容))
+type NotifyResult = (Vec<SocketAddr>, Vec<(SocketAddr, Arc<Vec<u8>>)>);
+
+struct PendingPush {
+    service_key: ServiceKey,
+    data: Arc<Vec<u8>>,
+    send_time: u64,
+    retry: u32,
+}
+
 #[derive(Debug)]
 pub struct ListenerItem {
     pub clusters: Vec<String>,
@@ -118,6 +136,7 @@ impl ListenerValue {
         service_key: &ServiceKey,
         instances: &HashMap<String, Vec<Arc<Instance>>>,
         item: &ListenerItem,
+        last_ref_time: u64,
     ) -> Vec<u8> {
         let mut cluster_names = vec![];
         if item.clusters.is_empty() {
@@ -129,15 +148,17 @@ impl ListenerValue {
                 cluster_names.push(key.to_owned());
             }
         }
-        let mut response = HashMap::new();
-        response.insert("type", "dom".to_owned());
         let res = Self::get_instance_list_string(
             service_key,
             cluster_names,
             item.only_healthy,
             instances,
         );
-        response.insert("data", res);
+        let response = serde_json::json!({
+            "type": "dom",
+            "data": res,
+            "lastRefTime": last_ref_time,
+        });
         let msg_str = serde_json::to_string(&response).unwrap_or_default();
         gz_encode(msg_str.as_bytes(), 1024)
     }
@@ -147,12 +168,12 @@ impl ListenerValue {
         service_key: &ServiceKey,
         sign: String,
         instances: &HashMap<String, Vec<Arc<Instance>>>,
-        period: u64,
+        last_ref_time: u64,
     ) -> HashMap<String, Arc<Vec<u8>>> {
         let mut cache = HashMap::new();
         for item in self.items.values() {
             if !cache.contains_key(&item.clusters_key) {
-                let msg = Self::build_msg(service_key, instances, item);
+                let msg = Self::build_msg(service_key, instances, item, last_ref_time);
                 cache.insert(item.clusters_key.to_owned(), Arc::new(msg));
             }
         }
@@ -165,8 +186,9 @@ impl ListenerValue {
         sign: String,
         instances: &HashMap<String, Vec<Arc<Instance>>>,
         period: u64,
+        last_ref_time: u64,
         sender: &Addr<UdpWorker>,
-    ) -> Vec<SocketAddr> {
+    ) -> NotifyResult {
         let now = now_millis();
         let remove_time = now - max(2 * period, 10);
         let mut removes = vec![];
@@ -179,15 +201,17 @@ impl ListenerValue {
         for key in &removes {
             self.items.remove(key);
         }
-        let cache = self.build_cache(&service_key, sign, instances, period);
+        let cache = self.build_cache(&service_key, sign, instances, last_ref_time);
+        let mut sent = vec![];
         for item in self.items.values_mut() {
             if let Some(data) = cache.get(&item.clusters_key) {
                 let msg = UdpSenderCmd::new(data.clone(), item.listener_addr.to_owned());
                 sender.do_send(msg);
                 item.last_modified = now;
+                sent.push((item.listener_addr.to_owned(), data.clone()));
             }
         }
-        removes
+        (removes, sent)
     }
 }
 
@@ -201,6 +225,10 @@ pub struct InnerNamingListener {
     sender: Addr<UdpWorker>,
     naming_addr: Option<Addr<NamingActor>>,
     listener_id: u64,
+    /// (client addr,lastRefTime) -> 等待ack的推送
+    pending_acks: HashMap<(SocketAddr, u64), PendingPush>,
+    last_ref_time: u64,
+    dead_letter_manager: Option<Addr<DeadLetterManager>>,
 }
 
 impl InnerNamingListener {
@@ -217,6 +245,9 @@ impl InnerNamingListener {
             sender,
             naming_addr,
             listener_id: 0,
+            pending_acks: Default::default(),
+            last_ref_time: 0,
+            dead_letter_manager: None,
         }
     }
 
@@ -265,8 +296,18 @@ impl InnerNamingListener {
         self.update_client_map(addr, listener_key);
     }
 
+    ///
+    /// 推送标识,客户端ack时原样返回
+    fn next_ref_time(&mut self) -> u64 {
+        self.last_ref_time = max(now_millis() * 1_000_000, self.last_ref_time + 1);
+        self.last_ref_time
+    }
+
     // 响应
-    fn client_response(&mut self, addr: &SocketAddr) {
+    fn client_response(&mut self, addr: &SocketAddr, last_ref_time: Option<u64>) {
+        if let Some(last_ref_time) = last_ref_time {
+            self.pending_acks.remove(&(addr.to_owned(), last_ref_time));
+        }
         if let Some(listener_keys) = self.client_to_listener_map.get(addr) {
             let now = now_millis();
             for key in listener_keys {
@@ -287,8 +328,29 @@ impl InnerNamingListener {
         let listener_key = Self::get_listener_key(&service_key);
         let mut is_empty = false;
         let mut clients = vec![];
+        let last_ref_time = self.next_ref_time();
         if let Some(value) = self.listeners.get_mut(&listener_key) {
-            clients = value.notify(service_key, sign, &instances, self.period, &self.sender);
+            let (removes, sent) = value.notify(
+                service_key.clone(),
+                sign,
+                &instances,
+                self.period,
+                last_ref_time,
+                &self.sender,
+            );
+            clients = removes;
+            let now = now_millis();
+            for (addr, data) in sent {
+                self.pending_acks.insert(
+                    (addr, last_ref_time),
+                    PendingPush {
+                        service_key: service_key.clone(),
+                        data,
+                        send_time: now,
+                        retry: 0,
+                    },
+                );
+            }
             if value.is_empty() {
                 is_empty = true;
             }
@@ -308,6 +370,48 @@ impl InnerNamingListener {
         }
     }
 
+    ///
+    /// 服务变更后立即推送,不等待定时心跳
+    fn changed(&mut self, service_key: ServiceKey) {
+        let listener_key = Self::get_listener_key(&service_key);
+        if let (Some(value), Some(naming_addr)) =
+            (self.listeners.get(&listener_key), self.naming_addr.as_ref())
+        {
+            naming_addr.do_send(NamingCmd::NotifyListener(service_key, value.id));
+        }
+    }
+
+    ///
+    /// 超时未ack的推送重发,超过重试次数后写入死信列表
+    fn check_pending_acks(&mut self, now: u64) {
+        let mut removes = vec![];
+        for (key, push) in self.pending_acks.iter_mut() {
+            if now < push.send_time + PUSH_ACK_TIMEOUT_MILLIS {
+                continue;
+            }
+            if push.retry >= PUSH_MAX_RETRY {
+                log::warn!("udp push ack timeout,addr:{},lastRefTime:{}", &key.0, key.1);
+                if let Some(dead_letter_manager) = &self.dead_letter_manager {
+                    let letter = DeadLetter::new_udp_push(
+                        &push.service_key,
+                        &key.0.to_string(),
+                        "udp push ack timeout".to_owned(),
+                    );
+                    dead_letter_manager.do_send(DeadLetterCmd::Add(letter));
+                }
+                removes.push(key.to_owned());
+                continue;
+            }
+            push.retry += 1;
+            push.send_time = now;
+            self.sender
+                .do_send(UdpSenderCmd::new(push.data.clone(), key.0.to_owned()));
+        }
+        for key in &removes {
+            self.pending_acks.remove(key);
+        }
+    }
+
     fn add_hb(&mut self, service_key: ServiceKey, id: u64) {
         let listener_key = Self::get_listener_key(&service_key);
         if let Some(value) = self.listeners.get(&listener_key) {
@@ -323,6 +427,7 @@ impl InnerNamingListener {
         ctx.run_later(Duration::new(1, 0), |act, ctx| {
             let current_time = now_millis();
             let addr = ctx.address();
+            act.check_pending_acks(current_time);
             let keys = act.timeout_set.timeout(current_time);
             if let Some(naming_addr) = act.naming_addr.as_ref() {
                 for (key, id) in keys {
@@ -364,6 +469,7 @@ impl Inject for InnerNamingListener {
         ctx: &mut Self::Context,
     ) {
         self.naming_addr = factory_data.get_actor();
+        self.dead_letter_manager = factory_data.get_actor();
         log::info!(" InnerNamingListener inject complete");
     }
 }
@@ -378,7 +484,9 @@ impl Supervised for InnerNamingListener {
 #[rtype(result = "Result<(),std::io::Error>")]
 pub enum NamingListenerCmd {
     Add(ServiceKey, ListenerItem),
-    Response(SocketAddr),
+    /// 客户端地址,ack中的lastRefTime
+    Response(SocketAddr, Option<u64>),
+    Changed(ServiceKey),
     Notify(ServiceKey, String, HashMap<String, Vec<Arc<Instance>>>, u64),
     AddHeartbeat(ServiceKey, u64),
 }
@@ -396,9 +504,12 @@ impl Handler<NamingListenerCmd> for InnerNamingListener {
                 );
                 self.add(service_key, listener_item);
             }
-            NamingListenerCmd::Response(socket_addr) => {
-                log::info!("naming-listener response,{:?}", &socket_addr);
-                self.client_response(&socket_addr);
+            NamingListenerCmd::Response(socket_addr, last_ref_time) => {
+                log::debug!("naming-listener response,{:?}", &socket_addr);
+                self.client_response(&socket_addr, last_ref_time);
+            }
+            NamingListenerCmd::Changed(service_key) => {
+                self.changed(service_key);
             }
             NamingListenerCmd::Notify(service_key, sign, instances, id) => {
                 log::info!("naming-listener notify,{:?},{}", &service_key, id);
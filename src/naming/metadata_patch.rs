@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::now_millis_i64;
+
+use super::core::{NamingCmd, NamingResult};
+use super::model::{Instance, InstanceUpdateTag};
+use super::persistent::PersistentInstanceUtils;
+
+///
+/// 实例metadata局部更新:先删除remove_keys,再写入upsert
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataPatch {
+    #[serde(default)]
+    pub upsert: HashMap<String, String>,
+    #[serde(default)]
+    pub remove_keys: Vec<String>,
+}
+
+impl InstanceMetadataPatch {
+    pub fn is_empty(&self) -> bool {
+        self.upsert.is_empty() && self.remove_keys.is_empty()
+    }
+
+    pub fn apply(&self, metadata: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut metadata = metadata.clone();
+        for key in &self.remove_keys {
+            metadata.remove(key);
+        }
+        for (key, value) in &self.upsert {
+            metadata.insert(key.to_owned(), value.to_owned());
+        }
+        metadata
+    }
+
+    ///
+    /// 只更新metadata,其它字段保持原值;metadata在写入时基于最新值合并
+    pub fn update_tag(self) -> InstanceUpdateTag {
+        InstanceUpdateTag {
+            weight: false,
+            metadata: true,
+            enabled: false,
+            ephemeral: false,
+            from_update: true,
+            metadata_patch: Some(Arc::new(self)),
+        }
+    }
+
+    ///
+    /// 按实例地址(namespace,group,service,ip,port)合并metadata,返回预期的metadata;
+    /// 临时实例由负责的节点在NamingActor中合并,持久化实例在写入前基于最新值合并
+    pub async fn patch_instance(
+        self,
+        app: &AppShareData,
+        instance: Instance,
+    ) -> anyhow::Result<Arc<HashMap<String, String>>> {
+        let old = match app.naming_addr.send(NamingCmd::Query(instance)).await?? {
+            NamingResult::Instance(v) => v,
+            _ => return Err(anyhow::anyhow!("instance not found")),
+        };
+        let metadata = Arc::new(self.apply(&old.metadata));
+        if self.is_empty() {
+            return Ok(metadata);
+        }
+        let mut instance = old.as_ref().clone();
+        instance.metadata = metadata.clone();
+        instance.last_modified_millis = now_millis_i64();
+        let tag = self.update_tag();
+        if !instance.ephemeral {
+            PersistentInstanceUtils::update_instance(app, instance, Some(tag)).await?;
+        } else {
+            app.naming_route
+                .update_instance(instance, Some(tag))
+                .await?;
+        }
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_patch() {
+        let mut metadata = HashMap::new();
+        metadata.insert("a".to_owned(), "1".to_owned());
+        metadata.insert("b".to_owned(), "2".to_owned());
+        let mut patch = InstanceMetadataPatch::default();
+        patch.upsert.insert("b".to_owned(), "3".to_owned());
+        patch.upsert.insert("c".to_owned(), "4".to_owned());
+        patch.remove_keys.push("a".to_owned());
+        let v = patch.apply(&metadata);
+        assert_eq!(v.len(), 2);
+        assert_eq!(v.get("b").unwrap(), "3");
+        assert_eq!(v.get("c").unwrap(), "4");
+    }
+}
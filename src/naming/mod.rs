@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
 pub mod api_model;
+pub mod change_history;
 pub mod core;
 pub(crate) mod filter;
+pub mod flapping;
 pub mod listener;
 pub mod model;
 pub mod naming_delay_nofity;
@@ -10,15 +12,35 @@ pub mod naming_subscriber;
 pub mod service;
 pub mod udp_actor;
 //pub(crate) mod dal;
+pub mod backup;
 pub mod cluster;
+pub mod consistent_hash;
+pub mod drain;
+pub mod health_check;
+pub mod instance_history;
+pub mod instance_id;
+pub mod metadata_patch;
 pub mod metrics;
 pub mod ops;
+pub mod pattern_subscriber;
+pub mod persistent;
+pub mod push_tracker;
+pub mod quarantine;
+pub mod selector;
+pub mod service_export;
 pub mod service_index;
+pub mod shuffle;
+pub mod srv;
+pub mod validate;
+pub mod warmup;
+pub mod zone;
 
 pub struct NamingUtils;
 
 pub const RESPONSE_CODE_KEY: &str = "code";
 pub const RESPONSE_CODE_OK: i32 = 10200;
+/// 心跳的实例不存在,客户端需要重新注册
+pub const RESPONSE_CODE_RESOURCE_NOT_FOUND: i32 = 20404;
 pub const CLIENT_BEAT_INTERVAL_KEY: &str = "clientBeatInterval";
 pub const LIGHT_BEAT_ENABLED_KEY: &str = "lightBeatEnabled";
 
@@ -27,6 +49,16 @@ pub const DEFAULT_CLUSTER: &str = "DEFAULT";
 pub const DEFAULT_GROUP: &str = "DEFAULT_GROUP";
 
 impl NamingUtils {
+    ///
+    /// 客户端心跳间隔,实例metadata中有设置时以实例设置为准
+    pub fn get_client_beat_interval(metadata: &HashMap<String, String>, default_value: i64) -> i64 {
+        metadata
+            .get(service::HEARTBEAT_INTERVAL_KEY)
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default_value)
+    }
+
     pub fn get_group_and_service_name(service_name: &str, group_name: &str) -> String {
         format!("{}@@{}", group_name, service_name)
     }
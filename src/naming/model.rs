@@ -2,6 +2,10 @@ use std::{collections::HashMap, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 
+use super::instance_id::InstanceIdGenerator;
+use super::metadata_patch::InstanceMetadataPatch;
+use super::selector::ServiceSelector;
+use super::NamingUtils;
 use crate::now_millis_i64;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +30,15 @@ pub struct Instance {
     //本节点管理的实例设置为0
     pub from_cluster: u64,
     pub client_id: Arc<String>,
+    /// 注册实例的grpc连接标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_labels: Option<Arc<HashMap<String, String>>>,
+    /// 注册版本,同一实例重新注册时递增;注销请求带的版本小于当前版本时不删除实例
+    #[serde(default)]
+    pub revision: u64,
+    /// 首次注册的时间,由服务端设置,实例存续期间保持不变
+    #[serde(default)]
+    pub register_time: u64,
 }
 
 impl Instance {
@@ -42,18 +55,27 @@ impl Instance {
     }
 
     pub fn is_enable_timeout(&self) -> bool {
-        //grpc 不走过期检查
-        !self.from_grpc && !self.is_from_cluster()
+        //grpc与持久化实例不走过期检查
+        self.ephemeral && !self.from_grpc && !self.is_from_cluster()
     }
 
+    ///
+    /// 按metadata中的preserved.instance.id.generator生成实例id
     pub fn generate_key(&mut self) {
-        //self.id = format!("{}#{}#{}#{}#{}",&self.ip,&self.port,&self.cluster_name,&self.service_name,&self.group_name)
-        self.id = Arc::new(format!("{}#{}", &self.ip, &self.port))
+        self.id = Arc::new(InstanceIdGenerator::from_metadata(&self.metadata).generate(self))
+    }
+
+    ///
+    /// 默认生成方式每次按当前属性重新生成;随机生成方式只在未生成过时生成
+    pub fn need_generate_key(&self) -> bool {
+        self.id.is_empty()
+            || !InstanceIdGenerator::from_metadata(&self.metadata).is_random()
+            || self.id.as_str() == InstanceIdGenerator::simple_id(self)
     }
 
     pub fn init(&mut self) {
         self.last_modified_millis = now_millis_i64();
-        if self.id.len() == 0 {
+        if self.need_generate_key() {
             self.generate_key();
         }
     }
@@ -77,6 +99,18 @@ impl Instance {
             || self.metadata != o.metadata
     }
 
+    ///
+    /// 迁移到其它服务,默认生成方式的实例id在init时按新服务重新生成
+    pub fn set_service_key(&mut self, key: &ServiceKey) {
+        self.namespace_id = key.namespace_id.clone();
+        self.group_name = key.group_name.clone();
+        self.service_name = key.service_name.clone();
+        self.group_service = Arc::new(NamingUtils::get_group_and_service_name(
+            key.service_name.as_ref(),
+            key.group_name.as_ref(),
+        ));
+    }
+
     pub fn get_service_key(&self) -> ServiceKey {
         //ServiceKey::new(&self.namespace_id,&self.group_name,&self.service_name)
         ServiceKey::new_by_arc(
@@ -129,6 +163,9 @@ impl Default for Instance {
             from_grpc: false,
             from_cluster: 0,
             client_id: Default::default(),
+            client_labels: None,
+            revision: 0,
+            register_time: 0,
         }
     }
 }
@@ -157,6 +194,9 @@ pub struct ServiceDetailDto {
     pub group_name: Arc<String>,
     pub metadata: Option<Arc<HashMap<String, String>>>,
     pub protect_threshold: Option<f32>,
+    /// 为空时不修改选择器
+    #[serde(default)]
+    pub selector: Option<ServiceSelector>,
 }
 
 impl ServiceDetailDto {
@@ -169,6 +209,45 @@ impl ServiceDetailDto {
     }
 }
 
+///
+/// 命名空间下的服务与实例统计
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceNamingStats {
+    pub namespace_id: Arc<String>,
+    pub service_count: u64,
+    /// 没有实例的服务数量
+    pub empty_service_count: u64,
+    pub instance_count: u64,
+    pub healthy_instance_count: u64,
+    pub unhealthy_instance_count: u64,
+    pub ephemeral_instance_count: u64,
+    pub persistent_instance_count: u64,
+}
+
+impl NamespaceNamingStats {
+    pub fn new(namespace_id: Arc<String>) -> Self {
+        Self {
+            namespace_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_instance(&mut self, instance: &Instance) {
+        self.instance_count += 1;
+        if instance.healthy {
+            self.healthy_instance_count += 1;
+        } else {
+            self.unhealthy_instance_count += 1;
+        }
+        if instance.ephemeral {
+            self.ephemeral_instance_count += 1;
+        } else {
+            self.persistent_instance_count += 1;
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InstanceUpdateTag {
     pub weight: bool,
@@ -176,6 +255,9 @@ pub struct InstanceUpdateTag {
     pub enabled: bool,
     pub ephemeral: bool,
     pub from_update: bool,
+    /// metadata局部更新,由实例所在节点基于当前metadata合并,避免并发局部更新互相覆盖
+    #[serde(default)]
+    pub metadata_patch: Option<Arc<InstanceMetadataPatch>>,
 }
 
 impl InstanceUpdateTag {
@@ -195,6 +277,7 @@ impl Default for InstanceUpdateTag {
             enabled: true,
             ephemeral: true,
             from_update: false,
+            metadata_patch: None,
         }
     }
 }
@@ -6,7 +6,10 @@ use actix::prelude::*;
 use bean_factory::{bean, Inject};
 
 use crate::{
-    common::delay_notify::{DelayNotify, NotifyEvent},
+    common::{
+        delay_notify::{DelayNotify, NotifyEvent},
+        AppSysConfig,
+    },
     grpc::bistream_manage::{BiStreamManage, BiStreamManageCmd},
     now_millis,
 };
@@ -52,6 +55,7 @@ pub struct DelayNotifyActor {
     conn_manage: Option<Addr<BiStreamManage>>,
     naming_addr: Option<Addr<NamingActor>>,
     delay: u64,
+    max_delay: u64,
 }
 
 impl Default for DelayNotifyActor {
@@ -67,11 +71,13 @@ impl DelayNotifyActor {
             conn_manage: None,
             naming_addr: None,
             delay: 500,
+            max_delay: 3000,
         }
     }
 
     pub fn notify_heartbeat(&self, ctx: &mut actix::Context<Self>) {
-        ctx.run_later(Duration::from_millis(500), |act, ctx| {
+        let interval = self.delay.clamp(50, 500);
+        ctx.run_later(Duration::from_millis(interval), |act, ctx| {
             let events = act.inner_delay_notify.timeout().unwrap_or_default();
             let naming_addr = act.naming_addr.clone();
             async move {
@@ -92,7 +98,7 @@ impl DelayNotifyActor {
         if let Some(naming_addr) = naming_addr {
             for mut event in events {
                 //println!("fill_event_data_and_notify, {:?}",&event.key);
-                let cmd = NamingCmd::QueryServiceInfo(event.key.clone(), "".to_owned(), true);
+                let cmd = NamingCmd::QueryServiceInfo(event.key.clone(), "".to_owned(), true, None);
                 match naming_addr.send(cmd).await {
                     Ok(res) => {
                         if let Ok(NamingResult::ServiceInfo(service_info)) = res {
@@ -131,6 +137,11 @@ impl Inject for DelayNotifyActor {
     ) {
         self.conn_manage = factory_data.get_actor();
         self.naming_addr = factory_data.get_actor();
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = sys_config {
+            self.delay = sys_config.naming_push_debounce_millis;
+            self.max_delay = sys_config.naming_push_max_delay_millis;
+        }
         log::info!(" DelayNotifyActor inject complete");
     }
 }
@@ -163,8 +174,12 @@ impl Handler<DelayNotifyCmd> for DelayNotifyActor {
                     service_info: None,
                     conn_manage: self.conn_manage.to_owned(),
                 };
-                self.inner_delay_notify
-                    .add_event(self.delay, event.key.clone(), event)?;
+                self.inner_delay_notify.add_debounce_event(
+                    self.delay,
+                    self.max_delay,
+                    event.key.clone(),
+                    event,
+                )?;
             }
         }
         Ok(DelayNotifyResult::None)
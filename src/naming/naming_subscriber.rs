@@ -6,6 +6,7 @@ use std::{
 };
 
 use actix::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use super::{
     model::{Instance, ServiceInfo, ServiceKey},
@@ -38,10 +39,34 @@ pub struct NamingListenerItem {
     pub clusters: Option<HashSet<String>>,
 }
 
+///
+/// 订阅方客户端身份
+#[derive(Debug, Clone, Default)]
+pub struct SubscriberClient {
+    pub client_ip: String,
+    pub agent: String,
+    pub app_name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriberInfo {
+    pub client_id: Arc<String>,
+    pub client_ip: String,
+    /// 1.x udp推送时的端口
+    pub port: u16,
+    pub agent: String,
+    pub app_name: String,
+    pub clusters: Vec<String>,
+    /// grpc,udp
+    pub protocol: String,
+}
+
 #[derive(Default)]
 pub struct Subscriber {
     listener: HashMap<ServiceKey, HashMap<Arc<String>, Option<HashSet<String>>>>,
     client_keys: HashMap<Arc<String>, HashSet<ServiceKey>>,
+    client_infos: HashMap<Arc<String>, Arc<SubscriberClient>>,
     notify_addr: Option<Addr<DelayNotifyActor>>,
 }
 
@@ -50,6 +75,7 @@ impl Subscriber {
         Self {
             listener: Default::default(),
             client_keys: Default::default(),
+            client_infos: Default::default(),
             notify_addr: Default::default(),
         }
     }
@@ -58,6 +84,39 @@ impl Subscriber {
         self.notify_addr = Some(notify_addr);
     }
 
+    pub fn set_client_info(&mut self, client_id: Arc<String>, client: Arc<SubscriberClient>) {
+        self.client_infos.insert(client_id, client);
+    }
+
+    pub fn get_subscribers(&self, key: &ServiceKey) -> Vec<SubscriberInfo> {
+        let mut list = vec![];
+        if let Some(map) = self.listener.get(key) {
+            for (client_id, clusters) in map {
+                let client = self
+                    .client_infos
+                    .get(client_id)
+                    .cloned()
+                    .unwrap_or_default();
+                let mut clusters: Vec<String> = clusters
+                    .as_ref()
+                    .map(|v| v.iter().cloned().collect())
+                    .unwrap_or_default();
+                clusters.sort();
+                list.push(SubscriberInfo {
+                    client_id: client_id.clone(),
+                    client_ip: client.client_ip.clone(),
+                    port: 0,
+                    agent: client.agent.clone(),
+                    app_name: client.app_name.clone(),
+                    clusters,
+                    protocol: "grpc".to_owned(),
+                });
+            }
+        }
+        list.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        list
+    }
+
     pub fn add_subscribe(&mut self, client_id: Arc<String>, items: Vec<NamingListenerItem>) {
         match self.client_keys.get_mut(&client_id) {
             Some(set) => {
@@ -112,10 +171,12 @@ impl Subscriber {
         };
         if remove_empty_client {
             self.client_keys.remove(&client_id);
+            self.client_infos.remove(&client_id);
         }
     }
 
     pub fn remove_client_subscribe(&mut self, client_id: Arc<String>) {
+        self.client_infos.remove(&client_id);
         if let Some(set) = self.client_keys.remove(&client_id) {
             let mut remove_keys = vec![];
             for key in set {
@@ -145,10 +206,28 @@ impl Subscriber {
             }
             for key in &remove_keys {
                 self.client_keys.remove(key);
+                self.client_infos.remove(key);
             }
         }
     }
 
+    pub fn is_subscribed(&self, key: &ServiceKey, client_id: &Arc<String>) -> bool {
+        self.listener
+            .get(key)
+            .map(|set| set.contains_key(client_id))
+            .unwrap_or(false)
+    }
+
+    ///
+    /// 只通知指定的订阅方
+    pub fn notify_client(&self, key: ServiceKey, client_id: Arc<String>) {
+        if let Some(notify_addr) = &self.notify_addr {
+            let mut client_id_set = HashSet::new();
+            client_id_set.insert(client_id);
+            notify_addr.do_send(DelayNotifyCmd::Notify(key, client_id_set));
+        }
+    }
+
     pub fn notify(&self, key: ServiceKey) {
         //log::info!("naming_subscriber notify {:?}",&key);
         if let Some(notify_addr) = &self.notify_addr {
@@ -162,6 +241,28 @@ impl Subscriber {
         }
     }
 
+    ///
+    /// 按订阅时指定的集群过滤条件分组,已取消订阅的客户端不返回
+    pub fn group_by_clusters(
+        &self,
+        key: &ServiceKey,
+        client_id_set: HashSet<Arc<String>>,
+    ) -> HashMap<String, HashSet<Arc<String>>> {
+        let mut result: HashMap<String, HashSet<Arc<String>>> = HashMap::new();
+        let set = match self.listener.get(key) {
+            Some(v) => v,
+            None => return result,
+        };
+        for client_id in client_id_set {
+            if let Some(clusters) = set.get(&client_id) {
+                let mut names: Vec<&str> = clusters.iter().flatten().map(|e| e.as_str()).collect();
+                names.sort_unstable();
+                result.entry(names.join(",")).or_default().insert(client_id);
+            }
+        }
+        result
+    }
+
     pub fn get_listener_key_size(&self) -> usize {
         self.listener.len()
     }
@@ -186,3 +287,62 @@ impl Subscriber {
         sum
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_subscribers() {
+        let mut subscriber = Subscriber::new();
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let client_id = Arc::new("c1".to_owned());
+        subscriber.set_client_info(
+            client_id.clone(),
+            Arc::new(SubscriberClient {
+                client_ip: "10.0.0.1".to_owned(),
+                agent: "Nacos-Java-Client:v2.1.0".to_owned(),
+                app_name: "demo".to_owned(),
+            }),
+        );
+        let item = NamingListenerItem {
+            service_key: key.clone(),
+            clusters: None,
+        };
+        subscriber.add_subscribe(client_id.clone(), vec![item]);
+        let list = subscriber.get_subscribers(&key);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].client_ip, "10.0.0.1");
+        assert_eq!(list[0].app_name, "demo");
+        subscriber.remove_client_subscribe(client_id);
+        assert!(subscriber.get_subscribers(&key).is_empty());
+        assert!(subscriber.client_infos.is_empty());
+    }
+
+    #[test]
+    fn test_group_by_clusters() {
+        let mut subscriber = Subscriber::new();
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let c1 = Arc::new("c1".to_owned());
+        let c2 = Arc::new("c2".to_owned());
+        subscriber.add_subscribe(
+            c1.clone(),
+            vec![NamingListenerItem {
+                service_key: key.clone(),
+                clusters: None,
+            }],
+        );
+        subscriber.add_subscribe(
+            c2.clone(),
+            vec![NamingListenerItem {
+                service_key: key.clone(),
+                clusters: Some(HashSet::from(["b".to_owned(), "a".to_owned()])),
+            }],
+        );
+        let client_id_set = HashSet::from([c1.clone(), c2.clone(), Arc::new("c3".to_owned())]);
+        let groups = subscriber.group_by_clusters(&key, client_id_set);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.get("").unwrap().contains(&c1));
+        assert!(groups.get("a,b").unwrap().contains(&c2));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::model::ServiceKey;
+
+/// 服务名以该前缀开头时按正则表达式匹配
+pub const SERVICE_REGEX_PREFIX: &str = "regex:";
+
+///
+/// 订阅的服务名模式:以*结尾表示前缀匹配,以regex:开头表示正则匹配
+#[derive(Debug, Clone)]
+pub enum ServiceNamePattern {
+    Prefix(String),
+    Regex(Regex),
+}
+
+impl ServiceNamePattern {
+    pub fn is_pattern(service_name: &str) -> bool {
+        service_name.starts_with(SERVICE_REGEX_PREFIX) || service_name.ends_with('*')
+    }
+
+    pub fn parse(service_name: &str) -> anyhow::Result<Self> {
+        if let Some(v) = service_name.strip_prefix(SERVICE_REGEX_PREFIX) {
+            let regex = Regex::new(&format!("^(?:{})$", v))
+                .map_err(|e| anyhow::anyhow!("service name regex is invalid,{}", e))?;
+            return Ok(Self::Regex(regex));
+        }
+        match service_name.strip_suffix('*') {
+            Some(prefix) if !prefix.contains('*') => Ok(Self::Prefix(prefix.to_owned())),
+            _ => Err(anyhow::anyhow!(
+                "service name pattern is invalid,{}",
+                service_name
+            )),
+        }
+    }
+
+    pub fn is_match(&self, service_name: &str) -> bool {
+        match self {
+            Self::Prefix(prefix) => service_name.starts_with(prefix.as_str()),
+            Self::Regex(regex) => regex.is_match(service_name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PatternSubscribeItem {
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    /// 订阅时的原始服务名
+    pub service_pattern: Arc<String>,
+    pub pattern: ServiceNamePattern,
+}
+
+impl PatternSubscribeItem {
+    pub fn new(key: &ServiceKey) -> anyhow::Result<Self> {
+        Ok(Self {
+            namespace_id: key.namespace_id.clone(),
+            group_name: key.group_name.clone(),
+            service_pattern: key.service_name.clone(),
+            pattern: ServiceNamePattern::parse(&key.service_name)?,
+        })
+    }
+
+    pub fn is_match(&self, key: &ServiceKey) -> bool {
+        self.namespace_id == key.namespace_id
+            && self.group_name == key.group_name
+            && self.pattern.is_match(&key.service_name)
+    }
+
+    fn is_same(&self, key: &ServiceKey) -> bool {
+        self.namespace_id == key.namespace_id
+            && self.group_name == key.group_name
+            && self.service_pattern == key.service_name
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternSubscribeInfo {
+    pub client_id: Arc<String>,
+    pub namespace_id: Arc<String>,
+    pub group_name: Arc<String>,
+    pub service_pattern: Arc<String>,
+}
+
+///
+/// 按服务名模式的订阅;新建服务时按模式匹配,自动为订阅方增加该服务的订阅
+#[derive(Debug, Default)]
+pub struct PatternSubscriber {
+    client_patterns: HashMap<Arc<String>, Vec<PatternSubscribeItem>>,
+    /// 由模式订阅产生的服务订阅,取消模式订阅时只移除这部分
+    derived_keys: HashMap<Arc<String>, HashSet<ServiceKey>>,
+}
+
+impl PatternSubscriber {
+    pub fn add(&mut self, client_id: Arc<String>, item: PatternSubscribeItem) {
+        let list = self.client_patterns.entry(client_id).or_default();
+        list.retain(|e| {
+            !(e.namespace_id == item.namespace_id
+                && e.group_name == item.group_name
+                && e.service_pattern == item.service_pattern)
+        });
+        list.push(item);
+    }
+
+    ///
+    /// 取消模式订阅,返回被移除的模式
+    pub fn remove(
+        &mut self,
+        client_id: &Arc<String>,
+        key: &ServiceKey,
+    ) -> Option<PatternSubscribeItem> {
+        let list = self.client_patterns.get_mut(client_id)?;
+        let index = list.iter().position(|e| e.is_same(key))?;
+        let item = list.remove(index);
+        if list.is_empty() {
+            self.client_patterns.remove(client_id);
+        }
+        Some(item)
+    }
+
+    pub fn remove_client(&mut self, client_id: &Arc<String>) {
+        self.client_patterns.remove(client_id);
+        self.derived_keys.remove(client_id);
+    }
+
+    pub fn add_derived(&mut self, client_id: Arc<String>, key: ServiceKey) {
+        self.derived_keys.entry(client_id).or_default().insert(key);
+    }
+
+    pub fn is_derived(&self, client_id: &Arc<String>, key: &ServiceKey) -> bool {
+        self.derived_keys
+            .get(client_id)
+            .map(|set| set.contains(key))
+            .unwrap_or(false)
+    }
+
+    ///
+    /// 移除模式订阅产生的服务订阅标记;客户端显式订阅该服务后也需要移除
+    pub fn remove_derived(&mut self, client_id: &Arc<String>, key: &ServiceKey) -> bool {
+        let set = match self.derived_keys.get_mut(client_id) {
+            Some(v) => v,
+            None => return false,
+        };
+        let removed = set.remove(key);
+        if set.is_empty() {
+            self.derived_keys.remove(client_id);
+        }
+        removed
+    }
+
+    ///
+    /// 客户端的其它模式是否仍匹配该服务
+    pub fn is_client_match(&self, client_id: &Arc<String>, key: &ServiceKey) -> bool {
+        self.client_patterns
+            .get(client_id)
+            .map(|list| list.iter().any(|e| e.is_match(key)))
+            .unwrap_or(false)
+    }
+
+    pub fn match_clients(&self, key: &ServiceKey) -> Vec<Arc<String>> {
+        self.client_patterns
+            .iter()
+            .filter(|(_, list)| list.iter().any(|e| e.is_match(key)))
+            .map(|(client_id, _)| client_id.clone())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.client_patterns.is_empty()
+    }
+
+    pub fn get_list(&self, namespace_id: Option<&Arc<String>>) -> Vec<PatternSubscribeInfo> {
+        let mut list = vec![];
+        for (client_id, items) in &self.client_patterns {
+            for item in items {
+                if let Some(namespace_id) = namespace_id {
+                    if namespace_id != &item.namespace_id {
+                        continue;
+                    }
+                }
+                list.push(PatternSubscribeInfo {
+                    client_id: client_id.clone(),
+                    namespace_id: item.namespace_id.clone(),
+                    group_name: item.group_name.clone(),
+                    service_pattern: item.service_pattern.clone(),
+                });
+            }
+        }
+        list.sort_by(|a, b| {
+            (
+                &a.namespace_id,
+                &a.group_name,
+                &a.service_pattern,
+                &a.client_id,
+            )
+                .cmp(&(
+                    &b.namespace_id,
+                    &b.group_name,
+                    &b.service_pattern,
+                    &b.client_id,
+                ))
+        });
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_subscriber() {
+        assert!(!ServiceNamePattern::is_pattern("foo"));
+        assert!(ServiceNamePattern::parse("a*b*").is_err());
+        assert!(ServiceNamePattern::parse("regex:(").is_err());
+        let mut subscriber = PatternSubscriber::default();
+        let c1 = Arc::new("c1".to_owned());
+        let c2 = Arc::new("c2".to_owned());
+        let prefix_key = ServiceKey::new("public", "gateway", "order-*");
+        let regex_key = ServiceKey::new("public", "gateway", "regex:(user|pay)-api");
+        subscriber.add(c1.clone(), PatternSubscribeItem::new(&prefix_key).unwrap());
+        subscriber.add(c2.clone(), PatternSubscribeItem::new(&regex_key).unwrap());
+        let key = ServiceKey::new("public", "gateway", "order-api");
+        assert_eq!(subscriber.match_clients(&key), vec![c1.clone()]);
+        let key = ServiceKey::new("public", "gateway", "pay-api");
+        assert_eq!(subscriber.match_clients(&key), vec![c2.clone()]);
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "order-api");
+        assert!(subscriber.match_clients(&key).is_empty());
+        assert!(subscriber.remove(&c1, &prefix_key).is_some());
+        subscriber.remove_client(&c2);
+        assert!(subscriber.is_empty());
+    }
+}
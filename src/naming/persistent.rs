@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use crate::common::appdata::AppShareData;
+use crate::common::constant::{
+    EMPTY_ARC_STRING, NAMING_PERSISTENT_INSTANCE_TREE_NAME, NAMING_SERVICE_TREE_NAME,
+};
+use crate::raft::db::table::{TableManagerQueryReq, TableManagerReq, TableManagerResult};
+
+use super::model::{Instance, InstanceUpdateTag, ServiceDetailDto, ServiceKey};
+
+///
+/// 持久化实例(ephemeral=false)通过raft写入状态机,不依赖心跳保活,重启后从raft数据恢复
+pub struct PersistentInstanceUtils;
+
+impl PersistentInstanceUtils {
+    pub fn build_key(instance: &Instance) -> Vec<u8> {
+        format!(
+            "{}#{}#{}#{}#{}",
+            &instance.namespace_id,
+            &instance.group_name,
+            &instance.service_name,
+            &instance.ip,
+            instance.port
+        )
+        .into_bytes()
+    }
+
+    pub fn to_bytes(instance: &Instance) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(instance)?)
+    }
+
+    ///
+    /// 从raft数据还原实例;持久化实例由每个节点自行维护,不属于任何客户端与集群节点
+    pub fn from_bytes(value: &[u8]) -> anyhow::Result<Instance> {
+        let mut instance: Instance = serde_json::from_slice(value)?;
+        instance.ephemeral = false;
+        instance.from_grpc = false;
+        instance.from_cluster = 0;
+        instance.client_id = EMPTY_ARC_STRING.clone();
+        if instance.need_generate_key() {
+            instance.generate_key();
+        }
+        Ok(instance)
+    }
+
+    async fn get_instance(app: &AppShareData, key: Vec<u8>) -> anyhow::Result<Option<Instance>> {
+        let req = TableManagerQueryReq::GetByBytes {
+            table_name: NAMING_PERSISTENT_INSTANCE_TREE_NAME.clone(),
+            key,
+        };
+        match app.raft_table_manage.send(req).await?? {
+            TableManagerResult::Value(v) => Ok(Some(Self::from_bytes(&v)?)),
+            _ => Ok(None),
+        }
+    }
+
+    ///
+    /// 注册或更新持久化实例,未标记更新的字段保留原值;按主节点上的最新值合并后比较写入
+    pub async fn update_instance(
+        app: &AppShareData,
+        mut instance: Instance,
+        tag: Option<InstanceUpdateTag>,
+    ) -> anyhow::Result<()> {
+        instance.ephemeral = false;
+        instance.from_grpc = false;
+        instance.client_id = EMPTY_ARC_STRING.clone();
+        let key = Self::build_key(&instance);
+        app.raft_table_route
+            .update_value(
+                NAMING_PERSISTENT_INSTANCE_TREE_NAME.clone(),
+                key,
+                |old_value| {
+                    let mut instance = instance.clone();
+                    if let (Some(old_value), Some(tag)) = (old_value, &tag) {
+                        let old = Self::from_bytes(old_value)?;
+                        if !tag.weight {
+                            instance.weight = old.weight;
+                        }
+                        if !tag.enabled {
+                            instance.enabled = old.enabled;
+                        }
+                        if !tag.metadata {
+                            instance.metadata = old.metadata;
+                        } else if let Some(patch) = &tag.metadata_patch {
+                            instance.metadata = Arc::new(patch.apply(&old.metadata));
+                        }
+                    }
+                    Self::to_bytes(&instance)
+                },
+            )
+            .await
+    }
+
+    pub async fn delete_instance(app: &AppShareData, instance: &Instance) -> anyhow::Result<()> {
+        let req = TableManagerReq::Remove {
+            table_name: NAMING_PERSISTENT_INSTANCE_TREE_NAME.clone(),
+            key: Self::build_key(instance),
+        };
+        app.raft_table_route.request(req).await
+    }
+
+    ///
+    /// 请求声明ephemeral=false或已存在同地址的持久化实例时,走持久化实例处理
+    pub async fn is_persistent(app: &AppShareData, instance: &Instance) -> anyhow::Result<bool> {
+        if !instance.ephemeral {
+            return Ok(true);
+        }
+        Ok(Self::get_instance(app, Self::build_key(instance))
+            .await?
+            .is_some())
+    }
+}
+
+///
+/// 控制台维护的服务定义(metadata、保护阈值、选择器)通过raft写入状态机,同步到所有节点且重启后恢复
+pub struct PersistentServiceUtils;
+
+impl PersistentServiceUtils {
+    pub fn build_key(key: &ServiceKey) -> Vec<u8> {
+        format!(
+            "{}#{}#{}",
+            &key.namespace_id, &key.group_name, &key.service_name
+        )
+        .into_bytes()
+    }
+
+    pub fn to_bytes(service: &ServiceDetailDto) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(service)?)
+    }
+
+    pub fn from_bytes(value: &[u8]) -> anyhow::Result<ServiceDetailDto> {
+        Ok(serde_json::from_slice(value)?)
+    }
+
+    pub async fn get_service(
+        app: &AppShareData,
+        key: &ServiceKey,
+    ) -> anyhow::Result<Option<ServiceDetailDto>> {
+        let req = TableManagerQueryReq::GetByBytes {
+            table_name: NAMING_SERVICE_TREE_NAME.clone(),
+            key: Self::build_key(key),
+        };
+        match app.raft_table_manage.send(req).await?? {
+            TableManagerResult::Value(v) => Ok(Some(Self::from_bytes(&v)?)),
+            _ => Ok(None),
+        }
+    }
+
+    ///
+    /// 新增或更新服务定义,未设置的字段保留原值;按主节点上的最新值合并后比较写入
+    pub async fn update_service(
+        app: &AppShareData,
+        service: ServiceDetailDto,
+    ) -> anyhow::Result<()> {
+        let key = service.to_service_key();
+        app.raft_table_route
+            .update_value(
+                NAMING_SERVICE_TREE_NAME.clone(),
+                Self::build_key(&key),
+                |old_value| {
+                    let mut service = service.clone();
+                    if let Some(old_value) = old_value {
+                        let old = Self::from_bytes(old_value)?;
+                        if service.metadata.is_none() {
+                            service.metadata = old.metadata;
+                        }
+                        if service.protect_threshold.is_none() {
+                            service.protect_threshold = old.protect_threshold;
+                        }
+                        if service.selector.is_none() {
+                            service.selector = old.selector;
+                        }
+                    }
+                    Self::to_bytes(&service)
+                },
+            )
+            .await
+    }
+
+    pub async fn remove_service(app: &AppShareData, key: &ServiceKey) -> anyhow::Result<()> {
+        let req = TableManagerReq::Remove {
+            table_name: NAMING_SERVICE_TREE_NAME.clone(),
+            key: Self::build_key(key),
+        };
+        app.raft_table_route.request(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_instance_bytes() {
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP".to_owned());
+        instance.service_name = Arc::new("foo".to_owned());
+        instance.client_id = Arc::new("client".to_owned());
+        instance.from_cluster = 2;
+        let key = PersistentInstanceUtils::build_key(&instance);
+        assert_eq!(key, b"public#DEFAULT_GROUP#foo#127.0.0.1#8080".to_vec());
+        let value = PersistentInstanceUtils::to_bytes(&instance).unwrap();
+        let v = PersistentInstanceUtils::from_bytes(&value).unwrap();
+        assert!(!v.ephemeral);
+        assert!(v.client_id.is_empty());
+        assert_eq!(v.from_cluster, 0);
+        assert_eq!(v.id.as_str(), "127.0.0.1#8080#DEFAULT#DEFAULT_GROUP@@foo");
+    }
+
+    #[test]
+    fn test_service_bytes() {
+        let service = ServiceDetailDto {
+            namespace_id: Arc::new("public".to_owned()),
+            service_name: Arc::new("foo".to_owned()),
+            group_name: Arc::new("DEFAULT_GROUP".to_owned()),
+            metadata: None,
+            protect_threshold: Some(0.5),
+            selector: None,
+        };
+        let key = PersistentServiceUtils::build_key(&service.to_service_key());
+        assert_eq!(key, b"public#DEFAULT_GROUP#foo".to_vec());
+        let value = PersistentServiceUtils::to_bytes(&service).unwrap();
+        let v = PersistentServiceUtils::from_bytes(&value).unwrap();
+        assert_eq!(v.to_service_key(), service.to_service_key());
+        assert_eq!(v.protect_threshold, Some(0.5));
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use super::model::ServiceKey;
+
+/// 同一客户端的同一服务连续未确认的推送超过该次数后不再重推
+const MAX_UNACKED_PUSH_TIMES: u32 = 3;
+
+#[derive(Debug, Clone, Default)]
+pub struct PushTrackerConfig {
+    /// 推送后等待客户端确认的时间,为0时不跟踪
+    pub ack_timeout_millis: u64,
+    /// 每次检查最多重推的客户端服务数量
+    pub repush_batch_size: usize,
+}
+
+#[derive(Debug)]
+struct PendingPush {
+    request_id: Arc<String>,
+    push_time: u64,
+    times: u32,
+}
+
+///
+/// 跟踪服务变更推送的客户端确认;
+/// 超时未确认的客户端持有的可能是旧版本,按批重推当前的服务信息
+#[derive(Debug, Default)]
+pub struct NamingPushTracker {
+    pub config: PushTrackerConfig,
+    /// 客户端 -> 服务 -> 最近一次未确认的推送
+    pending: HashMap<Arc<String>, HashMap<ServiceKey, PendingPush>>,
+}
+
+impl NamingPushTracker {
+    pub fn is_enabled(&self) -> bool {
+        self.config.ack_timeout_millis > 0
+    }
+
+    pub fn record_push(
+        &mut self,
+        client_id: &Arc<String>,
+        key: &ServiceKey,
+        request_id: &Arc<String>,
+        now: u64,
+    ) {
+        let services = self.pending.entry(client_id.clone()).or_default();
+        let times = services.get(key).map(|v| v.times + 1).unwrap_or(1);
+        services.insert(
+            key.clone(),
+            PendingPush {
+                request_id: request_id.clone(),
+                push_time: now,
+                times,
+            },
+        );
+    }
+
+    pub fn ack(&mut self, client_id: &Arc<String>, request_id: &str) {
+        if let Some(services) = self.pending.get_mut(client_id) {
+            services.retain(|_, v| v.request_id.as_str() != request_id);
+            if services.is_empty() {
+                self.pending.remove(client_id);
+            }
+        }
+    }
+
+    pub fn remove_client(&mut self, client_id: &Arc<String>) {
+        self.pending.remove(client_id);
+    }
+
+    ///
+    /// 超时未确认需要重推的客户端,按服务分组;超过重推次数的放弃跟踪
+    pub fn timeout_pushes(&mut self, now: u64) -> HashMap<ServiceKey, HashSet<Arc<String>>> {
+        let mut result: HashMap<ServiceKey, HashSet<Arc<String>>> = HashMap::new();
+        let mut count = 0;
+        let timeout = self.config.ack_timeout_millis;
+        let repush_batch_size = self.config.repush_batch_size;
+        for (client_id, services) in self.pending.iter_mut() {
+            services.retain(|key, v| {
+                if v.push_time + timeout > now {
+                    return true;
+                }
+                if v.times >= MAX_UNACKED_PUSH_TIMES {
+                    log::warn!(
+                        "give up repush service {} to client {}",
+                        key.get_join_service_name(),
+                        client_id
+                    );
+                    return false;
+                }
+                if count < repush_batch_size {
+                    count += 1;
+                    //重推前不再重复返回
+                    v.push_time = now;
+                    result
+                        .entry(key.clone())
+                        .or_default()
+                        .insert(client_id.clone());
+                }
+                true
+            });
+        }
+        self.pending.retain(|_, services| !services.is_empty());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_tracker() {
+        let mut tracker = NamingPushTracker {
+            config: PushTrackerConfig {
+                ack_timeout_millis: 1000,
+                repush_batch_size: 1,
+            },
+            ..Default::default()
+        };
+        let key_a = ServiceKey::new("public", "DEFAULT_GROUP", "a");
+        let key_b = ServiceKey::new("public", "DEFAULT_GROUP", "b");
+        let client = Arc::new("c1".to_owned());
+        tracker.record_push(&client, &key_a, &Arc::new("1".to_owned()), 0);
+        tracker.record_push(&client, &key_b, &Arc::new("2".to_owned()), 0);
+        tracker.ack(&client, "1");
+        assert!(tracker.timeout_pushes(500).is_empty());
+
+        //每次最多重推一个
+        let result = tracker.timeout_pushes(1000);
+        assert!(result.get(&key_b).unwrap().contains(&client));
+        assert!(tracker.timeout_pushes(1500).is_empty());
+
+        for i in 0..MAX_UNACKED_PUSH_TIMES {
+            tracker.record_push(&client, &key_b, &Arc::new(format!("r{}", i)), 2000);
+        }
+        assert!(tracker.timeout_pushes(5000).is_empty());
+        assert!(tracker.pending.is_empty());
+    }
+}
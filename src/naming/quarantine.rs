@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::{Instance, InstanceShortKey, ServiceKey};
+
+#[derive(Debug, Clone)]
+pub struct QuarantineConfig {
+    /// 过期实例的保留时长,为0时不保留
+    pub ttl_millis: u64,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            ttl_millis: 600_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineInstance {
+    pub instance: Arc<Instance>,
+    /// 心跳超时被移除的时间
+    pub expired_time: u64,
+}
+
+///
+/// 心跳超时被移除的实例,按服务保留一段时间;用于区分实例从未注册与最近过期
+#[derive(Debug, Default)]
+pub struct InstanceQuarantine {
+    pub config: QuarantineConfig,
+    records: HashMap<ServiceKey, Vec<QuarantineInstance>>,
+}
+
+impl InstanceQuarantine {
+    pub fn new(config: QuarantineConfig) -> Self {
+        Self {
+            config,
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, key: &ServiceKey, instance: Arc<Instance>, now: u64) {
+        if self.config.ttl_millis == 0 {
+            return;
+        }
+        let short_key = instance.get_short_key();
+        let list = self.records.entry(key.clone()).or_default();
+        list.retain(|e| e.instance.get_short_key() != short_key);
+        list.push(QuarantineInstance {
+            instance,
+            expired_time: now,
+        });
+    }
+
+    ///
+    /// 实例重新注册后从隔离列表移除
+    pub fn remove(&mut self, key: &ServiceKey, short_key: &InstanceShortKey) {
+        if let Some(list) = self.records.get_mut(key) {
+            list.retain(|e| &e.instance.get_short_key() != short_key);
+            if list.is_empty() {
+                self.records.remove(key);
+            }
+        }
+    }
+
+    pub fn clear_timeout(&mut self, now: u64) {
+        let ttl_millis = self.config.ttl_millis;
+        self.records.retain(|_, list| {
+            list.retain(|e| e.expired_time + ttl_millis > now);
+            !list.is_empty()
+        });
+    }
+
+    ///
+    /// 按过期时间倒序查询
+    pub fn query(&self, key: &ServiceKey) -> Vec<QuarantineInstance> {
+        let mut list = self.records.get(key).cloned().unwrap_or_default();
+        list.sort_by_key(|e| std::cmp::Reverse(e.expired_time));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_quarantine() {
+        let key = ServiceKey::new("public", "DEFAULT_GROUP", "foo");
+        let mut quarantine = InstanceQuarantine::new(QuarantineConfig { ttl_millis: 100 });
+        let a = Arc::new(Instance::new("127.0.0.1".to_owned(), 8080));
+        let b = Arc::new(Instance::new("127.0.0.1".to_owned(), 8081));
+        quarantine.add(&key, a.clone(), 10);
+        quarantine.add(&key, b.clone(), 20);
+        quarantine.add(&key, a.clone(), 30);
+        let list = quarantine.query(&key);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].expired_time, 30);
+        quarantine.clear_timeout(125);
+        assert_eq!(quarantine.query(&key).len(), 1);
+        quarantine.remove(&key, &a.get_short_key());
+        assert!(quarantine.query(&key).is_empty());
+    }
+}
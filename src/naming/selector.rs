@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::Instance;
+
+pub const SELECTOR_TYPE_NONE: &str = "none";
+pub const SELECTOR_TYPE_LABEL: &str = "label";
+const CONSUMER_LABEL_PREFIX: &str = "CONSUMER.label.";
+const PROVIDER_LABEL_PREFIX: &str = "PROVIDER.label.";
+
+///
+/// 服务选择器,兼容nacos格式: {"type":"label","expression":"CONSUMER.label.zone = PROVIDER.label.zone"}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSelector {
+    pub r#type: String,
+    #[serde(default)]
+    pub expression: String,
+}
+
+impl Default for ServiceSelector {
+    fn default() -> Self {
+        Self {
+            r#type: SELECTOR_TYPE_NONE.to_owned(),
+            expression: "".to_owned(),
+        }
+    }
+}
+
+impl ServiceSelector {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let selector: Self = serde_json::from_str(value)?;
+        selector.build_label_selector()?;
+        Ok(selector)
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.r#type.is_empty() || self.r#type == SELECTOR_TYPE_NONE
+    }
+
+    pub fn build_label_selector(&self) -> anyhow::Result<Option<LabelSelector>> {
+        if self.is_none() {
+            return Ok(None);
+        }
+        if self.r#type != SELECTOR_TYPE_LABEL {
+            return Err(anyhow::anyhow!(
+                "unsupported selector type: {}",
+                &self.r#type
+            ));
+        }
+        Ok(Some(LabelSelector::parse(&self.expression)?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelClause {
+    /// CONSUMER.label.key = PROVIDER.label.key
+    SameAsConsumer(String),
+    /// PROVIDER.label.key = value
+    Value(String, String),
+}
+
+///
+/// 标签选择器;提供方标签取实例metadata,消费方标签取消费方ip注册的实例metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelSelector {
+    pub clauses: Vec<LabelClause>,
+}
+
+impl LabelSelector {
+    ///
+    /// 多个条件用 & 连接
+    pub fn parse(expression: &str) -> anyhow::Result<Self> {
+        let mut clauses = vec![];
+        for item in expression.split('&') {
+            let item = item.trim();
+            if item.is_empty() {
+                continue;
+            }
+            let (left, right) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("selector expression is invalid: {}", item))?;
+            let (left, right) = (left.trim(), right.trim());
+            let clause = match (
+                left.strip_prefix(CONSUMER_LABEL_PREFIX),
+                right.strip_prefix(PROVIDER_LABEL_PREFIX),
+                left.strip_prefix(PROVIDER_LABEL_PREFIX),
+                right.strip_prefix(CONSUMER_LABEL_PREFIX),
+            ) {
+                (Some(a), Some(b), _, _) | (_, _, Some(b), Some(a)) if a == b && !a.is_empty() => {
+                    LabelClause::SameAsConsumer(a.to_owned())
+                }
+                (_, _, Some(key), None) if !key.is_empty() && !right.is_empty() => {
+                    LabelClause::Value(key.to_owned(), right.to_owned())
+                }
+                _ => return Err(anyhow::anyhow!("selector expression is invalid: {}", item)),
+            };
+            clauses.push(clause);
+        }
+        if clauses.is_empty() {
+            return Err(anyhow::anyhow!("selector expression is empty"));
+        }
+        Ok(Self { clauses })
+    }
+
+    pub fn need_consumer_labels(&self) -> bool {
+        self.clauses
+            .iter()
+            .any(|e| matches!(e, LabelClause::SameAsConsumer(_)))
+    }
+
+    ///
+    /// 消费方没有对应标签的条件不参与过滤;过滤后没有实例时返回全部实例
+    pub fn select(
+        &self,
+        list: Vec<Arc<Instance>>,
+        consumer_labels: &HashMap<String, String>,
+    ) -> Vec<Arc<Instance>> {
+        let is_match = |e: &Arc<Instance>| {
+            self.clauses.iter().all(|clause| match clause {
+                LabelClause::SameAsConsumer(key) => match consumer_labels.get(key) {
+                    Some(v) => e.metadata.get(key) == Some(v),
+                    None => true,
+                },
+                LabelClause::Value(key, value) => e.metadata.get(key) == Some(value),
+            })
+        };
+        let selected: Vec<Arc<Instance>> = list.iter().filter(|e| is_match(e)).cloned().collect();
+        if selected.is_empty() {
+            list
+        } else {
+            selected
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_instance(ip: &str, zone: &str) -> Arc<Instance> {
+        let mut metadata = HashMap::new();
+        metadata.insert("zone".to_owned(), zone.to_owned());
+        metadata.insert("env".to_owned(), "prod".to_owned());
+        Arc::new(Instance {
+            ip: Arc::new(ip.to_owned()),
+            metadata: Arc::new(metadata),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_label_selector() {
+        let selector = ServiceSelector::parse(
+            r#"{"type":"label","expression":"CONSUMER.label.zone = PROVIDER.label.zone & PROVIDER.label.env = prod"}"#,
+        )
+        .unwrap();
+        let selector = selector.build_label_selector().unwrap().unwrap();
+        assert!(selector.need_consumer_labels());
+        let list = vec![
+            build_instance("1.1.1.1", "a"),
+            build_instance("1.1.1.2", "b"),
+        ];
+        let mut consumer_labels = HashMap::new();
+        consumer_labels.insert("zone".to_owned(), "b".to_owned());
+        let selected = selector.select(list.clone(), &consumer_labels);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].ip.as_str(), "1.1.1.2");
+        assert_eq!(selector.select(list, &HashMap::new()).len(), 2);
+        assert!(LabelSelector::parse("CONSUMER.label.a = PROVIDER.label.b").is_err());
+        assert!(ServiceSelector::parse(r#"{"type":"cmdb"}"#).is_err());
+    }
+}
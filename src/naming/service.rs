@@ -1,7 +1,7 @@
 #![allow(unused_assignments, unused_imports)]
 
 use std::{
-    collections::{HashMap, LinkedList},
+    collections::{HashMap, HashSet, LinkedList},
     hash::Hash,
     sync::{atomic::Ordering, Arc},
 };
@@ -11,14 +11,17 @@ use crate::naming::cluster::model::ProcessRange;
 use actix_web::rt;
 use inner_mem_cache::TimeoutSet;
 
-use crate::now_millis;
+use crate::{now_millis, now_millis_i64};
 
 use super::{
     api_model::QueryListResult,
+    instance_id::InstanceIdGenerator,
     model::{
         Instance, InstanceShortKey, InstanceUpdateTag, ServiceDetailDto, ServiceKey,
         UpdateInstanceType,
     },
+    selector::{LabelSelector, ServiceSelector},
+    service_export::{NacosClusterInfo, NacosServiceExport, NacosServiceInfo},
 };
 
 #[derive(Debug, Clone, Default)]
@@ -28,6 +31,15 @@ pub struct ServiceMetadata {
 
 type InstanceMetaData = Arc<HashMap<String, String>>;
 
+/// 实例或服务metadata中设置的心跳超时时间(毫秒),超时后实例标记为不健康
+pub const HEARTBEAT_TIMEOUT_KEY: &str = "preserved.heart.beat.timeout";
+/// 实例metadata中设置的客户端心跳间隔(毫秒)
+pub const HEARTBEAT_INTERVAL_KEY: &str = "preserved.heart.beat.interval";
+/// 实例或服务metadata中设置的删除超时时间(毫秒),超时后删除实例
+pub const IP_DELETE_TIMEOUT_KEY: &str = "preserved.ip.delete.timeout";
+const DEFAULT_HEARTBEAT_TIMEOUT: i64 = 15000;
+const DEFAULT_IP_DELETE_TIMEOUT: i64 = 30000;
+
 #[derive(Default)]
 pub struct Service {
     pub service_name: Arc<String>,
@@ -43,16 +55,37 @@ pub struct Service {
     pub(crate) last_empty_times: u64,
     pub(crate) instance_size: i64,
     pub(crate) healthy_instance_size: i64,
-    //pub cluster_map:HashMap<String,Cluster>,
+    /// 集群名称->集群下的实例
+    pub(crate) cluster_map: HashMap<String, HashSet<InstanceShortKey>>,
     pub(crate) instances: HashMap<InstanceShortKey, Arc<Instance>>,
     pub(crate) instance_metadata_map: HashMap<InstanceShortKey, InstanceMetaData>,
     /// 健康状态过期记录，过期后把实例状态改为不健康
     pub(crate) healthy_timeout_set: TimeoutSet<InstanceShortKey>,
     /// 不健康状态过期记录，过期后反实例删除
     pub(crate) unhealthy_timeout_set: TimeoutSet<InstanceShortKey>,
+    pub(crate) selector: Option<ServiceSelector>,
+    pub(crate) label_selector: Option<Arc<LabelSelector>>,
+    /// 控制台维护的服务定义,没有实例时也不过期清理
+    pub(crate) persistent: bool,
 }
 
 impl Service {
+    ///
+    /// 设置服务选择器,类型为none时清除
+    pub(crate) fn set_selector(&mut self, selector: ServiceSelector) {
+        match selector.build_label_selector() {
+            Ok(Some(label_selector)) => {
+                self.selector = Some(selector);
+                self.label_selector = Some(Arc::new(label_selector));
+            }
+            Ok(None) => {
+                self.selector = None;
+                self.label_selector = None;
+            }
+            Err(err) => log::warn!("ignore invalid service selector,{}", err),
+        }
+    }
+
     pub(crate) fn recalculate_checksum(&mut self) {
         "".clone_into(&mut self.check_sum);
     }
@@ -84,6 +117,10 @@ impl Service {
         let old_instance = self.instances.get(&key);
         let mut replace_old_client_id = None;
         if let Some(old_instance) = old_instance {
+            if InstanceIdGenerator::from_metadata(&instance.metadata).is_random() {
+                //随机生成的实例id在实例存续期间保持不变
+                instance.id = old_instance.id.clone();
+            }
             if !instance.from_grpc && old_instance.from_grpc {
                 /*
                 match (old_instance.from_grpc, old_instance.is_from_cluster()) {
@@ -106,11 +143,29 @@ impl Service {
                  */
                 instance.from_grpc = old_instance.from_grpc;
                 instance.client_id = old_instance.client_id.clone();
+                instance.client_labels = old_instance.client_labels.clone();
                 //instance.from_cluster = old_instance.from_cluster;
             }
             if !old_instance.client_id.is_empty() && instance.client_id != old_instance.client_id {
                 replace_old_client_id = Some(old_instance.client_id.clone());
             }
+            if instance.register_time == 0 {
+                instance.register_time = old_instance.register_time;
+            }
+            if instance.revision == 0 {
+                //同一grpc连接的更新与心跳保持版本;新连接或http重新注册时生成更大的版本
+                let is_beat = update_tag
+                    .as_ref()
+                    .map(|e| e.is_none() && !e.from_update)
+                    .unwrap_or(false);
+                let same_connection =
+                    !instance.client_id.is_empty() && instance.client_id == old_instance.client_id;
+                instance.revision = if same_connection || is_beat {
+                    old_instance.revision
+                } else {
+                    now_millis().max(old_instance.revision + 1)
+                };
+            }
             if !old_instance.healthy && instance.healthy {
                 self.healthy_instance_size += 1;
             } else if old_instance.healthy && !instance.healthy {
@@ -130,6 +185,10 @@ impl Service {
                     }
                     if !update_tag.metadata {
                         instance.metadata = old_instance.metadata.clone();
+                    } else if let Some(patch) = &update_tag.metadata_patch {
+                        instance.metadata = Arc::new(patch.apply(&old_instance.metadata));
+                        self.instance_metadata_map
+                            .insert(short_key, instance.metadata.clone());
                     } else if update_tag.from_update {
                         //从控制台设置的metadata
                         self.instance_metadata_map
@@ -146,10 +205,18 @@ impl Service {
                     old_instance.ephemeral.clone_into(&mut instance.ephemeral);
                     old_instance.weight.clone_into(&mut instance.weight);
                     instance.metadata = old_instance.metadata.clone();
-                    rtype = UpdateInstanceType::UpdateTime;
+                    if old_instance.healthy == instance.healthy {
+                        rtype = UpdateInstanceType::UpdateTime;
+                    }
                 }
             }
         } else {
+            if instance.revision == 0 {
+                instance.revision = now_millis();
+            }
+            if instance.register_time == 0 {
+                instance.register_time = now_millis();
+            }
             //新增的尝试使用高优先级metadata
             if let Some(priority_metadata) = self.instance_metadata_map.get(&short_key) {
                 instance.metadata = priority_metadata.clone();
@@ -163,14 +230,32 @@ impl Service {
         let new_instance = Arc::new(instance);
         if new_instance.is_enable_timeout() {
             self.healthy_timeout_set.add(
-                new_instance.last_modified_millis as u64,
+                Self::heartbeat_timeout_at(&self.metadata, &new_instance),
                 new_instance.get_short_key(),
             );
         }
-        self.instances.insert(key, new_instance);
+        let cluster_name = new_instance.cluster_name.clone();
+        if let Some(old) = self.instances.insert(key.clone(), new_instance) {
+            if old.cluster_name != cluster_name {
+                self.remove_cluster_instance(&old.cluster_name, &key);
+            }
+        }
+        self.cluster_map
+            .entry(cluster_name)
+            .or_default()
+            .insert(key);
         (rtype, replace_old_client_id)
     }
 
+    fn remove_cluster_instance(&mut self, cluster_name: &str, key: &InstanceShortKey) {
+        if let Some(keys) = self.cluster_map.get_mut(cluster_name) {
+            keys.remove(key);
+            if keys.is_empty() {
+                self.cluster_map.remove(cluster_name);
+            }
+        }
+    }
+
     ///
     /// 刷新重新纳入本节点管理的实例
     /// 增量http实例增加过期管理
@@ -191,36 +276,85 @@ impl Service {
             );
              */
             self.healthy_timeout_set.add(
-                instance.last_modified_millis as u64,
+                Self::heartbeat_timeout_at(&self.metadata, instance),
                 instance.get_short_key(),
             );
         }
     }
 
-    pub(crate) fn time_check(
-        &mut self,
-        healthy_time: i64,
-        offline_time: i64,
-    ) -> (Vec<InstanceShortKey>, Vec<InstanceShortKey>) {
+    fn get_timeout(
+        service_metadata: &HashMap<String, String>,
+        instance: &Instance,
+        key: &str,
+        default_value: i64,
+    ) -> i64 {
+        instance
+            .metadata
+            .get(key)
+            .or_else(|| service_metadata.get(key))
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default_value)
+    }
+
+    ///
+    /// 实例心跳超时(标记为不健康)的时间点
+    fn heartbeat_timeout_at(
+        service_metadata: &HashMap<String, String>,
+        instance: &Instance,
+    ) -> u64 {
+        (instance.last_modified_millis
+            + Self::get_timeout(
+                service_metadata,
+                instance,
+                HEARTBEAT_TIMEOUT_KEY,
+                DEFAULT_HEARTBEAT_TIMEOUT,
+            )) as u64
+    }
+
+    ///
+    /// 实例心跳超时后被删除的时间点
+    fn ip_delete_timeout_at(
+        service_metadata: &HashMap<String, String>,
+        instance: &Instance,
+    ) -> u64 {
+        (instance.last_modified_millis
+            + Self::get_timeout(
+                service_metadata,
+                instance,
+                IP_DELETE_TIMEOUT_KEY,
+                DEFAULT_IP_DELETE_TIMEOUT,
+            )) as u64
+    }
+
+    ///
+    /// 检查心跳超时的实例;超时时间可以通过实例或服务metadata设置
+    pub(crate) fn time_check(&mut self, now: i64) -> (Vec<Arc<Instance>>, Vec<InstanceShortKey>) {
         let mut remove_list = vec![];
-        for key in self.unhealthy_timeout_set.timeout(offline_time as u64) {
+        for key in self.unhealthy_timeout_set.timeout(now as u64) {
             if let Some(instance) = self.instances.get(&key) {
-                if !instance.is_enable_timeout() || instance.last_modified_millis > offline_time {
+                if !instance.is_enable_timeout()
+                    || Self::ip_delete_timeout_at(&self.metadata, instance) > now as u64
+                {
                     continue;
                 }
             }
-            self.remove_instance(&key, None);
-            remove_list.push(key);
+            if let Some(instance) = self.remove_instance(&key, None) {
+                remove_list.push(instance);
+            }
         }
         let mut update_list = vec![];
-        for key in self.healthy_timeout_set.timeout(healthy_time as u64) {
+        for key in self.healthy_timeout_set.timeout(now as u64) {
             if let Some(instance) = self.instances.get(&key) {
-                if !instance.is_enable_timeout() || instance.last_modified_millis > healthy_time {
+                if !instance.is_enable_timeout()
+                    || Self::heartbeat_timeout_at(&self.metadata, instance) > now as u64
+                {
                     continue;
                 }
             }
-            self.update_instance_healthy_invalid(&key);
-            update_list.push(key);
+            if self.update_instance_healthy_invalid(&key) {
+                update_list.push(key);
+            }
         }
         (remove_list, update_list)
     }
@@ -239,6 +373,7 @@ impl Service {
             }
         }
         if let Some(old) = self.instances.remove(instance_key) {
+            self.remove_cluster_instance(&old.cluster_name, instance_key);
             self.instance_size -= 1;
             if self.instance_size == 0 {
                 self.last_empty_times = now_millis();
@@ -252,19 +387,64 @@ impl Service {
         }
     }
 
-    pub(crate) fn update_instance_healthy_invalid(&mut self, instance_id: &InstanceShortKey) {
+    ///
+    /// 把实例标记为不健康,返回实例健康状态是否有变更
+    pub(crate) fn update_instance_healthy_invalid(
+        &mut self,
+        instance_id: &InstanceShortKey,
+    ) -> bool {
         if let Some(i) = self.instances.remove(instance_id) {
+            let changed = i.healthy;
             if i.healthy {
                 self.healthy_instance_size -= 1;
             }
             let mut i = i.as_ref().clone();
             i.healthy = false;
-            self.unhealthy_timeout_set
-                .add(i.last_modified_millis as u64, instance_id.clone());
+            self.unhealthy_timeout_set.add(
+                Self::ip_delete_timeout_at(&self.metadata, &i),
+                instance_id.clone(),
+            );
+            self.instances.insert(instance_id.clone(), Arc::new(i));
+            changed
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// 主动健康检查成功,视为一次心跳;返回实例健康状态是否有变更
+    pub(crate) fn update_instance_healthy_valid(&mut self, instance_id: &InstanceShortKey) -> bool {
+        if let Some(i) = self.instances.remove(instance_id) {
+            let changed = !i.healthy;
+            if !i.healthy {
+                self.healthy_instance_size += 1;
+            }
+            let mut i = i.as_ref().clone();
+            i.healthy = true;
+            i.last_modified_millis = now_millis_i64();
+            if i.is_enable_timeout() {
+                self.healthy_timeout_set.add(
+                    Self::heartbeat_timeout_at(&self.metadata, &i),
+                    instance_id.clone(),
+                );
+            }
             self.instances.insert(instance_id.clone(), Arc::new(i));
+            changed
+        } else {
+            false
         }
     }
 
+    ///
+    /// 需要本节点主动检查的实例
+    pub(crate) fn get_health_check_instances(&self) -> Vec<InstanceShortKey> {
+        self.instances
+            .values()
+            .filter(|x| x.enabled && !x.is_from_cluster())
+            .map(|x| x.get_short_key())
+            .collect()
+    }
+
     pub(crate) fn get_instance(&self, instance_key: &InstanceShortKey) -> Option<Arc<Instance>> {
         self.instances.get(instance_key).cloned()
     }
@@ -295,13 +475,30 @@ impl Service {
     }
     */
 
+    ///
+    /// 查询指定集群下的实例;集群列表为空时返回全部实例
     pub(crate) fn get_instance_list(
         &self,
-        _cluster_names: Vec<String>,
+        cluster_names: Vec<String>,
         only_healthy: bool,
         only_enable: bool,
     ) -> Vec<Arc<Instance>> {
-        self.get_all_instances(only_healthy, only_enable)
+        if cluster_names.is_empty() {
+            return self.get_all_instances(only_healthy, only_enable);
+        }
+        let mut list = vec![];
+        for cluster_name in &cluster_names {
+            if let Some(keys) = self.cluster_map.get(cluster_name) {
+                for key in keys {
+                    if let Some(x) = self.instances.get(key) {
+                        if (x.enabled || !only_enable) && (x.healthy || !only_healthy) {
+                            list.push(x.clone());
+                        }
+                    }
+                }
+            }
+        }
+        list
     }
 
     pub fn get_service_key(&self) -> ServiceKey {
@@ -324,10 +521,11 @@ impl Service {
             group_name: self.group_name.clone(),
             instance_size: self.instance_size,
             healthy_instance_size: self.healthy_instance_size,
-            cluster_count: 0,
+            cluster_count: self.cluster_map.len() as i64,
             trigger_flag: false,
             metadata: Some(self.metadata.clone()),
             protect_threshold: Some(self.protect_threshold),
+            selector: self.selector.clone(),
         }
     }
 
@@ -343,13 +541,33 @@ impl Service {
             group_name: self.group_name.clone(),
             metadata,
             protect_threshold: Some(self.protect_threshold),
+            selector: self.selector.clone(),
+        }
+    }
+
+    pub fn get_export_info(&self) -> NacosServiceExport {
+        let mut clusters: Vec<NacosClusterInfo> = self
+            .cluster_map
+            .keys()
+            .map(|name| NacosClusterInfo::new(self.service_name.clone(), name.to_owned()))
+            .collect();
+        clusters.sort_by(|a, b| a.name.cmp(&b.name));
+        NacosServiceExport {
+            service: NacosServiceInfo {
+                name: self.service_name.clone(),
+                group_name: self.group_name.clone(),
+                protect_threshold: self.protect_threshold,
+                selector: self.selector.clone().unwrap_or_default(),
+                metadata: self.metadata.as_ref().clone(),
+            },
+            clusters,
         }
     }
 
     pub fn get_owner_http_instances(&self) -> Vec<Arc<Instance>> {
         self.instances
             .values()
-            .filter(|x| x.client_id.is_empty())
+            .filter(|x| x.client_id.is_empty() && x.ephemeral)
             .cloned()
             .collect::<Vec<_>>()
     }
@@ -377,4 +595,5 @@ pub struct ServiceInfoDto {
     pub trigger_flag: bool,
     pub metadata: Option<Arc<HashMap<String, String>>>,
     pub protect_threshold: Option<f32>,
+    pub selector: Option<ServiceSelector>,
 }
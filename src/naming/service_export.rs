@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::ServiceDetailDto;
+use super::selector::ServiceSelector;
+use super::NamingUtils;
+
+///
+/// 与nacos控制台服务详情一致的服务定义,用于服务导出与导入;不包含临时实例
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NacosServiceExport {
+    pub service: NacosServiceInfo,
+    #[serde(default)]
+    pub clusters: Vec<NacosClusterInfo>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NacosServiceInfo {
+    pub name: Arc<String>,
+    pub group_name: Arc<String>,
+    #[serde(default)]
+    pub protect_threshold: f32,
+    #[serde(default)]
+    pub selector: ServiceSelector,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+///
+/// 集群信息;rnacos不单独维护集群配置,导出时只包含集群名称
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NacosClusterInfo {
+    pub service_name: Arc<String>,
+    pub name: String,
+    #[serde(default)]
+    pub health_checker: serde_json::Value,
+    #[serde(default)]
+    pub default_port: u32,
+    #[serde(default)]
+    pub default_check_port: u32,
+    #[serde(rename = "useIPPort4Check", default)]
+    pub use_ip_port4_check: bool,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl NacosClusterInfo {
+    pub fn new(service_name: Arc<String>, name: String) -> Self {
+        Self {
+            service_name,
+            name,
+            health_checker: serde_json::json!({"type":"TCP"}),
+            default_port: 80,
+            default_check_port: 80,
+            use_ip_port4_check: true,
+            metadata: Default::default(),
+        }
+    }
+}
+
+impl NacosServiceExport {
+    pub fn to_service_detail(&self, namespace_id: Arc<String>) -> anyhow::Result<ServiceDetailDto> {
+        if self.service.name.is_empty() {
+            return Err(anyhow::anyhow!("service name is empty"));
+        }
+        let group_name = if self.service.group_name.is_empty() {
+            Arc::new(NamingUtils::default_group(String::new()))
+        } else {
+            self.service.group_name.clone()
+        };
+        Ok(ServiceDetailDto {
+            namespace_id,
+            service_name: self.service.name.clone(),
+            group_name,
+            metadata: Some(Arc::new(self.service.metadata.clone())),
+            protect_threshold: Some(self.service.protect_threshold),
+            selector: Some(self.service.selector.clone()),
+        })
+    }
+}
@@ -23,7 +23,7 @@ impl ServiceQueryParam {
         if let Some(group) = &self.group {
             group.is_empty() || StringUtils::eq(g, group)
         } else if let Some(like_group) = &self.like_group {
-            like_group.is_empty() || StringUtils::like(g, like_group).is_some()
+            like_group.is_empty() || StringUtils::like_pattern(g, like_group)
         } else {
             true
         }
@@ -32,7 +32,7 @@ impl ServiceQueryParam {
         if let Some(service) = &self.service {
             service.is_empty() || StringUtils::eq(s, service)
         } else if let Some(like_service) = &self.like_service {
-            like_service.is_empty() || StringUtils::like(s, like_service).is_some()
+            like_service.is_empty() || StringUtils::like_pattern(s, like_service)
         } else {
             true
         }
@@ -108,17 +108,18 @@ impl ServiceIndex {
     pub(crate) fn query_service_page(
         &self,
         namespace_id: &Arc<String>,
+        offset: usize,
         limit: usize,
         param: &ServiceQueryParam,
     ) -> (usize, Vec<ServiceKey>) {
         let mut rlist = vec![];
-        let end_index = param.offset + limit;
+        let end_index = offset.saturating_add(limit);
         let mut index = 0;
         for (g, set) in &self.group_service {
             if param.match_group(g) {
                 for s in set {
                     if param.match_service(s) {
-                        if index >= param.offset && index < end_index {
+                        if index >= offset && index < end_index {
                             let service_key =
                                 ServiceKey::new_by_arc(namespace_id.clone(), g.clone(), s.clone());
                             rlist.push(service_key);
@@ -208,13 +209,16 @@ impl NamespaceIndex {
         let mut limit = param.limit;
         if let Some(namespace_id) = &param.namespace_id {
             if let Some(index) = self.namespace_group.get(namespace_id) {
-                return index.query_service_page(namespace_id, limit, param);
+                return index.query_service_page(namespace_id, param.offset, limit, param);
             }
         } else {
+            //分页偏移量跨命名空间计算
+            let mut offset = param.offset;
             for (namespace_id, service_index) in &self.namespace_group {
                 let (sub_size, mut sub_list) =
-                    service_index.query_service_page(namespace_id, limit, param);
+                    service_index.query_service_page(namespace_id, offset, limit, param);
                 size += sub_size;
+                offset = offset.saturating_sub(sub_size);
                 limit -= sub_list.len();
                 rlist.append(&mut sub_list);
             }
@@ -302,3 +306,35 @@ fn query_service() {
     assert!(size == 0);
     assert!(list.is_empty());
 }
+
+#[test]
+fn query_service_by_pattern() {
+    let mut index = NamespaceIndex::new();
+    index.insert_service(ServiceKey::new("1", "DEFAULT_GROUP", "order-api"));
+    index.insert_service(ServiceKey::new("1", "DEFAULT_GROUP", "order-job"));
+    index.insert_service(ServiceKey::new("1", "pay", "pay-api"));
+    index.insert_service(ServiceKey::new("2", "DEFAULT_GROUP", "user-api"));
+
+    let mut param = ServiceQueryParam {
+        like_service: Some("*-api".to_owned()),
+        limit: 10,
+        ..ServiceQueryParam::default()
+    };
+    let (size, _) = index.query_service_page(&param);
+    assert_eq!(size, 3);
+
+    param.like_service = Some("order".to_owned());
+    param.like_group = Some("DEFAULT?GROUP".to_owned());
+    let (size, _) = index.query_service_page(&param);
+    assert_eq!(size, 2);
+
+    //跨命名空间分页
+    param.like_service = None;
+    param.like_group = None;
+    param.offset = 3;
+    param.limit = 2;
+    let (size, list) = index.query_service_page(&param);
+    assert_eq!(size, 4);
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].service_name.as_str(), "user-api");
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use super::model::Instance;
+
+///
+/// splitmix64,只用于打散实例顺序
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// (0,1]区间的随机数
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+}
+
+pub struct WeightedShuffleUtils;
+
+impl WeightedShuffleUtils {
+    ///
+    /// 按实例权重随机排序,权重越大越可能排在前面;权重不大于0的实例排在最后。
+    /// 指定seed时同一seed得到相同顺序
+    pub fn shuffle(list: Vec<Arc<Instance>>, seed: Option<u64>) -> Vec<Arc<Instance>> {
+        if list.len() < 2 {
+            return list;
+        }
+        let seed = seed.unwrap_or_else(|| uuid::Uuid::new_v4().as_u128() as u64);
+        let mut rng = SplitMix64(seed);
+        let mut items: Vec<(f64, Arc<Instance>)> = list
+            .into_iter()
+            .map(|e| {
+                let u = rng.next_f64();
+                let key = if e.weight > 0f32 {
+                    u.powf(1f64 / e.weight as f64)
+                } else {
+                    u - 1f64
+                };
+                (key, e)
+            })
+            .collect();
+        items.sort_by(|a, b| b.0.total_cmp(&a.0));
+        items.into_iter().map(|(_, e)| e).collect()
+    }
+
+    ///
+    /// 从健康且启用的实例中按权重随机选择一个,与nacos客户端selectOneHealthyInstance一致
+    pub fn select_one(list: &[Arc<Instance>], seed: Option<u64>) -> Option<Arc<Instance>> {
+        let items: Vec<&Arc<Instance>> = list
+            .iter()
+            .filter(|e| e.healthy && e.enabled && e.weight > 0f32)
+            .collect();
+        let total: f64 = items.iter().map(|e| e.weight as f64).sum();
+        if items.is_empty() || total <= 0f64 {
+            return None;
+        }
+        let seed = seed.unwrap_or_else(|| uuid::Uuid::new_v4().as_u128() as u64);
+        let mut point = SplitMix64(seed).next_f64() * total;
+        for item in &items {
+            point -= item.weight as f64;
+            if point <= 0f64 {
+                return Some((*item).clone());
+            }
+        }
+        items.last().map(|e| (*e).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_list() -> Vec<Arc<Instance>> {
+        (0..10)
+            .map(|i| {
+                let mut instance = Instance::new("127.0.0.1".to_owned(), 8000 + i);
+                instance.weight = if i == 0 { 0f32 } else { i as f32 };
+                Arc::new(instance)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_shuffle() {
+        let a = WeightedShuffleUtils::shuffle(build_list(), Some(7));
+        let b = WeightedShuffleUtils::shuffle(build_list(), Some(7));
+        let ports = |l: &Vec<Arc<Instance>>| l.iter().map(|e| e.port).collect::<Vec<_>>();
+        assert_eq!(ports(&a), ports(&b));
+        assert_eq!(a.len(), 10);
+        assert_eq!(a.last().unwrap().port, 8000);
+        let one = WeightedShuffleUtils::select_one(&build_list(), Some(7)).unwrap();
+        assert_ne!(one.port, 8000);
+        assert!(WeightedShuffleUtils::select_one(&build_list()[..1], None).is_none());
+    }
+}
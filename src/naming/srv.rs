@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::model::{Instance, ServiceKey};
+
+/// SRV记录权重的取值上限
+const MAX_SRV_WEIGHT: f32 = 65535f32;
+
+///
+/// 实例权重映射为SRV权重的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SrvWeightStrategy {
+    /// 实例权重乘以100后取整
+    #[default]
+    Scale,
+    /// 所有实例使用相同权重
+    Equal,
+}
+
+impl SrvWeightStrategy {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "equal" => Self::Equal,
+            _ => Self::Scale,
+        }
+    }
+
+    pub fn map_weight(&self, weight: f32) -> u16 {
+        match self {
+            Self::Scale => (weight * 100f32).round().clamp(0f32, MAX_SRV_WEIGHT) as u16,
+            Self::Equal => 1,
+        }
+    }
+}
+
+///
+/// 实例集群映射为SRV优先级的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SrvPriorityStrategy {
+    /// 按请求的集群顺序依次设置优先级,未指定的集群优先级最低
+    #[default]
+    Cluster,
+    /// 所有实例使用相同优先级
+    Flat,
+}
+
+impl SrvPriorityStrategy {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "flat" => Self::Flat,
+            _ => Self::Cluster,
+        }
+    }
+
+    pub fn map_priority(&self, cluster_name: &str, clusters: &[String]) -> u16 {
+        match self {
+            Self::Cluster => clusters
+                .iter()
+                .position(|e| e == cluster_name)
+                .unwrap_or(clusters.len()) as u16,
+            Self::Flat => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SrvRecord {
+    pub name: Arc<String>,
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u32,
+    pub target: Arc<String>,
+    pub ttl: u32,
+}
+
+pub struct SrvRecordUtils;
+
+impl SrvRecordUtils {
+    ///
+    /// SRV记录名称: _{service}._tcp.{group}.{namespace}
+    pub fn build_record_name(key: &ServiceKey) -> String {
+        format!(
+            "_{}._tcp.{}.{}",
+            &key.service_name, &key.group_name, &key.namespace_id
+        )
+    }
+
+    ///
+    /// 把可用实例转换为SRV记录,按优先级与权重排序
+    pub fn build_records(
+        key: &ServiceKey,
+        instances: &[Arc<Instance>],
+        clusters: &[String],
+        weight_strategy: SrvWeightStrategy,
+        priority_strategy: SrvPriorityStrategy,
+        ttl: u32,
+    ) -> Vec<SrvRecord> {
+        let name = Arc::new(Self::build_record_name(key));
+        let mut records: Vec<SrvRecord> = instances
+            .iter()
+            .filter(|e| e.enabled && e.healthy && e.weight > 0f32)
+            .map(|e| SrvRecord {
+                name: name.clone(),
+                priority: priority_strategy.map_priority(&e.cluster_name, clusters),
+                weight: weight_strategy.map_weight(e.weight),
+                port: e.port,
+                target: e.ip.clone(),
+                ttl,
+            })
+            .collect();
+        records.sort_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then(b.weight.cmp(&a.weight))
+                .then(a.target.cmp(&b.target))
+        });
+        records
+    }
+}
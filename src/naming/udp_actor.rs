@@ -14,6 +14,19 @@ use tokio::sync::Mutex;
 use super::listener::{InnerNamingListener, NamingListenerCmd};
 
 const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+///
+/// 解析1.x客户端的push-ack: {"type":"push-ack","lastRefTime":"...","data":""}
+pub(crate) fn parse_ack(data: &[u8]) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_slice(data).ok()?;
+    if value.get("type")?.as_str()? != "push-ack" {
+        return None;
+    }
+    match value.get("lastRefTime")? {
+        serde_json::Value::String(v) => v.parse().ok(),
+        v => v.as_u64(),
+    }
+}
 pub struct UdpWorker {
     local_addr_str: Option<String>,
     socket: Option<Arc<UdpSocket>>,
@@ -79,11 +92,11 @@ impl UdpWorker {
             if buf.len() < MAX_DATAGRAM_SIZE {
                 buf = vec![0u8; MAX_DATAGRAM_SIZE];
             }
-            while let Ok((_len, addr)) = socket.recv_from(&mut buf).await {
+            while let Ok((len, addr)) = socket.recv_from(&mut buf).await {
                 //let mut data:Vec<u8> = Vec::with_capacity(len);
                 //let mut data: Vec<u8> = vec![0u8; len];
                 //data.clone_from_slice(&buf[..len]);
-                let msg = NamingListenerCmd::Response(addr.to_owned());
+                let msg = NamingListenerCmd::Response(addr.to_owned(), parse_ack(&buf[..len]));
                 //let s=String::from_utf8_lossy(&buf[..len]);
                 //println!("rece from:{} | len:{} | str:{}",&addr,len,s);
                 if let Some(_notify_addr) = &notify_addr {
@@ -172,3 +185,19 @@ impl Handler<UdpWorkerCmd> for UdpWorker {
         Ok(UdpWorkerResult::None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ack() {
+        let ack = r#"{"type": "push-ack", "lastRefTime":"1700000000000000000", "data":""}"#;
+        assert_eq!(parse_ack(ack.as_bytes()), Some(1700000000000000000));
+        assert_eq!(
+            parse_ack(br#"{"type":"unknown-ack","lastRefTime":1}"#),
+            None
+        );
+        assert_eq!(parse_ack(b"abc"), None);
+    }
+}
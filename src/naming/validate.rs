@@ -0,0 +1,207 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use super::model::{Instance, ServiceKey};
+
+pub const MAX_NAMESPACE_LEN: usize = 128;
+pub const MAX_GROUP_LEN: usize = 128;
+pub const MAX_SERVICE_LEN: usize = 512;
+pub const MAX_CLUSTER_LEN: usize = 64;
+pub const MAX_HOST_LEN: usize = 253;
+pub const MAX_WEIGHT: f32 = 10000f32;
+/// metadata所有key与value的总字节数上限
+pub const MAX_METADATA_SIZE: usize = 16 * 1024;
+
+///
+/// 服务注册参数校验错误,http接口以json形式返回
+#[derive(Debug, Clone, Serialize, Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{field} is invalid, {message}")]
+pub struct NamingValidateError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl NamingValidateError {
+    fn new(field: &'static str, message: String) -> Self {
+        Self { field, message }
+    }
+}
+
+pub struct NamingValidateUtils;
+
+impl NamingValidateUtils {
+    fn is_name_char(c: char, extra_chars: &str) -> bool {
+        c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || extra_chars.contains(c)
+    }
+
+    fn normalize_name(
+        field: &'static str,
+        value: &Arc<String>,
+        max_len: usize,
+        extra_chars: &str,
+    ) -> Result<Arc<String>, NamingValidateError> {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return Err(NamingValidateError::new(field, "is empty".to_owned()));
+        }
+        if trimmed.len() > max_len {
+            return Err(NamingValidateError::new(
+                field,
+                format!("length over {}", max_len),
+            ));
+        }
+        if let Some(c) = trimmed
+            .chars()
+            .find(|c| !Self::is_name_char(*c, extra_chars))
+        {
+            return Err(NamingValidateError::new(
+                field,
+                format!("contains unsupported char '{}'", c),
+            ));
+        }
+        if trimmed.len() == value.len() {
+            Ok(value.clone())
+        } else {
+            Ok(Arc::new(trimmed.to_owned()))
+        }
+    }
+
+    pub fn normalize_namespace(value: &Arc<String>) -> Result<Arc<String>, NamingValidateError> {
+        Self::normalize_name("namespaceId", value, MAX_NAMESPACE_LEN, "")
+    }
+
+    pub fn normalize_group(value: &Arc<String>) -> Result<Arc<String>, NamingValidateError> {
+        Self::normalize_name("groupName", value, MAX_GROUP_LEN, ":")
+    }
+
+    pub fn normalize_service(value: &Arc<String>) -> Result<Arc<String>, NamingValidateError> {
+        let value = Self::normalize_name("serviceName", value, MAX_SERVICE_LEN, ":@")?;
+        if value.contains("@@") {
+            return Err(NamingValidateError::new(
+                "serviceName",
+                "contains group separator '@@'".to_owned(),
+            ));
+        }
+        Ok(value)
+    }
+
+    ///
+    /// ip地址统一为标准格式;非ip地址时按主机名校验
+    pub fn normalize_ip(value: &Arc<String>) -> Result<Arc<String>, NamingValidateError> {
+        let trimmed = value.trim();
+        if let Ok(ip) = trimmed.parse::<IpAddr>() {
+            let ip = ip.to_string();
+            if ip.as_str() == value.as_str() {
+                return Ok(value.clone());
+            }
+            return Ok(Arc::new(ip));
+        }
+        let host = Self::normalize_name("ip", value, MAX_HOST_LEN, "")?;
+        if host.starts_with('-') || host.starts_with('.') || host.ends_with('-') {
+            return Err(NamingValidateError::new(
+                "ip",
+                "is not a valid ip or host".to_owned(),
+            ));
+        }
+        Ok(host)
+    }
+
+    pub fn check_port(port: u32) -> Result<(), NamingValidateError> {
+        if port == 0 || port > u16::MAX as u32 {
+            return Err(NamingValidateError::new(
+                "port",
+                format!("{} is out of range 1-65535", port),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn check_weight(weight: f32) -> Result<(), NamingValidateError> {
+        if !weight.is_finite() || !(0f32..=MAX_WEIGHT).contains(&weight) {
+            return Err(NamingValidateError::new(
+                "weight",
+                format!("{} is out of range 0-{}", weight, MAX_WEIGHT),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn check_metadata(instance: &Instance) -> Result<(), NamingValidateError> {
+        let size: usize = instance
+            .metadata
+            .iter()
+            .map(|(k, v)| k.len() + v.len())
+            .sum();
+        if size > MAX_METADATA_SIZE {
+            return Err(NamingValidateError::new(
+                "metadata",
+                format!("size {} over {}", size, MAX_METADATA_SIZE),
+            ));
+        }
+        Ok(())
+    }
+
+    ///
+    /// 校验服务key并转换为标准格式
+    pub fn normalize_service_key(key: &ServiceKey) -> Result<ServiceKey, NamingValidateError> {
+        Ok(ServiceKey::new_by_arc(
+            Self::normalize_namespace(&key.namespace_id)?,
+            Self::normalize_group(&key.group_name)?,
+            Self::normalize_service(&key.service_name)?,
+        ))
+    }
+
+    ///
+    /// 校验注册实例并转换为标准格式;http与grpc入口统一使用,避免写入无法删除的异常数据
+    pub fn normalize_instance(instance: &mut Instance) -> Result<(), NamingValidateError> {
+        instance.namespace_id = Self::normalize_namespace(&instance.namespace_id)?;
+        instance.group_name = Self::normalize_group(&instance.group_name)?;
+        instance.service_name = Self::normalize_service(&instance.service_name)?;
+        let cluster_name = Self::normalize_name(
+            "clusterName",
+            &Arc::new(instance.cluster_name.clone()),
+            MAX_CLUSTER_LEN,
+            "",
+        )?;
+        instance.cluster_name = cluster_name.as_ref().to_owned();
+        instance.ip = Self::normalize_ip(&instance.ip)?;
+        Self::check_port(instance.port)?;
+        Self::check_weight(instance.weight)?;
+        Self::check_metadata(instance)?;
+        instance.generate_key();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_instance() {
+        let mut instance = Instance::new(" 127.0.0.1 ".to_owned(), 8080);
+        instance.namespace_id = Arc::new("public".to_owned());
+        instance.group_name = Arc::new("DEFAULT_GROUP ".to_owned());
+        instance.service_name = Arc::new("providers:com.demo.Api:1.0:".to_owned());
+        assert!(NamingValidateUtils::normalize_instance(&mut instance).is_ok());
+        assert_eq!(instance.ip.as_str(), "127.0.0.1");
+        assert_eq!(instance.group_name.as_str(), "DEFAULT_GROUP");
+        assert_eq!(
+            instance.id.as_str(),
+            "127.0.0.1#8080#DEFAULT#DEFAULT_GROUP@@providers:com.demo.Api:1.0:"
+        );
+
+        instance.service_name = Arc::new("demo service".to_owned());
+        let err = NamingValidateUtils::normalize_instance(&mut instance).unwrap_err();
+        assert_eq!(err.field, "serviceName");
+
+        instance.service_name = Arc::new("demo".to_owned());
+        instance.port = 70000;
+        let err = NamingValidateUtils::normalize_instance(&mut instance).unwrap_err();
+        assert_eq!(err.field, "port");
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::model::Instance;
+
+/// 服务metadata中设置的实例预热时长(毫秒)
+pub const WARMUP_TIME_KEY: &str = "preserved.warmup.time";
+/// 预热期内的最小权重比例,避免新实例完全没有流量
+const MIN_WARMUP_RATIO: f32 = 0.01;
+
+pub struct WarmupUtils;
+
+impl WarmupUtils {
+    pub fn get_warmup_millis(service_metadata: &HashMap<String, String>) -> Option<u64> {
+        service_metadata
+            .get(WARMUP_TIME_KEY)
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+    }
+
+    ///
+    /// 按注册后的时长线性增加权重,预热结束后为原权重
+    pub fn get_effective_weight(weight: f32, register_time: u64, warmup: u64, now: u64) -> f32 {
+        if register_time == 0 || warmup == 0 || now >= register_time + warmup {
+            return weight;
+        }
+        let ratio = now.saturating_sub(register_time) as f32 / warmup as f32;
+        weight * ratio.max(MIN_WARMUP_RATIO)
+    }
+
+    ///
+    /// 按实例首次注册时间调整预热期内实例的权重
+    pub fn apply(list: Vec<Arc<Instance>>, warmup: u64, now: u64) -> Vec<Arc<Instance>> {
+        list.into_iter()
+            .map(|e| {
+                let weight = Self::get_effective_weight(e.weight, e.register_time, warmup, now);
+                if weight == e.weight {
+                    e
+                } else {
+                    let mut instance = e.as_ref().clone();
+                    instance.weight = weight;
+                    Arc::new(instance)
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_weight() {
+        assert_eq!(
+            WarmupUtils::get_effective_weight(2f32, 1000, 10000, 6000),
+            1f32
+        );
+        assert_eq!(
+            WarmupUtils::get_effective_weight(2f32, 1000, 10000, 11000),
+            2f32
+        );
+        assert_eq!(
+            WarmupUtils::get_effective_weight(1f32, 1000, 10000, 1000),
+            0.01f32
+        );
+        let mut metadata = HashMap::new();
+        metadata.insert(WARMUP_TIME_KEY.to_owned(), "60000".to_owned());
+        assert_eq!(WarmupUtils::get_warmup_millis(&metadata), Some(60000));
+
+        let mut instance = Instance::new("127.0.0.1".to_owned(), 8080);
+        instance.weight = 2f32;
+        instance.register_time = 1000;
+        //版本号变化不影响预热
+        instance.revision = 9000;
+        let list = WarmupUtils::apply(vec![Arc::new(instance)], 10000, 6000);
+        assert_eq!(list[0].weight, 1f32);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::model::Instance;
+
+/// 节点与实例所在可用区的标签名;实例通过metadata中的zone声明
+pub const ZONE_LABEL: &str = "zone";
+/// 节点间请求中携带节点标签的扩展信息key
+pub const NODE_LABELS_EXTEND_KEY: &str = "node_labels";
+/// nacos客户端grpc端口相对主端口的偏移量
+const GRPC_PORT_OFFSET: u16 = 1000;
+
+pub struct ZoneUtils;
+
+impl ZoneUtils {
+    ///
+    /// 解析标签,格式: zone=az1,rack=r1
+    pub fn parse_labels(value: &str) -> HashMap<String, String> {
+        let mut labels = HashMap::new();
+        for item in value.split(',') {
+            if let Some((k, v)) = item.split_once('=') {
+                let (k, v) = (k.trim(), v.trim());
+                if !k.is_empty() && !v.is_empty() {
+                    labels.insert(k.to_owned(), v.to_owned());
+                }
+            }
+        }
+        labels
+    }
+
+    pub fn get_zone(labels: &HashMap<String, String>) -> Option<&String> {
+        labels.get(ZONE_LABEL)
+    }
+
+    ///
+    /// 同可用区存在可用实例时只返回同可用区的实例,否则返回全部实例
+    pub fn prefer_zone(list: Vec<Arc<Instance>>, zone: &str) -> Vec<Arc<Instance>> {
+        if zone.is_empty() {
+            return list;
+        }
+        let is_same_zone = |e: &Arc<Instance>| {
+            e.metadata
+                .get(ZONE_LABEL)
+                .map(|v| v == zone)
+                .unwrap_or(false)
+        };
+        if !list
+            .iter()
+            .any(|e| e.enabled && e.healthy && is_same_zone(e))
+        {
+            return list;
+        }
+        list.into_iter().filter(is_same_zone).collect()
+    }
+
+    ///
+    /// 节点地址为grpc地址,转换为客户端重连使用的ip与主端口
+    pub fn to_client_server_addr(node_addr: &str) -> Option<(String, String)> {
+        let (ip, port) = node_addr.rsplit_once(':')?;
+        let port: u16 = port.parse().ok()?;
+        Some((
+            ip.to_owned(),
+            port.saturating_sub(GRPC_PORT_OFFSET).to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_labels() {
+        let labels = ZoneUtils::parse_labels("zone=az1, rack = r1,,invalid,empty=");
+        assert_eq!(labels.len(), 2);
+        assert_eq!(ZoneUtils::get_zone(&labels).unwrap(), "az1");
+        assert_eq!(labels.get("rack").unwrap(), "r1");
+        assert_eq!(
+            ZoneUtils::to_client_server_addr("127.0.0.1:9848"),
+            Some(("127.0.0.1".to_owned(), "8848".to_owned()))
+        );
+    }
+}
@@ -1,14 +1,29 @@
 use crate::common::appdata::AppShareData;
-use crate::common::model::TokenSession;
+use crate::common::model::{TokenSession, UserSession};
 use crate::common::option_utils::OptionUtils;
+use crate::common::AppSysConfig;
 use crate::merge_web_param_with_result;
 use crate::raft::cache::model::{CacheKey, CacheType, CacheValue};
 use crate::raft::cache::{CacheLimiterReq, CacheManagerReq, CacheManagerResult};
+use crate::user::permission::{UserRoleHelper, USER_ROLE_MANAGER};
 use crate::user::{UserManagerReq, UserManagerResult};
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+///
+/// 请求方是否为管理员;未开启接口鉴权时没有会话信息,视为管理员
+pub(crate) fn is_manager_request(req: &HttpRequest, sys_config: &AppSysConfig) -> bool {
+    let extensions = req.extensions();
+    if let Some(session) = extensions.get::<Arc<UserSession>>() {
+        UserRoleHelper::is_manager(&session.roles)
+    } else if let Some(session) = extensions.get::<Arc<TokenSession>>() {
+        UserRoleHelper::is_manager(&session.roles)
+    } else {
+        !sys_config.openapi_enable_auth
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginParams {
@@ -31,6 +46,8 @@ pub struct LoginResult {
     pub access_token: Option<Arc<String>>,
     pub token_ttl: i64,
     pub global_admin: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<Arc<String>>,
 }
 
 const UNKNOWN_USER: &str = "unknown user!";
@@ -92,9 +109,11 @@ async fn do_login(
                 uuid::Uuid::new_v4().to_string().replace('-', "")
                     + &uuid::Uuid::new_v4().to_string().replace('-', ""),
             );
+            let roles = user.roles.unwrap_or_default();
+            let global_admin = roles.contains(&USER_ROLE_MANAGER);
             let session = Arc::new(TokenSession {
-                username: user.username,
-                roles: user.roles.unwrap_or_default(),
+                username: user.username.clone(),
+                roles,
                 extend_infos: user.extend_info.unwrap_or_default(),
             });
             let cache_req = CacheManagerReq::Set {
@@ -110,7 +129,8 @@ async fn do_login(
             let login_result = LoginResult {
                 access_token: Some(token),
                 token_ttl: app.sys_config.openapi_login_timeout as i64,
-                global_admin: false,
+                global_admin,
+                username: Some(user.username),
             };
             return Ok(HttpResponse::Ok().json(login_result));
         } else {
@@ -1,29 +1,46 @@
 use std::collections::HashMap;
+use std::io::Cursor;
 use std::sync::Arc;
 
 use actix::Addr;
-use actix_web::{web, HttpRequest, HttpResponse, Responder, Scope};
+use actix_multipart::Multipart;
+use actix_web::http::header;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder, Scope};
 use chrono::Local;
 use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt;
 
 use crate::common::appdata::AppShareData;
+use crate::common::constant::LOCAL_CLIENT_IP;
 use crate::common::model::ApiResult;
+use crate::common::model::{TokenSession, UserSession};
 use crate::common::option_utils::OptionUtils;
 use crate::common::string_utils::StringUtils;
 use crate::common::web_utils::get_req_body;
+use crate::config::beta::{ConfigBetaDto, ConfigBetaUtils};
 use crate::config::config_index::ConfigQueryParam;
 use crate::config::config_type::ConfigType;
 use crate::config::core::{
-    AppName, ConfigActor, ConfigCmd, ConfigInfoDto, ConfigKey, ConfigListenerInfo, ConfigResult,
-    ListenerItem, ListenerResult,
+    AppName, ConfigActor, ConfigAggregateItem, ConfigCmd, ConfigInfoDto, ConfigKey,
+    ConfigListenerInfo, ConfigResult, ListenerItem, ListenerResult,
 };
+use crate::config::dry_run::ConfigDryRunUtils;
+use crate::config::freeze::ConfigFreezeUtils;
+use crate::config::nacos_zip::{ConfigImportPolicy, ConfigImportResult, NacosZipUtils};
+use crate::config::tag::ConfigTagUtils;
 use crate::config::utils::param_utils;
+use crate::config::validation::ConfigValidationUtils;
 use crate::config::ConfigUtils;
 use crate::console::v2::ERROR_CODE_SYSTEM_ERROR;
+use crate::console::NamespaceUtils;
 use crate::merge_web_param;
+use crate::now_millis;
+use crate::openapi::auth::is_manager_request;
 use crate::openapi::constant::EMPTY;
+use crate::raft::cluster::ack::{WriteAckLevel, WriteAckUtils};
 use crate::raft::cluster::model::{DelConfigReq, SetConfigReq};
-use crate::utils::select_option_by_clone;
+use crate::raft::freshness::{StaleReadGuard, DATA_TIME_HEADER};
+use crate::utils::{get_bool_from_string, select_option_by_clone};
 
 pub(super) fn service() -> Scope {
     web::scope("/configs")
@@ -34,7 +51,124 @@ pub(super) fn service() -> Scope {
                 .route(web::put().to(add_config))
                 .route(web::delete().to(del_config)),
         )
-        .service(web::resource("/listener").route(web::post().to(listener_config)))
+        .service(
+            web::resource("/listener")
+                .route(web::get().to(query_config_listeners))
+                .route(web::post().to(listener_config)),
+        )
+        .service(web::resource("/snapshot").route(web::get().to(download_config_snapshot)))
+        .service(web::resource("/aggregate").route(web::get().to(query_aggregate_configs)))
+}
+
+/// 批量查询一次最多返回的配置数量
+const CONFIG_AGGREGATE_MAX_SIZE: usize = 1000;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAggregateParams {
+    pub tenant: Option<String>,
+    pub group: Option<String>,
+    /// dataId前缀,包含`*`、`?`时按通配符匹配
+    pub data_id: Option<String>,
+    /// 为false时只返回dataId与md5
+    pub with_content: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAggregateResponse {
+    pub tenant: String,
+    pub group: String,
+    pub items: Vec<ConfigAggregateItem>,
+}
+
+///
+/// 一次查询分组下dataId匹配前缀或通配符的全部配置,供sidecar启动时批量拉取
+async fn query_aggregate_configs(
+    req: HttpRequest,
+    web::Query(param): web::Query<ConfigAggregateParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let data_id = param.data_id.unwrap_or_default();
+    if data_id.is_empty() {
+        return HttpResponse::BadRequest().body("dataId is empty");
+    }
+    let pattern = if data_id.contains(['*', '?']) {
+        data_id
+    } else {
+        format!("{}*", data_id)
+    };
+    if let Err(staleness) = StaleReadGuard::check(&appdata, &req) {
+        return HttpResponse::ServiceUnavailable()
+            .body(format!("local data is stale, staleness {}ms", staleness));
+    }
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let group = param
+        .group
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "DEFAULT_GROUP".to_owned());
+    let query_param = ConfigQueryParam {
+        tenant: Some(Arc::new(tenant.clone())),
+        group: Some(Arc::new(group.clone())),
+        data_id_pattern: Some(pattern),
+        query_context: get_bool_from_string(&param.with_content, true),
+        limit: CONFIG_AGGREGATE_MAX_SIZE,
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryAggregate(Box::new(query_param), Arc::new(get_operator(&req)));
+    match appdata.config_addr.send(cmd).await {
+        Ok(Ok(ConfigResult::AggregateConfigs(items))) => {
+            HttpResponse::Ok().json(ConfigAggregateResponse {
+                tenant,
+                group,
+                items,
+            })
+        }
+        Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        _ => HttpResponse::InternalServerError().body("config result error"),
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSnapshotParams {
+    pub tenant: Option<String>,
+    pub group: Option<String>,
+}
+
+///
+/// 打包下载命名空间(可选分组)下的全部配置,格式与控制台导出一致;
+/// 用于初始化新环境或离线客户端的本地缓存
+async fn download_config_snapshot(
+    appdata: web::Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<ConfigSnapshotParams>,
+) -> impl Responder {
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let query_param = ConfigQueryParam {
+        tenant: Some(Arc::new(tenant.clone())),
+        group: StringUtils::map_not_empty(param.group).map(Arc::new),
+        query_context: true,
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryPageInfo(Box::new(query_param));
+    match appdata.config_addr.send(cmd).await {
+        Ok(Ok(ConfigResult::ConfigInfoPage(_, list))) => match NacosZipUtils::build_zip(&list) {
+            Ok(buf) => {
+                let namespace = if tenant.is_empty() { "public" } else { &tenant };
+                let filename = format!("rnacos_config_snapshot_{}_{}.zip", namespace, now_millis());
+                HttpResponse::Ok()
+                    .insert_header(header::ContentType::octet_stream())
+                    .insert_header(header::ContentDisposition::attachment(filename))
+                    .body(buf)
+            }
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+        Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        _ => HttpResponse::InternalServerError().body("config result error"),
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -49,6 +183,27 @@ pub struct ConfigWebParams {
     pub search: Option<String>,   //search type
     pub page_no: Option<usize>,   //use at search
     pub page_size: Option<usize>, //use at search
+    /// 为true时只预演发布,返回影响报告
+    pub dry_run: Option<String>,
+    /// 写入确认级别: committed(默认,leader为其别名)、majority
+    pub ack_level: Option<String>,
+    /// 为true时查询或删除beta配置
+    pub beta: Option<String>,
+    /// 按tag发布、查询或删除配置
+    pub tag: Option<String>,
+    /// 为true时跳过配置内容格式校验
+    pub force: Option<String>,
+    /// 为true时按nacos格式导出配置包
+    pub export: Option<String>,
+    pub export_v2: Option<String>,
+    /// nacos按配置id导出;配置没有数值id,指定时返回错误
+    pub ids: Option<String>,
+    /// 为true时导入nacos格式的配置包
+    pub import: Option<String>,
+    /// 导入的目标命名空间
+    pub namespace: Option<String>,
+    /// 导入时配置已存在的处理策略: ABORT(默认)、SKIP、OVERWRITE
+    pub policy: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -100,6 +255,17 @@ impl ConfigWebParams {
             search: OptionUtils::select(self.search, other.search),
             page_no: OptionUtils::select(self.page_no, other.page_no),
             page_size: OptionUtils::select(self.page_size, other.page_size),
+            dry_run: OptionUtils::select(self.dry_run, other.dry_run),
+            ack_level: OptionUtils::select(self.ack_level, other.ack_level),
+            beta: OptionUtils::select(self.beta, other.beta),
+            tag: OptionUtils::select(self.tag, other.tag),
+            force: OptionUtils::select(self.force, other.force),
+            export: OptionUtils::select(self.export, other.export),
+            export_v2: OptionUtils::select(self.export_v2, other.export_v2),
+            ids: OptionUtils::select(self.ids, other.ids),
+            import: OptionUtils::select(self.import, other.import),
+            namespace: OptionUtils::select(self.namespace, other.namespace),
+            policy: OptionUtils::select(self.policy, other.policy),
         }
     }
 
@@ -175,23 +341,52 @@ pub struct ConfigWebConfirmedParam {
 }
 
 pub(crate) async fn add_config(
+    req: HttpRequest,
     a: web::Query<ConfigWebParams>,
     payload: web::Payload,
     appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
+    if get_bool_from_string(&a.import, false) {
+        return import_nacos_config(&req, a.0, payload, &appdata).await;
+    }
     let selected_param = merge_web_param!(a.0, payload);
+    if get_bool_from_string(&selected_param.dry_run, false) {
+        return match selected_param.to_confirmed_param() {
+            Ok(p) => {
+                let key = ConfigKey::new(&p.data_id, &p.group, &p.tenant);
+                let config_type = selected_param.r#type.as_deref().filter(|v| !v.is_empty());
+                match ConfigDryRunUtils::publish_dry_run(
+                    &appdata,
+                    key,
+                    Arc::new(p.content),
+                    config_type,
+                )
+                .await
+                {
+                    Ok(impact) => HttpResponse::Ok().json(impact),
+                    Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+                }
+            }
+            Err(e) => HttpResponse::InternalServerError().body(e),
+        };
+    }
     match param_utils::check_tenant(&selected_param.tenant) {
         Ok(v) => v,
         Err(err) => {
             return HttpResponse::InternalServerError().body(err.to_string());
         }
     }
+    let ack_level = match WriteAckLevel::parse(&selected_param.ack_level) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
 
     match param_utils::check_param(
         &selected_param.data_id,
         &selected_param.group,
         &Some(String::from("datumId")),
         &selected_param.content,
+        appdata.cluster_settings.config_max_content(),
     ) {
         Ok(v) => v,
         Err(err) => {
@@ -199,18 +394,51 @@ pub(crate) async fn add_config(
         }
     }
 
+    let tag = match get_tag(&selected_param.tag) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
     let config_type = StringUtils::map_not_empty(selected_param.r#type.clone());
+    let force = get_bool_from_string(&selected_param.force, false);
+    //写入时会统一校验,这里提前校验以返回400
+    if let (Some(config_type), false) = (&config_type, force) {
+        let content = selected_param.content.as_deref().unwrap_or_default();
+        if let Err(err) = ConfigValidationUtils::validate(config_type, content) {
+            return HttpResponse::BadRequest().body(err.to_string());
+        }
+    }
     let desc = StringUtils::map_not_empty(selected_param.desc.clone());
     let param = selected_param.to_confirmed_param();
     match param {
         Ok(p) => {
-            let mut req = SetConfigReq::new(
-                ConfigKey::new(&p.data_id, &p.group, &p.tenant),
-                Arc::new(p.content.to_owned()),
-            );
-            req.config_type = config_type.map(|v| ConfigType::new_by_value(v.as_ref()).get_value());
-            req.desc = desc.map(Arc::new);
-            match appdata.config_route.set_config(req).await {
+            let config_key = ConfigKey::new(&p.data_id, &p.group, &p.tenant);
+            if let Err(err) = param_utils::check_reserved(&config_key, false, false)
+                .and_then(|_| check_namespace_write(&req, &appdata, &config_key.tenant))
+            {
+                return HttpResponse::BadRequest().body(err.to_string());
+            }
+            if let Err(err) =
+                ConfigFreezeUtils::check(&appdata, &config_key, &get_operator(&req), false).await
+            {
+                return HttpResponse::BadRequest().body(err.to_string());
+            }
+            let beta_ips = get_beta_ips(&req);
+            if !beta_ips.is_empty() {
+                return publish_beta(&appdata, ack_level, &config_key, p.content, beta_ips).await;
+            }
+            let config_key = match &tag {
+                Some(tag) => ConfigTagUtils::build_tag_key(&config_key, tag),
+                None => config_key,
+            };
+            let mut set_req = SetConfigReq::new(config_key, Arc::new(p.content.to_owned()));
+            set_req.config_type =
+                config_type.map(|v| ConfigType::new_by_value(v.as_ref()).get_value());
+            set_req.desc = desc.map(Arc::new);
+            set_req.op_user = get_session_user(&req);
+            set_req.skip_validation = force;
+            let config_route = appdata.config_route.clone();
+            let write = async move { config_route.set_config(set_req).await };
+            match WriteAckUtils::write(&appdata, ack_level, write).await {
                 Ok(_) => HttpResponse::Ok()
                     .content_type("text/html; charset=utf-8")
                     .body("true"),
@@ -222,6 +450,7 @@ pub(crate) async fn add_config(
 }
 
 pub(crate) async fn del_config(
+    req: HttpRequest,
     a: web::Query<ConfigWebParams>,
     payload: web::Payload,
     appdata: web::Data<Arc<AppShareData>>,
@@ -233,12 +462,17 @@ pub(crate) async fn del_config(
             return HttpResponse::InternalServerError().body(err.to_string());
         }
     }
+    let ack_level = match WriteAckLevel::parse(&selected_param.ack_level) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
 
     match param_utils::check_param(
         &selected_param.data_id,
         &selected_param.group,
         &Some(String::from("datumId")),
         &Some(String::from("rm")),
+        appdata.cluster_settings.config_max_content(),
     ) {
         Ok(v) => v,
         Err(err) => {
@@ -246,11 +480,38 @@ pub(crate) async fn del_config(
         }
     }
 
+    let tag = match get_tag(&selected_param.tag) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
     let param = selected_param.to_confirmed_param();
     match param {
         Ok(p) => {
-            let req = DelConfigReq::new(ConfigKey::new(&p.data_id, &p.group, &p.tenant));
-            match appdata.config_route.del_config(req).await {
+            let config_key = ConfigKey::new(&p.data_id, &p.group, &p.tenant);
+            if let Err(err) = param_utils::check_reserved(
+                &config_key,
+                true,
+                appdata.cluster_settings.config_protect_cipher_delete(),
+            )
+            .and_then(|_| check_namespace_write(&req, &appdata, &config_key.tenant))
+            {
+                return HttpResponse::BadRequest().body(err.to_string());
+            }
+            if let Err(err) =
+                ConfigFreezeUtils::check(&appdata, &config_key, &get_operator(&req), false).await
+            {
+                return HttpResponse::BadRequest().body(err.to_string());
+            }
+            let del_req = if get_bool_from_string(&selected_param.beta, false) {
+                DelConfigReq::new(ConfigBetaUtils::build_beta_key(&config_key))
+            } else if let Some(tag) = &tag {
+                DelConfigReq::new(ConfigTagUtils::build_tag_key(&config_key, tag))
+            } else {
+                DelConfigReq::new(config_key)
+            };
+            let config_route = appdata.config_route.clone();
+            let write = async move { config_route.del_config(del_req).await };
+            match WriteAckUtils::write(&appdata, ack_level, write).await {
                 Ok(_) => HttpResponse::Ok()
                     .content_type("text/html; charset=utf-8")
                     .body("true"),
@@ -261,10 +522,221 @@ pub(crate) async fn del_config(
     }
 }
 
+fn get_tag(tag: &Option<String>) -> anyhow::Result<Option<String>> {
+    match StringUtils::map_not_empty(tag.clone()) {
+        Some(tag) => {
+            ConfigTagUtils::check_tag(&tag)?;
+            Ok(Some(tag))
+        }
+        None => Ok(None),
+    }
+}
+
+///
+/// 与nacos一致,通过betaIps请求头发布beta配置
+fn get_beta_ips(req: &HttpRequest) -> Vec<String> {
+    req.headers()
+        .get("betaIps")
+        .and_then(|v| v.to_str().ok())
+        .map(ConfigBetaUtils::parse_beta_ips)
+        .unwrap_or_default()
+}
+
+async fn publish_beta(
+    appdata: &Arc<AppShareData>,
+    ack_level: WriteAckLevel,
+    config_key: &ConfigKey,
+    content: String,
+    beta_ips: Vec<String>,
+) -> HttpResponse {
+    let dto = ConfigBetaDto {
+        content: Arc::new(content),
+        beta_ips,
+        ..Default::default()
+    };
+    let value = match serde_json::to_string(&dto) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    };
+    let set_req = SetConfigReq::new(ConfigBetaUtils::build_beta_key(config_key), Arc::new(value));
+    let config_route = appdata.config_route.clone();
+    let write = async move { config_route.set_config(set_req).await };
+    match WriteAckUtils::write(appdata, ack_level, write).await {
+        Ok(_) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body("true"),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+async fn get_beta_config(appdata: &Arc<AppShareData>, config_key: &ConfigKey) -> HttpResponse {
+    let cmd = ConfigCmd::GET(ConfigBetaUtils::build_beta_key(config_key));
+    let beta = match appdata.config_addr.send(cmd).await {
+        Ok(Ok(ConfigResult::Data { value, .. })) => {
+            serde_json::from_str::<ConfigBetaDto>(&value).ok()
+        }
+        _ => None,
+    };
+    HttpResponse::Ok().json(ApiResult::success(beta))
+}
+
+///
+/// 配置变更操作人,记录在变更冻结日志中
+fn get_session_user(req: &HttpRequest) -> Option<Arc<String>> {
+    let extensions = req.extensions();
+    if let Some(session) = extensions.get::<Arc<UserSession>>() {
+        return Some(session.username.clone());
+    }
+    extensions
+        .get::<Arc<TokenSession>>()
+        .map(|v| v.username.clone())
+}
+
+///
+/// 系统命名空间中的配置只有管理员可以修改
+fn check_namespace_write(
+    req: &HttpRequest,
+    appdata: &AppShareData,
+    namespace_id: &str,
+) -> anyhow::Result<()> {
+    NamespaceUtils::check_config_write(
+        &appdata.sys_config,
+        namespace_id,
+        is_manager_request(req, &appdata.sys_config),
+    )
+}
+
+///
+/// 开启鉴权时为token对应的用户名,否则为客户端ip
+fn get_operator(req: &HttpRequest) -> String {
+    if let Some(username) = get_session_user(req) {
+        return username.as_ref().to_owned();
+    }
+    req.connection_info()
+        .realip_remote_addr()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+///
+/// 与nacos导入接口一致的返回格式
+#[derive(Serialize, Deserialize, Default)]
+pub struct ConfigImportResponse {
+    pub code: u16,
+    pub message: String,
+    pub data: Option<ConfigImportResult>,
+}
+
+impl ConfigImportResponse {
+    fn error(message: String) -> HttpResponse {
+        HttpResponse::Ok().json(Self {
+            code: 400,
+            message,
+            data: None,
+        })
+    }
+}
+
+///
+/// 兼容nacos的配置导出(export=true或exportV2=true),导出包含元数据的zip包
+async fn export_nacos_config(param: ConfigWebParams, appdata: &AppShareData) -> HttpResponse {
+    if StringUtils::map_not_empty(param.ids.clone()).is_some() {
+        return HttpResponse::BadRequest()
+            .body("export by ids is not supported, please use group and dataId");
+    }
+    let tenant = ConfigUtils::default_tenant(param.tenant.unwrap_or_default());
+    let query_param = ConfigQueryParam {
+        tenant: Some(Arc::new(tenant)),
+        group: StringUtils::map_not_empty(param.group).map(Arc::new),
+        data_id: StringUtils::map_not_empty(param.data_id).map(Arc::new),
+        query_context: true,
+        limit: 0xffff_ffff,
+        ..Default::default()
+    };
+    let cmd = ConfigCmd::QueryPageInfo(Box::new(query_param));
+    match appdata.config_addr.send(cmd).await {
+        Ok(Ok(ConfigResult::ConfigInfoPage(_, list))) => match NacosZipUtils::build_zip(&list) {
+            Ok(buf) => {
+                let filename = format!("nacos_config_export_{}.zip", now_millis());
+                HttpResponse::Ok()
+                    .insert_header(header::ContentType::octet_stream())
+                    .insert_header(header::ContentDisposition::attachment(filename))
+                    .body(buf)
+            }
+            Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        },
+        _ => HttpResponse::InternalServerError().body("query config error"),
+    }
+}
+
+///
+/// 兼容nacos的配置导入(import=true),以multipart的file字段上传nacos导出的zip包
+async fn import_nacos_config(
+    req: &HttpRequest,
+    param: ConfigWebParams,
+    payload: web::Payload,
+    appdata: &AppShareData,
+) -> HttpResponse {
+    let policy = match param.policy.as_deref().filter(|v| !v.is_empty()) {
+        Some(v) => match ConfigImportPolicy::new_by_value(v) {
+            Some(policy) => policy,
+            None => return ConfigImportResponse::error(format!("unknown policy {}", v)),
+        },
+        None => ConfigImportPolicy::default(),
+    };
+    let namespace = OptionUtils::select(param.namespace, param.tenant).unwrap_or_default();
+    if let Err(err) = param_utils::check_tenant(&Some(namespace.clone())) {
+        return ConfigImportResponse::error(err.to_string());
+    }
+    let tenant = Arc::new(ConfigUtils::default_tenant(namespace));
+    if let Err(err) = check_namespace_write(req, appdata, &tenant) {
+        return ConfigImportResponse::error(err.to_string());
+    }
+    let mut multipart = Multipart::new(req.headers(), payload);
+    let mut buf = vec![];
+    while let Some(field) = multipart.next().await {
+        let mut field = match field {
+            Ok(v) => v,
+            Err(err) => return ConfigImportResponse::error(err.to_string()),
+        };
+        if field.name() != "file" {
+            continue;
+        }
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(v) => buf.extend_from_slice(&v),
+                Err(err) => return ConfigImportResponse::error(err.to_string()),
+            }
+        }
+    }
+    if buf.is_empty() {
+        return ConfigImportResponse::error("import file is empty".to_owned());
+    }
+    let (items, invalid_list) = match NacosZipUtils::parse_zip(Cursor::new(buf)) {
+        Ok(v) => v,
+        Err(err) => return ConfigImportResponse::error(err.to_string()),
+    };
+    let op_user = get_session_user(req);
+    match NacosZipUtils::import(appdata, tenant, items, invalid_list, policy, op_user).await {
+        Ok(result) => HttpResponse::Ok().json(ConfigImportResponse {
+            code: 200,
+            message: "success".to_owned(),
+            data: Some(result),
+        }),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
 pub(crate) async fn get_config(
+    req: HttpRequest,
     web_param: web::Query<ConfigWebParams>,
     appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
+    if get_bool_from_string(&web_param.export, false)
+        || get_bool_from_string(&web_param.export_v2, false)
+    {
+        return export_nacos_config(web_param.0, &appdata).await;
+    }
     if let Some(search) = web_param.search.as_ref() {
         if search == "blur" {
             let query_param = web_param.0.build_like_search_param();
@@ -274,14 +746,45 @@ pub(crate) async fn get_config(
             return do_search_config(query_param, appdata).await;
         }
     };
+    let data_time = match StaleReadGuard::check(&appdata, &req) {
+        Ok(v) => v,
+        Err(staleness) => {
+            return HttpResponse::ServiceUnavailable()
+                .body(format!("local data is stale, staleness {}ms", staleness));
+        }
+    };
     let param = web_param.to_confirmed_param();
     match param {
         Ok(p) => {
-            let cmd = ConfigCmd::GET(ConfigKey::new(&p.data_id, &p.group, &p.tenant));
+            let config_key = ConfigKey::new(&p.data_id, &p.group, &p.tenant);
+            if get_bool_from_string(&web_param.beta, false) {
+                return get_beta_config(&appdata, &config_key).await;
+            }
+            let cmd = match get_tag(&web_param.tag) {
+                Ok(Some(tag)) => ConfigCmd::GET(ConfigTagUtils::build_tag_key(&config_key, &tag)),
+                Ok(None) => ConfigCmd::GetByClient(config_key, Arc::new(get_operator(&req))),
+                Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+            };
             match appdata.config_addr.send(cmd).await {
                 Ok(res) => {
                     let r: ConfigResult = res.unwrap();
                     match r {
+                        ConfigResult::BetaData {
+                            value: v,
+                            md5,
+                            config_type,
+                            ..
+                        } => HttpResponse::Ok()
+                            .content_type(
+                                config_type
+                                    .map(|v| ConfigType::new_by_value(&v))
+                                    .unwrap_or_default()
+                                    .get_media_type(),
+                            )
+                            .insert_header(("content-md5", md5.as_ref().to_string()))
+                            .insert_header(("isBeta", "true"))
+                            .insert_header((DATA_TIME_HEADER, data_time.to_string()))
+                            .body(v.as_ref().as_bytes().to_vec()),
                         ConfigResult::Data {
                             value: v,
                             md5,
@@ -295,6 +798,7 @@ pub(crate) async fn get_config(
                                     .get_media_type(),
                             )
                             .insert_header(("content-md5", md5.as_ref().to_string()))
+                            .insert_header((DATA_TIME_HEADER, data_time.to_string()))
                             .body(v.as_ref().as_bytes().to_vec()),
                         _ => HttpResponse::NotFound().body("config data not exist"),
                     }
@@ -362,6 +866,48 @@ impl ListenerParams {
     }
 }
 
+///
+/// 与nacos一致的配置监听状态,key为客户端ip,value为客户端持有的md5
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupKeyListenerStatus {
+    pub collect_status: u16,
+    pub lisenters_groupkey_status: HashMap<String, Arc<String>>,
+}
+
+///
+/// 查询本节点上监听指定配置的客户端ip与其持有的md5,对应nacos的配置监听查询接口
+pub(super) async fn query_config_listeners(
+    web_param: web::Query<ConfigWebParams>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = match web_param.to_confirmed_param() {
+        Ok(v) if !v.data_id.is_empty() => v,
+        Ok(_) => return HttpResponse::BadRequest().body("dataId is empty"),
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let config_key = ConfigKey::new(&param.data_id, &param.group, &param.tenant);
+    match appdata
+        .config_addr
+        .send(ConfigCmd::QueryConfigListenerConnections(config_key))
+        .await
+    {
+        Ok(Ok(ConfigResult::ListenerConnectionMap(list))) => {
+            let mut status = GroupKeyListenerStatus {
+                collect_status: 200,
+                ..Default::default()
+            };
+            for item in list {
+                if let Some(config) = item.configs.into_iter().next() {
+                    status.lisenters_groupkey_status.insert(item.ip, config.md5);
+                }
+            }
+            HttpResponse::Ok().json(status)
+        }
+        _ => HttpResponse::InternalServerError().body("query config listeners error"),
+    }
+}
+
 pub(super) async fn listener_config(
     _req: HttpRequest,
     a: web::Query<ListenerParams>,
@@ -384,34 +930,20 @@ pub(super) async fn listener_config(
             .body("error:listener empty"));
     }
     let (tx, rx) = tokio::sync::oneshot::channel();
-    let current_time = Local::now().timestamp_millis();
-    let mut time_out = 0;
-    if let Some(_timeout) = _req.headers().get("Long-Pulling-Timeout") {
-        match _timeout.to_str().unwrap().parse::<i64>() {
-            Ok(v) => {
-                time_out = current_time + v.clamp(10000, 120000) - 500;
-            }
-            Err(_) => {
-                time_out = 0;
-            }
-        }
-    }
+    let time_out = get_long_polling_timeout(&_req, Local::now().timestamp_millis());
 
-    let version = _req
-        .headers()
-        .get("User-Agent")
-        .and_then(|x| x.to_str().ok())
-        .ok_or(actix_web::error::ErrorNotAcceptable(
-            "error:User-Agent required",
-        ))?
+    //1.x客户端的User-Agent不统一,缺省时使用Client-Version
+    let version = ["User-Agent", "Client-Version"]
+        .iter()
+        .find_map(|name| _req.headers().get(*name).and_then(|x| x.to_str().ok()))
+        .unwrap_or_default()
         .to_string();
 
+    //unix domain socket连接没有对端地址,对端为本机
     let ip = _req
         .connection_info()
         .realip_remote_addr()
-        .ok_or(actix_web::error::ErrorNotAcceptable(
-            "error:parse ip failed",
-        ))?
+        .unwrap_or(LOCAL_CLIENT_IP)
         .to_string();
 
     let subscribe_info = ConfigListenerInfo {
@@ -423,7 +955,7 @@ pub(super) async fn listener_config(
     //println!("timeout header:{:?},time_out:{}",_req.headers().get("Long-Pulling-Timeout") ,time_out);
     let cmd = ConfigCmd::Listener(list, tx, subscribe_info, time_out);
     let _ = config_addr.send(cmd).await;
-    let res = rx.await.unwrap();
+    let res = rx.await.unwrap_or(ListenerResult::NULL);
     let v = match res {
         ListenerResult::DATA(list) => {
             let mut data = "".to_string();
@@ -441,7 +973,29 @@ pub(super) async fn listener_config(
 
     let res = HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
+        .insert_header(("Pragma", "no-cache"))
+        .insert_header(("Expires", "0"))
+        .insert_header(("Cache-Control", "no-cache,no-store"))
         .body(v);
 
     Ok(res)
 }
+
+///
+/// 长轮询的截止时间,为0时立即返回;
+/// 客户端初始化监听时设置Long-Pulling-No-Hangup,不挂起请求,避免首次拉取等待一个轮询周期
+fn get_long_polling_timeout(req: &HttpRequest, current_time: i64) -> i64 {
+    let no_hangup = req
+        .headers()
+        .get("Long-Pulling-No-Hangup")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    if no_hangup {
+        return 0;
+    }
+    req.headers()
+        .get("Long-Pulling-Timeout")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .map_or(0, |v| current_time + v.clamp(10000, 120000) - 500)
+}
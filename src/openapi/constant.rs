@@ -10,5 +10,4 @@ pub(crate) const CONFIG_V1_BASE_PATH: &str = "/v1/cs";
 #[allow(unused)]
 pub(crate) const CONFIG_V2_BASE_PATH: &str = "/v2/cs";
 pub(crate) const NAMING_V1_BASE_PATH: &str = "/v1/ns";
-#[allow(unused)]
 pub(crate) const NAMING_V2_BASE_PATH: &str = "/v2/ns";
@@ -1,7 +1,10 @@
 use crate::common::appdata::AppShareData;
-use crate::common::constant::{AUTHORIZATION_HEADER, EMPTY_ARC_STRING};
+use crate::common::constant::{
+    ACCESS_TOKEN_HEADER, AUTHORIZATION_HEADER, CANARY_HEADER, EMPTY_ARC_STRING,
+};
 use crate::common::datetime_utils;
 use crate::common::model::TokenSession;
+use crate::common::web_utils::get_bearer_token;
 use crate::metrics::core::MetricsManager;
 use crate::metrics::metrics_key::MetricsKey;
 use crate::metrics::model::{MetricsItem, MetricsRecord, MetricsRequest};
@@ -21,7 +24,7 @@ use std::time::SystemTime;
 
 lazy_static::lazy_static! {
     pub static ref IGNORE_PATH: Vec<&'static str> = vec![
-        "/nacos/v1/auth/login", "/nacos/v1/auth/users/login","/nacos/metrics"
+        "/nacos/v1/auth/login", "/nacos/v1/auth/users/login","/nacos/metrics","/nacos/status"
     ];
     pub static ref API_PATH: Regex = Regex::new(r"(?i)/nacos/.*").unwrap();
     pub static ref IGNORE_METRICS_PATH: Vec<&'static str> = vec![
@@ -90,13 +93,34 @@ where
             true
         };
         let ignore_metrics = IGNORE_METRICS_PATH.contains(&path);
+        let canary_pass = !API_PATH.is_match(path)
+            || IGNORE_PATH.contains(&path)
+            || self.app_share_data.sys_config.pass_canary(
+                request
+                    .headers()
+                    .get(CANARY_HEADER)
+                    .and_then(|v| v.to_str().ok()),
+            );
         let app_share_data = self.app_share_data.clone();
         let service = self.service.clone();
         Box::pin(async move {
+            if !canary_pass {
+                //金丝雀节点不处理没有路由标记的客户端请求
+                let body = format!("{{\"timestamp\":\"{}\",\"status\":503,\"error\":\"Service Unavailable\",\"message\":\"canary node only serves routed test traffic\",\"path\":\"{}\"}}",
+                                   datetime_utils::get_now_timestamp_str(&app_share_data.timezone_offset), request.path());
+                let response = HttpResponse::ServiceUnavailable()
+                    .insert_header(("Content-Type", "application/json;charset=UTF-8"))
+                    .body(body)
+                    .map_into_right_body();
+                let (http_request, _pl) = request.into_parts();
+                return Ok(ServiceResponse::new(http_request, response));
+            }
             let cache_manager = &app_share_data.cache_manager;
             let offset = &app_share_data.timezone_offset;
             let token = if enable_auth && is_check_path {
                 if let Some(v) = request.headers().get(AUTHORIZATION_HEADER) {
+                    Arc::new(get_bearer_token(v.to_str().unwrap_or_default()).to_owned())
+                } else if let Some(v) = request.headers().get(ACCESS_TOKEN_HEADER) {
                     Arc::new(v.to_str().unwrap_or_default().to_owned())
                 } else if let Ok(info) =
                     serde_urlencoded::from_str::<AccessInfo>(request.query_string())
@@ -0,0 +1,90 @@
+use std::future::{ready, Ready};
+use std::sync::Arc;
+
+use actix_http::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{dev, Error, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+
+use crate::common::appdata::AppShareData;
+use crate::common::concurrency_limit::EndpointClass;
+use crate::common::datetime_utils;
+
+///
+/// 按接口类别限制同时处理的请求数,排队已满或等待超时返回503
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    app_share_data: Arc<AppShareData>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(app_share_data: Arc<AppShareData>) -> Self {
+        Self { app_share_data }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConcurrencyLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ConcurrencyLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConcurrencyLimitMiddleware {
+            service: Arc::new(service),
+            app_share_data: self.app_share_data.clone(),
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct ConcurrencyLimitMiddleware<S> {
+    service: Arc<S>,
+    app_share_data: Arc<AppShareData>,
+}
+
+impl<S, B> Service<ServiceRequest> for ConcurrencyLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let class = EndpointClass::classify(request.method().as_str(), request.path());
+        let app_share_data = self.app_share_data.clone();
+        let service = self.service.clone();
+        Box::pin(async move {
+            let class = match class {
+                Some(v) => v,
+                None => return service.call(request).await.map(|r| r.map_into_left_body()),
+            };
+            match app_share_data.concurrency_limiter.acquire(class).await {
+                Ok(_permit) => service.call(request).await.map(|r| r.map_into_left_body()),
+                Err(err) => {
+                    log::warn!("request is rejected,{},path:{}", err, request.path());
+                    let body = format!("{{\"timestamp\":\"{}\",\"status\":503,\"error\":\"Service Unavailable\",\"message\":\"too many concurrent requests\",\"path\":\"{}\"}}",
+                                       datetime_utils::get_now_timestamp_str(&app_share_data.timezone_offset), request.path());
+                    let response = HttpResponse::ServiceUnavailable()
+                        .insert_header(("Content-Type", "application/json;charset=UTF-8"))
+                        .insert_header(("Retry-After", "1"))
+                        .body(body)
+                        .map_into_right_body();
+                    let (http_request, _pl) = request.into_parts();
+                    Ok(ServiceResponse::new(http_request, response))
+                }
+            }
+        })
+    }
+}
@@ -1 +1,3 @@
 pub mod auth_middle;
+pub mod concurrency_middle;
+pub mod trace_middle;
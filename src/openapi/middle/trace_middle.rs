@@ -0,0 +1,80 @@
+use std::future::{ready, Ready};
+use std::sync::Arc;
+
+use actix_http::header::{HeaderName, HeaderValue};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{dev, Error};
+use futures_util::future::LocalBoxFuture;
+
+use crate::common::trace_utils::{TraceUtils, REQUEST_ID_HEADER};
+
+///
+/// 读取或生成请求链路id,在请求处理过程中传递,并在响应头中返回
+#[derive(Clone, Default)]
+pub struct RequestTrace;
+
+impl RequestTrace {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTrace
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestTraceMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTraceMiddleware {
+            service: Arc::new(service),
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestTraceMiddleware<S> {
+    service: Arc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTraceMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let request_id = TraceUtils::get_or_new_request_id(
+            request
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|v| v.to_str().ok()),
+        );
+        let service = self.service.clone();
+        Box::pin(async move {
+            let mut res =
+                TraceUtils::scope(
+                    request_id.clone(),
+                    async move { service.call(request).await },
+                )
+                .await?;
+            if let Ok(v) = HeaderValue::from_str(&request_id) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), v);
+            }
+            Ok(res)
+        })
+    }
+}
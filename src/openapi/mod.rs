@@ -9,6 +9,7 @@ mod constant;
 pub(crate) mod metrics;
 pub mod middle;
 pub(crate) mod naming;
+pub(crate) mod status;
 pub(crate) mod v1;
 pub(crate) mod v2;
 
@@ -5,21 +5,35 @@ use std::sync::Arc;
 
 use actix::prelude::*;
 use actix_web::dev::HttpServiceFactory;
-use actix_web::{get, http::header, put, web, HttpResponse, Responder, Scope};
+use actix_web::{get, http::header, put, web, HttpRequest, HttpResponse, Responder, Scope};
 use serde::{Deserialize, Serialize};
 
 use crate::common::appdata::AppShareData;
+use crate::common::namespace_quota::QuotaExceededError;
+use crate::common::sdk_usage::SdkUsageCmd;
+use crate::common::string_utils::StringUtils;
 use crate::common::web_utils::get_req_body;
 use crate::merge_web_param;
-use crate::naming::api_model::InstanceVO;
+use crate::naming::api_model::{InstanceVO, QueryListResult};
+use crate::naming::consistent_hash::ConsistentHashUtils;
 use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
 use crate::naming::model::{Instance, InstanceUpdateTag, ServiceKey};
+use crate::naming::persistent::PersistentInstanceUtils;
+use crate::naming::shuffle::WeightedShuffleUtils;
+use crate::naming::srv::{SrvPriorityStrategy, SrvRecordUtils, SrvWeightStrategy};
+use crate::naming::validate::NamingValidateUtils;
+use crate::naming::zone::ZoneUtils;
 use crate::naming::{
     NamingUtils, CLIENT_BEAT_INTERVAL_KEY, LIGHT_BEAT_ENABLED_KEY, RESPONSE_CODE_KEY,
-    RESPONSE_CODE_OK,
+    RESPONSE_CODE_OK, RESPONSE_CODE_RESOURCE_NOT_FOUND,
 };
 use crate::openapi::constant::EMPTY;
-use crate::openapi::naming::model::{BeatRequest, InstanceWebParams, InstanceWebQueryListParams};
+use crate::openapi::naming::model::{
+    BatchBeatRequest, BeatRequest, InstanceDrainWebParams, InstanceMetadataPatchWebParams,
+    InstanceSrvQueryParams, InstanceWebParams, InstanceWebQueryListParams,
+};
+use crate::raft::cluster::ack::{WriteAckLevel, WriteAckUtils};
+use crate::raft::freshness::{StaleReadGuard, DATA_TIME_HEADER};
 use crate::utils::{get_bool_from_string, select_option_by_clone};
 
 pub(super) fn service() -> Scope {
@@ -32,8 +46,18 @@ pub(super) fn service() -> Scope {
                 .route(web::patch().to(update_instance))
                 .route(web::delete().to(del_instance)),
         )
+        .service(
+            web::resource("/metadata")
+                .route(web::put().to(patch_instance_metadata))
+                .route(web::patch().to(patch_instance_metadata)),
+        )
         .service(beat_instance)
+        .service(web::resource("/drain").route(web::put().to(drain_instance)))
+        .service(web::resource("/beat/batch").route(web::put().to(batch_beat_instance)))
         .service(get_instance_list)
+        .service(web::resource("/srv").route(web::get().to(get_instance_srv_records)))
+        .service(web::resource("/one").route(web::get().to(select_one_healthy_instance)))
+        .service(web::resource("/hash").route(web::get().to(select_hash_instance)))
 }
 
 pub async fn get_instance(
@@ -85,19 +109,47 @@ pub async fn update_instance(
             None => false,
         },
         from_update: true,
+        metadata_patch: None,
+    };
+    let ack_level = match WriteAckLevel::parse(&param.ack_level) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
     };
     let instance = param.convert_to_instance();
     match instance {
-        Ok(instance) => {
+        Ok(mut instance) => {
+            if let Err(err) = NamingValidateUtils::normalize_instance(&mut instance) {
+                return HttpResponse::BadRequest().json(err);
+            }
             if !instance.check_vaild() {
                 HttpResponse::InternalServerError().body("instance check is invalid")
             } else {
-                match appdata
-                    .naming_route
-                    .update_instance(instance, Some(update_tag))
-                    .await
-                {
+                let res = match PersistentInstanceUtils::is_persistent(&appdata, &instance).await {
+                    Ok(true) => {
+                        let app = appdata.get_ref().clone();
+                        let write = async move {
+                            PersistentInstanceUtils::update_instance(
+                                &app,
+                                instance,
+                                Some(update_tag),
+                            )
+                            .await
+                        };
+                        WriteAckUtils::write(&appdata, ack_level, write).await
+                    }
+                    Ok(false) => {
+                        appdata
+                            .naming_route
+                            .update_instance(instance, Some(update_tag))
+                            .await
+                    }
+                    Err(err) => Err(err),
+                };
+                match res {
                     Ok(_) => HttpResponse::Ok().body("ok"),
+                    Err(e) if e.downcast_ref::<QuotaExceededError>().is_some() => {
+                        HttpResponse::Forbidden().body(e.to_string())
+                    }
                     Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
                 }
             }
@@ -112,13 +164,28 @@ pub async fn del_instance(
     appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
     let param = merge_web_param!(param.0, payload);
+    let ack_level = match WriteAckLevel::parse(&param.ack_level) {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
     let instance = param.convert_to_instance();
     match instance {
         Ok(instance) => {
             if !instance.check_vaild() {
                 HttpResponse::InternalServerError().body("instance check is invalid")
             } else {
-                match appdata.naming_route.delete_instance(instance).await {
+                let res = match PersistentInstanceUtils::is_persistent(&appdata, &instance).await {
+                    Ok(true) => {
+                        let app = appdata.get_ref().clone();
+                        let write = async move {
+                            PersistentInstanceUtils::delete_instance(&app, &instance).await
+                        };
+                        WriteAckUtils::write(&appdata, ack_level, write).await
+                    }
+                    Ok(false) => appdata.naming_route.delete_instance(instance).await,
+                    Err(err) => Err(err),
+                };
+                match res {
                     Ok(_) => HttpResponse::Ok().body("ok"),
                     Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
                 }
@@ -128,8 +195,49 @@ pub async fn del_instance(
     }
 }
 
+///
+/// 局部更新实例metadata,不需要重新注册完整实例
+pub async fn patch_instance_metadata(
+    param: web::Query<InstanceMetadataPatchWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let (instance, patch) = match param.into_patch() {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    match patch.patch_instance(&appdata, instance).await {
+        Ok(metadata) => HttpResponse::Ok().json(metadata),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+///
+/// 实例摘流:立即设置为禁用并推送给订阅方,宽限期后自动删除;用于无损发布
+pub async fn drain_instance(
+    param: web::Query<InstanceDrainWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let instance = match param.to_instance() {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    match appdata
+        .naming_route
+        .drain_instance(instance, param.grace_millis())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().body("ok"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
 #[put("/beat")]
 pub async fn beat_instance(
+    req: HttpRequest,
     param: web::Query<BeatRequest>,
     payload: web::Payload,
     appdata: web::Data<Arc<AppShareData>>,
@@ -137,34 +245,67 @@ pub async fn beat_instance(
     let param = merge_web_param!(param.0, payload);
     //debug
     //log::info!("beat request param:{}",serde_json::to_string(&param).unwrap());
+    //轻量心跳不携带beat信息,只用于续约已注册的实例
+    let light_beat = param.beat.as_ref().is_none_or(|v| v.is_empty());
     let instance = param.convert_to_instance();
     match instance {
         Ok(instance) => {
             if !instance.check_vaild() {
                 HttpResponse::InternalServerError().body("instance check is invalid")
             } else {
+                if let Some(user_agent) = req
+                    .headers()
+                    .get(header::USER_AGENT)
+                    .and_then(|v| v.to_str().ok())
+                {
+                    let client_id = Arc::new(format!(
+                        "http#{}#{}:{}",
+                        &instance.namespace_id, &instance.ip, instance.port
+                    ));
+                    appdata.sdk_usage_manage.do_send(SdkUsageCmd::Report(
+                        client_id,
+                        instance.namespace_id.clone(),
+                        user_agent.to_owned(),
+                        false,
+                    ));
+                }
+                let old_instance = match appdata
+                    .naming_addr
+                    .send(NamingCmd::Query(instance.clone()))
+                    .await
+                {
+                    Ok(Ok(NamingResult::Instance(v))) => Some(v),
+                    _ => None,
+                };
+                let beat_interval = NamingUtils::get_client_beat_interval(
+                    old_instance
+                        .as_ref()
+                        .map_or(&instance.metadata, |v| &v.metadata),
+                    appdata.sys_config.naming_client_beat_interval_millis,
+                );
+                let light_beat_enabled = appdata.sys_config.naming_light_beat_enabled;
+                if light_beat && old_instance.is_none() {
+                    //实例不存在时不能用轻量心跳注册,由客户端重新注册
+                    return beat_response(
+                        RESPONSE_CODE_RESOURCE_NOT_FOUND,
+                        beat_interval,
+                        light_beat_enabled,
+                    );
+                }
                 let tag = InstanceUpdateTag {
                     weight: false,
                     enabled: false,
                     ephemeral: false,
                     metadata: false,
                     from_update: false,
+                    metadata_patch: None,
                 };
                 match appdata
                     .naming_route
                     .update_instance(instance, Some(tag))
                     .await
                 {
-                    Ok(_) => {
-                        let mut result = HashMap::new();
-                        result.insert(RESPONSE_CODE_KEY, serde_json::json!(RESPONSE_CODE_OK));
-                        result.insert(CLIENT_BEAT_INTERVAL_KEY, serde_json::json!(5000));
-                        result.insert(LIGHT_BEAT_ENABLED_KEY, serde_json::json!(true));
-                        let v = serde_json::to_string(&result).unwrap();
-                        HttpResponse::Ok()
-                            .insert_header(header::ContentType(mime::APPLICATION_JSON))
-                            .body(v)
-                    }
+                    Ok(_) => beat_response(RESPONSE_CODE_OK, beat_interval, light_beat_enabled),
                     Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
                 }
             }
@@ -173,14 +314,122 @@ pub async fn beat_instance(
     }
 }
 
+fn beat_response(code: i32, beat_interval: i64, light_beat_enabled: bool) -> HttpResponse {
+    let mut result = HashMap::new();
+    result.insert(RESPONSE_CODE_KEY, serde_json::json!(code));
+    result.insert(CLIENT_BEAT_INTERVAL_KEY, serde_json::json!(beat_interval));
+    result.insert(
+        LIGHT_BEAT_ENABLED_KEY,
+        serde_json::json!(light_beat_enabled),
+    );
+    HttpResponse::Ok()
+        .insert_header(header::ContentType(mime::APPLICATION_JSON))
+        .body(serde_json::to_string(&result).unwrap())
+}
+
+///
+/// 批量心跳,一次请求上报多个实例的心跳
+pub async fn batch_beat_instance(
+    param: web::Json<BatchBeatRequest>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = param.0;
+    let mut instances = Vec::with_capacity(param.beats.len());
+    let mut invalid_count = 0;
+    for mut beat in param.beats {
+        if StringUtils::is_option_empty(&beat.namespace_id) {
+            beat.namespace_id = param.namespace_id.clone();
+        }
+        match beat.convert_to_instance() {
+            Ok(instance) if instance.check_vaild() => instances.push(instance),
+            _ => invalid_count += 1,
+        }
+    }
+    let beat_count = instances.len();
+    let tag = InstanceUpdateTag {
+        weight: false,
+        enabled: false,
+        ephemeral: false,
+        metadata: false,
+        from_update: false,
+        metadata_patch: None,
+    };
+    match appdata.naming_route.beat_instances(instances, tag).await {
+        Ok(_) => {
+            let mut result = HashMap::new();
+            result.insert(RESPONSE_CODE_KEY, serde_json::json!(RESPONSE_CODE_OK));
+            result.insert(
+                CLIENT_BEAT_INTERVAL_KEY,
+                serde_json::json!(appdata.sys_config.naming_client_beat_interval_millis),
+            );
+            result.insert("beatCount", serde_json::json!(beat_count));
+            result.insert("invalidCount", serde_json::json!(invalid_count));
+            HttpResponse::Ok()
+                .insert_header(header::ContentType(mime::APPLICATION_JSON))
+                .body(serde_json::to_string(&result).unwrap())
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
 #[get("/list")]
 pub async fn get_instance_list(
+    req: HttpRequest,
     param: web::Query<InstanceWebQueryListParams>,
     naming_addr: web::Data<Addr<NamingActor>>,
+    appdata: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
+    let data_time = match StaleReadGuard::check(&appdata, &req) {
+        Ok(v) => v,
+        Err(staleness) => {
+            return HttpResponse::ServiceUnavailable()
+                .body(format!("local data is stale, staleness {}ms", staleness));
+        }
+    };
     let only_healthy = get_bool_from_string(&param.healthy_only, true);
     let addr = param.get_addr();
+    let zone = match param.zone.as_ref() {
+        Some(zone) => Some(zone.to_owned()),
+        None if appdata.sys_config.naming_zone_aware => {
+            ZoneUtils::get_zone(&appdata.sys_config.node_labels).cloned()
+        }
+        None => None,
+    };
+    let shuffle = get_bool_from_string(&param.shuffle, appdata.sys_config.naming_weighted_shuffle);
+    let consumer_ip = param
+        .client_ip
+        .clone()
+        .or_else(|| req.peer_addr().map(|v| v.ip().to_string()));
     match param.to_clusters_key() {
+        Ok((key, clusters)) if zone.is_some() || shuffle => {
+            match naming_addr
+                .send(NamingCmd::QueryList(
+                    key.clone(),
+                    clusters.clone(),
+                    only_healthy,
+                    addr,
+                    consumer_ip,
+                ))
+                .await
+            {
+                Ok(Ok(NamingResult::InstanceList(list))) => {
+                    let mut list =
+                        ZoneUtils::prefer_zone(list, zone.as_deref().unwrap_or_default());
+                    if shuffle {
+                        list = WeightedShuffleUtils::shuffle(list, param.shuffle_seed);
+                    }
+                    HttpResponse::Ok()
+                        .insert_header(header::ContentType(mime::APPLICATION_JSON))
+                        .insert_header((DATA_TIME_HEADER, data_time.to_string()))
+                        .body(QueryListResult::get_instance_list_string(
+                            clusters, &key, list, false,
+                        ))
+                }
+                Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+                Ok(_) => HttpResponse::InternalServerError().body("error"),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
         Ok((key, clusters)) => {
             match naming_addr
                 .send(NamingCmd::QueryListString(
@@ -188,6 +437,7 @@ pub async fn get_instance_list(
                     clusters,
                     only_healthy,
                     addr,
+                    consumer_ip,
                 ))
                 .await
             {
@@ -196,6 +446,7 @@ pub async fn get_instance_list(
                     match result {
                         NamingResult::InstanceListString(v) => HttpResponse::Ok()
                             .insert_header(header::ContentType(mime::APPLICATION_JSON))
+                            .insert_header((DATA_TIME_HEADER, data_time.to_string()))
                             .body(v),
                         _ => HttpResponse::InternalServerError().body("error"),
                     }
@@ -206,3 +457,133 @@ pub async fn get_instance_list(
         Err(err) => HttpResponse::InternalServerError().body(err),
     }
 }
+
+///
+/// 以SRV记录的形式返回服务的可用实例;集群参数只用于设置优先级,不过滤实例
+pub async fn get_instance_srv_records(
+    param: web::Query<InstanceSrvQueryParams>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let sys_config = &appdata.sys_config;
+    let weight_strategy = SrvWeightStrategy::from_name(
+        param
+            .weight_strategy
+            .as_ref()
+            .unwrap_or(&sys_config.naming_srv_weight_strategy),
+    );
+    let priority_strategy = SrvPriorityStrategy::from_name(
+        param
+            .priority_strategy
+            .as_ref()
+            .unwrap_or(&sys_config.naming_srv_priority_strategy),
+    );
+    match param.to_clusters_key() {
+        Ok((key, cluster_str)) => {
+            match naming_addr
+                .send(NamingCmd::QueryList(
+                    key.clone(),
+                    "".to_owned(),
+                    true,
+                    None,
+                    None,
+                ))
+                .await
+            {
+                Ok(Ok(NamingResult::InstanceList(list))) => {
+                    let clusters = NamingUtils::split_filters(&cluster_str);
+                    let records = SrvRecordUtils::build_records(
+                        &key,
+                        &list,
+                        &clusters,
+                        weight_strategy,
+                        priority_strategy,
+                        sys_config.naming_srv_ttl_second,
+                    );
+                    HttpResponse::Ok()
+                        .insert_header(header::ContentType(mime::APPLICATION_JSON))
+                        .body(serde_json::to_string(&records).unwrap())
+                }
+                Ok(Ok(_)) => HttpResponse::InternalServerError().body("error"),
+                Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+                Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+            }
+        }
+        Err(err) => HttpResponse::InternalServerError().body(err),
+    }
+}
+
+///
+/// 服务端按权重随机选择一个健康实例,供没有客户端负载均衡能力的调用方使用;没有可用实例时返回404
+pub async fn select_one_healthy_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceWebQueryListParams>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let (key, clusters) = match param.to_clusters_key() {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let zone = match param.zone.as_ref() {
+        Some(zone) => Some(zone.to_owned()),
+        None if appdata.sys_config.naming_zone_aware => {
+            ZoneUtils::get_zone(&appdata.sys_config.node_labels).cloned()
+        }
+        None => None,
+    };
+    let consumer_ip = param
+        .client_ip
+        .clone()
+        .or_else(|| req.peer_addr().map(|v| v.ip().to_string()));
+    match naming_addr
+        .send(NamingCmd::QueryList(key, clusters, true, None, consumer_ip))
+        .await
+    {
+        Ok(Ok(NamingResult::InstanceList(list))) => {
+            let list = ZoneUtils::prefer_zone(list, zone.as_deref().unwrap_or_default());
+            match WeightedShuffleUtils::select_one(&list, param.shuffle_seed) {
+                Some(instance) => HttpResponse::Ok().json(InstanceVO::from_instance(&instance)),
+                None => HttpResponse::NotFound().body("no healthy instance"),
+            }
+        }
+        Ok(Ok(_)) => HttpResponse::InternalServerError().body("error"),
+        Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+///
+/// 按hash_key一致性哈希选择健康实例,网关无需本地状态即可实现粘性路由
+pub async fn select_hash_instance(
+    req: HttpRequest,
+    param: web::Query<InstanceWebQueryListParams>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+) -> impl Responder {
+    let hash_key = match param.hash_key.as_ref().filter(|v| !v.is_empty()) {
+        Some(v) => v.to_owned(),
+        None => return HttpResponse::BadRequest().body("hashKey is empty"),
+    };
+    let (key, clusters) = match param.to_clusters_key() {
+        Ok(v) => v,
+        Err(err) => return HttpResponse::BadRequest().body(err),
+    };
+    let consumer_ip = param
+        .client_ip
+        .clone()
+        .or_else(|| req.peer_addr().map(|v| v.ip().to_string()));
+    match naming_addr
+        .send(NamingCmd::QueryList(key, clusters, true, None, consumer_ip))
+        .await
+    {
+        Ok(Ok(NamingResult::InstanceList(list))) => {
+            match ConsistentHashUtils::select(&list, &hash_key) {
+                Some(instance) => HttpResponse::Ok().json(InstanceVO::from_instance(&instance)),
+                None => HttpResponse::NotFound().body("no healthy instance"),
+            }
+        }
+        Ok(Ok(_)) => HttpResponse::InternalServerError().body("error"),
+        Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
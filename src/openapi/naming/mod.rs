@@ -11,7 +11,7 @@ pub(crate) mod service;
 mod v2;
 
 pub fn openapi_service(conf: RouteConf) -> Vec<Scope> {
-    vec![openapi_v1_route(conf)]
+    vec![openapi_v1_route(conf), v2::openapi_v2_route()]
 }
 
 pub fn openapi_v1_route(_conf: RouteConf) -> Scope {
@@ -1,5 +1,7 @@
 #![allow(unused_imports, unused_assignments, unused_variables)]
 use crate::common::option_utils::OptionUtils;
+use crate::naming::drain::DEFAULT_DRAIN_GRACE_MILLIS;
+use crate::naming::metadata_patch::InstanceMetadataPatch;
 use crate::naming::model::{Instance, ServiceKey};
 use crate::naming::NamingUtils;
 use crate::utils::{get_bool_from_string, select_option_by_clone};
@@ -8,6 +10,132 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+///
+/// 实例metadata局部更新参数;metadata为新增或覆盖的key,removeKeys为逗号分隔的待删除key
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceMetadataPatchWebParams {
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub namespace_id: Option<String>,
+    pub service_name: Option<String>,
+    pub group_name: Option<String>,
+    pub metadata: Option<String>,
+    pub remove_keys: Option<String>,
+}
+
+impl InstanceMetadataPatchWebParams {
+    pub(crate) fn merge(self, o: Self) -> Self {
+        Self {
+            ip: OptionUtils::select(self.ip, o.ip),
+            port: OptionUtils::select(self.port, o.port),
+            namespace_id: OptionUtils::select(self.namespace_id, o.namespace_id),
+            service_name: OptionUtils::select(self.service_name, o.service_name),
+            group_name: OptionUtils::select(self.group_name, o.group_name),
+            metadata: OptionUtils::select(self.metadata, o.metadata),
+            remove_keys: OptionUtils::select(self.remove_keys, o.remove_keys),
+        }
+    }
+
+    pub(crate) fn into_patch(self) -> Result<(Instance, InstanceMetadataPatch), String> {
+        let (ip, port, grouped_name) = match (self.ip, self.port, self.service_name) {
+            (Some(ip), Some(port), Some(service_name)) => (ip, port, service_name),
+            _ => return Err("ip,port,serviceName can't be empty".to_owned()),
+        };
+        let mut instance = Instance::new(ip, port);
+        instance.namespace_id = Arc::new(NamingUtils::default_namespace(
+            self.namespace_id.unwrap_or_default(),
+        ));
+        if let Some((group_name, service_name)) =
+            NamingUtils::split_group_and_serivce_name(&grouped_name)
+        {
+            instance.service_name = Arc::new(service_name);
+            instance.group_name = Arc::new(group_name);
+        } else {
+            return Err("serivceName is unvaild!".to_owned());
+        }
+        if let Some(group_name) = self.group_name {
+            if !group_name.is_empty() {
+                instance.group_name = Arc::new(group_name);
+            }
+        }
+        instance.generate_key();
+        let mut patch = InstanceMetadataPatch::default();
+        if let Some(metadata) = self.metadata {
+            if !metadata.is_empty() {
+                patch.upsert = NamingUtils::parse_metadata(&metadata).map_err(|e| e.to_string())?;
+            }
+        }
+        if let Some(remove_keys) = self.remove_keys {
+            patch.remove_keys = remove_keys
+                .split(',')
+                .map(|e| e.trim())
+                .filter(|e| !e.is_empty())
+                .map(|e| e.to_owned())
+                .collect();
+        }
+        Ok((instance, patch))
+    }
+}
+
+///
+/// 实例摘流参数;graceSecond为摘流后保留的秒数,到期后删除实例
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceDrainWebParams {
+    pub ip: Option<String>,
+    pub port: Option<u32>,
+    pub namespace_id: Option<String>,
+    pub service_name: Option<String>,
+    pub group_name: Option<String>,
+    pub grace_second: Option<u64>,
+}
+
+impl InstanceDrainWebParams {
+    pub(crate) fn merge(self, o: Self) -> Self {
+        Self {
+            ip: OptionUtils::select(self.ip, o.ip),
+            port: OptionUtils::select(self.port, o.port),
+            namespace_id: OptionUtils::select(self.namespace_id, o.namespace_id),
+            service_name: OptionUtils::select(self.service_name, o.service_name),
+            group_name: OptionUtils::select(self.group_name, o.group_name),
+            grace_second: OptionUtils::select(self.grace_second, o.grace_second),
+        }
+    }
+
+    pub(crate) fn grace_millis(&self) -> u64 {
+        self.grace_second
+            .map(|v| v * 1000)
+            .unwrap_or(DEFAULT_DRAIN_GRACE_MILLIS)
+    }
+
+    pub(crate) fn to_instance(&self) -> Result<Instance, String> {
+        let (ip, port, grouped_name) = match (&self.ip, self.port, &self.service_name) {
+            (Some(ip), Some(port), Some(service_name)) => (ip, port, service_name),
+            _ => return Err("ip,port,serviceName can't be empty".to_owned()),
+        };
+        let mut instance = Instance::new(ip.to_owned(), port);
+        instance.namespace_id = Arc::new(NamingUtils::default_namespace(
+            self.namespace_id.clone().unwrap_or_default(),
+        ));
+        if let Some((group_name, service_name)) =
+            NamingUtils::split_group_and_serivce_name(grouped_name)
+        {
+            instance.service_name = Arc::new(service_name);
+            instance.group_name = Arc::new(group_name);
+        } else {
+            return Err("serivceName is unvaild!".to_owned());
+        }
+        if let Some(group_name) = &self.group_name {
+            if !group_name.is_empty() {
+                instance.group_name = Arc::new(group_name.to_owned());
+            }
+        }
+        instance.generate_key();
+        Ok(instance)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InstanceWebParams {
@@ -22,6 +150,10 @@ pub struct InstanceWebParams {
     pub cluster_name: Option<String>,
     pub service_name: Option<String>,
     pub group_name: Option<String>,
+    /// 注册版本,如进程启动时间;注销时带上可避免误删新进程重新注册的实例
+    pub revision: Option<u64>,
+    /// 持久化实例的写入确认级别: committed(默认,leader为其别名)、majority
+    pub ack_level: Option<String>,
 }
 
 impl InstanceWebParams {
@@ -38,6 +170,8 @@ impl InstanceWebParams {
             cluster_name: OptionUtils::select(self.cluster_name, o.cluster_name),
             service_name: OptionUtils::select(self.service_name, o.service_name),
             group_name: OptionUtils::select(self.group_name, o.group_name),
+            revision: OptionUtils::select(self.revision, o.revision),
+            ack_level: OptionUtils::select(self.ack_level, o.ack_level),
         }
     }
 
@@ -61,6 +195,7 @@ impl InstanceWebParams {
                     .unwrap_or(&"".to_owned())
                     .to_owned(),
             )),
+            revision: self.revision.unwrap_or_default(),
             ..Default::default()
         };
 
@@ -91,6 +226,37 @@ impl InstanceWebParams {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceSrvQueryParams {
+    pub namespace_id: Option<String>,
+    pub service_name: Option<String>,
+    pub group_name: Option<String>,
+    /// 按顺序设置SRV优先级的集群列表
+    pub clusters: Option<String>,
+    pub weight_strategy: Option<String>,
+    pub priority_strategy: Option<String>,
+}
+
+impl InstanceSrvQueryParams {
+    pub(crate) fn to_clusters_key(&self) -> Result<(ServiceKey, String), String> {
+        InstanceWebQueryListParams {
+            namespace_id: self.namespace_id.clone(),
+            service_name: self.service_name.clone(),
+            group_name: self.group_name.clone(),
+            clusters: self.clusters.clone(),
+            healthy_only: None,
+            client_ip: None,
+            udp_port: None,
+            zone: None,
+            shuffle: None,
+            shuffle_seed: None,
+            hash_key: None,
+        }
+        .to_clusters_key()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InstanceWebQueryListParams {
@@ -102,6 +268,14 @@ pub struct InstanceWebQueryListParams {
     #[serde(rename = "clientIP")]
     pub client_ip: Option<String>,
     pub udp_port: Option<String>,
+    /// 优先返回该可用区的实例
+    pub zone: Option<String>,
+    /// 按实例权重随机打散返回顺序
+    pub shuffle: Option<String>,
+    /// 打散使用的随机种子,相同种子返回相同顺序
+    pub shuffle_seed: Option<u64>,
+    /// 一致性哈希选择实例使用的key,如用户id
+    pub hash_key: Option<String>,
 }
 
 impl InstanceWebQueryListParams {
@@ -286,6 +460,14 @@ impl BeatRequest {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchBeatRequest {
+    /// 心跳未指定命名空间时使用
+    pub namespace_id: Option<String>,
+    pub beats: Vec<BeatRequest>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct BeatInfo {
@@ -341,3 +523,22 @@ pub struct ServiceQueryListResponce {
     pub count: usize,
     pub doms: Vec<Arc<String>>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceSubscriberItem {
+    pub addr_str: String,
+    pub agent: String,
+    pub app: String,
+    pub ip: String,
+    pub port: u16,
+    pub namespace_id: Arc<String>,
+    pub service_name: String,
+    pub cluster: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ServiceSubscriberListResponse {
+    pub count: usize,
+    pub subscribers: Vec<ServiceSubscriberItem>,
+}
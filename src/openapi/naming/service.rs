@@ -7,7 +7,10 @@ use crate::naming::core::{NamingActor, NamingCmd, NamingResult};
 use crate::naming::model::ServiceKey;
 use crate::naming::NamingUtils;
 use crate::openapi::constant::EMPTY;
-use crate::openapi::naming::model::{ServiceQueryListRequest, ServiceQueryListResponce};
+use crate::openapi::naming::model::{
+    ServiceQueryListRequest, ServiceQueryListResponce, ServiceSubscriberItem,
+    ServiceSubscriberListResponse,
+};
 
 pub(super) fn service() -> Scope {
     web::scope("/service")
@@ -19,6 +22,7 @@ pub(super) fn service() -> Scope {
                 .route(web::get().to(query_service)),
         )
         .service(web::resource("/list").route(web::get().to(query_service_list)))
+        .service(web::resource("/subscribers").route(web::get().to(query_subscribers)))
 }
 
 pub async fn query_service(
@@ -89,7 +93,11 @@ pub async fn query_service_list(
             .unwrap_or(&"".to_owned())
             .to_owned(),
     );
-    let key = ServiceKey::new(&namespace_id, &group, "");
+    let key = ServiceKey::new(
+        &namespace_id,
+        &group,
+        param.service_name.as_deref().unwrap_or_default(),
+    );
     match naming_addr
         .send(NamingCmd::QueryServicePage(key, page_size, page_index))
         .await
@@ -107,3 +115,54 @@ pub async fn query_service_list(
         Err(_) => HttpResponse::InternalServerError().body("error"),
     }
 }
+
+pub async fn query_subscribers(
+    param: web::Query<ServiceQueryListRequest>,
+    naming_addr: web::Data<Addr<NamingActor>>,
+) -> impl Responder {
+    let param = param.0;
+    let page_size = param.page_size.unwrap_or(0x7fffffff).max(1);
+    let page_index = param.page_no.unwrap_or(1).max(1);
+    let grouped_name = param.service_name.unwrap_or_default();
+    let (group, service_name) = match NamingUtils::split_group_and_serivce_name(&grouped_name) {
+        Some((group, service_name)) => (group, service_name),
+        None => return HttpResponse::BadRequest().body("serviceName is unvaild!"),
+    };
+    let group = match param.group_name {
+        Some(v) if !v.is_empty() => v,
+        _ => group,
+    };
+    let namespace_id = NamingUtils::default_namespace(param.namespace_id.unwrap_or_default());
+    let key = ServiceKey::new(&namespace_id, &group, &service_name);
+    match naming_addr
+        .send(NamingCmd::QuerySubscriberList(key.clone()))
+        .await
+    {
+        Ok(Ok(NamingResult::SubscriberList(list))) => {
+            let count = list.len();
+            let subscribers = list
+                .into_iter()
+                .skip((page_index - 1) * page_size)
+                .take(page_size)
+                .map(|item| ServiceSubscriberItem {
+                    addr_str: format!("{}:{}", &item.client_ip, item.port),
+                    agent: item.agent,
+                    app: item.app_name,
+                    ip: item.client_ip,
+                    port: item.port,
+                    namespace_id: key.namespace_id.clone(),
+                    service_name: NamingUtils::get_group_and_service_name(
+                        &key.service_name,
+                        &key.group_name,
+                    ),
+                    cluster: item.clusters.join(","),
+                })
+                .collect();
+            let resp = ServiceSubscriberListResponse { count, subscribers };
+            HttpResponse::Ok().json(resp)
+        }
+        Ok(Err(err)) => HttpResponse::InternalServerError().body(err.to_string()),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+        _ => HttpResponse::InternalServerError().body("error"),
+    }
+}
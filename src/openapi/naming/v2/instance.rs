@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse, Responder, Scope};
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::common::option_utils::OptionUtils;
+use crate::merge_web_param;
+use crate::naming::model::{Instance, InstanceUpdateTag};
+use crate::naming::validate::NamingValidateUtils;
+use crate::naming::NamingUtils;
+use crate::now_millis_i64;
+
+const BATCH_REGISTER_INSTANCE: &str = "batchRegisterInstance";
+const BATCH_DEREGISTER_INSTANCE: &str = "batchDeregisterInstance";
+
+pub(super) fn service() -> Scope {
+    web::scope("/instance").service(
+        web::resource("/batch")
+            .route(web::post().to(batch_instance))
+            .route(web::put().to(batch_instance))
+            .route(web::delete().to(batch_instance)),
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchInstanceWebParams {
+    pub namespace_id: Option<String>,
+    pub group_name: Option<String>,
+    pub service_name: Option<String>,
+    pub r#type: Option<String>,
+    /// 实例列表json
+    pub instances: Option<String>,
+}
+
+impl BatchInstanceWebParams {
+    pub(crate) fn merge(self, o: Self) -> Self {
+        Self {
+            namespace_id: OptionUtils::select(self.namespace_id, o.namespace_id),
+            group_name: OptionUtils::select(self.group_name, o.group_name),
+            service_name: OptionUtils::select(self.service_name, o.service_name),
+            r#type: OptionUtils::select(self.r#type, o.r#type),
+            instances: OptionUtils::select(self.instances, o.instances),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchInstanceItem {
+    pub ip: String,
+    pub port: u32,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub healthy: bool,
+    #[serde(default = "default_true")]
+    pub ephemeral: bool,
+    #[serde(default)]
+    pub cluster_name: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+fn default_weight() -> f32 {
+    1f32
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn convert_to_instances(
+    param: BatchInstanceWebParams,
+    register: bool,
+) -> anyhow::Result<Vec<Instance>> {
+    let grouped_name = param.service_name.unwrap_or_default();
+    let (group_name, service_name) = NamingUtils::split_group_and_serivce_name(&grouped_name)
+        .ok_or_else(|| anyhow::anyhow!("serviceName is invalid"))?;
+    let group_name = Arc::new(match param.group_name {
+        Some(v) if !v.is_empty() => v,
+        _ => group_name,
+    });
+    let service_name = Arc::new(service_name);
+    let namespace_id = Arc::new(NamingUtils::default_namespace(
+        param.namespace_id.unwrap_or_default(),
+    ));
+    let items: Vec<BatchInstanceItem> =
+        serde_json::from_str(param.instances.as_deref().unwrap_or("[]"))?;
+    if items.is_empty() {
+        return Err(anyhow::anyhow!("instances is empty"));
+    }
+    let now = now_millis_i64();
+    let mut list = Vec::with_capacity(items.len());
+    for item in items {
+        if !item.ephemeral {
+            return Err(anyhow::anyhow!(
+                "batch instance only support ephemeral instance"
+            ));
+        }
+        let mut instance = Instance::new(item.ip, item.port);
+        instance.weight = item.weight;
+        instance.enabled = item.enabled;
+        instance.healthy = item.healthy;
+        instance.cluster_name = NamingUtils::default_cluster(item.cluster_name);
+        instance.metadata = Arc::new(item.metadata);
+        instance.namespace_id = namespace_id.clone();
+        instance.group_name = group_name.clone();
+        instance.service_name = service_name.clone();
+        instance.last_modified_millis = now;
+        //只在注册时校验参数
+        if register {
+            NamingValidateUtils::normalize_instance(&mut instance)?;
+        }
+        instance.generate_key();
+        list.push(instance);
+    }
+    Ok(list)
+}
+
+///
+/// 批量注册或注销同一服务下的实例;所有实例在一次处理中生效,只发出一次变更通知
+pub async fn batch_instance(
+    req: actix_web::HttpRequest,
+    param: web::Query<BatchInstanceWebParams>,
+    payload: web::Payload,
+    appdata: web::Data<Arc<AppShareData>>,
+) -> impl Responder {
+    let param = merge_web_param!(param.0, payload);
+    let register = match param.r#type.as_deref() {
+        Some(BATCH_DEREGISTER_INSTANCE) => false,
+        Some(BATCH_REGISTER_INSTANCE) => true,
+        _ => req.method() != actix_web::http::Method::DELETE,
+    };
+    let instances = match convert_to_instances(param, register) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let tag = if register {
+        Some(InstanceUpdateTag::default())
+    } else {
+        None
+    };
+    match appdata
+        .naming_route
+        .batch_instances(register, instances, tag)
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().body("ok"),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
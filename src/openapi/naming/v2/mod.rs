@@ -1 +1,10 @@
+use actix_web::{web, Scope};
+
+use crate::openapi::constant::NAMING_V2_BASE_PATH;
+
 mod api;
+mod instance;
+
+pub fn openapi_v2_route() -> Scope {
+    web::scope(NAMING_V2_BASE_PATH).service(instance::service())
+}
@@ -0,0 +1,30 @@
+use crate::common::appdata::AppShareData;
+use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+
+///
+/// 免鉴权的粗粒度状态接口;集群正常返回200,degraded返回503,超过限流返回429
+pub(crate) async fn public_status(appdata: web::Data<Arc<AppShareData>>) -> impl Responder {
+    let manager = &appdata.public_status;
+    if !manager.is_enabled() {
+        return HttpResponse::NotFound().body("404 Not Found");
+    }
+    if !manager.acquire() {
+        return HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", "1"))
+            .body("too many requests");
+    }
+    let status = manager.get_status(&appdata).await;
+    if status.is_ok() {
+        HttpResponse::Ok().json(status)
+    } else {
+        HttpResponse::ServiceUnavailable().json(status)
+    }
+}
+
+pub fn status_config(config: &mut web::ServiceConfig) {
+    config
+        .service(web::resource("/status").route(web::get().to(public_status)))
+        .service(web::resource("/nacos/status").route(web::get().to(public_status)))
+        .service(web::resource("/rnacos/status").route(web::get().to(public_status)));
+}
@@ -5,8 +5,9 @@ use crate::config::core::ConfigActor;
 use crate::console::model::{ConsoleResult, NamespaceInfo};
 use crate::console::NamespaceUtils;
 use crate::merge_web_param;
+use crate::openapi::auth::is_manager_request;
 use actix::Addr;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -123,12 +124,14 @@ pub async fn add_namespace(
 }
 
 pub async fn update_namespace(
+    req: HttpRequest,
     web::Query(param): web::Query<NamespaceParam>,
     payload: web::Payload,
     app_data: web::Data<Arc<AppShareData>>,
 ) -> impl Responder {
     let param = merge_web_param!(param, payload);
-    match NamespaceUtils::update_namespace(&app_data, param.into()).await {
+    let is_manager = is_manager_request(&req, &app_data.sys_config);
+    match NamespaceUtils::update_namespace(&app_data, param.into(), is_manager).await {
         Ok(_) => HttpResponse::Ok()
             .content_type("text/html; charset=utf-8")
             .body("true"),
@@ -1,5 +1,7 @@
 // raft缓存数据
 
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 use std::time::Duration;
 use std::{convert::TryInto, sync::Arc};
 
@@ -10,9 +12,14 @@ use ratelimiter_rs::RateLimiter;
 use serde::{Deserialize, Serialize};
 
 use crate::common::constant::CACHE_TREE_NAME;
+use crate::common::AppSysConfig;
+use crate::metrics::metrics_key::MetricsKey;
+use crate::metrics::model::{MetricsItem, MetricsQuery, MetricsRecord};
+use crate::raft::NacosRaft;
 use crate::{common::limiter_utils::LimiterData, now_millis_i64, now_second_i32};
 
 use self::model::{CacheItemDo, CacheKey, CacheValue};
+use self::session_store::{SessionStore, SessionStoreFactory};
 
 use super::db::{
     route::TableRoute,
@@ -22,6 +29,10 @@ use super::db::{
 pub mod api;
 pub mod model;
 pub mod route;
+pub mod session_store;
+
+/// 每次清理最多提交的过期key数量
+const GC_BATCH_SIZE: usize = 500;
 
 #[bean(inject)]
 pub struct CacheManager {
@@ -29,6 +40,15 @@ pub struct CacheManager {
     //default_timeout: i32,
     raft_table_route: Option<Arc<TableRoute>>,
     table_manager: Option<Addr<TableManager>>,
+    raft: Option<Arc<NacosRaft>>,
+    sys_config: Option<Arc<AppSysConfig>>,
+    /// 内存中已过期,等待主节点通过raft删除的key;
+    /// 过期数据只由主节点提交删除,保证各节点存储一致
+    expired_keys: Arc<Mutex<HashSet<Vec<u8>>>>,
+    session_store: Option<Arc<dyn SessionStore>>,
+    /// 用户名 -> 登录会话与open api token的key,用于用户权限变更时批量失效;
+    /// raft缓存表同步到每个节点,各节点的索引都是完整的;外部会话存储不使用该索引
+    user_index: HashMap<Arc<String>, HashSet<CacheKey>>,
 }
 
 impl Default for CacheManager {
@@ -46,9 +66,151 @@ impl CacheManager {
             //default_timeout: 1200,
             raft_table_route: None,
             table_manager: None,
+            raft: None,
+            sys_config: None,
+            expired_keys: Default::default(),
+            session_store: None,
+            user_index: Default::default(),
+        }
+    }
+
+    fn session_username(value: &CacheValue) -> Option<Arc<String>> {
+        match value {
+            CacheValue::UserSession(v) => Some(v.username.clone()),
+            CacheValue::ApiTokenSession(v) => Some(v.username.clone()),
+            _ => None,
+        }
+    }
+
+    fn add_user_index(&mut self, key: &CacheKey, value: &CacheValue) {
+        if let Some(username) = Self::session_username(value) {
+            self.user_index
+                .entry(username)
+                .or_default()
+                .insert(key.clone());
+        }
+    }
+
+    fn remove_user_index(&mut self, key: &CacheKey) {
+        if !key.cache_type.is_session() {
+            return;
+        }
+        if let Some(username) = self
+            .cache
+            .get(key)
+            .ok()
+            .and_then(|v| Self::session_username(&v))
+        {
+            if let Some(keys) = self.user_index.get_mut(&username) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.user_index.remove(&username);
+                }
+            }
+        }
+    }
+
+    ///
+    /// 清理索引中已过期的key;使用外部会话存储时由外部存储维护用户索引
+    fn clear_user_index(&mut self) {
+        let mut user_index = std::mem::take(&mut self.user_index);
+        user_index.retain(|_, keys| {
+            keys.retain(|key| self.cache.get(key).is_ok());
+            !keys.is_empty()
+        });
+        self.user_index = user_index;
+    }
+
+    fn is_leader(&self) -> bool {
+        match (&self.raft, &self.sys_config) {
+            (Some(raft), Some(sys_config)) => {
+                raft.metrics().borrow().current_leader == Some(sys_config.raft_node_id)
+            }
+            _ => false,
+        }
+    }
+
+    fn add_expired_key(expired_keys: &Arc<Mutex<HashSet<Vec<u8>>>>, key: Vec<u8>) {
+        if let Ok(mut set) = expired_keys.lock() {
+            set.insert(key);
         }
     }
 
+    fn remove_expired_key(&self, key: &[u8]) {
+        if let Ok(mut set) = self.expired_keys.lock() {
+            set.remove(key);
+        }
+    }
+
+    fn get_expired_size(&self) -> usize {
+        self.expired_keys
+            .lock()
+            .map(|e| e.len())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// 主节点通过raft提交过期key的删除;从节点只等待删除日志同步。
+    /// 删除以读取到的过期值为条件,避免删除提交前被重新设置的新值
+    fn gc_expired_keys(&mut self) {
+        if !self.is_leader() {
+            return;
+        }
+        let (raft_table_route, table_manager) =
+            match (self.raft_table_route.clone(), self.table_manager.clone()) {
+                (Some(raft_table_route), Some(table_manager)) => (raft_table_route, table_manager),
+                _ => return,
+            };
+        let keys: Vec<Vec<u8>> = if let Ok(set) = self.expired_keys.lock() {
+            set.iter().take(GC_BATCH_SIZE).cloned().collect()
+        } else {
+            return;
+        };
+        if keys.is_empty() {
+            return;
+        }
+        log::info!("CacheManager gc expired keys,size:{}", keys.len());
+        let expired_keys = self.expired_keys.clone();
+        actix::spawn(async move {
+            for key in keys {
+                let query_req = TableManagerQueryReq::GetByBytes {
+                    table_name: CACHE_TREE_NAME.clone(),
+                    key: key.clone(),
+                };
+                let expect = match table_manager.send(query_req).await {
+                    Ok(Ok(TableManagerResult::Value(v))) => v,
+                    Ok(Ok(_)) => {
+                        //已被删除
+                        if let Ok(mut set) = expired_keys.lock() {
+                            set.remove(&key);
+                        }
+                        continue;
+                    }
+                    _ => break,
+                };
+                let is_expired = CacheItemDo::from_bytes(&expect)
+                    .map(|e| e.timeout <= now_second_i32())
+                    .unwrap_or(true);
+                if !is_expired {
+                    //已被重新设置
+                    if let Ok(mut set) = expired_keys.lock() {
+                        set.remove(&key);
+                    }
+                    continue;
+                }
+                let req = TableManagerReq::CompareAndRemove {
+                    table_name: CACHE_TREE_NAME.clone(),
+                    key,
+                    expect,
+                };
+                if let Err(err) = raft_table_route.request(req).await {
+                    log::warn!("CacheManager gc expired key error,{}", err);
+                    break;
+                }
+            }
+        });
+    }
+
     fn load(&mut self, ctx: &mut Context<Self>) -> anyhow::Result<()> {
         let table_manager = self.table_manager.clone();
         async move {
@@ -86,26 +248,18 @@ impl CacheManager {
                 let cache_item = CacheItemDo::from_bytes(&v)?;
                 let ttl = cache_item.timeout - now;
                 if ttl <= 0 {
-                    Self::remove_key(&self.table_manager, k);
+                    Self::add_expired_key(&self.expired_keys, k);
                     continue;
                 }
+                self.remove_expired_key(&k);
                 let value: CacheValue = cache_item.try_into()?;
                 let key = CacheKey::from_db_key(k)?;
+                self.add_user_index(&key, &value);
                 self.cache.set(key, value, ttl);
             }
         }
         Ok(())
     }
-
-    fn remove_key(table_manager: &Option<Addr<TableManager>>, key: Vec<u8>) {
-        if let Some(table_manager) = table_manager.as_ref() {
-            let req = TableManagerReq::Remove {
-                table_name: CACHE_TREE_NAME.clone(),
-                key,
-            };
-            table_manager.do_send(req);
-        }
-    }
 }
 
 impl Actor for CacheManager {
@@ -127,9 +281,15 @@ impl Inject for CacheManager {
     ) {
         self.raft_table_route = factory_data.get_bean();
         self.table_manager = factory_data.get_actor();
-        let table_manager = self.table_manager.clone();
+        self.raft = factory_data.get_bean();
+        self.sys_config = factory_data.get_bean();
+        self.session_store = self
+            .sys_config
+            .as_ref()
+            .and_then(|e| SessionStoreFactory::build(e));
+        let expired_keys = self.expired_keys.clone();
         self.cache.time_out_fn = Some(Arc::new(move |key, _value| {
-            CacheManager::remove_key(&table_manager, key.to_key_string().as_bytes().to_vec());
+            CacheManager::add_expired_key(&expired_keys, key.to_key_string().into_bytes());
         }));
         //init
         self.load(ctx).ok();
@@ -137,6 +297,8 @@ impl Inject for CacheManager {
         self.cache.mode = MemCacheMode::None;
         ctx.run_interval(Duration::from_millis(10000), |act, _| {
             act.cache.clear_time_out();
+            act.clear_user_index();
+            act.gc_expired_keys();
         });
     }
 }
@@ -158,6 +320,8 @@ pub enum CacheManagerReq {
     NotifyRemove {
         key: Vec<u8>,
     },
+    /// 失效用户的全部登录会话与open api token
+    RemoveUserSessions(Arc<String>),
 }
 
 ///只能在主节点执行，才能保证限流的准确性
@@ -209,6 +373,10 @@ pub enum CacheManagerInnerCtx {
     NotifyRemove {
         key: Vec<u8>,
     },
+    /// 会话使用外部存储时的结果
+    External(Option<CacheValue>),
+    RemoveKeys(Vec<CacheKey>),
+    None,
 }
 
 impl Handler<CacheManagerReq> for CacheManager {
@@ -216,7 +384,57 @@ impl Handler<CacheManagerReq> for CacheManager {
 
     fn handle(&mut self, msg: CacheManagerReq, _ctx: &mut Self::Context) -> Self::Result {
         let raft_table_route = self.raft_table_route.clone();
+        let session_store = self.session_store.clone();
+        let user_keys: Vec<CacheKey> = match &msg {
+            CacheManagerReq::RemoveUserSessions(_) if session_store.is_some() => vec![],
+            CacheManagerReq::RemoveUserSessions(username) => self
+                .user_index
+                .get(username)
+                .map(|keys| keys.iter().cloned().collect())
+                .unwrap_or_default(),
+            _ => vec![],
+        };
         let fut = async move {
+            if let CacheManagerReq::RemoveUserSessions(username) = &msg {
+                if let Some(session_store) = &session_store {
+                    let size = session_store.remove_user_sessions(username).await?;
+                    if size > 0 {
+                        log::info!("remove user {} sessions,size:{}", username, size);
+                    }
+                    return Ok(CacheManagerInnerCtx::None);
+                }
+                for key in &user_keys {
+                    if let Some(raft_table_route) = &raft_table_route {
+                        let req = TableManagerReq::Remove {
+                            table_name: CACHE_TREE_NAME.clone(),
+                            key: key.to_key_string().into_bytes(),
+                        };
+                        raft_table_route.request(req).await?;
+                    }
+                }
+                if !user_keys.is_empty() {
+                    log::info!("remove user {} sessions,size:{}", username, user_keys.len());
+                }
+                return Ok(CacheManagerInnerCtx::RemoveKeys(user_keys));
+            }
+            if let Some(session_store) = &session_store {
+                match &msg {
+                    CacheManagerReq::Set { key, value, ttl } if key.cache_type.is_session() => {
+                        session_store.set(key, value.clone(), *ttl).await?;
+                        return Ok(CacheManagerInnerCtx::None);
+                    }
+                    CacheManagerReq::Get(key) if key.cache_type.is_session() => {
+                        return Ok(CacheManagerInnerCtx::External(
+                            session_store.get(key).await?,
+                        ));
+                    }
+                    CacheManagerReq::Remove(key) if key.cache_type.is_session() => {
+                        session_store.remove(key).await?;
+                        return Ok(CacheManagerInnerCtx::External(None));
+                    }
+                    _ => {}
+                }
+            }
             match msg {
                 CacheManagerReq::Set { key, value, ttl } => {
                     let now = now_second_i32();
@@ -254,6 +472,7 @@ impl Handler<CacheManagerReq> for CacheManager {
                 CacheManagerReq::NotifyRemove { key } => {
                     Ok(CacheManagerInnerCtx::NotifyRemove { key })
                 }
+                CacheManagerReq::RemoveUserSessions(_) => Ok(CacheManagerInnerCtx::None),
             }
         }
         .into_actor(self)
@@ -264,10 +483,13 @@ impl Handler<CacheManagerReq> for CacheManager {
                     Err(_) => Ok(CacheManagerResult::None),
                 },
                 CacheManagerInnerCtx::Remove(key) => {
+                    act.remove_user_index(&key);
                     act.cache.remove(&key);
                     Ok(CacheManagerResult::None)
                 }
                 CacheManagerInnerCtx::Set { key, value, ttl } => {
+                    act.remove_expired_key(key.to_key_string().as_bytes());
+                    act.add_user_index(&key, &value);
                     act.cache.set(key, value, ttl);
                     Ok(CacheManagerResult::None)
                 }
@@ -278,8 +500,20 @@ impl Handler<CacheManagerReq> for CacheManager {
                     };
                     Ok(CacheManagerResult::None)
                 }
+                CacheManagerInnerCtx::External(Some(v)) => Ok(CacheManagerResult::Value(v)),
+                CacheManagerInnerCtx::External(None) => Ok(CacheManagerResult::None),
+                CacheManagerInnerCtx::RemoveKeys(keys) => {
+                    for key in keys {
+                        act.remove_user_index(&key);
+                        act.cache.remove(&key);
+                    }
+                    Ok(CacheManagerResult::None)
+                }
+                CacheManagerInnerCtx::None => Ok(CacheManagerResult::None),
                 CacheManagerInnerCtx::NotifyRemove { key } => {
+                    act.remove_expired_key(&key);
                     let key = CacheKey::from_db_key(key)?;
+                    act.remove_user_index(&key);
                     act.cache.remove(&key);
                     Ok(CacheManagerResult::None)
                 }
@@ -339,3 +573,15 @@ impl Handler<CacheLimiterReq> for CacheManager {
         Ok(CacheManagerResult::Limiter(r))
     }
 }
+
+impl Handler<MetricsQuery> for CacheManager {
+    type Result = anyhow::Result<Vec<MetricsItem>>;
+
+    fn handle(&mut self, _msg: MetricsQuery, _ctx: &mut Self::Context) -> Self::Result {
+        let list = vec![MetricsItem {
+            metrics_type: MetricsKey::RaftCacheExpiredPendingSize,
+            record: MetricsRecord::Gauge(self.get_expired_size() as f32),
+        }];
+        Ok(list)
+    }
+}
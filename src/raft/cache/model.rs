@@ -4,6 +4,7 @@ use std::{collections::HashMap, convert::TryFrom, sync::Arc};
 use serde::{Deserialize, Serialize};
 
 use crate::common::model::{TokenSession, UserSession};
+use crate::common::schema_version::{SchemaVersionUtils, VersionedRecord};
 
 #[derive(Clone, prost::Message, Serialize, Deserialize)]
 pub struct CacheItemDo {
@@ -13,6 +14,9 @@ pub struct CacheItemDo {
     pub data: Vec<u8>,
     #[prost(int32, tag = "3")]
     pub timeout: i32,
+    #[prost(uint32, tag = "4")]
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 impl CacheItemDo {
@@ -23,7 +27,19 @@ impl CacheItemDo {
     }
 
     pub fn from_bytes(v: &[u8]) -> anyhow::Result<Self> {
-        Ok(prost::Message::decode(v)?)
+        let mut item: Self = prost::Message::decode(v)?;
+        SchemaVersionUtils::upgrade(&mut item)?;
+        Ok(item)
+    }
+}
+
+impl VersionedRecord for CacheItemDo {
+    fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    fn set_schema_version(&mut self, version: u32) {
+        self.schema_version = version;
     }
 }
 
@@ -51,6 +67,10 @@ impl CacheType {
         }
     }
 
+    pub fn is_session(&self) -> bool {
+        matches!(self, CacheType::UserSession | CacheType::ApiTokenSession)
+    }
+
     pub fn from_data(v: u8) -> anyhow::Result<Self> {
         match v {
             1 => Ok(CacheType::String),
@@ -175,6 +195,7 @@ impl From<CacheValue> for CacheItemDo {
             data: value.to_bytes(),
             timeout: 0,
             cache_type: value.get_cache_type().get_type_data() as u32,
+            schema_version: SchemaVersionUtils::write_version(),
         }
     }
 }
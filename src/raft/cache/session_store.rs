@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::common::AppSysConfig;
+
+use super::model::{CacheKey, CacheValue};
+
+pub const SESSION_STORE_RAFT: &str = "raft";
+pub const SESSION_STORE_REDIS: &str = "redis";
+
+///
+/// 登录会话与open api token的外部存储;未配置时使用raft缓存表
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn set(&self, key: &CacheKey, value: CacheValue, ttl: i32) -> anyhow::Result<()>;
+
+    async fn get(&self, key: &CacheKey) -> anyhow::Result<Option<CacheValue>>;
+
+    async fn remove(&self, key: &CacheKey) -> anyhow::Result<()>;
+
+    ///
+    /// 删除用户的全部会话与token,用于在任意节点上禁用用户或变更权限后立即失效;返回删除的数量
+    async fn remove_user_sessions(&self, username: &str) -> anyhow::Result<usize>;
+}
+
+pub struct SessionStoreFactory;
+
+impl SessionStoreFactory {
+    pub fn build(sys_config: &AppSysConfig) -> Option<Arc<dyn SessionStore>> {
+        match sys_config.session_store.as_str() {
+            SESSION_STORE_REDIS => Self::build_redis(sys_config),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "redis-session")]
+    fn build_redis(sys_config: &AppSysConfig) -> Option<Arc<dyn SessionStore>> {
+        match RedisSessionStore::new(&sys_config.session_redis_url) {
+            Ok(store) => {
+                log::info!("session store use redis");
+                Some(Arc::new(store))
+            }
+            Err(err) => {
+                log::error!("create redis session store error,{}", err);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "redis-session"))]
+    fn build_redis(_sys_config: &AppSysConfig) -> Option<Arc<dyn SessionStore>> {
+        log::warn!("the redis-session feature is not enabled,session store use raft");
+        None
+    }
+}
+
+#[cfg(feature = "redis-session")]
+pub struct RedisSessionStore {
+    client: redis::Client,
+    conn: tokio::sync::OnceCell<redis::aio::MultiplexedConnection>,
+}
+
+#[cfg(feature = "redis-session")]
+impl RedisSessionStore {
+    const KEY_PREFIX: &'static str = "rnacos:session:";
+    /// 用户名 -> 会话key集合,集合的过期时间不小于其中最晚过期的会话
+    const USER_KEY_PREFIX: &'static str = "rnacos:session:user:";
+
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            conn: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    fn build_key(key: &CacheKey) -> String {
+        format!(
+            "{}{}:{}",
+            Self::KEY_PREFIX,
+            key.cache_type.get_type_data(),
+            &key.key
+        )
+    }
+
+    fn build_user_key(username: &str) -> String {
+        format!("{}{}", Self::USER_KEY_PREFIX, username)
+    }
+
+    fn session_username(value: &CacheValue) -> Option<Arc<String>> {
+        match value {
+            CacheValue::UserSession(v) => Some(v.username.clone()),
+            CacheValue::ApiTokenSession(v) => Some(v.username.clone()),
+            _ => None,
+        }
+    }
+
+    fn encode_value(value: CacheValue) -> Vec<u8> {
+        let item: super::model::CacheItemDo = value.into();
+        item.to_bytes()
+    }
+
+    fn decode_value(data: &[u8]) -> anyhow::Result<CacheValue> {
+        use std::convert::TryInto;
+        super::model::CacheItemDo::from_bytes(data)?.try_into()
+    }
+
+    async fn get_conn(&self) -> anyhow::Result<redis::aio::MultiplexedConnection> {
+        let conn = self
+            .conn
+            .get_or_try_init(|| self.client.get_multiplexed_tokio_connection())
+            .await?;
+        Ok(conn.clone())
+    }
+}
+
+#[cfg(feature = "redis-session")]
+#[async_trait]
+impl SessionStore for RedisSessionStore {
+    async fn set(&self, key: &CacheKey, value: CacheValue, ttl: i32) -> anyhow::Result<()> {
+        let mut conn = self.get_conn().await?;
+        let ttl = ttl.max(1);
+        let session_key = Self::build_key(key);
+        let username = Self::session_username(&value);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .cmd("SET")
+            .arg(&session_key)
+            .arg(Self::encode_value(value))
+            .arg("EX")
+            .arg(ttl)
+            .ignore();
+        if let Some(username) = username {
+            //EXPIRE的NX与GT选项需要redis 7.0及以上版本
+            let user_key = Self::build_user_key(&username);
+            pipe.cmd("SADD")
+                .arg(&user_key)
+                .arg(&session_key)
+                .ignore()
+                .cmd("EXPIRE")
+                .arg(&user_key)
+                .arg(ttl)
+                .arg("NX")
+                .ignore()
+                .cmd("EXPIRE")
+                .arg(&user_key)
+                .arg(ttl)
+                .arg("GT")
+                .ignore();
+        }
+        pipe.query_async::<_, ()>(&mut conn).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &CacheKey) -> anyhow::Result<Option<CacheValue>> {
+        let mut conn = self.get_conn().await?;
+        let data: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(Self::build_key(key))
+            .query_async(&mut conn)
+            .await?;
+        match data {
+            Some(data) => Ok(Some(Self::decode_value(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn remove(&self, key: &CacheKey) -> anyhow::Result<()> {
+        let mut conn = self.get_conn().await?;
+        redis::cmd("DEL")
+            .arg(Self::build_key(key))
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_user_sessions(&self, username: &str) -> anyhow::Result<usize> {
+        let mut conn = self.get_conn().await?;
+        let user_key = Self::build_user_key(username);
+        let mut keys: Vec<String> = redis::cmd("SMEMBERS")
+            .arg(&user_key)
+            .query_async(&mut conn)
+            .await?;
+        let size = keys.len();
+        keys.push(user_key);
+        redis::cmd("DEL")
+            .arg(keys)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(size)
+    }
+}
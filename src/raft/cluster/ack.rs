@@ -0,0 +1,152 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::common::appdata::AppShareData;
+use crate::grpc::handler::RAFT_ROUTE_REQUEST;
+use crate::grpc::PayloadUtils;
+use crate::now_millis;
+
+use super::model::{RouterRequest, RouterResponse};
+
+/// 等待多数节点应用的最长时间
+const MAJORITY_APPLIED_TIMEOUT_MILLIS: u64 = 3000;
+const MAJORITY_APPLIED_CHECK_INTERVAL_MILLIS: u64 = 20;
+
+///
+/// 写请求的确认级别,由请求参数ackLevel指定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteAckLevel {
+    /// 等待raft日志提交并在leader上应用(默认);
+    /// async-raft在日志提交后才会应用到leader状态机,applied-on-leader与committed是同一个确认点,
+    /// ackLevel=leader作为该级别的别名
+    #[default]
+    Committed,
+    /// 提交后继续等待多数节点应用到状态机
+    Majority,
+}
+
+impl WriteAckLevel {
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "committed" | "leader" => Some(Self::Committed),
+            "majority" => Some(Self::Majority),
+            _ => None,
+        }
+    }
+
+    pub fn parse(value: &Option<String>) -> Result<Self, String> {
+        match value.as_ref().map(|v| v.trim()) {
+            None | Some("") => Ok(Self::default()),
+            Some(v) => Self::from_name(v).ok_or_else(|| format!("ackLevel is invalid: {}", v)),
+        }
+    }
+}
+
+pub struct WriteAckUtils;
+
+impl WriteAckUtils {
+    ///
+    /// 按确认级别执行写请求
+    pub async fn write<F>(
+        app: &Arc<AppShareData>,
+        level: WriteAckLevel,
+        fut: F,
+    ) -> anyhow::Result<()>
+    where
+        F: Future<Output = anyhow::Result<()>>,
+    {
+        match level {
+            WriteAckLevel::Committed => fut.await,
+            WriteAckLevel::Majority => {
+                fut.await?;
+                Self::wait_majority_applied(app).await
+            }
+        }
+    }
+
+    ///
+    /// 写请求完成后leader已应用的日志位置,包含本次写入
+    async fn get_leader_applied(app: &AppShareData) -> anyhow::Result<u64> {
+        let leader = app
+            .raft
+            .current_leader()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("unknown the raft leader addr!"))?;
+        Self::get_node_applied(app, leader).await
+    }
+
+    pub(crate) async fn get_node_applied(app: &AppShareData, node_id: u64) -> anyhow::Result<u64> {
+        if node_id == app.sys_config.raft_node_id {
+            return Ok(app.raft.metrics().borrow().last_applied);
+        }
+        let addr = app.raft_store.get_target_addr(node_id).await?;
+        let request = serde_json::to_string(&RouterRequest::QueryAppliedIndex)?;
+        let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+        let resp_payload = app.cluster_sender.send_request(addr, payload).await?;
+        let body_vec = resp_payload.body.unwrap_or_default().value;
+        match serde_json::from_slice(&body_vec)? {
+            RouterResponse::AppliedIndex { index } => Ok(index),
+            _ => Err(anyhow::anyhow!("QueryAppliedIndex response type is error!")),
+        }
+    }
+
+    pub async fn wait_majority_applied(app: &AppShareData) -> anyhow::Result<()> {
+        let target = Self::get_leader_applied(app).await?;
+        let members: Vec<u64> = app
+            .raft
+            .metrics()
+            .borrow()
+            .membership_config
+            .members
+            .iter()
+            .copied()
+            .collect();
+        let majority = members.len() / 2 + 1;
+        let deadline = now_millis() + MAJORITY_APPLIED_TIMEOUT_MILLIS;
+        loop {
+            let mut applied_count = 0;
+            for node_id in &members {
+                if let Ok(index) = Self::get_node_applied(app, *node_id).await {
+                    if index >= target {
+                        applied_count += 1;
+                    }
+                }
+            }
+            if applied_count >= majority {
+                return Ok(());
+            }
+            if now_millis() > deadline {
+                return Err(anyhow::anyhow!(
+                    "wait majority applied timeout, applied nodes: {}/{}",
+                    applied_count,
+                    members.len()
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(
+                MAJORITY_APPLIED_CHECK_INTERVAL_MILLIS,
+            ))
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ack_level() {
+        assert_eq!(WriteAckLevel::parse(&None), Ok(WriteAckLevel::Committed));
+        assert_eq!(
+            WriteAckLevel::parse(&Some("Majority".to_owned())),
+            Ok(WriteAckLevel::Majority)
+        );
+        assert_eq!(
+            WriteAckLevel::parse(&Some("leader".to_owned())),
+            Ok(WriteAckLevel::Committed)
+        );
+        assert!(WriteAckLevel::parse(&Some("accepted".to_owned())).is_err());
+        assert!(WriteAckLevel::parse(&Some("all".to_owned())).is_err());
+    }
+}
@@ -4,14 +4,16 @@ use async_raft_ext::raft::ClientWriteRequest;
 
 use crate::{
     common::appdata::AppShareData,
-    config::core::{ConfigAsyncCmd, ConfigKey},
+    config::core::{ConfigAsyncCmd, ConfigCmd, ConfigKey, ConfigResult},
 };
 
-use self::model::{RouterRequest, RouterResponse};
+use self::model::{RouterRequest, RouterResponse, SetConfigReq};
 
 use super::{db::table::TableManagerAsyncReq, join_node, store::ClientRequest};
 
+pub mod ack;
 pub mod model;
+pub mod restart;
 pub mod route;
 pub mod routeapi;
 
@@ -20,24 +22,10 @@ pub async fn handle_route(
     req: RouterRequest,
 ) -> anyhow::Result<RouterResponse> {
     match req {
-        RouterRequest::ConfigSet {
-            key,
-            value,
-            op_user,
-            config_type,
-            desc,
-            extend_info: _,
-        } => {
-            let config_key: ConfigKey = (&key as &str).into();
-            app.config_addr
-                .send(ConfigAsyncCmd::Add {
-                    key: config_key,
-                    value,
-                    op_user,
-                    config_type,
-                    desc,
-                })
-                .await??;
+        RouterRequest::ConfigSet { .. } => {
+            if let Some(req) = SetConfigReq::from_router_request(req) {
+                app.config_addr.send(ConfigAsyncCmd::Add(req)).await??;
+            }
         }
         RouterRequest::ConfigDel {
             key,
@@ -76,6 +64,32 @@ pub async fn handle_route(
             let result = app.cache_manager.send(req).await??;
             return Ok(RouterResponse::CacheManagerResult { result });
         }
+        RouterRequest::ConfigBatchSet { items } => {
+            let list: Vec<SetConfigReq> = items
+                .into_iter()
+                .filter_map(SetConfigReq::from_router_request)
+                .collect();
+            app.config_addr
+                .send(ConfigAsyncCmd::BatchAdd(list))
+                .await??;
+        }
+        RouterRequest::QueryAppliedIndex => {
+            let index = app.raft.metrics().borrow().last_applied;
+            return Ok(RouterResponse::AppliedIndex { index });
+        }
+        RouterRequest::TakeSubscribeReplay {
+            instance_id,
+            owner_ip,
+        } => {
+            let cmd = ConfigCmd::TakeSubscribeReplay {
+                instance_id,
+                owner_ip,
+            };
+            if let ConfigResult::SubscribeReplayKeys(keys) = app.config_addr.send(cmd).await?? {
+                let keys = keys.map(|list| list.iter().map(|key| key.build_key()).collect());
+                return Ok(RouterResponse::SubscribeReplayKeys { keys });
+            }
+        }
     };
     Ok(RouterResponse::None)
 }
@@ -11,6 +11,13 @@ use crate::{
     },
 };
 
+/// 路由写入请求extend_info中的期望md5
+const EXTEND_INFO_EXPECTED_MD5: &str = "expectedMd5";
+/// 路由写入请求extend_info中的允许冻结期变更标记
+const EXTEND_INFO_FREEZE_OVERRIDE: &str = "freezeOverride";
+/// 路由写入请求extend_info中的跳过内容格式校验标记
+const EXTEND_INFO_SKIP_VALIDATION: &str = "skipValidation";
+
 pub enum RouteAddr {
     Local,
     Remote(u64, Arc<String>),
@@ -24,6 +31,12 @@ pub struct SetConfigReq {
     pub op_user: Option<Arc<String>>,
     pub config_type: Option<Arc<String>>,
     pub desc: Option<Arc<String>>,
+    /// 写入前比较配置当前的md5,不一致时写入失败;空字符串表示配置不存在
+    pub expected_md5: Option<Arc<String>>,
+    /// 管理员强制在变更冻结期内写入
+    pub freeze_override: bool,
+    /// 跳过按配置类型的内容格式校验
+    pub skip_validation: bool,
     //pub can_route_to_remote: bool,
     //pub extend_info: Option<HashMap<String,String>>,
 }
@@ -36,6 +49,9 @@ impl SetConfigReq {
             op_user: None,
             config_type: None,
             desc: None,
+            expected_md5: None,
+            freeze_override: false,
+            skip_validation: false,
         }
     }
 
@@ -50,6 +66,36 @@ impl SetConfigReq {
             op_user: Some(op_user),
             config_type: None,
             desc: None,
+            expected_md5: None,
+            freeze_override: false,
+            skip_validation: false,
+        }
+    }
+
+    ///
+    /// 从路由请求还原写入请求,不是ConfigSet时返回None
+    pub fn from_router_request(req: RouterRequest) -> Option<Self> {
+        if let RouterRequest::ConfigSet {
+            key,
+            value,
+            op_user,
+            config_type,
+            desc,
+            extend_info,
+        } = req
+        {
+            let mut req = Self::new((&key as &str).into(), value);
+            req.op_user = op_user;
+            req.config_type = config_type;
+            req.desc = desc;
+            req.expected_md5 = extend_info
+                .get(EXTEND_INFO_EXPECTED_MD5)
+                .map(|v| Arc::new(v.to_owned()));
+            req.freeze_override = extend_info.contains_key(EXTEND_INFO_FREEZE_OVERRIDE);
+            req.skip_validation = extend_info.contains_key(EXTEND_INFO_SKIP_VALIDATION);
+            Some(req)
+        } else {
+            None
         }
     }
 
@@ -106,17 +152,38 @@ pub enum RouterRequest {
     CacheLimiterReq {
         req: CacheLimiterReq,
     },
+    /// 查询节点已应用到状态机的日志位置
+    QueryAppliedIndex,
+    /// 批量写入配置,items为ConfigSet
+    ConfigBatchSet {
+        items: Vec<RouterRequest>,
+    },
+    /// 取回本节点保存的客户端订阅记录
+    TakeSubscribeReplay {
+        instance_id: Arc<String>,
+        owner_ip: String,
+    },
 }
 
 impl From<SetConfigReq> for RouterRequest {
     fn from(req: SetConfigReq) -> Self {
+        let mut extend_info = HashMap::new();
+        if let Some(md5) = req.expected_md5 {
+            extend_info.insert(EXTEND_INFO_EXPECTED_MD5.to_owned(), md5.as_ref().to_owned());
+        }
+        if req.freeze_override {
+            extend_info.insert(EXTEND_INFO_FREEZE_OVERRIDE.to_owned(), "true".to_owned());
+        }
+        if req.skip_validation {
+            extend_info.insert(EXTEND_INFO_SKIP_VALIDATION.to_owned(), "true".to_owned());
+        }
         Self::ConfigSet {
             key: req.config_key.build_key(),
             value: req.value,
             op_user: req.op_user,
             config_type: req.config_type,
             desc: req.desc,
-            extend_info: Default::default(),
+            extend_info,
         }
     }
 }
@@ -139,6 +206,17 @@ impl From<CacheLimiterReq> for RouterRequest {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RouterResponse {
     None,
-    TableManagerResult { result: TableManagerResult },
-    CacheManagerResult { result: CacheManagerResult },
+    TableManagerResult {
+        result: TableManagerResult,
+    },
+    CacheManagerResult {
+        result: CacheManagerResult,
+    },
+    AppliedIndex {
+        index: u64,
+    },
+    /// 订阅记录中的配置key
+    SubscribeReplayKeys {
+        keys: Option<Vec<String>>,
+    },
 }
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::now_millis;
+use crate::raft::filestore::raftsnapshot::{
+    RaftSnapshotManager, RaftSnapshotRequest, RaftSnapshotResponse,
+};
+
+use super::ack::WriteAckUtils;
+
+/// 其它节点已应用日志落后leader不超过该值视为已同步
+const RESTART_MAX_APPLIED_LAG: u64 = 100;
+const RESTART_CHECK_INTERVAL_MILLIS: u64 = 1000;
+
+///
+/// 当前节点是否可以安全重启,用于滚动升级时的preStop检查
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartCheckReport {
+    pub node_id: u64,
+    pub safe: bool,
+    pub is_leader: bool,
+    pub leader_id: Option<u64>,
+    pub member_count: usize,
+    pub last_applied: u64,
+    pub leader_applied: u64,
+    pub snapshot_building: bool,
+    /// 落后或无法访问的其它节点
+    pub lagging_nodes: Vec<u64>,
+    /// 不能安全重启的原因
+    pub reasons: Vec<String>,
+}
+
+pub struct RestartCheckUtils;
+
+impl RestartCheckUtils {
+    async fn is_snapshot_building(app: &AppShareData) -> bool {
+        let snapshot_manager = match app.factory_data.get_actor::<RaftSnapshotManager>() {
+            Some(v) => v,
+            None => return false,
+        };
+        matches!(
+            snapshot_manager
+                .send(RaftSnapshotRequest::QueryBuilding)
+                .await,
+            Ok(Ok(RaftSnapshotResponse::Building(true)))
+        )
+    }
+
+    ///
+    /// 可以安全重启的条件:没有正在构建的镜像、集群有leader、其它节点都已同步到leader的应用位置
+    pub async fn check(app: &AppShareData) -> RestartCheckReport {
+        let node_id = app.sys_config.raft_node_id;
+        let metrics = app.raft.metrics().borrow().clone();
+        let members: Vec<u64> = metrics.membership_config.members.iter().copied().collect();
+        let mut report = RestartCheckReport {
+            node_id,
+            is_leader: metrics.current_leader == Some(node_id),
+            leader_id: metrics.current_leader,
+            member_count: members.len(),
+            last_applied: metrics.last_applied,
+            ..Default::default()
+        };
+        report.snapshot_building = Self::is_snapshot_building(app).await;
+        if report.snapshot_building {
+            report
+                .reasons
+                .push("snapshot is being built on this node".to_owned());
+        }
+        let leader_id = if let Some(v) = metrics.current_leader {
+            v
+        } else {
+            report.reasons.push("the raft leader is unknown".to_owned());
+            return report;
+        };
+        match WriteAckUtils::get_node_applied(app, leader_id).await {
+            Ok(v) => report.leader_applied = v,
+            Err(err) => {
+                report
+                    .reasons
+                    .push(format!("query leader applied index error,{}", err));
+                return report;
+            }
+        }
+        for member in members.iter().filter(|v| **v != node_id) {
+            let applied = if *member == leader_id {
+                report.leader_applied
+            } else {
+                WriteAckUtils::get_node_applied(app, *member)
+                    .await
+                    .unwrap_or_default()
+            };
+            if applied + RESTART_MAX_APPLIED_LAG < report.leader_applied {
+                report.lagging_nodes.push(*member);
+            }
+        }
+        if !report.lagging_nodes.is_empty() {
+            report.reasons.push(format!(
+                "other nodes are not in sync: {:?}",
+                &report.lagging_nodes
+            ));
+        }
+        report.safe = report.reasons.is_empty();
+        report
+    }
+
+    ///
+    /// 等待直到可以安全重启或超时,返回最后一次检查结果
+    pub async fn wait_safe(app: &Arc<AppShareData>, timeout_millis: u64) -> RestartCheckReport {
+        let deadline = now_millis() + timeout_millis;
+        loop {
+            let report = Self::check(app).await;
+            if report.safe || now_millis() >= deadline {
+                return report;
+            }
+            tokio::time::sleep(Duration::from_millis(RESTART_CHECK_INTERVAL_MILLIS)).await;
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
 use actix::prelude::*;
 
+use crate::config::core::{is_md5_conflict, ConfigKey, ConfigResult, CONFIG_MD5_CONFLICT_MSG};
+use crate::grpc::api_model::BaseResponse;
 use crate::grpc::handler::RAFT_ROUTE_REQUEST;
+use crate::grpc::nacos_proto::Payload;
 use crate::raft::filestore::core::FileStore;
 use crate::{
     config::core::{ConfigActor, ConfigAsyncCmd, ConfigCmd},
@@ -51,6 +54,9 @@ impl RaftAddrRouter {
     }
 }
 
+/// 比较md5写入冲突时的最大重试次数
+pub const CONFIG_CAS_RETRY_TIMES: u64 = 5;
+
 #[derive(Clone, Debug)]
 pub struct ConfigRoute {
     config_addr: Addr<ConfigActor>,
@@ -75,17 +81,75 @@ impl ConfigRoute {
         anyhow::anyhow!("unknown the raft leader addr!")
     }
 
+    ///
+    /// leader处理失败时返回ErrorResponse,转换为带原始错误信息的错误
+    fn parse_response(payload: Payload) -> anyhow::Result<RouterResponse> {
+        let is_error = payload
+            .metadata
+            .as_ref()
+            .map(|e| e.r#type == "ErrorResponse")
+            .unwrap_or(false);
+        let body_vec = payload.body.unwrap_or_default().value;
+        if is_error {
+            let response: BaseResponse = serde_json::from_slice(&body_vec)?;
+            return Err(anyhow::anyhow!(response.message.unwrap_or_default()));
+        }
+        Ok(serde_json::from_slice(&body_vec)?)
+    }
+
+    ///
+    /// 读取配置原始内容与md5,配置不存在时都为空字符串
+    pub async fn get_raw_config(
+        &self,
+        key: &ConfigKey,
+    ) -> anyhow::Result<(Arc<String>, Arc<String>)> {
+        match self
+            .config_addr
+            .send(ConfigCmd::GetRaw(key.clone()))
+            .await??
+        {
+            ConfigResult::Data { value, md5, .. } => Ok((value, md5)),
+            _ => Ok((Arc::new(String::new()), Arc::new(String::new()))),
+        }
+    }
+
+    ///
+    /// 比较md5写入冲突后等待一段时间再重试;从节点的缓存可能落后于leader,需要等待同步后再读取
+    pub async fn conflict_backoff(retry_times: u64) {
+        tokio::time::sleep(Duration::from_millis(50 * (retry_times + 1))).await;
+    }
+
+    ///
+    /// 读取-修改-写入单个配置,写入时比较md5,期间被其它请求修改时重新读取后重试;
+    /// update返回None时不写入,返回是否写入
+    pub async fn update_config<F>(&self, key: &ConfigKey, mut update: F) -> anyhow::Result<bool>
+    where
+        F: FnMut(&str) -> anyhow::Result<Option<String>>,
+    {
+        for i in 0..CONFIG_CAS_RETRY_TIMES {
+            let (value, md5) = self.get_raw_config(key).await?;
+            let value = match update(&value)? {
+                Some(v) => v,
+                None => return Ok(false),
+            };
+            let mut req = SetConfigReq::new(key.clone(), Arc::new(value));
+            req.expected_md5 = Some(md5);
+            match self.set_config(req).await {
+                Ok(_) => return Ok(true),
+                Err(err) if is_md5_conflict(&err) => Self::conflict_backoff(i).await,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(anyhow::anyhow!(
+            "{}, retry times exceeded",
+            CONFIG_MD5_CONFLICT_MSG
+        ))
+    }
+
     pub async fn set_config(&self, req: SetConfigReq) -> anyhow::Result<()> {
         match self.raft_addr_route.get_route_addr().await? {
             RouteAddr::Local => {
-                let cmd = ConfigAsyncCmd::Add {
-                    key: req.config_key,
-                    value: req.value,
-                    op_user: req.op_user,
-                    config_type: req.config_type,
-                    desc: req.desc,
-                };
-                self.config_addr.send(cmd).await?.ok();
+                self.config_addr.send(ConfigAsyncCmd::Add(req)).await??;
             }
             RouteAddr::Remote(_, addr) => {
                 let source_req = req.clone();
@@ -93,8 +157,7 @@ impl ConfigRoute {
                 let request = serde_json::to_string(&req).unwrap_or_default();
                 let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
                 let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
-                let body_vec = resp_payload.body.unwrap_or_default().value;
-                let _: RouterResponse = serde_json::from_slice(&body_vec)?;
+                Self::parse_response(resp_payload)?;
                 self.config_addr.do_send(ConfigCmd::SetTmpValue(
                     source_req.config_key,
                     source_req.value,
@@ -107,6 +170,41 @@ impl ConfigRoute {
         Ok(())
     }
 
+    ///
+    /// 多个配置合并为一条raft日志写入,全部成功或全部失败
+    pub async fn batch_set_config(&self, list: Vec<SetConfigReq>) -> anyhow::Result<()> {
+        if list.is_empty() {
+            return Ok(());
+        }
+        match self.raft_addr_route.get_route_addr().await? {
+            RouteAddr::Local => {
+                self.config_addr
+                    .send(ConfigAsyncCmd::BatchAdd(list))
+                    .await??;
+            }
+            RouteAddr::Remote(_, addr) => {
+                let tmp_values: Vec<_> = list
+                    .iter()
+                    .map(|e| (e.config_key.clone(), e.value.clone()))
+                    .collect();
+                let req = RouterRequest::ConfigBatchSet {
+                    items: list.into_iter().map(|e| e.into()).collect(),
+                };
+                let request = serde_json::to_string(&req).unwrap_or_default();
+                let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+                let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
+                Self::parse_response(resp_payload)?;
+                for (key, value) in tmp_values {
+                    self.config_addr.do_send(ConfigCmd::SetTmpValue(key, value));
+                }
+            }
+            RouteAddr::Unknown => {
+                return Err(self.unknown_err());
+            }
+        }
+        Ok(())
+    }
+
     pub async fn del_config(&self, req: DelConfigReq) -> anyhow::Result<()> {
         match self.raft_addr_route.get_route_addr().await? {
             RouteAddr::Local => {
@@ -118,8 +216,7 @@ impl ConfigRoute {
                 let request = serde_json::to_string(&req).unwrap_or_default();
                 let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
                 let resp_payload = self.cluster_sender.send_request(addr, payload).await?;
-                let body_vec = resp_payload.body.unwrap_or_default().value;
-                let _: RouterResponse = serde_json::from_slice(&body_vec)?;
+                Self::parse_response(resp_payload)?;
             }
             RouteAddr::Unknown => {
                 return Err(self.unknown_err());
@@ -8,7 +8,7 @@ use crate::{
     raft::{
         cluster::{
             model::{RouteAddr, RouterRequest, RouterResponse},
-            route::RaftAddrRouter,
+            route::{ConfigRoute, RaftAddrRouter, CONFIG_CAS_RETRY_TIMES},
         },
         network::factory::RaftClusterRequestSender,
     },
@@ -62,6 +62,59 @@ impl TableRoute {
         Ok(())
     }
 
+    ///
+    /// 读取-修改-写入主节点上的单个值,存在旧值时按读取到的值比较后写入,
+    /// 期间被其它请求修改时重新读取后重试
+    pub async fn update_value<F>(
+        &self,
+        table_name: Arc<String>,
+        key: Vec<u8>,
+        mut update: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(Option<&[u8]>) -> anyhow::Result<Vec<u8>>,
+    {
+        let query_req = TableManagerQueryReq::GetByBytes {
+            table_name: table_name.clone(),
+            key: key.clone(),
+        };
+        for i in 0..CONFIG_CAS_RETRY_TIMES {
+            let old = match self.get_leader_data(query_req.clone()).await? {
+                TableManagerResult::Value(v) => Some(v),
+                _ => None,
+            };
+            let value = update(old.as_deref())?;
+            let req = match old {
+                Some(expect) => TableManagerReq::CompareAndSet {
+                    table_name: table_name.clone(),
+                    key: key.clone(),
+                    expect,
+                    value: value.clone(),
+                },
+                None => {
+                    let req = TableManagerReq::Set {
+                        table_name: table_name.clone(),
+                        key: key.clone(),
+                        value,
+                        last_seq_id: None,
+                    };
+                    return self.request(req).await;
+                }
+            };
+            self.request(req).await?;
+            if let TableManagerResult::Value(v) = self.get_leader_data(query_req.clone()).await? {
+                if v == value {
+                    return Ok(());
+                }
+            }
+            ConfigRoute::conflict_backoff(i).await;
+        }
+        Err(anyhow::anyhow!(
+            "the value of table {} is changed concurrently, retry times exceeded",
+            &table_name
+        ))
+    }
+
     pub async fn get_leader_data(
         &self,
         req: TableManagerQueryReq,
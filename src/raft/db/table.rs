@@ -10,8 +10,12 @@ use serde::{Deserialize, Serialize};
 
 use actix::prelude::*;
 
-use crate::common::constant::CACHE_TREE_NAME;
+use crate::common::constant::{
+    CACHE_TREE_NAME, NAMING_PERSISTENT_INSTANCE_TREE_NAME, NAMING_SERVICE_TREE_NAME,
+};
 use crate::common::sequence_utils::SimpleSequence;
+use crate::naming::core::{NamingActor, NamingCmd};
+use crate::naming::persistent::{PersistentInstanceUtils, PersistentServiceUtils};
 use crate::raft::filestore::model::SnapshotRecordDto;
 use crate::raft::filestore::raftsnapshot::{SnapshotWriterActor, SnapshotWriterRequest};
 use crate::{
@@ -79,6 +83,7 @@ pub struct TableManager {
     pub table_map: HashMap<Arc<String>, TableInfo>,
     raft: Option<Weak<NacosRaft>>,
     cache_manager: Option<Addr<CacheManager>>,
+    naming_addr: Option<Addr<NamingActor>>,
 }
 
 impl TableManager {
@@ -251,6 +256,74 @@ impl TableManager {
         ret.push((k.to_vec(), v.to_vec()));
     }
 
+    fn notify_persistent_instance(&self, value: &[u8], is_remove: bool) {
+        if let Some(naming_addr) = &self.naming_addr {
+            match PersistentInstanceUtils::from_bytes(value) {
+                Ok(instance) if is_remove => {
+                    naming_addr.do_send(NamingCmd::RemovePersistent(instance))
+                }
+                Ok(instance) => naming_addr.do_send(NamingCmd::UpdatePersistent(instance)),
+                Err(err) => log::error!("parse persistent instance error,{}", err),
+            }
+        }
+    }
+
+    fn notify_persistent_service(&self, value: &[u8], is_remove: bool) {
+        if let Some(naming_addr) = &self.naming_addr {
+            match PersistentServiceUtils::from_bytes(value) {
+                Ok(service) if is_remove => naming_addr
+                    .do_send(NamingCmd::RemovePersistentService(service.to_service_key())),
+                Ok(service) => naming_addr.do_send(NamingCmd::UpdatePersistentService(service)),
+                Err(err) => log::error!("parse persistent service error,{}", err),
+            }
+        }
+    }
+
+    ///
+    /// 写入表数据,并通知依赖该表的模块
+    fn apply_set(
+        &mut self,
+        table_name: Arc<String>,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        last_seq_id: Option<u64>,
+    ) {
+        if table_name.as_str() == CACHE_TREE_NAME.as_str() {
+            if let Some(cache_manager) = &self.cache_manager {
+                let req = CacheManagerReq::NotifyChange {
+                    key: key.clone(),
+                    value: value.clone(),
+                };
+                cache_manager.do_send(req);
+            }
+        } else if table_name.as_str() == NAMING_PERSISTENT_INSTANCE_TREE_NAME.as_str() {
+            self.notify_persistent_instance(&value, false);
+        } else if table_name.as_str() == NAMING_SERVICE_TREE_NAME.as_str() {
+            self.notify_persistent_service(&value, false);
+        }
+        self.insert(table_name, key, value, last_seq_id);
+    }
+
+    ///
+    /// 删除表数据,并通知依赖该表的模块
+    fn apply_remove(&mut self, table_name: Arc<String>, key: Vec<u8>) -> Option<Vec<u8>> {
+        if table_name.as_str() == CACHE_TREE_NAME.as_str() {
+            if let Some(cache_manager) = &self.cache_manager {
+                let req = CacheManagerReq::NotifyRemove { key: key.clone() };
+                cache_manager.do_send(req);
+            }
+        } else if table_name.as_str() == NAMING_PERSISTENT_INSTANCE_TREE_NAME.as_str() {
+            if let Some(value) = self.get(table_name.clone(), key.clone()) {
+                self.notify_persistent_instance(&value, true);
+            }
+        } else if table_name.as_str() == NAMING_SERVICE_TREE_NAME.as_str() {
+            if let Some(value) = self.get(table_name.clone(), key.clone()) {
+                self.notify_persistent_service(&value, true);
+            }
+        }
+        self.remove(table_name, key)
+    }
+
     fn get_table_names(&self) -> Vec<Arc<String>> {
         self.table_map.values().map(|e| e.name.clone()).collect()
     }
@@ -294,6 +367,7 @@ impl Inject for TableManager {
         let raft: Option<Arc<NacosRaft>> = factory_data.get_bean();
         self.raft = raft.map(|e| Arc::downgrade(&e));
         self.cache_manager = factory_data.get_actor();
+        self.naming_addr = factory_data.get_actor();
     }
 }
 
@@ -328,6 +402,20 @@ pub enum TableManagerReq {
         last_seq_id: u64,
     },
     ReloadTable,
+    /// 当前值与expect一致时才写入,用于避免覆盖并发修改;
+    /// 旧版本节点无法解析该日志,混合版本集群需要全部节点升级后才会生效
+    CompareAndSet {
+        table_name: Arc<String>,
+        key: Vec<u8>,
+        expect: Vec<u8>,
+        value: Vec<u8>,
+    },
+    /// 当前值与expect一致时才删除;兼容性同CompareAndSet
+    CompareAndRemove {
+        table_name: Arc<String>,
+        key: Vec<u8>,
+        expect: Vec<u8>,
+    },
 }
 
 #[derive(Message)]
@@ -410,26 +498,11 @@ impl Handler<TableManagerReq> for TableManager {
                 value,
                 last_seq_id,
             } => {
-                if table_name.as_str() == CACHE_TREE_NAME.as_str() {
-                    if let Some(cache_manager) = &self.cache_manager {
-                        let req = CacheManagerReq::NotifyChange {
-                            key: key.clone(),
-                            value: value.clone(),
-                        };
-                        cache_manager.do_send(req);
-                    }
-                }
-                self.insert(table_name, key, value, last_seq_id);
+                self.apply_set(table_name, key, value, last_seq_id);
                 Ok(TableManagerResult::None)
             }
             TableManagerReq::Remove { table_name, key } => {
-                if table_name.as_str() == CACHE_TREE_NAME.as_str() {
-                    if let Some(cache_manager) = &self.cache_manager {
-                        let req = CacheManagerReq::NotifyRemove { key: key.clone() };
-                        cache_manager.do_send(req);
-                    }
-                }
-                match self.remove(table_name, key) {
+                match self.apply_remove(table_name, key) {
                     Some(v) => Ok(TableManagerResult::Value(v.to_vec())),
                     None => Ok(TableManagerResult::None),
                 }
@@ -462,6 +535,31 @@ impl Handler<TableManagerReq> for TableManager {
                 //self.load_tables();
                 Ok(TableManagerResult::None)
             }
+            TableManagerReq::CompareAndSet {
+                table_name,
+                key,
+                expect,
+                value,
+            } => {
+                if self.get(table_name.clone(), key.clone()).as_ref() != Some(&expect) {
+                    return Ok(TableManagerResult::None);
+                }
+                self.apply_set(table_name, key, value, None);
+                Ok(TableManagerResult::None)
+            }
+            TableManagerReq::CompareAndRemove {
+                table_name,
+                key,
+                expect,
+            } => {
+                if self.get(table_name.clone(), key.clone()).as_ref() != Some(&expect) {
+                    return Ok(TableManagerResult::None);
+                }
+                match self.apply_remove(table_name, key) {
+                    Some(v) => Ok(TableManagerResult::Value(v.to_vec())),
+                    None => Ok(TableManagerResult::None),
+                }
+            }
         }
     }
 }
@@ -519,3 +617,59 @@ impl Handler<TableManagerQueryReq> for TableManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_compare_and_set_or_remove() {
+        let table = TableManager::new().start();
+        let table_name = Arc::new("T_TEST".to_owned());
+        let set = |value: &[u8]| TableManagerReq::Set {
+            table_name: table_name.clone(),
+            key: b"k".to_vec(),
+            value: value.to_vec(),
+            last_seq_id: None,
+        };
+        let cas = |expect: &[u8], value: &[u8]| TableManagerReq::CompareAndSet {
+            table_name: table_name.clone(),
+            key: b"k".to_vec(),
+            expect: expect.to_vec(),
+            value: value.to_vec(),
+        };
+        let get = || TableManagerQueryReq::GetByBytes {
+            table_name: table_name.clone(),
+            key: b"k".to_vec(),
+        };
+        table.send(set(b"v1")).await.unwrap().unwrap();
+        //并发修改后期望值不一致,不覆盖
+        table.send(set(b"v2")).await.unwrap().unwrap();
+        table.send(cas(b"v1", b"v3")).await.unwrap().unwrap();
+        match table.send(get()).await.unwrap().unwrap() {
+            TableManagerResult::Value(v) => assert_eq!(v, b"v2"),
+            _ => panic!("value is none"),
+        }
+        table.send(cas(b"v2", b"v3")).await.unwrap().unwrap();
+        match table.send(get()).await.unwrap().unwrap() {
+            TableManagerResult::Value(v) => assert_eq!(v, b"v3"),
+            _ => panic!("value is none"),
+        }
+
+        let cas_remove = |expect: &[u8]| TableManagerReq::CompareAndRemove {
+            table_name: table_name.clone(),
+            key: b"k".to_vec(),
+            expect: expect.to_vec(),
+        };
+        table.send(cas_remove(b"v2")).await.unwrap().unwrap();
+        assert!(matches!(
+            table.send(get()).await.unwrap().unwrap(),
+            TableManagerResult::Value(_)
+        ));
+        table.send(cas_remove(b"v3")).await.unwrap().unwrap();
+        assert!(matches!(
+            table.send(get()).await.unwrap().unwrap(),
+            TableManagerResult::None
+        ));
+    }
+}
@@ -8,8 +8,9 @@ use crate::raft::filestore::raftlog::{
     RaftLogManager, RaftLogManagerAsyncRequest, RaftLogManagerRequest, RaftLogResponse,
 };
 use crate::raft::filestore::raftsnapshot::{
-    RaftSnapshotManager, RaftSnapshotRequest, RaftSnapshotResponse,
+    RaftSnapshotManager, RaftSnapshotRequest, RaftSnapshotResponse, SnapshotReader,
 };
+use crate::raft::filestore::snapshotdiff::{SnapshotDiffTracker, SnapshotDiffUtils};
 use crate::raft::filestore::StoreUtils;
 use crate::raft::store::{ClientRequest, ClientResponse, ShutdownError};
 use actix::prelude::*;
@@ -36,6 +37,7 @@ pub struct FileStore {
     log_manager: Addr<RaftLogManager>,
     //data_store: Addr<RaftDataStore>,
     apply_manager: Addr<StateApplyManager>,
+    diff_tracker: Arc<SnapshotDiffTracker>,
 }
 
 impl FileStore {
@@ -46,6 +48,7 @@ impl FileStore {
         log_manager: Addr<RaftLogManager>,
         //data_store: Addr<RaftDataStore>,
         apply_manager: Addr<StateApplyManager>,
+        diff_tracker: Arc<SnapshotDiffTracker>,
     ) -> Self {
         Self {
             node_id,
@@ -54,6 +57,35 @@ impl FileStore {
             log_manager,
             //data_store,
             apply_manager,
+            diff_tracker,
+        }
+    }
+
+    ///
+    /// 基于最新镜像构建从since_index开始的差异镜像;无法构建或收益不明显时返回None
+    pub async fn build_diff_snapshot(
+        &self,
+        since_index: u64,
+        last_index: u64,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        if since_index == 0 || since_index >= last_index {
+            return Ok(None);
+        }
+        let changed = match self.diff_tracker.changed_since(since_index) {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        if let RaftSnapshotResponse::LastSnapshot(Some(path), Some(header)) = self
+            .snapshot_manager
+            .send(RaftSnapshotRequest::GetLastSnapshot)
+            .await??
+        {
+            if header.last_index != last_index {
+                return Ok(None);
+            }
+            SnapshotDiffUtils::build(&path, since_index, &changed).await
+        } else {
+            Ok(None)
         }
     }
 
@@ -68,6 +100,13 @@ impl FileStore {
         }
     }
 
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        self.log_manager
+            .send(RaftLogManagerAsyncRequest::Flush)
+            .await??;
+        Ok(())
+    }
+
     /*
     pub async fn get_state_value(&self, key: String) -> anyhow::Result<Option<Arc<String>>> {
         if let RaftDataStoreResponse::Value(v) = self
@@ -341,16 +380,32 @@ impl RaftStorage<ClientRequest, ClientResponse> for FileStore {
         id: String,
         snapshot: Box<Self::Snapshot>,
     ) -> anyhow::Result<()> {
-        let snapshot_id: u64 = id.parse()?;
-        self.snapshot_manager
-            .send(RaftSnapshotRequest::InstallSnapshot {
-                end_index: index,
-                snapshot_id,
-            })
-            .await??;
-        self.apply_manager
-            .send(StateApplyRequest::ApplySnapshot { snapshot })
-            .await??;
+        let reader = SnapshotReader::init_by_file(snapshot).await?;
+        let is_diff = SnapshotDiffUtils::is_diff(reader.get_header());
+        let snapshot = reader.into_file();
+        let id = if is_diff {
+            //差异镜像应用到状态机后,会重新打包成本节点的完整镜像
+            match self
+                .apply_manager
+                .send(StateApplyAsyncRequest::ApplyDiffSnapshot { snapshot })
+                .await??
+            {
+                StateApplyResponse::Snapshot(_, _, snapshot_id) => snapshot_id.to_string(),
+                _ => return Err(anyhow::anyhow!("StateApplyResponse result is error")),
+            }
+        } else {
+            let snapshot_id: u64 = id.parse()?;
+            self.snapshot_manager
+                .send(RaftSnapshotRequest::InstallSnapshot {
+                    end_index: index,
+                    snapshot_id,
+                })
+                .await??;
+            self.apply_manager
+                .send(StateApplyRequest::ApplySnapshot { snapshot })
+                .await??;
+            id
+        };
         //清除废弃日志
         let split_off_index = if let Some(v) = delete_through {
             v + 1
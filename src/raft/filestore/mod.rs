@@ -12,6 +12,7 @@ pub mod raftdata;
 pub mod raftindex;
 pub mod raftlog;
 pub mod raftsnapshot;
+pub mod snapshotdiff;
 
 pub struct StoreUtils;
 
@@ -4,6 +4,7 @@ use async_raft_ext::raft::InstallSnapshotRequest;
 use binrw::prelude::*;
 use prost::Message;
 
+use crate::common::byte_utils::{bin_to_id, id_to_bin};
 use crate::raft::store::ClientRequest;
 
 use super::log::{
@@ -111,6 +112,8 @@ pub struct SnapshotHeaderDto {
     pub member: Vec<u64>,
     pub member_after_consensus: Vec<u64>,
     pub node_addrs: HashMap<u64, Arc<String>>,
+    /// 差异镜像的基准日志位置,完整镜像为0
+    pub diff_base_index: u64,
 }
 
 impl<'a> From<SnapshotHeader<'a>> for SnapshotHeaderDto {
@@ -119,12 +122,18 @@ impl<'a> From<SnapshotHeader<'a>> for SnapshotHeaderDto {
         for item in value.node_addrs {
             node_addrs.insert(item.id, Arc::new(item.addr.to_string()));
         }
+        let diff_base_index = if value.extend.len() >= 8 {
+            bin_to_id(&value.extend)
+        } else {
+            0
+        };
         Self {
             last_index: value.last_index,
             last_term: value.last_term,
             member: value.member,
             member_after_consensus: value.member_after_consensus,
             node_addrs,
+            diff_base_index,
         }
     }
 }
@@ -138,13 +147,18 @@ impl SnapshotHeaderDto {
                 addr: Cow::Borrowed(item.1.as_str()),
             });
         }
+        let extend = if self.diff_base_index > 0 {
+            id_to_bin(self.diff_base_index)
+        } else {
+            Vec::new()
+        };
         SnapshotHeader {
             last_index: self.last_index,
             last_term: self.last_term,
             member: self.member.clone(),
             member_after_consensus: self.member_after_consensus.clone(),
             node_addrs,
-            extend: Cow::Owned(Vec::new()),
+            extend: Cow::Owned(extend),
         }
     }
 }
@@ -3,7 +3,9 @@ use std::sync::Arc;
 
 use crate::common::byte_utils::bin_to_id;
 use crate::common::constant::{
-    CACHE_TREE_NAME, CONFIG_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG, USER_TREE_NAME,
+    CACHE_TREE_NAME, CONFIG_TREE_NAME, DEAD_LETTER_TREE_NAME, NAMING_PERSISTENT_INSTANCE_TREE_NAME,
+    NAMING_SERVICE_TREE_NAME, SEQUENCE_TREE_NAME, SEQ_KEY_CONFIG, USER_PREFERENCE_TREE_NAME,
+    USER_TREE_NAME,
 };
 use crate::config::core::{ConfigCmd, ConfigKey};
 use crate::config::model::{ConfigRaftCmd, ConfigValueDO};
@@ -17,12 +19,13 @@ use bean_factory::{bean, Inject};
 
 use super::{
     log::SnapshotRange,
-    model::{ApplyRequestDto, LogRecordLoader, MemberShip, SnapshotHeaderDto},
-    raftindex::{RaftIndexManager, RaftIndexRequest, RaftIndexResponse},
+    model::{ApplyRequestDto, LogRecordLoader, MemberShip, SnapshotHeaderDto, SnapshotRecordDto},
+    raftindex::{IndexApplyBatch, RaftIndexManager, RaftIndexRequest, RaftIndexResponse},
     raftlog::{RaftLogManager, RaftLogManagerAsyncRequest, RaftLogManagerRequest},
     raftsnapshot::{
         RaftSnapshotManager, RaftSnapshotRequest, RaftSnapshotResponse, SnapshotReader,
     },
+    snapshotdiff::{SnapshotDiffTracker, SNAPSHOT_OP_REMOVE},
     StoreUtils,
 };
 
@@ -85,6 +88,11 @@ impl LogRecordLoader for LogRecordLoaderInstance {
                 ClientRequest::TableManagerReq(req) => {
                     self.data_wrap.table.do_send(req);
                 }
+                ClientRequest::ConfigBatch(items) => {
+                    self.data_wrap
+                        .config
+                        .do_send(ConfigRaftCmd::new_batch(items));
+                }
             },
             _ => {}
         }
@@ -98,6 +106,7 @@ pub struct StateApplyManager {
     snapshot_manager: Option<Addr<RaftSnapshotManager>>,
     log_manager: Option<Addr<RaftLogManager>>,
     data_wrap: Option<Arc<RaftDataWrap>>,
+    diff_tracker: Option<Arc<SnapshotDiffTracker>>,
     snapshot_next_index: u64,
     last_applied_log: u64,
 }
@@ -115,6 +124,7 @@ impl StateApplyManager {
             snapshot_manager: None,
             log_manager: None,
             data_wrap: None,
+            diff_tracker: None,
             snapshot_next_index: 1,
             last_applied_log: 0,
         }
@@ -218,49 +228,123 @@ impl StateApplyManager {
         mut reader: SnapshotReader,
     ) -> anyhow::Result<()> {
         while let Ok(Some(record)) = reader.read_record().await {
-            if record.tree.as_str() == CONFIG_TREE_NAME.as_str() {
-                let config_key = ConfigKey::from(&String::from_utf8(record.key)? as &str);
-                let value_do = ConfigValueDO::from_bytes(&record.value)?;
+            Self::load_record(&data_wrap, record, false).await?;
+        }
+        Ok(())
+    }
+
+    fn snapshot_table_name(tree: &str) -> Option<Arc<String>> {
+        for table_name in [
+            &*USER_TREE_NAME,
+            &*CACHE_TREE_NAME,
+            &*USER_PREFERENCE_TREE_NAME,
+            &*DEAD_LETTER_TREE_NAME,
+            &*NAMING_PERSISTENT_INSTANCE_TREE_NAME,
+            &*NAMING_SERVICE_TREE_NAME,
+        ]
+        .iter()
+        {
+            if table_name.as_str() == tree {
+                return Some(Arc::clone(table_name));
+            }
+        }
+        None
+    }
+
+    ///
+    /// 加载一条镜像记录;运行中应用差异镜像时需要通知配置变更
+    async fn load_record(
+        data_wrap: &RaftDataWrap,
+        record: SnapshotRecordDto,
+        notify: bool,
+    ) -> anyhow::Result<()> {
+        let is_remove = record.op_type == SNAPSHOT_OP_REMOVE;
+        if record.tree.as_str() == CONFIG_TREE_NAME.as_str() {
+            let key = String::from_utf8(record.key)?;
+            if is_remove {
                 data_wrap
                     .config
-                    .send(ConfigCmd::InnerSet(config_key, value_do.into()))
+                    .send(ConfigRaftCmd::ConfigRemove { key })
                     .await??;
-            } else if record.tree.as_str() == SEQUENCE_TREE_NAME.as_str() {
-                let key = String::from_utf8(record.key)?;
-                let last_id = bin_to_id(&record.value);
-                if &key as &str == SEQ_KEY_CONFIG {
-                    data_wrap
-                        .config
-                        .send(ConfigCmd::InnerSetLastId(last_id))
-                        .await??;
-                };
-            } else if record.tree.as_str() == USER_TREE_NAME.as_str() {
-                let key = record.key;
-                let value = record.value;
-                let req = TableManagerReq::Set {
-                    table_name: USER_TREE_NAME.clone(),
-                    key,
-                    value,
-                    last_seq_id: None,
-                };
-                data_wrap.table.send(req).await??;
-            } else if record.tree.as_str() == CACHE_TREE_NAME.as_str() {
-                let key = record.key;
-                let value = record.value;
-                let req = TableManagerReq::Set {
-                    table_name: CACHE_TREE_NAME.clone(),
-                    key,
-                    value,
-                    last_seq_id: None,
-                };
-                data_wrap.table.send(req).await??;
+                return Ok(());
             }
+            let config_key = ConfigKey::from(&key as &str);
+            let value_do = ConfigValueDO::from_bytes(&record.value)?;
+            let cmd = if notify {
+                ConfigCmd::InnerSetAndNotify(config_key, value_do.into())
+            } else {
+                ConfigCmd::InnerSet(config_key, value_do.into())
+            };
+            data_wrap.config.send(cmd).await??;
+        } else if record.tree.as_str() == SEQUENCE_TREE_NAME.as_str() {
+            let key = String::from_utf8(record.key)?;
+            let last_id = bin_to_id(&record.value);
+            if &key as &str == SEQ_KEY_CONFIG {
+                data_wrap
+                    .config
+                    .send(ConfigCmd::InnerSetLastId(last_id))
+                    .await??;
+            };
+        } else if let Some(table_name) = Self::snapshot_table_name(&record.tree) {
+            let req = if is_remove {
+                TableManagerReq::Remove {
+                    table_name,
+                    key: record.key,
+                }
+            } else {
+                TableManagerReq::Set {
+                    table_name,
+                    key: record.key,
+                    value: record.value,
+                    last_seq_id: None,
+                }
+            };
+            data_wrap.table.send(req).await??;
         }
         Ok(())
     }
 
+    ///
+    /// 应用leader发送的差异镜像,再基于当前状态重新打包成完整镜像
+    async fn do_apply_diff_snapshot(
+        log_manager: Addr<RaftLogManager>,
+        index_manager: Addr<RaftIndexManager>,
+        snapshot_manager: Addr<RaftSnapshotManager>,
+        data_wrap: Arc<RaftDataWrap>,
+        snapshot: Box<tokio::fs::File>,
+    ) -> anyhow::Result<(SnapshotHeaderDto, Arc<String>, u64)> {
+        let mut reader = SnapshotReader::init_by_file(snapshot).await?;
+        let header = reader.get_header().clone();
+        let member_after_consensus = if header.member_after_consensus.is_empty() {
+            None
+        } else {
+            Some(header.member_after_consensus.clone())
+        };
+        index_manager
+            .send(RaftIndexRequest::SaveMember {
+                member: header.member.clone(),
+                member_after_consensus,
+                node_addr: Some(header.node_addrs.clone()),
+            })
+            .await??;
+        while let Some(record) = reader.read_record().await? {
+            Self::load_record(&data_wrap, record, true).await?;
+        }
+        index_manager.do_send(RaftIndexRequest::SaveLastAppliedLog(header.last_index));
+        Self::do_build_snapshot(
+            log_manager,
+            index_manager,
+            snapshot_manager,
+            data_wrap,
+            header.last_index,
+            Some(header.last_term),
+        )
+        .await
+    }
+
     fn load_log(&mut self, ctx: &mut Context<Self>) {
         if self.last_applied_log == 0 || self.log_manager.is_none() || self.data_wrap.is_none() {
+            self.reset_diff_tracker();
             return;
         }
         let start_index = self.snapshot_next_index;
@@ -281,27 +365,34 @@ impl StateApplyManager {
             Ok(())
         }
         .into_actor(self)
-        .map(|_r: anyhow::Result<()>, _act, _ctx| {})
+        .map(|_r: anyhow::Result<()>, act, _ctx| {
+            act.reset_diff_tracker();
+        })
         .wait(ctx);
     }
 
-    fn apply_request_to_state_machine(&mut self, request: ApplyRequestDto) -> anyhow::Result<()> {
-        //self.last_applied_log = request.index;
-        //todo
+    fn reset_diff_tracker(&self) {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            diff_tracker.reset(self.last_applied_log);
+        }
+    }
+
+    ///
+    /// 索引文件的变更记录到index_batch中,整批应用完后合并写入
+    fn apply_request_to_state_machine(
+        &mut self,
+        request: ApplyRequestDto,
+        index_batch: &mut IndexApplyBatch,
+    ) -> anyhow::Result<()> {
+        if let Some(diff_tracker) = &self.diff_tracker {
+            diff_tracker.record(request.index, &request.request);
+        }
         match request.request {
             ClientRequest::NodeAddr { id, addr } => {
-                if let Some(index_manager) = &self.index_manager {
-                    index_manager.do_send(RaftIndexRequest::AddNodeAddr(id, addr));
-                }
+                index_batch.node_addrs.push((id, addr));
             }
             ClientRequest::Members(member) => {
-                if let Some(index_manager) = &self.index_manager {
-                    index_manager.do_send(RaftIndexRequest::SaveMember {
-                        member: member.clone(),
-                        member_after_consensus: None,
-                        node_addr: None,
-                    });
-                }
+                index_batch.member = Some(member);
             }
             ClientRequest::ConfigSet {
                 key,
@@ -338,6 +429,13 @@ impl StateApplyManager {
                     raft_data_wrap.table.do_send(req);
                 }
             }
+            ClientRequest::ConfigBatch(items) => {
+                if let Some(raft_data_wrap) = &self.data_wrap {
+                    raft_data_wrap
+                        .config
+                        .do_send(ConfigRaftCmd::new_batch(items));
+                }
+            }
         };
         Ok(())
     }
@@ -393,6 +491,13 @@ impl StateApplyManager {
                 raft_data_wrap.table.send(req).await??;
                 Ok(ClientResponse::Success)
             }
+            ClientRequest::ConfigBatch(items) => {
+                raft_data_wrap
+                    .config
+                    .send(ConfigRaftCmd::new_batch(items))
+                    .await??;
+                Ok(ClientResponse::Success)
+            }
         };
         index_manager.do_send(RaftIndexRequest::SaveLastAppliedLog(last_applied_log));
         r
@@ -404,19 +509,24 @@ impl StateApplyManager {
         snapshot_manager: Addr<RaftSnapshotManager>,
         data_wrap: Arc<RaftDataWrap>,
         last_index: u64,
+        last_term: Option<u64>,
     ) -> anyhow::Result<(SnapshotHeaderDto, Arc<String>, u64)> {
         //1. get last applied log
-        let last_log = match log_manager
-            .send(RaftLogManagerAsyncRequest::Query {
-                start: last_index,
-                end: last_index + 1,
-            })
-            .await??
-        {
-            super::raftlog::RaftLogResponse::QueryResult(mut list) => {
-                list.pop().unwrap_or_default()
+        let last_term = if let Some(v) = last_term {
+            v
+        } else {
+            match log_manager
+                .send(RaftLogManagerAsyncRequest::Query {
+                    start: last_index,
+                    end: last_index + 1,
+                })
+                .await??
+            {
+                super::raftlog::RaftLogResponse::QueryResult(mut list) => {
+                    list.pop().unwrap_or_default().term
+                }
+                _ => return Err(anyhow::anyhow!("RaftLogResponse is error")),
             }
-            _ => return Err(anyhow::anyhow!("RaftLogResponse is error")),
         };
         //2. get membership
         let member_ship = match index_manager
@@ -437,10 +547,11 @@ impl StateApplyManager {
         //3. build writer
         let header = SnapshotHeaderDto {
             last_index,
-            last_term: last_log.term,
+            last_term,
             member: member_ship.member,
             member_after_consensus: member_ship.member_after_consensus,
             node_addrs: member_ship.node_addrs,
+            diff_base_index: 0,
         };
         let (writer, snapshot_id, path) = match snapshot_manager
             .send(RaftSnapshotRequest::NewSnapshot(header.clone()))
@@ -497,6 +608,7 @@ impl Inject for StateApplyManager {
         self.snapshot_manager = factory_data.get_actor();
         self.log_manager = factory_data.get_actor();
         self.data_wrap = factory_data.get_bean();
+        self.diff_tracker = factory_data.get_bean();
 
         self.init(ctx);
     }
@@ -516,6 +628,10 @@ pub enum StateApplyRequest {
 pub enum StateApplyAsyncRequest {
     BuildSnapshot,
     ApplyRequest(ApplyRequestDto),
+    /// 应用差异镜像,返回重新打包的完整镜像
+    ApplyDiffSnapshot {
+        snapshot: Box<tokio::fs::File>,
+    },
 }
 
 pub enum StateApplyResponse {
@@ -540,13 +656,12 @@ impl Handler<StateApplyRequest> for StateApplyManager {
                 if let Some(req) = requests.last() {
                     self.last_applied_log = req.index;
                 }
+                let mut index_batch = IndexApplyBatch::new(self.last_applied_log);
                 for request in requests.into_iter() {
-                    self.apply_request_to_state_machine(request)?;
+                    self.apply_request_to_state_machine(request, &mut index_batch)?;
                 }
                 if let Some(index_manager) = &self.index_manager {
-                    index_manager.do_send(super::raftindex::RaftIndexRequest::SaveLastAppliedLog(
-                        self.last_applied_log,
-                    ));
+                    index_manager.do_send(RaftIndexRequest::SaveApplyBatch(index_batch));
                 }
                 Ok(StateApplyResponse::None)
             }
@@ -570,9 +685,13 @@ impl Handler<StateApplyAsyncRequest> for StateApplyManager {
         let snapshot_manager = self.snapshot_manager.clone().unwrap();
         let data_wrap = self.data_wrap.clone().unwrap();
         match &msg {
-            StateApplyAsyncRequest::BuildSnapshot => {}
+            StateApplyAsyncRequest::BuildSnapshot
+            | StateApplyAsyncRequest::ApplyDiffSnapshot { .. } => {}
             StateApplyAsyncRequest::ApplyRequest(req) => {
                 self.last_applied_log = req.index;
+                if let Some(diff_tracker) = &self.diff_tracker {
+                    diff_tracker.record(req.index, &req.request);
+                }
             }
         };
         let last_index = self.last_applied_log;
@@ -585,6 +704,18 @@ impl Handler<StateApplyAsyncRequest> for StateApplyManager {
                         snapshot_manager,
                         data_wrap,
                         last_index,
+                        None,
+                    )
+                    .await?;
+                    Ok(StateApplyResponse::Snapshot(header, path, snapshot_id))
+                }
+                StateApplyAsyncRequest::ApplyDiffSnapshot { snapshot } => {
+                    let (header, path, snapshot_id) = Self::do_apply_diff_snapshot(
+                        log_manager,
+                        index_manager,
+                        snapshot_manager,
+                        data_wrap,
+                        snapshot,
                     )
                     .await?;
                     Ok(StateApplyResponse::Snapshot(header, path, snapshot_id))
@@ -598,7 +729,113 @@ impl Handler<StateApplyAsyncRequest> for StateApplyManager {
             }
         }
         .into_actor(self)
-        .map(|r, _act, _ctx| r);
+        .map(|r, act, _ctx| {
+            if let Ok(StateApplyResponse::Snapshot(header, _, _)) = &r {
+                if header.last_index > act.last_applied_log {
+                    //差异镜像应用后,状态机直接推进到镜像位置
+                    act.last_applied_log = header.last_index;
+                    act.reset_diff_tracker();
+                }
+            }
+            r
+        });
         Box::pin(fut)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::core::ConfigActor;
+    use crate::raft::cache::CacheManager;
+    use crate::raft::db::table::{TableManager, TableManagerQueryReq, TableManagerResult};
+    use crate::raft::filestore::raftsnapshot::{SnapshotWriterActor, SnapshotWriterRequest};
+    use std::collections::HashMap;
+
+    fn snapshot_trees() -> Vec<Arc<String>> {
+        vec![
+            USER_TREE_NAME.clone(),
+            CACHE_TREE_NAME.clone(),
+            USER_PREFERENCE_TREE_NAME.clone(),
+            DEAD_LETTER_TREE_NAME.clone(),
+        ]
+    }
+
+    ///
+    /// 将表数据打包成镜像后加载到新的TableManager,模拟日志压缩后重启或安装镜像
+    pub(crate) async fn install_table_snapshot(source: &Addr<TableManager>) -> Addr<TableManager> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Arc::new(dir.path().join("snapshot").to_str().unwrap().to_owned());
+        let header = SnapshotHeaderDto {
+            last_index: 1,
+            last_term: 1,
+            member: vec![1],
+            member_after_consensus: vec![],
+            node_addrs: HashMap::new(),
+            diff_base_index: 0,
+        };
+        let writer = SnapshotWriterActor::new(path.clone(), header).start();
+        source
+            .send(TableManagerInnerReq::BuildSnapshot(writer.clone()))
+            .await
+            .unwrap()
+            .unwrap();
+        writer
+            .send(SnapshotWriterRequest::Flush)
+            .await
+            .unwrap()
+            .unwrap();
+        //等待前一次flush完成
+        writer
+            .send(SnapshotWriterRequest::Flush)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let target = TableManager::new().start();
+        let data_wrap = Arc::new(RaftDataWrap::new(
+            ConfigActor::new().start(),
+            target.clone(),
+            CacheManager::new().start(),
+        ));
+        let reader = SnapshotReader::init(&path).await.unwrap();
+        StateApplyManager::do_load_snapshot(data_wrap, reader)
+            .await
+            .unwrap();
+        target
+    }
+
+    #[actix_rt::test]
+    async fn test_table_snapshot_round_trip() {
+        let source = TableManager::new().start();
+        for tree in snapshot_trees() {
+            source
+                .send(TableManagerReq::Set {
+                    table_name: tree.clone(),
+                    key: b"k1".to_vec(),
+                    value: tree.as_bytes().to_vec(),
+                    last_seq_id: None,
+                })
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        let target = install_table_snapshot(&source).await;
+
+        for tree in snapshot_trees() {
+            let v = target
+                .send(TableManagerQueryReq::Get {
+                    table_name: tree.clone(),
+                    key: "k1".to_owned(),
+                })
+                .await
+                .unwrap()
+                .unwrap();
+            match v {
+                TableManagerResult::Value(v) => assert_eq!(v, tree.as_bytes()),
+                _ => panic!("table {} is not loaded from snapshot", &tree),
+            }
+        }
+    }
+}
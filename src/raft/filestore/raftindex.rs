@@ -20,6 +20,37 @@ use super::{
     model::RaftIndexDto,
 };
 
+///
+/// 一次批量应用中对索引文件的变更,合并为一次写入
+#[derive(Debug, Default, Clone)]
+pub struct IndexApplyBatch {
+    pub last_applied_log: u64,
+    pub member: Option<Vec<u64>>,
+    pub node_addrs: Vec<(u64, Arc<String>)>,
+}
+
+impl IndexApplyBatch {
+    pub fn new(last_applied_log: u64) -> Self {
+        Self {
+            last_applied_log,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_index_changed(&self) -> bool {
+        self.member.is_some() || !self.node_addrs.is_empty()
+    }
+
+    fn apply_to(self, index: &mut RaftIndexDto) {
+        if let Some(member) = self.member {
+            index.member = member;
+        }
+        for (id, addr) in self.node_addrs {
+            index.node_addrs.insert(id, addr);
+        }
+    }
+}
+
 pub struct RaftIndexInnerManager {
     file: tokio::fs::File,
     pub(crate) raft_index: RaftIndexDto,
@@ -100,6 +131,22 @@ impl RaftIndexInnerManager {
         self.applied_flush = true;
         Ok(())
     }
+    ///
+    /// 先写索引再写last_applied_log,最后只flush一次;
+    /// 中途崩溃时last_applied_log停留在上一批,重启后重放的日志是幂等的
+    pub async fn write_apply_batch(&mut self, batch: IndexApplyBatch) -> anyhow::Result<()> {
+        let last_applied_log = batch.last_applied_log;
+        if batch.is_index_changed() {
+            let mut index = self.raft_index.clone();
+            batch.apply_to(&mut index);
+            self.write_index(index).await?;
+        }
+        self.write_last_applied_log(last_applied_log).await?;
+        self.file.flush().await?;
+        self.applied_flush = true;
+        Ok(())
+    }
+
     pub async fn flush(&mut self) -> anyhow::Result<()> {
         if !self.applied_flush {
             self.file.flush().await?;
@@ -248,6 +295,35 @@ impl RaftIndexManager {
         Ok(RaftIndexResponse::None)
     }
 
+    pub fn write_apply_batch(
+        &mut self,
+        ctx: &mut Context<Self>,
+        batch: IndexApplyBatch,
+    ) -> anyhow::Result<RaftIndexResponse> {
+        if self.inner.is_none() {
+            return Err(Self::inner_is_empty_error());
+        }
+        let change_member = batch.is_index_changed();
+        let mut inner = self.inner.take();
+        async move {
+            if let Some(v) = &mut inner {
+                if let Err(err) = v.write_apply_batch(batch).await {
+                    log::error!("write_apply_batch error,{}", err)
+                }
+            }
+            (inner, change_member)
+        }
+        .into_actor(self)
+        .map(|(v, change_member), act, _ctx| {
+            act.inner = v;
+            if change_member {
+                act.do_notify_membership(true);
+            }
+        })
+        .wait(ctx);
+        Ok(RaftIndexResponse::None)
+    }
+
     pub fn write_index(
         &mut self,
         ctx: &mut Context<Self>,
@@ -409,6 +485,8 @@ pub enum RaftIndexRequest {
     SaveLogs(Vec<LogRange>),
     SaveSnapshots(Vec<SnapshotRange>),
     SaveLastAppliedLog(u64),
+    /// 批量应用后合并写入成员、节点地址与last_applied_log
+    SaveApplyBatch(IndexApplyBatch),
     SaveMember {
         member: Vec<u64>,
         member_after_consensus: Option<Vec<u64>>,
@@ -451,6 +529,7 @@ impl Handler<RaftIndexRequest> for RaftIndexManager {
             RaftIndexRequest::SaveLastAppliedLog(last_applied_log) => {
                 self.write_last_applied_log(ctx, last_applied_log)
             }
+            RaftIndexRequest::SaveApplyBatch(batch) => self.write_apply_batch(ctx, batch),
             RaftIndexRequest::SaveLogs(logs) => self.write_logs(ctx, logs),
             RaftIndexRequest::SaveMember {
                 member,
@@ -498,3 +577,37 @@ impl Handler<RaftIndexRequest> for RaftIndexManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_apply_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index");
+        let path = path.to_str().unwrap();
+        let mut manager = RaftIndexInnerManager::init(path).await.unwrap();
+        let mut batch = IndexApplyBatch::new(5);
+        batch.member = Some(vec![1, 2]);
+        batch
+            .node_addrs
+            .push((2, Arc::new("127.0.0.1:9849".to_owned())));
+        manager.write_apply_batch(batch).await.unwrap();
+        drop(manager);
+
+        let mut manager = RaftIndexInnerManager::init(path).await.unwrap();
+        assert_eq!(manager.last_applied_log, 5);
+        assert_eq!(manager.raft_index.member, vec![1, 2]);
+        assert_eq!(manager.raft_index.node_addrs.len(), 1);
+
+        //模拟写入索引后、写入last_applied_log前崩溃
+        let mut index = manager.raft_index.clone();
+        index.member = vec![1, 2, 3];
+        manager.write_index(index).await.unwrap();
+        drop(manager);
+        let manager = RaftIndexInnerManager::init(path).await.unwrap();
+        assert_eq!(manager.last_applied_log, 5);
+        assert_eq!(manager.raft_index.member, vec![1, 2, 3]);
+    }
+}
@@ -1304,8 +1304,13 @@ pub enum RaftLogManagerRequest {
 #[derive(Message)]
 #[rtype(result = "anyhow::Result<RaftLogResponse>")]
 pub enum RaftLogManagerAsyncRequest {
-    Query { start: u64, end: u64 },
+    Query {
+        start: u64,
+        end: u64,
+    },
     GetLastLogIndex,
+    /// 把当前日志文件写入磁盘,关闭前调用
+    Flush,
 }
 
 pub enum RaftLogManagerInnerCtx {
@@ -1315,6 +1320,7 @@ pub enum RaftLogManagerInnerCtx {
         log_actors: Vec<Addr<RaftLogActor>>,
     },
     GetLastLogIndex(Option<Addr<RaftLogActor>>),
+    Flush(Option<Addr<RaftLogActor>>),
 }
 
 impl Inject for RaftLogManager {
@@ -1386,6 +1392,9 @@ impl Handler<RaftLogManagerAsyncRequest> for RaftLogManager {
             RaftLogManagerAsyncRequest::GetLastLogIndex => {
                 RaftLogManagerInnerCtx::GetLastLogIndex(self.current_log_actor.clone())
             }
+            RaftLogManagerAsyncRequest::Flush => {
+                RaftLogManagerInnerCtx::Flush(self.current_log_actor.clone())
+            }
         };
 
         let fut = async move {
@@ -1402,6 +1411,12 @@ impl Handler<RaftLogManagerAsyncRequest> for RaftLogManager {
                     let index = Self::get_last_index(log_actor).await?;
                     Ok(RaftLogResponse::LastLogIndex(index))
                 }
+                RaftLogManagerInnerCtx::Flush(log_actor) => {
+                    if let Some(log_actor) = log_actor {
+                        log_actor.send(RaftLogRequest::Flush).await??;
+                    }
+                    Ok(RaftLogResponse::None)
+                }
             }
         }
         .into_actor(self)
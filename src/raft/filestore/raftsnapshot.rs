@@ -30,6 +30,7 @@ impl SnapshotWriter {
             //.append(true)
             //.create_new(true)
             .create(true)
+            .truncate(true)
             .open(path)
             .await?;
         let mut buf = Vec::new();
@@ -218,6 +219,10 @@ impl SnapshotReader {
         &self.header
     }
 
+    pub fn into_file(self) -> Box<tokio::fs::File> {
+        self.file
+    }
+
     pub async fn read_record(&mut self) -> anyhow::Result<Option<SnapshotRecordDto>> {
         if self.is_end {
             return Ok(None);
@@ -446,13 +451,19 @@ pub enum RaftSnapshotRequest {
     NewSnapshot(SnapshotHeaderDto),
     NewSnapshotForLoad,
     CompleteSnapshot(SnapshotRange),
-    InstallSnapshot { end_index: u64, snapshot_id: u64 },
+    InstallSnapshot {
+        end_index: u64,
+        snapshot_id: u64,
+    },
+    /// 是否正在构建镜像
+    QueryBuilding,
 }
 
 pub enum RaftSnapshotResponse {
     LastSnapshot(Option<String>, Option<SnapshotHeaderDto>),
     NewSnapshot(Addr<SnapshotWriterActor>, u64, Arc<String>),
     NewSnapshotForLoad(String, u64),
+    Building(bool),
     None,
 }
 
@@ -493,6 +504,9 @@ impl Handler<RaftSnapshotRequest> for RaftSnapshotManager {
                 self.install_snapshot(ctx, snapshot_id, end_index).ok();
                 Ok(RaftSnapshotResponse::None)
             }
+            RaftSnapshotRequest::QueryBuilding => {
+                Ok(RaftSnapshotResponse::Building(self.building.is_some()))
+            }
         }
     }
 }
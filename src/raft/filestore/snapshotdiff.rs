@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use quick_protobuf::Writer;
+
+use crate::common::constant::{CONFIG_TREE_NAME, SEQUENCE_TREE_NAME};
+use crate::raft::db::table::TableManagerReq;
+use crate::raft::store::ClientRequest;
+
+use super::model::{SnapshotHeaderDto, SnapshotRecordDto};
+use super::raftsnapshot::SnapshotReader;
+
+/// 镜像记录的操作类型,差异镜像中表示key已被删除
+pub const SNAPSHOT_OP_REMOVE: u32 = 1;
+/// 最多跟踪的变更key数量,超过后从当前日志位置重新跟踪
+const MAX_TRACK_KEY_SIZE: usize = 100_000;
+
+type TreeKey = (Arc<String>, Vec<u8>);
+
+struct TrackData {
+    /// 开始跟踪时已应用的日志位置,只有应用位置不早于它的节点能使用差异镜像
+    start_index: u64,
+    changed: HashMap<TreeKey, u64>,
+}
+
+///
+/// 记录状态机中每个key最近一次变更的日志位置,用于给稍微落后的节点构建差异镜像
+pub struct SnapshotDiffTracker {
+    data: Mutex<TrackData>,
+}
+
+impl Default for SnapshotDiffTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnapshotDiffTracker {
+    pub fn new() -> Self {
+        Self {
+            data: Mutex::new(TrackData {
+                start_index: u64::MAX,
+                changed: HashMap::new(),
+            }),
+        }
+    }
+
+    ///
+    /// 状态机不是通过日志变更时(启动加载、安装镜像),从当前位置重新跟踪
+    pub fn reset(&self, index: u64) {
+        if let Ok(mut data) = self.data.lock() {
+            data.start_index = index;
+            data.changed.clear();
+        }
+    }
+
+    pub fn record(&self, index: u64, request: &ClientRequest) {
+        let key = match request {
+            ClientRequest::ConfigSet { key, .. } | ClientRequest::ConfigRemove { key } => {
+                (CONFIG_TREE_NAME.clone(), key.as_bytes().to_vec())
+            }
+            ClientRequest::TableManagerReq(TableManagerReq::Set {
+                table_name, key, ..
+            })
+            | ClientRequest::TableManagerReq(TableManagerReq::CompareAndSet {
+                table_name,
+                key,
+                ..
+            })
+            | ClientRequest::TableManagerReq(TableManagerReq::CompareAndRemove {
+                table_name,
+                key,
+                ..
+            })
+            | ClientRequest::TableManagerReq(TableManagerReq::Remove { table_name, key }) => {
+                (table_name.clone(), key.to_owned())
+            }
+            //自增id与整表变更无法按key跟踪
+            ClientRequest::TableManagerReq(_) => {
+                self.reset(index);
+                return;
+            }
+            ClientRequest::ConfigBatch(items) => {
+                for item in items {
+                    self.record(index, item);
+                }
+                return;
+            }
+            ClientRequest::NodeAddr { .. } | ClientRequest::Members(_) => return,
+        };
+        if let Ok(mut data) = self.data.lock() {
+            if data.start_index == u64::MAX {
+                return;
+            }
+            if data.changed.len() >= MAX_TRACK_KEY_SIZE && !data.changed.contains_key(&key) {
+                data.start_index = index;
+                data.changed.clear();
+                return;
+            }
+            data.changed.insert(key, index);
+        }
+    }
+
+    ///
+    /// 应用位置在since_index之后变更过的key;跟踪范围不包含since_index时返回None
+    pub fn changed_since(&self, since_index: u64) -> Option<HashSet<TreeKey>> {
+        let data = self.data.lock().ok()?;
+        if since_index < data.start_index {
+            return None;
+        }
+        Some(
+            data.changed
+                .iter()
+                .filter(|(_, index)| **index > since_index)
+                .map(|(key, _)| key.clone())
+                .collect(),
+        )
+    }
+}
+
+pub struct SnapshotDiffUtils;
+
+impl SnapshotDiffUtils {
+    fn write_message<M: quick_protobuf::MessageWrite>(
+        buf: &mut Vec<u8>,
+        message: &M,
+    ) -> anyhow::Result<()> {
+        let mut writer = Writer::new(buf);
+        writer.write_message(message)?;
+        Ok(())
+    }
+
+    ///
+    /// 从完整镜像中筛选出变更的key构建差异镜像;差异镜像不小于完整镜像一半时返回None
+    pub async fn build(
+        path: &str,
+        since_index: u64,
+        changed: &HashSet<TreeKey>,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let full_len = tokio::fs::metadata(path).await?.len() as usize;
+        let mut reader = SnapshotReader::init(path).await?;
+        let mut header = reader.get_header().clone();
+        header.diff_base_index = since_index;
+        let mut buf = Vec::new();
+        Self::write_message(&mut buf, &header.to_record_do())?;
+        let mut found = HashSet::new();
+        while let Some(record) = reader.read_record().await? {
+            let key = (record.tree.clone(), record.key.clone());
+            let is_changed = changed.contains(&key);
+            if record.tree.as_str() == SEQUENCE_TREE_NAME.as_str() || is_changed {
+                Self::write_message(&mut buf, &record.to_record_do())?;
+                if is_changed {
+                    found.insert(key);
+                }
+                if buf.len() * 2 >= full_len {
+                    return Ok(None);
+                }
+            }
+        }
+        for (tree, key) in changed.difference(&found) {
+            let record = SnapshotRecordDto {
+                tree: tree.clone(),
+                key: key.to_owned(),
+                value: vec![],
+                op_type: SNAPSHOT_OP_REMOVE,
+            };
+            Self::write_message(&mut buf, &record.to_record_do())?;
+        }
+        if buf.len() * 2 >= full_len {
+            return Ok(None);
+        }
+        Ok(Some(buf))
+    }
+
+    pub fn is_diff(header: &SnapshotHeaderDto) -> bool {
+        header.diff_base_index > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_tracker() {
+        let tracker = SnapshotDiffTracker::new();
+        let req = ClientRequest::ConfigRemove {
+            key: "a".to_owned(),
+        };
+        tracker.record(5, &req);
+        assert!(tracker.changed_since(5).is_none());
+        tracker.reset(10);
+        tracker.record(11, &req);
+        tracker.record(
+            12,
+            &ClientRequest::TableManagerReq(TableManagerReq::Remove {
+                table_name: Arc::new("T_USER".to_owned()),
+                key: b"b".to_vec(),
+            }),
+        );
+        assert!(tracker.changed_since(9).is_none());
+        assert_eq!(tracker.changed_since(10).unwrap().len(), 2);
+        assert_eq!(tracker.changed_since(11).unwrap().len(), 1);
+        tracker.record(
+            13,
+            &ClientRequest::TableManagerReq(TableManagerReq::ReloadTable),
+        );
+        assert!(tracker.changed_since(12).is_none());
+        assert_eq!(tracker.changed_since(13).unwrap().len(), 0);
+        //过期缓存的条件删除按key跟踪,不重置
+        tracker.record(
+            14,
+            &ClientRequest::TableManagerReq(TableManagerReq::CompareAndRemove {
+                table_name: Arc::new("T_CACHE".to_owned()),
+                key: b"c".to_vec(),
+                expect: b"v".to_vec(),
+            }),
+        );
+        assert_eq!(tracker.changed_since(13).unwrap().len(), 1);
+    }
+}
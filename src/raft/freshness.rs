@@ -0,0 +1,67 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use actix_web::HttpRequest;
+
+use crate::common::appdata::AppShareData;
+use crate::now_millis;
+
+/// 响应中返回本节点数据同步时间的header
+pub const DATA_TIME_HEADER: &str = "x-rnacos-data-time";
+/// 客户端可通过该header指定可接受的最大数据延迟(毫秒)
+pub const MAX_STALENESS_HEADER: &str = "x-rnacos-max-staleness";
+
+///
+/// 记录本节点最近一次与raft leader同步成功的时间,用于判断从节点读请求的数据新鲜度
+#[derive(Debug, Default)]
+pub struct DataFreshness {
+    last_sync_time: AtomicU64,
+}
+
+impl DataFreshness {
+    pub fn new() -> Self {
+        Self {
+            last_sync_time: AtomicU64::new(now_millis()),
+        }
+    }
+
+    pub fn update_sync_time(&self, time: u64) {
+        self.last_sync_time.store(time, Ordering::Relaxed);
+    }
+
+    pub fn get_sync_time(&self) -> u64 {
+        self.last_sync_time.load(Ordering::Relaxed)
+    }
+}
+
+pub struct StaleReadGuard;
+
+impl StaleReadGuard {
+    ///
+    /// 本节点数据时间;leader节点数据总是最新的
+    pub fn get_data_time(app: &AppShareData) -> u64 {
+        let leader = app.raft.metrics().borrow().current_leader;
+        if leader == Some(app.sys_config.raft_node_id) {
+            now_millis()
+        } else {
+            app.data_freshness.get_sync_time()
+        }
+    }
+
+    ///
+    /// 检查读请求的数据延迟,超过请求指定或系统配置的上限时返回Err(延迟毫秒数)
+    pub fn check(app: &AppShareData, req: &HttpRequest) -> Result<u64, u64> {
+        let data_time = Self::get_data_time(app);
+        let max_staleness = req
+            .headers()
+            .get(MAX_STALENESS_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_else(|| app.cluster_settings.stale_read_max_millis());
+        let staleness = now_millis().saturating_sub(data_time);
+        if max_staleness > 0 && staleness > max_staleness {
+            Err(staleness)
+        } else {
+            Ok(data_time)
+        }
+    }
+}
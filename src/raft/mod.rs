@@ -9,6 +9,7 @@ pub mod cache;
 pub mod cluster;
 pub mod db;
 pub mod filestore;
+pub mod freshness;
 pub mod network;
 pub mod store;
 
@@ -1,29 +1,90 @@
-use crate::grpc::handler::{RAFT_APPEND_REQUEST, RAFT_SNAPSHOT_REQUEST, RAFT_VOTE_REQUEST};
+use crate::grpc::handler::{
+    RAFT_APPEND_REQUEST, RAFT_ROUTE_REQUEST, RAFT_SNAPSHOT_REQUEST, RAFT_VOTE_REQUEST,
+};
 use async_raft_ext::raft::{
     AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, InstallSnapshotResponse,
     VoteRequest, VoteResponse,
 };
 use async_raft_ext::{NodeId, RaftNetwork};
 use async_trait::async_trait;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use crate::grpc::nacos_proto::Payload;
 use crate::grpc::PayloadUtils;
+use crate::raft::cluster::model::{RouterRequest, RouterResponse};
 use crate::raft::filestore::core::FileStore;
 use crate::raft::store::ClientRequest;
+use crate::utils::{gz_decode, gz_encode};
 
 use super::factory::RaftClusterRequestSender;
 
+/// 请求体编码方式的header
+pub const RAFT_ENCODING_HEADER: &str = "raft-encoding";
+const RAFT_ENCODING_GZIP: &str = "gzip";
+/// 压缩请求体解压后的最大字节数
+const RAFT_PAYLOAD_MAX_DECODE_SIZE: usize = 64 * 1024 * 1024;
+
+pub struct RaftPayloadUtils;
+
+impl RaftPayloadUtils {
+    ///
+    /// 请求体超过阈值时压缩,阈值为0时不压缩
+    pub fn build_payload(url: &str, request: String, compress_threshold: usize) -> Payload {
+        if compress_threshold == 0 || request.len() <= compress_threshold {
+            return PayloadUtils::build_payload(url, request);
+        }
+        let mut payload = PayloadUtils::build_payload(url, String::new());
+        if let Some(body) = payload.body.as_mut() {
+            body.value = gz_encode(request.as_bytes(), 0);
+        }
+        if let Some(meta) = payload.metadata.as_mut() {
+            meta.headers.insert(
+                RAFT_ENCODING_HEADER.to_owned(),
+                RAFT_ENCODING_GZIP.to_owned(),
+            );
+        }
+        payload
+    }
+
+    pub fn get_body(payload: Payload) -> anyhow::Result<Vec<u8>> {
+        let is_gzip = payload
+            .metadata
+            .as_ref()
+            .and_then(|e| e.headers.get(RAFT_ENCODING_HEADER))
+            .map(|e| e == RAFT_ENCODING_GZIP)
+            .unwrap_or(false);
+        let body_vec = payload.body.unwrap_or_default().value;
+        if is_gzip {
+            gz_decode(&body_vec, RAFT_PAYLOAD_MAX_DECODE_SIZE)
+        } else {
+            Ok(body_vec)
+        }
+    }
+}
+
 pub struct RaftRouter {
     store: Arc<FileStore>, //get target addr
     cluster_sender: Arc<RaftClusterRequestSender>,
+    compress_threshold: usize,
+    diff_snapshot_enable: bool,
+    /// 已用差异镜像代替的传输,后续分片直接确认: target -> (last_included_index, term)
+    diff_transfers: Mutex<HashMap<NodeId, (u64, u64)>>,
 }
 
 impl RaftRouter {
-    pub fn new(store: Arc<FileStore>, cluster_sender: Arc<RaftClusterRequestSender>) -> Self {
+    pub fn new(
+        store: Arc<FileStore>,
+        cluster_sender: Arc<RaftClusterRequestSender>,
+        compress_threshold: usize,
+        diff_snapshot_enable: bool,
+    ) -> Self {
         Self {
             store,
             cluster_sender,
+            compress_threshold,
+            diff_snapshot_enable,
+            diff_transfers: Mutex::new(HashMap::new()),
         }
     }
 
@@ -31,6 +92,68 @@ impl RaftRouter {
         let addr = self.store.get_target_addr(target).await?;
         self.cluster_sender.send_request(addr, payload).await
     }
+
+    async fn query_applied_index(&self, target: NodeId) -> anyhow::Result<u64> {
+        let request = serde_json::to_string(&RouterRequest::QueryAppliedIndex)?;
+        let payload = PayloadUtils::build_payload(RAFT_ROUTE_REQUEST, request);
+        let resp_payload = self.send_request(target, payload).await?;
+        let body_vec = resp_payload.body.unwrap_or_default().value;
+        match serde_json::from_slice(&body_vec)? {
+            RouterResponse::AppliedIndex { index } => Ok(index),
+            _ => Err(anyhow::anyhow!("QueryAppliedIndex response type is error!")),
+        }
+    }
+
+    ///
+    /// 目标节点落后不多时,用差异镜像代替完整镜像的第一个分片
+    async fn try_build_diff_request(
+        &self,
+        target: NodeId,
+        req: &InstallSnapshotRequest,
+    ) -> anyhow::Result<Option<InstallSnapshotRequest>> {
+        let applied_index = self.query_applied_index(target).await?;
+        let data = match self
+            .store
+            .build_diff_snapshot(applied_index, req.last_included_index)
+            .await?
+        {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        log::info!(
+            "send diff snapshot to node {}, applied index: {}, snapshot index: {}, size: {}",
+            target,
+            applied_index,
+            req.last_included_index,
+            data.len()
+        );
+        Ok(Some(InstallSnapshotRequest {
+            term: req.term,
+            leader_id: req.leader_id,
+            last_included_index: req.last_included_index,
+            last_included_term: req.last_included_term,
+            offset: 0,
+            data,
+            done: true,
+        }))
+    }
+
+    async fn do_install_snapshot(
+        &self,
+        target: NodeId,
+        req: &InstallSnapshotRequest,
+    ) -> anyhow::Result<InstallSnapshotResponse> {
+        let request = serde_json::to_string(req).unwrap_or_default();
+        let payload = RaftPayloadUtils::build_payload(
+            RAFT_SNAPSHOT_REQUEST,
+            request,
+            self.compress_threshold,
+        );
+        let resp_payload = self.send_request(target, payload).await?;
+        let body_vec = resp_payload.body.unwrap_or_default().value;
+        let res: InstallSnapshotResponse = serde_json::from_slice(&body_vec)?;
+        Ok(res)
+    }
 }
 
 #[async_trait]
@@ -41,7 +164,8 @@ impl RaftNetwork<ClientRequest> for RaftRouter {
         req: AppendEntriesRequest<ClientRequest>,
     ) -> anyhow::Result<AppendEntriesResponse> {
         let request = serde_json::to_string(&req).unwrap_or_default();
-        let payload = PayloadUtils::build_payload(RAFT_APPEND_REQUEST, request);
+        let payload =
+            RaftPayloadUtils::build_payload(RAFT_APPEND_REQUEST, request, self.compress_threshold);
         let resp_payload = self.send_request(target, payload).await?;
         let body_vec = resp_payload.body.unwrap_or_default().value;
         let res: AppendEntriesResponse = serde_json::from_slice(&body_vec)?;
@@ -53,12 +177,37 @@ impl RaftNetwork<ClientRequest> for RaftRouter {
         target: NodeId,
         req: InstallSnapshotRequest,
     ) -> anyhow::Result<InstallSnapshotResponse> {
-        let request = serde_json::to_string(&req).unwrap_or_default();
-        let payload = PayloadUtils::build_payload(RAFT_SNAPSHOT_REQUEST, request);
-        let resp_payload = self.send_request(target, payload).await?;
-        let body_vec = resp_payload.body.unwrap_or_default().value;
-        let res: InstallSnapshotResponse = serde_json::from_slice(&body_vec)?;
-        Ok(res)
+        if !self.diff_snapshot_enable {
+            return self.do_install_snapshot(target, &req).await;
+        }
+        if req.offset > 0 {
+            let mut diff_transfers = self.diff_transfers.lock().unwrap();
+            if diff_transfers.get(&target) == Some(&(req.last_included_index, req.term)) {
+                if req.done {
+                    diff_transfers.remove(&target);
+                }
+                return Ok(InstallSnapshotResponse { term: req.term });
+            }
+        } else {
+            self.diff_transfers.lock().unwrap().remove(&target);
+            match self.try_build_diff_request(target, &req).await {
+                Ok(Some(diff_req)) => {
+                    let res = self.do_install_snapshot(target, &diff_req).await?;
+                    if !req.done {
+                        self.diff_transfers
+                            .lock()
+                            .unwrap()
+                            .insert(target, (req.last_included_index, req.term));
+                    }
+                    return Ok(res);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    log::warn!("build diff snapshot error, use full snapshot,{}", err);
+                }
+            }
+        }
+        self.do_install_snapshot(target, &req).await
     }
 
     async fn vote(&self, target: NodeId, req: VoteRequest) -> anyhow::Result<VoteResponse> {
@@ -130,3 +279,41 @@ impl RaftNetwork<ClientRequest> for HttpRaftRouter {
     }
 }
  */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_gzip(payload: &Payload) -> bool {
+        payload
+            .metadata
+            .as_ref()
+            .and_then(|e| e.headers.get(RAFT_ENCODING_HEADER))
+            .is_some()
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let request = "a".repeat(1024);
+        //阈值为0或请求体未超过阈值时不压缩
+        for threshold in [0, 2048] {
+            let payload =
+                RaftPayloadUtils::build_payload(RAFT_APPEND_REQUEST, request.clone(), threshold);
+            assert!(!is_gzip(&payload));
+            let body = RaftPayloadUtils::get_body(payload).unwrap();
+            assert_eq!(body, request.as_bytes());
+        }
+        let payload = RaftPayloadUtils::build_payload(RAFT_APPEND_REQUEST, request.clone(), 100);
+        assert!(is_gzip(&payload));
+        assert!(payload.body.as_ref().unwrap().value.len() < request.len());
+        let body = RaftPayloadUtils::get_body(payload).unwrap();
+        assert_eq!(body, request.as_bytes());
+    }
+
+    #[test]
+    fn test_gz_decode_limit() {
+        let data = gz_encode(&[0u8; 4096], 0);
+        assert_eq!(gz_decode(&data, 4096).unwrap().len(), 4096);
+        assert!(gz_decode(&data, 4095).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::common::trace_utils::{TraceUtils, REQUEST_ID_HEADER};
 use crate::common::AppSysConfig;
 use crate::grpc::handler::CLUSTER_TOKEN;
 use actix::prelude::*;
@@ -40,6 +41,12 @@ impl RaftClusterRequestSender {
     ) -> anyhow::Result<Payload> {
         let channel = self.get_node_channel(addr.clone()).await?;
         let mut request_client = RequestClient::new(channel.as_ref().clone());
+        if let Some(request_id) = TraceUtils::current_request_id() {
+            if let Some(meta) = payload.metadata.as_mut() {
+                meta.headers
+                    .insert(REQUEST_ID_HEADER.to_owned(), request_id.as_ref().to_owned());
+            }
+        }
         if !self.sys_config.cluster_token.is_empty() {
             if let Some(meta) = payload.metadata.as_mut() {
                 meta.headers.insert(
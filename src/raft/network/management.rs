@@ -3,10 +3,12 @@ use std::sync::Arc;
 
 use actix_web::web::Data;
 use actix_web::web::Json;
-use actix_web::Responder;
+use actix_web::{web, HttpResponse, Responder};
 use async_raft_ext::raft::ClientWriteRequest;
+use serde::{Deserialize, Serialize};
 
 use crate::common::appdata::AppShareData;
+use crate::raft::cluster::restart::RestartCheckUtils;
 use crate::raft::join_node;
 use crate::raft::store::ClientRequest;
 use crate::raft::store::NodeId;
@@ -89,3 +91,37 @@ pub async fn metrics(app: Data<Arc<AppShareData>>) -> actix_web::Result<impl Res
     let metrics = app.raft.metrics().borrow().clone();
     Ok(Json(metrics))
 }
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartCheckParam {
+    /// 为true时等待直到可以安全重启或超时
+    pub wait: Option<bool>,
+    pub timeout_second: Option<u64>,
+}
+
+/// 等待安全重启的最长时间
+const RESTART_CHECK_MAX_TIMEOUT_SECOND: u64 = 600;
+
+/// 当前节点是否可以安全重启;可用于k8s滚动升级的preStop,不安全时返回503
+//#[get("/restart-check")]
+pub async fn restart_check(
+    app: Data<Arc<AppShareData>>,
+    web::Query(param): web::Query<RestartCheckParam>,
+) -> actix_web::Result<impl Responder> {
+    let report = if param.wait.unwrap_or(false) {
+        let timeout_second = param
+            .timeout_second
+            .unwrap_or(60)
+            .min(RESTART_CHECK_MAX_TIMEOUT_SECOND);
+        let timeout_millis = timeout_second.saturating_mul(1000);
+        RestartCheckUtils::wait_safe(&app, timeout_millis).await
+    } else {
+        RestartCheckUtils::check(&app).await
+    };
+    if report.safe {
+        Ok(HttpResponse::Ok().json(report))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(report))
+    }
+}
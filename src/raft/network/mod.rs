@@ -21,7 +21,10 @@ pub fn raft_config(config: &mut web::ServiceConfig) {
                 web::resource("/change-membership")
                     .route(web::post().to(management::change_membership)),
             )
-            .service(web::resource("/metrics").route(web::get().to(management::metrics))),
+            .service(web::resource("/metrics").route(web::get().to(management::metrics)))
+            .service(
+                web::resource("/restart-check").route(web::get().to(management::restart_check)),
+            ),
     );
     // for debug
     // .service(web::resource("/route").route(web::post().to(routeapi::route_request)))
@@ -34,6 +34,9 @@ pub enum ClientRequest {
         key: String,
     },
     TableManagerReq(TableManagerReq),
+    /// 多个配置变更合并为一条日志整体提交,只包含ConfigSet与ConfigRemove;
+    /// 旧版本节点无法解析该日志,混合版本集群需要全部节点升级后再使用批量写入(导入、复制等)
+    ConfigBatch(Vec<ClientRequest>),
 }
 
 impl AppData for ClientRequest {}
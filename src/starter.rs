@@ -1,6 +1,19 @@
 use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use crate::common::actor_utils::{create_actor_at_thread, create_actor_at_thread2};
+use crate::common::cluster_settings::ClusterSettingsManager;
+use crate::common::concurrency_limit::ConcurrencyLimiter;
+use crate::common::dead_letter::DeadLetterManager;
+use crate::common::group_rule::GroupRuleManager;
+use crate::common::metadata_schema::MetadataSchemaManager;
+use crate::common::namespace_quota::NamespaceQuotaManager;
+use crate::common::public_status::PublicStatusManager;
+use crate::common::resource_event::ResourceEventStore;
+use crate::common::sdk_usage::SdkUsageManage;
+use crate::common::service_link::ServiceLinkManager;
+use crate::common::shutdown::ShutdownManager;
+use crate::common::support_bundle::SlowRequestLog;
+use crate::config::encryption::ConfigEncryptionManager;
 use crate::grpc::handler::RAFT_ROUTE_REQUEST;
 use crate::metrics::core::MetricsManager;
 use crate::raft::filestore::core::FileStore;
@@ -9,6 +22,8 @@ use crate::raft::filestore::raftdata::RaftDataWrap;
 use crate::raft::filestore::raftindex::RaftIndexManager;
 use crate::raft::filestore::raftlog::RaftLogManager;
 use crate::raft::filestore::raftsnapshot::RaftSnapshotManager;
+use crate::raft::filestore::snapshotdiff::SnapshotDiffTracker;
+use crate::raft::freshness::DataFreshness;
 use crate::{
     common::{appdata::AppShareData, AppSysConfig},
     config::core::ConfigActor,
@@ -20,6 +35,9 @@ use crate::{
             route::NamingRoute,
         },
         core::NamingActor,
+        health_check::HealthCheckActor,
+        instance_history::InstanceHistoryActor,
+        listener::{InnerNamingListener, NAMING_UDP_PUSH_PERIOD},
         naming_delay_nofity::DelayNotifyActor,
     },
     raft::{
@@ -43,7 +61,6 @@ use crate::{
 use actix::prelude::*;
 use async_raft_ext::{raft::ClientWriteRequest, Config, Raft, RaftStorage};
 use bean_factory::{BeanDefinition, BeanFactory, FactoryData};
-use chrono::{FixedOffset, Local, Offset};
 
 pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<FactoryData> {
     /*
@@ -62,6 +79,30 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     let base_path = Arc::new(sys_config.config_db_dir.clone());
     let factory = BeanFactory::new();
     factory.register(BeanDefinition::from_obj(sys_config.clone()));
+    factory.register(BeanDefinition::from_obj(Arc::new(GroupRuleManager::new())));
+    factory.register(BeanDefinition::from_obj(
+        Arc::new(ServiceLinkManager::new()),
+    ));
+    factory.register(BeanDefinition::from_obj(Arc::new(
+        MetadataSchemaManager::new(),
+    )));
+    factory.register(BeanDefinition::from_obj(Arc::new(
+        ConfigEncryptionManager::new(&sys_config)?,
+    )));
+    factory.register(BeanDefinition::from_obj(Arc::new(
+        ClusterSettingsManager::new(sys_config.clone()),
+    )));
+    factory.register(BeanDefinition::from_obj(
+        Arc::new(ResourceEventStore::new()),
+    ));
+    factory.register(BeanDefinition::from_obj(Arc::new(SlowRequestLog::new())));
+    factory.register(BeanDefinition::from_obj(Arc::new(ShutdownManager::new())));
+    factory.register(BeanDefinition::from_obj(Arc::new(ConcurrencyLimiter::new(
+        &sys_config,
+    ))));
+    factory.register(BeanDefinition::from_obj(Arc::new(
+        PublicStatusManager::new(sys_config.public_status_tps),
+    )));
 
     let index_manager = RaftIndexManager::new(base_path.clone());
     let (index_manager, config_addr) = create_actor_at_thread2(index_manager, ConfigActor::new());
@@ -75,6 +116,10 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         DelayNotifyActor::new().start(),
     ));
+    //1.x客户端查询实例时携带udpPort,变更时通过udp推送
+    factory.register(BeanDefinition::actor_with_inject_from_obj(
+        InnerNamingListener::new_and_create(NAMING_UDP_PUSH_PERIOD, Some(naming_addr.clone())),
+    ));
 
     //raft
     let conn_factory = RaftConnectionFactory::new(60).start();
@@ -89,6 +134,8 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     let log_manager = create_actor_at_thread(log_manager);
     let snapshot_manager = RaftSnapshotManager::new(base_path.clone(), Some(index_manager.clone()));
     let apply_manager = StateApplyManager::new();
+    let diff_tracker = Arc::new(SnapshotDiffTracker::new());
+    factory.register(BeanDefinition::from_obj(diff_tracker.clone()));
     let (snapshot_manager, apply_manager) =
         create_actor_at_thread2(snapshot_manager, apply_manager);
     factory.register(BeanDefinition::actor_with_inject_from_obj(
@@ -110,6 +157,7 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
         snapshot_manager,
         log_manager,
         apply_manager,
+        diff_tracker,
     ));
     factory.register(BeanDefinition::from_obj(store.clone()));
     let raft = build_raft(&sys_config, store.clone(), cluster_sender.clone()).await?;
@@ -138,8 +186,11 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     ));
     factory.register(BeanDefinition::from_obj(config_route.clone()));
 
-    let naming_inner_node_manage_addr =
-        InnerNodeManage::new(sys_config.raft_node_id.to_owned()).start();
+    let naming_inner_node_manage_addr = InnerNodeManage::new(
+        sys_config.raft_node_id.to_owned(),
+        sys_config.node_labels.clone(),
+    )
+    .start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         naming_inner_node_manage_addr.clone(),
     ));
@@ -156,6 +207,9 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
         naming_cluster_delay_notify_addr.clone(),
     ));
 
+    let sdk_usage_addr = SdkUsageManage::new().start();
+    factory.register(BeanDefinition::actor_from_obj(sdk_usage_addr));
+
     let bistream_manage_addr = BiStreamManage::new().start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(
         bistream_manage_addr.clone(),
@@ -181,18 +235,27 @@ pub async fn config_factory(sys_config: Arc<AppSysConfig>) -> anyhow::Result<Fac
     factory.register(BeanDefinition::from_obj(raft_data_wrap));
     let metrics_manager = MetricsManager::new(sys_config.clone()).start();
     factory.register(BeanDefinition::actor_with_inject_from_obj(metrics_manager));
+    let health_check_actor = HealthCheckActor::new().start();
+    factory.register(BeanDefinition::actor_with_inject_from_obj(
+        health_check_actor,
+    ));
+    let instance_history = InstanceHistoryActor::new().start();
+    factory.register(BeanDefinition::actor_with_inject_from_obj(instance_history));
+    let namespace_quota_manager = NamespaceQuotaManager::new().start();
+    factory.register(BeanDefinition::actor_with_inject_from_obj(
+        namespace_quota_manager,
+    ));
+    let dead_letter_manager = DeadLetterManager::new(&sys_config).start();
+    factory.register(BeanDefinition::actor_with_inject_from_obj(
+        dead_letter_manager,
+    ));
 
     Ok(factory.init().await)
 }
 
 pub fn build_share_data(factory_data: FactoryData) -> anyhow::Result<Arc<AppShareData>> {
     let sys_config: Arc<AppSysConfig> = factory_data.get_bean().unwrap();
-    let timezone_offset =
-        if let Some(offset_value) = sys_config.gmt_fixed_offset_hours.map(|e| e * 3600) {
-            FixedOffset::east_opt(offset_value).unwrap_or(Local::now().offset().fix())
-        } else {
-            Local::now().offset().fix()
-        };
+    let timezone_offset = sys_config.get_timezone_offset();
     let app_data = Arc::new(AppShareData {
         config_addr: factory_data.get_actor().unwrap(),
         naming_addr: factory_data.get_actor().unwrap(),
@@ -211,6 +274,17 @@ pub fn build_share_data(factory_data: FactoryData) -> anyhow::Result<Arc<AppShar
         user_manager: factory_data.get_actor().unwrap(),
         cache_manager: factory_data.get_actor().unwrap(),
         metrics_manager: factory_data.get_actor().unwrap(),
+        sdk_usage_manage: factory_data.get_actor().unwrap(),
+        data_freshness: Arc::new(DataFreshness::new()),
+        cluster_settings: factory_data.get_bean().unwrap(),
+        namespace_quota_manager: factory_data.get_actor().unwrap(),
+        dead_letter_manager: factory_data.get_actor().unwrap(),
+        instance_history: factory_data.get_actor().unwrap(),
+        resource_event_store: factory_data.get_bean().unwrap(),
+        slow_request_log: factory_data.get_bean().unwrap(),
+        public_status: factory_data.get_bean().unwrap(),
+        shutdown_manager: factory_data.get_bean().unwrap(),
+        concurrency_limiter: factory_data.get_bean().unwrap(),
         factory_data,
         timezone_offset: Arc::new(timezone_offset),
     });
@@ -238,10 +312,16 @@ async fn build_raft(
             sys_config.raft_snapshot_log_size,
         ))
         .snapshot_max_chunk_size(3 * 1024 * 1024)
+        .max_payload_entries(sys_config.raft_max_payload_entries)
         .validate()
         .unwrap();
     let config = Arc::new(config);
-    let network = Arc::new(RaftRouter::new(store.clone(), cluster_sender.clone()));
+    let network = Arc::new(RaftRouter::new(
+        store.clone(),
+        cluster_sender.clone(),
+        sys_config.raft_compress_threshold,
+        sys_config.raft_diff_snapshot_enable,
+    ));
     let raft = Arc::new(Raft::new(
         sys_config.raft_node_id.to_owned(),
         config,
@@ -0,0 +1,123 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::common::string_utils::StringUtils;
+
+use super::model::UserDto;
+use super::permission::UserRole;
+
+/// 全局权限所在的命名空间
+pub const ALL_NAMESPACE: &str = "*";
+
+///
+/// 权限审查报表的一行:用户在一个命名空间下拥有的权限;
+/// open api token 继承所属用户的权限,不单独列出
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessReportItem {
+    pub username: Arc<String>,
+    pub nickname: Option<String>,
+    pub enable: bool,
+    pub roles: Vec<Arc<String>>,
+    pub namespace_id: Arc<String>,
+    pub permissions: Vec<&'static str>,
+}
+
+pub struct AccessReportUtils;
+
+impl AccessReportUtils {
+    ///
+    /// 配置与服务权限对每个命名空间展开,其它权限记录在命名空间`*`下
+    pub fn build(users: Vec<UserDto>, namespaces: &[Arc<String>]) -> Vec<AccessReportItem> {
+        let mut list = vec![];
+        for user in users {
+            let roles = user.roles.unwrap_or_default();
+            let mut permissions = BTreeSet::new();
+            for role in &roles {
+                permissions.extend(UserRole::new(role).get_permissions());
+            }
+            let (namespace_permissions, global_permissions): (
+                Vec<&'static str>,
+                Vec<&'static str>,
+            ) = permissions
+                .into_iter()
+                .partition(|v| UserRole::is_namespace_permission(v));
+            let item = AccessReportItem {
+                username: user.username,
+                nickname: user.nickname,
+                enable: user.enable.unwrap_or(true),
+                roles,
+                ..Default::default()
+            };
+            if !namespace_permissions.is_empty() {
+                for namespace_id in namespaces {
+                    list.push(AccessReportItem {
+                        namespace_id: namespace_id.clone(),
+                        permissions: namespace_permissions.clone(),
+                        ..item.clone()
+                    });
+                }
+            }
+            if !global_permissions.is_empty() {
+                list.push(AccessReportItem {
+                    namespace_id: Arc::new(ALL_NAMESPACE.to_owned()),
+                    permissions: global_permissions,
+                    ..item
+                });
+            }
+        }
+        list
+    }
+
+    pub fn to_csv(list: &[AccessReportItem]) -> String {
+        let mut csv = String::from("username,nickname,enable,roles,namespaceId,permissions\n");
+        for item in list {
+            let roles: Vec<&str> = item.roles.iter().map(|v| v.as_str()).collect();
+            let row = [
+                StringUtils::csv_field(&item.username),
+                StringUtils::csv_field(item.nickname.as_deref().unwrap_or_default()),
+                item.enable.to_string(),
+                StringUtils::csv_field(&roles.join("|")),
+                StringUtils::csv_field(&item.namespace_id),
+                StringUtils::csv_field(&item.permissions.join("|")),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::permission::{USER_ROLE_DEVELOPER, USER_ROLE_VISITOR};
+
+    #[test]
+    fn test_access_report() {
+        let users = vec![
+            UserDto {
+                username: Arc::new("dev".to_owned()),
+                roles: Some(vec![USER_ROLE_DEVELOPER.clone()]),
+                ..Default::default()
+            },
+            UserDto {
+                username: Arc::new("guest".to_owned()),
+                nickname: Some("a,b".to_owned()),
+                roles: Some(vec![USER_ROLE_VISITOR.clone()]),
+                ..Default::default()
+            },
+        ];
+        let namespaces = vec![Arc::new("public".to_owned()), Arc::new("test".to_owned())];
+        let list = AccessReportUtils::build(users, &namespaces);
+        //开发者:2个命名空间 + 全局权限;访客只有命名空间权限
+        assert_eq!(list.len(), 5);
+        assert_eq!(list[2].namespace_id.as_str(), ALL_NAMESPACE);
+        assert!(list[2].permissions.contains(&"namespace:write"));
+        assert_eq!(list[3].permissions, vec!["config:read", "naming:read"]);
+        let csv = AccessReportUtils::to_csv(&list);
+        assert!(csv.contains("guest,\"a,b\",true,2,test,config:read|naming:read"));
+    }
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::appdata::AppShareData;
+use crate::config::core::{ConfigCmd, ConfigKey, ConfigResult};
+use crate::naming::core::{NamingCmd, NamingResult};
+use crate::naming::model::ServiceKey;
+use crate::naming::NamingUtils;
+
+use super::preference::UserPreferenceUtils;
+
+/// 收藏列表在用户偏好中的key
+const FAVORITES_PREFERENCE_KEY: &str = "favorites";
+/// 单个用户最多收藏的数量
+const FAVORITES_MAX_COUNT: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FavoriteType {
+    #[default]
+    Config,
+    Service,
+}
+
+///
+/// 收藏项;配置的name为dataId,服务的name为serviceName
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FavoriteItem {
+    #[serde(rename = "type", default)]
+    pub favorite_type: FavoriteType,
+    #[serde(default)]
+    pub namespace_id: String,
+    #[serde(default)]
+    pub group_name: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+///
+/// 收藏项及其当前状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FavoriteItemDto {
+    #[serde(flatten)]
+    pub item: FavoriteItem,
+    pub exists: bool,
+    pub md5: Option<Arc<String>>,
+    pub last_modified: Option<i64>,
+    pub instance_count: Option<usize>,
+    pub healthy_instance_count: Option<usize>,
+}
+
+pub struct UserFavoriteUtils;
+
+impl UserFavoriteUtils {
+    fn normalize(mut item: FavoriteItem) -> anyhow::Result<FavoriteItem> {
+        if item.name.is_empty() {
+            return Err(anyhow::anyhow!("favorite name is empty"));
+        }
+        if item.favorite_type == FavoriteType::Service {
+            item.namespace_id = NamingUtils::default_namespace(item.namespace_id);
+            item.group_name = NamingUtils::default_group(item.group_name);
+        } else if item.group_name.is_empty() {
+            return Err(anyhow::anyhow!("favorite config group is empty"));
+        }
+        Ok(item)
+    }
+
+    pub async fn get_favorites(
+        app: &AppShareData,
+        username: &str,
+    ) -> anyhow::Result<Vec<FavoriteItem>> {
+        match UserPreferenceUtils::get_preference(app, username, FAVORITES_PREFERENCE_KEY).await? {
+            Some(v) => Ok(serde_json::from_value(v).unwrap_or_default()),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn save_favorites(
+        app: &AppShareData,
+        username: &str,
+        list: Vec<FavoriteItem>,
+    ) -> anyhow::Result<()> {
+        let value = if list.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_value(list)?)
+        };
+        UserPreferenceUtils::set_preference(app, username, FAVORITES_PREFERENCE_KEY, value).await
+    }
+
+    pub async fn add_favorite(
+        app: &AppShareData,
+        username: &str,
+        item: FavoriteItem,
+    ) -> anyhow::Result<()> {
+        let item = Self::normalize(item)?;
+        let mut list = Self::get_favorites(app, username).await?;
+        if list.contains(&item) {
+            return Ok(());
+        }
+        if list.len() >= FAVORITES_MAX_COUNT {
+            return Err(anyhow::anyhow!(
+                "favorite count is over {}",
+                FAVORITES_MAX_COUNT
+            ));
+        }
+        list.push(item);
+        Self::save_favorites(app, username, list).await
+    }
+
+    pub async fn remove_favorite(
+        app: &AppShareData,
+        username: &str,
+        item: FavoriteItem,
+    ) -> anyhow::Result<()> {
+        let item = Self::normalize(item)?;
+        let mut list = Self::get_favorites(app, username).await?;
+        let len = list.len();
+        list.retain(|e| e != &item);
+        if list.len() != len {
+            Self::save_favorites(app, username, list).await?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// 查询收藏项的当前状态:配置返回md5与修改时间,服务返回实例数量
+    pub async fn query_favorite_status(
+        app: &AppShareData,
+        list: Vec<FavoriteItem>,
+    ) -> anyhow::Result<Vec<FavoriteItemDto>> {
+        let mut result = Vec::with_capacity(list.len());
+        for item in list {
+            let mut dto = FavoriteItemDto::default();
+            match item.favorite_type {
+                FavoriteType::Config => {
+                    let key = ConfigKey::new(&item.name, &item.group_name, &item.namespace_id);
+                    if let ConfigResult::Data {
+                        md5, last_modified, ..
+                    } = app.config_addr.send(ConfigCmd::GetRaw(key)).await??
+                    {
+                        dto.exists = true;
+                        dto.md5 = Some(md5);
+                        dto.last_modified = Some(last_modified);
+                    }
+                }
+                FavoriteType::Service => {
+                    let key = ServiceKey::new(&item.namespace_id, &item.group_name, &item.name);
+                    if let NamingResult::InstanceList(instances) = app
+                        .naming_addr
+                        .send(NamingCmd::QueryAllInstanceList(key))
+                        .await??
+                    {
+                        dto.exists = !instances.is_empty();
+                        dto.instance_count = Some(instances.len());
+                        dto.healthy_instance_count =
+                            Some(instances.iter().filter(|e| e.healthy).count());
+                    }
+                }
+            }
+            dto.item = item;
+            result.push(dto);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::constant::USER_PREFERENCE_TREE_NAME;
+    use crate::raft::db::table::{
+        TableManager, TableManagerQueryReq, TableManagerReq, TableManagerResult,
+    };
+    use crate::raft::filestore::raftapply::tests::install_table_snapshot;
+    use actix::Actor;
+
+    #[actix_rt::test]
+    async fn test_favorites_survive_snapshot_install() {
+        let list = vec![
+            FavoriteItem {
+                favorite_type: FavoriteType::Config,
+                namespace_id: "dev".to_owned(),
+                group_name: "DEFAULT_GROUP".to_owned(),
+                name: "app.yaml".to_owned(),
+            },
+            FavoriteItem {
+                favorite_type: FavoriteType::Service,
+                namespace_id: "public".to_owned(),
+                group_name: "DEFAULT_GROUP".to_owned(),
+                name: "order-service".to_owned(),
+            },
+        ];
+        let key = UserPreferenceUtils::build_key("admin", FAVORITES_PREFERENCE_KEY);
+        let source = TableManager::new().start();
+        source
+            .send(TableManagerReq::Set {
+                table_name: USER_PREFERENCE_TREE_NAME.clone(),
+                key: key.clone(),
+                value: serde_json::to_vec(&serde_json::to_value(&list).unwrap()).unwrap(),
+                last_seq_id: None,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        let target = install_table_snapshot(&source).await;
+        let v = target
+            .send(TableManagerQueryReq::GetByBytes {
+                table_name: USER_PREFERENCE_TREE_NAME.clone(),
+                key,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        match v {
+            TableManagerResult::Value(v) => {
+                let loaded: Vec<FavoriteItem> = serde_json::from_slice(&v).unwrap();
+                assert_eq!(loaded, list);
+            }
+            _ => panic!("favorites are not loaded from snapshot"),
+        }
+    }
+}
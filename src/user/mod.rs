@@ -6,6 +6,8 @@ use bean_factory::{bean, Inject};
 //use inner_mem_cache::MemCache;
 
 use crate::common::constant::USER_TREE_NAME;
+use crate::common::schema_version::SchemaVersionUtils;
+use crate::raft::cache::{CacheManager, CacheManagerReq};
 use crate::{
     now_millis,
     raft::{
@@ -19,12 +21,17 @@ use crate::{
 
 use self::{
     model::{UserDo, UserDto},
+    password::PasswordPolicy,
     permission::USER_ROLE_MANAGER,
 };
 
+pub mod access_report;
 pub mod api;
+pub mod favorite;
 pub mod model;
+pub mod password;
 pub mod permission;
+pub mod preference;
 
 #[bean(inject)]
 pub struct UserManager {
@@ -32,6 +39,8 @@ pub struct UserManager {
     //cache_sec: i32,
     raft_table_route: Option<Arc<TableRoute>>,
     table_manager: Option<Addr<TableManager>>,
+    cache_manager: Option<Addr<CacheManager>>,
+    password_policy: PasswordPolicy,
 }
 
 impl UserManager {
@@ -41,6 +50,34 @@ impl UserManager {
             //cache_sec: 1200,
             raft_table_route: Default::default(),
             table_manager: Default::default(),
+            cache_manager: Default::default(),
+            password_policy: Default::default(),
+        }
+    }
+
+    async fn hash_password(policy: PasswordPolicy, password: String) -> anyhow::Result<String> {
+        tokio::task::spawn_blocking(move || policy.hash(&password)).await?
+    }
+
+    async fn verify_password(stored: String, password: String) -> anyhow::Result<bool> {
+        Ok(tokio::task::spawn_blocking(move || PasswordPolicy::verify(&stored, &password)).await?)
+    }
+
+    ///
+    /// 用户角色、启用状态或扩展权限变更后,立即失效其已有的登录会话与token
+    async fn remove_user_sessions(
+        cache_manager: &Option<Addr<CacheManager>>,
+        username: Arc<String>,
+    ) {
+        if let Some(cache_manager) = cache_manager {
+            match cache_manager
+                .send(CacheManagerReq::RemoveUserSessions(username.clone()))
+                .await
+            {
+                Ok(Err(err)) => log::warn!("remove user {} sessions error,{}", &username, err),
+                Err(err) => log::warn!("remove user {} sessions error,{}", &username, err),
+                _ => {}
+            }
         }
     }
 
@@ -96,6 +133,11 @@ impl Inject for UserManager {
     ) {
         self.raft_table_route = factory_data.get_bean();
         self.table_manager = factory_data.get_actor();
+        self.cache_manager = factory_data.get_actor();
+        let sys_config: Option<Arc<AppSysConfig>> = factory_data.get_bean();
+        if let Some(sys_config) = sys_config {
+            self.password_policy = sys_config.user_password_policy;
+        }
         let raft_addr_route: Option<Arc<RaftAddrRouter>> = factory_data.get_bean();
         ctx.run_later(Duration::from_millis(500), |act, ctx| {
             let self_addr = ctx.address();
@@ -184,6 +226,8 @@ impl Handler<UserManagerReq> for UserManager {
     fn handle(&mut self, msg: UserManagerReq, _ctx: &mut Self::Context) -> Self::Result {
         let raft_table_route = self.raft_table_route.clone();
         let table_manager = self.table_manager.clone();
+        let password_policy = self.password_policy;
+        let cache_manager = self.cache_manager.clone();
         //let query_info_at_cache = match &msg {
         //    UserManagerReq::Query { name } => self.cache.get(name).ok().is_some(),
         //    _ => false,
@@ -193,9 +237,12 @@ impl Handler<UserManagerReq> for UserManager {
             match msg {
                 UserManagerReq::AddUser { user } => {
                     let now = (now_millis() / 1000) as u32;
+                    let password =
+                        Self::hash_password(password_policy, user.password.unwrap_or_default())
+                            .await?;
                     let user_do = UserDo {
                         username: user.username.as_ref().to_owned(),
-                        password: user.password.unwrap_or_default(),
+                        password,
                         nickname: user.nickname.unwrap_or_default(),
                         gmt_create: now,
                         gmt_modified: now,
@@ -207,6 +254,7 @@ impl Handler<UserManagerReq> for UserManager {
                             .collect(),
                         enable: true,
                         extend_info: user.extend_info.unwrap_or_default(),
+                        schema_version: SchemaVersionUtils::write_version(),
                     };
                     let user_data = user_do.to_bytes();
                     let req = TableManagerReq::Set {
@@ -236,6 +284,11 @@ impl Handler<UserManagerReq> for UserManager {
                     } else {
                         return Err(anyhow::anyhow!("raft_table_route is none "));
                     };
+                    let old_permission = (
+                        last_user.enable,
+                        last_user.roles.clone(),
+                        last_user.extend_info.clone(),
+                    );
                     let now = (now_millis() / 1000) as u32;
                     last_user.gmt_modified = now;
                     if let Some(nickname) = user.nickname {
@@ -245,7 +298,8 @@ impl Handler<UserManagerReq> for UserManager {
                     }
                     if let Some(password) = user.password {
                         if !password.is_empty() {
-                            last_user.password = password;
+                            last_user.password =
+                                Self::hash_password(password_policy, password).await?;
                         }
                     }
                     if let Some(enable) = user.enable {
@@ -273,6 +327,12 @@ impl Handler<UserManagerReq> for UserManager {
                     if let Some(raft_table_route) = raft_table_route {
                         raft_table_route.request(req).await.ok();
                     }
+                    if old_permission.0 != last_user.enable
+                        || old_permission.1 != last_user.roles
+                        || old_permission.2 != last_user.extend_info
+                    {
+                        Self::remove_user_sessions(&cache_manager, user.username.clone()).await;
+                    }
                     Ok(UserManagerInnerCtx::UpdateUser {
                         key: user.username,
                         value: last_user,
@@ -282,23 +342,43 @@ impl Handler<UserManagerReq> for UserManager {
                     if name.is_empty() || password.is_empty() {
                         return Err(anyhow::anyhow!("args is empty"));
                     }
-                    let last_user = if let Some(raft_table_route) = &raft_table_route {
-                        let query_req = TableManagerQueryReq::GetByArcKey {
+                    let (mut last_user, old_value) =
+                        if let Some(raft_table_route) = &raft_table_route {
+                            let query_req = TableManagerQueryReq::GetByArcKey {
+                                table_name: USER_TREE_NAME.clone(),
+                                key: name.clone(),
+                            };
+                            match raft_table_route.get_leader_data(query_req).await? {
+                                TableManagerResult::Value(old_value) => {
+                                    (UserDo::from_bytes(&old_value)?, old_value)
+                                }
+                                //_ => return Err(anyhow::anyhow!("not found user {}", &name)),
+                                _ => return Ok(UserManagerInnerCtx::None),
+                            }
+                        } else {
+                            return Err(anyhow::anyhow!("raft_table_route is none "));
+                        };
+                    let is_valid = last_user.enable
+                        && Self::verify_password(last_user.password.clone(), password.clone())
+                            .await?;
+                    if is_valid && password_policy.need_rehash(&last_user.password) {
+                        //旧算法保存的密码,登录成功后按当前策略重新保存;
+                        //用户在此期间被修改时放弃本次写入,下次登录再重新保存
+                        last_user.password = Self::hash_password(password_policy, password).await?;
+                        let req = TableManagerReq::CompareAndSet {
                             table_name: USER_TREE_NAME.clone(),
-                            key: name.clone(),
+                            key: name.as_bytes().to_owned(),
+                            expect: old_value,
+                            value: last_user.to_bytes(),
                         };
-                        match raft_table_route.get_leader_data(query_req).await? {
-                            TableManagerResult::Value(old_value) => UserDo::from_bytes(&old_value)?,
-                            //_ => return Err(anyhow::anyhow!("not found user {}", &name)),
-                            _ => return Ok(UserManagerInnerCtx::None),
+                        if let Some(raft_table_route) = &raft_table_route {
+                            if let Err(err) = raft_table_route.request(req).await {
+                                log::warn!("rehash user {} password error,{}", &name, err);
+                            }
                         }
-                    } else {
-                        return Err(anyhow::anyhow!("raft_table_route is none "));
-                    };
+                    }
                     Ok(UserManagerInnerCtx::CheckUserResult(
-                        name,
-                        last_user.enable && last_user.password == password,
-                        last_user,
+                        name, is_valid, last_user,
                     ))
                 }
                 UserManagerReq::Remove { username } => {
@@ -309,6 +389,7 @@ impl Handler<UserManagerReq> for UserManager {
                     if let Some(raft_table_route) = raft_table_route {
                         raft_table_route.request(req).await.ok();
                     }
+                    Self::remove_user_sessions(&cache_manager, username).await;
                     Ok(UserManagerInnerCtx::None)
                 }
                 UserManagerReq::Query { name } => {
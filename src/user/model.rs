@@ -2,6 +2,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 
+use crate::common::schema_version::{SchemaVersionUtils, VersionedRecord};
 use crate::user::permission::UserRoleHelper;
 
 #[derive(Clone, prost::Message, Serialize, Deserialize)]
@@ -23,6 +24,9 @@ pub struct UserDo {
     #[prost(map = "string, string", tag = "8")]
     pub extend_info:
         ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>,
+    #[prost(uint32, tag = "9")]
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 impl UserDo {
@@ -33,7 +37,19 @@ impl UserDo {
     }
 
     pub fn from_bytes(v: &[u8]) -> anyhow::Result<Self> {
-        Ok(prost::Message::decode(v)?)
+        let mut user: Self = prost::Message::decode(v)?;
+        SchemaVersionUtils::upgrade(&mut user)?;
+        Ok(user)
+    }
+}
+
+impl VersionedRecord for UserDo {
+    fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    fn set_schema_version(&mut self, version: u32) {
+        self.schema_version = version;
     }
 }
 
@@ -0,0 +1,129 @@
+use argon2::password_hash::SaltString;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+
+const BCRYPT_PREFIXES: [&str; 3] = ["$2a$", "$2b$", "$2y$"];
+const ARGON2_PREFIX: &str = "$argon2";
+
+///
+/// 用户密码存储算法;plain为旧版本的明文存储,只用于兼容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordHashAlgorithm {
+    Plain,
+    Bcrypt,
+    Argon2,
+}
+
+impl PasswordHashAlgorithm {
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "plain" => Self::Plain,
+            "argon2" | "argon2id" => Self::Argon2,
+            _ => Self::Bcrypt,
+        }
+    }
+
+    ///
+    /// 根据已存储的密码判断使用的算法,参数保存在密码串中
+    pub fn of_stored(stored: &str) -> Self {
+        if stored.starts_with(ARGON2_PREFIX) {
+            Self::Argon2
+        } else if BCRYPT_PREFIXES.iter().any(|e| stored.starts_with(e)) {
+            Self::Bcrypt
+        } else {
+            Self::Plain
+        }
+    }
+}
+
+///
+/// 密码存储策略
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordPolicy {
+    pub algorithm: PasswordHashAlgorithm,
+    pub bcrypt_cost: u32,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            algorithm: PasswordHashAlgorithm::Bcrypt,
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn hash(&self, password: &str) -> anyhow::Result<String> {
+        match self.algorithm {
+            PasswordHashAlgorithm::Plain => Ok(password.to_owned()),
+            PasswordHashAlgorithm::Bcrypt => Ok(bcrypt::hash(password, self.bcrypt_cost)?),
+            PasswordHashAlgorithm::Argon2 => {
+                let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+                    .map_err(|e| anyhow::anyhow!("{}", e))?;
+                let hash = Argon2::default()
+                    .hash_password(password.as_bytes(), &salt)
+                    .map_err(|e| anyhow::anyhow!("{}", e))?;
+                Ok(hash.to_string())
+            }
+        }
+    }
+
+    pub fn verify(stored: &str, password: &str) -> bool {
+        match PasswordHashAlgorithm::of_stored(stored) {
+            PasswordHashAlgorithm::Plain => stored == password,
+            PasswordHashAlgorithm::Bcrypt => bcrypt::verify(password, stored).unwrap_or(false),
+            PasswordHashAlgorithm::Argon2 => match PasswordHash::new(stored) {
+                Ok(hash) => Argon2::default()
+                    .verify_password(password.as_bytes(), &hash)
+                    .is_ok(),
+                Err(_) => false,
+            },
+        }
+    }
+
+    ///
+    /// 已存储密码的算法或参数与当前策略不一致时,需要在登录成功后重新计算
+    pub fn need_rehash(&self, stored: &str) -> bool {
+        let algorithm = PasswordHashAlgorithm::of_stored(stored);
+        if algorithm != self.algorithm {
+            return true;
+        }
+        if algorithm == PasswordHashAlgorithm::Bcrypt {
+            return stored
+                .get(4..6)
+                .and_then(|v| v.parse::<u32>().ok())
+                .map(|cost| cost != self.bcrypt_cost)
+                .unwrap_or(true);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_policy() {
+        let policy = PasswordPolicy {
+            algorithm: PasswordHashAlgorithm::Bcrypt,
+            bcrypt_cost: 4,
+        };
+        let stored = policy.hash("admin").unwrap();
+        assert!(PasswordPolicy::verify(&stored, "admin"));
+        assert!(!PasswordPolicy::verify(&stored, "admin1"));
+        assert!(!policy.need_rehash(&stored));
+        assert!(policy.need_rehash("admin"));
+        assert!(PasswordPolicy::verify("admin", "admin"));
+
+        let policy = PasswordPolicy {
+            algorithm: PasswordHashAlgorithm::Argon2,
+            bcrypt_cost: 4,
+        };
+        assert!(policy.need_rehash(&stored));
+        let stored = policy.hash("admin").unwrap();
+        assert!(PasswordPolicy::verify(&stored, "admin"));
+        assert!(!PasswordPolicy::verify(&stored, "admin1"));
+        assert!(!policy.need_rehash(&stored));
+    }
+}
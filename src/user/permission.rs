@@ -105,6 +105,16 @@ impl GroupResource {
 
 type R = Resource;
 
+pub const PERMISSION_CONFIG_READ: &str = "config:read";
+pub const PERMISSION_CONFIG_WRITE: &str = "config:write";
+pub const PERMISSION_NAMING_READ: &str = "naming:read";
+pub const PERMISSION_NAMING_WRITE: &str = "naming:write";
+pub const PERMISSION_NAMESPACE_WRITE: &str = "namespace:write";
+pub const PERMISSION_USER_WRITE: &str = "user:write";
+pub const PERMISSION_CLUSTER_READ: &str = "cluster:read";
+pub const PERMISSION_CLUSTER_WRITE: &str = "cluster:write";
+pub const PERMISSION_METRICS_READ: &str = "metrics:read";
+
 lazy_static::lazy_static! {
     pub(crate) static ref USER_ROLE_MANAGER: Arc<String> =  Arc::new("0".to_string());
     pub(crate) static ref USER_ROLE_DEVELOPER: Arc<String> =  Arc::new("1".to_string());
@@ -150,7 +160,13 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/user/info",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/user/web_resources",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/user/reset_password",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/user/preferences",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/user/preferences/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/user/favorites",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/user/favorites/add",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/user/favorites/remove",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/namespaces/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/search",HTTP_METHOD_GET),
 
     ]);
 
@@ -162,6 +178,19 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/manage/cluster",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/cluster/cluster_node_list",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/cluster/cluster_node_list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/cluster/settings",HTTP_METHOD_GET),
+    ]);
+
+    static ref M_CLUSTER_MANAGE: ModuleResource = ModuleResource::new(vec![
+        R::Path("/rnacos/api/console/v2/cluster/settings/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/cluster/rebalance_by_zone",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/cluster/shutdown",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/cluster/support_bundle",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/cluster/dead_letters",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/cluster/dead_letters/requeue",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/cluster/dead_letters/purge",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/freeze_rules/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/encryption/rotate",HTTP_METHOD_ALL),
     ]);
 
     static ref M_NAMESPACE_VISITOR: ModuleResource = ModuleResource::new(vec![
@@ -171,6 +200,10 @@ lazy_static::lazy_static! {
         //path
         R::Path("/rnacos/manage/namespace",HTTP_METHOD_GET),
         //R::Path("/rnacos/api/console/namespaces",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/group_rules",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/service_links",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/quota_usage",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/compare",HTTP_METHOD_GET),
     ]);
 
     static ref M_NAMESPACE_MANAGE: ModuleResource = ModuleResource::new(vec![
@@ -185,6 +218,12 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/namespaces/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/namespaces/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/namespaces/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/namespaces/group_rules",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/group_rules/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/namespaces/service_links",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/service_links/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/namespaces/quota_usage",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/namespaces/compare",HTTP_METHOD_GET),
     ]);
 
     static ref M_USER_MANAGE: ModuleResource = ModuleResource::new(vec![
@@ -205,6 +244,7 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/user/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/user/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/user/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/user/access_report",HTTP_METHOD_GET),
     ]);
 
     static ref M_CONFIG_VISITOR: ModuleResource = ModuleResource::new(vec![
@@ -226,6 +266,16 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/config/download",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/info",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/history",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/timeline",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/beta/info",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/tag/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/history/diff",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/as_of",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/schedule/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/template/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/template/variables",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/listener_connections",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/listeners",HTTP_METHOD_GET),
     ]);
 
     static ref M_CONFIG_MANAGE: ModuleResource = ModuleResource::new(vec![
@@ -249,10 +299,33 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/v2/config/download",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/info",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/history",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/timeline",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/config/import",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/clone",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/config/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/config/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/config/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/beta/info",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/beta/publish",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/publish/dry_run",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/beta/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/tag/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/tag/publish",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/tag/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/history/diff",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/as_of",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/schedule/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/schedule/add",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/schedule/cancel",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/template/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/template/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/template/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/template/variables",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/template/variables/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/history/rollback",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/config/freeze_rules",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/listener_connections",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/config/listeners",HTTP_METHOD_GET),
     ]);
 
     static ref M_NAMING_VISITOR: ModuleResource = ModuleResource::new(vec![
@@ -271,7 +344,19 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/ns/instance",HTTP_METHOD_GET),
 
         R::Path("/rnacos/api/console/v2/service/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/info",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/subscribers",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/timeline",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/export",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/instance/flapping_list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/change_history",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/quarantine_instances",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/pattern_subscribers",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/stats",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/instance_history",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/metadata_schemas",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/instance/query_by_ip",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/info",HTTP_METHOD_GET),
     ]);
 
@@ -292,14 +377,32 @@ lazy_static::lazy_static! {
         R::Path("/rnacos/api/console/ns/instance",HTTP_METHOD_ALL),
 
         R::Path("/rnacos/api/console/v2/service/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/info",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/subscribers",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/timeline",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/service/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/service/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/service/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/service/move",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/service/export",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/import",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/service/backup/export",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/backup/import",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/instance/list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/instance/flapping_list",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/change_history",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/quarantine_instances",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/pattern_subscribers",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/stats",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/instance_history",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/metadata_schemas",HTTP_METHOD_GET),
+        R::Path("/rnacos/api/console/v2/service/metadata_schemas/set",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/instance/query_by_ip",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/info",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/instance/add",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/instance/update",HTTP_METHOD_ALL),
         R::Path("/rnacos/api/console/v2/instance/remove",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/instance/drain",HTTP_METHOD_ALL),
     ]);
 
     static ref M_METRICS_VISITOR: ModuleResource = ModuleResource::new(vec![
@@ -308,6 +411,7 @@ lazy_static::lazy_static! {
         //path
         R::Path("/rnacos/manage/appmonitor",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/metrics/timeline",HTTP_METHOD_ALL),
+        R::Path("/rnacos/api/console/v2/client/sdk_usage",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/cluster/cluster_node_list",HTTP_METHOD_GET),
         R::Path("/rnacos/api/console/v2/cluster/cluster_node_list",HTTP_METHOD_GET),
     ]);
@@ -336,6 +440,7 @@ lazy_static::lazy_static! {
         &M_CONFIG_MANAGE,
         &M_NAMING_MANAGE,
         &M_USER_MANAGE,
+        &M_CLUSTER_MANAGE,
         &M_METRICS_VISITOR,
     ]));
 
@@ -371,6 +476,41 @@ impl UserRole {
         }
     }
 
+    ///
+    /// 角色拥有的功能权限,用于权限审查;与get_resources中的功能模块对应
+    pub fn get_permissions(&self) -> Vec<&'static str> {
+        match &self {
+            UserRole::Visitor => vec![PERMISSION_CONFIG_READ, PERMISSION_NAMING_READ],
+            UserRole::Developer | UserRole::OldConsole => vec![
+                PERMISSION_CONFIG_READ,
+                PERMISSION_CONFIG_WRITE,
+                PERMISSION_NAMING_READ,
+                PERMISSION_NAMING_WRITE,
+                PERMISSION_NAMESPACE_WRITE,
+                PERMISSION_CLUSTER_READ,
+                PERMISSION_METRICS_READ,
+            ],
+            UserRole::Manager => vec![
+                PERMISSION_CONFIG_READ,
+                PERMISSION_CONFIG_WRITE,
+                PERMISSION_NAMING_READ,
+                PERMISSION_NAMING_WRITE,
+                PERMISSION_NAMESPACE_WRITE,
+                PERMISSION_CLUSTER_READ,
+                PERMISSION_METRICS_READ,
+                PERMISSION_USER_WRITE,
+                PERMISSION_CLUSTER_WRITE,
+            ],
+            UserRole::None => vec![],
+        }
+    }
+
+    ///
+    /// 按命名空间生效的权限;其它权限为全局权限
+    pub fn is_namespace_permission(permission: &str) -> bool {
+        permission.starts_with("config:") || permission.starts_with("naming:")
+    }
+
     pub fn match_url(&self, path: &str, method: &str) -> bool {
         for item in self.get_resources() {
             if item.match_url(path, method) {
@@ -435,6 +575,12 @@ impl UserRoleHelper {
         ALL_ROLES.clone()
     }
 
+    pub fn is_manager(roles: &[Arc<String>]) -> bool {
+        roles
+            .iter()
+            .any(|e| e.as_str() == USER_ROLE_MANAGER.as_str())
+    }
+
     pub fn get_role(role_value: &str) -> Arc<String> {
         for item in ALL_ROLES.iter() {
             if role_value == item.as_str() {
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::common::appdata::AppShareData;
+use crate::common::constant::USER_PREFERENCE_TREE_NAME;
+use crate::raft::db::table::{TableManagerQueryReq, TableManagerReq, TableManagerResult};
+
+const PREFERENCE_KEY_MAX_LEN: usize = 128;
+/// 单个偏好值序列化后的最大字节数
+const PREFERENCE_VALUE_MAX_LEN: usize = 64 * 1024;
+/// 单个用户最多保存的偏好数量
+const PREFERENCE_MAX_COUNT: usize = 128;
+const KEY_SPLIT: char = '\x02';
+
+///
+/// 控制台用户偏好设置(常用命名空间、表格列布局、最近使用的过滤条件等),按用户隔离,通过raft表在集群内同步
+pub struct UserPreferenceUtils;
+
+impl UserPreferenceUtils {
+    pub(crate) fn build_key(username: &str, key: &str) -> Vec<u8> {
+        format!("{}{}{}", username, KEY_SPLIT, key).into_bytes()
+    }
+
+    fn check_key(key: &str) -> anyhow::Result<()> {
+        if key.is_empty() || key.len() > PREFERENCE_KEY_MAX_LEN {
+            return Err(anyhow::anyhow!(
+                "preference key length must be 1-{}",
+                PREFERENCE_KEY_MAX_LEN
+            ));
+        }
+        if !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || c == ':')
+        {
+            return Err(anyhow::anyhow!("preference key is invalid: {}", key));
+        }
+        Ok(())
+    }
+
+    pub async fn get_preferences(
+        app: &AppShareData,
+        username: &str,
+    ) -> anyhow::Result<HashMap<String, serde_json::Value>> {
+        let prefix = format!("{}{}", username, KEY_SPLIT);
+        let req = TableManagerQueryReq::QueryPageList {
+            table_name: USER_PREFERENCE_TREE_NAME.clone(),
+            like_key: Some(prefix.clone()),
+            offset: None,
+            limit: None,
+            is_rev: false,
+        };
+        let mut map = HashMap::new();
+        if let TableManagerResult::PageListResult(_, list) =
+            app.raft_table_manage.send(req).await??
+        {
+            for (k, v) in list {
+                let key = String::from_utf8_lossy(&k);
+                if let Some(key) = key.strip_prefix(&prefix) {
+                    if let Ok(value) = serde_json::from_slice(&v) {
+                        map.insert(key.to_owned(), value);
+                    }
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    pub async fn get_preference(
+        app: &AppShareData,
+        username: &str,
+        key: &str,
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        Self::check_key(key)?;
+        let req = TableManagerQueryReq::GetByBytes {
+            table_name: USER_PREFERENCE_TREE_NAME.clone(),
+            key: Self::build_key(username, key),
+        };
+        match app.raft_table_manage.send(req).await?? {
+            TableManagerResult::Value(v) => Ok(Some(serde_json::from_slice(&v)?)),
+            _ => Ok(None),
+        }
+    }
+
+    ///
+    /// 设置偏好,value为空时删除
+    pub async fn set_preference(
+        app: &AppShareData,
+        username: &str,
+        key: &str,
+        value: Option<serde_json::Value>,
+    ) -> anyhow::Result<()> {
+        Self::check_key(key)?;
+        let req = match value {
+            Some(value) => {
+                let value = serde_json::to_vec(&value)?;
+                if value.len() > PREFERENCE_VALUE_MAX_LEN {
+                    return Err(anyhow::anyhow!(
+                        "preference value is too long, over {}",
+                        PREFERENCE_VALUE_MAX_LEN
+                    ));
+                }
+                let preferences = Self::get_preferences(app, username).await?;
+                if !preferences.contains_key(key) && preferences.len() >= PREFERENCE_MAX_COUNT {
+                    return Err(anyhow::anyhow!(
+                        "preference count is over {}",
+                        PREFERENCE_MAX_COUNT
+                    ));
+                }
+                TableManagerReq::Set {
+                    table_name: USER_PREFERENCE_TREE_NAME.clone(),
+                    key: Self::build_key(username, key),
+                    value,
+                    last_seq_id: None,
+                }
+            }
+            None => TableManagerReq::Remove {
+                table_name: USER_PREFERENCE_TREE_NAME.clone(),
+                key: Self::build_key(username, key),
+            },
+        };
+        app.raft_table_route.request(req).await
+    }
+
+    ///
+    /// 删除用户时清理其偏好设置
+    pub async fn remove_user_preferences(app: &AppShareData, username: &str) -> anyhow::Result<()> {
+        let preferences = Self::get_preferences(app, username).await?;
+        for key in preferences.keys() {
+            let req = TableManagerReq::Remove {
+                table_name: USER_PREFERENCE_TREE_NAME.clone(),
+                key: Self::build_key(username, key),
+            };
+            app.raft_table_route.request(req).await?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::io::Read;
 
 use crypto::digest::Digest;
-use flate2::read::GzEncoder;
+use flate2::read::{GzDecoder, GzEncoder};
 
 pub fn get_md5(content: &str) -> String {
     let mut m = crypto::md5::Md5::new();
@@ -63,3 +63,18 @@ pub fn gz_encode(data: &[u8], threshold: usize) -> Vec<u8> {
         result
     }
 }
+
+///
+/// 解压数据,解压后超过max_len时返回错误
+pub fn gz_decode(data: &[u8], max_len: usize) -> anyhow::Result<Vec<u8>> {
+    let mut result = Vec::new();
+    let mut z = GzDecoder::new(data).take(max_len as u64 + 1);
+    z.read_to_end(&mut result)?;
+    if result.len() > max_len {
+        return Err(anyhow::anyhow!(
+            "gzip decoded data is too large, over {}",
+            max_len
+        ));
+    }
+    Ok(result)
+}
@@ -2,11 +2,15 @@ use actix_web::web::ServiceConfig;
 use actix_web::{web, HttpResponse, Responder};
 use mime_guess::from_path;
 use rnacos_web_dist_wrap::get_embedded_file;
+use std::path::{Component, Path};
+use std::sync::Arc;
 
+use crate::common::appdata::AppShareData;
 use crate::common::AppSysConfig;
 use crate::console::api::{console_api_config_v1, console_api_config_v2};
 use crate::openapi::auth::{login_config, mock_token};
 use crate::openapi::metrics::metrics_config;
+use crate::openapi::status::status_config;
 use crate::openapi::{openapi_config, v1::console as nacos_console};
 use crate::raft::network::raft_config;
 
@@ -17,49 +21,71 @@ use rust_embed::RustEmbed;
 struct Asset;
 */
 
-fn handle_embedded_file(path: &str) -> HttpResponse {
-    match get_embedded_file(path) {
+///
+/// 读取控制台静态文件;配置了外部静态目录时从该目录读取,否则使用编译时内嵌的前端资源
+async fn load_console_file(app: &AppShareData, path: &str) -> Option<Vec<u8>> {
+    if let Some(static_dir) = &app.sys_config.console_static_dir {
+        let file_path = Path::new(path);
+        if file_path
+            .components()
+            .any(|e| !matches!(e, Component::Normal(_)))
+        {
+            return None;
+        }
+        tokio::fs::read(Path::new(static_dir).join(file_path))
+            .await
+            .ok()
+    } else {
+        get_embedded_file(path).map(|e| e.data.into_owned())
+    }
+}
+
+async fn handle_embedded_file(app: &AppShareData, path: &str) -> HttpResponse {
+    match load_console_file(app, path).await {
         Some(content) => HttpResponse::Ok()
             .content_type(from_path(path).first_or_octet_stream().as_ref())
-            .body(content.data.into_owned()),
+            .body(content),
         None => HttpResponse::NotFound().body("404 Not Found"),
     }
 }
 
-fn handle_embedded_file_with_cache(path: &str) -> HttpResponse {
-    match get_embedded_file(path) {
+async fn handle_embedded_file_with_cache(app: &AppShareData, path: &str) -> HttpResponse {
+    match load_console_file(app, path).await {
         Some(content) => HttpResponse::Ok()
             .content_type(from_path(path).first_or_octet_stream().as_ref())
             .insert_header(("Cache-Control", "max-age=604800, public"))
-            .body(content.data.into_owned()),
+            .body(content),
         None => HttpResponse::NotFound().body("404 Not Found"),
     }
 }
 
-async fn index() -> impl Responder {
-    handle_embedded_file("index.html")
+async fn index(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    handle_embedded_file(&app, "index.html").await
 }
 
 #[actix_web::get("/server.svg")]
-async fn icon() -> impl Responder {
-    handle_embedded_file_with_cache("server.svg")
+async fn icon(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    handle_embedded_file_with_cache(&app, "server.svg").await
 }
 
 #[actix_web::get("/rnacos/server.svg")]
-async fn console_icon() -> impl Responder {
-    handle_embedded_file_with_cache("rnacos/server.svg")
+async fn console_icon(app: web::Data<Arc<AppShareData>>) -> impl Responder {
+    handle_embedded_file_with_cache(&app, "rnacos/server.svg").await
 }
 
 #[actix_web::get("/assets/{_:.*}")]
-async fn assets(path: web::Path<String>) -> impl Responder {
+async fn assets(app: web::Data<Arc<AppShareData>>, path: web::Path<String>) -> impl Responder {
     let file = format!("assets/{}", path.as_ref());
-    handle_embedded_file_with_cache(&file)
+    handle_embedded_file_with_cache(&app, &file).await
 }
 
 #[actix_web::get("/rnacos/assets/{_:.*}")]
-async fn console_assets(path: web::Path<String>) -> impl Responder {
+async fn console_assets(
+    app: web::Data<Arc<AppShareData>>,
+    path: web::Path<String>,
+) -> impl Responder {
     let file = format!("rnacos/assets/{}", path.as_ref());
-    handle_embedded_file_with_cache(&file)
+    handle_embedded_file_with_cache(&app, &file).await
 }
 
 async fn disable_no_auth_console_index() -> impl Responder {
@@ -101,12 +127,14 @@ pub fn app_config(conf_data: AppSysConfig) -> impl FnOnce(&mut ServiceConfig) {
                 );
             login_config(config);
             metrics_config(config);
+            status_config(config);
             raft_config(config);
             nacos_console_api_config(config);
             config.configure(openapi_config(conf_data));
         } else {
             login_config(config);
             metrics_config(config);
+            status_config(config);
             raft_config(config);
             nacos_console_api_config(config);
             config.configure(openapi_config(conf_data));